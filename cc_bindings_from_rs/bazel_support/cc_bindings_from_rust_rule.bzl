@@ -37,6 +37,16 @@ load(
 )
 load("@bazel_tools//tools/cpp:toolchain_utils.bzl", "find_cpp_toolchain", "use_cpp_toolchain")
 
+CcBindingsFromRustInfo = provider(
+    doc = ("A provider that describes the C++ bindings generated for a Rust crate " +
+           "(used by `cc_bindings_from_rust` targets that depend on this crate)."),
+    fields = {
+        "crate_name": "Name of the Rust crate that the bindings were generated for.",
+        "h_out_file": "The generated `..._cc_api.h` header file.",
+        "rust_crate": "The `rust_library` target that the bindings were generated for.",
+    },
+)
+
 def _generate_bindings(ctx, basename, inputs, rustc_args):
     """Invokes the `cc_bindings_from_rs` tool to generate C++ bindings for a Rust crate.
 
@@ -61,11 +71,32 @@ def _generate_bindings(ctx, basename, inputs, rustc_args):
     crubit_args.add("--clang-format-exe-path", ctx.file._clang_format)
     crubit_args.add("--rustfmt-exe-path", ctx.file._rustfmt)
     crubit_args.add("--rustfmt-config-path", ctx.file._rustfmt_cfg)
+    for dep in ctx.attr.deps:
+        dep_info = dep[CcBindingsFromRustInfo]
+        crubit_args.add(
+            "--crate-header",
+            "%s=%s" % (dep_info.crate_name, dep_info.h_out_file.short_path),
+        )
+
+    extra_inputs = []
+    if ctx.attr.instantiations:
+        instantiations_file = ctx.actions.declare_file(basename + "_instantiations.txt")
+        ctx.actions.write(
+            output = instantiations_file,
+            content = "\n".join(ctx.attr.instantiations) + "\n",
+        )
+        crubit_args.add("--instantiations-file", instantiations_file)
+        extra_inputs.append(instantiations_file)
+
+    crubit_args.add("--int128-mapping", ctx.attr.int128_mapping)
+    crubit_args.add("--result-mapping", ctx.attr.result_mapping)
+    if ctx.attr.exclude_unsafe_fns:
+        crubit_args.add("--exclude-unsafe-fns")
 
     ctx.actions.run(
         outputs = [h_out_file, rs_out_file],
         inputs = depset(
-            [ctx.file._clang_format, ctx.file._rustfmt, ctx.file._rustfmt_cfg],
+            [ctx.file._clang_format, ctx.file._rustfmt, ctx.file._rustfmt_cfg] + extra_inputs,
             transitive = [inputs],
         ),
         executable = ctx.executable._cc_bindings_from_rs_tool,
@@ -90,7 +121,11 @@ def _make_cc_info_for_h_out_file(ctx, h_out_file, linking_contexts):
     Returns:
       A CcInfo provider.
     """
-    cc_deps = ctx.attr._cc_deps_for_bindings
+    cc_deps = ctx.attr._cc_deps_for_bindings + ctx.attr.deps
+    if ctx.attr.int128_mapping == "absl":
+        cc_deps = cc_deps + [ctx.attr._absl_int128]
+    if ctx.attr.result_mapping == "absl-status-or":
+        cc_deps = cc_deps + [ctx.attr._absl_status, ctx.attr._absl_statusor]
     cc_deps_compilation_contexts = [
         cc_dep[CcInfo].compilation_context
         for cc_dep in cc_deps
@@ -143,7 +178,10 @@ def _compile_rs_out_file(ctx, rs_out_file, target_crate):
             cc_info = dep[CcInfo],
             build_info = None,
         )
-        for dep in ctx.attr._rs_deps_for_bindings + [target_crate]
+        for dep in ctx.attr._rs_deps_for_bindings + [target_crate] + [
+            cc_bindings_dep[CcBindingsFromRustInfo].rust_crate
+            for cc_bindings_dep in ctx.attr.deps
+        ]
     ]
     dep_variant_info = compile_rust(ctx, ctx.attr, rs_out_file, [], deps)
     return dep_variant_info.cc_info.linking_context
@@ -217,7 +255,14 @@ def _cc_bindings_from_rust_rule_impl(ctx):
         h_out_file,
         [target_crate_linking_context, impl_linking_context],
     )
-    return [cc_info]
+    return [
+        cc_info,
+        CcBindingsFromRustInfo(
+            crate_name = crate_info.name,
+            h_out_file = h_out_file,
+            rust_crate = ctx.attr.crate,
+        ),
+    ]
 
 # TODO(b/257283134): Register actions via an `aspect`, rather than directly
 # from the `rule` implementation?
@@ -231,6 +276,43 @@ cc_bindings_from_rust = rule(
             mandatory = True,
             providers = [CrateInfo],
         ),
+        "deps": attr.label_list(
+            doc = ("`cc_bindings_from_rust` targets with C++ bindings for the crates " +
+                   "that the public API of `crate` refers to"),
+            providers = [CcBindingsFromRustInfo],
+        ),
+        "exclude_unsafe_fns": attr.bool(
+            doc = "Whether `unsafe` functions from `crate` should be excluded from the bindings",
+            default = False,
+        ),
+        "instantiations": attr.string_list(
+            doc = ("Instantiations of generic functions and types from `crate` that " +
+                   "should get C++ bindings - for example: `my_crate::parse::<u32>` or " +
+                   "`my_crate::Wrapper<i32>`"),
+        ),
+        "int128_mapping": attr.string(
+            doc = ("How Rust's `i128` and `u128` should be represented in C++: " +
+                   "`builtin` (`__int128` and `unsigned __int128`) or " +
+                   "`absl` (`absl::int128` and `absl::uint128`)"),
+            default = "builtin",
+            values = ["builtin", "absl"],
+        ),
+        "result_mapping": attr.string(
+            doc = ("How Rust's `Result<T, E>` should be represented in C++: " +
+                   "`rs-std` (`rs_std::Result<T, E>`) or " +
+                   "`absl-status-or` (`absl::StatusOr<T>` if `E` implements `Display`)"),
+            default = "rs-std",
+            values = ["rs-std", "absl-status-or"],
+        ),
+        "_absl_int128": attr.label(
+            default = "@absl//absl/numeric:int128",
+        ),
+        "_absl_status": attr.label(
+            default = "@absl//absl/status",
+        ),
+        "_absl_statusor": attr.label(
+            default = "@absl//absl/status:statusor",
+        ),
         "_cc_bindings_from_rs_tool": attr.label(
             default = Label("//cc_bindings_from_rs:cc_bindings_from_rs_legacy_toolchain_runner.sar"),
             executable = True,
@@ -250,7 +332,16 @@ cc_bindings_from_rust = rule(
             doc = "Dependencies needed to build the C++ sources generated by cc_bindings_from_rs.",
             default = [
                 "//support/internal:bindings_support",
+                "//support/rs_std:arc",
+                "//support/rs_std:box",
+                "//support/rs_std:fn_ptr",
+                "//support/rs_std:rc",
+                "//support/rs_std:result",
                 "//support/rs_std:rs_char",
+                "//support/rs_std:rs_string",
+                "//support/rs_std:slice_ref",
+                "//support/rs_std:str_ref",
+                "//support/rs_std:vec",
             ],
         ),
         "_process_wrapper": attr.label(
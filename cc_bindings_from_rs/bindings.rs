@@ -10,16 +10,27 @@ use code_gen_utils::{
 use itertools::Itertools;
 use proc_macro2::{Ident, Literal, TokenStream};
 use quote::{format_ident, quote, ToTokens};
-use rustc_hir::{AssocItemKind, ImplItemKind, ImplicitSelfKind, Item, ItemKind, Node, Unsafety};
+use rustc_hir::def::DefKind;
+use rustc_hir::{AssocItemKind, ImplItemKind, Item, ItemKind, Node, Unsafety};
+use rustc_infer::infer::TyCtxtInferExt;
+use rustc_infer::traits::{Obligation, ObligationCause};
 use rustc_middle::dep_graph::DepContext;
+use rustc_middle::mir::interpret::GlobalId;
 use rustc_middle::mir::Mutability;
+use rustc_middle::ty::layout::IntegerExt;
+use rustc_middle::ty::TypeFoldable;
 use rustc_middle::ty::{self, Ty, TyCtxt}; // See <internal link>/ty.html#import-conventions
-use rustc_span::def_id::{DefId, LocalDefId, LOCAL_CRATE};
-use rustc_span::symbol::Symbol;
-use rustc_target::abi::{Abi, FieldsShape, Integer, Layout, Primitive, Scalar};
+use rustc_span::def_id::{CrateNum, DefId, LocalDefId, LOCAL_CRATE};
+use rustc_span::symbol::{kw, Symbol};
+use rustc_target::abi::{
+    Abi, FieldsShape, Integer, Layout, Primitive, Scalar, Size, TagEncoding, Variants,
+};
 use rustc_target::spec::PanicStrategy;
+use rustc_trait_selection::infer::InferCtxtExt;
+use rustc_trait_selection::traits::query::evaluate_obligation::InferCtxtExt as _;
+use std::cell::RefCell;
 use std::cmp::Ordering;
-use std::collections::{BTreeSet, HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
 use std::iter::once;
 use std::ops::AddAssign;
 use std::rc::Rc;
@@ -37,8 +48,32 @@ pub struct Input<'tcx> {
     // TODO(b/262878759): Provide a set of enabled/disabled Crubit features.
     pub _features: (),
 
-    // TODO(b/258261328): Provide a map from crate name into C++ header path with crate bindings.
-    pub _crate_to_include_map: (),
+    /// How the generated bindings should handle Rust panics (only relevant if
+    /// the crate is compiled with `-Cpanic=unwind`).
+    pub panic_handling: PanicHandling,
+
+    /// How Rust's 128-bit integer types (`i128` and `u128`) should be
+    /// represented in C++.
+    pub int128_mapping: Int128Mapping,
+
+    /// How Rust's `Result<T, E>` should be represented in C++.
+    pub result_mapping: ResultMapping,
+
+    /// Whether `unsafe` functions should be excluded from the generated
+    /// bindings (for C++ consumers that only want to call safe Rust APIs).
+    pub exclude_unsafe_fns: bool,
+
+    /// Map from a crate name to the C++ header with the bindings of that crate
+    /// (the header is expected to be `#include`d when the generated bindings
+    /// refer to the types from the other crate).  Example:
+    /// "other_crate" => `CcInclude` of "some/path/other_crate_cc_api.h".
+    pub crate_name_to_include_path: HashMap<Rc<str>, CcInclude>,
+
+    /// Instantiations of generic functions and generic types that should get
+    /// C++ bindings (see also `parse_instantiations`).  Example:
+    /// `my_crate::parse::<u32>` would be represented as `DefId` of `parse` =>
+    /// `[[u32]]`.
+    pub instantiations: HashMap<DefId, Vec<ty::SubstsRef<'tcx>>>,
 }
 
 impl<'tcx> Input<'tcx> {
@@ -52,17 +87,50 @@ impl<'tcx> Input<'tcx> {
     }
 }
 
+/// Specifies what happens when a Rust function called from C++ panics (see
+/// also `panics_and_exceptions.md`).  This is only relevant for crates compiled
+/// with `-Cpanic=unwind` - with `-Cpanic=abort` all panics abort the process.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PanicHandling {
+    /// Print the panic message and abort the process.
+    Abort,
+
+    /// Report the panic to the C++ caller, by throwing `crubit::RustPanic` (or,
+    /// if C++ exceptions are disabled, by printing the panic message and
+    /// aborting the process).
+    Report,
+}
+
+/// Specifies how Rust's 128-bit integer types (`i128` and `u128`) are
+/// represented in C++ (see also `rust_builtin_type_abi_assumptions.md`).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Int128Mapping {
+    /// Use Clang's (and GCC's) builtin `__int128` and `unsigned __int128`.
+    Builtin,
+
+    /// Use `absl::int128` and `absl::uint128` from `absl/numeric/int128.h`.
+    Absl,
+}
+
+/// Specifies how Rust's `Result<T, E>` is represented in C++.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ResultMapping {
+    /// Use `rs_std::Result<T, E>` from `rs_std/result.h`.
+    RsStd,
+
+    /// Use `absl::StatusOr<T>` (or `absl::Status` if `T` is `()`) if `E` is
+    /// a struct, enum, or union that implements `Display` (the message of the
+    /// `absl::Status` comes from `E::to_string`).  Use `rs_std::Result<T, E>`
+    /// otherwise.
+    AbslStatusOr,
+}
+
 pub struct Output {
     pub h_body: TokenStream,
     pub rs_body: TokenStream,
 }
 
 pub fn generate_bindings(input: &Input) -> Result<Output> {
-    match input.tcx.sess().panic_strategy() {
-        PanicStrategy::Unwind => bail!("No support for panic=unwind strategy (b/254049425)"),
-        PanicStrategy::Abort => (),
-    };
-
     let top_comment = {
         let crate_name = input.tcx.crate_name(LOCAL_CRATE);
         let txt = format!(
@@ -78,6 +146,34 @@ pub fn generate_bindings(input: &Input) -> Result<Output> {
         Output { h_body: src.clone(), rs_body: src }
     });
 
+    let rs_panic_helpers = match input.tcx.sess().panic_strategy() {
+        PanicStrategy::Abort => quote! {},
+        PanicStrategy::Unwind => quote! {
+            #[allow(dead_code)]
+            fn __crubit_panic_message(payload: &(dyn ::core::any::Any + Send)) -> &str {
+                if let Some(message) = payload.downcast_ref::<&'static str>() {
+                    message
+                } else if let Some(message) = payload.downcast_ref::<String>() {
+                    message.as_str()
+                } else {
+                    "<unknown panic payload>"
+                }
+            }
+            __NEWLINE__
+
+            #[allow(dead_code)]
+            fn __crubit_abort_on_panic(
+                payload: ::std::boxed::Box<dyn ::core::any::Any + Send>
+            ) -> ! {
+                ::std::eprintln!(
+                    "Aborting after a panic in Rust code called from C++: {}",
+                    __crubit_panic_message(&*payload));
+                ::std::process::abort()
+            }
+            __NEWLINE__
+        },
+    };
+
     let h_body = quote! {
         #top_comment
 
@@ -88,6 +184,135 @@ pub fn generate_bindings(input: &Input) -> Result<Output> {
         #h_body
     };
 
+    let rs_fmt_helpers = quote! {
+        // Used by the thunks of `Display` and `Debug` bindings (see `format_fmt_traits`) to
+        // format directly into a C++ `std::string`.  Errors returned by a `Display` or `Debug`
+        // impl are ignored (the output written so far is kept).
+        #[allow(dead_code)]
+        fn __crubit_write_fmt(
+            out: *mut ::core::ffi::c_void,
+            append: extern "C" fn(*mut ::core::ffi::c_void, *const u8, usize),
+            args: ::core::fmt::Arguments,
+        ) {
+            struct Writer(
+                *mut ::core::ffi::c_void,
+                extern "C" fn(*mut ::core::ffi::c_void, *const u8, usize),
+            );
+            impl ::core::fmt::Write for Writer {
+                fn write_str(&mut self, s: &str) -> ::core::fmt::Result {
+                    (self.1)(self.0, s.as_ptr(), s.len());
+                    Ok(())
+                }
+            }
+            let _ = ::core::fmt::Write::write_fmt(&mut Writer(out, append), args);
+        }
+        __NEWLINE__
+    };
+
+    let drop_vec_body = format_rs_thunk_body_aborting_on_panic(
+        input.tcx,
+        quote! { ::core::mem::drop(::std::vec::Vec::from_raw_parts(data, size, capacity)); },
+    );
+    let rs_vec_helpers = quote! {
+        // Used by the thunks to pass `String` and `Vec<T>` by value to/from C++.  The layout
+        // of `Vec<T>` is unspecified, so the thunks use `#[repr(C)]` `__CrubitVec<T>` which
+        // needs to stay in sync with the fields of `rs_std::Vec<T>` (`rs_std::String` wraps
+        // `rs_std::Vec<char>`).
+        #[allow(dead_code)]
+        #[repr(C)]
+        struct __CrubitVec<T> {
+            data: *mut T,
+            size: usize,
+            capacity: usize,
+            drop: ::core::option::Option<unsafe extern "C" fn(*mut T, usize, usize)>,
+        }
+        __NEWLINE__
+
+        #[allow(dead_code)]
+        impl<T> __CrubitVec<T> {
+            fn from_vec(vec: ::std::vec::Vec<T>) -> Self {
+                let mut vec = ::core::mem::ManuallyDrop::new(vec);
+                Self {
+                    data: vec.as_mut_ptr(),
+                    size: vec.len(),
+                    capacity: vec.capacity(),
+                    drop: Some(__crubit_vec_drop::<T>),
+                }
+            }
+            __NEWLINE__
+
+            // Takes the ownership of the allocation away from C++ (which is left with an
+            // empty `rs_std::Vec<T>`, similarly to how `::core::mem::take` would leave it).
+            fn take_vec(vec: &mut Self) -> ::std::vec::Vec<T> {
+                let vec = ::core::mem::replace(vec, Self {
+                    data: ::core::ptr::NonNull::dangling().as_ptr(),
+                    size: 0,
+                    capacity: 0,
+                    drop: None,
+                });
+                if vec.drop.is_none() {
+                    return ::std::vec::Vec::new();
+                }
+                unsafe { ::std::vec::Vec::from_raw_parts(vec.data, vec.size, vec.capacity) }
+            }
+        }
+        __NEWLINE__
+
+        #[allow(dead_code)]
+        impl __CrubitVec<u8> {
+            fn from_string(s: ::std::string::String) -> Self {
+                Self::from_vec(s.into_bytes())
+            }
+            __NEWLINE__
+
+            // `rs_std::String` can only be created from a Rust `String`, so the bytes are
+            // guaranteed to be valid UTF-8.
+            fn take_string(s: &mut Self) -> ::std::string::String {
+                unsafe { ::std::string::String::from_utf8_unchecked(Self::take_vec(s)) }
+            }
+        }
+        __NEWLINE__
+
+        #[allow(dead_code)]
+        unsafe extern "C" fn __crubit_vec_drop<T>(data: *mut T, size: usize, capacity: usize) {
+            #drop_vec_body
+        }
+        __NEWLINE__
+    };
+
+    let rs_fn_ptr_helpers = quote! {
+        // Used by the thunks to pass Rust-ABI function pointers to/from C++ (see
+        // `format_into_ffi_value_for_rs`).  Needs to stay in sync with `rs_std::FnPtr<F>`.
+        #[allow(dead_code)]
+        #[repr(C)]
+        struct __CrubitFnPtr<F> {
+            fn_ptr: F,
+            trampoline: *const (),
+        }
+        __NEWLINE__
+    };
+
+    let rs_ref_counted_ptr_helpers = quote! {
+        // Used by the thunks to take `Rc<T>` and `Arc<T>` parameters out of `rs_std::Rc<T>` and
+        // `rs_std::Arc<T>` (see `RefCountedPtr`).  A null pointer is left behind, so that the
+        // C++ destructor of the moved-away object is a no-op.
+        #[allow(dead_code)]
+        fn __crubit_take_rc<T>(ptr: &mut *const T) -> ::std::rc::Rc<T> {
+            let ptr = ::core::mem::replace(ptr, ::core::ptr::null());
+            assert!(!ptr.is_null(), "Null `rs_std::Rc` passed as `Rc<T>`");
+            unsafe { ::std::rc::Rc::from_raw(ptr) }
+        }
+        __NEWLINE__
+
+        #[allow(dead_code)]
+        fn __crubit_take_arc<T>(ptr: &mut *const T) -> ::std::sync::Arc<T> {
+            let ptr = ::core::mem::replace(ptr, ::core::ptr::null());
+            assert!(!ptr.is_null(), "Null `rs_std::Arc` passed as `Arc<T>`");
+            unsafe { ::std::sync::Arc::from_raw(ptr) }
+        }
+        __NEWLINE__
+    };
+
     let rs_body = quote! {
         #top_comment
 
@@ -97,12 +322,233 @@ pub fn generate_bindings(input: &Input) -> Result<Output> {
         #![allow(improper_ctypes_definitions)] __NEWLINE__
         __NEWLINE__
 
+        #rs_panic_helpers
+        #rs_fmt_helpers
+        #rs_vec_helpers
+        #rs_fn_ptr_helpers
+        #rs_ref_counted_ptr_helpers
         #rs_body
     };
 
     Ok(Output { h_body, rs_body })
 }
 
+/// Parses `instantiations` of generic functions and generic types that should
+/// get C++ bindings.  Each instantiation is spelled as a path to a generic
+/// item from the current crate, followed by concrete type arguments:
+/// - Functions use a turbofish - for example: `my_crate::parse::<u32>` or
+///   `my_crate::SomeStruct::some_method::<f64, my_crate::SomeOtherStruct>`.
+/// - Structs, enums, and unions don't - for example: `my_crate::Wrapper<i32>`.
+///
+/// Type arguments may be either primitive types (e.g. `u32` or `bool`), or
+/// structs, enums, or unions from the current crate (e.g. `my_crate::Foo` or
+/// `my_crate::Wrapper<i32>`).
+pub fn parse_instantiations<'tcx, 'a>(
+    tcx: TyCtxt<'tcx>,
+    instantiations: impl IntoIterator<Item = &'a str>,
+) -> Result<HashMap<DefId, Vec<ty::SubstsRef<'tcx>>>> {
+    let mut result: HashMap<DefId, Vec<ty::SubstsRef<'tcx>>> = HashMap::new();
+    for instantiation in instantiations {
+        let (def_id, substs) = parse_instantiation(tcx, instantiation)
+            .with_context(|| format!("Error parsing instantiation `{instantiation}`"))?;
+        let substs_list = result.entry(def_id).or_default();
+        if !substs_list.contains(&substs) {
+            substs_list.push(substs);
+        }
+    }
+    Ok(result)
+}
+
+fn parse_instantiation<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    instantiation: &str,
+) -> Result<(DefId, ty::SubstsRef<'tcx>)> {
+    let (path, type_args) = instantiation
+        .trim()
+        .strip_suffix('>')
+        .and_then(|s| s.split_once('<'))
+        .ok_or_else(|| anyhow!("Expected `PATH::<TYPE_ARGS>` or `PATH<TYPE_ARGS>`"))?;
+    let def_id = match path.strip_suffix("::") {
+        Some(path) => find_local_item_by_path(tcx, path, |def_kind| {
+            matches!(def_kind, DefKind::Fn | DefKind::AssocFn)
+        })
+        .ok_or_else(|| anyhow!("Couldn't find a function named `{path}` in the current crate"))?,
+        None => find_local_item_by_path(tcx, path, |def_kind| {
+            matches!(def_kind, DefKind::Struct | DefKind::Enum | DefKind::Union)
+        })
+        .ok_or_else(|| {
+            anyhow!("Couldn't find a struct, enum, or union named `{path}` in the current crate")
+        })?,
+    };
+    let path = path.trim_end_matches("::");
+
+    let generics = tcx.generics_of(def_id);
+    ensure!(
+        generics.parent_count == 0,
+        "Methods of generic `impl`s are not supported yet (b/259749095)"
+    );
+    let mut type_params_count = 0;
+    for param in generics.params.iter() {
+        match param.kind {
+            ty::GenericParamDefKind::Lifetime => (),
+            ty::GenericParamDefKind::Type { synthetic: true, .. } => {
+                bail!("`impl Trait` parameters are not supported yet (b/259749023)")
+            }
+            ty::GenericParamDefKind::Type { .. } => type_params_count += 1,
+            ty::GenericParamDefKind::Const { .. } => {
+                bail!("Const generics are not supported yet (b/259749023)")
+            }
+        }
+    }
+    let type_args = split_type_args(type_args)?
+        .into_iter()
+        .map(|type_arg| parse_instantiation_ty(tcx, type_arg))
+        .collect::<Result<Vec<_>>>()?;
+    ensure!(
+        type_args.len() == type_params_count,
+        "`{path}` expects {type_params_count} type argument(s), but got {}",
+        type_args.len()
+    );
+    let mut type_args = type_args.into_iter();
+    let substs = ty::InternalSubsts::for_item(tcx, def_id, |param, _| match param.kind {
+        ty::GenericParamDefKind::Lifetime => tcx.lifetimes.re_erased.into(),
+        _ => type_args.next().expect("The number of type arguments is verified above").into(),
+    });
+
+    // Unsatisfied trait bounds would only be detected when compiling the generated
+    // `..._cc_api_impl.rs` - it is more user-friendly to report them here.
+    if let Some(predicate) = find_unsatisfied_predicate(tcx, def_id, substs) {
+        bail!("The type arguments don't satisfy the trait bound: `{predicate}`");
+    }
+
+    Ok((def_id, substs))
+}
+
+/// Returns the first predicate (e.g. a trait bound) of the generic item
+/// identified by `def_id` that doesn't hold for the given `substs`.
+fn find_unsatisfied_predicate<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    def_id: DefId,
+    substs: ty::SubstsRef<'tcx>,
+) -> Option<ty::Predicate<'tcx>> {
+    let infcx = tcx.infer_ctxt().build();
+    let param_env = ty::ParamEnv::empty();
+    tcx.predicates_of(def_id).instantiate(tcx, substs).predicates.into_iter().find(|&predicate| {
+        let obligation = Obligation::new(tcx, ObligationCause::dummy(), param_env, predicate);
+        !infcx.predicate_must_hold_modulo_regions(&obligation)
+    })
+}
+
+/// Splits a comma-separated list of type arguments (e.g. `u32, SomeStruct`),
+/// ignoring commas nested inside `<...>` or `(...)`.
+fn split_type_args(type_args: &str) -> Result<Vec<&str>> {
+    let mut result = vec![];
+    let mut depth = 0_usize;
+    let mut start = 0;
+    for (i, c) in type_args.char_indices() {
+        match c {
+            '<' | '(' => depth += 1,
+            '>' | ')' => {
+                depth = depth
+                    .checked_sub(1)
+                    .ok_or_else(|| anyhow!("Unbalanced `{c}` in `{type_args}`"))?;
+            }
+            ',' if depth == 0 => {
+                result.push(type_args[start..i].trim());
+                start = i + 1;
+            }
+            _ => (),
+        }
+    }
+    ensure!(depth == 0, "Unbalanced brackets in `{type_args}`");
+    result.push(type_args[start..].trim());
+    ensure!(result.iter().all(|s| !s.is_empty()), "Empty type argument in `{type_args}`");
+    Ok(result)
+}
+
+fn parse_instantiation_ty<'tcx>(tcx: TyCtxt<'tcx>, type_arg: &str) -> Result<Ty<'tcx>> {
+    Ok(match type_arg {
+        "bool" => tcx.types.bool,
+        "char" => tcx.types.char,
+        "f32" => tcx.types.f32,
+        "f64" => tcx.types.f64,
+        "i8" => tcx.types.i8,
+        "i16" => tcx.types.i16,
+        "i32" => tcx.types.i32,
+        "i64" => tcx.types.i64,
+        "i128" => tcx.types.i128,
+        "isize" => tcx.types.isize,
+        "u8" => tcx.types.u8,
+        "u16" => tcx.types.u16,
+        "u32" => tcx.types.u32,
+        "u64" => tcx.types.u64,
+        "u128" => tcx.types.u128,
+        "usize" => tcx.types.usize,
+        _ if type_arg.ends_with('>') => {
+            let (def_id, substs) = parse_instantiation(tcx, type_arg)?;
+            ensure!(
+                matches!(tcx.def_kind(def_id), DefKind::Struct | DefKind::Enum | DefKind::Union),
+                "Unsupported type argument `{type_arg}` (expected a type, not a function)"
+            );
+            tcx.type_of(def_id).subst(tcx, substs)
+        }
+        _ => {
+            let def_id = find_local_item_by_path(tcx, type_arg, |def_kind| {
+                matches!(def_kind, DefKind::Struct | DefKind::Enum | DefKind::Union)
+            })
+            .ok_or_else(|| {
+                anyhow!(
+                    "Unsupported type argument `{type_arg}` (expected either a primitive type, \
+                     or a path to a struct, enum, or union from the current crate)"
+                )
+            })?;
+            ensure!(
+                tcx.generics_of(def_id).count() == 0,
+                "Missing type arguments of the generic type `{type_arg}`"
+            );
+            tcx.type_of(def_id).subst_identity()
+        }
+    })
+}
+
+/// Finds an item from the current crate by its `path` (e.g.
+/// `my_crate::some_module::SomeStruct::some_method`).  Only items with a
+/// `DefKind` accepted by `is_expected_kind` are considered.
+fn find_local_item_by_path(
+    tcx: TyCtxt,
+    path: &str,
+    is_expected_kind: impl Fn(DefKind) -> bool,
+) -> Option<DefId> {
+    let path = path.split("::").map(str::trim).collect_vec();
+    tcx.hir_crate_items(())
+        .definitions()
+        .map(|local_def_id| local_def_id.to_def_id())
+        .filter(|&def_id| is_expected_kind(tcx.def_kind(def_id)))
+        .find(|&def_id| format_item_path(tcx, def_id) == path)
+}
+
+/// Formats the path of `def_id` as a sequence of names - e.g. `["my_crate",
+/// "some_module", "SomeStruct", "some_method"]`.  Methods are qualified with
+/// the name of the self type of their `impl`.
+fn format_item_path(tcx: TyCtxt, def_id: DefId) -> Vec<String> {
+    let mut path = vec![tcx.crate_name(def_id.krate).to_string()];
+    path.extend(
+        tcx.def_path(def_id)
+            .data
+            .iter()
+            .filter_map(|elem| elem.data.get_opt_name())
+            .map(|name| name.to_string()),
+    );
+    if let Some(impl_id) = tcx.impl_of_method(def_id) {
+        if let ty::ImplSubject::Inherent(self_ty) = tcx.impl_subject(impl_id) {
+            if let Some(adt) = self_ty.ty_adt_def() {
+                path.insert(path.len() - 1, tcx.item_name(adt.did()).to_string());
+            }
+        }
+    }
+    path
+}
+
 #[derive(Clone, Debug, Default)]
 struct CcPrerequisites {
     /// Set of `#include`s that a `CcSnippet` depends on.  For example if
@@ -192,6 +638,7 @@ impl CcSnippet {
 
 /// Represents the fully qualified name of a Rust item (e.g. of a `struct` or a
 /// function).
+#[derive(Clone, PartialEq)]
 struct FullyQualifiedName {
     /// Name of the crate that defines the item.
     /// For example, this would be `std` for `std::cmp::Ordering`.
@@ -212,9 +659,29 @@ struct FullyQualifiedName {
 impl FullyQualifiedName {
     /// Computes a `FullyQualifiedName` for `def_id`.
     ///
+    /// Items that are not directly public, but that have been re-exported via
+    /// `pub use` are named by their public path (see `public_path`).
+    /// Associated items (e.g. methods) use the module path of their `Self`
+    /// type.
+    ///
     /// May panic if `def_id` is an invalid id.
     // TODO(b/259724276): This function's results should be memoized.
     fn new(tcx: TyCtxt, def_id: DefId) -> Self {
+        let self_adt = tcx
+            .impl_of_method(def_id)
+            .and_then(|impl_id| tcx.type_of(impl_id).subst_identity().ty_adt_def())
+            .filter(|adt| adt.did().krate == def_id.krate);
+        if let Some(self_adt) = self_adt {
+            let Self { krate, mod_path, .. } = Self::new(tcx, self_adt.did());
+            return Self { krate, mod_path, name: tcx.opt_item_name(def_id) };
+        }
+
+        public_path(tcx, def_id).unwrap_or_else(|| Self::from_def_path(tcx, def_id))
+    }
+
+    /// Computes a `FullyQualifiedName` for `def_id`, based on the path where
+    /// the item has been defined (ignoring any re-exports).
+    fn from_def_path(tcx: TyCtxt, def_id: DefId) -> Self {
         let krate = tcx.crate_name(def_id.krate);
 
         let mut full_path = tcx.def_path(def_id).data; // mod_path + name
@@ -257,22 +724,92 @@ fn format_ret_ty_for_cc<'tcx>(input: &Input<'tcx>, ty: Ty<'tcx>) -> Result<CcSni
     match ty.kind() {
         ty::TyKind::Never => void,  // `!`
         ty::TyKind::Tuple(types) if types.len() == 0 => void,  // `()`
+        _ => format_param_ty_for_cc(input, ty),
+    }
+}
+
+/// Formats the type of a function parameter.  This is the same as
+/// `format_ty_for_cc`, except that `i128` and `u128` are supported on more
+/// targets (see `format_int128_for_cc`).
+fn format_param_ty_for_cc<'tcx>(input: &Input<'tcx>, ty: Ty<'tcx>) -> Result<CcSnippet> {
+    match ty.kind() {
+        ty::TyKind::Int(ty::IntTy::I128) | ty::TyKind::Uint(ty::UintTy::U128) => {
+            format_int128_for_cc(input, ty, /* is_fn_param_or_ret = */ true)
+        }
         _ => format_ty_for_cc(input, ty),
     }
 }
 
+/// Formats `i128` or `u128` as `__int128` or `unsigned __int128` (or as
+/// `absl::int128` or `absl::uint128` - see `Int128Mapping`).
+///
+/// Note that "the alignment of Rust's {i,u}128 is unspecified and allowed to change" according
+/// to
+/// https://rust-lang.github.io/unsafe-code-guidelines/layout/scalars.html#fixed-width-integer-types
+/// and that some Rust targets use 8-byte alignment.  Clang (and GCC) always use 16-byte
+/// alignment for `__int128` - this is verified by the static assertions in
+/// `crubit/support/internal/int128.h`.  `absl::int128` uses `__int128` under the hood (when it
+/// is available) and therefore has the same layout.
+///
+/// In general (e.g. in struct fields, or behind pointers and references) the Rust layout has
+/// to match the C++ layout exactly.  Function parameters and return values are an exception
+/// (`is_fn_param_or_ret = true`): they are always passed to and from the Rust thunks by pointer
+/// (see `is_c_abi_compatible_by_value`) and the pointers always point to C++ storage - 16-byte
+/// aligned storage also satisfies the alignment requirements of a Rust type with a lower
+/// alignment.
+fn format_int128_for_cc<'tcx>(
+    input: &Input<'tcx>,
+    ty: Ty<'tcx>,
+    is_fn_param_or_ret: bool,
+) -> Result<CcSnippet> {
+    let layout = input
+        .tcx
+        .layout_of(ty::ParamEnv::empty().and(ty))
+        .expect("`layout_of` is expected to succeed for the builtin 128-bit integer types")
+        .layout;
+    let (size, align) = (layout.size().bytes(), layout.align().abi.bytes());
+    let is_align_compatible = if is_fn_param_or_ret { 16 % align == 0 } else { align == 16 };
+    ensure!(
+        size == 16 && is_align_compatible,
+        "`{ty}` has a different layout in Rust (size = {size}, align = {align}) \
+         than in C++ (size = 16, align = 16) on this target (b/254094650)"
+    );
+
+    let is_signed = ty.is_signed();
+    let mut prereqs = CcPrerequisites::default();
+    prereqs.includes.insert(input.support_header("internal/int128.h"));
+    let tokens = match (input.int128_mapping, is_signed) {
+        (Int128Mapping::Builtin, true) => quote! { __int128 },
+        (Int128Mapping::Builtin, false) => quote! { unsigned __int128 },
+        (Int128Mapping::Absl, _) => {
+            prereqs.includes.insert(CcInclude::user_header("absl/numeric/int128.h".into()));
+            if is_signed { quote! { absl::int128 } } else { quote! { absl::uint128 } }
+        }
+    };
+    Ok(CcSnippet { tokens, prereqs })
+}
+
 /// Whether functions using `extern "C"` ABI can safely handle values of type
 /// `ty` (e.g. when passing by value arguments or return values of such type).
 fn is_c_abi_compatible_by_value(ty: Ty) -> bool {
     match ty.kind() {
+        // Rust doesn't guarantee that the `extern "C"` ABI of `i128` and `u128` matches the ABI
+        // of `__int128` (and historically it didn't on x86-64).  Values of these types are
+        // therefore passed by pointer - this only depends on the layout of these types (which
+        // `format_ty_for_cc` verifies).
+        ty::TyKind::Int(ty::IntTy::I128) | ty::TyKind::Uint(ty::UintTy::U128) => false,
+
         // `improper_ctypes_definitions` warning doesn't complain about the following types:
         ty::TyKind::Bool |
         ty::TyKind::Float{..} |
         ty::TyKind::Int{..} |
         ty::TyKind::Uint{..} |
         ty::TyKind::Never |
-        ty::TyKind::RawPtr{..} |
-        ty::TyKind::FnPtr{..} => true,
+        ty::TyKind::RawPtr{..} => true,
+
+        // `extern "C"` function pointers are represented as C++ function pointers, but Rust-ABI
+        // function pointers are represented as `rs_std::FnPtr<F>` (see `is_rust_abi_fn_ptr`).
+        ty::TyKind::FnPtr{..} => !is_rust_abi_fn_ptr(ty),
         ty::TyKind::Tuple(types) if types.len() == 0 => true,
 
         // Crubit assumes that `char` is compatible with a certain `extern "C"` ABI.
@@ -297,16 +834,15 @@ fn is_c_abi_compatible_by_value(ty: Ty) -> bool {
         ty::TyKind::Tuple{..} |  // An empty tuple (`()` - the unit type) is handled above.
         ty::TyKind::Adt{..} => false,
 
-        // These kinds of reference-related types are not implemented yet - `is_c_abi_compatible_by_value`
+        // References to sized types have the same ABI as `TyKind::RawPtr` (and as C++
+        // references).  Crubit assumes that references to slices (`&[T]`) or strings (`&str`)
+        // are compatible with a certain `extern "C"` ABI (the one of `rs_std::SliceRef<T>` and
+        // `rs_std::StrRef`).  See `rust_builtin_type_abi_assumptions.md` for more details.
+        ty::TyKind::Ref{..} => true,
+
+        // These kinds of types are not implemented yet - `is_c_abi_compatible_by_value`
         // should never need to handle them, because `format_ty_for_cc` fails for such types.
-        //
-        // TODO(b/258235219): When implementing support for references we should
-        // consider returning `true` for `TyKind::Ref` and document the rationale
-        // for such decision - maybe something like this will be sufficient:
-        // - In general `TyKind::Ref` should have the same ABI as `TyKind::RawPtr`
-        // - References to slices (`&[T]`) or strings (`&str`) rely on assumptions
-        //   spelled out in `rust_builtin_type_abi_assumptions.md`..
-        ty::TyKind::Ref{..} |
+        // (Slices and strings are only supported behind a reference - see above.)
         ty::TyKind::Str |
         ty::TyKind::Array{..} |
         ty::TyKind::Slice{..} =>
@@ -319,6 +855,363 @@ fn is_c_abi_compatible_by_value(ty: Ty) -> bool {
     }
 }
 
+/// Returns the element types of `ty` if `ty` is a non-empty tuple.  Values of
+/// such types are marshalled one element at a time when crossing the FFI
+/// boundary (because Rust doesn't guarantee any specific layout of tuples).
+fn tuple_element_types<'tcx>(ty: Ty<'tcx>) -> Option<&'tcx ty::List<Ty<'tcx>>> {
+    match ty.kind() {
+        ty::TyKind::Tuple(types) if !types.is_empty() => Some(types),
+        _ => None,
+    }
+}
+
+/// Returns `T` if `ty` is either `Box<T>` or `Option<Box<T>>`.  Both of these
+/// types are represented in C++ as `rs_std::Box<T>` (see also
+/// `rust_builtin_type_abi_assumptions.md`).
+fn boxed_ty<'tcx>(tcx: TyCtxt<'tcx>, ty: Ty<'tcx>) -> Option<Ty<'tcx>> {
+    match ty.kind() {
+        _ if ty.is_box() => Some(ty.boxed_ty()),
+        ty::TyKind::Adt(adt, substs)
+            if tcx.is_diagnostic_item(rustc_span::symbol::sym::Option, adt.did())
+                && substs.type_at(0).is_box() =>
+        {
+            Some(substs.type_at(0).boxed_ty())
+        }
+        _ => None,
+    }
+}
+
+/// `Rc<T>` or `Arc<T>` (other than `Arc<dyn Trait>` which is handled by
+/// `dyn_ptr`).  These types are represented in C++ as `rs_std::Rc<T>` and
+/// `rs_std::Arc<T>`.  Rust doesn't guarantee any specific layout of `Rc<T>`,
+/// so `format_fn` passes them across the FFI boundary as the pointer returned
+/// by `Rc::into_raw` (see also `rust_builtin_type_abi_assumptions.md`).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum RefCountedPtr {
+    Rc,
+    Arc,
+}
+
+impl RefCountedPtr {
+    /// Formats the path of the Rust type (without the type argument).
+    fn format_for_rs(self) -> TokenStream {
+        match self {
+            RefCountedPtr::Rc => quote! { ::std::rc::Rc },
+            RefCountedPtr::Arc => quote! { ::std::sync::Arc },
+        }
+    }
+}
+
+/// Returns the kind of the pointer and the pointee (i.e. `T`) if `ty` is
+/// either `Rc<T>` or `Arc<T>` (see `RefCountedPtr`).
+fn ref_counted_ptr<'tcx>(tcx: TyCtxt<'tcx>, ty: Ty<'tcx>) -> Option<(RefCountedPtr, Ty<'tcx>)> {
+    let (adt, substs) = match ty.kind() {
+        ty::TyKind::Adt(adt, substs) => (adt, substs),
+        _ => return None,
+    };
+    let kind = if tcx.is_diagnostic_item(rustc_span::symbol::sym::Rc, adt.did()) {
+        RefCountedPtr::Rc
+    } else if tcx.is_diagnostic_item(rustc_span::symbol::sym::Arc, adt.did()) {
+        RefCountedPtr::Arc
+    } else {
+        return None;
+    };
+    let pointee = substs.type_at(0);
+    if pointee.is_trait() {
+        None
+    } else {
+        Some((kind, pointee))
+    }
+}
+
+/// `Option<T>` or `Result<T, E>` (other than `Option<Box<T>>` which is handled
+/// by `boxed_ty`).  Rust doesn't guarantee any specific layout of these types,
+/// so `format_fn` marshals the `T` value (or the `E` error) and the
+/// discriminant separately when crossing the FFI boundary.
+#[derive(Clone, Copy, Debug)]
+enum OptionOrResult<'tcx> {
+    Option(Ty<'tcx>),
+    Result(Ty<'tcx>, Ty<'tcx>),
+}
+
+fn option_or_result<'tcx>(tcx: TyCtxt<'tcx>, ty: Ty<'tcx>) -> Option<OptionOrResult<'tcx>> {
+    if boxed_ty(tcx, ty).is_some() {
+        return None;
+    }
+    match ty.kind() {
+        ty::TyKind::Adt(adt, substs)
+            if tcx.is_diagnostic_item(rustc_span::symbol::sym::Option, adt.did()) =>
+        {
+            Some(OptionOrResult::Option(substs.type_at(0)))
+        }
+        ty::TyKind::Adt(adt, substs)
+            if tcx.is_diagnostic_item(rustc_span::symbol::sym::Result, adt.did()) =>
+        {
+            Some(OptionOrResult::Result(substs.type_at(0), substs.type_at(1)))
+        }
+        _ => None,
+    }
+}
+
+/// Whether `Result<ok_ty, err_ty>` should be represented in C++ as
+/// `absl::StatusOr<T>` (or `absl::Status`) - see `ResultMapping::AbslStatusOr`.
+fn is_absl_status_or<'tcx>(input: &Input<'tcx>, err_ty: Ty<'tcx>) -> bool {
+    let tcx = input.tcx;
+    if input.result_mapping != ResultMapping::AbslStatusOr || !err_ty.is_adt() {
+        return false;
+    }
+    let display_trait_id = match tcx.get_diagnostic_item(rustc_span::symbol::sym::Display) {
+        Some(display_trait_id) => display_trait_id,
+        None => return false,
+    };
+    // `ToString` is only generated for structs, (non-`enum class`) enums, and unions - see
+    // `format_fmt_traits`.
+    does_type_implement_trait(tcx, err_ty, display_trait_id)
+        && format_adt_core(tcx, err_ty)
+            .map_or(false, |core| core.enum_class_underlying_type.is_none())
+}
+
+/// Returns true if `ty` is a Rust-ABI function pointer (e.g. `fn(i32) -> i32`).
+/// Such function pointers are represented in C++ as `rs_std::FnPtr<F>` which
+/// also holds a pointer to an `extern "C"` trampoline (see
+/// `format_into_ffi_value_for_rs`).  Unlike `extern "C"` function pointers,
+/// they therefore don't have the same layout in C++ and in Rust.
+fn is_rust_abi_fn_ptr(ty: Ty) -> bool {
+    match ty.kind() {
+        ty::TyKind::FnPtr(sig) => sig.abi() == rustc_target::spec::abi::Abi::Rust,
+        _ => false,
+    }
+}
+
+/// `String` or `Vec<T>` (using the global allocator).  These types are
+/// represented in C++ as `rs_std::String` and `rs_std::Vec<T>`.  Rust doesn't
+/// guarantee any specific layout of `Vec<T>`, so `format_fn` passes them
+/// across the FFI boundary as `__CrubitVec<T>` (see `generate_bindings`) - a
+/// `#[repr(C)]` struct that holds the raw parts of a `Vec<T>` and that has the
+/// same layout as `rs_std::Vec<T>`.
+#[derive(Clone, Copy, Debug)]
+enum StringOrVec<'tcx> {
+    String,
+    Vec(Ty<'tcx>),
+}
+
+fn string_or_vec<'tcx>(tcx: TyCtxt<'tcx>, ty: Ty<'tcx>) -> Option<StringOrVec<'tcx>> {
+    // `Vec::from_raw_parts` (used by `__CrubitVec::take_vec`) assumes the global allocator.
+    let is_global_allocator = |ty: Ty<'tcx>| match ty.kind() {
+        ty::TyKind::Adt(adt, _) => {
+            tcx.crate_name(adt.did().krate).as_str() == "alloc"
+                && tcx.item_name(adt.did()).as_str() == "Global"
+        }
+        _ => false,
+    };
+    match ty.kind() {
+        ty::TyKind::Adt(adt, _)
+            if tcx.is_diagnostic_item(rustc_span::symbol::sym::String, adt.did()) =>
+        {
+            Some(StringOrVec::String)
+        }
+        ty::TyKind::Adt(adt, substs)
+            if tcx.is_diagnostic_item(rustc_span::symbol::sym::Vec, adt.did())
+                && is_global_allocator(substs.type_at(1)) =>
+        {
+            Some(StringOrVec::Vec(substs.type_at(0)))
+        }
+        _ => None,
+    }
+}
+
+/// Formats the Rust type that is used by the thunks to pass values of `ty`
+/// across the FFI boundary.  This is the same as `format_ty_for_rs`, except
+/// for `String` and `Vec<T>` (see `StringOrVec`), for Rust-ABI function
+/// pointers (see `is_rust_abi_fn_ptr`), for `Rc<T>` and `Arc<T>` (which are
+/// passed as the pointer returned by `into_raw` - see `RefCountedPtr`), and
+/// for `Box<dyn Trait>` and `Arc<dyn Trait>` (which are wrapped in an
+/// `Option`, so that a moved-from `Trait::DynBox` or `Trait::DynArc` can hold
+/// `None`).
+fn format_ffi_ty_for_rs<'tcx>(tcx: TyCtxt<'tcx>, ty: Ty<'tcx>) -> Result<TokenStream> {
+    if is_rust_abi_fn_ptr(ty) {
+        let fn_ptr_type = format_ty_for_rs(tcx, ty)?;
+        return Ok(quote! { __CrubitFnPtr<#fn_ptr_type> });
+    }
+    if let Some((_, pointee)) = ref_counted_ptr(tcx, ty) {
+        let pointee = format_ty_for_rs(tcx, pointee)?;
+        return Ok(quote! { *const #pointee });
+    }
+    if let Some((DynPtr::Box | DynPtr::Arc, _)) = dyn_ptr(tcx, ty) {
+        let ptr_type = format_ty_for_rs(tcx, ty)?;
+        return Ok(quote! { ::core::option::Option<#ptr_type> });
+    }
+    Ok(match string_or_vec(tcx, ty) {
+        Some(StringOrVec::String) => quote! { __CrubitVec<u8> },
+        Some(StringOrVec::Vec(element_ty)) => {
+            let element = format_ty_for_rs(tcx, element_ty)?;
+            quote! { __CrubitVec<#element> }
+        }
+        None => format_ty_for_rs(tcx, ty)?,
+    })
+}
+
+/// Converts `value` of type `ty` into the type from `format_ffi_ty_for_rs`.
+///
+/// Rust-ABI function pointers are paired with an `extern "C"` trampoline that
+/// C++ can call (the trampoline takes the Rust function pointer as its first
+/// argument - see `rs_std::FnPtr<F>::Trampoline`).
+fn format_into_ffi_value_for_rs<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    ty: Ty<'tcx>,
+    value: TokenStream,
+) -> Result<TokenStream> {
+    if let ty::TyKind::FnPtr(sig) = ty.kind() {
+        if is_rust_abi_fn_ptr(ty) {
+            let sig = tcx.erase_late_bound_regions(*sig);
+            let fn_ptr_type = format_ty_for_rs(tcx, ty)?;
+            let arg_names = (0..sig.inputs().len())
+                .map(|i| format_ident!("__arg{}", i))
+                .collect_vec();
+            let arg_types = sig
+                .inputs()
+                .iter()
+                .map(|&arg_ty| format_ty_for_rs(tcx, arg_ty))
+                .collect::<Result<Vec<_>>>()?;
+            let ret_type = format_ty_for_rs(tcx, sig.output())?;
+            let call = match sig.unsafety {
+                Unsafety::Normal => quote! { __fn_ptr( #( #arg_names ),* ) },
+                Unsafety::Unsafe => quote! { unsafe { __fn_ptr( #( #arg_names ),* ) } },
+            };
+            let body = format_rs_thunk_body_aborting_on_panic(tcx, call);
+            return Ok(quote! {
+                __CrubitFnPtr {
+                    fn_ptr: #value,
+                    trampoline: {
+                        extern "C" fn __crubit_trampoline(
+                            __fn_ptr: #fn_ptr_type, #( #arg_names: #arg_types ),*
+                        ) -> #ret_type {
+                            #body
+                        }
+                        __crubit_trampoline as *const ()
+                    },
+                }
+            });
+        }
+    }
+    if let Some((DynPtr::Box | DynPtr::Arc, _)) = dyn_ptr(tcx, ty) {
+        return Ok(quote! { ::core::option::Option::Some(#value) });
+    }
+    if let Some((kind, _)) = ref_counted_ptr(tcx, ty) {
+        let ptr_type = kind.format_for_rs();
+        return Ok(quote! { #ptr_type::into_raw(#value) });
+    }
+    Ok(match string_or_vec(tcx, ty) {
+        Some(StringOrVec::String) => quote! { __CrubitVec::from_string(#value) },
+        Some(StringOrVec::Vec(_)) => quote! { __CrubitVec::from_vec(#value) },
+        None => value,
+    })
+}
+
+/// Formats a function that takes a `&mut` reference to the type from
+/// `format_ffi_ty_for_rs` and moves the value out of it (leaving behind a
+/// value that is safe to drop from C++).
+fn format_take_fn_for_rs<'tcx>(tcx: TyCtxt<'tcx>, ty: Ty<'tcx>) -> TokenStream {
+    match ref_counted_ptr(tcx, ty) {
+        Some((RefCountedPtr::Rc, _)) => return quote! { __crubit_take_rc },
+        Some((RefCountedPtr::Arc, _)) => return quote! { __crubit_take_arc },
+        None => (),
+    }
+    match string_or_vec(tcx, ty) {
+        Some(StringOrVec::String) => quote! { __CrubitVec::take_string },
+        Some(StringOrVec::Vec(_)) => quote! { __CrubitVec::take_vec },
+        None => quote! { ::core::mem::take },
+    }
+}
+
+/// Returns true if `ty` implements the trait identified by `trait_def_id`.
+/// Generic parameters of the trait (e.g. `Rhs` in `PartialEq<Rhs = Self>`) are
+/// assumed to be `Self` (i.e. `ty`).
+fn does_type_implement_trait<'tcx>(tcx: TyCtxt<'tcx>, ty: Ty<'tcx>, trait_def_id: DefId) -> bool {
+    // TODO(b/259749095): Support non-empty set of generic parameters.
+    let param_env = ty::ParamEnv::empty();
+    let trait_args = std::iter::repeat(ty).take(tcx.generics_of(trait_def_id).count());
+    tcx.infer_ctxt()
+        .build()
+        .type_implements_trait(trait_def_id, trait_args, param_env)
+        .must_apply_modulo_regions()
+}
+
+/// Returns true if the C++ bindings of `ty` have a (non-deleted) move
+/// constructor.  See the comments in `format_adt_core` for more details.
+fn is_cc_movable<'tcx>(tcx: TyCtxt<'tcx>, ty: Ty<'tcx>) -> bool {
+    // TODO(b/259749095): Support non-empty set of generic parameters.
+    let param_env = ty::ParamEnv::empty();
+    if !ty.needs_drop(tcx, param_env) {
+        return true;
+    }
+    // The C++ move constructor of `rs_std::Box` leaves a null pointer behind (and the C++
+    // destructor of a null `rs_std::Box` is a no-op).
+    if boxed_ty(tcx, ty).is_some() {
+        return true;
+    }
+    // Similarly, moved-from `rs_std::Rc` and `rs_std::Arc` hold a null pointer, and moved-from
+    // `Trait::DynBox` and `Trait::DynArc` hold `None` (see `format_trait`).
+    if ref_counted_ptr(tcx, ty).is_some() {
+        return true;
+    }
+    if let Some((DynPtr::Box | DynPtr::Arc, _)) = dyn_ptr(tcx, ty) {
+        return true;
+    }
+    let default_trait_id = tcx
+        .get_diagnostic_item(rustc_span::symbol::sym::Default)
+        .expect("`Default` trait should always be present");
+    does_type_implement_trait(tcx, ty, default_trait_id)
+}
+
+/// Verifies that values of type `ty` can be moved between C++ and Rust (e.g.
+/// when passing by value arguments or return values of such type).
+fn ensure_cc_movable<'tcx>(tcx: TyCtxt<'tcx>, ty: Ty<'tcx>) -> Result<()> {
+    ensure!(
+        is_cc_movable(tcx, ty),
+        "`{ty}` needs drop glue, but doesn't implement `Default` - this means that \
+         values of this type can't be moved in C++ (b/258251148)"
+    );
+    Ok(())
+}
+
+/// Returns the mangled symbol name of `instance`, with the characters that are
+/// not allowed in C++ or Rust identifiers replaced by `_`.
+fn format_sanitized_symbol_name<'tcx>(tcx: TyCtxt<'tcx>, instance: ty::Instance<'tcx>) -> String {
+    tcx.symbol_name(instance)
+        .name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' })
+        .collect()
+}
+
+/// Returns a suffix that should be used for the names of thunks generated for
+/// the ADT `ty` (e.g. `__crubit_thunk_drop_{suffix}`).  The suffix is derived
+/// from the mangled symbol name of the drop glue of `ty` (which disambiguates
+/// ADTs with the same name from different modules or crates).
+fn format_adt_thunk_suffix<'tcx>(tcx: TyCtxt<'tcx>, ty: Ty<'tcx>) -> String {
+    format_sanitized_symbol_name(tcx, ty::Instance::resolve_drop_in_place(tcx, ty))
+}
+
+/// Wraps the body of a Rust thunk, so that Rust panics don't unwind across the
+/// FFI boundary (this would be Undefined Behavior - see
+/// `panics_and_exceptions.md`).  Instead, the panic message is printed and the
+/// process is aborted.
+///
+/// `thunk_body` is returned unmodified if the crate is compiled with
+/// `-Cpanic=abort` (because in this case panics never unwind).
+fn format_rs_thunk_body_aborting_on_panic(tcx: TyCtxt, thunk_body: TokenStream) -> TokenStream {
+    match tcx.sess().panic_strategy() {
+        PanicStrategy::Abort => thunk_body,
+        PanicStrategy::Unwind => quote! {
+            match ::std::panic::catch_unwind(::std::panic::AssertUnwindSafe(|| { #thunk_body })) {
+                Ok(__ret_value) => __ret_value,
+                Err(__panic_payload) => __crubit_abort_on_panic(__panic_payload),
+            }
+        },
+    }
+}
+
 /// Formats `ty` into a `CcSnippet` that represents how the type should be
 /// spelled in a C++ declaration of a function parameter or field.
 //
@@ -340,8 +1233,40 @@ fn format_ty_for_cc<'tcx>(input: &Input<'tcx>, ty: Ty<'tcx>) -> Result<CcSnippet
                 // TODO(b/254507801): Maybe translate into `crubit::Unit`?
                 bail!("`()` / `void` is only supported as a return type (b/254507801)");
             } else {
-                // TODO(b/254099023): Add support for tuples.
-                bail!("Tuples are not supported yet: {} (b/254099023)", ty);
+                // Rust doesn't guarantee any particular layout of tuples (and `std::tuple` layout
+                // differs between C++ standard libraries).  This is okay, because `format_fn`
+                // never passes whole tuples across the FFI boundary - instead it marshals tuples
+                // one element at a time.  Nested tuples (and pointers to tuples - see the
+                // `RawPtr` case below) would have to depend on the layout and therefore are
+                // rejected.
+                let mut prereqs = CcPrerequisites::default();
+                prereqs.includes.insert(CcInclude::tuple());
+                let elements = types
+                    .iter()
+                    .enumerate()
+                    .map(|(i, element_ty)| -> Result<TokenStream> {
+                        ensure!(!element_ty.is_tuple(),
+                                "Nested tuples are not supported yet: {ty} (b/254099023)");
+                        // A `std::tuple` of C++ references can't be returned via
+                        // `crubit::ReturnValueSlot`.
+                        ensure!(!element_ty.is_ref(),
+                                "References in tuples are not supported yet: {ty} (b/258235219)");
+                        ensure!(option_or_result(input.tcx, element_ty).is_none(),
+                                "`Option` and `Result` in tuples are not supported yet: {ty}");
+                        ensure!(!is_rust_abi_fn_ptr(element_ty),
+                                "Rust-ABI function pointers in tuples are not supported yet: \
+                                 {ty}");
+                        let tokens = format_ty_for_cc(input, element_ty)
+                            .with_context(|| format!(
+                                    "Failed to format element #{i} of the tuple type `{ty}`"))?
+                            .into_tokens(&mut prereqs);
+                        Ok(tokens)
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                CcSnippet {
+                    prereqs,
+                    tokens: quote!{ std::tuple< #( #elements ),* > },
+                }
             }
         }
 
@@ -411,39 +1336,214 @@ fn format_ty_for_cc<'tcx>(input: &Input<'tcx>, ty: Ty<'tcx>) -> Result<CcSnippet
         ty::TyKind::Uint(ty::UintTy::Usize) => cstdint(quote!{ std::uintptr_t }),
 
         ty::TyKind::Int(ty::IntTy::I128) | ty::TyKind::Uint(ty::UintTy::U128) => {
-            // Note that "the alignment of Rust's {i,u}128 is unspecified and allowed to
-            // change" according to
-            // https://rust-lang.github.io/unsafe-code-guidelines/layout/scalars.html#fixed-width-integer-types
-            //
-            // TODO(b/254094650): Consider mapping this to Clang's (and GCC's) `__int128`
-            // or to `absl::in128`.
-            bail!("C++ doesn't have a standard equivalent of `{ty}` (b/254094650)");
+            format_int128_for_cc(input, ty, /* is_fn_param_or_ret = */ false)?
         }
 
+        // Pointers to trait objects are represented by the C++ bindings of the trait (see
+        // `DynPtr` and `format_trait`).  A forward declaration of the trait is not sufficient,
+        // because `Trait::DynBox` and `Trait::DynArc` are nested classes and because `format_fn`
+        // passes `Trait::__crubit_vtable` together with `&dyn Trait`.
+        _ if dyn_ptr(input.tcx, ty).is_some() => {
+            let (kind, dyn_ty) = dyn_ptr(input.tcx, ty).expect("Checked by the match guard above");
+            let trait_def_id = get_dyn_trait_def_id(input, dyn_ty)?;
+            let name = FullyQualifiedName::new(input.tcx, trait_def_id.to_def_id())
+                .format_for_cc()?;
+            let tokens = match kind {
+                DynPtr::Ref(Mutability::Not) => quote!{ const #name & },
+                DynPtr::Ref(Mutability::Mut) => quote!{ #name & },
+                DynPtr::Box => quote!{ #name::DynBox },
+                DynPtr::Arc => quote!{ #name::DynArc },
+            };
+            let mut prereqs = CcPrerequisites::default();
+            prereqs.defs.insert(trait_def_id);
+            CcSnippet { prereqs, tokens }
+        },
+
+        // `Box<T>` and `Option<Box<T>>` are represented as `rs_std::Box<T>`.  ABI compatibility
+        // and other details are described in `rust_builtin_type_abi_assumptions.md`.
+        ty::TyKind::Adt(..) if boxed_ty(input.tcx, ty).is_some() => {
+            let pointee = boxed_ty(input.tcx, ty).expect("Checked by the match guard above");
+            // `rs_std::Box<T>` calls a drop thunk generated by `format_adt` (or
+            // `format_enum_class`).
+            ensure!(pointee.is_adt()
+                        && option_or_result(input.tcx, pointee).is_none()
+                        && string_or_vec(input.tcx, pointee).is_none(),
+                    "`Box<T>` is only supported for structs, enums, and unions: {ty}");
+            let layout = get_layout(input.tcx, ty)?;
+            ensure!(
+                layout.size() == input.tcx.data_layout.pointer_size
+                    && layout.align().abi == input.tcx.data_layout.pointer_align.abi,
+                "`{ty}` is not represented as a pointer (a non-global allocator?)"
+            );
+            let CcSnippet { tokens, mut prereqs } = format_ty_for_cc(input, pointee)
+                .with_context(|| format!("Failed to format the pointee of `{ty}`"))?;
+            prereqs.move_defs_to_fwd_decls();
+            prereqs.includes.insert(input.support_header("rs_std/box.h"));
+            CcSnippet { prereqs, tokens: quote! { rs_std::Box< #tokens > } }
+        },
+
+        // `Rc<T>` and `Arc<T>` are represented as `rs_std::Rc<T>` and `rs_std::Arc<T>` (see
+        // `RefCountedPtr`).
+        ty::TyKind::Adt(..) if ref_counted_ptr(input.tcx, ty).is_some() => {
+            let (kind, pointee) =
+                ref_counted_ptr(input.tcx, ty).expect("Checked by the match guard above");
+            let (ptr_name, header) = match kind {
+                RefCountedPtr::Rc => (quote! { rs_std::Rc }, "rs_std/rc.h"),
+                RefCountedPtr::Arc => (quote! { rs_std::Arc }, "rs_std/arc.h"),
+            };
+            // `rs_std::Rc<T>` and `rs_std::Arc<T>` call thunks generated by `format_adt` (or
+            // `format_enum_class`).
+            ensure!(pointee.is_adt()
+                        && boxed_ty(input.tcx, pointee).is_none()
+                        && option_or_result(input.tcx, pointee).is_none()
+                        && string_or_vec(input.tcx, pointee).is_none()
+                        && ref_counted_ptr(input.tcx, pointee).is_none(),
+                    "`{kind:?}<T>` is only supported for structs, enums, and unions: {ty}");
+            let CcSnippet { tokens, mut prereqs } = format_ty_for_cc(input, pointee)
+                .with_context(|| format!("Failed to format the pointee of `{ty}`"))?;
+            prereqs.move_defs_to_fwd_decls();
+            prereqs.includes.insert(input.support_header(header));
+            CcSnippet { prereqs, tokens: quote! { #ptr_name< #tokens > } }
+        },
+
+        // `String` and `Vec<T>` are represented as `rs_std::String` and `rs_std::Vec<T>` (see
+        // `StringOrVec`).  Nesting them in other types (e.g. in pointers or struct fields) would
+        // have to depend on the layout of Rust's `Vec<T>` and therefore is rejected.
+        ty::TyKind::Adt(..) if string_or_vec(input.tcx, ty).is_some() => {
+            match string_or_vec(input.tcx, ty) {
+                Some(StringOrVec::String) => CcSnippet::with_include(
+                    quote! { rs_std::String },
+                    input.support_header("rs_std/rs_string.h"),
+                ),
+                Some(StringOrVec::Vec(element_ty)) => {
+                    // `rs_std::Vec<T>` needs the same layout of `T` as Rust.
+                    ensure!(!element_ty.is_tuple(),
+                            "Tuples in `Vec` are not supported yet: {ty} (b/254099023)");
+                    ensure!(!element_ty.is_ref(),
+                            "References in `Vec` are not supported yet: {ty} (b/258235219)");
+                    ensure!(option_or_result(input.tcx, element_ty).is_none()
+                                && string_or_vec(input.tcx, element_ty).is_none(),
+                            "`Option`, `Result`, `String`, and `Vec` in `Vec` are not \
+                             supported yet: {ty}");
+                    ensure!(!is_rust_abi_fn_ptr(element_ty),
+                            "Rust-ABI function pointers in `Vec` are not supported yet: {ty}");
+                    ensure!(dyn_ptr(input.tcx, element_ty).is_none(),
+                            "Trait objects in `Vec` are not supported yet: {ty}");
+                    let CcSnippet { tokens, mut prereqs } = format_ty_for_cc(input, element_ty)
+                        .with_context(|| format!("Failed to format the element type of `{ty}`"))?;
+                    prereqs.includes.insert(input.support_header("rs_std/vec.h"));
+                    CcSnippet { prereqs, tokens: quote! { rs_std::Vec< #tokens > } }
+                }
+                None => unreachable!("Checked by the match guard above"),
+            }
+        },
+
+        // `Option<T>` is represented as `std::optional<T>` and `Result<T, E>` is represented as
+        // `rs_std::Result<T, E>` (or as `absl::StatusOr<T>` - see `ResultMapping`).  Rust doesn't
+        // guarantee any particular layout of these types.  This is okay, because `format_fn`
+        // never passes them across the FFI boundary as a whole - instead it marshals the
+        // discriminant and the payload separately.  Nesting them in other types (e.g. in
+        // pointers, struct fields, or tuples) would have to depend on the layout and therefore
+        // is rejected.
+        ty::TyKind::Adt(..) if option_or_result(input.tcx, ty).is_some() => {
+            let format_type_arg = |prereqs: &mut CcPrerequisites, arg_ty: Ty<'tcx>| {
+                ensure!(!arg_ty.is_tuple() || arg_ty.is_unit(),
+                        "Tuples in `Option` and `Result` are not supported yet: {ty}");
+                ensure!(!arg_ty.is_ref(),
+                        "References in `Option` and `Result` are not supported yet: {ty}");
+                ensure!(option_or_result(input.tcx, arg_ty).is_none(),
+                        "Nested `Option` and `Result` types are not supported yet: {ty}");
+                ensure!(!is_rust_abi_fn_ptr(arg_ty),
+                        "Rust-ABI function pointers in `Option` and `Result` are not supported \
+                         yet: {ty}");
+                ensure!(dyn_ptr(input.tcx, arg_ty).is_none(),
+                        "Trait objects in `Option` and `Result` are not supported yet: {ty}");
+                let tokens = if arg_ty.is_unit() {
+                    prereqs.includes.insert(CcInclude::variant());
+                    quote! { std::monostate }
+                } else {
+                    format_ty_for_cc(input, arg_ty)
+                        .with_context(|| format!("Failed to format a type argument of `{ty}`"))?
+                        .into_tokens(prereqs)
+                };
+                Ok(tokens)
+            };
+            let mut prereqs = CcPrerequisites::default();
+            let tokens = match option_or_result(input.tcx, ty) {
+                Some(OptionOrResult::Option(value_ty)) => {
+                    ensure!(!value_ty.is_unit(), "`Option<()>` is not supported yet");
+                    let value = format_type_arg(&mut prereqs, value_ty)?;
+                    prereqs.includes.insert(CcInclude::optional());
+                    quote! { std::optional< #value > }
+                }
+                Some(OptionOrResult::Result(ok_ty, err_ty)) if is_absl_status_or(input, err_ty) => {
+                    // `E` is only used to produce the message of the `absl::Status`.
+                    format_type_arg(&mut prereqs, err_ty)?;
+                    if ok_ty.is_unit() {
+                        prereqs.includes.insert(
+                            CcInclude::user_header("absl/status/status.h".into()));
+                        quote! { absl::Status }
+                    } else {
+                        let value = format_type_arg(&mut prereqs, ok_ty)?;
+                        prereqs.includes.insert(
+                            CcInclude::user_header("absl/status/statusor.h".into()));
+                        quote! { absl::StatusOr< #value > }
+                    }
+                }
+                Some(OptionOrResult::Result(ok_ty, err_ty)) => {
+                    ensure!(!err_ty.is_unit(), "`Result<T, ()>` is not supported yet");
+                    let value = format_type_arg(&mut prereqs, ok_ty)?;
+                    let error = format_type_arg(&mut prereqs, err_ty)?;
+                    prereqs.includes.insert(input.support_header("rs_std/result.h"));
+                    quote! { rs_std::Result< #value, #error > }
+                }
+                None => unreachable!("Checked by the match guard above"),
+            };
+            CcSnippet { prereqs, tokens }
+        },
+
         ty::TyKind::Adt(adt, substs) => {
-            ensure!(substs.len() == 0, "Generic types are not supported yet (b/259749095)");
+            // Generic types are only supported if the bindings have been requested for an
+            // explicit list of instantiations (see `Input::instantiations`).
+            let substs = input.tcx.erase_regions(*substs);
             ensure!(
-                is_directly_public(input.tcx, adt.did()),
-                "Not directly public type (re-exports are not supported yet - b/262052635)");
+                substs.is_empty() || input
+                    .instantiations
+                    .get(&adt.did())
+                    .map_or(false, |substs_list| substs_list.contains(&substs)),
+                "Generic types are not supported yet (b/259749095)");
+            ensure!(
+                public_path(input.tcx, adt.did()).is_some(),
+                "Not a public type (renaming re-exports are not supported yet - b/262052635)");
 
             let def_id = adt.did();
             let mut prereqs = CcPrerequisites::default();
             if def_id.krate == LOCAL_CRATE {
                 prereqs.defs.insert(def_id.expect_local());
             } else {
-                // TODO(b/258261328): Add `#include` of other crate's `..._cc_api.h`.
-                bail!("Cross-crate dependencies are not supported yet (b/258261328)");
+                let other_crate_name = input.tcx.crate_name(def_id.krate);
+                let include = input
+                    .crate_name_to_include_path
+                    .get(other_crate_name.as_str())
+                    .ok_or_else(|| anyhow!(
+                        "Type `{ty}` comes from the `{other_crate_name}` crate, \
+                         but no `--crate-header` was specified for this crate"))?;
+                prereqs.includes.insert(include.clone());
             };
 
             // Verify if definition of `ty` can be succesfully imported and bail otherwise.
-            format_adt_core(input.tcx, def_id)
+            format_adt_core(input.tcx, input.tcx.erase_regions(ty))
                 .with_context(|| format!(
                         "Failed to generate bindings for the definition of `{ty}`"))?;
 
-            CcSnippet {
-                tokens: FullyQualifiedName::new(input.tcx, def_id).format_for_cc()?,
-                prereqs
-            }
+            let name = FullyQualifiedName::new(input.tcx, def_id).format_for_cc()?;
+            let tokens = if substs.is_empty() {
+                name
+            } else {
+                let type_args = format_type_args_for_cc(input, substs, &mut prereqs)?;
+                quote!{ #name #type_args }
+            };
+            CcSnippet { tokens, prereqs }
         },
 
         ty::TyKind::RawPtr(ty::TypeAndMut{ty, mutbl}) => {
@@ -451,6 +1551,19 @@ fn format_ty_for_cc<'tcx>(input: &Input<'tcx>, ty: Ty<'tcx>) -> Result<CcSnippet
                 Mutability::Mut => quote!{},
                 Mutability::Not => quote!{ const },
             };
+            ensure!(!ty.is_tuple(),
+                    "Pointers to tuples are not supported yet: {ty} (b/254099023)");
+            ensure!(!ty.is_ref(),
+                    "Pointers to references are not supported yet: {ty} (b/258235219)");
+            ensure!(option_or_result(input.tcx, *ty).is_none(),
+                    "Pointers to `Option` and `Result` are not supported yet: {ty}");
+            ensure!(string_or_vec(input.tcx, *ty).is_none(),
+                    "Pointers to `String` and `Vec` are not supported yet: {ty}");
+            ensure!(!is_rust_abi_fn_ptr(*ty),
+                    "Pointers to Rust-ABI function pointers are not supported yet: {ty}");
+            ensure!(dyn_ptr(input.tcx, *ty).is_none(),
+                    "Pointers to `Box<dyn Trait>` and `Arc<dyn Trait>` are not supported yet: \
+                     {ty}");
             let CcSnippet{ tokens, mut prereqs } = format_ty_for_cc(input, *ty)
                 .with_context(|| format!(
                         "Failed to format the pointee of the pointer type `{ty}`"))?;
@@ -461,6 +1574,114 @@ fn format_ty_for_cc<'tcx>(input: &Input<'tcx>, ty: Ty<'tcx>) -> Result<CcSnippet
             }
         },
 
+        // Rust references to string slices are represented as `rs_std::StrRef`.  ABI
+        // compatibility and other details are described in `rust_builtin_type_abi_assumptions.md`
+        // and in the doc comments in `crubit/support/rs_std/str_ref.h`.
+        ty::TyKind::Ref(_region, referent, mutbl) if referent.is_str() => {
+            ensure!(*mutbl == Mutability::Not, "`&mut str` is not supported yet: {ty}");
+            assert_slice_ref_abi(input.tcx);
+            CcSnippet::with_include(
+                quote! { rs_std::StrRef },
+                input.support_header("rs_std/str_ref.h"),
+            )
+        },
+
+        // Rust references to slices are represented as `rs_std::SliceRef<const T>` (for `&[T]`)
+        // or `rs_std::SliceRef<T>` (for `&mut [T]`).  ABI compatibility and other details are
+        // described in `rust_builtin_type_abi_assumptions.md` and in the doc comments in
+        // `crubit/support/rs_std/slice_ref.h`.
+        ty::TyKind::Ref(_region, referent, mutbl) if referent.is_slice() => {
+            assert_slice_ref_abi(input.tcx);
+            let element = match referent.kind() {
+                ty::TyKind::Slice(element) => *element,
+                _ => unreachable!("`is_slice` should imply `TyKind::Slice`"),
+            };
+            let CcSnippet{ tokens, mut prereqs } =
+                format_qualified_referent_ty_for_cc(input, element, *mutbl, || format!(
+                        "Failed to format the element type of the slice type `{ty}`"))?;
+            prereqs.includes.insert(input.support_header("rs_std/slice_ref.h"));
+            CcSnippet {
+                prereqs,
+                tokens: quote!{ rs_std::SliceRef< #tokens > },
+            }
+        },
+
+        // Rust references are represented as C++ references.  Rust lifetimes are not part of
+        // the C++ type, but `format_fn` may mark function parameters that can be borrowed by the
+        // return value as `[[clang::lifetimebound]]`.
+        ty::TyKind::Ref(_region, referent, mutbl) => {
+            let CcSnippet{ tokens, prereqs } =
+                format_qualified_referent_ty_for_cc(input, *referent, *mutbl, || format!(
+                        "Failed to format the referent of the reference type `{ty}`"))?;
+            CcSnippet { prereqs, tokens: quote!{ #tokens & } }
+        },
+
+        // `extern "C"` function pointers are represented as C++ function pointers.  C++ can't
+        // directly call a Rust-ABI function, so Rust-ABI function pointers are represented as
+        // `rs_std::FnPtr<F>` (see `is_rust_abi_fn_ptr`).  In both cases the parameters and the
+        // return value are passed directly (without thunks), so they need to have a C-compatible
+        // ABI.
+        ty::TyKind::FnPtr(sig) => {
+            let sig = input.tcx.erase_late_bound_regions(*sig);
+            ensure!(!sig.c_variadic, "C-variadic function pointers are not supported: {ty}");
+            let is_rust_abi = match sig.abi {
+                rustc_target::spec::abi::Abi::Rust => true,
+                rustc_target::spec::abi::Abi::C { .. } => false,
+                _ => bail!("Function pointers are only supported with the Rust or the \
+                            `extern \"C\"` ABI: {ty}"),
+            };
+            let mut prereqs = CcPrerequisites::default();
+            let params = sig
+                .inputs()
+                .iter()
+                .enumerate()
+                .map(|(i, &param_ty)| -> Result<TokenStream> {
+                    let tokens = format_ty_for_cc(input, param_ty)
+                        .with_context(|| format!(
+                                "Failed to format parameter #{i} of the function pointer type \
+                                 `{ty}`"))?
+                        .into_tokens(&mut prereqs);
+                    ensure!(is_c_abi_compatible_by_value(param_ty),
+                            "Function pointer parameters need to have a C-compatible ABI: \
+                             `{param_ty}` in `{ty}`");
+                    Ok(tokens)
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let ret_ty = sig.output();
+            let ret = if ret_ty.is_unit() || ret_ty.is_never() {
+                quote!{ void }
+            } else {
+                // The lifetime of a returned reference can't be elided in the signature of the
+                // trampoline (see `format_into_ffi_value_for_rs`) if there are multiple
+                // reference parameters.
+                ensure!(!is_rust_abi || !ret_ty.is_ref(),
+                        "Rust-ABI function pointers returning references are not supported \
+                         yet: {ty}");
+                let tokens = format_ty_for_cc(input, ret_ty)
+                    .with_context(|| format!(
+                            "Failed to format the return type of the function pointer type \
+                             `{ty}`"))?
+                    .into_tokens(&mut prereqs);
+                ensure!(is_c_abi_compatible_by_value(ret_ty),
+                        "Function pointer return types need to have a C-compatible ABI: \
+                         `{ret_ty}` in `{ty}`");
+                tokens
+            };
+            let tokens = if is_rust_abi {
+                prereqs.includes.insert(input.support_header("rs_std/fn_ptr.h"));
+                quote!{ rs_std::FnPtr< #ret ( #( #params ),* ) > }
+            } else {
+                prereqs.includes.insert(CcInclude::type_traits());
+                quote!{ std::add_pointer_t< #ret ( #( #params ),* ) > }
+            };
+            CcSnippet { prereqs, tokens }
+        },
+
+        // Type parameters are only expected when formatting the signature of the primary
+        // C++ function template of a generic Rust function (see `format_fn_primary_template`).
+        ty::TyKind::Param(param) => CcSnippet::new(
+            format_cc_ident(param.name.as_str()).context("Error formatting type parameter")?),
+
         // TODO(b/260268230, b/260729464): When recursively processing nested types (e.g. an
         // element type of an Array, a referent of a Ref, a parameter type of an FnPtr, etc), one
         // should also 1) propagate `CcPrerequisites::defs`, 2) cover `CcPrerequisites::defs` in
@@ -470,6 +1691,180 @@ fn format_ty_for_cc<'tcx>(input: &Input<'tcx>, ty: Ty<'tcx>) -> Result<CcSnippet
     })
 }
 
+/// Formats `ty` (the referent of a reference, or the element type of a slice
+/// reference) for C++, adding a `const` qualifier if `mutbl` is
+/// `Mutability::Not` - e.g. `const std::int32_t` or `std::int32_t* const`.
+/// `error_context` is used to describe errors from formatting `ty` itself.
+fn format_qualified_referent_ty_for_cc<'tcx>(
+    input: &Input<'tcx>,
+    ty: Ty<'tcx>,
+    mutbl: Mutability,
+    error_context: impl FnOnce() -> String,
+) -> Result<CcSnippet> {
+    let const_qualifier = match mutbl {
+        Mutability::Mut => quote! {},
+        Mutability::Not => quote! { const },
+    };
+    ensure!(!ty.is_tuple(), "References to tuples are not supported yet: {ty} (b/254099023)");
+    ensure!(!ty.is_ref(), "References to references are not supported yet: {ty} (b/258235219)");
+    ensure!(
+        option_or_result(input.tcx, ty).is_none(),
+        "References to `Option` and `Result` are not supported yet: {ty}"
+    );
+    ensure!(
+        string_or_vec(input.tcx, ty).is_none(),
+        "References to `String` and `Vec` are not supported yet (`&str` and `&[T]` are \
+         supported): {ty}"
+    );
+    ensure!(
+        !is_rust_abi_fn_ptr(ty),
+        "References to Rust-ABI function pointers are not supported yet: {ty}"
+    );
+    ensure!(
+        dyn_ptr(input.tcx, ty).is_none(),
+        "References to `Box<dyn Trait>` and `Arc<dyn Trait>` are not supported yet: {ty}"
+    );
+    let CcSnippet { tokens, mut prereqs } =
+        format_ty_for_cc(input, ty).with_context(error_context)?;
+    prereqs.move_defs_to_fwd_decls();
+    // `const` needs to go after a pointer type (e.g. `int* const&`) to apply to the
+    // pointer rather than to the pointee.
+    let tokens = if ty.is_unsafe_ptr() {
+        quote! { #tokens #const_qualifier }
+    } else {
+        quote! { #const_qualifier #tokens }
+    };
+    Ok(CcSnippet { prereqs, tokens })
+}
+
+/// Asserts that the target architecture meets the assumption from Crubit's
+/// `rust_builtin_type_abi_assumptions.md` - we assume that `&[T]` and `&str`
+/// have the same ABI as a C++ struct with a pointer field followed by a
+/// `size_t` field (i.e. the same ABI as `rs_std::SliceRef<T>` and
+/// `rs_std::StrRef`).
+fn assert_slice_ref_abi(tcx: TyCtxt) {
+    let ty = tcx.mk_imm_ref(tcx.lifetimes.re_static, tcx.mk_slice(tcx.types.u8));
+    let layout = tcx
+        .layout_of(ty::ParamEnv::empty().and(ty))
+        .expect("`layout_of` is expected to succeed for the builtin `&[u8]` type")
+        .layout;
+    let pointer_size = tcx.data_layout.pointer_size;
+    assert_eq!(pointer_size.bytes() * 2, layout.size().bytes());
+    assert_eq!(tcx.data_layout.pointer_align.abi, layout.align().abi);
+    match layout.abi() {
+        Abi::ScalarPair(
+            Scalar::Initialized { value: first, .. },
+            Scalar::Initialized { value: second, .. },
+        ) => {
+            assert!(matches!(first, Primitive::Pointer { .. }));
+            assert!(matches!(second, Primitive::Int(integer, /* signedness = */ false)
+                                     if integer.size() == pointer_size));
+        }
+        abi => panic!("Unexpected ABI of `&[u8]`: {abi:?}"),
+    }
+}
+
+/// Returns the type arguments from `substs`, ignoring lifetime arguments
+/// (which are always elided in the generated code).
+fn type_args_of<'tcx>(substs: &[ty::GenericArg<'tcx>]) -> Result<Vec<Ty<'tcx>>> {
+    substs
+        .iter()
+        .filter_map(|arg| match arg.unpack() {
+            ty::GenericArgKind::Lifetime(_) => None,
+            ty::GenericArgKind::Type(ty) => Some(Ok(ty)),
+            ty::GenericArgKind::Const(_) => {
+                Some(Err(anyhow!("Const generics are not supported yet (b/259749095)")))
+            }
+        })
+        .collect()
+}
+
+/// Formats the type arguments from `substs` as C++ template arguments - e.g.
+/// `<std::int32_t, float>`.
+fn format_type_args_for_cc<'tcx>(
+    input: &Input<'tcx>,
+    substs: &[ty::GenericArg<'tcx>],
+    prereqs: &mut CcPrerequisites,
+) -> Result<TokenStream> {
+    let type_args = type_args_of(substs)?
+        .into_iter()
+        .map(|ty| -> Result<TokenStream> { Ok(format_ty_for_cc(input, ty)?.into_tokens(prereqs)) })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(quote! { < #( #type_args ),* > })
+}
+
+/// Verifies that the C++ template arguments of the instantiation `substs` of
+/// the generic item identified by `def_id` are different from the ones of the
+/// instantiations in `cc_type_args_seen` (and records them there).  Different
+/// Rust types may map to the same C++ type (e.g. on LP64 Linux both `i64` and
+/// `isize` map to `long`) and duplicate explicit specializations wouldn't
+/// compile.
+fn ensure_distinct_cc_type_args<'tcx>(
+    input: &Input<'tcx>,
+    def_id: DefId,
+    substs: ty::SubstsRef<'tcx>,
+    cc_type_args_seen: &mut HashMap<String, ty::SubstsRef<'tcx>>,
+) -> Result<()> {
+    let tcx = input.tcx;
+    // `std::intptr_t` and `std::uintptr_t` (see `format_ty_for_cc`) are aliases of the same
+    // C++ types as the fixed-width integer types of the same size on most targets.  Apple
+    // targets are the exception: there `std::intptr_t` is `long`, but `std::int64_t` is
+    // `long long` (and `std::int32_t` is `int`).
+    let normalized_substs = if tcx.sess.target.is_like_osx {
+        substs
+    } else {
+        let ptr_sized_integer = tcx.data_layout.ptr_sized_integer();
+        substs.fold_with(&mut ty::fold::BottomUpFolder {
+            tcx,
+            ty_op: |ty| match ty.kind() {
+                ty::TyKind::Int(ty::IntTy::Isize) => ptr_sized_integer.to_ty(tcx, true),
+                ty::TyKind::Uint(ty::UintTy::Usize) => ptr_sized_integer.to_ty(tcx, false),
+                _ => ty,
+            },
+            lt_op: |lt| lt,
+            ct_op: |ct| ct,
+        })
+    };
+    let cc_type_args =
+        format_type_args_for_cc(input, normalized_substs, &mut CcPrerequisites::default())?;
+    if let Some(other_substs) = cc_type_args_seen.get(&cc_type_args.to_string()) {
+        let other_instantiation = tcx.def_path_str_with_substs(def_id, *other_substs);
+        bail!(
+            "The C++ template arguments of this instantiation are the same as the ones of \
+             the instantiation `{other_instantiation}`"
+        );
+    }
+    cc_type_args_seen.insert(cc_type_args.to_string(), substs);
+    Ok(())
+}
+
+/// Formats a C++ comment explaining why no explicit specialization has been
+/// generated for the instantiation `substs` of the generic item identified by
+/// `def_id`.  The other instantiations of the item are still emitted.
+fn format_unsupported_instantiation<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    def_id: DefId,
+    substs: ty::SubstsRef<'tcx>,
+    err: anyhow::Error,
+) -> TokenStream {
+    let instantiation = tcx.def_path_str_with_substs(def_id, substs);
+    let msg = format!("Error generating bindings for the instantiation `{instantiation}`: {err:#}");
+    quote! { __NEWLINE__ __COMMENT__ #msg __NEWLINE__ }
+}
+
+/// Formats the type arguments from `substs` as Rust generic arguments - e.g.
+/// `<i32, f32>`.
+fn format_type_args_for_rs<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    substs: &[ty::GenericArg<'tcx>],
+) -> Result<TokenStream> {
+    let type_args = type_args_of(substs)?
+        .into_iter()
+        .map(|ty| format_ty_for_rs(tcx, ty))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(quote! { < #( #type_args ),* > })
+}
+
 /// Formats `ty` for Rust - to be used in `..._cc_api_impl.rs` (e.g. as a type
 /// of a parameter in a Rust thunk).  Because `..._cc_api_impl.rs` is a
 /// distinct, separate crate, the returned `TokenStream` uses crate-qualified
@@ -492,13 +1887,69 @@ fn format_ty_for_rs(tcx: TyCtxt, ty: Ty) -> Result<TokenStream> {
             if types.len() == 0 {
                 quote! { () }
             } else {
-                // TODO(b/254099023): Add support for tuples.
-                bail!("Tuples are not supported yet: {} (b/254099023)", ty);
+                let elements = types
+                    .iter()
+                    .map(|element_ty| format_ty_for_rs(tcx, element_ty))
+                    .collect::<Result<Vec<_>>>()?;
+                // A trailing comma is required for single-element tuples: `(i32,)`.
+                quote! { ( #( #elements , )* ) }
             }
         }
+        ty::TyKind::Adt(..) if ty.is_box() => {
+            let pointee = format_ty_for_rs(tcx, ty.boxed_ty())
+                .with_context(|| format!("Failed to format the pointee of `{ty}`"))?;
+            quote! { ::std::boxed::Box< #pointee > }
+        },
+        ty::TyKind::Adt(adt, substs)
+            if tcx.is_diagnostic_item(rustc_span::symbol::sym::Option, adt.did()) =>
+        {
+            let element = format_ty_for_rs(tcx, substs.type_at(0))
+                .with_context(|| format!("Failed to format the type argument of `{ty}`"))?;
+            quote! { ::core::option::Option< #element > }
+        },
+        ty::TyKind::Adt(adt, substs)
+            if tcx.is_diagnostic_item(rustc_span::symbol::sym::Arc, adt.did()) =>
+        {
+            let pointee = format_ty_for_rs(tcx, substs.type_at(0))
+                .with_context(|| format!("Failed to format the pointee of `{ty}`"))?;
+            quote! { ::std::sync::Arc< #pointee > }
+        },
+        ty::TyKind::Adt(adt, substs)
+            if tcx.is_diagnostic_item(rustc_span::symbol::sym::Rc, adt.did()) =>
+        {
+            let pointee = format_ty_for_rs(tcx, substs.type_at(0))
+                .with_context(|| format!("Failed to format the pointee of `{ty}`"))?;
+            quote! { ::std::rc::Rc< #pointee > }
+        },
+        ty::TyKind::Adt(..) if string_or_vec(tcx, ty).is_some() => {
+            match string_or_vec(tcx, ty) {
+                Some(StringOrVec::String) => quote! { ::std::string::String },
+                Some(StringOrVec::Vec(element_ty)) => {
+                    let element = format_ty_for_rs(tcx, element_ty)
+                        .with_context(|| format!("Failed to format the element type of `{ty}`"))?;
+                    quote! { ::std::vec::Vec< #element > }
+                }
+                None => unreachable!("Checked by the match guard above"),
+            }
+        },
+        ty::TyKind::Adt(adt, substs)
+            if tcx.is_diagnostic_item(rustc_span::symbol::sym::Result, adt.did()) =>
+        {
+            let type_args = format_type_args_for_rs(tcx, substs)
+                .with_context(|| format!("Failed to format a type argument of `{ty}`"))?;
+            quote! { ::core::result::Result #type_args }
+        },
         ty::TyKind::Adt(adt, substs) => {
-            ensure!(substs.len() == 0, "Generic types are not supported yet (b/259749095)");
-            FullyQualifiedName::new(tcx, adt.did()).format_for_rs()
+            // Only local generic types may be listed in `Input::instantiations`.
+            ensure!(substs.len() == 0 || adt.did().is_local(),
+                    "Generic types are not supported yet (b/259749095)");
+            let name = FullyQualifiedName::new(tcx, adt.did()).format_for_rs();
+            if substs.is_empty() {
+                name
+            } else {
+                let type_args = format_type_args_for_rs(tcx, substs)?;
+                quote!{ #name #type_args }
+            }
         },
         ty::TyKind::RawPtr(ty::TypeAndMut{ty, mutbl}) => {
             let qualifier = match mutbl {
@@ -510,6 +1961,57 @@ fn format_ty_for_rs(tcx: TyCtxt, ty: Ty) -> Result<TokenStream> {
                         "Failed to format the pointee of the pointer type `{ty}`"))?;
             quote!{ * #qualifier #ty }
         },
+        ty::TyKind::Str => quote! { str },
+        ty::TyKind::Slice(element) => {
+            let element = format_ty_for_rs(tcx, *element)
+                .with_context(|| format!(
+                        "Failed to format the element type of the slice type `{ty}`"))?;
+            quote!{ [ #element ] }
+        },
+        ty::TyKind::Ref(region, referent, mutbl) => {
+            // Other lifetimes are erased by `liberate_fn_sig` (and elided in the thunks).
+            let lifetime = if region.is_static() { quote!{ 'static } } else { quote!{} };
+            let qualifier = match mutbl {
+                Mutability::Mut => quote!{ mut },
+                Mutability::Not => quote!{},
+            };
+            let referent = format_ty_for_rs(tcx, *referent)
+                .with_context(|| format!(
+                        "Failed to format the referent of the reference type `{ty}`"))?;
+            quote!{ & #lifetime #qualifier #referent }
+        },
+        ty::TyKind::FnPtr(sig) => {
+            let sig = tcx.erase_late_bound_regions(*sig);
+            let unsafety = match sig.unsafety {
+                Unsafety::Normal => quote!{},
+                Unsafety::Unsafe => quote!{ unsafe },
+            };
+            let abi = match sig.abi {
+                rustc_target::spec::abi::Abi::Rust => quote!{},
+                rustc_target::spec::abi::Abi::C { unwind: false } => quote!{ extern "C" },
+                rustc_target::spec::abi::Abi::C { unwind: true } => quote!{ extern "C-unwind" },
+                _ => bail!("The following Rust type is not supported yet: {ty}"),
+            };
+            let params = sig
+                .inputs()
+                .iter()
+                .map(|&param_ty| format_ty_for_rs(tcx, param_ty))
+                .collect::<Result<Vec<_>>>()
+                .with_context(|| format!(
+                        "Failed to format a parameter type of the function pointer type `{ty}`"))?;
+            let ret = format_ty_for_rs(tcx, sig.output())
+                .with_context(|| format!(
+                        "Failed to format the return type of the function pointer type `{ty}`"))?;
+            quote!{ #unsafety #abi fn( #( #params ),* ) -> #ret }
+        },
+        // Only trait objects without auto traits are supported (see `get_dyn_trait_def_id`).
+        ty::TyKind::Dynamic(preds, _region, _kind) if preds.len() == 1 => {
+            let trait_def_id = preds
+                .principal_def_id()
+                .ok_or_else(|| anyhow!("The following Rust type is not supported yet: {ty}"))?;
+            let name = FullyQualifiedName::new(tcx, trait_def_id).format_for_rs();
+            quote!{ dyn #name }
+        },
         _ => bail!("The following Rust type is not supported yet: {ty}"),
     })
 }
@@ -557,6 +2059,15 @@ impl From<CcSnippet> for MixedSnippet {
     }
 }
 
+impl AddAssign for MixedSnippet {
+    fn add_assign(&mut self, rhs: Self) {
+        let Self { cc: CcSnippet { tokens, prereqs }, rs } = rhs;
+        self.cc.tokens.extend(tokens);
+        self.cc.prereqs += prereqs;
+        self.rs.extend(rs);
+    }
+}
+
 /// Formats a function with the given `local_def_id`.
 ///
 /// Returns multiple snippets, so that a function declaration can be emitted
@@ -580,18 +2091,346 @@ fn format_fn(input: &Input, local_def_id: LocalDefId) -> Result<Vec<(SnippetKey,
     let tcx = input.tcx;
     let def_id: DefId = local_def_id.to_def_id(); // Convert LocalDefId to DefId.
 
-    ensure!(
-        tcx.generics_of(def_id).count() == 0,
-        "Generic functions are not supported yet (b/259749023)"
-    );
-    let sig = match tcx.fn_sig(def_id).subst_identity().no_bound_vars() {
-        None => bail!("Generic functions are not supported yet (b/259749023)"),
-        Some(sig) => sig,
+    // `--exclude-unsafe-fns` asks for a subset of the API of the crate, so `unsafe`
+    // functions are skipped silently (rather than reported as unsupported).
+    if input.exclude_unsafe_fns && is_unsafe_fn(tcx, def_id) {
+        return Ok(vec![]);
+    }
+
+    if tcx.generics_of(def_id).count() == 0 {
+        return format_fn_instance(input, local_def_id, ty::List::empty());
+    }
+
+    // Generic functions are only supported if the bindings have been requested for an
+    // explicit list of instantiations.  Each instantiation is exposed in C++ as an
+    // explicit specialization of a C++ function template.
+    let substs_list = match input.instantiations.get(&def_id) {
+        Some(substs_list) if !substs_list.is_empty() => substs_list,
+        _ => bail!("Generic functions are not supported yet (b/259749023)"),
+    };
+    let mut impl_details = MixedSnippet::default();
+    let mut cc_type_args_seen = HashMap::new();
+    for &substs in substs_list.iter() {
+        let snippets = format_fn_instance(input, local_def_id, substs).with_context(|| {
+            let instantiation = tcx.def_path_str_with_substs(def_id, substs);
+            format!("Error handling the instantiation `{instantiation}`")
+        })?;
+        if let Err(err) =
+            ensure_distinct_cc_type_args(input, def_id, substs, &mut cc_type_args_seen)
+        {
+            let comment = format_unsupported_instantiation(tcx, def_id, substs, err);
+            impl_details.cc.tokens.extend(comment);
+            continue;
+        }
+        for (key, snippet) in snippets {
+            assert_eq!(key.kind, SnippetKind::ImplDetails, "Instances only have impl details");
+            impl_details += snippet;
+        }
+    }
+    let main_api = format_fn_primary_template(input, local_def_id)?;
+
+    Ok(vec![
+        (SnippetKey { def_id: local_def_id, kind: SnippetKind::MainApi }, main_api.into()),
+        (SnippetKey { def_id: local_def_id, kind: SnippetKind::ImplDetails }, impl_details),
+    ])
+}
+
+/// Describes whether a function is a free function or a method (and how the
+/// method takes its `self` parameter).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum FnKind {
+    /// A free function (or a trait method that is exposed in C++ as a free
+    /// function - see `is_trait_impl_item_name_clash`).
+    Free,
+
+    /// A static method (i.e. a method without a `self` parameter).  Methods
+    /// that take `self: Rc<Self>` or `self: Arc<Self>` are also represented in
+    /// C++ as static member functions (that take `rs_std::Rc<Self>` or
+    /// `rs_std::Arc<Self>` as their first parameter), because C++ member
+    /// functions can't be called on a `rs_std::Rc` or a `rs_std::Arc`.
+    StaticMethod,
+
+    /// A method that takes `&self` (`Mutability::Not`) or `&mut self`
+    /// (`Mutability::Mut`).  Such a method is represented in C++ as a `const`
+    /// or a non-`const` member function.
+    RefSelfMethod(Mutability),
+
+    /// A method that takes `self: Pin<&Self>` (`Mutability::Not`) or
+    /// `self: Pin<&mut Self>` (`Mutability::Mut`).  Such a method is
+    /// represented in C++ the same way as `RefSelfMethod`.
+    ///
+    /// Known limitation: this is only supported for `Unpin` types, because the
+    /// C++ bindings of `!Unpin` types are movable (and therefore C++ can't
+    /// uphold the pinning guarantees).
+    PinnedRefSelfMethod(Mutability),
+
+    /// A method that takes `self: Box<Self>`.  Such a method is represented in
+    /// C++ as a `&&`-qualified member function that moves the value out of
+    /// `*this` (into a new `Box<Self>` created by the Rust thunk), and as a
+    /// static overload that takes `rs_std::Box<Self>&&` as its first parameter
+    /// (and hands the boxed object over to Rust without reallocating it).
+    BoxSelfMethod,
+}
+
+impl FnKind {
+    /// Whether the first parameter of the function is the `self` parameter.
+    fn has_self_param(self) -> bool {
+        match self {
+            FnKind::Free | FnKind::StaticMethod => false,
+            FnKind::RefSelfMethod(_) | FnKind::PinnedRefSelfMethod(_) | FnKind::BoxSelfMethod => {
+                true
+            }
+        }
+    }
+}
+
+/// Returns the `FnKind` of the function identified by `local_def_id`.
+///
+/// Will panic if `local_def_id` doesn't identify a function.
+fn get_fn_kind<'tcx>(tcx: TyCtxt<'tcx>, local_def_id: LocalDefId) -> Result<FnKind> {
+    let def_id = local_def_id.to_def_id();
+    match tcx.hir().get_by_def_id(local_def_id) {
+        Node::ImplItem(impl_item) => match &impl_item.kind {
+            ImplItemKind::Fn(..) => (),
+            _ => panic!("`format_fn` can only work with functions"),
+        },
+        Node::Item(_) => return Ok(FnKind::Free),
+        other => panic!("Unexpected HIR node kind: {other:?}"),
+    };
+    if is_trait_impl_item_name_clash(tcx, def_id) {
+        return Ok(FnKind::Free);
+    }
+    if !tcx.associated_item(def_id).fn_has_self_parameter {
+        return Ok(FnKind::StaticMethod);
+    }
+
+    // `ImplicitSelfKind` only covers the shorthand forms of `self` (e.g. `&self`), so
+    // arbitrary receiver types (e.g. `self: Box<Self>`) are recognized by looking at the
+    // type of the first parameter.
+    let impl_id = tcx.impl_of_method(def_id).expect("Methods are assumed to be in an `impl`");
+    let self_ty = tcx.type_of(impl_id).subst_identity();
+    let receiver_ty = tcx.fn_sig(def_id).subst_identity().skip_binder().inputs()[0];
+    let as_self_ref = |ty: Ty<'tcx>| match ty.kind() {
+        ty::TyKind::Ref(_, referent, mutbl) if *referent == self_ty => Some(*mutbl),
+        _ => None,
+    };
+    Ok(match receiver_ty.kind() {
+        _ if receiver_ty == self_ty => bail!("`self` parameter is not supported yet"),
+        ty::TyKind::Ref(..) => match as_self_ref(receiver_ty) {
+            Some(mutbl) => FnKind::RefSelfMethod(mutbl),
+            None => bail!("Receiver type `{receiver_ty}` is not supported yet"),
+        },
+        _ if receiver_ty.is_box() && receiver_ty.boxed_ty() == self_ty => FnKind::BoxSelfMethod,
+        ty::TyKind::Adt(adt, substs) if tcx.lang_items().pin_type() == Some(adt.did()) => {
+            let mutbl = match as_self_ref(substs.type_at(0)) {
+                Some(mutbl) => mutbl,
+                None => bail!("Receiver type `{receiver_ty}` is not supported yet"),
+            };
+            // The C++ bindings of `Self` are movable (see `format_adt_core`), so C++ can't
+            // guarantee that the object stays pinned.  This is only sound for `Unpin` types.
+            let is_unpin = tcx
+                .lang_items()
+                .unpin_trait()
+                .map_or(false, |unpin_trait_id| {
+                    does_type_implement_trait(tcx, self_ty, unpin_trait_id)
+                });
+            ensure!(
+                is_unpin,
+                "`self: {receiver_ty}` receivers are only supported for types that implement \
+                 `Unpin` (C++ doesn't prevent moving pinned objects)"
+            );
+            FnKind::PinnedRefSelfMethod(mutbl)
+        }
+        // See the doc comment of `FnKind::StaticMethod`.
+        _ if ref_counted_ptr(tcx, receiver_ty).map(|(_, pointee)| pointee) == Some(self_ty) => {
+            FnKind::StaticMethod
+        }
+        _ => bail!("Receiver type `{receiver_ty}` is not supported yet"),
+    })
+}
+
+/// Replaces the late-bound lifetimes of `sig` (e.g. the lifetimes elided in
+/// `fn foo(arg: &i32) -> &i32`) with erased regions.  `'static` is preserved,
+/// because it matters for the signatures of the Rust thunks.
+///
+/// Also returns which of the inputs (`sig.inputs()`) may be borrowed by the
+/// output - i.e. which inputs have a lifetime (other than `'static`) that also
+/// appears in the output.
+fn liberate_fn_sig<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    def_id: DefId,
+    sig: ty::PolyFnSig<'tcx>,
+) -> (ty::FnSig<'tcx>, Vec<bool>) {
+    let sig = tcx.liberate_late_bound_regions(def_id, sig);
+    let mut output_regions = HashSet::new();
+    tcx.for_each_free_region(&sig.output(), |region| {
+        if !region.is_static() {
+            output_regions.insert(region);
+        }
+    });
+    let inputs_borrowed_by_output = sig
+        .inputs()
+        .iter()
+        .map(|input| {
+            let mut is_borrowed = false;
+            tcx.for_each_free_region(input, |region| {
+                is_borrowed |= output_regions.contains(&region);
+            });
+            is_borrowed
+        })
+        .collect_vec();
+    let sig = tcx.fold_regions(sig, |region, _debruijn| {
+        if region.is_static() { region } else { tcx.lifetimes.re_erased }
+    });
+    (sig, inputs_borrowed_by_output)
+}
+
+/// Formats the `[[clang::lifetimebound]]` attribute if `is_lifetimebound` (see
+/// the `inputs_borrowed_by_output` returned by `liberate_fn_sig`).  This lets
+/// C++ static analysis detect references that outlive the borrowed arguments.
+fn format_lifetimebound_attr(is_lifetimebound: bool) -> TokenStream {
+    if is_lifetimebound {
+        quote! { [[clang::lifetimebound]] }
+    } else {
+        quote! {}
+    }
+}
+
+/// Formats the parts of a C++ function declaration that depend on `fn_kind`:
+/// 1) `static` (that goes before the return type) and 2) the qualifiers of
+/// the implicit object parameter (that go after the parameter list - e.g.
+/// `const [[clang::lifetimebound]]`).
+fn format_fn_kind_for_cc(
+    fn_kind: FnKind,
+    inputs_borrowed_by_output: &[bool],
+) -> (TokenStream, TokenStream) {
+    match fn_kind {
+        FnKind::Free => (quote! {}, quote! {}),
+        FnKind::StaticMethod => (quote! { static }, quote! {}),
+        FnKind::RefSelfMethod(mutbl) | FnKind::PinnedRefSelfMethod(mutbl) => {
+            let const_qualifier = match mutbl {
+                Mutability::Mut => quote! {},
+                Mutability::Not => quote! { const },
+            };
+            let lifetimebound = format_lifetimebound_attr(inputs_borrowed_by_output[0]);
+            (quote! {}, quote! { #const_qualifier #lifetimebound })
+        }
+        FnKind::BoxSelfMethod => (quote! {}, quote! { && }),
+    }
+}
+
+/// Formats the C++ template parameters of the generic item identified by
+/// `def_id` - e.g. `template <typename T, typename U>`.  Lifetime parameters
+/// are ignored.
+fn format_template_params(tcx: TyCtxt, def_id: DefId) -> Result<TokenStream> {
+    let template_params = tcx
+        .generics_of(def_id)
+        .params
+        .iter()
+        .filter(|param| matches!(param.kind, ty::GenericParamDefKind::Type { .. }))
+        .map(|param| -> Result<TokenStream> {
+            let name = format_cc_ident(param.name.as_str())
+                .context("Error formatting type parameter")?;
+            Ok(quote! { typename #name })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(quote! { template < #( #template_params ),* > })
+}
+
+/// Formats the primary C++ function template for the generic function
+/// identified by `local_def_id`.  The primary template is `delete`d - C++ code
+/// can only call the explicit specializations that `format_fn_instance`
+/// generates for the instantiations listed in `Input::instantiations`.
+fn format_fn_primary_template(input: &Input, local_def_id: LocalDefId) -> Result<CcSnippet> {
+    let tcx = input.tcx;
+    let def_id: DefId = local_def_id.to_def_id(); // Convert LocalDefId to DefId.
+    let (sig, inputs_borrowed_by_output) =
+        liberate_fn_sig(tcx, def_id, tcx.fn_sig(def_id).subst_identity());
+    let fn_kind = get_fn_kind(tcx, local_def_id)?;
+
+    let template_params = format_template_params(tcx, def_id)?;
+    let mut prereqs = CcPrerequisites::default();
+    let fn_name = format_cc_ident(tcx.item_name(def_id).as_str())
+        .context("Error formatting function name")?;
+    let ret_type = format_ret_ty_for_cc(input, sig.output())
+        .context("Error formatting function return type")?
+        .into_tokens(&mut prereqs);
+    let params = tcx
+        .fn_arg_names(def_id)
+        .iter()
+        .zip(sig.inputs().iter())
+        .zip(inputs_borrowed_by_output.iter())
+        .enumerate()
+        .skip(if fn_kind.has_self_param() { 1 } else { 0 })
+        .map(|(i, ((name, &ty), &is_lifetimebound))| -> Result<TokenStream> {
+            let cc_name = format_cc_ident(name.as_str())
+                .unwrap_or_else(|_err| format_cc_ident(&format!("__param_{i}")).unwrap());
+            let cc_type = format_param_ty_for_cc(input, ty)
+                .with_context(|| format!("Error handling parameter #{i}"))?
+                .into_tokens(&mut prereqs);
+            let lifetimebound = format_lifetimebound_attr(is_lifetimebound);
+            Ok(quote! { #cc_type #cc_name #lifetimebound })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let (static_, self_qualifiers) = format_fn_kind_for_cc(fn_kind, &inputs_borrowed_by_output);
+    // See `FnKind::BoxSelfMethod`.
+    let box_self_overload = if fn_kind == FnKind::BoxSelfMethod {
+        let box_cc_type = format_ty_for_cc(input, sig.inputs()[0])
+            .context("Error handling parameter #0")?
+            .into_tokens(&mut prereqs);
+        quote! {
+            #template_params
+            static #ret_type #fn_name ( #box_cc_type&& __self #( , #params )* ) = delete;
+            __NEWLINE__
+        }
+    } else {
+        quote! {}
     };
+    let doc_comment = format_doc_comment(tcx, local_def_id);
+    prereqs.move_defs_to_fwd_decls();
+
+    Ok(CcSnippet {
+        prereqs,
+        tokens: quote! {
+            __NEWLINE__
+            __NEWLINE__ #doc_comment
+            #template_params
+            #static_ #ret_type #fn_name ( #( #params ),* ) #self_qualifiers = delete;
+            __NEWLINE__
+            #box_self_overload
+        },
+    })
+}
+
+/// Formats an instance of the function identified by `local_def_id` - either
+/// a non-generic function (if `substs` is empty), an explicit instantiation
+/// of a generic function, or a method of an explicit instantiation of a
+/// generic type (see `Input::instantiations`).
+///
+/// For a non-generic function (or a non-generic method of a generic type),
+/// returns the main API (i.e. the C++ declaration of the function) and
+/// optional implementation details (see `format_fn`).  For an instantiation
+/// of a generic function, returns only the implementation details (i.e. a C++
+/// explicit specialization of the function template from
+/// `format_fn_primary_template` and a Rust thunk).
+fn format_fn_instance<'tcx>(
+    input: &Input<'tcx>,
+    local_def_id: LocalDefId,
+    substs: ty::SubstsRef<'tcx>,
+) -> Result<Vec<(SnippetKey, MixedSnippet)>> {
+    let tcx = input.tcx;
+    let def_id: DefId = local_def_id.to_def_id(); // Convert LocalDefId to DefId.
+    let is_generic = !substs.is_empty();
+
+    // Type arguments of the function itself (rather than of the generic `impl` that contains it).
+    let own_type_args = type_args_of(&substs[tcx.generics_of(def_id).parent_count..])?;
+    let is_generic_fn = !own_type_args.is_empty();
+
+    let (sig, inputs_borrowed_by_output) =
+        liberate_fn_sig(tcx, def_id, tcx.fn_sig(def_id).subst(tcx, substs));
 
     let mut symbol_name = {
-        // Call to `mono` is ok - `generics_of` have been checked above.
-        let instance = ty::Instance::mono(tcx, def_id);
+        let instance = ty::Instance::new(def_id, substs);
         tcx.symbol_name(instance)
     };
 
@@ -600,43 +2439,91 @@ fn format_fn(input: &Input, local_def_id: LocalDefId) -> Result<Vec<(SnippetKey,
         bail!("C variadic functions are not supported (b/254097223)");
     }
 
-    match sig.unsafety {
-        Unsafety::Normal => (),
-        Unsafety::Unsafe => {
-            // TODO(b/254095482): Figure out how to handle `unsafe` functions.
-            bail!("Bindings for `unsafe` functions are not fully designed yet (b/254095482)");
-        }
-    }
+    // `unsafe` functions are exposed to C++ just like safe functions (C++ has no equivalent
+    // of `unsafe`), but their doc comment is annotated by `format_doc_comment`.
+    // `--exclude-unsafe-fns` is handled by the callers (see `format_fn`).
+    let is_unsafe = sig.unsafety == Unsafety::Unsafe;
 
     let needs_thunk: bool;
     match sig.abi {
         // "C" ABI is okay: Before https://rust-lang.github.io/rfcs/2945-c-unwind-abi.html a Rust
         // panic that "escapes" a "C" ABI function leads to Undefined Behavior.  This is
-        // unfortunate, but Crubit's `panics_and_exceptions.md` documents that `-Cpanic=abort` is
-        // the only supported configuration.
+        // unfortunate, but Crubit's `panics_and_exceptions.md` documents that `extern "C"`
+        // functions are called directly (i.e. without a thunk that could catch the panic).
         //
         // After https://rust-lang.github.io/rfcs/2945-c-unwind-abi.html a Rust panic that
         // tries to "escape" a "C" ABI function will terminate the program.  This is okay.
-        rustc_target::spec::abi::Abi::C { unwind: false } => {
+        rustc_target::spec::abi::Abi::C { unwind: false } if !is_generic => {
             needs_thunk = false;
         },
 
         // "C-unwind" ABI is okay: After https://rust-lang.github.io/rfcs/2945-c-unwind-abi.html a
         // new "C-unwind" ABI may be used by Rust functions that want to safely propagate Rust
         // panics through frames that may belong to another language.
-        rustc_target::spec::abi::Abi::C { unwind: true } => {
+        rustc_target::spec::abi::Abi::C { unwind: true } if !is_generic => {
             needs_thunk = false;
         },
 
         // All other ABIs trigger thunk generation.  This covers Rust ABI functions, but
         // also ABIs that theoretically are understood both by C++ and Rust (e.g. see
         // `format_cc_call_conv_as_clang_attribute` in `rs_bindings_from_cc/src_code_gen.rs`).
+        //
+        // Instantiations of generic functions always need a thunk, because the thunk is what
+        // causes the instantiation to be monomorphized (and exported) in the first place.
         _ => {
             let thunk_name = format!("__crubit_thunk_{}", symbol_name.name);
             symbol_name = ty::SymbolName::new(tcx, &thunk_name);
             needs_thunk = true;
         }
     };
+    // With `PanicHandling::Report` the C++ caller needs to check whether the thunk has
+    // reported a panic *before* reading the return value - this is why in this mode all
+    // return values (other than `()`) are returned via a `ReturnValueSlot`.
+    let reports_panics = needs_thunk
+        && tcx.sess().panic_strategy() == PanicStrategy::Unwind
+        && input.panic_handling == PanicHandling::Report
+        && !sig.output().is_never();
+    let returns_via_slot = !is_c_abi_compatible_by_value(sig.output())
+        || (reports_panics && !sig.output().is_unit());
+
+    if !needs_thunk {
+        // Tuples are marshalled one element at a time by the Rust thunk, but `extern "C"`
+        // functions are called directly (without a thunk).
+        ensure!(
+            sig.inputs_and_output.iter().all(|ty| tuple_element_types(ty).is_none()),
+            "Tuples are not supported in signatures of `extern \"C\"` functions (b/254099023)"
+        );
+
+        // Similarly, `Option`, `Result`, `Rc`, `Arc`, `String`, `Vec`, and Rust-ABI function
+        // pointers are marshalled by the Rust thunk.  `rs_std::Box` has the same layout as
+        // `Box<T>`, but it is not trivially copyable and therefore it may have a different ABI.
+        ensure!(
+            sig.inputs_and_output.iter().all(|ty| {
+                option_or_result(tcx, ty).is_none()
+                    && string_or_vec(tcx, ty).is_none()
+                    && boxed_ty(tcx, ty).is_none()
+                    && ref_counted_ptr(tcx, ty).is_none()
+                    && !is_rust_abi_fn_ptr(ty)
+            }),
+            "`Option`, `Result`, `Box`, `Rc`, `Arc`, `String`, `Vec`, and Rust-ABI function \
+             pointers are not supported in signatures of `extern \"C\"` functions"
+        );
+
+        // `&dyn Trait` is passed together with a vtable (see `DynPtr::Ref`).
+        ensure!(
+            sig.inputs_and_output.iter().all(|ty| dyn_ptr(tcx, ty).is_none()),
+            "Trait objects are not supported in signatures of `extern \"C\"` functions"
+        );
+
+        // Both the Rust callee and the C++ destructor of the by-value parameter would
+        // drop the parameter, unless the call goes through a thunk.
+        // TODO(b/259749095): Support generic structs (with non-empty ParamEnv).
+        ensure!(
+            sig.inputs().iter().all(|ty| !ty.needs_drop(tcx, ty::ParamEnv::empty())),
+            "Types that need drop glue can't be passed by value to `extern \"C\"` functions \
+             (b/258251148)"
+        );
+    }
 
     let FullyQualifiedName { krate, mod_path, name, .. } = FullyQualifiedName::new(tcx, def_id);
     let fn_name = name.expect("Functions are assumed to always have a name");
@@ -647,16 +2534,28 @@ fn format_fn(input: &Input, local_def_id: LocalDefId) -> Result<Vec<(SnippetKey,
     let main_api_ret_type = format_ret_ty_for_cc(input, sig.output())
         .context("Error formatting function return type")?
         .into_tokens(&mut main_api_prereqs);
-    let is_static_method = match tcx.hir().get_by_def_id(local_def_id) {
-        Node::ImplItem(impl_item) => match &impl_item.kind {
-            ImplItemKind::Fn(fn_sig, _) => match fn_sig.decl.implicit_self {
-                ImplicitSelfKind::None => true,
-                _ => bail!("`self` parameter is not supported yet"),
-            },
-            _ => panic!("`format_fn` can only work with functions"),
-        },
-        Node::Item(_) => false, // Free function
-        other => panic!("Unexpected HIR node kind: {other:?}"),
+    let fn_kind = get_fn_kind(tcx, local_def_id)?;
+    ensure!(
+        !matches!(dyn_ptr(tcx, sig.output()), Some((DynPtr::Ref(_), _))),
+        "`&dyn Trait` is only supported as a function parameter type"
+    );
+
+    // References are returned from the thunks as pointers (e.g. because C++ references can't be
+    // stored in a `crubit::ReturnValueSlot` and because the Rust thunks can't rely on lifetime
+    // elision in their return types).
+    let thunk_ret_ty = match sig.output().kind() {
+        ty::TyKind::Ref(_, referent, mutbl) => {
+            tcx.mk_ptr(ty::TypeAndMut { ty: *referent, mutbl: *mutbl })
+        }
+        _ => sig.output(),
+    };
+    let returns_reference = sig.output().is_ref();
+    // References to slices and strings are returned to C++ by value (as `rs_std::SliceRef<T>` or
+    // `rs_std::StrRef`) - this relies on raw slice pointers having the same ABI as slice
+    // references (see `rust_builtin_type_abi_assumptions.md`).
+    let returns_thin_reference = match sig.output().kind() {
+        ty::TyKind::Ref(_, referent, _) => !referent.is_slice() && !referent.is_str(),
+        _ => false,
     };
 
     struct Param<'tcx> {
@@ -665,48 +2564,107 @@ fn format_fn(input: &Input, local_def_id: LocalDefId) -> Result<Vec<(SnippetKey,
         rs_name: Ident,
         rs_type: TokenStream,
         ty: Ty<'tcx>,
+        is_lifetimebound: bool,
     }
     let params = {
         let names = tcx.fn_arg_names(def_id).iter();
         let types = sig.inputs().iter();
         names
             .zip(types)
+            .zip(inputs_borrowed_by_output.iter())
             .enumerate()
-            .map(|(i, (name, &ty))| -> Result<Param> {
-                let cc_name = format_cc_ident(name.as_str())
-                    .unwrap_or_else(|_err| format_cc_ident(&format!("__param_{i}")).unwrap());
-                let cc_type = format_ty_for_cc(input, ty)?.into_tokens(&mut main_api_prereqs);
-                let rs_name = if name.as_str().is_empty() {
+            .map(|(i, ((name, &ty), &is_lifetimebound))| -> Result<Param> {
+                let is_self_param = i == 0 && fn_kind.has_self_param();
+                let cc_name = if is_self_param {
+                    quote! { __self }
+                } else {
+                    format_cc_ident(name.as_str())
+                        .unwrap_or_else(|_err| format_cc_ident(&format!("__param_{i}")).unwrap())
+                };
+                // `absl::StatusOr` can't be converted back into a Rust `Result<T, E>` (because
+                // `E` can't be recreated from an `absl::Status`).  `rs_std::Result` parameters
+                // are supported.
+                if let Some(OptionOrResult::Result(_, err_ty)) = option_or_result(tcx, ty) {
+                    ensure!(
+                        !is_absl_status_or(input, err_ty),
+                        "`Result` mapped to `absl::StatusOr` is only supported as a return type"
+                    );
+                }
+                // `Pin<&mut Self>` is passed to the thunks as `&mut Self` and `Box<Self>` as
+                // `*mut Self` (the Rust thunk moves the value out of the C++ object).
+                let marshalled_ty = match fn_kind {
+                    _ if !is_self_param => ty,
+                    FnKind::PinnedRefSelfMethod(_) => match ty.kind() {
+                        ty::TyKind::Adt(_, substs) => substs.type_at(0),
+                        _ => panic!("`Pin<P>` is assumed to be an ADT"),
+                    },
+                    FnKind::BoxSelfMethod => tcx.mk_mut_ptr(ty.boxed_ty()),
+                    _ => ty,
+                };
+                let cc_type = format_param_ty_for_cc(input, marshalled_ty)?
+                    .into_tokens(&mut main_api_prereqs);
+                // `self` is also used as the name of the first parameter of trait methods that
+                // are exposed as free functions (see `FnKind::Free`).
+                let rs_name = if is_self_param || name.name == kw::SelfLower {
+                    format_ident!("__self")
+                } else if name.as_str().is_empty() {
                     format_ident!("__param_{i}")
                 } else {
                     make_rs_ident(name.as_str())
                 };
-                let rs_type = format_ty_for_rs(tcx, ty)?;
-                Ok(Param { cc_name, cc_type, rs_name, rs_type, ty })
+                let rs_type = format_ty_for_rs(tcx, marshalled_ty)?;
+                Ok(Param { cc_name, cc_type, rs_name, rs_type, ty, is_lifetimebound })
             })
             .enumerate()
             .map(|(i, result)| result.with_context(|| format!("Error handling parameter #{i}")))
             .collect::<Result<Vec<_>>>()?
     };
+    // The `self` parameter is represented in C++ as the implicit object parameter (`*this`).
     let main_api_params = params
         .iter()
-        .map(|Param { cc_name, cc_type, .. }| quote! { #cc_type #cc_name })
+        .skip(if fn_kind.has_self_param() { 1 } else { 0 })
+        .map(|Param { cc_name, cc_type, is_lifetimebound, .. }| {
+            let lifetimebound = format_lifetimebound_attr(*is_lifetimebound);
+            quote! { #cc_type #cc_name #lifetimebound }
+        })
         .collect_vec();
+    let (static_, self_qualifiers) = format_fn_kind_for_cc(fn_kind, &inputs_borrowed_by_output);
+    // The parameters of the static overload that takes an `rs_std::Box<Self>` (see
+    // `FnKind::BoxSelfMethod`).
+    let box_self_overload_params = if fn_kind == FnKind::BoxSelfMethod {
+        let box_cc_type = format_ty_for_cc(input, sig.inputs()[0])
+            .context("Error handling parameter #0")?
+            .into_tokens(&mut main_api_prereqs);
+        let box_param = quote! { #box_cc_type&& __self };
+        Some(
+            std::iter::once(box_param)
+                .chain(main_api_params.iter().cloned())
+                .collect_vec(),
+        )
+    } else {
+        None
+    };
 
-    let struct_name = match tcx.impl_of_method(def_id) {
-        Some(impl_id) => match tcx.impl_subject(impl_id) {
-            ty::ImplSubject::Inherent(ty) => match ty.kind() {
-                ty::TyKind::Adt(adt, substs) => {
-                    assert_eq!(0, substs.len(), "Callers should filter out generics");
-                    Some(tcx.item_name(adt.did()))
-                }
-                _ => panic!("Non-ADT `impl`s should be filtered by caller"),
-            },
-            ty::ImplSubject::Trait(_) => panic!("Trait methods should be filtered by caller"),
+    // The `Self` type of a method and the trait implemented by the `impl` that contains the
+    // method (if any).
+    let (impl_self_ty, trait_def_id) = match tcx.impl_of_method(def_id) {
+        Some(impl_id) => {
+            (Some(tcx.type_of(impl_id).subst(tcx, substs)), tcx.trait_id_of_impl(impl_id))
+        }
+        None => (None, None),
+    };
+    // The name and the type arguments of the C++ class that the function is a member of.
+    // Trait methods that are exposed as free functions (see `FnKind::Free`) are not members.
+    let struct_name = match impl_self_ty {
+        Some(_) if fn_kind == FnKind::Free => None,
+        Some(self_ty) => match self_ty.kind() {
+            ty::TyKind::Adt(adt, adt_substs) => Some((tcx.item_name(adt.did()), *adt_substs)),
+            _ => panic!("Non-ADT `impl`s should be filtered by caller"),
         },
         None => None,
     };
-    let needs_definition = fn_name.as_str() != symbol_name.name;
+    // Member functions (i.e. methods with a `self` parameter) can't be `extern "C"`.
+    let needs_definition = fn_name.as_str() != symbol_name.name || fn_kind.has_self_param();
     let main_api = {
         let doc_comment = {
             let doc_comment = format_doc_comment(tcx, local_def_id);
@@ -715,25 +2673,34 @@ fn format_fn(input: &Input, local_def_id: LocalDefId) -> Result<Vec<(SnippetKey,
 
         let mut prereqs = main_api_prereqs.clone();
         prereqs.move_defs_to_fwd_decls();
+        // `Trait::DynBox` and `Trait::DynArc` can't be used with just a forward declaration of
+        // `Trait`.
+        prereqs.defs.extend(
+            main_api_prereqs.defs.iter().filter(|&&def_id| tcx.def_kind(def_id) == DefKind::Trait),
+        );
 
-        let static_ = if is_static_method {
-            quote! { static }
-        } else {
-            quote! {}
-        };
-        let extern_c_or_inline = if !needs_definition {
-            quote! { extern "C" }
+        let extern_c_or_inline = if !needs_definition {
+            quote! { extern "C" }
         } else {
             quote! { inline }
         };
+        let box_self_overload = match &box_self_overload_params {
+            None => quote! {},
+            Some(params) => quote! {
+                static inline #main_api_ret_type #main_api_fn_name ( #( #params ),* );
+                __NEWLINE__
+            },
+        };
         CcSnippet {
             prereqs,
             tokens: quote! {
                 __NEWLINE__
                 #doc_comment
                 #static_ #extern_c_or_inline
-                    #main_api_ret_type #main_api_fn_name ( #( #main_api_params ),* );
+                    #main_api_ret_type #main_api_fn_name ( #( #main_api_params ),* )
+                    #self_qualifiers;
                 __NEWLINE__
+                #box_self_overload
             },
         }
     };
@@ -743,59 +2710,307 @@ fn format_fn(input: &Input, local_def_id: LocalDefId) -> Result<Vec<(SnippetKey,
         let cc = {
             let thunk_name =
                 format_cc_ident(symbol_name.name).context("Error formatting exported name")?;
-            let struct_name = match struct_name.as_ref() {
+            let mut prereqs = main_api_prereqs;
+            let struct_name = match struct_name {
                 None => quote! {},
-                Some(symbol) => {
+                Some((symbol, adt_substs)) => {
                     let name = format_cc_ident(symbol.as_str())
                         .expect("Caller of format_fn should verify struct via format_adt_core");
-                    quote! { #name :: }
+                    let type_args = if adt_substs.is_empty() {
+                        quote! {}
+                    } else {
+                        format_type_args_for_cc(input, adt_substs, &mut prereqs)?
+                    };
+                    quote! { #name #type_args :: }
                 }
             };
+            let (template_prefix, template_args) = if !is_generic_fn {
+                (quote! {}, quote! {})
+            } else {
+                // An explicit specialization of the primary template from
+                // `format_fn_primary_template`.
+                let type_args = own_type_args
+                    .iter()
+                    .map(|&ty| -> Result<TokenStream> {
+                        Ok(format_ty_for_cc(input, ty)?.into_tokens(&mut prereqs))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                (quote! { template <> }, quote! { < #( #type_args ),* > })
+            };
+            // The thunk returns a pointer (rather than a reference) - see `thunk_ret_ty`.
+            let deref = if returns_thin_reference { quote! { * } } else { quote! {} };
+            let mut thunk_params = vec![];
+            let mut thunk_args = vec![];
+            for (i, Param { cc_name, cc_type, rs_name, ty, .. }) in params.iter().enumerate() {
+                if i == 0 && fn_kind.has_self_param() {
+                    thunk_params.push(quote! { #cc_type #cc_name });
+                    if fn_kind == FnKind::BoxSelfMethod {
+                        // The Rust thunk will move the value either out of `*this` (see the
+                        // handling of by-value parameters below) or out of the `rs_std::Box`
+                        // passed to the static overload (which passes `nullptr` as `this`).
+                        ensure_cc_movable(tcx, ty.boxed_ty())?;
+                        let box_cc_type = format_ty_for_cc(input, *ty)?.into_tokens(&mut prereqs);
+                        thunk_params.push(quote! { #box_cc_type* __self_box });
+                        thunk_args.push(quote! { this });
+                        thunk_args.push(quote! { nullptr });
+                    } else {
+                        thunk_args.push(quote! { *this });
+                    }
+                    continue;
+                }
+                if let Some((DynPtr::Ref(_), dyn_ty)) = dyn_ptr(tcx, *ty) {
+                    // The vtable is used by the Rust thunk to call the C++ virtual methods.
+                    let trait_def_id = get_dyn_trait_def_id(input, dyn_ty)?.to_def_id();
+                    let trait_name = FullyQualifiedName::new(tcx, trait_def_id).format_for_cc()?;
+                    let vtable_name = format_ident!("__param_{}_vtable", i);
+                    thunk_params.push(quote! { #cc_type #cc_name });
+                    thunk_params.push(quote! { const #trait_name::__CrubitVtable* #vtable_name });
+                    thunk_args.push(quote! { #cc_name });
+                    thunk_args.push(quote! { &#trait_name::__crubit_vtable });
+                    continue;
+                }
+                if is_c_abi_compatible_by_value(*ty) {
+                    thunk_params.push(quote! { #cc_type #cc_name });
+                    thunk_args.push(quote! { #cc_name });
+                    continue;
+                }
+                if let Some(OptionOrResult::Option(value_ty)) = option_or_result(tcx, *ty) {
+                    // `std::nullopt` is passed as a null pointer.  Otherwise the Rust thunk
+                    // moves the value out of the `std::optional` (see the `None` case below).
+                    ensure_cc_movable(tcx, value_ty)?;
+                    let value_cc_type =
+                        format_ty_for_cc(input, value_ty)?.into_tokens(&mut prereqs);
+                    thunk_params.push(quote! { #value_cc_type* #cc_name });
+                    thunk_args.push(quote! { #cc_name ? &* #cc_name : nullptr });
+                    continue;
+                }
+                if let Some(OptionOrResult::Result(ok_ty, err_ty)) = option_or_result(tcx, *ty) {
+                    // The discriminant is passed as a `bool` and the payloads as nullable
+                    // pointers.  The Rust thunk moves the payload out of the `rs_std::Result`
+                    // (see the `Option` case above).
+                    let is_ok_name = format_ident!("{}_is_ok", rs_name);
+                    thunk_params.push(quote! { bool #is_ok_name });
+                    thunk_args.push(quote! { #cc_name.is_ok() });
+                    let payloads = [
+                        (ok_ty, "ok", quote! { get_if_ok }),
+                        (err_ty, "err", quote! { get_if_err }),
+                    ];
+                    for (payload_ty, suffix, get_if) in payloads {
+                        if payload_ty.is_unit() {
+                            continue;
+                        }
+                        ensure_cc_movable(tcx, payload_ty)?;
+                        let payload_cc_type =
+                            format_ty_for_cc(input, payload_ty)?.into_tokens(&mut prereqs);
+                        let payload_name = format_ident!("{}_{}", rs_name, suffix);
+                        thunk_params.push(quote! { #payload_cc_type* #payload_name });
+                        thunk_args.push(quote! { #cc_name.#get_if() });
+                    }
+                    continue;
+                }
 
-            let mut prereqs = main_api_prereqs;
-            let mut thunk_params = params
-                .iter()
-                .map(|Param { cc_name, cc_type, ty, .. }| -> Result<TokenStream> {
-                     if is_c_abi_compatible_by_value(*ty) {
-                         Ok(quote! { #cc_type #cc_name })
-                     } else {
-                         // Rust thunk will move a value via memcpy - we need to `ensure` that
-                         // invoking the C++ destructor (on the moved-away value) is safe.
-                         // TODO(b/259749095): Support generic structs (with non-empty ParamEnv).
-                         ensure!(!ty.needs_drop(tcx, ty::ParamEnv::empty()),
-                                 "Only trivially-movable and trivially-destructible types \
-                                  may be passed by value over the FFI boundary");
-                         Ok(quote! { #cc_type* #cc_name })
-                     }})
-                .collect::<Result<Vec<_>>>()?;
-            let mut thunk_args = params
-                .iter()
-                .map(|Param{ cc_name, ty, ..}|
-                     if is_c_abi_compatible_by_value(*ty) {
-                         quote!{ #cc_name }
-                     } else {
-                         quote!{ & #cc_name }
-                     })
-                .collect_vec();
+                match tuple_element_types(*ty) {
+                    None => {
+                        // Rust thunk will either move the value via memcpy (if invoking the
+                        // C++ destructor on the moved-away value is a no-op) or will replace
+                        // it with `Default::default()` (if the C++ destructor drops the value).
+                        ensure_cc_movable(tcx, *ty)?;
+                        thunk_params.push(quote! { #cc_type* #cc_name });
+                        thunk_args.push(quote! { & #cc_name });
+                    }
+                    Some(element_types) => {
+                        // Rust thunk will move tuple elements via memcpy - we need to `ensure`
+                        // that invoking the C++ destructor (on the moved-away value) is safe.
+                        // TODO(b/259749095): Support generic structs (with non-empty ParamEnv).
+                        ensure!(!ty.needs_drop(tcx, ty::ParamEnv::empty()),
+                                "Only trivially-movable and trivially-destructible types \
+                                 may be passed by value over the FFI boundary");
+                        for (i, element_ty) in element_types.iter().enumerate() {
+                            let element_cc_type =
+                                format_ty_for_cc(input, element_ty)?.into_tokens(&mut prereqs);
+                            let element_name = format_ident!("{}_{}", rs_name, i);
+                            let index = Literal::usize_unsuffixed(i);
+                            thunk_params.push(quote! { #element_cc_type* #element_name });
+                            thunk_args.push(quote! { &std::get<#index>(#cc_name) });
+                        }
+                    }
+                }
+            }
             let thunk_ret_type: TokenStream;
-            let impl_body: TokenStream;
-            if is_c_abi_compatible_by_value(sig.output()) {
-                thunk_ret_type = main_api_ret_type.clone();
-                impl_body = quote!{
-                    return __crubit_internal :: #thunk_name( #( #thunk_args ),* );
+            let ret_slot_decls: TokenStream;
+            let ret_stmt: Option<TokenStream>;
+            if let Some(element_types) = tuple_element_types(sig.output()) {
+                // Tuples are returned one element at a time - see `tuple_element_types`.
+                let mut ret_slots = vec![];
+                let mut element_cc_types = vec![];
+                for (i, element_ty) in element_types.iter().enumerate() {
+                    ensure_cc_movable(tcx, element_ty)?;
+                    let element_cc_type =
+                        format_ty_for_cc(input, element_ty)?.into_tokens(&mut prereqs);
+                    let ret_ptr = format_ident!("__ret_ptr_{}", i);
+                    let ret_slot = format_ident!("__ret_slot_{}", i);
+                    thunk_params.push(quote!{ #element_cc_type* #ret_ptr });
+                    thunk_args.push(quote!{ #ret_slot.Get() });
+                    element_cc_types.push(element_cc_type);
+                    ret_slots.push(ret_slot);
+                }
+                thunk_ret_type = quote!{ void };
+                ret_slot_decls = quote!{
+                    #( crubit::ReturnValueSlot<#element_cc_types> #ret_slots; )*
                 };
+                ret_stmt = Some(quote!{
+                    return #main_api_ret_type(
+                        #( std::move(#ret_slots).AssumeInitAndTakeValue() ),* );
+                });
+                prereqs.includes.insert(CcInclude::utility());
+                prereqs.includes.insert(input.support_header("internal/return_value_slot.h"));
+            } else if let Some(option_or_result) = option_or_result(tcx, sig.output()) {
+                // The discriminant and the payload are returned separately - see
+                // `OptionOrResult`.
+                let (flag, payloads) = match option_or_result {
+                    OptionOrResult::Option(value_ty) => {
+                        (format_ident!("__ret_is_some"), vec![("", value_ty)])
+                    }
+                    OptionOrResult::Result(ok_ty, err_ty) if ok_ty.is_unit() => {
+                        (format_ident!("__ret_is_ok"), vec![("_err", err_ty)])
+                    }
+                    OptionOrResult::Result(ok_ty, err_ty) => {
+                        (format_ident!("__ret_is_ok"), vec![("_ok", ok_ty), ("_err", err_ty)])
+                    }
+                };
+                let mut slot_decls = vec![quote!{ bool #flag; }];
+                thunk_params.push(quote!{ bool* #flag });
+                thunk_args.push(quote!{ &#flag });
+                for (suffix, payload_ty) in payloads.into_iter() {
+                    ensure_cc_movable(tcx, payload_ty)?;
+                    let payload_cc_type =
+                        format_ty_for_cc(input, payload_ty)?.into_tokens(&mut prereqs);
+                    let ret_ptr = format_ident!("__ret_ptr{}", suffix);
+                    let ret_slot = format_ident!("__ret_slot{}", suffix);
+                    thunk_params.push(quote!{ #payload_cc_type* #ret_ptr });
+                    thunk_args.push(quote!{ #ret_slot.Get() });
+                    slot_decls.push(quote!{ crubit::ReturnValueSlot<#payload_cc_type> #ret_slot; });
+                }
+                let take_value = |suffix: &str| {
+                    let ret_slot = format_ident!("__ret_slot{}", suffix);
+                    quote!{ std::move(#ret_slot).AssumeInitAndTakeValue() }
+                };
+                thunk_ret_type = quote!{ void };
+                ret_slot_decls = quote!{ #( #slot_decls )* };
+                ret_stmt = Some(match option_or_result {
+                    OptionOrResult::Option(_) => {
+                        let value = take_value("");
+                        quote!{
+                            if (!__ret_is_some) { return std::nullopt; }
+                            return #value;
+                        }
+                    }
+                    OptionOrResult::Result(ok_ty, err_ty) if is_absl_status_or(input, err_ty) => {
+                        let ok = if ok_ty.is_unit() {
+                            quote!{ absl::OkStatus() }
+                        } else {
+                            take_value("_ok")
+                        };
+                        let err = take_value("_err");
+                        quote!{
+                            if (__ret_is_ok) { return #ok; }
+                            return absl::UnknownError(#err.ToString());
+                        }
+                    }
+                    OptionOrResult::Result(ok_ty, _) => {
+                        let ok = if ok_ty.is_unit() {
+                            quote!{ std::monostate() }
+                        } else {
+                            take_value("_ok")
+                        };
+                        let err = take_value("_err");
+                        quote!{
+                            if (__ret_is_ok) { return #main_api_ret_type::Ok(#ok); }
+                            return #main_api_ret_type::Err(#err);
+                        }
+                    }
+                });
+                prereqs.includes.insert(CcInclude::utility());
+                prereqs.includes.insert(input.support_header("internal/return_value_slot.h"));
+            } else if !returns_via_slot {
+                thunk_ret_type = if returns_thin_reference {
+                    format_ty_for_cc(input, thunk_ret_ty)?.into_tokens(&mut prereqs)
+                } else {
+                    main_api_ret_type.clone()
+                };
+                ret_slot_decls = quote!{};
+                ret_stmt = None;
             } else {
+                // `ReturnValueSlot::AssumeInitAndTakeValue` uses the C++ move constructor.
+                ensure_cc_movable(tcx, sig.output())?;
+                let ret_slot_type = if returns_thin_reference {
+                    format_ty_for_cc(input, thunk_ret_ty)?.into_tokens(&mut prereqs)
+                } else {
+                    main_api_ret_type.clone()
+                };
                 thunk_ret_type = quote!{ void };
-                thunk_params.push(quote!{ #main_api_ret_type* __ret_ptr });
+                thunk_params.push(quote!{ #ret_slot_type* __ret_ptr });
                 thunk_args.push(quote!{ __ret_slot.Get() });
-                impl_body = quote!{
-                    crubit::ReturnValueSlot<#main_api_ret_type> __ret_slot;
-                    __crubit_internal :: #thunk_name( #( #thunk_args ),* );
-                    return std::move(__ret_slot).AssumeInitAndTakeValue();
+                ret_slot_decls = quote!{
+                    crubit::ReturnValueSlot<#ret_slot_type> __ret_slot;
                 };
+                ret_stmt = Some(quote!{
+                    return #deref std::move(__ret_slot).AssumeInitAndTakeValue();
+                });
                 prereqs.includes.insert(CcInclude::utility());
                 prereqs.includes.insert(input.support_header("internal/return_value_slot.h"));
             };
+            let (panic_slot_decl, panic_slot_check) = if reports_panics {
+                thunk_params.push(quote!{
+                    crubit::internal::PanicSlot* __panic_slot,
+                    crubit::internal::PanicSlot::ReportFn __report_panic
+                });
+                thunk_args.push(quote!{
+                    &__panic_slot, &crubit::internal::PanicSlot::Report
+                });
+                prereqs.includes.insert(CcInclude::utility());
+                prereqs.includes.insert(input.support_header("internal/rust_panic.h"));
+                (
+                    quote!{ crubit::internal::PanicSlot __panic_slot; },
+                    quote!{ std::move(__panic_slot).ThrowIfPanicked(); },
+                )
+            } else {
+                (quote!{}, quote!{})
+            };
+            let format_impl_body = |thunk_args: &[TokenStream]| {
+                let thunk_call =
+                    quote!{ __crubit_internal :: #thunk_name( #( #thunk_args ),* ) };
+                match &ret_stmt {
+                    None if !reports_panics => quote!{ return #deref #thunk_call; },
+                    ret_stmt => quote!{
+                        #ret_slot_decls
+                        #panic_slot_decl
+                        #thunk_call;
+                        #panic_slot_check
+                        #ret_stmt
+                    },
+                }
+            };
+            let impl_body = format_impl_body(&thunk_args);
+            let box_self_overload = match &box_self_overload_params {
+                None => quote!{},
+                Some(params) => {
+                    // The `rs_std::Box` is passed instead of `this` (see the `self` parameter
+                    // above).
+                    let mut thunk_args = thunk_args.clone();
+                    thunk_args[0] = quote!{ nullptr };
+                    thunk_args[1] = quote!{ &__self };
+                    let impl_body = format_impl_body(&thunk_args);
+                    quote!{
+                        #template_prefix
+                        inline #main_api_ret_type #struct_name #main_api_fn_name #template_args (
+                                #( #params ),* ) {
+                            #impl_body
+                        }
+                        __NEWLINE__
+                    }
+                }
+            };
             CcSnippet {
                 prereqs,
                 tokens: quote! {
@@ -803,11 +3018,13 @@ fn format_fn(input: &Input, local_def_id: LocalDefId) -> Result<Vec<(SnippetKey,
                     namespace __crubit_internal {
                         extern "C" #thunk_ret_type #thunk_name ( #( #thunk_params ),* );
                     }
-                    inline #main_api_ret_type #struct_name #main_api_fn_name (
-                            #( #main_api_params ),* ) {
+                    #template_prefix
+                    inline #main_api_ret_type #struct_name #main_api_fn_name #template_args (
+                            #( #main_api_params ),* ) #self_qualifiers {
                         #impl_body
                     }
                     __NEWLINE__
+                    #box_self_overload
                 },
             }
         };
@@ -816,44 +3033,340 @@ fn format_fn(input: &Input, local_def_id: LocalDefId) -> Result<Vec<(SnippetKey,
             quote! {}
         } else {
             let thunk_name = make_rs_ident(symbol_name.name);
-            let mut thunk_params = params
-                .iter()
-                .map(|Param{ rs_name, rs_type, ty, ..}|
-                    if is_c_abi_compatible_by_value(*ty) {
-                        quote!{ #rs_name: #rs_type }
+            let mut thunk_params = vec![];
+            let mut fn_args = vec![];
+            for (i, Param { rs_name, rs_type, ty, .. }) in params.iter().enumerate() {
+                if i == 0 && fn_kind == FnKind::BoxSelfMethod {
+                    // The `Box` passed to the static overload is used as is (see the handling
+                    // of `Box<T>` parameters below).  Otherwise `*this` is moved into a new
+                    // `Box` (leaving behind either a memcpy-ed copy that C++ won't drop or
+                    // `Default::default()`).
+                    let self_rs_type = format_ty_for_rs(tcx, ty.boxed_ty())?;
+                    let box_rs_type = format_ty_for_rs(tcx, *ty)?;
+                    let this = quote!{ #rs_name.expect("Null `this` passed as `self: Box<Self>`") };
+                    let self_value = if ty.boxed_ty().needs_drop(tcx, ty::ParamEnv::empty()) {
+                        thunk_params.push(quote!{
+                            #rs_name: ::core::option::Option<&mut #self_rs_type>
+                        });
+                        quote!{ ::core::mem::take(#this) }
                     } else {
-                        quote!{ #rs_name: &mut ::core::mem::MaybeUninit<#rs_type> }
-                    })
-                .collect_vec();
-            let mut thunk_ret_type = format_ty_for_rs(tcx, sig.output())?;
+                        thunk_params.push(quote!{
+                            #rs_name: ::core::option::Option<
+                                &mut ::core::mem::MaybeUninit<#self_rs_type>>
+                        });
+                        quote!{ unsafe { #this.assume_init_read() } }
+                    };
+                    thunk_params.push(quote!{
+                        __self_box: ::core::option::Option<
+                            &mut ::core::option::Option<#box_rs_type>>
+                    });
+                    fn_args.push(quote!{
+                        match __self_box {
+                            Some(__self_box) => __self_box
+                                .take()
+                                .expect("Null `rs_std::Box` passed as `Box<T>`"),
+                            None => ::std::boxed::Box::new(#self_value),
+                        }
+                    });
+                    continue;
+                }
+                if i == 0 && matches!(fn_kind, FnKind::PinnedRefSelfMethod(_)) {
+                    // `Pin::new` requires `Self: Unpin` (which `get_fn_kind` verifies).
+                    thunk_params.push(quote!{ #rs_name: #rs_type });
+                    fn_args.push(quote!{ ::core::pin::Pin::new(#rs_name) });
+                    continue;
+                }
+                if let Some((DynPtr::Ref(mutbl), dyn_ty)) = dyn_ptr(tcx, *ty) {
+                    // The C++ object is wrapped in an adapter that implements the trait by
+                    // calling through the vtable (see `format_trait`).
+                    let trait_def_id = get_dyn_trait_def_id(input, dyn_ty)?.to_def_id();
+                    let thunk_suffix = format_trait_thunk_suffix(tcx, trait_def_id);
+                    let vtable_struct = format_ident!("__CrubitCcVtable_{thunk_suffix}");
+                    let object_struct = format_ident!("__CrubitCcObject_{thunk_suffix}");
+                    let vtable_name = format_ident!("__param_{}_vtable", i);
+                    thunk_params.push(quote!{ #rs_name: *mut ::core::ffi::c_void });
+                    thunk_params.push(quote!{ #vtable_name: &'static #vtable_struct });
+                    let object = quote!{ #object_struct { obj: #rs_name, vtable: #vtable_name } };
+                    fn_args.push(match mutbl {
+                        Mutability::Not => quote!{ &#object },
+                        Mutability::Mut => quote!{ &mut #object },
+                    });
+                } else if let Some((DynPtr::Box | DynPtr::Arc, _)) = dyn_ptr(tcx, *ty) {
+                    // `Option::take` leaves `None` behind in the moved-from `Trait::DynBox` or
+                    // `Trait::DynArc`.
+                    let ffi_type = format_ffi_ty_for_rs(tcx, *ty)?;
+                    thunk_params.push(quote!{ #rs_name: &mut #ffi_type });
+                    fn_args.push(quote!{
+                        #rs_name.take().expect("Moved-from trait object passed from C++")
+                    });
+                } else if is_c_abi_compatible_by_value(*ty) {
+                    thunk_params.push(quote!{ #rs_name: #rs_type });
+                    fn_args.push(quote!{ #rs_name });
+                } else if let Some(element_types) = tuple_element_types(*ty) {
+                    let mut element_args = vec![];
+                    for (i, element_ty) in element_types.iter().enumerate() {
+                        let element_rs_type = format_ty_for_rs(tcx, element_ty)?;
+                        let element_name = format_ident!("{}_{}", rs_name, i);
+                        thunk_params.push(quote!{
+                            #element_name: &mut ::core::mem::MaybeUninit<#element_rs_type>
+                        });
+                        element_args.push(quote!{ unsafe { #element_name.assume_init_read() } });
+                    }
+                    fn_args.push(quote!{ ( #( #element_args , )* ) });
+                } else if let Some(OptionOrResult::Option(value_ty)) =
+                    option_or_result(tcx, *ty)
+                {
+                    // `Option<&mut T>` is guaranteed to have the same ABI as a nullable pointer.
+                    let value_rs_type = format_ffi_ty_for_rs(tcx, value_ty)?;
+                    if value_ty.needs_drop(tcx, ty::ParamEnv::empty()) {
+                        let take_fn = format_take_fn_for_rs(tcx, value_ty);
+                        thunk_params.push(quote!{
+                            #rs_name: ::core::option::Option<&mut #value_rs_type>
+                        });
+                        fn_args.push(quote!{ #rs_name.map(#take_fn) });
+                    } else {
+                        thunk_params.push(quote!{
+                            #rs_name: ::core::option::Option<
+                                &mut ::core::mem::MaybeUninit<#value_rs_type>>
+                        });
+                        fn_args.push(quote!{
+                            #rs_name.map(|value| unsafe { value.assume_init_read() })
+                        });
+                    }
+                } else if let Some(OptionOrResult::Result(ok_ty, err_ty)) =
+                    option_or_result(tcx, *ty)
+                {
+                    // `rs_std::Result` is marshalled as an `is_ok` discriminant and nullable
+                    // pointers to the payloads (`()` payloads are omitted).  A null pointer for
+                    // the selected payload means that C++ passed a valueless `std::variant`.
+                    let is_ok_name = format_ident!("{}_is_ok", rs_name);
+                    thunk_params.push(quote!{ #is_ok_name: bool });
+                    let mut payload_values = vec![];
+                    for (payload_ty, suffix) in [(ok_ty, "ok"), (err_ty, "err")] {
+                        if payload_ty.is_unit() {
+                            payload_values.push(quote!{ () });
+                            continue;
+                        }
+                        let payload_rs_type = format_ffi_ty_for_rs(tcx, payload_ty)?;
+                        let payload_name = format_ident!("{}_{}", rs_name, suffix);
+                        let payload = quote!{
+                            #payload_name.expect("Valueless `rs_std::Result` passed from C++")
+                        };
+                        if payload_ty.needs_drop(tcx, ty::ParamEnv::empty()) {
+                            let take_fn = format_take_fn_for_rs(tcx, payload_ty);
+                            thunk_params.push(quote!{
+                                #payload_name: ::core::option::Option<&mut #payload_rs_type>
+                            });
+                            payload_values.push(quote!{ #take_fn(#payload) });
+                        } else {
+                            thunk_params.push(quote!{
+                                #payload_name: ::core::option::Option<
+                                    &mut ::core::mem::MaybeUninit<#payload_rs_type>>
+                            });
+                            payload_values.push(quote!{ unsafe { #payload.assume_init_read() } });
+                        }
+                    }
+                    let (ok_value, err_value) = (&payload_values[0], &payload_values[1]);
+                    fn_args.push(quote!{
+                        if #is_ok_name {
+                            ::core::result::Result::Ok(#ok_value)
+                        } else {
+                            ::core::result::Result::Err(#err_value)
+                        }
+                    });
+                } else if ty.is_box() {
+                    // `Option<Box<T>>` has the same layout as `Box<T>` (and as `rs_std::Box<T>`).
+                    // `Option::take` leaves a null pointer behind, so that the C++ destructor
+                    // of the moved-away `rs_std::Box` is a no-op.
+                    thunk_params.push(quote!{ #rs_name: &mut ::core::option::Option<#rs_type> });
+                    fn_args.push(quote!{
+                        #rs_name.take().expect("Null `rs_std::Box` passed as `Box<T>`")
+                    });
+                } else if is_rust_abi_fn_ptr(*ty) {
+                    // The trampoline half of `rs_std::FnPtr<F>` is not needed to call `F` from
+                    // Rust.
+                    let ffi_type = format_ffi_ty_for_rs(tcx, *ty)?;
+                    thunk_params.push(quote!{
+                        #rs_name: &mut ::core::mem::MaybeUninit<#ffi_type>
+                    });
+                    fn_args.push(quote!{ unsafe { #rs_name.assume_init_read() }.fn_ptr });
+                } else if ty.needs_drop(tcx, ty::ParamEnv::empty()) {
+                    // The C++ destructor will drop the moved-away value, so it needs to be
+                    // replaced with `Default::default()` (see also `ensure_cc_movable` above) or
+                    // with an empty `__CrubitVec`.
+                    let ffi_type = format_ffi_ty_for_rs(tcx, *ty)?;
+                    let take_fn = format_take_fn_for_rs(tcx, *ty);
+                    thunk_params.push(quote!{ #rs_name: &mut #ffi_type });
+                    fn_args.push(quote!{ #take_fn(#rs_name) });
+                } else {
+                    thunk_params.push(quote!{ #rs_name: &mut ::core::mem::MaybeUninit<#rs_type> });
+                    fn_args.push(quote!{ unsafe { #rs_name.assume_init_read() } });
+                }
+            }
+            let mut thunk_ret_type = format_ty_for_rs(tcx, thunk_ret_ty)?;
             let mut thunk_body = {
                 let crate_name = make_rs_ident(krate.as_str());
                 let mod_path = mod_path.format_for_rs();
                 let fn_name = make_rs_ident(fn_name.as_str());
-                let struct_name = match struct_name.as_ref() {
+                let struct_name = match struct_name {
                     None => quote! {},
-                    Some(symbol) => {
+                    Some((symbol, adt_substs)) => {
                         let name = make_rs_ident(symbol.as_str());
-                        quote! { #name :: }
+                        if adt_substs.is_empty() {
+                            quote! { #name :: }
+                        } else {
+                            let type_args = format_type_args_for_rs(tcx, adt_substs)?;
+                            quote! { #name :: #type_args :: }
+                        }
                     }
                 };
-                let fn_args = params.iter().map(|Param{ rs_name, ty, .. }|
-                    if is_c_abi_compatible_by_value(*ty) {
-                        quote!{ #rs_name }
-                    } else {
-                        quote!{ unsafe { #rs_name.assume_init_read() } }
-                    });
-                quote!{
-                    :: #crate_name :: #mod_path #struct_name #fn_name( #( #fn_args ),* )
+                // Trait methods are called via a fully-qualified path, because an inherent
+                // method with the same name would take precedence.
+                let callee = match (impl_self_ty, trait_def_id) {
+                    (Some(self_ty), Some(trait_def_id)) => {
+                        let self_ty = format_ty_for_rs(tcx, self_ty)?;
+                        let trait_name = FullyQualifiedName::new(tcx, trait_def_id).format_for_rs();
+                        quote! { < #self_ty as #trait_name > :: }
+                    }
+                    _ => quote! { :: #crate_name :: #mod_path #struct_name },
+                };
+                let turbofish = if !is_generic_fn {
+                    quote! {}
+                } else {
+                    let type_args = own_type_args
+                        .iter()
+                        .map(|&ty| format_ty_for_rs(tcx, ty))
+                        .collect::<Result<Vec<_>>>()?;
+                    quote! { :: < #( #type_args ),* > }
+                };
+                let call = quote!{ #callee #fn_name #turbofish ( #( #fn_args ),* ) };
+                let call = if returns_reference {
+                    quote!{ #call as #thunk_ret_type }
+                } else {
+                    call
+                };
+                if is_unsafe {
+                    quote!{ unsafe { #call } }
+                } else {
+                    call
                 }
             };
-            if !is_c_abi_compatible_by_value(sig.output()) {
+            if let Some(element_types) = tuple_element_types(sig.output()) {
+                let mut ret_slots = vec![];
+                let mut ret_values = vec![];
+                let mut ret_names = vec![];
+                for (i, element_ty) in element_types.iter().enumerate() {
+                    let element_rs_type = format_ffi_ty_for_rs(tcx, element_ty)?;
+                    let ret_slot = format_ident!("__ret_slot_{}", i);
+                    let ret_value = format_ident!("__ret_value_{}", i);
+                    thunk_params.push(quote!{
+                        #ret_slot: &mut ::core::mem::MaybeUninit<#element_rs_type>
+                    });
+                    ret_slots.push(ret_slot);
+                    ret_values.push(
+                        format_into_ffi_value_for_rs(tcx, element_ty, quote!{ #ret_value })?);
+                    ret_names.push(ret_value);
+                }
+                thunk_ret_type = quote!{ () };
+                thunk_body = quote!{
+                    let ( #( #ret_names , )* ) = #thunk_body;
+                    #( #ret_slots.write(#ret_values); )*
+                };
+            } else if let Some(option_or_result) = option_or_result(tcx, sig.output()) {
+                let format_ret_slot_param =
+                    |ret_slot: Ident, payload_ty: Ty<'tcx>| -> Result<TokenStream> {
+                        let payload_rs_type = format_ffi_ty_for_rs(tcx, payload_ty)?;
+                        Ok(quote!{ #ret_slot: &mut ::core::mem::MaybeUninit<#payload_rs_type> })
+                    };
+                thunk_body = match option_or_result {
+                    OptionOrResult::Option(value_ty) => {
+                        thunk_params.push(quote!{
+                            __ret_is_some: &mut ::core::mem::MaybeUninit<bool>
+                        });
+                        thunk_params.push(
+                            format_ret_slot_param(format_ident!("__ret_slot"), value_ty)?);
+                        let value =
+                            format_into_ffi_value_for_rs(tcx, value_ty, quote!{ __ret_value })?;
+                        quote!{
+                            match #thunk_body {
+                                Some(__ret_value) => {
+                                    __ret_slot.write(#value);
+                                    __ret_is_some.write(true);
+                                }
+                                None => { __ret_is_some.write(false); }
+                            }
+                        }
+                    }
+                    OptionOrResult::Result(ok_ty, err_ty) => {
+                        thunk_params.push(quote!{
+                            __ret_is_ok: &mut ::core::mem::MaybeUninit<bool>
+                        });
+                        let ok_arm = if ok_ty.is_unit() {
+                            quote!{ Ok(()) => { __ret_is_ok.write(true); } }
+                        } else {
+                            thunk_params.push(
+                                format_ret_slot_param(format_ident!("__ret_slot_ok"), ok_ty)?);
+                            let value =
+                                format_into_ffi_value_for_rs(tcx, ok_ty, quote!{ __ret_value })?;
+                            quote!{
+                                Ok(__ret_value) => {
+                                    __ret_slot_ok.write(#value);
+                                    __ret_is_ok.write(true);
+                                }
+                            }
+                        };
+                        thunk_params.push(
+                            format_ret_slot_param(format_ident!("__ret_slot_err"), err_ty)?);
+                        let error =
+                            format_into_ffi_value_for_rs(tcx, err_ty, quote!{ __ret_error })?;
+                        quote!{
+                            match #thunk_body {
+                                #ok_arm
+                                Err(__ret_error) => {
+                                    __ret_slot_err.write(#error);
+                                    __ret_is_ok.write(false);
+                                }
+                            }
+                        }
+                    }
+                };
+                thunk_ret_type = quote!{ () };
+            } else if returns_via_slot {
+                let ret_slot_type = format_ffi_ty_for_rs(tcx, thunk_ret_ty)?;
+                let ret_value = format_into_ffi_value_for_rs(tcx, thunk_ret_ty, thunk_body)?;
                 thunk_params.push(quote!{
-                    __ret_slot: &mut ::core::mem::MaybeUninit<#thunk_ret_type>
+                    __ret_slot: &mut ::core::mem::MaybeUninit<#ret_slot_type>
                 });
                 thunk_ret_type = quote!{ () };
-                thunk_body = quote!{ __ret_slot.write(#thunk_body); };
+                thunk_body = quote!{ __ret_slot.write(#ret_value); };
             };
+            if reports_panics {
+                thunk_params.push(quote!{
+                    __panic_slot: *mut ::core::ffi::c_void,
+                    __report_panic: extern "C" fn(*mut ::core::ffi::c_void, *const u8, usize)
+                });
+                thunk_body = quote!{
+                    let __result = ::std::panic::catch_unwind(
+                        ::std::panic::AssertUnwindSafe(|| { #thunk_body }));
+                    if let Err(__panic_payload) = __result {
+                        let __panic_message = __crubit_panic_message(&*__panic_payload);
+                        __report_panic(
+                            __panic_slot, __panic_message.as_ptr(), __panic_message.len());
+                    }
+                };
+            } else if sig.output().is_never() {
+                if tcx.sess().panic_strategy() == PanicStrategy::Unwind {
+                    // A function that never returns can't report a panic to the C++ caller
+                    // (because the C++ caller doesn't expect the thunk to return).
+                    thunk_body = quote!{
+                        let __panic_payload = ::std::panic::catch_unwind(
+                            ::std::panic::AssertUnwindSafe(|| { #thunk_body; })).unwrap_err();
+                        __crubit_abort_on_panic(__panic_payload)
+                    };
+                }
+            } else {
+                thunk_body = format_rs_thunk_body_aborting_on_panic(tcx, thunk_body);
+            }
             quote! {
                 #[no_mangle]
                 extern "C" fn #thunk_name( #( #thunk_params ),* ) -> #thunk_ret_type {
@@ -865,8 +3378,13 @@ fn format_fn(input: &Input, local_def_id: LocalDefId) -> Result<Vec<(SnippetKey,
         Some(MixedSnippet { cc, rs })
     };
 
-    let mut result =
-        vec![(SnippetKey { def_id: local_def_id, kind: SnippetKind::MainApi }, main_api.into())];
+    let mut result = vec![];
+    if !is_generic_fn {
+        result.push((
+            SnippetKey { def_id: local_def_id, kind: SnippetKind::MainApi },
+            main_api.into(),
+        ));
+    }
     if let Some(impl_details) = impl_details {
         result.push((
             SnippetKey { def_id: local_def_id, kind: SnippetKind::ImplDetails },
@@ -891,10 +3409,15 @@ fn format_fn(input: &Input, local_def_id: LocalDefId) -> Result<Vec<(SnippetKey,
 ///
 /// `keyword`, `name` are stored separately, to support formatting them as a
 /// forward declaration - e.g. `struct SomeStruct`.
-struct AdtCoreBindings {
+struct AdtCoreBindings<'tcx> {
     /// DefId of the ADT.
     def_id: DefId,
 
+    /// The type of the ADT.  For instantiations of generic ADTs (see
+    /// `Input::instantiations`) this includes the type arguments - e.g.
+    /// `Wrapper<i32>`.
+    self_ty: Ty<'tcx>,
+
     /// C++ tag - e.g. `struct`, `class`, `enum`, or `union`.  This isn't always
     /// a direct mapping from Rust (e.g. a Rust `enum` might end up being
     /// represented as an opaque C++ `struct`).
@@ -916,6 +3439,26 @@ struct AdtCoreBindings {
     /// - the destructor
     core: TokenStream,
 
+    /// The C++ underlying type (e.g. `std::uint8_t`) of a fieldless enum.  Such
+    /// enums are represented in C++ as an `enum class` (see
+    /// `format_enum_class`).  `None` for all other ADTs.
+    enum_class_underlying_type: Option<TokenStream>,
+
+    /// Whether the ADT needs drop glue (e.g. because it implements the `Drop`
+    /// trait, or because one of its fields does).  The C++ destructor of such
+    /// an ADT is non-trivial and is defined by `format_adt`.
+    needs_drop: bool,
+
+    /// Whether the C++ copy constructor and the copy assignment operator call
+    /// Rust thunks that invoke `Clone::clone` and `Clone::clone_from` (these
+    /// thunks are defined by `format_adt`).  This is `false` for `Copy` types,
+    /// which are trivially copyable in C++.
+    has_clone_thunks: bool,
+
+    /// Whether the C++ default constructor calls a Rust thunk that invokes
+    /// `Default::default` (the thunk is defined by `format_adt`).
+    has_default_thunk: bool,
+
     alignment_in_bytes: u64,
     size_in_bytes: u64,
 }
@@ -935,6 +3478,166 @@ fn is_directly_public(tcx: TyCtxt, def_id: DefId) -> bool {
     }
 }
 
+/// Returns the path under which other crates can refer to `def_id`:
+/// - the path where the item has been defined, if the item is directly public
+///   (i.e. if the item and all of its parent modules are `pub`),
+/// - otherwise, the shortest path where the item has been re-exported via `pub
+///   use` under its original name (e.g. when a "facade" `lib.rs` publishes
+///   items from a private module).
+///
+/// Returns `None` if the item is private or if it has only been re-exported
+/// under a different name (e.g. `pub use private::Foo as Bar;`).
+//
+// TODO(b/262052635): Support items that are only re-exported under a different
+// name.  This requires using the public name (rather than `tcx.item_name`) when
+// generating the C++ declaration of the item.
+fn public_path(tcx: TyCtxt, def_id: DefId) -> Option<FullyQualifiedName> {
+    if is_directly_public(tcx, def_id) {
+        return Some(FullyQualifiedName::from_def_path(tcx, def_id));
+    }
+    let name = tcx.opt_item_name(def_id)?;
+    public_paths(tcx, def_id.krate)
+        .get(&def_id)?
+        .iter()
+        .find(|path| path.name == Some(name))
+        .cloned()
+}
+
+/// Public paths of the items defined in a crate (see `public_paths`).
+type PublicPaths = HashMap<DefId, Vec<FullyQualifiedName>>;
+
+thread_local! {
+    /// Memoized results of `public_paths`.  `None` outside of the scope of a
+    /// `PublicPathsCacheScope` (the memoized results are only valid for a
+    /// single `TyCtxt`).
+    static PUBLIC_PATHS_CACHE: RefCell<Option<HashMap<CrateNum, Rc<PublicPaths>>>> =
+        RefCell::new(None);
+}
+
+/// Enables memoizing the results of `public_paths` until the scope is dropped.
+///
+/// `public_path` is called for every item and every type mention that isn't
+/// directly public (e.g. `String` and `Vec` are re-exported by `std`), and
+/// computing `public_paths` requires walking all the modules of a crate.
+struct PublicPathsCacheScope {
+    /// Whether this scope has enabled the cache (i.e. whether the cache was
+    /// disabled before this scope was created).
+    is_outermost: bool,
+}
+
+impl PublicPathsCacheScope {
+    fn new() -> Self {
+        let is_outermost = PUBLIC_PATHS_CACHE.with(|cache| {
+            let mut cache = cache.borrow_mut();
+            let is_outermost = cache.is_none();
+            cache.get_or_insert_with(HashMap::new);
+            is_outermost
+        });
+        Self { is_outermost }
+    }
+}
+
+impl Drop for PublicPathsCacheScope {
+    fn drop(&mut self) {
+        if self.is_outermost {
+            PUBLIC_PATHS_CACHE.with(|cache| *cache.borrow_mut() = None);
+        }
+    }
+}
+
+/// Returns all the public paths of the items defined in `krate`.  The paths of
+/// a given item are ordered from the shortest to the longest one.  Private items
+/// (i.e. items that can't be named by other crates) are not present in the
+/// returned map.
+///
+/// Items in a module that is reachable through multiple paths (e.g. through
+/// `pub mod m` and through `pub use m as m2`) are only reported at the first
+/// (i.e. shortest) path of the module.
+///
+/// The results are memoized within the scope of a `PublicPathsCacheScope`.
+fn public_paths(tcx: TyCtxt, krate: CrateNum) -> Rc<PublicPaths> {
+    let cached = PUBLIC_PATHS_CACHE
+        .with(|cache| cache.borrow().as_ref().and_then(|cache| cache.get(&krate).cloned()));
+    if let Some(cached) = cached {
+        return cached;
+    }
+    let result = Rc::new(compute_public_paths(tcx, krate));
+    PUBLIC_PATHS_CACHE.with(|cache| {
+        if let Some(cache) = cache.borrow_mut().as_mut() {
+            cache.insert(krate, result.clone());
+        }
+    });
+    result
+}
+
+/// Computes the results of `public_paths` (without memoization).
+fn compute_public_paths(tcx: TyCtxt, krate: CrateNum) -> PublicPaths {
+    let krate_name = tcx.crate_name(krate);
+    let mut result: HashMap<DefId, Vec<FullyQualifiedName>> = HashMap::new();
+    let mut visited_mods = HashSet::from([krate.as_def_id()]);
+    let mut mods_to_visit = VecDeque::from([(krate.as_def_id(), Vec::<Symbol>::new())]);
+    while let Some((mod_def_id, mod_path)) = mods_to_visit.pop_front() {
+        for (name, def_id) in public_module_children(tcx, mod_def_id) {
+            if def_id.krate != krate {
+                continue;
+            }
+            if tcx.def_kind(def_id) == DefKind::Mod {
+                if visited_mods.insert(def_id) {
+                    let child_mod_path = mod_path.iter().copied().chain(once(name)).collect();
+                    mods_to_visit.push_back((def_id, child_mod_path));
+                }
+                continue;
+            }
+            result.entry(def_id).or_default().push(FullyQualifiedName {
+                krate: krate_name,
+                mod_path: NamespaceQualifier::new(
+                    mod_path.iter().map(|s| Rc::<str>::from(s.as_str())),
+                ),
+                name: Some(name),
+            });
+        }
+    }
+    result
+}
+
+/// Returns the names and `DefId`s of the public children of the module
+/// identified by `mod_def_id`.  This includes both the items defined in the
+/// module and the items re-exported from the module via `pub use` (including
+/// glob re-exports like `pub use other_module::*`).
+fn public_module_children(tcx: TyCtxt, mod_def_id: DefId) -> Vec<(Symbol, DefId)> {
+    // For the current crate `module_children` only covers re-exports, so the items
+    // defined in the module are found via HIR.
+    let local_items = mod_def_id.as_local().into_iter().flat_map(|local_mod_def_id| {
+        let (module, _, _) = tcx.hir().get_module(local_mod_def_id);
+        module.item_ids.iter().filter_map(move |&item_id| {
+            let item = tcx.hir().item(item_id);
+            match item.kind {
+                ItemKind::Const(..)
+                | ItemKind::Static(..)
+                | ItemKind::Fn(..)
+                | ItemKind::Mod(_)
+                | ItemKind::TyAlias(..)
+                | ItemKind::Enum(..)
+                | ItemKind::Struct(..)
+                | ItemKind::Union(..)
+                | ItemKind::Trait(..) => Some((item.ident.name, item.owner_id.to_def_id())),
+                _ => None,
+            }
+        })
+    });
+    let reexports = tcx
+        .module_children(mod_def_id)
+        .iter()
+        .filter(|child| child.vis.is_public())
+        .filter_map(|child| Some((child.ident.name, child.res.opt_def_id()?)));
+    local_items
+        .filter(|&(_, def_id)| tcx.visibility(def_id).is_public())
+        .chain(reexports)
+        .filter(|&(name, _)| name != kw::Underscore)
+        .unique()
+        .collect()
+}
+
 fn get_layout<'tcx>(tcx: TyCtxt<'tcx>, ty: Ty<'tcx>) -> Result<Layout<'tcx>> {
     // TODO(b/259749095): Support non-empty set of generic parameters.
     let param_env = ty::ParamEnv::empty();
@@ -948,8 +3651,89 @@ fn get_layout<'tcx>(tcx: TyCtxt<'tcx>, ty: Ty<'tcx>) -> Result<Layout<'tcx>> {
     )
 }
 
+/// Formats the C++ tag of an ADT - see `AdtCoreBindings::keyword`.
+fn format_adt_keyword(adt_def: ty::AdtDef) -> TokenStream {
+    match adt_def.adt_kind() {
+        ty::AdtKind::Struct | ty::AdtKind::Enum => quote! { struct },
+        ty::AdtKind::Union => quote! { union },
+    }
+}
+
+/// Returns the integer type and the signedness of the scalar `tag` of an enum.
+fn get_tag_int(tag: Scalar) -> (Integer, bool) {
+    match tag.primitive() {
+        Primitive::Int(integer, signed) => (integer, signed),
+        other => panic!("Unexpected primitive type of an enum tag: {other:?}"),
+    }
+}
+
+/// Returns the integer type and the signedness of the fieldless enum with the
+/// given `layout` (i.e. the integer type that has the same size and signedness
+/// as the tag of the enum).
+fn get_fieldless_enum_int(layout: Layout<'_>) -> (Integer, bool) {
+    match layout.abi() {
+        Abi::Scalar(tag) => get_tag_int(tag),
+        other => panic!("Unexpected ABI of a fieldless enum: {other:?}"),
+    }
+}
+
+/// Returns true if the ADT identified by `adt_def_id` has public items in an
+/// inherent `impl` or any items in an `impl` of a public trait from the same
+/// crate (i.e. items that `format_adt` would expose as members of the C++
+/// class - see also `get_trait_impls_of_adt`).
+///
+/// This only depends on the crate that defines the ADT, so that all crates agree
+/// whether a fieldless enum is represented as an `enum class`.
+fn has_public_impl_items(tcx: TyCtxt, adt_def_id: DefId) -> bool {
+    let has_inherent_items = tcx
+        .inherent_impls(adt_def_id)
+        .iter()
+        .flat_map(|&impl_id| tcx.associated_items(impl_id).in_definition_order())
+        .any(|item| tcx.visibility(item.def_id).is_public());
+    let has_trait_impl_items =
+        tcx.trait_impls_in_crate(adt_def_id.krate).iter().any(|&impl_id| {
+            let trait_def_id = tcx.trait_id_of_impl(impl_id).expect("Expecting a trait `impl`");
+            let self_ty = tcx.type_of(impl_id).subst_identity();
+            trait_def_id.krate == adt_def_id.krate
+                && public_path(tcx, trait_def_id).is_some()
+                && self_ty.ty_adt_def().map(|adt| adt.did()) == Some(adt_def_id)
+                && !tcx.associated_item_def_ids(impl_id).is_empty()
+        });
+    has_inherent_items || has_trait_impl_items
+}
+
+/// Formats the discriminant `value` of an enum variant as a C++ literal.  The
+/// tag of the enum has the given `integer` type and signedness.
+fn format_discriminant(value: u128, integer: Integer, signed: bool) -> Literal {
+    if signed {
+        Literal::i128_unsuffixed(integer.size().sign_extend(value) as i128)
+    } else {
+        Literal::u128_unsuffixed(value)
+    }
+}
+
+/// Formats the C++ fixed-width integer type (e.g. `std::uint8_t`) that has the
+/// same size and signedness as `integer`.  The caller is responsible for
+/// adding `CcInclude::cstdint()` to the prerequisites.
+fn format_int_for_cc(integer: Integer, signed: bool) -> Result<TokenStream> {
+    Ok(match (integer, signed) {
+        (Integer::I8, true) => quote! { std::int8_t },
+        (Integer::I16, true) => quote! { std::int16_t },
+        (Integer::I32, true) => quote! { std::int32_t },
+        (Integer::I64, true) => quote! { std::int64_t },
+        (Integer::I8, false) => quote! { std::uint8_t },
+        (Integer::I16, false) => quote! { std::uint16_t },
+        (Integer::I32, false) => quote! { std::uint32_t },
+        (Integer::I64, false) => quote! { std::uint64_t },
+        (Integer::I128, _) => {
+            bail!("C++ doesn't have a standard equivalent of 128-bit integers (b/254094650)")
+        }
+    })
+}
+
 /// Formats the core of an algebraic data type (an ADT - a struct, an enum, or a
-/// union) represented by `def_id`.
+/// union) represented by `ty`.  `ty` may be either a non-generic ADT, or an
+/// instantiation of a generic ADT (e.g. `Wrapper<i32>`).
 ///
 /// The "core" means things that are necessary for a succesful binding (e.g.
 /// inability to generate a correct C++ destructor means that the ADT cannot
@@ -963,28 +3747,21 @@ fn get_layout<'tcx>(tcx: TyCtxt<'tcx>, ty: Ty<'tcx>) -> Result<Layout<'tcx>> {
 ///
 /// `format_adt_core` is used both to 1) format bindings for the core of an ADT,
 /// and 2) check if formatting would have succeeded (e.g. when called from
-/// `format_ty`).  The 2nd case is needed for ADTs defined in any crate - this
-/// is why the `def_id` parameter is a DefId rather than LocalDefId.
+/// `format_ty`).  The 2nd case is needed for ADTs defined in any crate.
 //
 // TODO(b/259724276): This function's results should be memoized.
-fn format_adt_core(tcx: TyCtxt, def_id: DefId) -> Result<AdtCoreBindings> {
-    let ty = tcx.type_of(def_id).subst_identity();
-    assert!(ty.is_adt());
-    assert!(is_directly_public(tcx, def_id), "Caller should verify");
+fn format_adt_core<'tcx>(tcx: TyCtxt<'tcx>, ty: Ty<'tcx>) -> Result<AdtCoreBindings<'tcx>> {
+    let adt_def = ty.ty_adt_def().expect("`ty` needs to be an ADT");
+    let def_id = adt_def.did();
+    assert!(public_path(tcx, def_id).is_some(), "Caller should verify");
 
-    // TODO(b/259749095): Support non-empty set of generic parameters.
+    // `ty` doesn't refer to any generic parameters (generic ADTs are only supported via
+    // explicit instantiations - see `Input::instantiations`).
     let param_env = ty::ParamEnv::empty();
 
-    if ty.needs_drop(tcx, param_env) {
-        // TODO(b/258251148): Support custom `Drop` impls.
-        bail!("`Drop` trait and \"drop glue\" are not supported yet (b/258251148)");
-    }
+    let needs_drop = ty.needs_drop(tcx, param_env);
 
-    let adt_def = ty.ty_adt_def().expect("`def_id` needs to identify an ADT");
-    let keyword = match adt_def.adt_kind() {
-        ty::AdtKind::Struct | ty::AdtKind::Enum => quote! { struct },
-        ty::AdtKind::Union => quote! { union },
-    };
+    let keyword = format_adt_keyword(adt_def);
 
     let item_name = tcx.item_name(def_id);
     let rs_name = format_ty_for_rs(tcx, ty)?;
@@ -1002,799 +3779,7102 @@ fn format_adt_core(tcx: TyCtxt, def_id: DefId) -> Result<AdtCoreBindings> {
     let size_in_bytes = layout.size().bytes();
     ensure!(size_in_bytes != 0, "Zero-sized types (ZSTs) are not supported (b/258259459)");
 
+    // Fieldless enums (e.g. `enum Color { Red, Green = 123 }`) are represented in C++ as an
+    // `enum class` (see `format_enum_class`).
+    // (`enum class` can't have a destructor, so this excludes enums that implement `Drop`.
+    // `enum class` also can't have member functions, so this excludes enums with methods or
+    // associated constants - such enums are represented as a C++ struct, just like enums with
+    // fields.)
+    let is_fieldless_enum =
+        adt_def.is_enum() && adt_def.variants().iter().all(|variant| variant.fields.is_empty());
+    if is_fieldless_enum && !needs_drop && !has_public_impl_items(tcx, def_id) {
+        let (integer, signed) = get_fieldless_enum_int(layout);
+        let underlying_type = format_int_for_cc(integer, signed)?;
+        for variant in adt_def.variants() {
+            format_cc_ident(variant.name.as_str()).with_context(|| {
+                format!("Error formatting the name of the enum variant `{}`", variant.name)
+            })?;
+        }
+        return Ok(AdtCoreBindings {
+            def_id,
+            self_ty: ty,
+            keyword: quote! { enum class },
+            cc_name,
+            rs_name,
+            core: quote! {},
+            enum_class_underlying_type: Some(underlying_type),
+            needs_drop,
+            has_clone_thunks: false,
+            has_default_thunk: false,
+            alignment_in_bytes,
+            size_in_bytes,
+        });
+    }
+
+    let (move_ctor, dtor) = if !needs_drop {
+        // The generated bindings have to follow Rust move semantics:
+        // * All Rust types are memcpy-movable (e.g. <internal link>/constructors.html says
+        //   that "Every type must be ready for it to be blindly memcopied to somewhere else
+        //   in memory")
+        // * The only valid operation on a moved-from non-`Copy` Rust struct is to assign to
+        //   it.
+        //
+        // The generated C++ bindings match the required semantics because they:
+        // * Generate trivial` C++ move constructor and move assignment operator. Per
+        //   <internal link>/cpp/language/move_constructor#Trivial_move_constructor: "A trivial move
+        //   constructor is a constructor that performs the same action as the trivial copy
+        //   constructor, that is, makes a copy of the object representation as if by
+        //   std::memmove."
+        // * Generate trivial C++ destructor. (Types that implement `Drop` trait or require
+        //   "drop glue" are handled in the `else` branch below). Per
+        //   <internal link>/cpp/language/destructor#Trivial_destructor: "A trivial destructor is a
+        //   destructor that performs no action."
+        //
+        // In particular, note that the following C++ code and Rust code are exactly equivalent
+        // (except that in Rust, reuse of `y` is forbidden at compile time, whereas in C++,
+        // it's only prohibited by convention):
+        // * C++, assumming trivial move constructor and trivial destructor:
+        //   `auto x = std::move(y);`
+        // * Rust, assumming non-`Copy`, no custom `Drop` or drop glue:
+        //   `let x = y;`
+        (quote! { #cc_name(#cc_name&&) = default; }, quote! { ~#cc_name() = default; })
+    } else {
+        // The C++ destructor calls a Rust thunk that runs `drop_in_place` (see `format_adt`).
+        //
+        // This means that a moved-from object will also be dropped, so the move constructor
+        // can't just `memcpy` the object (this would drop the same object twice).  Instead,
+        // if the ADT implements `Default`, then the move constructor calls a Rust thunk that
+        // replaces the moved-from object with `Default::default()` (using `std::mem::take`).
+        // Otherwise, the move constructor is deleted.
+        let move_ctor = if is_cc_movable(tcx, ty) {
+            quote! { #cc_name(#cc_name&&); }
+        } else {
+            quote! { #cc_name(#cc_name&&) = delete; }
+        };
+        (move_ctor, quote! { ~#cc_name(); })
+    };
+
+    // User-provided C++ constructors default-initialize all the members of the class.  This
+    // doesn't work for members of ADT types (which may have a deleted default constructor),
+    // so only the trivial (defaulted) special member functions are provided for such ADTs.
+    // (Fields of ADTs that need drop glue are always replaced with a blob of bytes - see
+    // `format_adt`.)
+    //
+    // TODO(b/258249980): Support ADTs with fields of ADT types (e.g. by default-constructing
+    // such fields when the field type implements `Default`).
+    let substs = match ty.kind() {
+        ty::TyKind::Adt(_, substs) => *substs,
+        _ => unreachable!("`ty_adt_def` above should imply `TyKind::Adt`"),
+    };
+    let has_adt_members =
+        !needs_drop && adt_def.all_fields().any(|field| field.ty(tcx, substs).is_adt());
+
+    let is_copy = ty.is_copy_modulo_regions(tcx, param_env);
+    let implements_clone = {
+        let clone_trait_id = tcx.lang_items().clone_trait().expect("`Clone` is a lang item");
+        does_type_implement_trait(tcx, ty, clone_trait_id)
+    };
+    let implements_default = {
+        let default_trait_id = tcx
+            .get_diagnostic_item(rustc_span::symbol::sym::Default)
+            .expect("`Default` trait should always be present");
+        does_type_implement_trait(tcx, ty, default_trait_id)
+    };
+    let has_clone_thunks = implements_clone && !is_copy && !has_adt_members;
+    let has_default_thunk = implements_default && !has_adt_members;
+
+    // The default C++ constructor calls `Default::default` (via a Rust thunk).
+    let default_ctor = if has_default_thunk {
+        quote! { #cc_name(); }
+    } else {
+        quote! { #cc_name() = delete; }
+    };
+
+    // `Copy` types are trivially copyable in C++ - copying them is equivalent to `memcpy`
+    // (and therefore to what Rust does for `Copy` types).  Other types that implement
+    // `Clone` call `Clone::clone` from the copy constructor and `Clone::clone_from` from the
+    // copy assignment operator (via Rust thunks).
+    //
+    // Move operations of such types that would otherwise be `delete`d are not declared at all.
+    // They are not implicitly declared either (because the copy operations are user-declared),
+    // so rvalues fall back to the copy operations - e.g. `x = std::move(y)`, `std::swap`, and
+    // `std::sort` work with types that implement `Clone`.
+    let move_ctor = if has_clone_thunks && needs_drop && !is_cc_movable(tcx, ty) {
+        quote! {}
+    } else {
+        move_ctor
+    };
+    let (copy_ctor, copy_assignment, move_assignment) = if is_copy {
+        (
+            quote! { #cc_name(const #cc_name&) = default; },
+            quote! { #cc_name& operator=(const #cc_name&) = default; },
+            quote! { #cc_name& operator=(#cc_name&&) = default; },
+        )
+    } else if has_clone_thunks {
+        (
+            quote! { #cc_name(const #cc_name&); },
+            quote! { #cc_name& operator=(const #cc_name&); },
+            quote! {},
+        )
+    } else {
+        (
+            quote! { #cc_name(const #cc_name&) = delete; },
+            quote! { #cc_name& operator=(const #cc_name&) = delete; },
+            quote! { #cc_name& operator=(#cc_name&&) = delete; },
+        )
+    };
+
     let core = quote! {
         public:
-            // TODO(b/258249980): If the wrapped type implements the `Default` trait, then we
-            // should call its `impl` from the default C++ constructor (instead of `delete`ing
-            // the default C++ constructor).
-            #cc_name() = delete;
-
-            // TODO(b/258249993): Provide `default` copy constructor and assignment operator if
-            // the wrapped type is `Copy` on Rust side.
-            // TODO(b/259741191): If the wrapped type implements the `Clone` trait, then we should
-            // *consider* calling `clone` from the copy constructor and `clone_from` from the copy
-            // assignment operator.
-            #cc_name(const #cc_name&) = delete;
-
-            // The generated bindings have to follow Rust move semantics:
-            // * All Rust types are memcpy-movable (e.g. <internal link>/constructors.html says
-            //   that "Every type must be ready for it to be blindly memcopied to somewhere else
-            //   in memory")
-            // * The only valid operation on a moved-from non-`Copy` Rust struct is to assign to
-            //   it.
-            //
-            // The generated C++ bindings match the required semantics because they:
-            // * Generate trivial` C++ move constructor and move assignment operator. Per
-            //   <internal link>/cpp/language/move_constructor#Trivial_move_constructor: "A trivial move
-            //   constructor is a constructor that performs the same action as the trivial copy
-            //   constructor, that is, makes a copy of the object representation as if by
-            //   std::memmove."
-            // * Generate trivial C++ destructor. (Types that implement `Drop` trait or require
-            //   "drop glue" are not *yet* supported - this might eventually change as part of the
-            //   work tracked under b/258251148). Per
-            //   <internal link>/cpp/language/destructor#Trivial_destructor: "A trivial destructor is a
-            //   destructor that performs no action."
-            //
-            // In particular, note that the following C++ code and Rust code are exactly equivalent
-            // (except that in Rust, reuse of `y` is forbidden at compile time, whereas in C++,
-            // it's only prohibited by convention):
-            // * C++, assumming trivial move constructor and trivial destructor:
-            //   `auto x = std::move(y);`
-            // * Rust, assumming non-`Copy`, no custom `Drop` or drop glue:
-            //   `let x = y;`
-            //
-            // TODO(b/258251148): If the ADT provides a custom `Drop` impls or requires drop glue,
-            // then extra care should be taken to ensure the C++ destructor can handle the
-            // moved-from object in a way that meets Rust move semantics.  For example, the
-            // generated C++ move constructor might need to assign `Default::default()` to the
-            // moved-from object.
-            #cc_name(#cc_name&&) = default;
-
-            // TODO(b/258235219): Providing assignment operators enables mutation which
-            // may negatively interact with support for references.  Therefore until we
-            // have more confidence in our reference-handling-plans, we are deleting the
-            // assignment operators.
+            #default_ctor
+            #copy_ctor
+            #move_ctor
+
+            // TODO(b/258235219): Providing a move assignment operator for non-`Copy` types
+            // enables mutation which may negatively interact with support for references.
+            // Therefore until we have more confidence in our reference-handling-plans, we
+            // are deleting the move assignment operator of such types.
             //
             // (Move assignment operator has another set of concerns and constraints - see the
             // comment for the move constructor above).
-            #cc_name& operator=(const #cc_name&) = delete;
-            #cc_name& operator=(#cc_name&&) = delete;
+            #copy_assignment
+            #move_assignment
 
-            // TODO(b/258251148): Support custom `Drop` impls and drop glue.
-            ~#cc_name() = default;
+            #dtor
     };
     Ok(AdtCoreBindings {
         def_id,
+        self_ty: ty,
         keyword,
         cc_name,
         rs_name,
         core,
+        enum_class_underlying_type: None,
+        needs_drop,
+        has_clone_thunks,
+        has_default_thunk,
         alignment_in_bytes,
         size_in_bytes,
     })
 }
 
-/// Formats an algebraic data type (an ADT - a struct, an enum, or a union)
-/// represented by `core`.  This function is infallible - after
-/// `format_adt_core` returns success we have committed to emitting C++ bindings
-/// for the ADT.
-///
-/// Returns multiple snippets to support independent reordering of 1) the struct
-/// definition (including declarations of methods - aka member functions), and
-/// 2) an arbitrary number of method definitions.  For motivation see the
-/// `non_contiguous_method_decls_and_defs` module in `cc_bindings_from_rs/test/
-/// impls/impls.rs`).  Secondary motivation is to keep implementation
-/// details out of the way (to improve readability of the main apis).
-fn format_adt(input: &Input, core: &AdtCoreBindings) -> Vec<(SnippetKey, MixedSnippet)> {
-    let tcx = input.tcx;
-
-    // TODO(b/259749095): Support non-empty set of generic parameters.
-    let param_env = ty::ParamEnv::empty();
-    let substs_ref = ty::List::empty().as_substs();
-
-    // `format_adt` should only be called for local ADTs.
-    let local_def_id = core.def_id.expect_local();
+/// Returns the generic arguments of the `impl` identified by `impl_id`
+/// that make the `impl` apply to the (non-generic) `ty` - e.g. returns `[i32]`
+/// for `impl<T> Wrapper<T>` and `Wrapper<i32>`.  Returns `None` if the `impl`
+/// doesn't apply to `ty` - e.g. for `impl Wrapper<f32>` and `Wrapper<i32>`.
+fn match_impl_substs<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    impl_id: DefId,
+    ty: Ty<'tcx>,
+) -> Option<ty::SubstsRef<'tcx>> {
+    let impl_self_ty = tcx.type_of(impl_id).subst_identity();
+    let (impl_self_substs, substs) = match (impl_self_ty.kind(), ty.kind()) {
+        (ty::TyKind::Adt(_, impl_self_substs), ty::TyKind::Adt(_, substs)) => {
+            (impl_self_substs, substs)
+        }
+        _ => panic!("`impl`s of ADTs should have an ADT as their `Self` type"),
+    };
+    let mut type_args_of_impl_params = HashMap::new();
+    for (impl_arg, arg) in impl_self_substs.iter().zip(substs.iter()) {
+        match (impl_arg.unpack(), arg.unpack()) {
+            (ty::GenericArgKind::Lifetime(_), _) => (),
+            (ty::GenericArgKind::Type(impl_arg_ty), ty::GenericArgKind::Type(arg_ty)) => {
+                match impl_arg_ty.kind() {
+                    ty::TyKind::Param(param) => {
+                        let prev = type_args_of_impl_params.entry(param.index).or_insert(arg);
+                        if *prev != arg {
+                            return None; // E.g. `impl<T> Pair<T, T>` vs `Pair<i32, f32>`.
+                        }
+                    }
+                    // TODO(b/259749095): Support `impl`s with nested generic parameters (e.g.
+                    // `impl<T> Wrapper<Vec<T>>`).
+                    _ if tcx.erase_regions(impl_arg_ty) != arg_ty => return None,
+                    _ => (),
+                }
+            }
+            _ => return None,
+        }
+    }
+    let impl_substs = ty::InternalSubsts::for_item(tcx, impl_id, |param, _| match param.kind {
+        ty::GenericParamDefKind::Lifetime => tcx.lifetimes.re_erased.into(),
+        _ => *type_args_of_impl_params
+            .get(&param.index)
+            .expect("All generic parameters of an `impl` should appear in its `Self` type"),
+    });
 
-    struct FieldTypeInfo {
-        size: u64,
-        cc_type: CcSnippet,
+    // E.g. `impl<T: Copy> Wrapper<T>` doesn't apply to `Wrapper<String>`.
+    match find_unsatisfied_predicate(tcx, impl_id, impl_substs) {
+        None => Some(impl_substs),
+        Some(_) => None,
     }
-    struct Field {
-        type_info: Result<FieldTypeInfo>,
-        cc_name: TokenStream,
-        rs_name: TokenStream,
-        is_public: bool,
-        index: usize,
-        offset: u64,
-        offset_of_next_field: u64,
+}
+
+/// Formats a method from an `impl` of a generic ADT (e.g. from
+/// `impl<T> Wrapper<T>`) for an explicit instantiation of the ADT (e.g. for
+/// `Wrapper<i32>`).  `impl_substs` are the generic arguments of the `impl`
+/// (see `match_impl_substs`).
+fn format_method_of_instantiation<'tcx>(
+    input: &Input<'tcx>,
+    local_def_id: LocalDefId,
+    impl_substs: ty::SubstsRef<'tcx>,
+) -> Result<Vec<(SnippetKey, MixedSnippet)>> {
+    let tcx = input.tcx;
+    let def_id = local_def_id.to_def_id(); // Convert LocalDefId to DefId.
+    ensure!(
+        tcx.generics_of(def_id)
+            .params
+            .iter()
+            .all(|param| matches!(param.kind, ty::GenericParamDefKind::Lifetime)),
+        "Generic methods of generic types are not supported yet (b/259749023)"
+    );
+    if input.exclude_unsafe_fns && is_unsafe_fn(tcx, def_id) {
+        return Ok(vec![]);
     }
-    let ty = tcx.type_of(core.def_id).subst_identity();
-    let layout = tcx
-        .layout_of(param_env.and(ty))
-        .expect("Layout should be already verified by `format_adt_core`")
-        .layout;
-    let fields: Vec<Field> = if ty.is_enum() || ty.is_union() {
-        // Note that `#[repr(Rust)]` unions don't guarantee that all their fields
-        // have offset 0.
-        vec![Field {
-            type_info: Err(anyhow!(
-                "No support for bindings of individual fields of \
-                                    `union` (b/272801632) or `enum`"
-            )),
-            cc_name: quote! { __opaque_blob_of_bytes },
-            rs_name: quote! { __opaque_blob_of_bytes },
-            is_public: false,
-            index: 0,
-            offset: 0,
-            offset_of_next_field: core.size_in_bytes,
-        }]
-    } else {
-        let mut fields = ty
-            .ty_adt_def()
-            .expect("`core.def_id` needs to identify an ADT")
-            .all_fields()
-            .sorted_by_key(|f| tcx.def_span(f.did))
-            .enumerate()
-            .map(|(index, field_def)| {
-                let field_ty = field_def.ty(tcx, substs_ref);
-                let size = get_layout(tcx, field_ty).map(|layout| layout.size().bytes());
-                let type_info = size.and_then(|size| {
-                    Ok(FieldTypeInfo { size, cc_type: format_ty_for_cc(input, field_ty)? })
-                });
-                let name = field_def.ident(tcx);
-                let cc_name = format_cc_ident(name.as_str()).unwrap_or_else(|_err|
-                    format_ident!("__field{index}").into_token_stream()
-                );
-                let rs_name = {
-                    let name_starts_with_digit = name
-                        .as_str()
-                        .chars()
-                        .next()
-                        .expect("Empty names are unexpected (here and in general)")
-                        .is_ascii_digit();
-                    if name_starts_with_digit {
-                        let index = Literal::usize_unsuffixed(index);
-                        quote! { #index }
-                    } else {
-                        let name = make_rs_ident(name.as_str());
-                        quote! { #name }
-                    }
-                };
-                let is_public = field_def.vis == ty::Visibility::Public;
+    let substs = ty::InternalSubsts::for_item(tcx, def_id, |param, _| match param.kind {
+        ty::GenericParamDefKind::Lifetime if param.index as usize >= impl_substs.len() => {
+            tcx.lifetimes.re_erased.into()
+        }
+        _ => impl_substs[param.index as usize],
+    });
+    format_fn_instance(input, local_def_id, substs)
+}
 
-                // `offset` and `offset_of_next_field` will be fixed by FieldsShape::Arbitrary
-                // branch below.
-                let offset = 0;
-                let offset_of_next_field = 0;
+/// Returns the `impl`s of local, public traits (e.g. `impl SomeTrait for
+/// SomeStruct`) that have the ADT identified by `adt_def_id` as their `Self`
+/// type.  Methods and associated constants from these `impl`s are exposed in
+/// C++ by `format_trait_impl_items`.
+fn get_trait_impls_of_adt(tcx: TyCtxt, adt_def_id: DefId) -> Vec<LocalDefId> {
+    tcx.all_local_trait_impls(())
+        .iter()
+        .filter(|(trait_def_id, _)| {
+            trait_def_id.is_local() && public_path(tcx, **trait_def_id).is_some()
+        })
+        .flat_map(|(_, impl_ids)| impl_ids.iter().copied())
+        .filter(|impl_id| {
+            let self_ty = tcx.type_of(impl_id.to_def_id()).subst_identity();
+            self_ty.ty_adt_def().map(|adt| adt.did()) == Some(adt_def_id)
+        })
+        .sorted_by_key(|impl_id| tcx.def_span(*impl_id))
+        .collect_vec()
+}
 
-                Field {
-                    type_info,
-                    cc_name,
-                    rs_name,
-                    is_public,
-                    index,
-                    offset,
-                    offset_of_next_field,
-                }
-            })
-            .collect_vec();
-        match layout.fields() {
-            FieldsShape::Arbitrary { offsets, .. } => {
-                for (index, offset) in offsets.iter().enumerate() {
-                    // Documentation of `FieldsShape::Arbitrary says that the offsets are "ordered
-                    // to match the source definition order".  We can coorelate them with elements
-                    // of the `fields` vector because we've explicitly `sorted_by_key` using
-                    // `def_span`.
-                    fields[index].offset = offset.bytes();
-                }
-                // Deterministic outcome of `fields.sort_by_key` depends on each field
-                // having a unique offset (this assumption might be broken in the future by
-                // ZSTs).
-                assert!(fields.iter().map(|f| f.offset).all_unique());
-                fields.sort_by_key(|field| field.offset);
-                let next_offsets = fields
-                    .iter()
-                    .map(|Field { offset, .. }| *offset)
-                    .skip(1)
-                    .chain(once(core.size_in_bytes))
-                    .collect_vec();
-                for (field, next_offset) in fields.iter_mut().zip(next_offsets) {
-                    field.offset_of_next_field = next_offset;
-                }
-                fields
-            }
-            unexpected => panic!("Unexpected FieldsShape: {unexpected:?}"),
-        }
+/// Returns true if the associated item identified by `def_id` comes from an
+/// `impl` of a trait (see `get_trait_impls_of_adt`) and its name clashes with
+/// another member of the C++ class of the `Self` type - i.e. with the class
+/// name, with a field, with a public item of an inherent `impl`, or with an
+/// item of an `impl` of another trait.
+///
+/// Such items are exposed as free functions (or constants) in a namespace
+/// named after the trait (e.g. `SomeTrait::some_method(some_struct)`) rather
+/// than as members of the C++ class.
+fn is_trait_impl_item_name_clash(tcx: TyCtxt, def_id: DefId) -> bool {
+    let impl_id = match tcx.impl_of_method(def_id) {
+        Some(impl_id) if tcx.trait_id_of_impl(impl_id).is_some() => impl_id,
+        _ => return false,
     };
-    let adt_cc_name = &core.cc_name;
-    let adt_rs_name = &core.rs_name;
-    let cc_field_assertions: TokenStream = fields
+    let adt_def_id = match tcx.type_of(impl_id).subst_identity().ty_adt_def() {
+        Some(adt) => adt.did(),
+        None => return false,
+    };
+    let name = tcx.item_name(def_id);
+    let inherent_items = tcx
+        .inherent_impls(adt_def_id)
         .iter()
-        .map(|Field { cc_name, offset, .. }| {
-            let offset = Literal::u64_unsuffixed(*offset);
-            quote! { static_assert(#offset == offsetof(#adt_cc_name, #cc_name)); }
+        .flat_map(|&impl_id| tcx.associated_items(impl_id).in_definition_order())
+        .filter(|item| tcx.visibility(item.def_id).is_public());
+    let other_trait_impl_items = get_trait_impls_of_adt(tcx, adt_def_id)
+        .into_iter()
+        .filter(|other_impl_id| other_impl_id.to_def_id() != impl_id)
+        .flat_map(|other_impl_id| tcx.associated_items(other_impl_id).in_definition_order());
+    name == tcx.item_name(adt_def_id)
+        || tcx.adt_def(adt_def_id).all_fields().any(|field| field.name == name)
+        || inherent_items.chain(other_trait_impl_items).any(|item| item.name == name)
+}
+
+/// Formats the methods and the associated constants from the `impl`s of
+/// traits for the ADT represented by `core` (see `get_trait_impls_of_adt`).
+///
+/// Just like the items of inherent `impl`s, most of the items are formatted as
+/// members of the C++ class.  The only difference is that the items with
+/// clashing names (see `is_trait_impl_item_name_clash`) are wrapped in a
+/// namespace named after the trait - the `MainApi` snippets of such items
+/// shouldn't be moved into the C++ class by the caller.
+fn format_trait_impl_items<'tcx>(
+    input: &Input<'tcx>,
+    core: &AdtCoreBindings<'tcx>,
+    adt_cc_type: &TokenStream,
+) -> Vec<(SnippetKey, MixedSnippet)> {
+    let tcx = input.tcx;
+    get_trait_impls_of_adt(tcx, core.def_id)
+        .into_iter()
+        .filter_map(|impl_id| {
+            let impl_substs = match_impl_substs(tcx, impl_id.to_def_id(), core.self_ty)?;
+            Some((impl_id, impl_substs))
         })
-        .collect();
-    let rs_field_assertions: TokenStream = fields
-        .iter()
-        .filter(|Field { is_public, .. }| *is_public)
-        .map(|Field { rs_name, offset, .. }| {
-            let expected_offset = Literal::u64_unsuffixed(*offset);
-            let actual_offset = quote! { memoffset::offset_of!(#adt_rs_name, #rs_name) };
-            quote! { const _: () = assert!(#actual_offset == #expected_offset); }
+        .flat_map(|(impl_id, impl_substs)| {
+            tcx.associated_items(impl_id)
+                .in_definition_order()
+                .map(move |item| (impl_id, impl_substs, item))
         })
-        .collect();
+        .flat_map(|(impl_id, impl_substs, item)| {
+            format_trait_impl_item(input, core, adt_cc_type, impl_id, impl_substs, item)
+                .unwrap_or_else(|err| {
+                    vec![format_unsupported_def(tcx, item.def_id.expect_local(), err)]
+                })
+        })
+        .collect_vec()
+}
 
-    let (impl_item_main_apis, impl_item_other_snippets) = tcx
-        .inherent_impls(core.def_id)
-        .iter()
-        .map(|impl_id| tcx.hir().expect_item(impl_id.expect_local()))
-        .flat_map(|item| match &item.kind {
-            ItemKind::Impl(impl_) => impl_.items,
-            other => panic!("Unexpected `ItemKind` from `inherent_impls`: {other:?}"),
+/// Formats a single item of the trait `impl` identified by `impl_id` (see
+/// `format_trait_impl_items`).
+fn format_trait_impl_item<'tcx>(
+    input: &Input<'tcx>,
+    core: &AdtCoreBindings<'tcx>,
+    adt_cc_type: &TokenStream,
+    impl_id: LocalDefId,
+    impl_substs: ty::SubstsRef<'tcx>,
+    item: &ty::AssocItem,
+) -> Result<Vec<(SnippetKey, MixedSnippet)>> {
+    let tcx = input.tcx;
+    let local_def_id = item.def_id.expect_local();
+    let adt_def_id = core.def_id.expect_local();
+    let trait_def_id =
+        tcx.trait_id_of_impl(impl_id.to_def_id()).expect("`impl_id` should be a trait `impl`");
+    // TODO(b/259749095): Support generic traits.
+    ensure!(tcx.generics_of(trait_def_id).count() == 1, "Generic traits are not supported yet");
+
+    let is_name_clash = is_trait_impl_item_name_clash(tcx, item.def_id);
+    let snippets = match item.kind {
+        ty::AssocKind::Fn if impl_substs.is_empty() => format_fn(input, local_def_id)?,
+        ty::AssocKind::Fn => format_method_of_instantiation(input, local_def_id, impl_substs)?,
+        ty::AssocKind::Const if !impl_substs.is_empty() => {
+            bail!("Associated constants of generic types are not supported yet")
+        }
+        ty::AssocKind::Const if is_name_clash => format_const(input, local_def_id)?,
+        ty::AssocKind::Const => format_assoc_const(input, local_def_id, adt_def_id, adt_cc_type)?,
+        ty::AssocKind::Type => bail!("Associated types of trait `impl`s are not supported yet"),
+    };
+    if !is_name_clash {
+        return Ok(snippets);
+    }
+
+    // A C++ namespace can't have the same name as the C++ class that `format_trait` generates
+    // for the trait.
+    let trait_name = FullyQualifiedName::new(tcx, trait_def_id);
+    ensure!(
+        trait_name.mod_path != FullyQualifiedName::new(tcx, core.def_id).mod_path
+            || format_trait_core(input, trait_def_id).is_err(),
+        "The name of `{}` clashes with another member of `{}`, but it can't be exposed in \
+         the `{}` namespace, because the namespace would clash with the bindings of the trait",
+        item.name,
+        tcx.item_name(core.def_id),
+        tcx.item_name(trait_def_id),
+    );
+
+    // Traits from different modules may have the same name (e.g. `a::Named` and `b::Named`) -
+    // exposing their items with the same name in the same C++ namespace would redefine them.
+    let has_namespace_clash = get_trait_impls_of_adt(tcx, core.def_id)
+        .into_iter()
+        .filter(|other_impl_id| {
+            tcx.trait_id_of_impl(other_impl_id.to_def_id()).map_or(false, |other_trait_def_id| {
+                other_trait_def_id != trait_def_id
+                    && tcx.item_name(other_trait_def_id) == tcx.item_name(trait_def_id)
+            })
         })
-        .flat_map(|impl_item_ref| {
-            let def_id = impl_item_ref.id.owner_id.def_id;
-            if !tcx.effective_visibilities(()).is_directly_public(def_id) {
-                return vec![];
-            }
-            let result = match impl_item_ref.kind {
-                AssocItemKind::Fn { .. } => format_fn(input, def_id),
-                other => Err(anyhow!("Unsupported `impl` item kind: {other:?}")),
+        .flat_map(|other_impl_id| tcx.associated_items(other_impl_id).in_definition_order())
+        .any(|other_item| other_item.name == item.name);
+    ensure!(
+        !has_namespace_clash,
+        "The name of `{}` clashes with another member of `{}`, but it can't be exposed in the \
+         `{}` namespace, because another trait with the same name also has an item named `{}`",
+        item.name,
+        tcx.item_name(core.def_id),
+        tcx.item_name(trait_def_id),
+        item.name,
+    );
+
+    let namespace = format_cc_ident(tcx.item_name(trait_def_id).as_str())
+        .context("Error formatting trait name")?;
+    Ok(snippets
+        .into_iter()
+        .map(|(key, MixedSnippet { mut cc, rs })| {
+            let tokens = cc.tokens;
+            cc.tokens = quote! {
+                __NEWLINE__
+                namespace #namespace {
+                    #tokens
+                }
+                __NEWLINE__
             };
-            result.unwrap_or_else(|err| vec![format_unsupported_def(tcx, def_id, err)])
+            // The free functions and constants are emitted after the C++ class, so that they
+            // can be found next to its definition.
+            if key.kind == SnippetKind::MainApi {
+                cc.prereqs.defs.insert(adt_def_id);
+            }
+            (key, MixedSnippet { cc, rs })
         })
-        .partition::<Vec<_>, _>(|(SnippetKey { kind, .. }, _)| *kind == SnippetKind::MainApi);
+        .collect())
+}
 
-    let alignment = Literal::u64_unsuffixed(core.alignment_in_bytes);
-    let size = Literal::u64_unsuffixed(core.size_in_bytes);
-    let main_api = {
-        let cc_packed_attribute = {
-            let has_packed_attribute = tcx
-                .get_attrs(core.def_id, rustc_span::symbol::sym::repr)
-                .flat_map(|attr| rustc_attr::parse_repr_attr(tcx.sess(), attr))
-                .any(|repr| matches!(repr, rustc_attr::ReprPacked { .. }));
-            if has_packed_attribute {
-                quote! { __attribute__((packed)) }
-            } else {
-                quote! {}
-            }
-        };
+/// Formats a fieldless enum (e.g. `enum Color { Red, Green = 123 }`)
+/// represented by `core` as a C++ `enum class` with the same underlying type
+/// and the same discriminant values.
+///
+/// Note that (unlike a Rust enum) a C++ `enum class` may hold any value of its
+/// underlying type.  Passing a value that doesn't correspond to any of the
+/// enumerators to Rust is Undefined Behavior - the generated `enum class` is
+/// annotated with a comment that warns C++ users about this.
+fn format_enum_class<'tcx>(
+    input: &Input<'tcx>,
+    core: &AdtCoreBindings<'tcx>,
+    underlying_type: &TokenStream,
+) -> Vec<(SnippetKey, MixedSnippet)> {
+    let tcx = input.tcx;
+    let local_def_id = core.def_id.expect_local();
+    let adt_def = core.self_ty.ty_adt_def().expect("`core.self_ty` needs to be an ADT");
+    let layout = get_layout(tcx, core.self_ty)
+        .expect("Layout should be already verified by `format_adt_core`");
+    let (integer, signed) = get_fieldless_enum_int(layout);
 
-        let doc_comment = format_doc_comment(tcx, core.def_id.expect_local());
-        let keyword = &core.keyword;
-        let core = &core.core;
+    let adt_cc_name = &core.cc_name;
+    let adt_rs_name = &core.rs_name;
+    // `Arc<dyn Trait>` only gives shared access to the trait object, so `&mut self`
+    // methods can't be called through `DynArc`.
+    let is_dyn_wrapper_method = |wrapper: &TokenStream, method: &TraitMethod| {
+        method.self_mutbl == Mutability::Not || wrapper.to_string() != "DynArc"
+    };
 
-        let mut prereqs = CcPrerequisites::default();
-        let fields: TokenStream = fields
-            .into_iter()
-            .map(|field| {
-                let cc_name = field.cc_name;
-                match field.type_info {
-                    Err(err) => {
-                        let size =
-                            Literal::u64_unsuffixed(field.offset_of_next_field - field.offset);
-                        let msg =
-                            format!("Field type has been replaced with a blob of bytes: {err:#}");
-                        quote! {
-                            __COMMENT__ #msg
-                            unsigned char #cc_name[#size];
-                        }
-                    }
-                    Ok(FieldTypeInfo { cc_type, size }) => {
-                        let padding = field.offset_of_next_field - field.offset - size;
-                        let padding = if padding == 0 {
-                            quote! {}
-                        } else {
-                            let padding = Literal::u64_unsuffixed(padding);
-                            let ident = format_ident!("__padding{}", field.index);
-                            quote! { unsigned char #ident[#padding]; }
-                        };
-                        let cc_type = cc_type.into_tokens(&mut prereqs);
-                        quote! { #cc_type #cc_name; #padding }
-                    }
-                }
+    let main_api = {
+        let doc_comment = format_doc_comment(tcx, local_def_id);
+        let ub_warning = "Warning: Unlike a Rust enum, this `enum class` can hold values that \
+                          don't correspond to any of its enumerators (e.g. values created with \
+                          `static_cast`).  Passing such values to Rust is Undefined Behavior.";
+        let enumerators = adt_def
+            .discriminants(tcx)
+            .map(|(variant_idx, discr)| {
+                let name = format_cc_ident(adt_def.variant(variant_idx).name.as_str())
+                    .expect("Variant names should be already verified by `format_adt_core`");
+                let value = format_discriminant(discr.val, integer, signed);
+                quote! { #name = #value }
             })
-            .collect();
-        let impl_item_decls = if impl_item_main_apis.is_empty() {
-            quote! {}
-        } else {
-            let cmp = preferred_snippet_order(tcx);
-            let tokens = impl_item_main_apis
-                .into_iter()
-                .sorted_by(|(key1, _), (key2, _)| cmp(key1, key2))
-                .map(|(_key, snippet)| snippet.cc.into_tokens(&mut prereqs))
-                .collect_vec();
+            .collect_vec();
+        CcSnippet::with_include(
             quote! {
-                public:
-                    #( #tokens )*
-            }
-        };
-        prereqs.fwd_decls.remove(&local_def_id);
-        let assertions_method_decl = if cc_field_assertions.is_empty() {
-            quote! {}
-        } else {
-            // We put the assertions in a method so that they can read private member
-            // variables.
-            quote! { inline static void __crubit_field_offset_assertions(); }
-        };
-
-        CcSnippet {
-            prereqs,
-            tokens: quote! {
                 __NEWLINE__ #doc_comment
-                #keyword alignas(#alignment) #cc_packed_attribute #adt_cc_name final {
-                    #core
-                    #impl_item_decls
-
-                    // TODO(b/271002281): Preserve actual field visibility.
-                    private: __NEWLINE__
-                        #fields
-                        #assertions_method_decl
+                __COMMENT__ #ub_warning
+                enum class #adt_cc_name : #underlying_type {
+                    #( #enumerators ),*
                 };
                 __NEWLINE__
             },
-        }
+            CcInclude::cstdint(),
+        )
     };
-    let impl_details = {
-        let mut cc = {
-            let assertions_method_def = if cc_field_assertions.is_empty() {
-                quote! {}
-            } else {
-                quote! {
-                    inline void #adt_cc_name::__crubit_field_offset_assertions() {
-                        #cc_field_assertions
-                    }
-                }
-            };
-            CcSnippet::new(quote! {
-                __NEWLINE__
-                static_assert(
-                    sizeof(#adt_cc_name) == #size,
-                    "Verify that struct layout didn't change since this header got generated");
-                static_assert(
-                    alignof(#adt_cc_name) == #alignment,
-                    "Verify that struct layout didn't change since this header got generated");
-                __NEWLINE__
-                #assertions_method_def
-            })
-        };
+
+    let alignment = Literal::u64_unsuffixed(core.alignment_in_bytes);
+    let size = Literal::u64_unsuffixed(core.size_in_bytes);
+    let impl_details = {
+        let mut cc = CcSnippet::new(quote! {
+            __NEWLINE__
+            static_assert(
+                sizeof(#adt_cc_name) == #size,
+                "Verify that enum layout didn't change since this header got generated");
+            static_assert(
+                alignof(#adt_cc_name) == #alignment,
+                "Verify that enum layout didn't change since this header got generated");
+            __NEWLINE__
+        });
         cc.prereqs.defs.insert(local_def_id);
-        let rs = {
-            quote! {
-                const _: () = assert!(::std::mem::size_of::<#adt_rs_name>() == #size);
-                const _: () = assert!(::std::mem::align_of::<#adt_rs_name>() == #alignment);
-                #rs_field_assertions
-            }
+        let mut rs = quote! {
+            const _: () = assert!(::std::mem::size_of::<#adt_rs_name>() == #size);
+            const _: () = assert!(::std::mem::align_of::<#adt_rs_name>() == #alignment);
         };
+        let (cc_box_drop, rs_box_drop) = format_box_drop(input, core, &quote! { #adt_cc_name });
+        cc.tokens.extend(cc_box_drop);
+        rs.extend(rs_box_drop);
+        let (cc_ref_counted_ptr_thunks, rs_ref_counted_ptr_thunks) =
+            format_ref_counted_ptr_thunks(input, core, &quote! { #adt_cc_name });
+        cc.tokens.extend(cc_ref_counted_ptr_thunks);
+        rs.extend(rs_ref_counted_ptr_thunks);
         MixedSnippet { cc, rs }
     };
 
-    let mut result = vec![
+    vec![
         (SnippetKey { def_id: local_def_id, kind: SnippetKind::MainApi }, main_api.into()),
         (SnippetKey { def_id: local_def_id, kind: SnippetKind::ImplDetails }, impl_details),
-    ];
-    result.extend(impl_item_other_snippets);
-    result
+    ]
 }
 
-/// Formats the forward declaration of an algebraic data type (an ADT - a
-/// struct, an enum, or a union), returning something like
-/// `quote!{ struct SomeStruct; }`.
-///
-/// Will panic if `def_id` doesn't identify an ADT that can be successfully
-/// handled by `format_adt_core`.
-fn format_fwd_decl(tcx: TyCtxt, def_id: LocalDefId) -> TokenStream {
-    let def_id = def_id.to_def_id(); // LocalDefId -> DefId conversion.
+/// Verifies that values of `field_ty` can be represented as fields of the C++
+/// bindings of a struct (or as elements of a tuple field - see
+/// `format_tuple_layout_struct`).
+fn ensure_supported_field_ty<'tcx>(tcx: TyCtxt<'tcx>, field_ty: Ty<'tcx>) -> Result<()> {
+    // C++ structs with reference members are not standard-layout (and
+    // therefore `offsetof` can't be used to verify field offsets).
+    ensure!(
+        !field_ty.is_ref(),
+        "References are only supported as function parameter or return types \
+         (b/258235219)"
+    );
+    // `std::optional` and `rs_std::Result` can't replicate the layout of Rust's
+    // `Option` and `Result`.
+    ensure!(
+        option_or_result(tcx, field_ty).is_none(),
+        "`Option` and `Result` are only supported as function parameter or \
+         return types"
+    );
+    // `rs_std::String` and `rs_std::Vec<T>` can't replicate the layout of Rust's
+    // `String` and `Vec<T>`.
+    ensure!(
+        string_or_vec(tcx, field_ty).is_none(),
+        "`String` and `Vec` are only supported as function parameter or \
+         return types"
+    );
+    // `rs_std::FnPtr<F>` also holds a pointer to a trampoline.
+    ensure!(
+        !is_rust_abi_fn_ptr(field_ty),
+        "Rust-ABI function pointers are only supported as function parameter \
+         or return types"
+    );
+    // `rs_std::Rc<T>` and `rs_std::Arc<T>` hold the pointer returned by
+    // `into_raw` (rather than the pointer held by Rust's `Rc<T>` and `Arc<T>`).
+    ensure!(
+        ref_counted_ptr(tcx, field_ty).is_none(),
+        "`Rc` and `Arc` are only supported as function parameter or return \
+         types"
+    );
+    Ok(())
+}
 
-    // `format_fwd_decl` should only be called for items from
-    // `CcPrerequisites::fwd_decls` and `fwd_decls` should only contain ADTs
-    // that `format_adt_core` succeeds for.
-    let AdtCoreBindings { keyword, cc_name, .. } = format_adt_core(tcx, def_id)
-        .expect("`format_fwd_decl` should only be called if `format_adt_core` succeeded");
+/// Represents a C++ struct that replicates the layout of a Rust tuple - see
+/// `format_tuple_layout_struct`.
+struct TupleLayoutBindings {
+    /// Declaration of the struct.
+    decl: CcSnippet,
 
-    quote! { #keyword #cc_name; }
-}
+    /// `static_assert`s that verify the layout of the struct (to be put into
+    /// `__crubit_field_offset_assertions` by `format_adt`).
+    cc_assertions: TokenStream,
 
-fn format_source_location(tcx: TyCtxt, local_def_id: LocalDefId) -> String {
-    let def_span = tcx.def_span(local_def_id);
-    let rustc_span::FileLines { file, lines } =
-        match tcx.sess().source_map().span_to_lines(def_span) {
-            Ok(filelines) => filelines,
-            Err(_) => return "unknown location".to_string(),
-        };
-    let file_name = file.name.prefer_local().to_string();
-    // Note: line_index starts at 0, while CodeSearch starts indexing at 1.
-    let line_number = lines[0].line_index + 1;
-    let google3_prefix = {
-        // If rustc_span::FileName isn't a 'real' file, then it's surrounded by by angle
-        // brackets, thus don't prepend "google3/" prefix.
-        if file.name.is_real() { "google3/" } else { "" }
-    };
-    format!("{google3_prefix}{file_name};l={line_number}")
+    /// Rust assertions that verify the layout of the tuple.
+    rs_assertions: TokenStream,
 }
 
-/// Formats the doc comment (if any) associated with the item identified by
-/// `local_def_id`, and appends the source location at which the item is
-/// defined.
-fn format_doc_comment(tcx: TyCtxt, local_def_id: LocalDefId) -> TokenStream {
-    let hir_id = tcx.local_def_id_to_hir_id(local_def_id);
-    let doc_comment = tcx
-        .hir()
-        .attrs(hir_id)
+/// Formats a C++ struct named `cc_name` that replicates the layout of the
+/// Rust tuple type `ty`.  This is used for struct fields of tuple types,
+/// because `std::tuple` doesn't replicate the layout of Rust tuples (Rust may
+/// reorder the tuple elements).  The struct is nested in the C++ class of the
+/// ADT that has the field (see `format_adt`).
+///
+/// Just like the fields of tuple structs, the elements of the tuple are
+/// represented as `__field0`, `__field1`, etc.
+fn format_tuple_layout_struct<'tcx>(
+    input: &Input<'tcx>,
+    cc_name: &TokenStream,
+    ty: Ty<'tcx>,
+) -> Result<TupleLayoutBindings> {
+    let tcx = input.tcx;
+    let element_types = tuple_element_types(ty).expect("`ty` needs to be a tuple");
+    let layout = get_layout(tcx, ty)?;
+    let size = layout.size().bytes();
+    let mut prereqs = CcPrerequisites::default();
+    let mut elements = element_types
         .iter()
-        .filter_map(|attr| attr.doc_str())
-        .map(|symbol| symbol.to_string())
-        .chain(once(format!("Generated from: {}", format_source_location(tcx, local_def_id))))
-        .join("\n\n");
-    quote! { __COMMENT__ #doc_comment}
-}
+        .enumerate()
+        .map(|(index, element_ty)| {
+            ensure!(
+                tuple_element_types(element_ty).is_none(),
+                "Nested tuples are not supported in struct fields yet (b/254099023)"
+            );
+            ensure_supported_field_ty(tcx, element_ty)?;
+            let element_size = get_layout(tcx, element_ty)?.size().bytes();
+            ensure!(
+                element_size != 0,
+                "Zero-sized types (ZSTs) are not supported in tuple fields (b/258259459)"
+            );
+            let cc_type = format_ty_for_cc(input, element_ty)?.into_tokens(&mut prereqs);
+            let offset = layout.fields().offset(index).bytes();
+            Ok((index, cc_type, offset, element_size))
+        })
+        .collect::<Result<Vec<_>>>()
+        .with_context(|| format!("Failed to format the tuple type `{ty}`"))?;
+    elements.sort_by_key(|&(_, _, offset, _)| offset);
+    let next_offsets = elements
+        .iter()
+        .map(|&(_, _, offset, _)| offset)
+        .skip(1)
+        .chain(once(size))
+        .collect_vec();
 
-/// Formats a HIR item idenfied by `def_id`.  Returns `None` if the item
-/// can be ignored. Returns an `Err` if the definition couldn't be formatted.
-///
-/// Will panic if `def_id` is invalid (i.e. doesn't identify a HIR item).
-fn format_item(input: &Input, def_id: LocalDefId) -> Result<Vec<(SnippetKey, MixedSnippet)>> {
-    // TODO(b/262052635): When adding support for re-exports we may need to change
-    // `is_directly_public` below into `is_exported`.  (OTOH such change *alone* is undesirable,
-    // because it would mean exposing items from a private module.  Exposing a private module is
-    // undesirable, because it would mean that changes of private implementation details of the
-    // crate could become breaking changes for users of the generated C++ bindings.)
-    if !input.tcx.effective_visibilities(()).is_directly_public(def_id) {
-        return Ok(vec![]);
+    let mut members = quote! {};
+    let mut cc_assertions = quote! {};
+    let mut rs_assertions = quote! {};
+    let rs_type = format_ty_for_rs(tcx, ty)?;
+    for ((index, cc_type, offset, element_size), next_offset) in
+        elements.into_iter().zip(next_offsets)
+    {
+        let name = format_ident!("__field{index}");
+        let padding = next_offset - offset - element_size;
+        members.extend(quote! { #cc_type #name; });
+        if padding != 0 {
+            let padding = Literal::u64_unsuffixed(padding);
+            let padding_name = format_ident!("__padding{index}");
+            members.extend(quote! { unsigned char #padding_name[#padding]; });
+        }
+        let offset = Literal::u64_unsuffixed(offset);
+        let rs_index = Literal::usize_unsuffixed(index);
+        cc_assertions.extend(quote! { static_assert(#offset == offsetof(#cc_name, #name)); });
+        rs_assertions.extend(quote! {
+            const _: () = assert!(memoffset::offset_of_tuple!(#rs_type, #rs_index) == #offset);
+        });
     }
 
-    match input.tcx.hir().expect_item(def_id) {
-        Item { kind: ItemKind::Struct(_, generics) |
-                     ItemKind::Enum(_, generics) |
-                     ItemKind::Union(_, generics),
-               .. } if !generics.params.is_empty() => {
-            bail!("Generic types are not supported yet (b/259749095)");
+    let alignment = Literal::u64_unsuffixed(layout.align().abi.bytes());
+    let size = Literal::u64_unsuffixed(size);
+    cc_assertions.extend(quote! {
+        static_assert(sizeof(#cc_name) == #size);
+        static_assert(alignof(#cc_name) == #alignment);
+    });
+    rs_assertions.extend(quote! {
+        const _: () = assert!(::std::mem::size_of::<#rs_type>() == #size);
+        const _: () = assert!(::std::mem::align_of::<#rs_type>() == #alignment);
+    });
+    let comment = format!("Replicates the layout of the Rust tuple type `{ty}`.");
+    let decl = CcSnippet {
+        prereqs,
+        tokens: quote! {
+            __COMMENT__ #comment
+            struct alignas(#alignment) #cc_name final { #members };
         },
-        Item { kind: ItemKind::Fn(..), .. } => format_fn(input, def_id),
-        Item { kind: ItemKind::Struct(..) | ItemKind::Enum(..) | ItemKind::Union(..), .. } =>
-            format_adt_core(input.tcx, def_id.to_def_id())
-                .map(|core| format_adt(input, &core)),
-        Item { kind: ItemKind::Impl(_), .. } |  // Handled by `format_adt`
-        Item { kind: ItemKind::Mod(_), .. } =>  // Handled by `format_crate`
-            Ok(vec![]),
-        Item { kind, .. } => bail!("Unsupported rustc_hir::hir::ItemKind: {}", kind.descr()),
-    }
+    };
+    Ok(TupleLayoutBindings { decl, cc_assertions, rs_assertions })
 }
 
-/// Formats a C++ comment explaining why no bindings have been generated for
-/// `local_def_id`.
-fn format_unsupported_def(
-    tcx: TyCtxt,
-    local_def_id: LocalDefId,
-    err: anyhow::Error,
-) -> (SnippetKey, MixedSnippet) {
-    let source_loc = format_source_location(tcx, local_def_id);
-    let name = tcx.def_path_str(local_def_id.to_def_id());
+/// Represents the C++ bindings for the variants of an enum that has a defined
+/// layout - see `format_tagged_union`.
+struct TaggedUnionBindings {
+    /// Public declarations of the nested `Tag` enum, the nested variant structs,
+    /// the accessors and the static constructor functions.
+    public_decls: TokenStream,
 
-    // https://docs.rs/anyhow/latest/anyhow/struct.Error.html#display-representations
-    // says: To print causes as well [...], use the alternate selector “{:#}”.
-    let msg = format!("Error generating bindings for `{name}` defined at {source_loc}: {err:#}");
-    let cc = CcSnippet::new(quote! { __NEWLINE__ __NEWLINE__ __COMMENT__ #msg __NEWLINE__ });
+    /// Private declarations of the anonymous union of the variant structs and
+    /// of the C++ constructors that initialize one of the union members.
+    private_decls: TokenStream,
 
-    (SnippetKey { def_id: local_def_id, kind: SnippetKind::MainApi }, cc.into())
+    /// Definitions of the accessors and the constructors.
+    defs: TokenStream,
+
+    /// `static_assert`s that verify the offsets of the variant fields (to be
+    /// put into `__crubit_field_offset_assertions` by `format_adt`).
+    field_assertions: TokenStream,
+
+    prereqs: CcPrerequisites,
 }
 
-/// Formats all public items from the Rust crate being compiled.
-fn format_crate(input: &Input) -> Result<Output> {
+/// Formats the variants of the enum represented by `core` as a C++ tagged
+/// union.  This is only supported for enums with a defined layout: `#[repr(C)]`
+/// enums and enums with a primitive representation (e.g. `#[repr(u8)]`) - see
+/// https://doc.rust-lang.org/reference/type-layout.html#reprc-enums-with-fields
+/// and https://doc.rust-lang.org/reference/type-layout.html#primitive-representation-of-enums-with-fields
+///
+/// For each variant (e.g. `Circle { radius: f64 }`) the C++ struct gets:
+/// - a nested variant struct (e.g. `CircleVariant`) that starts with the tag,
+///   followed by the variant fields (at the same offsets as in Rust).  All the
+///   variant structs are members of an anonymous union - the tag is their
+///   common initial sequence,
+/// - `is_Circle()` and (for variants with fields) `as_Circle()` accessors,
+/// - a static `Circle(...)` function that constructs the given variant.
+/// The discriminant can be read via the `tag()` accessor.
+fn format_tagged_union<'tcx>(
+    input: &Input<'tcx>,
+    core: &AdtCoreBindings<'tcx>,
+    adt_cc_type: &TokenStream,
+) -> Result<TaggedUnionBindings> {
     let tcx = input.tcx;
-    let mut bindings: HashMap<SnippetKey, MixedSnippet> = tcx
-        .hir()
-        .items()
-        .flat_map(|item_id| {
-            let def_id: LocalDefId = item_id.owner_id.def_id;
-            format_item(input, def_id)
-                .unwrap_or_else(|err| vec![format_unsupported_def(tcx, def_id, err)])
-                .into_iter()
-        })
-        .fold(HashMap::new(), |mut map, (key, value)| {
-            let old_item = map.insert(key, value);
-            assert!(old_item.is_none(), "Duplicated key: {key:?}");
-            map
-        });
-
-    // Find the order of `bindings` that 1) meets the requirements of
-    // `CcPrerequisites::defs` and 2) makes a best effort attempt to keep the
-    // `bindings` in the same order as the source order of the Rust APIs.
-    let ordered_ids = {
-        let toposort::TopoSortResult { ordered: ordered_ids, failed: failed_ids } = {
-            let nodes = bindings.keys().copied();
-            let deps = bindings.iter().flat_map(|(&successor, snippet)| {
-                let predecessors = snippet.cc.prereqs.defs.iter().map(|&def_id|
-                    SnippetKey { def_id, kind: SnippetKind::MainApi }
-                );
-                predecessors.map(move |predecessor| toposort::Dependency { predecessor, successor })
-            });
-            toposort::toposort(nodes, deps, preferred_snippet_order(tcx))
-        };
-        assert_eq!(
-            0,
-            failed_ids.len(),
-            "There are no known scenarios where CcPrerequisites::defs can form \
-                    a dependency cycle. These `LocalDefId`s form an unexpected cycle: {}",
-            failed_ids.into_iter().map(|id| format!("{:?}", id)).join(",")
-        );
-        ordered_ids
+    let ty = core.self_ty;
+    let (adt_def, substs) = match ty.kind() {
+        ty::TyKind::Adt(adt_def, substs) => (*adt_def, *substs),
+        _ => panic!("`core.self_ty` needs to be an ADT"),
     };
+    let repr = adt_def.repr();
+    ensure!(
+        repr.c() || repr.int.is_some(),
+        "No support for bindings of individual fields of `union` (b/272801632) or `enum`"
+    );
 
-    // Destructure/rebuild `bindings` (in the same order as `ordered_ids`) into
-    // `includes`, and into separate C++ snippets and Rust snippets.
-    let (includes, ordered_cc, rs_body) = {
-        let mut already_declared = HashSet::new();
-        let mut fwd_decls = HashSet::new();
-        let mut includes = BTreeSet::new();
-        let mut ordered_cc = Vec::new();
-        let mut rs_body = quote! {};
-        for key in ordered_ids.into_iter() {
-            let mod_path = FullyQualifiedName::new(tcx, key.def_id.to_def_id()).mod_path;
-            let MixedSnippet {
-                rs: inner_rs,
-                cc: CcSnippet {
-                    tokens: cc_tokens,
-                    prereqs: CcPrerequisites {
-                        includes: mut inner_includes,
-                        fwd_decls: inner_fwd_decls,
-                        .. // `defs` have already been utilized by `toposort` above
-                    }
-                }
-            } = bindings.remove(&key).unwrap();
-
-            fwd_decls.extend(inner_fwd_decls.difference(&already_declared).copied());
-            already_declared.insert(key.def_id);
-            already_declared.extend(inner_fwd_decls.into_iter());
-
-            includes.append(&mut inner_includes);
-            ordered_cc.push((mod_path, cc_tokens));
-            rs_body.extend(inner_rs);
+    let layout = get_layout(tcx, ty).expect("Layout should be already verified by `format_adt_core`");
+    let (tag, variant_layouts) = match layout.variants() {
+        Variants::Multiple { tag, tag_encoding: TagEncoding::Direct, tag_field, variants } => {
+            // Both `#[repr(C)]` and primitive representations put the tag first.
+            assert_eq!(0, layout.fields().offset(*tag_field).bytes());
+            (*tag, variants)
         }
+        other => panic!("Unexpected layout of an enum with a defined layout: {other:?}"),
+    };
+    let (tag_integer, tag_signed) = get_tag_int(tag);
+    let tag_size = tag_integer.size().bytes();
+    let tag_underlying_type = format_int_for_cc(tag_integer, tag_signed)?;
 
-        // Prepend `fwd_decls` (in the original source order) to `ordered_cc`.
-        let fwd_decls = fwd_decls
-            .into_iter()
-            .sorted_by_key(|def_id| tcx.def_span(*def_id))
-            .map(|local_def_id| {
-                let mod_path = FullyQualifiedName::new(tcx, local_def_id.to_def_id()).mod_path;
-                (mod_path, format_fwd_decl(tcx, local_def_id))
-            })
-            .collect_vec();
-        let ordered_cc = fwd_decls.into_iter().chain(ordered_cc.into_iter()).collect_vec();
+    let adt_cc_name = &core.cc_name;
+    let mut prereqs = CcPrerequisites::default();
+    prereqs.includes.insert(CcInclude::cassert());
+    prereqs.includes.insert(CcInclude::cstdint());
+    prereqs.includes.insert(CcInclude::utility());
+
+    // The names of the generated members can't conflict with each other.
+    let mut member_names: HashSet<String> =
+        ["Tag".to_string(), "tag".to_string(), adt_cc_name.to_string()].into_iter().collect();
+    let mut enumerators = vec![];
+    let mut variant_structs = vec![];
+    let mut union_members = vec![];
+    let mut fn_decls = vec![];
+    let mut private_ctor_decls = vec![];
+    let mut defs = vec![];
+    let mut field_assertions = vec![];
+    for (variant_idx, discr) in adt_def.discriminants(tcx) {
+        let variant = adt_def.variant(variant_idx);
+        let variant_name = variant.name.as_str();
+        let name = format_cc_ident(variant_name).with_context(|| {
+            format!("Error formatting the name of the enum variant `{variant_name}`")
+        })?;
+        let struct_name = format_ident!("{variant_name}Variant");
+        let is_fn = format_ident!("is_{variant_name}");
+        let as_fn = format_ident!("as_{variant_name}");
+        let member = format_ident!("__variant_{variant_name}");
+        for member_name in [variant_name.to_string(), struct_name.to_string()] {
+            ensure!(
+                member_names.insert(member_name.clone()),
+                "The name `{member_name}` of a member of the generated C++ struct is ambiguous"
+            );
+        }
 
-        (includes, ordered_cc, rs_body)
-    };
+        let discr_value = format_discriminant(discr.val, tag_integer, tag_signed);
+        enumerators.push(quote! { #name = #discr_value });
 
-    // Generate top-level elements of the C++ header file.
-    let h_body = {
-        // TODO(b/254690602): Decide whether using `#crate_name` as the name of the
-        // top-level namespace is okay (e.g. investigate if this name is globally
-        // unique + ergonomic).
-        let crate_name = format_cc_ident(tcx.crate_name(LOCAL_CRATE).as_str())?;
+        // Members of the variant struct: the tag, followed by the fields of the variant
+        // (sorted by their offsets, with explicit padding between them).
+        let mut members = vec![quote! { Tag __tag; }];
+        let mut initializers = vec![quote! { Tag::#name }];
+        let mut ctor_params = Some(vec![]); // `None` if any of the fields is a blob of bytes.
+        let fields = variant
+            .fields
+            .iter()
+            .enumerate()
+            .map(|(index, field_def)| -> Result<_> {
+                let field_ty = field_def.ty(tcx, substs);
+                let size = get_layout(tcx, field_ty)?.size().bytes();
+                ensure!(
+                    size != 0,
+                    "Zero-sized fields of enum variants are not supported (b/258259459)"
+                );
+                // See the corresponding checks of struct fields in `format_adt`.
+                let cc_type = if tuple_element_types(field_ty).is_some() {
+                    Err(anyhow!(
+                        "Tuples are only supported in fields of structs and as function \
+                         parameter or return types (b/254099023)"
+                    ))
+                } else if core.needs_drop && field_ty.is_adt() {
+                    Err(anyhow!(
+                        "Fields of ADT types are not supported yet in types that need \
+                         drop glue (b/258251148)"
+                    ))
+                } else {
+                    ensure_supported_field_ty(tcx, field_ty)
+                        .and_then(|()| format_ty_for_cc(input, field_ty))
+                };
+                let name = field_def.ident(tcx);
+                let cc_name = format_cc_ident(name.as_str()).unwrap_or_else(|_err| {
+                    format_ident!("__field{index}").into_token_stream()
+                });
+                let offset = variant_layouts[variant_idx].fields.offset(index).bytes();
+                Ok((cc_name, cc_type, offset, size))
+            })
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .sorted_by_key(|(_, _, offset, _)| *offset);
+        let mut end_of_previous_member = tag_size;
+        for (index, (cc_name, cc_type, offset, size)) in fields.enumerate() {
+            if offset > end_of_previous_member {
+                let padding = Literal::u64_unsuffixed(offset - end_of_previous_member);
+                let padding_name = format_ident!("__padding{index}");
+                members.push(quote! { unsigned char #padding_name[#padding]; });
+                initializers.push(quote! { {} });
+            }
+            match cc_type {
+                Ok(cc_type) => {
+                    let cc_type = cc_type.into_tokens(&mut prereqs);
+                    members.push(quote! { #cc_type #cc_name; });
+                    initializers.push(quote! { std::move(#cc_name) });
+                    if let Some(ctor_params) = ctor_params.as_mut() {
+                        ctor_params.push(quote! { #cc_type #cc_name });
+                    }
+                }
+                Err(err) => {
+                    let msg = format!("Field type has been replaced with a blob of bytes: {err:#}");
+                    let size = Literal::u64_unsuffixed(size);
+                    members.push(quote! {
+                        __COMMENT__ #msg
+                        unsigned char #cc_name[#size];
+                    });
+                    ctor_params = None;
+                }
+            }
+            let offset_literal = Literal::u64_unsuffixed(offset);
+            field_assertions.push(quote! {
+                static_assert(#offset_literal == offsetof(#struct_name, #cc_name));
+            });
+            end_of_previous_member = offset + size;
+        }
+        variant_structs.push(quote! {
+            struct #struct_name final {
+                #( #members )*
+            };
+        });
+        union_members.push(quote! { #struct_name #member; });
+        field_assertions.push(quote! { static_assert(0 == offsetof(#adt_cc_name, #member)); });
 
-        let includes = format_cc_includes(&includes);
-        let ordered_cc = format_namespace_bound_cc_tokens(ordered_cc);
-        quote! {
-            #includes
-            __NEWLINE__ __NEWLINE__
-            namespace #crate_name {
-                __NEWLINE__
-                #ordered_cc
-                __NEWLINE__
+        for member_name in [is_fn.to_string(), as_fn.to_string()] {
+            ensure!(
+                member_names.insert(member_name.clone()),
+                "The name `{member_name}` of a member of the generated C++ struct is ambiguous"
+            );
+        }
+        fn_decls.push(quote! { bool #is_fn() const; });
+        defs.push(quote! {
+            inline bool #adt_cc_type::#is_fn() const {
+                return tag() == Tag::#name;
             }
-            __NEWLINE__
+        });
+        if !variant.fields.is_empty() {
+            let precondition = format!("Precondition: `{is_fn}()`.");
+            fn_decls.push(quote! {
+                __NEWLINE__ __COMMENT__ #precondition
+                const #struct_name& #as_fn() const;
+            });
+            defs.push(quote! {
+                inline const #adt_cc_type::#struct_name& #adt_cc_type::#as_fn() const {
+                    assert(#is_fn());
+                    return #member;
+                }
+            });
+        }
+        if let Some(ctor_params) = ctor_params {
+            fn_decls.push(quote! { static #adt_cc_name #name( #( #ctor_params ),* ); });
+            private_ctor_decls.push(quote! { explicit #adt_cc_name(#struct_name&& __variant); });
+            defs.push(quote! {
+                inline #adt_cc_type #adt_cc_type::#name( #( #ctor_params ),* ) {
+                    return #adt_cc_name(#struct_name{ #( #initializers ),* });
+                }
+                inline #adt_cc_type::#adt_cc_name(#struct_name&& __variant)
+                    : #member(std::move(__variant)) {}
+            });
         }
+    }
+    let first_member = {
+        let first_variant = adt_def.variants().iter().next().expect("Enums with fields have variants");
+        format_ident!("__variant_{}", first_variant.name.as_str())
     };
 
-    Ok(Output { h_body, rs_body })
+    Ok(TaggedUnionBindings {
+        public_decls: quote! {
+            public:
+                enum class Tag : #tag_underlying_type {
+                    #( #enumerators ),*
+                };
+                #( #variant_structs )*
+                Tag tag() const;
+                #( #fn_decls )*
+        },
+        private_decls: quote! {
+            #( #private_ctor_decls )*
+            union {
+                #( #union_members )*
+            };
+        },
+        defs: quote! {
+            inline #adt_cc_type::Tag #adt_cc_type::tag() const {
+                // All the variant structs start with the tag (their common initial sequence).
+                return #first_member.__tag;
+            }
+            #( #defs )*
+            __NEWLINE__
+        },
+        field_assertions: quote! { #( #field_assertions )* },
+        prereqs,
+    })
+}
+
+/// Formats `__crubit_box_drop` - a C++ function that the destructor of
+/// `rs_std::Box<T>` finds via ADL (see `support/rs_std/box.h`) - together with
+/// the Rust thunk that it calls to drop and deallocate a `Box<T>`.
+///
+/// Returns the C++ and the Rust snippets.
+fn format_box_drop<'tcx>(
+    input: &Input<'tcx>,
+    core: &AdtCoreBindings<'tcx>,
+    adt_cc_type: &TokenStream,
+) -> (TokenStream, TokenStream) {
+    let tcx = input.tcx;
+    let adt_rs_name = &core.rs_name;
+    let thunk_suffix = format_adt_thunk_suffix(tcx, core.self_ty);
+    let box_drop_thunk = format!("__crubit_thunk_box_drop_{thunk_suffix}");
+    let cc_box_drop_thunk = format_cc_ident(&box_drop_thunk).expect("Suffix should be sanitized");
+    let rs_box_drop_thunk = make_rs_ident(&box_drop_thunk);
+    let rs_box_drop_body = format_rs_thunk_body_aborting_on_panic(
+        tcx,
+        quote! { ::core::mem::drop(unsafe { ::std::boxed::Box::from_raw(__ptr) }); },
+    );
+    let cc = quote! {
+        namespace __crubit_internal {
+            extern "C" void #cc_box_drop_thunk(#adt_cc_type* __ptr);
+        }
+        inline void __crubit_box_drop(#adt_cc_type* ptr) {
+            __crubit_internal::#cc_box_drop_thunk(ptr);
+        }
+        __NEWLINE__
+    };
+    let rs = quote! {
+        #[no_mangle]
+        extern "C" fn #rs_box_drop_thunk(__ptr: *mut #adt_rs_name) {
+            #rs_box_drop_body
+        }
+    };
+    (cc, rs)
+}
+
+/// Formats `__crubit_rc_clone`, `__crubit_rc_drop`, `__crubit_arc_clone`, and
+/// `__crubit_arc_drop` - C++ functions that the copy constructors and the
+/// destructors of `rs_std::Rc<T>` and `rs_std::Arc<T>` find via ADL (see
+/// `support/rs_std/rc.h` and `support/rs_std/arc.h`) - together with the Rust
+/// thunks that they call to increment and decrement the reference count.
+///
+/// Returns the C++ and the Rust snippets.
+fn format_ref_counted_ptr_thunks<'tcx>(
+    input: &Input<'tcx>,
+    core: &AdtCoreBindings<'tcx>,
+    adt_cc_type: &TokenStream,
+) -> (TokenStream, TokenStream) {
+    let tcx = input.tcx;
+    let adt_rs_name = &core.rs_name;
+    let thunk_suffix = format_adt_thunk_suffix(tcx, core.self_ty);
+    let mut cc = quote! {};
+    let mut rs = quote! {};
+    for (kind, prefix) in [(RefCountedPtr::Rc, "rc"), (RefCountedPtr::Arc, "arc")] {
+        let ptr_type = kind.format_for_rs();
+        let clone_fn = format_ident!("__crubit_{prefix}_clone");
+        let drop_fn = format_ident!("__crubit_{prefix}_drop");
+        let clone_thunk = format!("__crubit_thunk_{prefix}_clone_{thunk_suffix}");
+        let drop_thunk = format!("__crubit_thunk_{prefix}_drop_{thunk_suffix}");
+        let cc_clone_thunk = format_cc_ident(&clone_thunk).expect("Suffix should be sanitized");
+        let cc_drop_thunk = format_cc_ident(&drop_thunk).expect("Suffix should be sanitized");
+        let rs_clone_thunk = make_rs_ident(&clone_thunk);
+        let rs_drop_thunk = make_rs_ident(&drop_thunk);
+        // Dropping the last reference drops the object (which may panic).
+        let rs_drop_body = format_rs_thunk_body_aborting_on_panic(
+            tcx,
+            quote! { unsafe { #ptr_type::decrement_strong_count(__ptr) } },
+        );
+        cc.extend(quote! {
+            namespace __crubit_internal {
+                extern "C" void #cc_clone_thunk(const #adt_cc_type* __ptr);
+                extern "C" void #cc_drop_thunk(const #adt_cc_type* __ptr);
+            }
+            inline void #clone_fn(const #adt_cc_type* ptr) {
+                __crubit_internal::#cc_clone_thunk(ptr);
+            }
+            inline void #drop_fn(const #adt_cc_type* ptr) {
+                __crubit_internal::#cc_drop_thunk(ptr);
+            }
+            __NEWLINE__
+        });
+        rs.extend(quote! {
+            #[no_mangle]
+            extern "C" fn #rs_clone_thunk(__ptr: *const #adt_rs_name) {
+                unsafe { #ptr_type::increment_strong_count(__ptr) }
+            }
+            #[no_mangle]
+            extern "C" fn #rs_drop_thunk(__ptr: *const #adt_rs_name) {
+                #rs_drop_body
+            }
+        });
+    }
+    (cc, rs)
+}
+
+/// C++ bindings of a group of traits implemented by an ADT (see
+/// `format_comparison_operators` and `format_fmt_traits`).
+struct AdtTraitBindings {
+    /// Declarations of the C++ member functions and operators (to be placed
+    /// inside the C++ class).
+    decls: TokenStream,
+
+    /// Definitions of the C++ member functions and operators and declarations
+    /// of the C++ side of the thunks.
+    defs: TokenStream,
+
+    /// Rust thunks that call into the trait `impl`s.
+    rs_thunks: TokenStream,
+
+    prereqs: CcPrerequisites,
+}
+
+/// Formats C++ comparison operators of the ADT represented by `core`:
+/// - `PartialEq<Self>` is represented as `operator==` and `operator!=`,
+/// - `PartialOrd<Self>` and `Ord` are represented as `operator<=>` in C++20
+///   (returning `std::partial_ordering` or `std::weak_ordering`) and as
+///   `operator<`, `operator<=`, `operator>`, and `operator>=` before C++20.
+///
+/// The operators call exported Rust thunks.  Returns `None` if the ADT doesn't
+/// implement any of the comparison traits.
+fn format_comparison_operators<'tcx>(
+    input: &Input<'tcx>,
+    core: &AdtCoreBindings<'tcx>,
+    adt_cc_type: &TokenStream,
+) -> Option<AdtTraitBindings> {
+    let tcx = input.tcx;
+    let ty = core.self_ty;
+    let adt_cc_name = &core.cc_name;
+    let adt_rs_name = &core.rs_name;
+
+    let lang_items = tcx.lang_items();
+    let implements_partial_eq = lang_items
+        .eq_trait()
+        .map_or(false, |trait_id| does_type_implement_trait(tcx, ty, trait_id));
+    let implements_partial_ord = lang_items
+        .partial_ord_trait()
+        .map_or(false, |trait_id| does_type_implement_trait(tcx, ty, trait_id));
+    let implements_ord = tcx
+        .get_diagnostic_item(rustc_span::symbol::sym::Ord)
+        .map_or(false, |trait_id| does_type_implement_trait(tcx, ty, trait_id));
+    if !implements_partial_eq && !implements_partial_ord {
+        return None;
+    }
+
+    let thunk_suffix = format_adt_thunk_suffix(tcx, ty);
+    let mut decls = quote! {};
+    let mut thunk_decls = quote! {};
+    let mut defs = quote! {};
+    let mut rs_thunks = quote! {};
+    let mut prereqs = CcPrerequisites::default();
+
+    if implements_partial_eq {
+        let eq_thunk = format!("__crubit_thunk_eq_{thunk_suffix}");
+        let cc_eq_thunk = format_cc_ident(&eq_thunk).expect("Suffix should be sanitized");
+        let rs_eq_thunk = make_rs_ident(&eq_thunk);
+        let rs_eq_body = format_rs_thunk_body_aborting_on_panic(
+            tcx,
+            quote! { ::core::cmp::PartialEq::eq(__self, __other) },
+        );
+        decls.extend(quote! {
+            bool operator==(const #adt_cc_name& other) const;
+            bool operator!=(const #adt_cc_name& other) const;
+        });
+        thunk_decls.extend(quote! {
+            extern "C" bool #cc_eq_thunk(const #adt_cc_type* __self,
+                                         const #adt_cc_type* __other);
+        });
+        defs.extend(quote! {
+            inline bool #adt_cc_type::operator==(const #adt_cc_type& other) const {
+                return __crubit_internal::#cc_eq_thunk(this, &other);
+            }
+            inline bool #adt_cc_type::operator!=(const #adt_cc_type& other) const {
+                return !(*this == other);
+            }
+        });
+        rs_thunks.extend(quote! {
+            #[no_mangle]
+            extern "C" fn #rs_eq_thunk(__self: &#adt_rs_name, __other: &#adt_rs_name) -> bool {
+                #rs_eq_body
+            }
+        });
+    }
+
+    if implements_partial_ord {
+        // The thunk returns the `i8` representation of `core::cmp::Ordering` (i.e. -1, 0, or
+        // 1), or 2 if the values are unordered (i.e. if `partial_cmp` returns `None`).
+        let (cmp_thunk, rs_cmp_body, ordering) = if implements_ord {
+            // Rust's `Ord` doesn't guarantee that equal values are substitutable, so this
+            // maps to `std::weak_ordering` (rather than to `std::strong_ordering`).
+            (
+                format!("__crubit_thunk_cmp_{thunk_suffix}"),
+                quote! { ::core::cmp::Ord::cmp(__self, __other) as i8 },
+                quote! { std::weak_ordering },
+            )
+        } else {
+            (
+                format!("__crubit_thunk_partial_cmp_{thunk_suffix}"),
+                quote! {
+                    match ::core::cmp::PartialOrd::partial_cmp(__self, __other) {
+                        Some(ordering) => ordering as i8,
+                        None => 2,
+                    }
+                },
+                quote! { std::partial_ordering },
+            )
+        };
+        let cc_cmp_thunk = format_cc_ident(&cmp_thunk).expect("Suffix should be sanitized");
+        let rs_cmp_thunk = make_rs_ident(&cmp_thunk);
+        let rs_cmp_body = format_rs_thunk_body_aborting_on_panic(tcx, rs_cmp_body);
+        let unordered_case = if implements_ord {
+            quote! {}
+        } else {
+            quote! { if (result == 2) return std::partial_ordering::unordered; }
+        };
+        prereqs.includes.insert(CcInclude::cstdint());
+        // `cxx20_backports.h` includes `<compare>` (only when compiling as C++20).
+        prereqs.includes.insert(input.support_header("internal/cxx20_backports.h"));
+        decls.extend(quote! {
+            __HASH_TOKEN__ if __cplusplus > 201703L __NEWLINE__
+                #ordering operator<=>(const #adt_cc_name& other) const;
+            __HASH_TOKEN__ else __NEWLINE__
+                bool operator<(const #adt_cc_name& other) const;
+                bool operator<=(const #adt_cc_name& other) const;
+                bool operator>(const #adt_cc_name& other) const;
+                bool operator>=(const #adt_cc_name& other) const;
+            __HASH_TOKEN__ endif __NEWLINE__
+        });
+        thunk_decls.extend(quote! {
+            extern "C" std::int8_t #cc_cmp_thunk(const #adt_cc_type* __self,
+                                                 const #adt_cc_type* __other);
+        });
+        defs.extend(quote! {
+            __HASH_TOKEN__ if __cplusplus > 201703L __NEWLINE__
+                inline #ordering #adt_cc_type::operator<=>(const #adt_cc_type& other) const {
+                    std::int8_t result = __crubit_internal::#cc_cmp_thunk(this, &other);
+                    #unordered_case
+                    if (result < 0) return #ordering::less;
+                    if (result > 0) return #ordering::greater;
+                    return #ordering::equivalent;
+                }
+            __HASH_TOKEN__ else __NEWLINE__
+                inline bool #adt_cc_type::operator<(const #adt_cc_type& other) const {
+                    return __crubit_internal::#cc_cmp_thunk(this, &other) == -1;
+                }
+                inline bool #adt_cc_type::operator<=(const #adt_cc_type& other) const {
+                    std::int8_t result = __crubit_internal::#cc_cmp_thunk(this, &other);
+                    return result == -1 || result == 0;
+                }
+                inline bool #adt_cc_type::operator>(const #adt_cc_type& other) const {
+                    return __crubit_internal::#cc_cmp_thunk(this, &other) == 1;
+                }
+                inline bool #adt_cc_type::operator>=(const #adt_cc_type& other) const {
+                    std::int8_t result = __crubit_internal::#cc_cmp_thunk(this, &other);
+                    return result == 1 || result == 0;
+                }
+            __HASH_TOKEN__ endif __NEWLINE__
+        });
+        rs_thunks.extend(quote! {
+            #[no_mangle]
+            extern "C" fn #rs_cmp_thunk(__self: &#adt_rs_name, __other: &#adt_rs_name) -> i8 {
+                #rs_cmp_body
+            }
+        });
+    }
+
+    Some(AdtTraitBindings {
+        decls,
+        defs: quote! {
+            namespace __crubit_internal {
+                #thunk_decls
+            }
+            #defs
+            __NEWLINE__
+        },
+        rs_thunks,
+        prereqs,
+    })
+}
+
+/// Formats C++ bindings of the `Display` and `Debug` traits implemented by the
+/// ADT represented by `core`:
+/// - `Display` is represented as a `std::string ToString() const` member
+///   function,
+/// - `Debug` is represented as a `std::string DebugString() const` member
+///   function,
+/// - `operator<<` for `std::ostream` uses `ToString()` if available (and
+///   `DebugString()` otherwise).
+///
+/// The member functions call exported Rust thunks that format the ADT into a
+/// `std::string` owned by the C++ caller.  Returns `None` if the ADT doesn't
+/// implement `Display` nor `Debug`.
+fn format_fmt_traits<'tcx>(
+    input: &Input<'tcx>,
+    core: &AdtCoreBindings<'tcx>,
+    adt_cc_type: &TokenStream,
+) -> Option<AdtTraitBindings> {
+    let tcx = input.tcx;
+    let ty = core.self_ty;
+    let adt_rs_name = &core.rs_name;
+
+    let implements_trait = |trait_name| {
+        tcx.get_diagnostic_item(trait_name)
+            .map_or(false, |trait_id| does_type_implement_trait(tcx, ty, trait_id))
+    };
+    let fmt_traits = [
+        (rustc_span::symbol::sym::Display, "to_string", "ToString", quote! { "{}" }),
+        (rustc_span::symbol::sym::Debug, "debug_string", "DebugString", quote! { "{:?}" }),
+    ]
+    .into_iter()
+    .filter(|(trait_name, ..)| implements_trait(*trait_name))
+    .collect_vec();
+    if fmt_traits.is_empty() {
+        return None;
+    }
+
+    let thunk_suffix = format_adt_thunk_suffix(tcx, ty);
+    let mut decls = quote! {};
+    let mut thunk_decls = quote! {};
+    let mut defs = quote! {};
+    let mut rs_thunks = quote! {};
+    for (_, thunk_infix, cc_method_name, format_string) in fmt_traits.iter() {
+        let thunk = format!("__crubit_thunk_{thunk_infix}_{thunk_suffix}");
+        let cc_thunk = format_cc_ident(&thunk).expect("Suffix should be sanitized");
+        let rs_thunk = make_rs_ident(&thunk);
+        let cc_method_name = format_cc_ident(cc_method_name).expect("Valid C++ identifier");
+        let rs_body = format_rs_thunk_body_aborting_on_panic(
+            tcx,
+            quote! {
+                __crubit_write_fmt(__out, __append, ::core::format_args!(#format_string, __self));
+            },
+        );
+        decls.extend(quote! {
+            std::string #cc_method_name() const;
+        });
+        thunk_decls.extend(quote! {
+            extern "C" void #cc_thunk(const #adt_cc_type* __self, std::string* __out,
+                                      crubit::internal::AppendToStringFn __append);
+        });
+        defs.extend(quote! {
+            inline std::string #adt_cc_type::#cc_method_name() const {
+                std::string result;
+                __crubit_internal::#cc_thunk(this, &result, &crubit::internal::AppendToString);
+                return result;
+            }
+        });
+        rs_thunks.extend(quote! {
+            #[no_mangle]
+            extern "C" fn #rs_thunk(
+                __self: &#adt_rs_name,
+                __out: *mut ::core::ffi::c_void,
+                __append: extern "C" fn(*mut ::core::ffi::c_void, *const u8, usize)
+            ) {
+                #rs_body
+            }
+        });
+    }
+
+    // `fmt_traits` lists `Display` first - `operator<<` prefers `ToString()`.
+    let ostream_method_name = format_cc_ident(fmt_traits[0].2).expect("Valid C++ identifier");
+    defs.extend(quote! {
+        inline std::ostream& operator<<(std::ostream& os, const #adt_cc_type& value) {
+            return os << value.#ostream_method_name();
+        }
+    });
+
+    let mut prereqs = CcPrerequisites::default();
+    prereqs.includes.insert(CcInclude::string());
+    prereqs.includes.insert(CcInclude::ostream());
+    prereqs.includes.insert(input.support_header("internal/fmt.h"));
+    Some(AdtTraitBindings {
+        decls,
+        defs: quote! {
+            namespace __crubit_internal {
+                #thunk_decls
+            }
+            #defs
+            __NEWLINE__
+        },
+        rs_thunks,
+        prereqs,
+    })
 }
 
-#[cfg(test)]
-pub mod tests {
-    use super::*;
+/// Formats an algebraic data type (an ADT - a struct, an enum, or a union)
+/// represented by `core`.  This function is infallible - after
+/// `format_adt_core` returns success we have committed to emitting C++ bindings
+/// for the ADT.
+///
+/// Returns multiple snippets to support independent reordering of 1) the struct
+/// definition (including declarations of methods - aka member functions), and
+/// 2) an arbitrary number of method definitions.  For motivation see the
+/// `non_contiguous_method_decls_and_defs` module in `cc_bindings_from_rs/test/
+/// impls/impls.rs`).  Secondary motivation is to keep implementation
+/// details out of the way (to improve readability of the main apis).
+///
+/// For an instantiation of a generic ADT (see `Input::instantiations`) the
+/// returned main API is a C++ explicit specialization of the primary class
+/// template from `format_generic_adt`.
+fn format_adt<'tcx>(
+    input: &Input<'tcx>,
+    core: &AdtCoreBindings<'tcx>,
+) -> Vec<(SnippetKey, MixedSnippet)> {
+    let tcx = input.tcx;
+
+    // `core.self_ty` doesn't refer to any generic parameters (see `format_adt_core`).
+    let param_env = ty::ParamEnv::empty();
+    let ty = core.self_ty;
+    let substs_ref = match ty.kind() {
+        ty::TyKind::Adt(_, substs) => *substs,
+        _ => panic!("`core.self_ty` needs to be an ADT"),
+    };
+    let is_specialization = !substs_ref.is_empty();
+
+    // `format_adt` should only be called for local ADTs.
+    let local_def_id = core.def_id.expect_local();
+
+    if let Some(underlying_type) = core.enum_class_underlying_type.as_ref() {
+        return format_enum_class(input, core, underlying_type);
+    }
+
+    let adt_cc_name = &core.cc_name;
+    let adt_rs_name = &core.rs_name;
+
+    // `adt_cc_type` is the C++ spelling of the type - e.g. `Wrapper<std::int32_t>`.  Note that
+    // `adt_cc_name` can still be used inside the class (and in `offsetof`) where it refers to the
+    // injected-class-name.
+    let mut type_args_prereqs = CcPrerequisites::default();
+    let adt_cc_type = if !is_specialization {
+        quote! { #adt_cc_name }
+    } else {
+        let type_args = format_type_args_for_cc(input, substs_ref, &mut type_args_prereqs)
+            .expect("Type arguments should be already verified by `format_generic_adt`");
+        quote! { #adt_cc_name #type_args }
+    };
+    type_args_prereqs.move_defs_to_fwd_decls();
+
+    // Enums with a defined layout are represented in C++ as a tagged union.
+    let (tagged_union, tagged_union_err) = if !ty.is_enum() {
+        (None, None)
+    } else {
+        match format_tagged_union(input, core, &adt_cc_type) {
+            Ok(tagged_union) => (Some(tagged_union), None),
+            Err(err) => (None, Some(err)),
+        }
+    };
+
+    let trait_bindings = [
+        format_comparison_operators(input, core, &adt_cc_type),
+        format_fmt_traits(input, core, &adt_cc_type),
+    ]
+    .into_iter()
+    .flatten()
+    .collect_vec();
+
+    struct FieldTypeInfo {
+        size: u64,
+        cc_type: CcSnippet,
+        /// The nested struct that represents the type of a tuple field.
+        tuple_layout: Option<TupleLayoutBindings>,
+    }
+    struct Field {
+        type_info: Result<FieldTypeInfo>,
+        cc_name: TokenStream,
+        rs_name: TokenStream,
+        is_public: bool,
+        index: usize,
+        offset: u64,
+        offset_of_next_field: u64,
+    }
+    let layout = tcx
+        .layout_of(param_env.and(ty))
+        .expect("Layout should be already verified by `format_adt_core`")
+        .layout;
+    let core_adt_def = ty.ty_adt_def().expect("`core.self_ty` needs to be an ADT");
+    let fields: Vec<Field> = if tagged_union.is_some() {
+        vec![] // Fields of enum variants are handled by `format_tagged_union`.
+    } else if ty.is_enum() || (ty.is_union() && !core_adt_def.repr().c()) {
+        // Note that `#[repr(Rust)]` unions don't guarantee that all their fields
+        // have offset 0.
+        vec![Field {
+            type_info: Err(tagged_union_err.unwrap_or_else(|| {
+                anyhow!(
+                    "No support for bindings of individual fields of \
+                     `union` (b/272801632) or `enum`"
+                )
+            })),
+            cc_name: quote! { __opaque_blob_of_bytes },
+            rs_name: quote! { __opaque_blob_of_bytes },
+            is_public: false,
+            index: 0,
+            offset: 0,
+            offset_of_next_field: core.size_in_bytes,
+        }]
+    } else {
+        let mut fields = ty
+            .ty_adt_def()
+            .expect("`core.def_id` needs to identify an ADT")
+            .all_fields()
+            .sorted_by_key(|f| tcx.def_span(f.did))
+            .enumerate()
+            .map(|(index, field_def)| {
+                let field_ty = field_def.ty(tcx, substs_ref);
+                let size = get_layout(tcx, field_ty).map(|layout| layout.size().bytes());
+                let type_info = size.and_then(|size| {
+                    // Implicit initialization (in the C++ move constructor) and destruction (in
+                    // the C++ destructor) of fields with ADT types would be incorrect when the
+                    // whole ADT is moved or dropped via Rust thunks.
+                    let has_adt_type = match tuple_element_types(field_ty) {
+                        Some(element_types) => element_types.iter().any(|ty| ty.is_adt()),
+                        None => field_ty.is_adt(),
+                    };
+                    ensure!(
+                        !core.needs_drop || !has_adt_type,
+                        "Fields of ADT types are not supported yet in types that need \
+                         drop glue (b/258251148)"
+                    );
+                    if tuple_element_types(field_ty).is_some() {
+                        // `std::tuple` (used by `format_ty_for_cc`) can't replicate the layout
+                        // of a Rust tuple.
+                        let tuple_name = format_ident!("__CrubitTupleField{index}");
+                        let tuple_layout = format_tuple_layout_struct(
+                            input,
+                            &tuple_name.clone().into_token_stream(),
+                            field_ty,
+                        )?;
+                        let cc_type = CcSnippet::new(tuple_name.into_token_stream());
+                        let tuple_layout = Some(tuple_layout);
+                        return Ok(FieldTypeInfo { size, cc_type, tuple_layout });
+                    }
+                    ensure_supported_field_ty(tcx, field_ty)?;
+                    let cc_type = format_ty_for_cc(input, field_ty)?;
+                    Ok(FieldTypeInfo { size, cc_type, tuple_layout: None })
+                });
+                let name = field_def.ident(tcx);
+                let cc_name = format_cc_ident(name.as_str()).unwrap_or_else(|_err|
+                    format_ident!("__field{index}").into_token_stream()
+                );
+                let rs_name = {
+                    let name_starts_with_digit = name
+                        .as_str()
+                        .chars()
+                        .next()
+                        .expect("Empty names are unexpected (here and in general)")
+                        .is_ascii_digit();
+                    if name_starts_with_digit {
+                        let index = Literal::usize_unsuffixed(index);
+                        quote! { #index }
+                    } else {
+                        let name = make_rs_ident(name.as_str());
+                        quote! { #name }
+                    }
+                };
+                let is_public = field_def.vis == ty::Visibility::Public;
+
+                // `offset` and `offset_of_next_field` will be fixed by FieldsShape::Arbitrary
+                // branch below.
+                let offset = 0;
+                let offset_of_next_field = 0;
+
+                Field {
+                    type_info,
+                    cc_name,
+                    rs_name,
+                    is_public,
+                    index,
+                    offset,
+                    offset_of_next_field,
+                }
+            })
+            .collect_vec();
+        match layout.fields() {
+            FieldsShape::Arbitrary { offsets, .. } => {
+                for (index, offset) in offsets.iter().enumerate() {
+                    // Documentation of `FieldsShape::Arbitrary says that the offsets are "ordered
+                    // to match the source definition order".  We can coorelate them with elements
+                    // of the `fields` vector because we've explicitly `sorted_by_key` using
+                    // `def_span`.
+                    fields[index].offset = offset.bytes();
+                }
+                // Deterministic outcome of `fields.sort_by_key` depends on each field
+                // having a unique offset (this assumption might be broken in the future by
+                // ZSTs).
+                assert!(fields.iter().map(|f| f.offset).all_unique());
+                fields.sort_by_key(|field| field.offset);
+                let next_offsets = fields
+                    .iter()
+                    .map(|Field { offset, .. }| *offset)
+                    .skip(1)
+                    .chain(once(core.size_in_bytes))
+                    .collect_vec();
+                for (field, next_offset) in fields.iter_mut().zip(next_offsets) {
+                    field.offset_of_next_field = next_offset;
+                }
+                fields
+            }
+            FieldsShape::Union(_) => {
+                // All fields of a `#[repr(C)]` union have offset 0 (and `offset` has been
+                // initialized to 0 above).  Each field occupies only its own bytes (the size of
+                // the whole union is determined by its largest field and by `alignas`), except
+                // for blobs of bytes which cover the whole union.
+                for field in fields.iter_mut() {
+                    field.offset_of_next_field = match &field.type_info {
+                        Ok(FieldTypeInfo { size, .. }) => *size,
+                        Err(_) => core.size_in_bytes,
+                    };
+                }
+                fields
+            }
+            unexpected => panic!("Unexpected FieldsShape: {unexpected:?}"),
+        }
+    };
+    let mut cc_field_assertions: TokenStream = fields
+        .iter()
+        .map(|Field { cc_name, offset, .. }| {
+            let offset = Literal::u64_unsuffixed(*offset);
+            quote! { static_assert(#offset == offsetof(#adt_cc_name, #cc_name)); }
+        })
+        .collect();
+    let tuple_layouts =
+        fields.iter().filter_map(|field| field.type_info.as_ref().ok()?.tuple_layout.as_ref());
+    for tuple_layout in tuple_layouts.clone() {
+        cc_field_assertions.extend(tuple_layout.cc_assertions.clone());
+    }
+    if let Some(tagged_union) = tagged_union.as_ref() {
+        cc_field_assertions.extend(tagged_union.field_assertions.clone());
+    }
+    // `memoffset::offset_of!` doesn't support unions (and all the fields of a `#[repr(C)]` union
+    // have offset 0 anyway).
+    let rs_field_assertions: TokenStream = fields
+        .iter()
+        .filter(|_| !ty.is_union())
+        .filter(|Field { is_public, .. }| *is_public)
+        .map(|Field { rs_name, offset, .. }| {
+            let expected_offset = Literal::u64_unsuffixed(*offset);
+            let actual_offset = quote! { memoffset::offset_of!(#adt_rs_name, #rs_name) };
+            quote! { const _: () = assert!(#actual_offset == #expected_offset); }
+        })
+        .chain(tuple_layouts.map(|tuple_layout| tuple_layout.rs_assertions.clone()))
+        .collect();
+
+    let (impl_item_main_apis, impl_item_other_snippets) = tcx
+        .inherent_impls(core.def_id)
+        .iter()
+        .filter_map(|&impl_id| {
+            let impl_substs = match_impl_substs(tcx, impl_id, ty)?;
+            Some((impl_substs, tcx.hir().expect_item(impl_id.expect_local())))
+        })
+        .flat_map(|(impl_substs, item)| match &item.kind {
+            ItemKind::Impl(impl_) => {
+                impl_.items.iter().map(move |item_ref| (impl_substs, item_ref))
+            }
+            other => panic!("Unexpected `ItemKind` from `inherent_impls`: {other:?}"),
+        })
+        .flat_map(|(impl_substs, impl_item_ref)| {
+            let def_id = impl_item_ref.id.owner_id.def_id;
+            if !tcx.visibility(def_id).is_public() {
+                return vec![];
+            }
+            let result = match impl_item_ref.kind {
+                AssocItemKind::Fn { .. } if impl_substs.is_empty() => format_fn(input, def_id),
+                AssocItemKind::Fn { .. } => {
+                    format_method_of_instantiation(input, def_id, impl_substs)
+                }
+                AssocItemKind::Const if impl_substs.is_empty() => {
+                    format_assoc_const(input, def_id, local_def_id, &adt_cc_type)
+                }
+                AssocItemKind::Const => {
+                    Err(anyhow!("Associated constants of generic types are not supported yet"))
+                }
+                other => Err(anyhow!("Unsupported `impl` item kind: {other:?}")),
+            };
+            result.unwrap_or_else(|err| vec![format_unsupported_def(tcx, def_id, err)])
+        })
+        .chain(format_trait_impl_items(input, core, &adt_cc_type))
+        .partition::<Vec<_>, _>(|(SnippetKey { def_id, kind }, _)| {
+            *kind == SnippetKind::MainApi
+                && !is_trait_impl_item_name_clash(tcx, def_id.to_def_id())
+        });
+
+    let alignment = Literal::u64_unsuffixed(core.alignment_in_bytes);
+    let size = Literal::u64_unsuffixed(core.size_in_bytes);
+    let main_api = {
+        let cc_packed_attribute = {
+            let has_packed_attribute = tcx
+                .get_attrs(core.def_id, rustc_span::symbol::sym::repr)
+                .flat_map(|attr| rustc_attr::parse_repr_attr(tcx.sess(), attr))
+                .any(|repr| matches!(repr, rustc_attr::ReprPacked { .. }));
+            if has_packed_attribute {
+                quote! { __attribute__((packed)) }
+            } else {
+                quote! {}
+            }
+        };
+
+        // The doc comment of a generic ADT is attached to the primary class template (see
+        // `format_generic_adt`).
+        let (doc_comment, template_prefix) = if !is_specialization {
+            (format_doc_comment(tcx, core.def_id.expect_local()), quote! {})
+        } else {
+            (quote! {}, quote! { template <> })
+        };
+        let keyword = &core.keyword;
+        let core = &core.core;
+
+        let mut prereqs = type_args_prereqs.clone();
+        let (tagged_union_public_decls, tagged_union_private_decls) = match tagged_union.as_ref() {
+            None => (quote! {}, quote! {}),
+            Some(tagged_union) => {
+                prereqs += tagged_union.prereqs.clone();
+                (tagged_union.public_decls.clone(), tagged_union.private_decls.clone())
+            }
+        };
+        let trait_decls: TokenStream = trait_bindings
+            .iter()
+            .map(|trait_bindings| {
+                prereqs += trait_bindings.prereqs.clone();
+                trait_bindings.decls.clone()
+            })
+            .collect();
+        let constexpr_ctor_decl = if !is_constexpr_struct(tcx, ty) {
+            quote! {}
+        } else {
+            let type_info = |field: &Field| {
+                field
+                    .type_info
+                    .as_ref()
+                    .expect("`is_constexpr_struct` implies that all field types are supported")
+            };
+            let params = fields
+                .iter()
+                .sorted_by_key(|field| field.index)
+                .map(|field| {
+                    let cc_name = &field.cc_name;
+                    let cc_type = type_info(field).cc_type.clone().into_tokens(&mut prereqs);
+                    quote! { #cc_type #cc_name }
+                })
+                .collect_vec();
+            // C++17 requires `constexpr` constructors to initialize all the members.
+            let initializers = fields.iter().flat_map(|field| {
+                let cc_name = &field.cc_name;
+                let padding = field.offset_of_next_field - field.offset - type_info(field).size;
+                let padding_initializer = if padding == 0 {
+                    None
+                } else {
+                    let ident = format_ident!("__padding{}", field.index);
+                    Some(quote! { #ident{} })
+                };
+                once(quote! { #cc_name(#cc_name) }).chain(padding_initializer)
+            });
+            // The constructor is private, because it bypasses the invariants that Rust code may
+            // associate with the fields.  It is only used by the bindings of Rust constants of
+            // this type (see `format_valtree_for_cc`).
+            prereqs.includes.insert(input.support_header("internal/const_factory.h"));
+            quote! {
+                constexpr explicit #adt_cc_name( #( #params ),* )
+                    : #( #initializers ),* {}
+                friend struct ::crubit::ConstFactory;
+            }
+        };
+        // `pub` fields of a `#[repr(C)]` union are public members of the C++ union (just like in
+        // Rust, C++ code is responsible for only reading the active member).
+        let has_public_fields = ty.is_union() && core_adt_def.repr().c();
+        let (public_fields, private_fields): (Vec<_>, Vec<_>) = fields
+            .into_iter()
+            .map(|field| {
+                let is_public =
+                    has_public_fields && field.is_public && field.type_info.is_ok();
+                let cc_name = field.cc_name;
+                let tokens = match field.type_info {
+                    Err(err) => {
+                        let size =
+                            Literal::u64_unsuffixed(field.offset_of_next_field - field.offset);
+                        let msg =
+                            format!("Field type has been replaced with a blob of bytes: {err:#}");
+                        quote! {
+                            __COMMENT__ #msg
+                            unsigned char #cc_name[#size];
+                        }
+                    }
+                    Ok(FieldTypeInfo { cc_type, size, tuple_layout }) => {
+                        let padding = field.offset_of_next_field - field.offset - size;
+                        let padding = if padding == 0 {
+                            quote! {}
+                        } else {
+                            let padding = Literal::u64_unsuffixed(padding);
+                            let ident = format_ident!("__padding{}", field.index);
+                            quote! { unsigned char #ident[#padding]; }
+                        };
+                        let tuple_decl = tuple_layout
+                            .map(|tuple_layout| tuple_layout.decl.into_tokens(&mut prereqs));
+                        let cc_type = cc_type.into_tokens(&mut prereqs);
+                        quote! { #tuple_decl #cc_type #cc_name; #padding }
+                    }
+                };
+                (is_public, tokens)
+            })
+            .partition(|(is_public, _)| *is_public);
+        let public_field_decls = if public_fields.is_empty() {
+            quote! {}
+        } else {
+            let tokens = public_fields.into_iter().map(|(_, tokens)| tokens);
+            quote! {
+                public: __NEWLINE__
+                    #( #tokens )*
+            }
+        };
+        let private_fields = private_fields.into_iter().map(|(_, tokens)| tokens);
+        let impl_item_decls = if impl_item_main_apis.is_empty() {
+            quote! {}
+        } else {
+            let cmp = preferred_snippet_order(tcx);
+            let tokens = impl_item_main_apis
+                .into_iter()
+                .sorted_by(|(key1, _), (key2, _)| cmp(key1, key2))
+                .map(|(_key, snippet)| snippet.cc.into_tokens(&mut prereqs))
+                .collect_vec();
+            quote! {
+                public:
+                    #( #tokens )*
+            }
+        };
+        prereqs.fwd_decls.remove(&local_def_id);
+        let assertions_method_decl = if cc_field_assertions.is_empty() {
+            quote! {}
+        } else {
+            // We put the assertions in a method so that they can read private member
+            // variables.
+            quote! { inline static void __crubit_field_offset_assertions(); }
+        };
+
+        CcSnippet {
+            prereqs,
+            tokens: quote! {
+                __NEWLINE__ #doc_comment
+                #template_prefix
+                #keyword alignas(#alignment) #cc_packed_attribute #adt_cc_type final {
+                    #core
+                    #trait_decls
+                    #tagged_union_public_decls
+                    #impl_item_decls
+                    #public_field_decls
+
+                    // TODO(b/271002281): Preserve actual field visibility.
+                    private: __NEWLINE__
+                        #( #private_fields )*
+                        #constexpr_ctor_decl
+                        #tagged_union_private_decls
+                        #assertions_method_decl
+                };
+                __NEWLINE__
+            },
+        }
+    };
+    let impl_details = {
+        let mut cc = {
+            let assertions_method_def = if cc_field_assertions.is_empty() {
+                quote! {}
+            } else {
+                quote! {
+                    inline void #adt_cc_type::__crubit_field_offset_assertions() {
+                        #cc_field_assertions
+                    }
+                }
+            };
+            CcSnippet {
+                prereqs: type_args_prereqs,
+                tokens: quote! {
+                    __NEWLINE__
+                    static_assert(
+                        sizeof(#adt_cc_type) == #size,
+                        "Verify that struct layout didn't change since this header got generated");
+                    static_assert(
+                        alignof(#adt_cc_type) == #alignment,
+                        "Verify that struct layout didn't change since this header got generated");
+                    __NEWLINE__
+                    #assertions_method_def
+                },
+            }
+        };
+        cc.prereqs.defs.insert(local_def_id);
+        if let Some(tagged_union) = tagged_union {
+            cc.tokens.extend(tagged_union.defs);
+            cc.prereqs += tagged_union.prereqs;
+        }
+        let mut rs = {
+            quote! {
+                const _: () = assert!(::std::mem::size_of::<#adt_rs_name>() == #size);
+                const _: () = assert!(::std::mem::align_of::<#adt_rs_name>() == #alignment);
+                #rs_field_assertions
+            }
+        };
+        if core.needs_drop {
+            let thunk_suffix = format_adt_thunk_suffix(tcx, ty);
+            let drop_thunk = format!("__crubit_thunk_drop_{thunk_suffix}");
+            let cc_drop_thunk = format_cc_ident(&drop_thunk).expect("Suffix should be sanitized");
+            let rs_drop_thunk = make_rs_ident(&drop_thunk);
+            let (cc_move_thunk_decl, cc_move_ctor_def, rs_move_thunk) = if is_cc_movable(tcx, ty) {
+                let move_thunk = format!("__crubit_thunk_move_{thunk_suffix}");
+                let cc_move_thunk =
+                    format_cc_ident(&move_thunk).expect("Suffix should be sanitized");
+                let rs_move_thunk = make_rs_ident(&move_thunk);
+                let rs_move_body = format_rs_thunk_body_aborting_on_panic(
+                    tcx,
+                    quote! { __self.write(::core::mem::take(__other)); },
+                );
+                (
+                    quote! {
+                        extern "C" void #cc_move_thunk(#adt_cc_type* __self,
+                                                       #adt_cc_type* __other);
+                    },
+                    quote! {
+                        inline #adt_cc_type::#adt_cc_name(#adt_cc_type&& other) {
+                            __crubit_internal::#cc_move_thunk(this, &other);
+                        }
+                    },
+                    quote! {
+                        #[no_mangle]
+                        extern "C" fn #rs_move_thunk(
+                            __self: &mut ::core::mem::MaybeUninit<#adt_rs_name>,
+                            __other: &mut #adt_rs_name
+                        ) {
+                            #rs_move_body
+                        }
+                    },
+                )
+            } else {
+                (quote! {}, quote! {}, quote! {})
+            };
+            cc.tokens.extend(quote! {
+                namespace __crubit_internal {
+                    extern "C" void #cc_drop_thunk(#adt_cc_type* __self);
+                    #cc_move_thunk_decl
+                }
+                inline #adt_cc_type::~#adt_cc_name() {
+                    __crubit_internal::#cc_drop_thunk(this);
+                }
+                #cc_move_ctor_def
+                __NEWLINE__
+            });
+            // C++ destructors are implicitly `noexcept` - a panic in `Drop` aborts the process
+            // even with `PanicHandling::Report`.
+            let rs_drop_body = format_rs_thunk_body_aborting_on_panic(
+                tcx,
+                quote! { unsafe { ::core::ptr::drop_in_place(__self.as_mut_ptr()) }; },
+            );
+            rs.extend(quote! {
+                #[no_mangle]
+                extern "C" fn #rs_drop_thunk(
+                    __self: &mut ::core::mem::MaybeUninit<#adt_rs_name>
+                ) {
+                    #rs_drop_body
+                }
+                #rs_move_thunk
+            });
+        }
+        if core.has_default_thunk || core.has_clone_thunks {
+            let thunk_suffix = format_adt_thunk_suffix(tcx, ty);
+            let mut cc_thunk_decls = quote! {};
+            let mut cc_defs = quote! {};
+            if core.has_default_thunk {
+                let default_thunk = format!("__crubit_thunk_default_{thunk_suffix}");
+                let cc_default_thunk =
+                    format_cc_ident(&default_thunk).expect("Suffix should be sanitized");
+                let rs_default_thunk = make_rs_ident(&default_thunk);
+                let rs_default_body = format_rs_thunk_body_aborting_on_panic(
+                    tcx,
+                    quote! { __self.write(::core::default::Default::default()); },
+                );
+                cc_thunk_decls.extend(quote! {
+                    extern "C" void #cc_default_thunk(#adt_cc_type* __self);
+                });
+                cc_defs.extend(quote! {
+                    inline #adt_cc_type::#adt_cc_name() {
+                        __crubit_internal::#cc_default_thunk(this);
+                    }
+                });
+                rs.extend(quote! {
+                    #[no_mangle]
+                    extern "C" fn #rs_default_thunk(
+                        __self: &mut ::core::mem::MaybeUninit<#adt_rs_name>
+                    ) {
+                        #rs_default_body
+                    }
+                });
+            }
+            if core.has_clone_thunks {
+                let clone_thunk = format!("__crubit_thunk_clone_{thunk_suffix}");
+                let cc_clone_thunk =
+                    format_cc_ident(&clone_thunk).expect("Suffix should be sanitized");
+                let rs_clone_thunk = make_rs_ident(&clone_thunk);
+                let rs_clone_body = format_rs_thunk_body_aborting_on_panic(
+                    tcx,
+                    quote! { __self.write(::core::clone::Clone::clone(__other)); },
+                );
+                let clone_from_thunk = format!("__crubit_thunk_clone_from_{thunk_suffix}");
+                let cc_clone_from_thunk =
+                    format_cc_ident(&clone_from_thunk).expect("Suffix should be sanitized");
+                let rs_clone_from_thunk = make_rs_ident(&clone_from_thunk);
+                let rs_clone_from_body = format_rs_thunk_body_aborting_on_panic(
+                    tcx,
+                    quote! { ::core::clone::Clone::clone_from(__self, __other); },
+                );
+                cc_thunk_decls.extend(quote! {
+                    extern "C" void #cc_clone_thunk(#adt_cc_type* __self,
+                                                    const #adt_cc_type* __other);
+                    extern "C" void #cc_clone_from_thunk(#adt_cc_type* __self,
+                                                         const #adt_cc_type* __other);
+                });
+                cc_defs.extend(quote! {
+                    inline #adt_cc_type::#adt_cc_name(const #adt_cc_type& other) {
+                        __crubit_internal::#cc_clone_thunk(this, &other);
+                    }
+                    inline #adt_cc_type& #adt_cc_type::operator=(const #adt_cc_type& other) {
+                        // `Clone::clone_from` takes `&mut self` and `&Self` (which may not
+                        // alias), so self-assignment has to be a no-op.
+                        if (this != &other) {
+                            __crubit_internal::#cc_clone_from_thunk(this, &other);
+                        }
+                        return *this;
+                    }
+                });
+                rs.extend(quote! {
+                    #[no_mangle]
+                    extern "C" fn #rs_clone_thunk(
+                        __self: &mut ::core::mem::MaybeUninit<#adt_rs_name>,
+                        __other: &#adt_rs_name
+                    ) {
+                        #rs_clone_body
+                    }
+                    #[no_mangle]
+                    extern "C" fn #rs_clone_from_thunk(
+                        __self: &mut #adt_rs_name,
+                        __other: &#adt_rs_name
+                    ) {
+                        #rs_clone_from_body
+                    }
+                });
+            }
+            cc.tokens.extend(quote! {
+                namespace __crubit_internal {
+                    #cc_thunk_decls
+                }
+                #cc_defs
+                __NEWLINE__
+            });
+        }
+        for trait_bindings in trait_bindings {
+            cc.tokens.extend(trait_bindings.defs);
+            cc.prereqs += trait_bindings.prereqs;
+            rs.extend(trait_bindings.rs_thunks);
+        }
+        let (cc_box_drop, rs_box_drop) = format_box_drop(input, core, &adt_cc_type);
+        cc.tokens.extend(cc_box_drop);
+        rs.extend(rs_box_drop);
+        let (cc_ref_counted_ptr_thunks, rs_ref_counted_ptr_thunks) =
+            format_ref_counted_ptr_thunks(input, core, &adt_cc_type);
+        cc.tokens.extend(cc_ref_counted_ptr_thunks);
+        rs.extend(rs_ref_counted_ptr_thunks);
+        MixedSnippet { cc, rs }
+    };
+
+    let mut result = vec![
+        (SnippetKey { def_id: local_def_id, kind: SnippetKind::MainApi }, main_api.into()),
+        (SnippetKey { def_id: local_def_id, kind: SnippetKind::ImplDetails }, impl_details),
+    ];
+    result.extend(impl_item_other_snippets);
+    result
+}
+
+/// Returns whether the ADT identified by `def_id` has any generic parameters.
+fn is_generic_adt(tcx: TyCtxt, def_id: DefId) -> bool {
+    tcx.generics_of(def_id).count() != 0
+}
+
+/// Formats a generic algebraic data type (an ADT - a struct, an enum, or a
+/// union) identified by `local_def_id`.
+///
+/// Generic ADTs are only supported if the bindings have been requested for an
+/// explicit list of instantiations (see `Input::instantiations`).  The main
+/// API consists of a primary C++ class template (declared, but never defined)
+/// followed by a C++ explicit specialization for each instantiation (see
+/// `format_adt`).  Snippets of all the instantiations are merged, so that
+/// there is only one snippet for each `SnippetKey`.  An instantiation that
+/// maps to the same C++ specialization as an earlier one is replaced with a
+/// comment (see `format_unsupported_instantiation`).
+fn format_generic_adt(
+    input: &Input,
+    local_def_id: LocalDefId,
+) -> Result<Vec<(SnippetKey, MixedSnippet)>> {
+    let tcx = input.tcx;
+    let def_id = local_def_id.to_def_id(); // Convert LocalDefId to DefId.
+    let substs_list = match input.instantiations.get(&def_id) {
+        Some(substs_list) if !substs_list.is_empty() => substs_list,
+        _ => bail!("Generic types are not supported yet (b/259749095)"),
+    };
+
+    let primary_template = {
+        let doc_comment = format_doc_comment(tcx, local_def_id);
+        let template_params = format_template_params(tcx, def_id)?;
+        let keyword = format_adt_keyword(tcx.adt_def(def_id));
+        let cc_name = format_cc_ident(tcx.item_name(def_id).as_str())
+            .context("Error formatting item name")?;
+        CcSnippet::new(quote! {
+            __NEWLINE__ #doc_comment
+            #template_params #keyword #cc_name;
+            __NEWLINE__
+        })
+    };
+    let mut result: Vec<(SnippetKey, MixedSnippet)> = vec![(
+        SnippetKey { def_id: local_def_id, kind: SnippetKind::MainApi },
+        primary_template.into(),
+    )];
+    let mut cc_type_args_seen = HashMap::new();
+    for &substs in substs_list.iter() {
+        let ty = tcx.type_of(def_id).subst(tcx, substs);
+        // `format_ty_for_cc` verifies both `format_adt_core` and the type arguments.
+        format_ty_for_cc(input, ty).with_context(|| {
+            let instantiation = tcx.def_path_str_with_substs(def_id, substs);
+            format!("Error handling the instantiation `{instantiation}`")
+        })?;
+        if let Err(err) =
+            ensure_distinct_cc_type_args(input, def_id, substs, &mut cc_type_args_seen)
+        {
+            let comment = format_unsupported_instantiation(tcx, def_id, substs, err);
+            let (_, MixedSnippet { cc, .. }) = &mut result[0];
+            cc.tokens.extend(comment);
+            continue;
+        }
+        let core = format_adt_core(tcx, ty).expect("Verified by `format_ty_for_cc` above");
+        for (key, snippet) in format_adt(input, &core) {
+            match result.iter_mut().find(|(existing_key, _)| *existing_key == key) {
+                None => result.push((key, snippet)),
+                Some((_, existing_snippet)) => *existing_snippet += snippet,
+            }
+        }
+    }
+    for (key, MixedSnippet { cc, .. }) in result.iter_mut() {
+        if key.def_id == local_def_id && key.kind == SnippetKind::MainApi {
+            // Specializations may refer to each other (e.g. `Wrapper<Wrapper<i32>>` may have a
+            // field of type `Wrapper<i32>`), but they all come after the primary template.
+            // Specializations are emitted in the order of `Input::instantiations`.
+            cc.prereqs.defs.remove(&local_def_id);
+            cc.prereqs.fwd_decls.remove(&local_def_id);
+        }
+    }
+    Ok(result)
+}
+
+/// `&dyn Trait`, `&mut dyn Trait`, `Box<dyn Trait>`, or `Arc<dyn Trait>` - the
+/// kinds of pointers to trait objects that are supported in function
+/// signatures (see `format_trait`).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum DynPtr {
+    /// `&dyn Trait` or `&mut dyn Trait` is represented in C++ as a reference to
+    /// the abstract `Trait` class.  It is only supported as a function
+    /// parameter type - C++ objects are passed to Rust together with a vtable
+    /// of C++ thunks that call their virtual methods.
+    Ref(Mutability),
+
+    /// `Box<dyn Trait>` is represented in C++ as `Trait::DynBox`.
+    Box,
+
+    /// `Arc<dyn Trait>` is represented in C++ as `Trait::DynArc`.
+    Arc,
+}
+
+/// Returns the kind of the pointer and the pointee (the `dyn Trait` type) if
+/// `ty` is a pointer to a trait object (see `DynPtr`).
+fn dyn_ptr<'tcx>(tcx: TyCtxt<'tcx>, ty: Ty<'tcx>) -> Option<(DynPtr, Ty<'tcx>)> {
+    match ty.kind() {
+        ty::TyKind::Ref(_, referent, mutbl) if referent.is_trait() => {
+            Some((DynPtr::Ref(*mutbl), *referent))
+        }
+        _ if ty.is_box() && ty.boxed_ty().is_trait() => Some((DynPtr::Box, ty.boxed_ty())),
+        ty::TyKind::Adt(adt, substs)
+            if tcx.is_diagnostic_item(rustc_span::symbol::sym::Arc, adt.did())
+                && substs.type_at(0).is_trait() =>
+        {
+            Some((DynPtr::Arc, substs.type_at(0)))
+        }
+        _ => None,
+    }
+}
+
+/// Returns the trait of the trait object `dyn_ty` (e.g. `SomeTrait` for `dyn
+/// SomeTrait`), verifying that C++ bindings can be generated for the trait.
+fn get_dyn_trait_def_id(input: &Input, dyn_ty: Ty) -> Result<LocalDefId> {
+    let tcx = input.tcx;
+    let preds = match dyn_ty.kind() {
+        ty::TyKind::Dynamic(preds, _region, _kind) => preds,
+        _ => panic!("`dyn_ptr` should only return trait objects"),
+    };
+    // TODO(b/258260473): Support auto traits (e.g. `dyn SomeTrait + Send`).
+    ensure!(
+        preds.len() == 1,
+        "Trait objects with auto traits or associated types are not supported yet: {dyn_ty}"
+    );
+    let trait_def_id = preds
+        .principal_def_id()
+        .ok_or_else(|| anyhow!("Trait objects without a principal trait are not supported"))?;
+    let local_def_id = trait_def_id.as_local().ok_or_else(|| {
+        anyhow!("Trait objects of traits from other crates are not supported yet")
+    })?;
+    ensure!(
+        public_path(tcx, trait_def_id).is_some(),
+        "Not a public type (renaming re-exports are not supported yet - b/262052635)"
+    );
+    format_trait_core(input, trait_def_id).with_context(|| {
+        format!(
+            "Failed to generate bindings for the definition of `{}`",
+            tcx.item_name(trait_def_id)
+        )
+    })?;
+    Ok(local_def_id)
+}
+
+/// Returns a suffix that should be used for the names of thunks and helper
+/// structs generated for the trait identified by `def_id` (e.g.
+/// `__crubit_thunk_dyn_box_drop_{suffix}`).  The suffix is made of the crate
+/// name, the module path, and the name of the trait.
+fn format_trait_thunk_suffix(tcx: TyCtxt, def_id: DefId) -> String {
+    once(tcx.crate_name(def_id.krate).to_string())
+        .chain(
+            tcx.def_path(def_id)
+                .data
+                .iter()
+                .filter_map(|elem| elem.data.get_opt_name())
+                .map(|name| name.to_string()),
+        )
+        .join("_")
+}
+
+/// A parameter of a trait method (other than `self`) - see `TraitMethod`.
+struct TraitMethodParam {
+    cc_name: TokenStream,
+    cc_type: TokenStream,
+    rs_name: Ident,
+    rs_type: TokenStream,
+}
+
+/// A method of a trait that has C++ bindings (see `format_trait_core`).
+struct TraitMethod {
+    def_id: DefId,
+
+    /// `Mutability::Not` for `&self` methods and `Mutability::Mut` for `&mut
+    /// self` methods.
+    self_mutbl: Mutability,
+
+    cc_name: TokenStream,
+    rs_name: Ident,
+    params: Vec<TraitMethodParam>,
+    cc_ret_type: TokenStream,
+    rs_ret_type: TokenStream,
+}
+
+/// Represents the bindings of a trait, shared by `format_trait` and by the
+/// code that formats trait objects in function signatures.
+struct TraitCoreBindings {
+    /// C++ name of the trait (e.g. `SomeTrait`).
+    cc_name: TokenStream,
+
+    /// Fully-qualified Rust name of the trait (e.g. `::some_crate::SomeTrait`).
+    rs_name: TokenStream,
+
+    /// Suffix of the names of thunks and helper structs (see
+    /// `format_trait_thunk_suffix`).
+    thunk_suffix: String,
+
+    /// Methods that can be called through a trait object.  Methods with a
+    /// `where Self: Sized` bound are skipped.
+    methods: Vec<TraitMethod>,
+
+    prereqs: CcPrerequisites,
+}
+
+/// Formats the "core" of the bindings of the trait identified by `def_id`.
+///
+/// The bindings are built on top of trait objects, and therefore only
+/// object-safe traits are supported.  Additionally, all parameters and return
+/// values of the methods need to have a C-compatible ABI, because they are
+/// passed directly (without any marshalling) through the vtable of the C++
+/// implementations of the trait.
+fn format_trait_core(input: &Input, def_id: DefId) -> Result<TraitCoreBindings> {
+    let tcx = input.tcx;
+    // TODO(b/259749095): Support generic traits.
+    ensure!(tcx.generics_of(def_id).count() == 1, "Generic traits are not supported yet");
+    ensure!(
+        tcx.super_predicates_of(def_id).predicates.is_empty(),
+        "Traits with supertraits are not supported yet"
+    );
+    ensure!(
+        tcx.trait_def(def_id).unsafety == Unsafety::Normal,
+        "`unsafe` traits are not supported yet"
+    );
+    ensure!(tcx.check_is_object_safe(def_id), "Only object-safe traits are supported");
+
+    let cc_name =
+        format_cc_ident(tcx.item_name(def_id).as_str()).context("Error formatting trait name")?;
+    let rs_name = FullyQualifiedName::new(tcx, def_id).format_for_rs();
+    let thunk_suffix = format_trait_thunk_suffix(tcx, def_id);
+    let mut prereqs = CcPrerequisites::default();
+    let mut methods = vec![];
+    for item in tcx.associated_items(def_id).in_definition_order() {
+        let method = format_trait_method(input, item, &mut prereqs)
+            .with_context(|| format!("Error handling `{}`", item.name))?;
+        methods.extend(method);
+    }
+    prereqs.move_defs_to_fwd_decls();
+    Ok(TraitCoreBindings { cc_name, rs_name, thunk_suffix, methods, prereqs })
+}
+
+/// Formats a method of a trait (see `format_trait_core`).  Returns `None` if
+/// the method can't be called through a trait object.
+fn format_trait_method(
+    input: &Input,
+    item: &ty::AssocItem,
+    prereqs: &mut CcPrerequisites,
+) -> Result<Option<TraitMethod>> {
+    let tcx = input.tcx;
+    ensure!(
+        item.kind == ty::AssocKind::Fn,
+        "Associated types and constants of traits are not supported yet"
+    );
+
+    // Methods with a `where Self: Sized` bound can't be called through a trait object.
+    let requires_sized_self =
+        tcx.predicates_of(item.def_id).predicates.iter().any(|(predicate, _span)| match predicate
+            .kind()
+            .skip_binder()
+        {
+            ty::PredicateKind::Clause(ty::Clause::Trait(trait_predicate)) => {
+                Some(trait_predicate.def_id()) == tcx.lang_items().sized_trait()
+                    && trait_predicate.self_ty().is_param(0)
+            }
+            _ => false,
+        });
+    if requires_sized_self {
+        // C++ implementations of the trait couldn't provide such a method.
+        ensure!(
+            item.defaultness(tcx).has_value(),
+            "Methods with a `where Self: Sized` bound need a default implementation"
+        );
+        return Ok(None);
+    }
+
+    let (sig, _) = liberate_fn_sig(tcx, item.def_id, tcx.fn_sig(item.def_id).subst_identity());
+    ensure!(sig.unsafety == Unsafety::Normal, "`unsafe` methods are not supported yet");
+    let self_mutbl = match sig.inputs().first().map(|ty| ty.kind()) {
+        Some(ty::TyKind::Ref(_, self_ty, mutbl))
+            if item.fn_has_self_parameter && self_ty.is_param(0) =>
+        {
+            *mutbl
+        }
+        _ => bail!("Only `&self` and `&mut self` receivers are supported yet"),
+    };
+
+    let mut params = vec![];
+    let names = tcx.fn_arg_names(item.def_id).iter();
+    for (i, (name, &ty)) in names.zip(sig.inputs().iter()).enumerate().skip(1) {
+        // Trait objects are marshalled by the thunks of `format_fn`, but the methods of
+        // C++ implementations of the trait are called without thunks.
+        ensure!(
+            dyn_ptr(tcx, ty).is_none(),
+            "Trait objects are not supported in signatures of trait methods yet"
+        );
+        let cc_type = format_ty_for_cc(input, ty)
+            .with_context(|| format!("Error handling parameter #{i}"))?
+            .into_tokens(prereqs);
+        ensure!(
+            is_c_abi_compatible_by_value(ty),
+            "Parameters of trait methods need to have a C-compatible ABI: {ty}"
+        );
+        let (cc_name, rs_name) = if name.as_str().is_empty() || name.as_str() == "_" {
+            (format_cc_ident(&format!("__param_{i}"))?, format_ident!("__param_{i}"))
+        } else {
+            let cc_name = format_cc_ident(name.as_str())
+                .unwrap_or_else(|_err| format_cc_ident(&format!("__param_{i}")).unwrap());
+            (cc_name, make_rs_ident(name.as_str()))
+        };
+        let rs_type = format_ty_for_rs(tcx, ty)?;
+        params.push(TraitMethodParam { cc_name, cc_type, rs_name, rs_type });
+    }
+
+    let ret_ty = sig.output();
+    let (cc_ret_type, rs_ret_type) = if ret_ty.is_unit() {
+        (quote! { void }, quote! { () })
+    } else {
+        ensure!(!ret_ty.is_never(), "The never type `!` is not supported in trait methods yet");
+        ensure!(
+            !ret_ty.is_ref(),
+            "Trait methods returning references are not supported yet (b/258235219)"
+        );
+        ensure!(
+            dyn_ptr(tcx, ret_ty).is_none(),
+            "Trait objects are not supported in signatures of trait methods yet"
+        );
+        let cc_ret_type = format_ty_for_cc(input, ret_ty)
+            .context("Error formatting the return type")?
+            .into_tokens(prereqs);
+        ensure!(
+            is_c_abi_compatible_by_value(ret_ty),
+            "Return types of trait methods need to have a C-compatible ABI: {ret_ty}"
+        );
+        (cc_ret_type, format_ty_for_rs(tcx, ret_ty)?)
+    };
+
+    Ok(Some(TraitMethod {
+        def_id: item.def_id,
+        self_mutbl,
+        cc_name: format_cc_ident(item.name.as_str()).context("Error formatting method name")?,
+        rs_name: make_rs_ident(item.name.as_str()),
+        params,
+        cc_ret_type,
+        rs_ret_type,
+    }))
+}
+
+/// Formats the trait identified by `local_def_id`.  The main API consists of:
+/// - An abstract C++ class with a pure virtual method for each trait method.
+///   C++ classes can derive from it to implement the trait - such objects can
+///   be passed to Rust functions that take `&dyn Trait` or `&mut dyn Trait`
+///   (see `DynPtr::Ref`).
+/// - `Trait::DynBox` and `Trait::DynArc` - C++ classes that wrap `Box<dyn
+///   Trait>` and `Arc<dyn Trait>` (holding Rust implementations of the trait)
+///   and that forward method calls to exported Rust thunks.
+///
+/// `DynArc` only provides the `&self` methods of the trait, because `Arc<dyn
+/// Trait>` doesn't give mutable access to the trait object.
+///
+/// The implementation details consist of the vtable that is passed to Rust
+/// together with a C++ object (and of the Rust adapter that implements the
+/// trait by calling through this vtable) and of the thunks of `DynBox` and
+/// `DynArc`.  The vtable entries are `noexcept` lambdas, because they are
+/// called from Rust (unwinding across the FFI boundary is undefined behavior);
+/// an exception thrown by a C++ implementation of a trait method terminates
+/// the process.
+fn format_trait(
+    input: &Input,
+    local_def_id: LocalDefId,
+) -> Result<Vec<(SnippetKey, MixedSnippet)>> {
+    let tcx = input.tcx;
+    let def_id = local_def_id.to_def_id(); // Convert LocalDefId to DefId.
+    let TraitCoreBindings {
+        cc_name: trait_cc_name,
+        rs_name: trait_rs_name,
+        thunk_suffix,
+        methods,
+        prereqs,
+    } = format_trait_core(input, def_id)?;
+
+    let format_cc_params = |method: &TraitMethod| -> Vec<TokenStream> {
+        method
+            .params
+            .iter()
+            .map(|TraitMethodParam { cc_name, cc_type, .. }| quote! { #cc_type #cc_name })
+            .collect_vec()
+    };
+    let format_cc_const_qualifier = |method: &TraitMethod| match method.self_mutbl {
+        Mutability::Not => quote! { const },
+        Mutability::Mut => quote! {},
+    };
+
+    // `Arc<dyn Trait>` only gives shared access to the trait object, so `&mut self`
+    // methods can't be called through `DynArc`.
+    let is_dyn_wrapper_method = |wrapper: &TokenStream, method: &TraitMethod| {
+        method.self_mutbl == Mutability::Not || wrapper.to_string() != "DynArc"
+    };
+
+    let main_api = {
+        let doc_comment = format_doc_comment(tcx, local_def_id);
+        let virtual_methods = methods
+            .iter()
+            .map(|method| {
+                let doc_comment = format_doc_comment(tcx, method.def_id.expect_local());
+                let TraitMethod { cc_name, cc_ret_type, .. } = method;
+                let params = format_cc_params(method);
+                let const_qualifier = format_cc_const_qualifier(method);
+                quote! {
+                    __NEWLINE__ #doc_comment
+                    virtual #cc_ret_type #cc_name( #( #params ),* ) #const_qualifier = 0;
+                }
+            })
+            .collect_vec();
+        let format_wrapper = |wrapper: &TokenStream, comment: &str, copy_decls: TokenStream| {
+            let wrapper_methods = methods
+                .iter()
+                .filter(|method| is_dyn_wrapper_method(wrapper, method))
+                .map(|method| {
+                    let TraitMethod { cc_name, cc_ret_type, .. } = method;
+                    let params = format_cc_params(method);
+                    let const_qualifier = format_cc_const_qualifier(method);
+                    quote! { #cc_ret_type #cc_name( #( #params ),* ) #const_qualifier; }
+                })
+                .collect_vec();
+            quote! {
+                __NEWLINE__ __COMMENT__ #comment
+                class #trait_cc_name::#wrapper final {
+                  public:
+                    #wrapper(#wrapper&&);
+                    #wrapper& operator=(#wrapper&&);
+                    #copy_decls
+                    ~#wrapper();
+                    __NEWLINE__
+                    #( #wrapper_methods )*
+                    __NEWLINE__
+                  private:
+                    __COMMENT__ "`Option<...>` - `None` is left behind in a moved-from object."
+                    alignas(void*) unsigned char __opaque_blob_of_bytes[2 * sizeof(void*)];
+                };
+            }
+        };
+        let dyn_box = format_wrapper(
+            &quote! { DynBox },
+            "`Box<dyn ...>` - owns a Rust implementation of the trait.",
+            quote! {
+                DynBox(const DynBox&) = delete;
+                DynBox& operator=(const DynBox&) = delete;
+            },
+        );
+        let dyn_arc = format_wrapper(
+            &quote! { DynArc },
+            "`Arc<dyn ...>` - shares a Rust implementation of the trait.  Methods \
+             that take `&mut self` are not available.",
+            quote! {
+                DynArc(const DynArc&);
+                DynArc& operator=(const DynArc&);
+            },
+        );
+        CcSnippet {
+            prereqs,
+            tokens: quote! {
+                __NEWLINE__ #doc_comment
+                class #trait_cc_name {
+                  public:
+                    class DynBox;
+                    class DynArc;
+                    struct __CrubitVtable;
+                    __COMMENT__ "Rust calls the methods of C++ implementations through this \
+                                 vtable - they must not throw exceptions."
+                    static const __CrubitVtable __crubit_vtable;
+                    __NEWLINE__
+                    virtual ~#trait_cc_name() = default;
+                    #( #virtual_methods )*
+                };
+                #dyn_box
+                #dyn_arc
+                __NEWLINE__
+            },
+        }
+    };
+
+    let impl_details = {
+        let mut cc = quote! {};
+        let mut rs = quote! {};
+
+        // The vtable of C++ implementations of the trait and its Rust counterpart.
+        let vtable_struct = format_ident!("__CrubitCcVtable_{thunk_suffix}");
+        let object_struct = format_ident!("__CrubitCcObject_{thunk_suffix}");
+        {
+            let mut cc_vtable_fields = vec![];
+            let mut cc_vtable_entries = vec![];
+            let mut rs_vtable_fields = vec![];
+            let mut rs_impl_methods = vec![];
+            for method in methods.iter() {
+                let TraitMethod { cc_name, rs_name, cc_ret_type, rs_ret_type, .. } = method;
+                let const_qualifier = format_cc_const_qualifier(method);
+                let cc_params =
+                    once(quote! { #const_qualifier void* __self }).chain(format_cc_params(method));
+                let cc_param_types = once(quote! { #const_qualifier void* })
+                    .chain(method.params.iter().map(|param| param.cc_type.clone()));
+                let cc_args = method.params.iter().map(|param| &param.cc_name);
+                let rs_param_names = method.params.iter().map(|param| &param.rs_name).collect_vec();
+                let rs_param_types = method.params.iter().map(|param| &param.rs_type).collect_vec();
+                let (rs_self_ptr, rs_self) = match method.self_mutbl {
+                    Mutability::Not => (quote! { *const ::core::ffi::c_void }, quote! { &self }),
+                    Mutability::Mut => (quote! { *mut ::core::ffi::c_void }, quote! { &mut self }),
+                };
+                cc_vtable_fields.push(quote! {
+                    #cc_ret_type (*#cc_name)( #( #cc_param_types ),* );
+                });
+                cc_vtable_entries.push(quote! {
+                    []( #( #cc_params ),* ) noexcept -> #cc_ret_type {
+                        return static_cast<#const_qualifier #trait_cc_name*>(__self)
+                            ->#cc_name( #( #cc_args ),* );
+                    },
+                });
+                rs_vtable_fields.push(quote! {
+                    #rs_name: extern "C" fn(#rs_self_ptr, #( #rs_param_types ),* ) -> #rs_ret_type,
+                });
+                rs_impl_methods.push(quote! {
+                    fn #rs_name(#rs_self, #( #rs_param_names: #rs_param_types ),* )
+                        -> #rs_ret_type
+                    {
+                        (self.vtable.#rs_name)(self.obj, #( #rs_param_names ),* )
+                    }
+                });
+            }
+            cc.extend(quote! {
+                __NEWLINE__
+                struct #trait_cc_name::__CrubitVtable final {
+                    #( #cc_vtable_fields )*
+                };
+                inline const #trait_cc_name::__CrubitVtable #trait_cc_name::__crubit_vtable = {
+                    #( #cc_vtable_entries )*
+                };
+                __NEWLINE__
+            });
+            rs.extend(quote! {
+                #[allow(non_camel_case_types)]
+                #[repr(C)]
+                struct #vtable_struct {
+                    #( #rs_vtable_fields )*
+                }
+                #[allow(non_camel_case_types)]
+                struct #object_struct {
+                    obj: *mut ::core::ffi::c_void,
+                    vtable: &'static #vtable_struct,
+                }
+                impl #trait_rs_name for #object_struct {
+                    #( #rs_impl_methods )*
+                }
+            });
+        }
+
+        // Thunks of `DynBox` and `DynArc`.
+        let wrappers = [
+            ("box", quote! { DynBox }, quote! { ::std::boxed::Box<dyn #trait_rs_name> }),
+            ("arc", quote! { DynArc }, quote! { ::std::sync::Arc<dyn #trait_rs_name> }),
+        ];
+        for (thunk_infix, wrapper, ptr_rs_type) in wrappers.into_iter() {
+            let cc_type = quote! { #trait_cc_name::#wrapper };
+            let rs_type = quote! { ::core::option::Option<#ptr_rs_type> };
+            let thunk = |name: &str| {
+                let thunk = format!("__crubit_thunk_dyn_{thunk_infix}_{name}_{thunk_suffix}");
+                (
+                    format_cc_ident(&thunk).expect("Suffix should be sanitized"),
+                    make_rs_ident(&thunk),
+                )
+            };
+            let mut cc_thunk_decls = quote! {};
+            let mut cc_defs = quote! {};
+
+            let (cc_drop_thunk, rs_drop_thunk) = thunk("drop");
+            let (cc_move_thunk, rs_move_thunk) = thunk("move");
+            let (cc_move_assign_thunk, rs_move_assign_thunk) = thunk("move_assign");
+            let rs_drop_body = format_rs_thunk_body_aborting_on_panic(
+                tcx,
+                quote! { unsafe { ::core::ptr::drop_in_place(__self.as_mut_ptr()) }; },
+            );
+            let rs_move_body = format_rs_thunk_body_aborting_on_panic(
+                tcx,
+                quote! { __self.write(__other.take()); },
+            );
+            let rs_move_assign_body =
+                format_rs_thunk_body_aborting_on_panic(tcx, quote! { *__self = __other.take(); });
+            cc_thunk_decls.extend(quote! {
+                extern "C" void #cc_drop_thunk(#cc_type* __self);
+                extern "C" void #cc_move_thunk(#cc_type* __self, #cc_type* __other);
+                extern "C" void #cc_move_assign_thunk(#cc_type* __self, #cc_type* __other);
+            });
+            cc_defs.extend(quote! {
+                inline #cc_type::~#wrapper() {
+                    __crubit_internal::#cc_drop_thunk(this);
+                }
+                inline #cc_type::#wrapper(#wrapper&& other) {
+                    __crubit_internal::#cc_move_thunk(this, &other);
+                }
+                inline #cc_type& #cc_type::operator=(#wrapper&& other) {
+                    // The Rust thunk takes two `&mut` references (which may not alias), so
+                    // self-assignment has to be a no-op.
+                    if (this != &other) {
+                        __crubit_internal::#cc_move_assign_thunk(this, &other);
+                    }
+                    return *this;
+                }
+            });
+            rs.extend(quote! {
+                const _: () = assert!(
+                    ::std::mem::size_of::<#rs_type>() == 2 * ::std::mem::size_of::<usize>());
+                const _: () = assert!(
+                    ::std::mem::align_of::<#rs_type>() == ::std::mem::align_of::<usize>());
+                #[no_mangle]
+                extern "C" fn #rs_drop_thunk(__self: &mut ::core::mem::MaybeUninit<#rs_type>) {
+                    #rs_drop_body
+                }
+                #[no_mangle]
+                extern "C" fn #rs_move_thunk(
+                    __self: &mut ::core::mem::MaybeUninit<#rs_type>,
+                    __other: &mut #rs_type
+                ) {
+                    #rs_move_body
+                }
+                #[no_mangle]
+                extern "C" fn #rs_move_assign_thunk(__self: &mut #rs_type, __other: &mut #rs_type) {
+                    #rs_move_assign_body
+                }
+            });
+
+            if thunk_infix == "arc" {
+                let (cc_clone_thunk, rs_clone_thunk) = thunk("clone");
+                let (cc_clone_from_thunk, rs_clone_from_thunk) = thunk("clone_from");
+                let rs_clone_body = format_rs_thunk_body_aborting_on_panic(
+                    tcx,
+                    quote! { __self.write(::core::clone::Clone::clone(__other)); },
+                );
+                let rs_clone_from_body = format_rs_thunk_body_aborting_on_panic(
+                    tcx,
+                    quote! { ::core::clone::Clone::clone_from(__self, __other); },
+                );
+                cc_thunk_decls.extend(quote! {
+                    extern "C" void #cc_clone_thunk(#cc_type* __self, const #cc_type* __other);
+                    extern "C" void #cc_clone_from_thunk(#cc_type* __self,
+                                                         const #cc_type* __other);
+                });
+                cc_defs.extend(quote! {
+                    inline #cc_type::#wrapper(const #wrapper& other) {
+                        __crubit_internal::#cc_clone_thunk(this, &other);
+                    }
+                    inline #cc_type& #cc_type::operator=(const #wrapper& other) {
+                        // `Clone::clone_from` takes `&mut self` and `&Self` (which may not
+                        // alias), so self-assignment has to be a no-op.
+                        if (this != &other) {
+                            __crubit_internal::#cc_clone_from_thunk(this, &other);
+                        }
+                        return *this;
+                    }
+                });
+                rs.extend(quote! {
+                    #[no_mangle]
+                    extern "C" fn #rs_clone_thunk(
+                        __self: &mut ::core::mem::MaybeUninit<#rs_type>,
+                        __other: &#rs_type
+                    ) {
+                        #rs_clone_body
+                    }
+                    #[no_mangle]
+                    extern "C" fn #rs_clone_from_thunk(__self: &mut #rs_type, __other: &#rs_type) {
+                        #rs_clone_from_body
+                    }
+                });
+            }
+
+            let wrapper_methods =
+                methods.iter().filter(|method| is_dyn_wrapper_method(&wrapper, method));
+            for method in wrapper_methods {
+                let TraitMethod { cc_name, rs_name, cc_ret_type, rs_ret_type, .. } = method;
+                let (cc_method_thunk, rs_method_thunk) =
+                    thunk(&format!("fn_{}", tcx.item_name(method.def_id)));
+                let cc_params = format_cc_params(method);
+                let const_qualifier = format_cc_const_qualifier(method);
+                let cc_thunk_params = once(quote! { #const_qualifier #cc_type* __self })
+                    .chain(cc_params.iter().cloned());
+                let cc_thunk_args = once(quote! { this })
+                    .chain(method.params.iter().map(|param| param.cc_name.clone()));
+                let rs_param_names = method.params.iter().map(|param| &param.rs_name).collect_vec();
+                let rs_param_types = method.params.iter().map(|param| &param.rs_type);
+                let (rs_self_type, rs_self) = match method.self_mutbl {
+                    Mutability::Not => (
+                        quote! { &#rs_type },
+                        quote! { __self.as_deref().expect("Moved-from trait object") },
+                    ),
+                    Mutability::Mut => (
+                        quote! { &mut #rs_type },
+                        quote! { __self.as_deref_mut().expect("Moved-from trait object") },
+                    ),
+                };
+                let rs_method_body = format_rs_thunk_body_aborting_on_panic(
+                    tcx,
+                    quote! { #trait_rs_name::#rs_name(#rs_self, #( #rs_param_names ),* ) },
+                );
+                cc_thunk_decls.extend(quote! {
+                    extern "C" #cc_ret_type #cc_method_thunk( #( #cc_thunk_params ),* );
+                });
+                cc_defs.extend(quote! {
+                    inline #cc_ret_type #cc_type::#cc_name( #( #cc_params ),* ) #const_qualifier {
+                        return __crubit_internal::#cc_method_thunk( #( #cc_thunk_args ),* );
+                    }
+                });
+                rs.extend(quote! {
+                    #[no_mangle]
+                    extern "C" fn #rs_method_thunk(
+                        __self: #rs_self_type, #( #rs_param_names: #rs_param_types ),*
+                    ) -> #rs_ret_type {
+                        #rs_method_body
+                    }
+                });
+            }
+
+            cc.extend(quote! {
+                namespace __crubit_internal {
+                    #cc_thunk_decls
+                }
+                #cc_defs
+                __NEWLINE__
+            });
+        }
+
+        let mut prereqs = CcPrerequisites::default();
+        prereqs.defs.insert(local_def_id);
+        MixedSnippet { cc: CcSnippet { prereqs, tokens: cc }, rs }
+    };
+
+    Ok(vec![
+        (SnippetKey { def_id: local_def_id, kind: SnippetKind::MainApi }, main_api.into()),
+        (SnippetKey { def_id: local_def_id, kind: SnippetKind::ImplDetails }, impl_details),
+    ])
+}
+
+/// Returns whether `ty` is a struct that can be constructed in C++ constant
+/// expressions - i.e. a struct that doesn't need drop glue and that only has
+/// fields of scalar types.  C++ bindings of such structs provide a private
+/// `constexpr` constructor (see `format_adt`) which is used (via
+/// `crubit::ConstFactory`) to initialize the C++ constants that represent Rust
+/// constants of the struct type (see `format_valtree_for_cc`).
+fn is_constexpr_struct<'tcx>(tcx: TyCtxt<'tcx>, ty: Ty<'tcx>) -> bool {
+    match *ty.kind() {
+        ty::TyKind::Adt(adt_def, substs) if adt_def.is_struct() => {
+            !ty.needs_drop(tcx, ty::ParamEnv::empty())
+                && adt_def.all_fields().all(|field| {
+                    matches!(
+                        field.ty(tcx, substs).kind(),
+                        ty::TyKind::Bool
+                            | ty::TyKind::Char
+                            | ty::TyKind::Int(_)
+                            | ty::TyKind::Uint(_)
+                            | ty::TyKind::Float(_)
+                    )
+                })
+        }
+        _ => false,
+    }
+}
+
+/// Formats an integer constant as a C++ literal.  `bits` holds the value of
+/// the constant, which has the given `size` and signedness.
+fn format_int_literal_for_cc(bits: u128, size: Size, signed: bool) -> TokenStream {
+    if signed {
+        let value = size.sign_extend(bits) as i128;
+        if value == i64::MIN as i128 {
+            // `-9223372036854775808` would negate a literal that doesn't fit into a `long long`.
+            let max = Literal::i64_unsuffixed(i64::MAX);
+            quote! { (-#max - 1) }
+        } else {
+            Literal::i128_unsuffixed(value).into_token_stream()
+        }
+    } else if bits > i64::MAX as u128 {
+        // Unsuffixed decimal literals that don't fit into a `long long` are ill-formed, so the
+        // value is spelled as a conversion of its two's complement representation.
+        let signed_value = format_int_literal_for_cc(bits, size, true);
+        quote! { static_cast<std::uint64_t>(#signed_value) }
+    } else {
+        Literal::u128_unsuffixed(bits).into_token_stream()
+    }
+}
+
+/// Formats `valtree` (the value of a constant of type `ty`) as a C++ constant
+/// expression - e.g. `42`, `true`, or
+/// `::crubit::ConstFactory::Make<::some_crate::Point>(1, 2)`.
+fn format_valtree_for_cc<'tcx>(
+    input: &Input<'tcx>,
+    ty: Ty<'tcx>,
+    valtree: ty::ValTree<'tcx>,
+) -> Result<CcSnippet> {
+    let tcx = input.tcx;
+    let leaf_bits = || -> Result<(u128, Size)> {
+        let size = get_layout(tcx, ty)?.size();
+        Ok((valtree.unwrap_leaf().assert_bits(size), size))
+    };
+    let CcSnippet { tokens: cc_type, mut prereqs } = format_ty_for_cc(input, ty)?;
+    let tokens = match *ty.kind() {
+        ty::TyKind::Bool => {
+            let (bits, _) = leaf_bits()?;
+            if bits != 0 {
+                quote! { true }
+            } else {
+                quote! { false }
+            }
+        }
+        ty::TyKind::Char => {
+            let (bits, _) = leaf_bits()?;
+            let value = Literal::u128_unsuffixed(bits);
+            quote! { #cc_type::from_u32(#value).value() }
+        }
+        ty::TyKind::Int(ty::IntTy::I128) | ty::TyKind::Uint(ty::UintTy::U128) => {
+            bail!("Constants of 128-bit integer types are not supported yet (b/254094650)")
+        }
+        ty::TyKind::Int(_) => {
+            let (bits, size) = leaf_bits()?;
+            format_int_literal_for_cc(bits, size, /* signed = */ true)
+        }
+        ty::TyKind::Uint(_) => {
+            let (bits, size) = leaf_bits()?;
+            format_int_literal_for_cc(bits, size, /* signed = */ false)
+        }
+        ty::TyKind::Float(float_ty) => {
+            let (bits, _) = leaf_bits()?;
+            // Widening `f32` to `f64` is exact, so the `f64` literal converts back to the
+            // original `f32` value.
+            let value = match float_ty {
+                ty::FloatTy::F32 => f32::from_bits(bits as u32) as f64,
+                ty::FloatTy::F64 => f64::from_bits(bits as u64),
+            };
+            if value.is_finite() {
+                Literal::f64_unsuffixed(value).into_token_stream()
+            } else {
+                prereqs.includes.insert(CcInclude::limits());
+                if value.is_nan() {
+                    quote! { std::numeric_limits<#cc_type>::quiet_NaN() }
+                } else if value.is_sign_negative() {
+                    quote! { -std::numeric_limits<#cc_type>::infinity() }
+                } else {
+                    quote! { std::numeric_limits<#cc_type>::infinity() }
+                }
+            }
+        }
+        ty::TyKind::Adt(adt_def, substs) if adt_def.is_struct() => {
+            ensure!(
+                is_constexpr_struct(tcx, ty),
+                "Constants of struct types are only supported if the struct doesn't need drop \
+                 glue and only has fields of scalar types: {ty}"
+            );
+            let field_values = adt_def
+                .non_enum_variant()
+                .fields
+                .iter()
+                .zip(valtree.unwrap_branch())
+                .map(|(field, &field_valtree)| {
+                    format_valtree_for_cc(input, field.ty(tcx, substs), field_valtree)
+                })
+                .collect::<Result<Vec<_>>>()?
+                .into_iter()
+                .map(|field_value| field_value.into_tokens(&mut prereqs))
+                .collect_vec();
+            prereqs.includes.insert(input.support_header("internal/const_factory.h"));
+            quote! { ::crubit::ConstFactory::Make<#cc_type>( #( #field_values ),* ) }
+        }
+        _ => bail!("Only constants of scalar and struct types are supported yet: {ty}"),
+    };
+    Ok(CcSnippet { prereqs, tokens })
+}
+
+/// Evaluates the constant identified by `def_id` (a `const` item or an
+/// associated constant from an `impl` block) and formats its value as a C++
+/// constant expression (see `format_valtree_for_cc`).
+fn format_const_value<'tcx>(input: &Input<'tcx>, def_id: DefId, ty: Ty<'tcx>) -> Result<CcSnippet> {
+    let tcx = input.tcx;
+    let global_id = GlobalId { instance: ty::Instance::mono(tcx, def_id), promoted: None };
+    let valtree =
+        match tcx.const_eval_global_id_for_typeck(ty::ParamEnv::reveal_all(), global_id, None) {
+            Ok(Some(valtree)) => valtree,
+            // Values that contain raw pointers, unions, etc. can't be represented as a valtree.
+            Ok(None) => bail!("Only constants of scalar and struct types are supported yet: {ty}"),
+            Err(_) => bail!("Failed to evaluate the constant"),
+        };
+    format_valtree_for_cc(input, ty, valtree)
+}
+
+/// Formats a `const` item identified by `local_def_id` as a C++ `constexpr`
+/// variable (e.g. `inline constexpr std::int32_t MAX_SIZE = 42;`).
+fn format_const(
+    input: &Input,
+    local_def_id: LocalDefId,
+) -> Result<Vec<(SnippetKey, MixedSnippet)>> {
+    let tcx = input.tcx;
+    let def_id = local_def_id.to_def_id();
+
+    // Unnamed constants (`const _: () = ...;`) are only useful for their compile-time
+    // side effects (e.g. static assertions).
+    let name = tcx.item_name(def_id);
+    if name == kw::Underscore {
+        return Ok(vec![]);
+    }
+    let cc_name = format_cc_ident(name.as_str()).context("Error formatting item name")?;
+
+    let ty = tcx.type_of(def_id).subst_identity();
+    let mut prereqs = CcPrerequisites::default();
+    let value = format_const_value(input, def_id, ty)?.into_tokens(&mut prereqs);
+    let cc_type = format_ty_for_cc(input, ty)?.into_tokens(&mut prereqs);
+    let doc_comment = format_doc_comment(tcx, local_def_id);
+    let main_api = CcSnippet {
+        prereqs,
+        tokens: quote! {
+            __NEWLINE__ #doc_comment
+            inline constexpr #cc_type #cc_name = #value;
+            __NEWLINE__
+        },
+    };
+    Ok(vec![(SnippetKey { def_id: local_def_id, kind: SnippetKind::MainApi }, main_api.into())])
+}
+
+/// Formats an associated constant (identified by `local_def_id`) from an
+/// inherent `impl` of the ADT identified by `adt_def_id` (and spelled as
+/// `adt_cc_type` in C++) as a `static constexpr` member of the C++ class.
+fn format_assoc_const(
+    input: &Input,
+    local_def_id: LocalDefId,
+    adt_def_id: LocalDefId,
+    adt_cc_type: &TokenStream,
+) -> Result<Vec<(SnippetKey, MixedSnippet)>> {
+    let tcx = input.tcx;
+    let def_id = local_def_id.to_def_id();
+    let cc_name =
+        format_cc_ident(tcx.item_name(def_id).as_str()).context("Error formatting item name")?;
+    let ty = tcx.type_of(def_id).subst_identity();
+    let mut prereqs = CcPrerequisites::default();
+    let value = format_const_value(input, def_id, ty)?.into_tokens(&mut prereqs);
+    let cc_type = format_ty_for_cc(input, ty)?.into_tokens(&mut prereqs);
+    let doc_comment = format_doc_comment(tcx, local_def_id);
+
+    if !ty.is_adt() {
+        let main_api = CcSnippet {
+            prereqs,
+            tokens: quote! {
+                __NEWLINE__ #doc_comment
+                static constexpr #cc_type #cc_name = #value;
+                __NEWLINE__
+            },
+        };
+        return Ok(vec![(
+            SnippetKey { def_id: local_def_id, kind: SnippetKind::MainApi },
+            main_api.into(),
+        )]);
+    }
+
+    // A `constexpr` static member can't have a class type that is incomplete at the point of
+    // the declaration (e.g. the type of the enclosing class).  Therefore the member is only
+    // declared as `const` within the class and defined as `constexpr` after the class (this
+    // mimics how `rs_std::rs_char::MAX` is defined).
+    let main_api = {
+        let mut prereqs = prereqs.clone();
+        prereqs.move_defs_to_fwd_decls();
+        CcSnippet {
+            prereqs,
+            tokens: quote! {
+                __NEWLINE__ #doc_comment
+                static const #cc_type #cc_name;
+                __NEWLINE__
+            },
+        }
+    };
+    let impl_details = {
+        prereqs.defs.insert(adt_def_id);
+        CcSnippet {
+            prereqs,
+            tokens: quote! {
+                __NEWLINE__
+                constexpr #cc_type #adt_cc_type::#cc_name = #value;
+                __NEWLINE__
+            },
+        }
+    };
+    Ok(vec![
+        (SnippetKey { def_id: local_def_id, kind: SnippetKind::MainApi }, main_api.into()),
+        (SnippetKey { def_id: local_def_id, kind: SnippetKind::ImplDetails }, impl_details.into()),
+    ])
+}
+
+/// Formats a `static` item identified by `local_def_id`.
+///
+/// The generated Rust code re-exports a pointer to the `static` under a
+/// `#[no_mangle]` name.  The generated C++ code declares this pointer as an
+/// `extern "C"` variable and exposes the `static` through an inline accessor
+/// function that returns a `const` reference.
+///
+/// The accessor function (rather than an `inline` reference variable) avoids
+/// the static initialization order fiasco - a reference variable initialized
+/// with `*pointer` would be initialized dynamically and therefore might still
+/// be unbound when used by static initializers in other translation units.
+fn format_static(
+    input: &Input,
+    local_def_id: LocalDefId,
+) -> Result<Vec<(SnippetKey, MixedSnippet)>> {
+    let tcx = input.tcx;
+    let def_id = local_def_id.to_def_id();
+    ensure!(
+        !tcx.is_mutable_static(def_id),
+        "Bindings for `static mut` items are not supported yet"
+    );
+    let cc_name =
+        format_cc_ident(tcx.item_name(def_id).as_str()).context("Error formatting item name")?;
+    let ty = tcx.type_of(def_id).subst_identity();
+    let CcSnippet { tokens: cc_type, prereqs } =
+        format_qualified_referent_ty_for_cc(input, ty, Mutability::Not, || {
+            format!("Failed to format the type of the static: {ty}")
+        })?;
+    let rs_type = format_ty_for_rs(tcx, ty)?;
+
+    let export_name = format!(
+        "__crubit_static_{}",
+        format_sanitized_symbol_name(tcx, ty::Instance::mono(tcx, def_id))
+    );
+    let cc_export_name = format_cc_ident(&export_name).expect("Suffix should be sanitized");
+    let rs_export_name = make_rs_ident(&export_name);
+    let fully_qualified_name = FullyQualifiedName::new(tcx, def_id).format_for_rs();
+
+    let doc_comment = format_doc_comment(tcx, local_def_id);
+    let main_api = CcSnippet {
+        prereqs,
+        tokens: quote! {
+            __NEWLINE__
+            namespace __crubit_internal {
+                extern "C" #cc_type* const #cc_export_name;
+            }
+            __NEWLINE__ #doc_comment
+            inline #cc_type& #cc_name() { return *__crubit_internal::#cc_export_name; }
+            __NEWLINE__
+        },
+    };
+    let impl_details = MixedSnippet {
+        cc: CcSnippet::default(),
+        rs: quote! {
+            #[no_mangle]
+            static #rs_export_name: &#rs_type = &#fully_qualified_name;
+        },
+    };
+    Ok(vec![
+        (SnippetKey { def_id: local_def_id, kind: SnippetKind::MainApi }, main_api.into()),
+        (SnippetKey { def_id: local_def_id, kind: SnippetKind::ImplDetails }, impl_details),
+    ])
+}
+
+/// Formats a type alias identified by `local_def_id` as a C++ alias
+/// declaration (e.g. `using Meters = std::int32_t;`).
+fn format_type_alias(
+    input: &Input,
+    local_def_id: LocalDefId,
+) -> Result<Vec<(SnippetKey, MixedSnippet)>> {
+    let tcx = input.tcx;
+    let def_id = local_def_id.to_def_id();
+    ensure!(
+        tcx.generics_of(def_id).count() == 0,
+        "Generic type aliases are not supported yet (b/254096006)"
+    );
+    let cc_name =
+        format_cc_ident(tcx.item_name(def_id).as_str()).context("Error formatting item name")?;
+
+    // The aliased type is a `defs` prerequisite of the alias, which means that `toposort`
+    // will order the alias after the definition of the aliased type.
+    let ty = tcx.type_of(def_id).subst_identity();
+    let mut prereqs = CcPrerequisites::default();
+    let cc_type = format_ty_for_cc(input, ty)
+        .context("Error formatting the aliased type")?
+        .into_tokens(&mut prereqs);
+    let doc_comment = format_doc_comment(tcx, local_def_id);
+    let main_api = CcSnippet {
+        prereqs,
+        tokens: quote! {
+            __NEWLINE__ #doc_comment
+            using #cc_name = #cc_type;
+            __NEWLINE__
+        },
+    };
+    Ok(vec![(SnippetKey { def_id: local_def_id, kind: SnippetKind::MainApi }, main_api.into())])
+}
+
+/// Formats the forward declaration of an algebraic data type (an ADT - a
+/// struct, an enum, or a union) or of a trait, returning something like
+/// `quote!{ struct SomeStruct; }`.
+///
+/// Will panic if `def_id` doesn't identify an ADT that can be successfully
+/// handled by `format_adt_core` (or a trait that can be successfully handled
+/// by `format_trait_core`).
+fn format_fwd_decl(tcx: TyCtxt, def_id: LocalDefId) -> TokenStream {
+    let def_id = def_id.to_def_id(); // LocalDefId -> DefId conversion.
+
+    // Traits are represented as an abstract C++ class (see `format_trait`).
+    if tcx.def_kind(def_id) == DefKind::Trait {
+        let cc_name = format_cc_ident(tcx.item_name(def_id).as_str())
+            .expect("`format_trait_core` should verify the name");
+        return quote! { class #cc_name; };
+    }
+
+    // Generic ADTs are forward-declared as a primary class template (see
+    // `format_generic_adt`).
+    if is_generic_adt(tcx, def_id) {
+        let keyword = format_adt_keyword(tcx.adt_def(def_id));
+        let cc_name = format_cc_ident(tcx.item_name(def_id).as_str())
+            .expect("`format_generic_adt` should verify the name");
+        let template_params = format_template_params(tcx, def_id)
+            .expect("`format_generic_adt` should verify the template parameters");
+        return quote! { #template_params #keyword #cc_name; };
+    }
+
+    // `format_fwd_decl` should only be called for items from
+    // `CcPrerequisites::fwd_decls` and `fwd_decls` should only contain ADTs
+    // that `format_adt_core` succeeds for.
+    let AdtCoreBindings { keyword, cc_name, enum_class_underlying_type, .. } =
+        format_adt_core(tcx, tcx.type_of(def_id).subst_identity())
+            .expect("`format_fwd_decl` should only be called if `format_adt_core` succeeded");
+
+    match enum_class_underlying_type {
+        // An opaque declaration of an `enum class` has to specify the underlying type.
+        Some(underlying_type) => quote! { #keyword #cc_name : #underlying_type; },
+        None => quote! { #keyword #cc_name; },
+    }
+}
+
+fn format_source_location(tcx: TyCtxt, local_def_id: LocalDefId) -> String {
+    let def_span = tcx.def_span(local_def_id);
+    let rustc_span::FileLines { file, lines } =
+        match tcx.sess().source_map().span_to_lines(def_span) {
+            Ok(filelines) => filelines,
+            Err(_) => return "unknown location".to_string(),
+        };
+    let file_name = file.name.prefer_local().to_string();
+    // Note: line_index starts at 0, while CodeSearch starts indexing at 1.
+    let line_number = lines[0].line_index + 1;
+    let google3_prefix = {
+        // If rustc_span::FileName isn't a 'real' file, then it's surrounded by by angle
+        // brackets, thus don't prepend "google3/" prefix.
+        if file.name.is_real() { "google3/" } else { "" }
+    };
+    format!("{google3_prefix}{file_name};l={line_number}")
+}
+
+/// Formats the doc comment (if any) associated with the item identified by
+/// `local_def_id`, and appends the source location at which the item is
+/// defined.  Doc comments of `unsafe` functions are additionally annotated
+/// with a note that points C++ callers at the `# Safety` section of the doc
+/// comment.
+fn format_doc_comment(tcx: TyCtxt, local_def_id: LocalDefId) -> TokenStream {
+    let hir_id = tcx.local_def_id_to_hir_id(local_def_id);
+    let doc_lines = tcx
+        .hir()
+        .attrs(hir_id)
+        .iter()
+        .filter_map(|attr| attr.doc_str())
+        .map(|symbol| symbol.to_string())
+        .collect_vec();
+    let unsafe_fn_note = if is_unsafe_fn(tcx, local_def_id.to_def_id()) {
+        // A block doc comment (`/** ... */`) is a single, multi-line doc string (whose lines
+        // may start with a `*`).
+        let has_safety_section = doc_lines
+            .iter()
+            .flat_map(|doc| doc.split('\n'))
+            .map(|line| line.trim_start().trim_start_matches('*').trim())
+            .any(|line| line == "# Safety");
+        Some(if has_safety_section {
+            "This function is `unsafe` in Rust. C++ callers are responsible for upholding \
+             the safety requirements described in the `# Safety` section above."
+                .to_string()
+        } else {
+            "This function is `unsafe` in Rust, but its doc comment doesn't have \
+             a `# Safety` section describing the requirements that C++ callers need to uphold."
+                .to_string()
+        })
+    } else {
+        None
+    };
+    let doc_comment = doc_lines
+        .into_iter()
+        .chain(unsafe_fn_note)
+        .chain(once(format!("Generated from: {}", format_source_location(tcx, local_def_id))))
+        .join("\n\n");
+    quote! { __COMMENT__ #doc_comment}
+}
+
+/// Returns `true` if `def_id` identifies an `unsafe` function or method.
+fn is_unsafe_fn(tcx: TyCtxt, def_id: DefId) -> bool {
+    matches!(tcx.def_kind(def_id), DefKind::Fn | DefKind::AssocFn)
+        && tcx.fn_sig(def_id).skip_binder().unsafety() == Unsafety::Unsafe
+}
+
+/// Formats a HIR item idenfied by `def_id`.  Returns `None` if the item
+/// can be ignored. Returns an `Err` if the definition couldn't be formatted.
+///
+/// Will panic if `def_id` is invalid (i.e. doesn't identify a HIR item).
+fn format_item(input: &Input, def_id: LocalDefId) -> Result<Vec<(SnippetKey, MixedSnippet)>> {
+    // Items that can't be named by other crates (e.g. items in a private module that haven't
+    // been re-exported via `pub use`) are ignored.  Exposing them would mean that changes of
+    // private implementation details of the crate could become breaking changes for users of
+    // the generated C++ bindings.
+    if !input.tcx.effective_visibilities(()).is_directly_public(def_id)
+        && !public_paths(input.tcx, LOCAL_CRATE).contains_key(&def_id.to_def_id())
+    {
+        return Ok(vec![]);
+    }
+    ensure!(
+        public_path(input.tcx, def_id.to_def_id()).is_some(),
+        "Items that are only re-exported under a different name are not supported yet \
+         (b/262052635)"
+    );
+
+    match input.tcx.hir().expect_item(def_id) {
+        Item { kind: ItemKind::Struct(..) | ItemKind::Enum(..) | ItemKind::Union(..), .. }
+            if is_generic_adt(input.tcx, def_id.to_def_id()) => format_generic_adt(input, def_id),
+        Item { kind: ItemKind::Fn(..), .. } => format_fn(input, def_id),
+        Item { kind: ItemKind::Trait(..), .. } => format_trait(input, def_id),
+        Item { kind: ItemKind::Const(..), .. } => format_const(input, def_id),
+        Item { kind: ItemKind::Static(..), .. } => format_static(input, def_id),
+        Item { kind: ItemKind::TyAlias(..), .. } => format_type_alias(input, def_id),
+        Item { kind: ItemKind::Struct(..) | ItemKind::Enum(..) | ItemKind::Union(..), .. } =>
+            format_adt_core(input.tcx, input.tcx.type_of(def_id).subst_identity())
+                .map(|core| format_adt(input, &core)),
+        Item { kind: ItemKind::Impl(_), .. } |  // Handled by `format_adt`
+        Item { kind: ItemKind::Mod(_), .. } |  // Handled by `format_crate`
+        Item { kind: ItemKind::Use(..), .. } =>  // Handled by `format_crate`
+            Ok(vec![]),
+        Item { kind, .. } => bail!("Unsupported rustc_hir::hir::ItemKind: {}", kind.descr()),
+    }
+}
+
+/// Formats a C++ comment explaining why no bindings have been generated for
+/// `local_def_id`.
+fn format_unsupported_def(
+    tcx: TyCtxt,
+    local_def_id: LocalDefId,
+    err: anyhow::Error,
+) -> (SnippetKey, MixedSnippet) {
+    let source_loc = format_source_location(tcx, local_def_id);
+    let name = tcx.def_path_str(local_def_id.to_def_id());
+
+    // https://docs.rs/anyhow/latest/anyhow/struct.Error.html#display-representations
+    // says: To print causes as well [...], use the alternate selector “{:#}”.
+    let msg = format!("Error generating bindings for `{name}` defined at {source_loc}: {err:#}");
+    let cc = CcSnippet::new(quote! { __NEWLINE__ __NEWLINE__ __COMMENT__ #msg __NEWLINE__ });
+
+    (SnippetKey { def_id: local_def_id, kind: SnippetKind::MainApi }, cc.into())
+}
+
+/// Formats a C++ `using` declaration that exposes the item identified by
+/// `def_id` at an additional public path (e.g. at a path introduced by `pub
+/// use some_module::SomeStruct;`).
+fn format_reexport(
+    tcx: TyCtxt,
+    def_id: LocalDefId,
+    reexport_path: &FullyQualifiedName,
+) -> Result<TokenStream> {
+    let def_id = def_id.to_def_id(); // LocalDefId -> DefId conversion.
+    let target = FullyQualifiedName::new(tcx, def_id).format_for_cc()?;
+    let name = reexport_path.name.expect("Re-exports should always have a name");
+    if name == tcx.item_name(def_id) {
+        return Ok(quote! { using #target; __NEWLINE__ });
+    }
+
+    // A renaming re-export (e.g. `pub use some_module::SomeStruct as OtherName;`) can only be
+    // represented as a C++ type alias.
+    let is_non_generic_type = match tcx.def_kind(def_id) {
+        DefKind::Struct | DefKind::Enum | DefKind::Union => !is_generic_adt(tcx, def_id),
+        DefKind::TyAlias => tcx.generics_of(def_id).count() == 0,
+        DefKind::Trait => true,
+        _ => false,
+    };
+    ensure!(
+        is_non_generic_type,
+        "Renaming re-exports are only supported for non-generic types (b/262052635)"
+    );
+    let alias = format_cc_ident(name.as_str()).context("Error formatting re-export name")?;
+    Ok(quote! { using #alias = #target; __NEWLINE__ })
+}
+
+/// Formats all public items from the Rust crate being compiled.
+fn format_crate(input: &Input) -> Result<Output> {
+    let tcx = input.tcx;
+    let _public_paths_cache_scope = PublicPathsCacheScope::new();
+    let mut failed_def_ids = HashSet::new();
+    let mut bindings: HashMap<SnippetKey, MixedSnippet> = tcx
+        .hir()
+        .items()
+        .flat_map(|item_id| {
+            let def_id: LocalDefId = item_id.owner_id.def_id;
+            format_item(input, def_id)
+                .unwrap_or_else(|err| {
+                    failed_def_ids.insert(def_id);
+                    vec![format_unsupported_def(tcx, def_id, err)]
+                })
+                .into_iter()
+        })
+        .fold(HashMap::new(), |mut map, (key, value)| {
+            let old_item = map.insert(key, value);
+            assert!(old_item.is_none(), "Duplicated key: {key:?}");
+            map
+        });
+
+    // Items with additional public paths (e.g. `pub use some_mod::SomeStruct;`) are exposed
+    // at those paths via C++ `using` declarations.  They are emitted after all the other
+    // snippets, so that they can refer to any item.
+    let reexports = {
+        let bound_def_ids: HashSet<LocalDefId> = bindings
+            .keys()
+            .filter(|key| key.kind == SnippetKind::MainApi)
+            .map(|key| key.def_id)
+            .filter(|def_id| !failed_def_ids.contains(def_id))
+            .collect();
+        public_paths(tcx, LOCAL_CRATE)
+            .iter()
+            .filter_map(|(def_id, paths)| Some((def_id.as_local()?, paths.clone())))
+            .filter(|(def_id, _)| bound_def_ids.contains(def_id))
+            .sorted_by_key(|(def_id, _)| tcx.def_span(*def_id))
+            .flat_map(|(def_id, paths)| {
+                let canonical_name = FullyQualifiedName::new(tcx, def_id.to_def_id());
+                paths
+                    .into_iter()
+                    .filter(move |path| *path != canonical_name)
+                    .map(move |path| {
+                        let tokens = format_reexport(tcx, def_id, &path).unwrap_or_else(|err| {
+                            let name = path.name.expect("Re-exports should always have a name");
+                            let (_, MixedSnippet { cc, .. }) = format_unsupported_def(
+                                tcx,
+                                def_id,
+                                err.context(format!("Error handling the `{name}` re-export")),
+                            );
+                            cc.tokens
+                        });
+                        (path.mod_path, tokens)
+                    })
+            })
+            .collect_vec()
+    };
+
+    // Find the order of `bindings` that 1) meets the requirements of
+    // `CcPrerequisites::defs` and 2) makes a best effort attempt to keep the
+    // `bindings` in the same order as the source order of the Rust APIs.
+    let ordered_ids = {
+        let toposort::TopoSortResult { ordered: ordered_ids, failed: failed_ids } = {
+            let nodes = bindings.keys().copied();
+            let deps = bindings.iter().flat_map(|(&successor, snippet)| {
+                let predecessors = snippet.cc.prereqs.defs.iter().map(|&def_id|
+                    SnippetKey { def_id, kind: SnippetKind::MainApi }
+                );
+                predecessors.map(move |predecessor| toposort::Dependency { predecessor, successor })
+            });
+            toposort::toposort(nodes, deps, preferred_snippet_order(tcx))
+        };
+        assert_eq!(
+            0,
+            failed_ids.len(),
+            "There are no known scenarios where CcPrerequisites::defs can form \
+                    a dependency cycle. These `LocalDefId`s form an unexpected cycle: {}",
+            failed_ids.into_iter().map(|id| format!("{:?}", id)).join(",")
+        );
+        ordered_ids
+    };
+
+    // Destructure/rebuild `bindings` (in the same order as `ordered_ids`) into
+    // `includes`, and into separate C++ snippets and Rust snippets.
+    let (includes, ordered_cc, rs_body) = {
+        let mut already_declared = HashSet::new();
+        let mut fwd_decls = HashSet::new();
+        let mut includes = BTreeSet::new();
+        let mut ordered_cc = Vec::new();
+        let mut rs_body = quote! {};
+        for key in ordered_ids.into_iter() {
+            let mod_path = FullyQualifiedName::new(tcx, key.def_id.to_def_id()).mod_path;
+            let MixedSnippet {
+                rs: inner_rs,
+                cc: CcSnippet {
+                    tokens: cc_tokens,
+                    prereqs: CcPrerequisites {
+                        includes: mut inner_includes,
+                        fwd_decls: inner_fwd_decls,
+                        .. // `defs` have already been utilized by `toposort` above
+                    }
+                }
+            } = bindings.remove(&key).unwrap();
+
+            fwd_decls.extend(inner_fwd_decls.difference(&already_declared).copied());
+            already_declared.insert(key.def_id);
+            already_declared.extend(inner_fwd_decls.into_iter());
+
+            includes.append(&mut inner_includes);
+            ordered_cc.push((mod_path, cc_tokens));
+            rs_body.extend(inner_rs);
+        }
+
+        // Prepend `fwd_decls` (in the original source order) to `ordered_cc`.
+        let fwd_decls = fwd_decls
+            .into_iter()
+            .sorted_by_key(|def_id| tcx.def_span(*def_id))
+            .map(|local_def_id| {
+                let mod_path = FullyQualifiedName::new(tcx, local_def_id.to_def_id()).mod_path;
+                (mod_path, format_fwd_decl(tcx, local_def_id))
+            })
+            .collect_vec();
+        let ordered_cc = fwd_decls
+            .into_iter()
+            .chain(ordered_cc.into_iter())
+            .chain(reexports.into_iter())
+            .collect_vec();
+
+        (includes, ordered_cc, rs_body)
+    };
+
+    // Generate top-level elements of the C++ header file.
+    let h_body = {
+        // TODO(b/254690602): Decide whether using `#crate_name` as the name of the
+        // top-level namespace is okay (e.g. investigate if this name is globally
+        // unique + ergonomic).
+        let crate_name = format_cc_ident(tcx.crate_name(LOCAL_CRATE).as_str())?;
+
+        let includes = format_cc_includes(&includes);
+        let ordered_cc = format_namespace_bound_cc_tokens(ordered_cc);
+        quote! {
+            #includes
+            __NEWLINE__ __NEWLINE__
+            namespace #crate_name {
+                __NEWLINE__
+                #ordered_cc
+                __NEWLINE__
+            }
+            __NEWLINE__
+        }
+    };
+
+    Ok(Output { h_body, rs_body })
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    use anyhow::Result;
+    use itertools::Itertools;
+    use proc_macro2::TokenStream;
+    use quote::quote;
+    use rustc_middle::ty::{Ty, TyCtxt};
+    use rustc_span::def_id::LocalDefId;
+
+    use crate::run_compiler::tests::run_compiler_for_testing;
+    use code_gen_utils::format_cc_includes;
+    use token_stream_matchers::{
+        assert_cc_matches, assert_cc_not_matches, assert_rs_matches, assert_rs_not_matches,
+    };
+
+    #[test]
+    #[should_panic(expected = "No items named `missing_name`.\n\
+                               Instead found:\n`bar`,\n`foo`,\n`m1`,\n`m2`,\n`std`")]
+    fn test_find_def_id_by_name_panic_when_no_item_with_matching_name() {
+        let test_src = r#"
+                pub extern "C" fn foo() {}
+
+                pub mod m1 {
+                    pub fn bar() {}
+                }
+                pub mod m2 {
+                    pub fn bar() {}
+                }
+            "#;
+        run_compiler_for_testing(test_src, |tcx| find_def_id_by_name(tcx, "missing_name"));
+    }
+
+    #[test]
+    #[should_panic(expected = "More than one item named `some_name`")]
+    fn test_find_def_id_by_name_panic_when_multiple_items_with_matching_name() {
+        let test_src = r#"
+                pub mod m1 {
+                    pub fn some_name() {}
+                }
+                pub mod m2 {
+                    pub fn some_name() {}
+                }
+            "#;
+        run_compiler_for_testing(test_src, |tcx| find_def_id_by_name(tcx, "some_name"));
+    }
+
+    /// This test covers only a single example of a function that should get a
+    /// C++ binding. The test focuses on verification that the output from
+    /// `format_fn` gets propagated all the way to `GenerateBindings::new`.
+    /// Additional coverage of how functions are formatted is provided
+    /// by `test_format_item_..._fn_...` tests (which work at the `format_fn`
+    /// level).
+    #[test]
+    fn test_generated_bindings_fn_no_mangle_extern_c() {
+        let test_src = r#"
+                #[no_mangle]
+                pub extern "C" fn public_function() {
+                    println!("foo");
+                }
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    extern "C" void public_function();
+                }
+            );
+
+            // No Rust thunks should be generated in this test scenario.
+            assert_rs_not_matches!(bindings.rs_body, quote!{ public_function });
+        });
+    }
+
+    /// `test_generated_bindings_fn_export_name` covers a scenario where
+    /// `MixedSnippet::cc` is present but `MixedSnippet::rs` is empty
+    /// (because no Rust thunks are needed).
+    #[test]
+    fn test_generated_bindings_fn_export_name() {
+        let test_src = r#"
+                #[export_name = "export_name"]
+                pub extern "C" fn public_function(x: f64, y: f64) -> f64 { x + y }
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    namespace rust_out {
+                        ...
+                        inline double public_function(double x, double y);
+                        namespace __crubit_internal {
+                            extern "C" double export_name(double x, double y);
+                        }
+                        inline double public_function(double x, double y) {
+                            return __crubit_internal::export_name(x, y);
+                        }
+                    }
+                }
+            );
+        });
+    }
+
+    /// The `test_generated_bindings_struct` test covers only a single example
+    /// of an ADT (struct/enum/union) that should get a C++ binding.
+    /// Additional coverage of how items are formatted is provided by
+    /// `test_format_item_..._struct_...`, `test_format_item_..._enum_...`,
+    /// and `test_format_item_..._union_...` tests.
+    ///
+    /// We don't want to duplicate coverage already provided by
+    /// `test_format_item_struct_with_fields`, but we do want to verify that
+    /// * `format_crate` will actually find and process the struct
+    ///   (`test_format_item_...` doesn't cover this aspect - it uses a
+    ///   test-only `find_def_id_by_name` instead)
+    /// * The actual shape of the bindings still looks okay at this level.
+    #[test]
+    fn test_generated_bindings_struct() {
+        let test_src = r#"
+                pub struct Point {
+                    pub x: i32,
+                    pub y: i32,
+                }
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    namespace rust_out {
+                        ...
+                        struct alignas(4) Point final {
+                            // No point replicating test coverage of
+                            // `test_format_item_struct_with_fields`.
+                            ...
+                        };
+                        static_assert(sizeof(Point) == 8, ...);
+                        static_assert(alignof(Point) == 4, ...);
+                        ... // Other static_asserts are covered by
+                            // `test_format_item_struct_with_fields`
+                    }  // namespace rust_out
+                }
+            );
+            assert_rs_matches!(
+                bindings.rs_body,
+                quote! {
+                    // No point replicating test coverage of
+                    // `test_format_item_struct_with_fields`.
+                    const _: () = assert!(::std::mem::size_of::<::rust_out::Point>() == 8);
+                    const _: () = assert!(::std::mem::align_of::<::rust_out::Point>() == 4);
+                    const _: () = assert!( memoffset::offset_of!(::rust_out::Point, x) == 0);
+                    const _: () = assert!( memoffset::offset_of!(::rust_out::Point, y) == 4);
+                }
+            );
+        });
+    }
+
+    /// The `test_generated_bindings_impl` test covers only a single example of
+    /// a non-trait `impl`. Additional coverage of how items are formatted
+    /// should be provided in the future by `test_format_item_...` tests.
+    ///
+    /// We don't want to duplicate coverage already provided by
+    /// `test_format_item_static_method`, but we do want to verify that
+    /// * `format_crate` won't process the `impl` as a standalone HIR item
+    /// * The actual shape of the bindings still looks okay at this level.
+    #[test]
+    fn test_generated_bindings_impl() {
+        let test_src = r#"
+                pub struct SomeStruct(i32);
+
+                impl SomeStruct {
+                    pub fn public_static_method() -> i32 { 123 }
+
+                    #[allow(dead_code)]
+                    fn private_static_method() -> i32 { 123 }
+                }
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    namespace rust_out {
+                        ...
+                        struct ... SomeStruct ... {
+                            // No point replicating test coverage of
+                            // `test_format_item_static_method`.
+                            ...
+                            std::int32_t public_static_method();
+                            ...
+                        };
+                        ...
+                        std::int32_t SomeStruct::public_static_method() {
+                            ...
+                        }
+                    }  // namespace rust_out
+                }
+            );
+            assert_rs_matches!(
+                bindings.rs_body,
+                quote! {
+                    extern "C" fn ...() -> i32 {
+                        ::rust_out::SomeStruct::public_static_method()
+                    }
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn test_generated_bindings_includes() {
+        let test_src = r#"
+                #[no_mangle]
+                pub extern "C" fn public_function(i: i32, d: isize, u: u64) {
+                    dbg!(i);
+                    dbg!(d);
+                    dbg!(u);
+                }
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    __HASH_TOKEN__ include <cstdint> ...
+                    namespace ... {
+                        ...
+                        extern "C" void public_function(
+                            std::int32_t i,
+                            std::intptr_t d,
+                            std::uint64_t u);
+                    }
+                }
+            );
+        });
+    }
+
+    /// Tests that `toposort` is used to reorder item bindings.
+    #[test]
+    fn test_generated_bindings_prereq_defs_field_deps_require_reordering() {
+        let test_src = r#"
+                // In the generated bindings `Outer` needs to come *after* `Inner`.
+                pub struct Outer(Inner);
+                pub struct Inner(bool);
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    namespace rust_out {
+                    ...
+                        struct alignas(1) Inner final {
+                          ...  bool __field0; ...
+                        };
+                    ...
+                        struct alignas(1) Outer final {
+                          ...  ::rust_out::Inner __field0; ...
+                        };
+                    ...
+                    }  // namespace rust_out
+                }
+            );
+        });
+    }
+
+    /// Tests that a forward declaration is present when it is required to
+    /// preserve the original source order.  In this test the
+    /// `CcPrerequisites::fwd_decls` dependency comes from a pointer parameter.
+    #[test]
+    fn test_generated_bindings_prereq_fwd_decls_for_ptr_param() {
+        let test_src = r#"
+                // To preserve original API order we need to forward declare S.
+                pub fn f(_: *const S) {}
+                pub struct S(bool);
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    namespace rust_out {
+                        ...
+                        // Verifing the presence of this forward declaration
+                        // it the essence of this test.  The order of the items
+                        // below also matters.
+                        struct S;
+                        ...
+                        inline void f(const ::rust_out::S* __param_0);
+                        ...
+                        struct alignas(...) S final { ... }
+                        ...
+                        inline void f(const ::rust_out::S* __param_0) { ... }
+                        ...
+                    }  // namespace rust_out
+                }
+            );
+        });
+    }
+
+    /// Tests that a forward declaration is present when it is required to
+    /// preserve the original source order.  In this test the
+    /// `CcPrerequisites::fwd_decls` dependency comes from a parameter
+    /// that takes a struct by value, but is only needed in a C++ function
+    /// declaration (not in a C++ function definition).
+    #[test]
+    fn test_generated_bindings_prereq_fwd_decls_for_cpp_fn_decl() {
+        let test_src = r#"
+                // `f` will only have a C++ declaration (and no C++ definition)
+                // in the generated `..._cc_api.h` header.
+                //
+                // Therefore `f`'s `CcPrerequisites` should include `S`
+                // as a `fwd_decls` edge, rather than as a `defs` edge.
+                #[no_mangle]
+                pub extern "C" fn f(s: S) -> bool { s.0 }
+
+                #[repr(C)]
+                pub struct S(bool);
+            "#;
+
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    namespace rust_out {
+                        ...
+                        // Verifing the presence of this forward declaration
+                        // it the essence of this test.  The order also matters:
+                        // 1. The fwd decl of `S` should come first,
+                        // 2. Declaration of `f` and definition of `S` should come next
+                        //    (in their original order - `f` first and then `S`).
+                        struct S;
+                        ...
+                        extern "C" bool f(::rust_out::S s);
+                        ...
+                        struct alignas(...) S final { ... }
+                        ...
+                    }  // namespace rust_out
+                }
+            );
+        });
+    }
+
+    /// This test verifies that a forward declaration for a given ADT is only
+    /// emitted once (and not once for every API item that requires the
+    /// forward declaration as a prerequisite).
+    #[test]
+    fn test_generated_bindings_prereq_fwd_decls_no_duplication() {
+        let test_src = r#"
+                // All three functions below require a forward declaration of S.
+                pub fn f1(_: *const S) {}
+                pub fn f2(_: *const S) {}
+                pub fn f3(_: *const S) {}
+
+                pub struct S(bool);
+
+                // This function also includes S in its CcPrerequisites::fwd_decls
+                // (although here it is not required, because the definition of S
+                // is already available above).
+                pub fn f4(_: *const S) {}
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap().h_body.to_string();
+
+            // Only a single forward declaration is expected.
+            assert_eq!(1, bindings.matches("struct S ;").count(), "bindings = {bindings}");
+        });
+    }
+
+    /// This test verifies that forward declarations are emitted in a
+    /// deterministic order. The particular order doesn't matter _that_
+    /// much, but it definitely shouldn't change every time
+    /// `cc_bindings_from_rs` is invoked again.  The current order preserves
+    /// the original source order of the Rust API items.
+    #[test]
+    fn test_generated_bindings_prereq_fwd_decls_deterministic_order() {
+        let test_src = r#"
+                // To try to mix things up, the bindings for the functions below
+                // will *ask* for forward declarations in a different order:
+                // * Different from the order in which the forward declarations
+                //   are expected to be *emitted* (the original source order).
+                // * Different from alphabetical order.
+                pub fn f1(_: *const b::S3) {}
+                pub fn f2(_: *const a::S2) {}
+                pub fn f3(_: *const a::S1) {}
+
+                pub mod a {
+                    pub struct S1(bool);
+                    pub struct S2(bool);
+                }
+
+                pub mod b {
+                    pub struct S3(bool);
+                }
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    namespace rust_out {
+                        ...
+                        // Verifying that we get the same order in each test
+                        // run is the essence of this test.
+                        namespace a {
+                        struct S1;
+                        struct S2;
+                        }
+                        namespace b {
+                        struct S3;
+                        }
+                        ...
+                        inline void f1 ...
+                        inline void f2 ...
+                        inline void f3 ...
+
+                        namespace a { ...
+                        struct alignas(...) S1 final { ... } ...
+                        struct alignas(...) S2 final { ... } ...
+                        } ...
+                        namespace b { ...
+                        struct alignas(...) S3 final { ... } ...
+                        } ...
+                    }  // namespace rust_out
+                }
+            );
+        });
+    }
+
+    /// This test verifies that forward declarations are not emitted if they are
+    /// not needed (e.g. if bindings the given `struct` or other ADT have
+    /// already been defined earlier).  In particular, we don't want to emit
+    /// forward declarations for *all* `structs` (regardless if they are
+    /// needed or not).
+    #[test]
+    fn test_generated_bindings_prereq_fwd_decls_not_needed_because_of_initial_order() {
+        let test_src = r#"
+                pub struct S(bool);
+
+                // S is already defined above - no need for forward declaration in C++.
+                pub fn f(_s: *const S) {}
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            assert_cc_not_matches!(bindings.h_body, quote! { struct S; });
+            assert_cc_matches!(bindings.h_body, quote! { void f(const ::rust_out::S* _s); });
+        });
+    }
+
+    /// This test verifies that a method declaration doesn't ask for a forward
+    /// declaration to the struct.
+    #[test]
+    fn test_generated_bindings_prereq_fwd_decls_not_needed_inside_struct_definition() {
+        let test_src = r#"
+                #![allow(dead_code)]
+
+                pub struct S {
+                    // This shouldn't require a fwd decl of S.
+                    field: *const S,
+                }
+
+                impl S {
+                    // This shouldn't require a fwd decl of S.
+                    pub fn create() -> S { Self{ field: std::ptr::null() } }
+                }
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            assert_cc_not_matches!(bindings.h_body, quote! { struct S; });
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    static inline ::rust_out::S create(); ...
+                    const ::rust_out::S* field; ...
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn test_generated_bindings_module_basics() {
+        let test_src = r#"
+                pub mod some_module {
+                    pub fn some_func() {}
+                }
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    namespace rust_out {
+                        namespace some_module {
+                            ...
+                            inline void some_func() { ... }
+                            ...
+                        }  // namespace some_module
+                    }  // namespace rust_out
+                }
+            );
+            assert_rs_matches!(
+                bindings.rs_body,
+                quote! {
+                    #[no_mangle]
+                    extern "C"
+                    fn ...() -> () {
+                        ::rust_out::some_module::some_func()
+                    }
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn test_generated_bindings_module_name_is_cpp_reserved_keyword() {
+        let test_src = r#"
+                pub mod working_module {
+                    pub fn working_module_f1() {}
+                    pub fn working_module_f2() {}
+                }
+                pub mod reinterpret_cast {
+                    pub fn broken_module_f1() {}
+                    pub fn broken_module_f2() {}
+                }
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+
+            // Items in the broken module should be replaced with a comment explaining the
+            // problem.
+            let broken_module_msg = "Failed to format namespace name `reinterpret_cast`: \
+                                     `reinterpret_cast` is a C++ reserved keyword \
+                                     and can't be used as a C++ identifier";
+            assert_cc_not_matches!(bindings.h_body, quote! { namespace reinterpret_cast });
+            assert_cc_not_matches!(bindings.h_body, quote! { broken_module_f1 });
+            assert_cc_not_matches!(bindings.h_body, quote! { broken_module_f2 });
+
+            // Items in the other module should still go through.
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    namespace rust_out {
+                        namespace working_module {
+                            ...
+                            inline void working_module_f1();
+                            ...
+                            inline void working_module_f2();
+                            ...
+                        }  // namespace some_module
+
+                        __COMMENT__ #broken_module_msg
+                        ...
+                    }  // namespace rust_out
+                }
+            );
+        });
+    }
+
+    /// `test_generated_bindings_non_pub_items` verifies that non-public items
+    /// are not present/propagated into the generated bindings.
+    #[test]
+    fn test_generated_bindings_non_pub_items() {
+        let test_src = r#"
+                #![allow(dead_code)]
+
+                extern "C" fn private_function() {
+                    println!("foo");
+                }
+
+                struct PrivateStruct {
+                    x: i32,
+                    y: i32,
+                }
+
+                pub struct PublicStruct(i32);
+
+                impl PublicStruct {
+                    fn private_method() {}
+                }
+
+                pub mod public_module {
+                    fn priv_func_in_pub_module() {}
+                }
+
+                mod private_module {
+                    pub fn pub_func_in_priv_module() { priv_func_in_priv_module() }
+                    fn priv_func_in_priv_module() {}
+                }
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            assert_cc_not_matches!(bindings.h_body, quote! { private_function });
+            assert_rs_not_matches!(bindings.rs_body, quote! { private_function });
+            assert_cc_not_matches!(bindings.h_body, quote! { PrivateStruct });
+            assert_rs_not_matches!(bindings.rs_body, quote! { PrivateStruct });
+            assert_cc_not_matches!(bindings.h_body, quote! { private_method });
+            assert_rs_not_matches!(bindings.rs_body, quote! { private_method });
+            assert_cc_not_matches!(bindings.h_body, quote! { priv_func_in_priv_module });
+            assert_rs_not_matches!(bindings.rs_body, quote! { priv_func_in_priv_module });
+            assert_cc_not_matches!(bindings.h_body, quote! { priv_func_in_pub_module });
+            assert_rs_not_matches!(bindings.rs_body, quote! { priv_func_in_pub_module });
+            assert_cc_not_matches!(bindings.h_body, quote! { private_module });
+            assert_rs_not_matches!(bindings.rs_body, quote! { private_module });
+            assert_cc_not_matches!(bindings.h_body, quote! { pub_func_in_priv_module });
+            assert_rs_not_matches!(bindings.rs_body, quote! { pub_func_in_priv_module });
+        });
+    }
+
+    #[test]
+    fn test_generated_bindings_top_level_items() {
+        let test_src = "pub fn public_function() {}";
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            let expected_comment_txt =
+                "Automatically @generated C++ bindings for the following Rust crate:\n\
+                 rust_out";
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    __COMMENT__ #expected_comment_txt
+                    ...
+                    __HASH_TOKEN__ pragma once
+                    ...
+                    namespace rust_out {
+                        ...
+                    }
+                }
+            );
+            assert_cc_matches!(
+                bindings.rs_body,
+                quote! {
+                    __COMMENT__ #expected_comment_txt
+                }
+            );
+        })
+    }
+
+    /// The `test_generated_bindings_unsupported_item` test verifies how `Err`
+    /// from `format_item` is formatted as a C++ comment (in `format_crate`
+    /// and `format_unsupported_def`):
+    /// - This test covers only a single example of an unsupported item.
+    ///   Additional coverage is provided by `test_format_item_unsupported_...`
+    ///   tests.
+    /// - This test somewhat arbitrarily chooses an example of an unsupported
+    ///   item, trying to pick one that 1) will never be supported (b/254104998
+    ///   has some extra notes about APIs named after reserved C++ keywords) and
+    ///   2) tests that the full error chain is included in the message.
+    #[test]
+    fn test_generated_bindings_unsupported_item() {
+        let test_src = r#"
+                #[no_mangle]
+                pub extern "C" fn reinterpret_cast() {}
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            let expected_comment_txt = "Error generating bindings for `reinterpret_cast` \
+                 defined at <crubit_unittests.rs>;l=3: \
+                 Error formatting function name: \
+                 `reinterpret_cast` is a C++ reserved keyword \
+                 and can't be used as a C++ identifier";
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    __COMMENT__ #expected_comment_txt
+                }
+            );
+        })
+    }
+
+    #[test]
+    fn test_generated_bindings_reimports() {
+        let test_src = r#"
+                #![allow(dead_code)]
+                #![allow(unused_imports)]
+                mod private_submodule1 {
+                    pub fn subfunction1() {}
+                    pub fn subfunction2() {}
+                    pub fn subfunction3() {}
+                }
+                mod private_submodule2 {
+                    pub fn subfunction8() {}
+                    pub fn subfunction9() {}
+                }
+
+                // Public re-import.
+                pub use private_submodule1::subfunction1;
+
+                // Private re-import.
+                use private_submodule1::subfunction2;
+
+                // Re-import that renames.
+                pub use private_submodule1::subfunction3 as public_function3;
+
+                // Re-import of multiple items via glob.
+                pub use private_submodule2::*;
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+
+            // Items re-exported via `pub use` are exposed at their public path (rather than
+            // in the namespace of the private module where they have been defined).
+            assert_cc_not_matches!(bindings.h_body, quote! { namespace private_submodule1 });
+            assert_cc_not_matches!(bindings.h_body, quote! { namespace private_submodule2 });
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    namespace rust_out {
+                        ...
+                        inline void subfunction1();
+                        ...
+                    }
+                }
+            );
+            assert_cc_matches!(bindings.h_body, quote! { inline void subfunction8(); });
+            assert_cc_matches!(bindings.h_body, quote! { inline void subfunction9(); });
+            assert_rs_matches!(bindings.rs_body, quote! { ::rust_out::subfunction1() });
+            assert_rs_matches!(bindings.rs_body, quote! { ::rust_out::subfunction8() });
+            assert_rs_matches!(bindings.rs_body, quote! { ::rust_out::subfunction9() });
+
+            // Private re-imports don't make an item public.
+            assert_cc_not_matches!(bindings.h_body, quote! { subfunction2 });
+
+            // Items that are only re-exported under a different name are not supported yet.
+            let expected_comment_txt = "Error generating bindings for \
+                 `private_submodule1::subfunction3` defined at <crubit_unittests.rs>;l=7: \
+                 Items that are only re-exported under a different name are not supported yet \
+                 (b/262052635)";
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    __COMMENT__ #expected_comment_txt
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn test_generated_bindings_reexported_struct_from_private_module() {
+        let test_src = r#"
+                mod private_module {
+                    pub struct SomeStruct {
+                        pub x: i32,
+                    }
+                    impl SomeStruct {
+                        pub fn get_x(&self) -> i32 { self.x }
+                    }
+                }
+                pub use private_module::SomeStruct;
+
+                pub fn create_struct(x: i32) -> SomeStruct { SomeStruct { x } }
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            assert_cc_not_matches!(bindings.h_body, quote! { namespace private_module });
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    namespace rust_out {
+                        ...
+                        struct ... SomeStruct final {
+                            ...
+                            std::int32_t get_x() const;
+                            ...
+                        };
+                        ...
+                        inline ::rust_out::SomeStruct create_struct(std::int32_t x);
+                        ...
+                    }
+                }
+            );
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    inline std::int32_t SomeStruct::get_x() const { ... }
+                }
+            );
+            assert_rs_matches!(
+                bindings.rs_body,
+                quote! {
+                    const _: () = assert!(::std::mem::size_of::<::rust_out::SomeStruct>() == 4);
+                }
+            );
+            assert_rs_matches!(bindings.rs_body, quote! { ::rust_out::SomeStruct::get_x(__self) });
+            assert_rs_matches!(bindings.rs_body, quote! { ::rust_out::create_struct(x) });
+        });
+    }
+
+    #[test]
+    fn test_generated_bindings_reexported_module() {
+        let test_src = r#"
+                mod private_module {
+                    pub mod inner_module {
+                        pub fn some_function() {}
+                    }
+                }
+                pub use private_module::inner_module as public_module;
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            assert_cc_not_matches!(bindings.h_body, quote! { namespace private_module });
+            assert_cc_not_matches!(bindings.h_body, quote! { namespace inner_module });
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    namespace public_module {
+                        ...
+                        inline void some_function();
+                        ...
+                    }
+                }
+            );
+            assert_rs_matches!(
+                bindings.rs_body,
+                quote! { ::rust_out::public_module::some_function() }
+            );
+        });
+    }
+
+    #[test]
+    fn test_public_paths_memoization() {
+        let test_src = r#"
+                mod private_module {
+                    pub struct SomeStruct(pub i32);
+                }
+                pub use private_module::SomeStruct;
+            "#;
+        run_compiler_for_testing(test_src, |tcx| {
+            // Outside of a `PublicPathsCacheScope` the results are recomputed each time.
+            let paths1 = public_paths(tcx, LOCAL_CRATE);
+            let paths2 = public_paths(tcx, LOCAL_CRATE);
+            assert!(!Rc::ptr_eq(&paths1, &paths2));
+
+            {
+                let _scope = PublicPathsCacheScope::new();
+                let paths1 = public_paths(tcx, LOCAL_CRATE);
+                {
+                    // Nested scopes don't discard the memoized results.
+                    let _nested_scope = PublicPathsCacheScope::new();
+                }
+                let paths2 = public_paths(tcx, LOCAL_CRATE);
+                assert!(Rc::ptr_eq(&paths1, &paths2));
+                assert_eq!(1, paths1.len());
+            }
+
+            // Memoized results are discarded at the end of the scope.
+            let paths3 = public_paths(tcx, LOCAL_CRATE);
+            assert!(!Rc::ptr_eq(&paths1, &paths3));
+        });
+    }
+
+    #[test]
+    fn test_generated_bindings_additional_public_paths() {
+        let test_src = r#"
+                pub mod some_module {
+                    pub struct SomeStruct(pub i32);
+                    pub fn some_function() {}
+                }
+                pub use some_module::SomeStruct;
+                pub use some_module::SomeStruct as RenamedStruct;
+                pub use some_module::some_function;
+                pub use some_module::some_function as renamed_function;
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+
+            // The items are defined at their own (directly public) path.
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    namespace some_module {
+                        ...
+                        struct ... SomeStruct final {
+                            ...
+                        };
+                        ...
+                        inline void some_function();
+                        ...
+                    }
+                }
+            );
+
+            // Additional public paths are exposed via `using` declarations that come after
+            // the definitions of the items.
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    namespace some_module { ... }
+                    ...
+                    using ::rust_out::some_module::SomeStruct;
+                }
+            );
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    namespace some_module { ... }
+                    ...
+                    using RenamedStruct = ::rust_out::some_module::SomeStruct;
+                }
+            );
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    namespace some_module { ... }
+                    ...
+                    using ::rust_out::some_module::some_function;
+                }
+            );
+
+            // Renaming re-exports of functions can't be represented in C++.
+            let expected_comment_txt = "Error generating bindings for \
+                 `some_module::some_function` defined at <crubit_unittests.rs>;l=4: \
+                 Error handling the `renamed_function` re-export: \
+                 Renaming re-exports are only supported for non-generic types (b/262052635)";
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    __COMMENT__ #expected_comment_txt
+                }
+            );
+            assert_cc_not_matches!(bindings.h_body, quote! { renamed_function = });
+        });
+    }
+
+    #[test]
+    fn test_format_item_fn_extern_c_no_mangle_no_params_no_return_type() {
+        let test_src = r#"
+                #[no_mangle]
+                pub extern "C" fn public_function() {}
+            "#;
+        test_format_item(test_src, "public_function", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            assert!(main_api.prereqs.is_empty());
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    extern "C" void public_function();
+                }
+            );
+        });
+    }
+
+    /// The `test_format_item_fn_explicit_unit_return_type` test below is very
+    /// similar to the
+    /// `test_format_item_fn_extern_c_no_mangle_no_params_no_return_type` above,
+    /// except that the return type is explicitly spelled out.  There is no
+    /// difference in `ty::FnSig` so our code behaves exactly the same, but the
+    /// test has been planned based on earlier, hir-focused approach and having
+    /// this extra test coverage shouldn't hurt. (`hir::FnSig`
+    /// and `hir::FnRetTy` _would_ see a difference between the two tests, even
+    /// though there is no different in the current `bindings.rs` code).
+    #[test]
+    fn test_format_item_fn_explicit_unit_return_type() {
+        let test_src = r#"
+                #[no_mangle]
+                pub extern "C" fn explicit_unit_return_type() -> () {}
+            "#;
+        test_format_item(test_src, "explicit_unit_return_type", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            assert!(main_api.prereqs.is_empty());
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    extern "C" void explicit_unit_return_type();
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_fn_never_return_type() {
+        let test_src = r#"
+                #[no_mangle]
+                pub extern "C" fn never_returning_function() -> ! {
+                    panic!("This function panics and therefore never returns");
+                }
+            "#;
+        test_format_item(test_src, "never_returning_function", |result| {
+            // TODO(b/254507801): The function should be annotated with the `[[noreturn]]`
+            // attribute.
+            // TODO(b/254507801): Expect `crubit::Never` instead (see the bug for more
+            // details).
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            assert!(main_api.prereqs.is_empty());
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    extern "C" void never_returning_function();
+                }
+            );
+        })
+    }
+
+    /// `test_format_item_fn_mangling` checks that bindings can be generated for
+    /// `extern "C"` functions that do *not* have `#[no_mangle]` attribute.  The
+    /// test elides away the mangled name in the `assert_cc_matches` checks
+    /// below, but end-to-end test coverage should eventually be provided by
+    /// `test/functions` (see b/262904507).
+    #[test]
+    fn test_format_item_fn_mangling() {
+        let test_src = r#"
+                pub extern "C" fn public_function(x: f64, y: f64) -> f64 { x + y }
+            "#;
+        test_format_item(test_src, "public_function", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            assert!(main_api.prereqs.is_empty());
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    inline double public_function(double x, double y);
+                }
+            );
+            assert!(impl_details.rs.is_empty());
+            assert!(impl_details.cc.prereqs.is_empty());
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" double ...(double x, double y);
+                    }
+                    ...
+                    inline double public_function(double x, double y) {
+                        return __crubit_internal::...(x, y);
+                    }
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_fn_export_name() {
+        let test_src = r#"
+                #[export_name = "export_name"]
+                pub extern "C" fn public_function(x: f64, y: f64) -> f64 { x + y }
+            "#;
+        test_format_item(test_src, "public_function", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            assert!(main_api.prereqs.is_empty());
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    inline double public_function(double x, double y);
+                }
+            );
+            assert!(impl_details.rs.is_empty());
+            assert!(impl_details.cc.prereqs.is_empty());
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" double export_name(double x, double y);
+                    }
+                    ...
+                    inline double public_function(double x, double y) {
+                        return __crubit_internal::export_name(x, y);
+                    }
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_fn_unsafe_extern_c() {
+        let test_src = r#"
+                /// Doc comment of an unsafe function.
+                ///
+                /// # Safety
+                ///
+                /// `ptr` needs to be valid.
+                #[no_mangle]
+                pub unsafe extern "C" fn foo(ptr: *const i32) -> i32 { *ptr }
+            "#;
+        test_format_item(test_src, "foo", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let doc_comment = " Doc comment of an unsafe function.\n\n\n\n # Safety\n\n\n\n \
+                               `ptr` needs to be valid.\n\n\
+                               This function is `unsafe` in Rust. C++ callers are responsible \
+                               for upholding the safety requirements described in the \
+                               `# Safety` section above.\n\n\
+                               Generated from: <crubit_unittests.rs>;l=8";
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    __COMMENT__ #doc_comment
+                    extern "C" std::int32_t foo(const std::int32_t* ptr);
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_fn_unsafe_with_block_doc_comment() {
+        let test_src = r#"
+                /** Doc comment of an unsafe function.
+                 *
+                 * # Safety
+                 *
+                 * `ptr` needs to be valid.
+                 */
+                pub unsafe fn foo(ptr: *const i32) -> i32 { *ptr }
+            "#;
+        run_compiler_for_testing(test_src, |tcx| {
+            let doc_comment = format_doc_comment(tcx, find_def_id_by_name(tcx, "foo")).to_string();
+            assert!(doc_comment.contains(
+                "C++ callers are responsible for upholding the safety requirements described \
+                 in the `# Safety` section above."
+            ));
+        });
+    }
+
+    #[test]
+    fn test_format_item_fn_unsafe_rust_abi() {
+        let test_src = r#"
+                pub unsafe fn foo(ptr: *const i32) -> i32 { *ptr }
+            "#;
+        test_format_item(test_src, "foo", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            let doc_comment = "This function is `unsafe` in Rust, but its doc comment doesn't \
+                               have a `# Safety` section describing the requirements that C++ \
+                               callers need to uphold.\n\n\
+                               Generated from: <crubit_unittests.rs>;l=2";
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    __COMMENT__ #doc_comment
+                    inline std::int32_t foo(const std::int32_t* ptr);
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    #[no_mangle]
+                    extern "C"
+                    fn ...(ptr: *const i32) -> i32 {
+                        unsafe { ::rust_out::foo(ptr) }
+                    }
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_fn_unsafe_excluded() {
+        let test_src = r#"
+                pub unsafe fn foo() {}
+                pub struct SomeStruct(i32);
+                impl SomeStruct {
+                    pub unsafe fn unsafe_method(&self) {}
+                    pub fn safe_method(&self) {}
+                }
+            "#;
+        run_compiler_for_testing(test_src, |tcx| {
+            let input = Input { exclude_unsafe_fns: true, ..bindings_input_for_tests(tcx) };
+            let result = format_item(&input, find_def_id_by_name(tcx, "foo")).unwrap();
+            assert!(result.is_empty());
+
+            let result = format_item(&input, find_def_id_by_name(tcx, "SomeStruct")).unwrap();
+            let main_api = get_main_api_snippet(&result);
+            assert_cc_matches!(main_api.tokens, quote! { inline void safe_method() const; });
+            assert_cc_not_matches!(main_api.tokens, quote! { unsafe_method });
+        });
+    }
+
+    /// `test_format_item_fn_const` tests how bindings for an `const fn` are
+    /// generated.
+    ///
+    /// Right now the `const` qualifier is ignored, but one can imagine that in
+    /// the (very) long-term future such functions (including their bodies)
+    /// could be translated into C++ `consteval` functions.
+    #[test]
+    fn test_format_item_fn_const() {
+        let test_src = r#"
+                pub const fn foo(i: i32) -> i32 { i * 42 }
+            "#;
+        test_format_item(test_src, "foo", |result| {
+            // TODO(b/254095787): Update test expectations below once `const fn` from Rust
+            // is translated into a `consteval` C++ function.
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            assert!(!main_api.prereqs.is_empty());
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    inline std::int32_t foo(std::int32_t i);
+                }
+            );
+            assert!(!impl_details.cc.prereqs.is_empty());
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" std::int32_t ...( std::int32_t i);
+                    }
+                    ...
+                    inline std::int32_t foo(std::int32_t i) {
+                        return __crubit_internal::...(i);
+                    }
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    #[no_mangle]
+                    extern "C"
+                    fn ...(i: i32) -> i32 {
+                        ::rust_out::foo(i)
+                    }
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_fn_with_c_unwind_abi() {
+        // See also https://rust-lang.github.io/rfcs/2945-c-unwind-abi.html
+        let test_src = r#"
+                #![feature(c_unwind)]
+
+                #[no_mangle]
+                pub extern "C-unwind" fn may_throw() {}
+            "#;
+        test_format_item(test_src, "may_throw", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            assert!(main_api.prereqs.is_empty());
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    extern "C" void may_throw();
+                }
+            );
+        });
+    }
+
+    /// This test mainly verifies that `format_item` correctly propagates
+    /// `CcPrerequisites` of parameter types and return type.
+    #[test]
+    fn test_format_item_fn_cc_prerequisites_if_cpp_definition_needed() {
+        let test_src = r#"
+                pub fn foo(_i: i32) -> S { panic!("foo") }
+                pub struct S(i32);
+            "#;
+        test_format_item(test_src, "foo", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+
+            // Minimal coverage, just to double-check that the test setup works.
+            //
+            // Note that this is a definition, and therefore `S` should be defined
+            // earlier (not just forward declared).
+            assert_cc_matches!(main_api.tokens, quote! { S foo(std::int32_t _i);});
+            assert_cc_matches!(impl_details.cc.tokens, quote! { S foo(std::int32_t _i) { ... }});
+
+            // Main checks: `CcPrerequisites::includes`.
+            assert_cc_matches!(
+                format_cc_includes(&main_api.prereqs.includes),
+                quote! { include <cstdint> }
+            );
+            assert_cc_matches!(
+                format_cc_includes(&impl_details.cc.prereqs.includes),
+                quote! { include <cstdint> }
+            );
+
+            // Main checks: `CcPrerequisites::defs` and `CcPrerequisites::fwd_decls`.
+            //
+            // Verifying the actual def_id is tricky, because `test_format_item` doesn't
+            // expose `tcx` to the verification function (and therefore calling
+            // `find_def_id_by_name` is not easily possible).
+            //
+            // Note that `main_api` and `impl_details` have different expectations.
+            assert_eq!(0, main_api.prereqs.defs.len());
+            assert_eq!(1, main_api.prereqs.fwd_decls.len());
+            assert_eq!(1, impl_details.cc.prereqs.defs.len());
+            assert_eq!(0, impl_details.cc.prereqs.fwd_decls.len());
+        });
+    }
+
+    /// This test verifies that `format_item` uses `CcPrerequisites::fwd_decls`
+    /// rather than `CcPrerequisites::defs` for functions that only need a
+    /// C++ declaration (and don't need a C++ definition).
+    #[test]
+    fn test_format_item_fn_cc_prerequisites_if_only_cpp_declaration_needed() {
+        let test_src = r#"
+                // `foo` will only have a C++ declaration (and no C++ definition)
+                // in the generated `..._cc_api.h` header.
+                //
+                // Therefore `foo`'s `CcPrerequisites` should include `S`
+                // as a `fwd_decls` edge, rather than as a `defs` edge.
+                #[no_mangle]
+                pub extern "C" fn foo(s: S) -> bool { s.0 }
+
+                #[repr(C)]
+                pub struct S(bool);
+            "#;
+        test_format_item(test_src, "foo", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+
+            // Minimal coverage, just to double-check that the test setup works.
+            //
+            // Note that this is only a function *declaration* (not a function definition -
+            // there is no function body), and therefore `S` just needs to be
+            // forward-declared earlier.
+            assert_cc_matches!(main_api.tokens, quote! { extern "C" bool foo(::rust_out::S s); });
+
+            // Main checks: `CcPrerequisites::defs` and `CcPrerequisites::fwd_decls`.
+            //
+            // Verifying the actual def_id is tricky, because `test_format_item` doesn't
+            // expose `tcx` to the verification function (and therefore calling
+            // `find_def_id_by_name` is not easily possible).
+            assert_eq!(0, main_api.prereqs.defs.len());
+            assert_eq!(1, main_api.prereqs.fwd_decls.len());
+        });
+    }
+
+    #[test]
+    fn test_format_item_fn_with_type_aliased_return_type() {
+        // Type aliases disappear at the `rustc_middle::ty::Ty` level and therefore in
+        // the short-term the generated bindings also ignore type aliases.
+        //
+        // TODO(b/254096006): Consider preserving `type` aliases when generating
+        // bindings.
+        let test_src = r#"
+                type MyTypeAlias = f64;
+
+                #[no_mangle]
+                pub extern "C" fn type_aliased_return() -> MyTypeAlias { 42.0 }
+            "#;
+        test_format_item(test_src, "type_aliased_return", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            assert!(main_api.prereqs.is_empty());
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    extern "C" double type_aliased_return();
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_fn_with_doc_comment_with_unmangled_name() {
+        let test_src = r#"
+            /// Outer line doc.
+            /** Outer block doc that spans lines.
+             */
+            #[doc = "Doc comment via doc attribute."]
+            #[no_mangle]
+            pub extern "C" fn fn_with_doc_comment_with_unmangled_name() {}
+          "#;
+        test_format_item(test_src, "fn_with_doc_comment_with_unmangled_name", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            assert!(main_api.prereqs.is_empty());
+            let doc_comments = [
+                " Outer line doc.",
+                "",
+                " Outer block doc that spans lines.",
+                "             ",
+                "",
+                "Doc comment via doc attribute.",
+                "",
+                "Generated from: <crubit_unittests.rs>;l=7",
+            ]
+            .join("\n");
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    __COMMENT__ #doc_comments
+                    extern "C" void fn_with_doc_comment_with_unmangled_name();
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_fn_with_inner_doc_comment_with_unmangled_name() {
+        let test_src = r#"
+            /// Outer doc comment.
+            #[no_mangle]
+            pub extern "C" fn fn_with_inner_doc_comment_with_unmangled_name() {
+                //! Inner doc comment.
+            }
+          "#;
+        test_format_item(test_src, "fn_with_inner_doc_comment_with_unmangled_name", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            assert!(main_api.prereqs.is_empty());
+            let doc_comments = [
+                " Outer doc comment.",
+                " Inner doc comment.",
+                "Generated from: <crubit_unittests.rs>;l=4",
+            ]
+            .join("\n\n");
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    __COMMENT__ #doc_comments
+                    extern "C" void fn_with_inner_doc_comment_with_unmangled_name();
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_fn_with_doc_comment_with_mangled_name() {
+        let test_src = r#"
+                /// Doc comment of a function with mangled name.
+                pub extern "C" fn fn_with_doc_comment_with_mangled_name() {}
+            "#;
+        test_format_item(test_src, "fn_with_doc_comment_with_mangled_name", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            assert!(main_api.prereqs.is_empty());
+            let comment = " Doc comment of a function with mangled name.\n\n\
+                           Generated from: <crubit_unittests.rs>;l=3";
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    __COMMENT__ #comment
+                    inline void fn_with_doc_comment_with_mangled_name();
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_unsupported_fn_name_is_reserved_cpp_keyword() {
+        let test_src = r#"
+                #[no_mangle]
+                pub extern "C" fn reinterpret_cast() -> () {}
+            "#;
+        test_format_item(test_src, "reinterpret_cast", |result| {
+            let err = result.unwrap_err();
+            assert_eq!(
+                err,
+                "Error formatting function name: \
+                       `reinterpret_cast` is a C++ reserved keyword \
+                       and can't be used as a C++ identifier"
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_unsupported_fn_ret_type() {
+        let test_src = r#"
+                pub fn foo() -> [i32; 2] { [123, 456] }
+            "#;
+        test_format_item(test_src, "foo", |result| {
+            let err = result.unwrap_err();
+            assert_eq!(
+                err,
+                "Error formatting function return type: \
+                       The following Rust type is not supported yet: [i32; 2]"
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_fn_with_late_bound_lifetimes() {
+        let test_src = r#"
+                pub fn foo(arg: &i32) -> &i32 { arg }
+
+                // Lifetime inference translates the above into:
+                //     pub fn foo<'a>(arg: &'a i32) -> &'a i32 { ... }
+                // leaving 'a lifetime late-bound (it is bound with a lifetime
+                // taken from each of the callsites).  In other words, we can't
+                // just call `no_bound_vars` on this `FnSig`'s `Binder`.
+            "#;
+        test_format_item(test_src, "foo", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    inline const std::int32_t& foo(
+                        const std::int32_t& arg [[clang::lifetimebound]]);
+                }
+            );
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" const std::int32_t* ...(const std::int32_t& arg);
+                    }
+                    inline const std::int32_t& foo(
+                            const std::int32_t& arg [[clang::lifetimebound]]) {
+                        return *__crubit_internal::...(arg);
+                    }
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    #[no_mangle]
+                    extern "C" fn ...(arg: &i32) -> *const i32 {
+                        ::rust_out::foo(arg) as *const i32
+                    }
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_fn_with_references_and_lifetimebound() {
+        let test_src = r#"
+                pub fn first<'a, 'b>(x: &'a mut i32, _y: &'b i32, _z: &'static i32)
+                    -> &'a mut i32 { x }
+                pub fn get_static(_x: &i32) -> &'static i32 { &42 }
+            "#;
+        test_format_item(test_src, "first", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    inline std::int32_t& first(
+                        std::int32_t& x [[clang::lifetimebound]],
+                        const std::int32_t& _y,
+                        const std::int32_t& _z);
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    extern "C" fn ...(x: &mut i32, _y: &i32, _z: &'static i32) -> *mut i32 {
+                        ::rust_out::first(x, _y, _z) as *mut i32
+                    }
+                }
+            );
+        });
+        test_format_item(test_src, "get_static", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    inline const std::int32_t& get_static(const std::int32_t& _x);
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_fn_with_reference_to_struct() {
+        let test_src = r#"
+                pub struct SomeStruct(i32);
+                pub fn get_ref(s: &SomeStruct) -> &SomeStruct { s }
+            "#;
+        test_format_item(test_src, "get_ref", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            // References only need a forward declaration of `SomeStruct`.
+            assert_eq!(0, main_api.prereqs.defs.len());
+            assert_eq!(1, main_api.prereqs.fwd_decls.len());
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    inline const ::rust_out::SomeStruct& get_ref(
+                        const ::rust_out::SomeStruct& s [[clang::lifetimebound]]);
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_fn_with_slice_and_str_references() {
+        let test_src = r#"
+                pub fn first_word(s: &str) -> &str { s }
+                pub fn sum(xs: &[i32]) -> i32 { xs.iter().sum() }
+                pub fn increment_all(xs: &mut [i32]) -> &mut [i32] { xs }
+            "#;
+        test_format_item(test_src, "first_word", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    inline rs_std::StrRef first_word(
+                        rs_std::StrRef s [[clang::lifetimebound]]);
+                }
+            );
+            // `rs_std::StrRef` is passed and returned by value (not via a pointer).
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" rs_std::StrRef ...(rs_std::StrRef s);
+                    }
+                    inline rs_std::StrRef first_word(rs_std::StrRef s) {
+                        return __crubit_internal::...(s);
+                    }
+                }
+            );
+            // The Rust thunk returns a raw slice pointer (because it can't rely on lifetime
+            // elision).
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    extern "C" fn ...(s: &str) -> *const str {
+                        ::rust_out::first_word(s) as *const str
+                    }
+                }
+            );
+        });
+        test_format_item(test_src, "sum", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    inline std::int32_t sum(rs_std::SliceRef<const std::int32_t> xs);
+                }
+            );
+        });
+        test_format_item(test_src, "increment_all", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    inline rs_std::SliceRef<std::int32_t> increment_all(
+                        rs_std::SliceRef<std::int32_t> xs [[clang::lifetimebound]]);
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    extern "C" fn ...(xs: &mut [i32]) -> *mut [i32] {
+                        ::rust_out::increment_all(xs) as *mut [i32]
+                    }
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_fn_with_box() {
+        let test_src = r#"
+                pub struct SomeStruct {
+                    pub x: i32,
+                }
+                pub fn make_box(x: i32) -> Box<SomeStruct> { Box::new(SomeStruct { x }) }
+                pub fn take_box(b: Box<SomeStruct>) -> i32 { b.x }
+                pub fn take_optional_box(b: Option<Box<SomeStruct>>) -> bool { b.is_some() }
+            "#;
+        test_format_item(test_src, "make_box", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    inline rs_std::Box<::rust_out::SomeStruct> make_box(std::int32_t x);
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    extern "C" fn ...(
+                        x: i32,
+                        __ret_slot: &mut ::core::mem::MaybeUninit<
+                            ::std::boxed::Box<::rust_out::SomeStruct>>
+                    ) -> () {
+                        __ret_slot.write(::rust_out::make_box(x));
+                    }
+                }
+            );
+        });
+        test_format_item(test_src, "take_box", |result| {
+            let result = result.unwrap();
+            let impl_details = get_impl_details_snippet(&result);
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" std::int32_t ...(rs_std::Box<::rust_out::SomeStruct>* b);
+                    }
+                    inline std::int32_t take_box(rs_std::Box<::rust_out::SomeStruct> b) {
+                        return __crubit_internal::...(&b);
+                    }
+                }
+            );
+            // The Rust thunk leaves a null pointer behind (so that the C++ destructor of the
+            // moved-away `rs_std::Box` is a no-op).
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    extern "C" fn ...(
+                        b: &mut ::core::option::Option<::std::boxed::Box<::rust_out::SomeStruct>>
+                    ) -> i32 {
+                        ::rust_out::take_box(
+                            b.take().expect("Null `rs_std::Box` passed as `Box<T>`"))
+                    }
+                }
+            );
+        });
+        test_format_item(test_src, "take_optional_box", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    inline bool take_optional_box(rs_std::Box<::rust_out::SomeStruct> b);
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    extern "C" fn ...(
+                        b: &mut ::core::option::Option<::std::boxed::Box<::rust_out::SomeStruct>>
+                    ) -> bool {
+                        ::rust_out::take_optional_box(::core::mem::take(b))
+                    }
+                }
+            );
+        });
+        test_format_item(test_src, "SomeStruct", |result| {
+            let result = result.unwrap();
+            let impl_details = get_impl_details_snippet(&result);
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" void ...(SomeStruct* __ptr);
+                    }
+                    inline void __crubit_box_drop(SomeStruct* ptr) {
+                        __crubit_internal::...(ptr);
+                    }
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    #[no_mangle]
+                    extern "C" fn ...(__ptr: *mut ::rust_out::SomeStruct) {
+                        ::core::mem::drop(unsafe { ::std::boxed::Box::from_raw(__ptr) });
+                    }
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_fn_with_rc_and_arc() {
+        let test_src = r#"
+                use std::rc::Rc;
+                use std::sync::Arc;
+
+                pub struct SomeStruct {
+                    pub x: i32,
+                }
+                pub fn make_rc(x: i32) -> Rc<SomeStruct> { Rc::new(SomeStruct { x }) }
+                pub fn take_rc(rc: Rc<SomeStruct>) -> i32 { rc.x }
+                pub fn take_optional_arc(arc: Option<Arc<SomeStruct>>) -> bool { arc.is_some() }
+            "#;
+        test_format_item(test_src, "make_rc", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    inline rs_std::Rc<::rust_out::SomeStruct> make_rc(std::int32_t x);
+                }
+            );
+            // `Rc<T>` is returned as the pointer from `Rc::into_raw`.
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    extern "C" fn ...(
+                        x: i32,
+                        __ret_slot: &mut ::core::mem::MaybeUninit<*const ::rust_out::SomeStruct>
+                    ) -> () {
+                        __ret_slot.write(::std::rc::Rc::into_raw(::rust_out::make_rc(x)));
+                    }
+                }
+            );
+        });
+        test_format_item(test_src, "take_rc", |result| {
+            let result = result.unwrap();
+            let impl_details = get_impl_details_snippet(&result);
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" std::int32_t ...(rs_std::Rc<::rust_out::SomeStruct>* rc);
+                    }
+                    inline std::int32_t take_rc(rs_std::Rc<::rust_out::SomeStruct> rc) {
+                        return __crubit_internal::...(&rc);
+                    }
+                }
+            );
+            // `__crubit_take_rc` leaves a null pointer behind (so that the C++ destructor of
+            // the moved-away `rs_std::Rc` is a no-op).
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    extern "C" fn ...(rc: &mut *const ::rust_out::SomeStruct) -> i32 {
+                        ::rust_out::take_rc(__crubit_take_rc(rc))
+                    }
+                }
+            );
+        });
+        test_format_item(test_src, "take_optional_arc", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    inline bool take_optional_arc(
+                        std::optional<rs_std::Arc<::rust_out::SomeStruct>> arc);
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    extern "C" fn ...(
+                        arc: ::core::option::Option<&mut *const ::rust_out::SomeStruct>
+                    ) -> bool {
+                        ::rust_out::take_optional_arc(arc.map(__crubit_take_arc))
+                    }
+                }
+            );
+        });
+        test_format_item(test_src, "SomeStruct", |result| {
+            let result = result.unwrap();
+            let impl_details = get_impl_details_snippet(&result);
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" void ...(const SomeStruct* __ptr);
+                        extern "C" void ...(const SomeStruct* __ptr);
+                    }
+                    inline void __crubit_rc_clone(const SomeStruct* ptr) {
+                        __crubit_internal::...(ptr);
+                    }
+                    inline void __crubit_rc_drop(const SomeStruct* ptr) {
+                        __crubit_internal::...(ptr);
+                    }
+                    ...
+                    inline void __crubit_arc_clone(const SomeStruct* ptr) {
+                        __crubit_internal::...(ptr);
+                    }
+                    inline void __crubit_arc_drop(const SomeStruct* ptr) {
+                        __crubit_internal::...(ptr);
+                    }
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    #[no_mangle]
+                    extern "C" fn ...(__ptr: *const ::rust_out::SomeStruct) {
+                        unsafe { ::std::rc::Rc::increment_strong_count(__ptr) }
+                    }
+                    #[no_mangle]
+                    extern "C" fn ...(__ptr: *const ::rust_out::SomeStruct) {
+                        unsafe { ::std::rc::Rc::decrement_strong_count(__ptr) }
+                    }
+                    ...
+                    extern "C" fn ...(__ptr: *const ::rust_out::SomeStruct) {
+                        unsafe { ::std::sync::Arc::decrement_strong_count(__ptr) }
+                    }
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_fn_with_option() {
+        let test_src = r#"
+                pub fn find(x: i32) -> Option<i32> { if x > 0 { Some(x) } else { None } }
+                pub fn unwrap_or_zero(x: Option<i32>) -> i32 { x.unwrap_or(0) }
+            "#;
+        test_format_item(test_src, "find", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    inline std::optional<std::int32_t> find(std::int32_t x);
+                }
+            );
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" void ...(
+                            std::int32_t x, bool* __ret_is_some, std::int32_t* __ret_ptr);
+                    }
+                    inline std::optional<std::int32_t> find(std::int32_t x) {
+                        bool __ret_is_some;
+                        crubit::ReturnValueSlot<std::int32_t> __ret_slot;
+                        __crubit_internal::...(x, &__ret_is_some, __ret_slot.Get());
+                        if (!__ret_is_some) { return std::nullopt; }
+                        return std::move(__ret_slot).AssumeInitAndTakeValue();
+                    }
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    extern "C" fn ...(
+                        x: i32,
+                        __ret_is_some: &mut ::core::mem::MaybeUninit<bool>,
+                        __ret_slot: &mut ::core::mem::MaybeUninit<i32>
+                    ) -> () {
+                        match ::rust_out::find(x) {
+                            Some(__ret_value) => {
+                                __ret_slot.write(__ret_value);
+                                __ret_is_some.write(true);
+                            }
+                            None => { __ret_is_some.write(false); }
+                        }
+                    }
+                }
+            );
+        });
+        test_format_item(test_src, "unwrap_or_zero", |result| {
+            let result = result.unwrap();
+            let impl_details = get_impl_details_snippet(&result);
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" std::int32_t ...(std::int32_t* x);
+                    }
+                    inline std::int32_t unwrap_or_zero(std::optional<std::int32_t> x) {
+                        return __crubit_internal::...(x ? &*x : nullptr);
+                    }
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    extern "C" fn ...(
+                        x: ::core::option::Option<&mut ::core::mem::MaybeUninit<i32>>
+                    ) -> i32 {
+                        ::rust_out::unwrap_or_zero(
+                            x.map(|value| unsafe { value.assume_init_read() }))
+                    }
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_fn_with_string_and_vec() {
+        let test_src = r#"
+                pub fn make_vec(len: usize) -> Vec<i32> { vec![0; len] }
+                pub fn concat(s: String, suffix: Option<String>) -> String {
+                    s + &suffix.unwrap_or_default()
+                }
+            "#;
+        test_format_item(test_src, "make_vec", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    inline rs_std::Vec<std::int32_t> make_vec(std::uintptr_t len);
+                }
+            );
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" void ...(
+                            std::uintptr_t len, rs_std::Vec<std::int32_t>* __ret_ptr);
+                    }
+                    inline rs_std::Vec<std::int32_t> make_vec(std::uintptr_t len) {
+                        crubit::ReturnValueSlot<rs_std::Vec<std::int32_t>> __ret_slot;
+                        __crubit_internal::...(len, __ret_slot.Get());
+                        return std::move(__ret_slot).AssumeInitAndTakeValue();
+                    }
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    extern "C" fn ...(
+                        len: usize,
+                        __ret_slot: &mut ::core::mem::MaybeUninit<__CrubitVec<i32>>
+                    ) -> () {
+                        __ret_slot.write(__CrubitVec::from_vec(::rust_out::make_vec(len)));
+                    }
+                }
+            );
+        });
+        test_format_item(test_src, "concat", |result| {
+            let result = result.unwrap();
+            let impl_details = get_impl_details_snippet(&result);
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    extern "C" fn ...(
+                        s: &mut __CrubitVec<u8>,
+                        suffix: ::core::option::Option<&mut __CrubitVec<u8>>,
+                        __ret_slot: &mut ::core::mem::MaybeUninit<__CrubitVec<u8>>
+                    ) -> () {
+                        __ret_slot.write(__CrubitVec::from_string(::rust_out::concat(
+                            __CrubitVec::take_string(s),
+                            suffix.map(__CrubitVec::take_string))));
+                    }
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_fn_with_fn_ptrs() {
+        let test_src = r#"
+                pub fn apply(f: extern "C" fn(i32) -> i32, x: i32) -> i32 { f(x) }
+                fn double(x: i32) -> i32 { x * 2 }
+                pub fn get_doubler() -> fn(i32) -> i32 { double }
+                pub fn call(f: fn(i32) -> i32, x: i32) -> i32 { f(x) }
+            "#;
+        test_format_item(test_src, "apply", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    inline std::int32_t apply(
+                        std::add_pointer_t<std::int32_t(std::int32_t)> f, std::int32_t x);
+                }
+            );
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" std::int32_t ...(
+                            std::add_pointer_t<std::int32_t(std::int32_t)> f, std::int32_t x);
+                    }
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    extern "C" fn ...(f: extern "C" fn(i32) -> i32, x: i32) -> i32 {
+                        ::rust_out::apply(f, x)
+                    }
+                }
+            );
+        });
+        test_format_item(test_src, "get_doubler", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    inline rs_std::FnPtr<std::int32_t(std::int32_t)> get_doubler();
+                }
+            );
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" void ...(
+                            rs_std::FnPtr<std::int32_t(std::int32_t)>* __ret_ptr);
+                    }
+                    inline rs_std::FnPtr<std::int32_t(std::int32_t)> get_doubler() {
+                        crubit::ReturnValueSlot<rs_std::FnPtr<std::int32_t(std::int32_t)>>
+                            __ret_slot;
+                        __crubit_internal::...(__ret_slot.Get());
+                        return std::move(__ret_slot).AssumeInitAndTakeValue();
+                    }
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    extern "C" fn ...(
+                        __ret_slot: &mut ::core::mem::MaybeUninit<
+                            __CrubitFnPtr<fn(i32) -> i32>>
+                    ) -> () {
+                        __ret_slot.write(__CrubitFnPtr {
+                            fn_ptr: ::rust_out::get_doubler(),
+                            trampoline: {
+                                extern "C" fn __crubit_trampoline(
+                                    __fn_ptr: fn(i32) -> i32, __arg0: i32
+                                ) -> i32 {
+                                    ...
+                                }
+                                __crubit_trampoline as *const ()
+                            },
+                        });
+                    }
+                }
+            );
+        });
+        test_format_item(test_src, "call", |result| {
+            let result = result.unwrap();
+            let impl_details = get_impl_details_snippet(&result);
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    inline std::int32_t call(
+                        rs_std::FnPtr<std::int32_t(std::int32_t)> f, std::int32_t x) {
+                        return __crubit_internal::...(&f, x);
+                    }
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    extern "C" fn ...(
+                        f: &mut ::core::mem::MaybeUninit<__CrubitFnPtr<fn(i32) -> i32>>,
+                        x: i32
+                    ) -> i32 {
+                        ::rust_out::call(unsafe { f.assume_init_read() }.fn_ptr, x)
+                    }
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_trait() {
+        let test_src = r#"
+                /// Doc comment of `Shape`.
+                pub trait Shape {
+                    /// Doc comment of `area`.
+                    fn area(&self) -> f64;
+                    fn scale(&mut self, factor: f64);
+                    fn name(&self) -> String where Self: Sized { "shape".to_string() }
+                }
+            "#;
+        test_format_item(test_src, "Shape", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    __COMMENT__ "Doc comment of `Shape`.\n\nGenerated from: ..."
+                    class Shape {
+                      public:
+                        class DynBox;
+                        class DynArc;
+                        struct __CrubitVtable;
+                        __COMMENT__ "Rust calls the methods of C++ implementations through \
+                                     this vtable - they must not throw exceptions."
+                        static const __CrubitVtable __crubit_vtable;
+                        virtual ~Shape() = default;
+                        __COMMENT__ "Doc comment of `area`.\n\nGenerated from: ..."
+                        virtual double area() const = 0;
+                        __COMMENT__ "Generated from: ..."
+                        virtual void scale(double factor) = 0;
+                    };
+                    ...
+                    class Shape::DynBox final {
+                      public:
+                        DynBox(DynBox&&);
+                        DynBox& operator=(DynBox&&);
+                        DynBox(const DynBox&) = delete;
+                        DynBox& operator=(const DynBox&) = delete;
+                        ~DynBox();
+                        double area() const;
+                        void scale(double factor);
+                      private:
+                        ...
+                        alignas(void*) unsigned char __opaque_blob_of_bytes[2 * sizeof(void*)];
+                    };
+                    ...
+                    class Shape::DynArc final {
+                      public:
+                        DynArc(DynArc&&);
+                        DynArc& operator=(DynArc&&);
+                        DynArc(const DynArc&);
+                        DynArc& operator=(const DynArc&);
+                        ~DynArc();
+                        double area() const;
+                      private:
+                        ...
+                    };
+                }
+            );
+            // Methods with a `where Self: Sized` bound can't be called through a trait
+            // object.
+            assert_cc_not_matches!(main_api.tokens, quote! { name });
+
+            let impl_details = get_impl_details_snippet(&result);
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    struct Shape::__CrubitVtable final {
+                        double (*area)(const void*);
+                        void (*scale)(void*, double);
+                    };
+                    inline const Shape::__CrubitVtable Shape::__crubit_vtable = {
+                        [](const void* __self) noexcept -> double {
+                            return static_cast<const Shape*>(__self)->area();
+                        },
+                        [](void* __self, double factor) noexcept -> void {
+                            return static_cast<Shape*>(__self)->scale(factor);
+                        },
+                    };
+                }
+            );
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" void __crubit_thunk_dyn_box_drop_rust_out_Shape(
+                            Shape::DynBox* __self);
+                        extern "C" void __crubit_thunk_dyn_box_move_rust_out_Shape(
+                            Shape::DynBox* __self, Shape::DynBox* __other);
+                        extern "C" void __crubit_thunk_dyn_box_move_assign_rust_out_Shape(
+                            Shape::DynBox* __self, Shape::DynBox* __other);
+                        extern "C" double __crubit_thunk_dyn_box_fn_area_rust_out_Shape(
+                            const Shape::DynBox* __self);
+                        extern "C" void __crubit_thunk_dyn_box_fn_scale_rust_out_Shape(
+                            Shape::DynBox* __self, double factor);
+                    }
+                    inline Shape::DynBox::~DynBox() {
+                        __crubit_internal::__crubit_thunk_dyn_box_drop_rust_out_Shape(this);
+                    }
+                    ...
+                    inline double Shape::DynBox::area() const {
+                        return __crubit_internal::__crubit_thunk_dyn_box_fn_area_rust_out_Shape(
+                            this);
+                    }
+                    inline void Shape::DynBox::scale(double factor) {
+                        return __crubit_internal::__crubit_thunk_dyn_box_fn_scale_rust_out_Shape(
+                            this, factor);
+                    }
+                }
+            );
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    inline Shape::DynArc::DynArc(const DynArc& other) {
+                        __crubit_internal::__crubit_thunk_dyn_arc_clone_rust_out_Shape(
+                            this, &other);
+                    }
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    #[allow(non_camel_case_types)]
+                    #[repr(C)]
+                    struct __CrubitCcVtable_rust_out_Shape {
+                        area: extern "C" fn(*const ::core::ffi::c_void,) -> f64,
+                        scale: extern "C" fn(*mut ::core::ffi::c_void, f64) -> (),
+                    }
+                    #[allow(non_camel_case_types)]
+                    struct __CrubitCcObject_rust_out_Shape {
+                        obj: *mut ::core::ffi::c_void,
+                        vtable: &'static __CrubitCcVtable_rust_out_Shape,
+                    }
+                    impl ::rust_out::Shape for __CrubitCcObject_rust_out_Shape {
+                        fn area(&self,) -> f64 {
+                            (self.vtable.area)(self.obj,)
+                        }
+                        fn scale(&mut self, factor: f64) -> () {
+                            (self.vtable.scale)(self.obj, factor)
+                        }
+                    }
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    #[no_mangle]
+                    extern "C" fn __crubit_thunk_dyn_box_move_rust_out_Shape(
+                        __self: &mut ::core::mem::MaybeUninit<
+                            ::core::option::Option<::std::boxed::Box<dyn ::rust_out::Shape>>>,
+                        __other: &mut ::core::option::Option<
+                            ::std::boxed::Box<dyn ::rust_out::Shape>>
+                    ) {
+                        ...
+                    }
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    #[no_mangle]
+                    extern "C" fn __crubit_thunk_dyn_box_fn_scale_rust_out_Shape(
+                        __self: &mut ::core::option::Option<
+                            ::std::boxed::Box<dyn ::rust_out::Shape>>,
+                        factor: f64
+                    ) -> () {
+                        ...
+                    }
+                }
+            );
+            // `Arc<dyn Shape>` doesn't give mutable access to the trait object.
+            assert_cc_not_matches!(
+                impl_details.cc.tokens,
+                quote! { __crubit_thunk_dyn_arc_fn_scale_rust_out_Shape }
+            );
+            assert_rs_not_matches!(
+                impl_details.rs,
+                quote! { __crubit_thunk_dyn_arc_fn_scale_rust_out_Shape }
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_unsupported_trait() {
+        let test_src = r#"
+                pub trait GenericTrait<T> {
+                    fn get(&self) -> T;
+                }
+                pub trait SuperTrait {}
+                pub trait SubTrait: SuperTrait {}
+                pub trait NotObjectSafe {
+                    fn new() -> Self;
+                }
+                pub trait TraitWithAssocType {
+                    type Output;
+                }
+                pub struct SomeStruct(i32);
+                pub trait TraitWithStructParam {
+                    fn take(&self, s: SomeStruct);
+                }
+                pub trait TraitWithRefReturn {
+                    fn get(&self) -> &i32;
+                }
+            "#;
+        test_format_item(test_src, "GenericTrait", |result| {
+            let err = result.unwrap_err();
+            assert_eq!(err, "Generic traits are not supported yet");
+        });
+        test_format_item(test_src, "SubTrait", |result| {
+            let err = result.unwrap_err();
+            assert_eq!(err, "Traits with supertraits are not supported yet");
+        });
+        test_format_item(test_src, "NotObjectSafe", |result| {
+            let err = result.unwrap_err();
+            assert_eq!(err, "Only object-safe traits are supported");
+        });
+        test_format_item(test_src, "TraitWithAssocType", |result| {
+            let err = result.unwrap_err();
+            assert_eq!(
+                err,
+                "Error handling `Output`: \
+                 Associated types and constants of traits are not supported yet"
+            );
+        });
+        test_format_item(test_src, "TraitWithStructParam", |result| {
+            let err = result.unwrap_err();
+            assert_eq!(
+                err,
+                "Error handling `take`: \
+                 Parameters of trait methods need to have a C-compatible ABI: SomeStruct"
+            );
+        });
+        test_format_item(test_src, "TraitWithRefReturn", |result| {
+            let err = result.unwrap_err();
+            assert_eq!(
+                err,
+                "Error handling `get`: \
+                 Trait methods returning references are not supported yet (b/258235219)"
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_fn_with_trait_objects() {
+        let test_src = r#"
+                use std::sync::Arc;
+                pub trait Shape {
+                    fn area(&self) -> f64;
+                }
+                pub fn total_area(a: &dyn Shape, b: &mut dyn Shape) -> f64 {
+                    a.area() + b.area()
+                }
+                pub fn make_box() -> Box<dyn Shape> { unimplemented!() }
+                pub fn take_arc(_shape: Arc<dyn Shape>) {}
+                pub fn return_ref(shape: &dyn Shape) -> &dyn Shape { shape }
+                #[allow(improper_ctypes_definitions)]
+                pub extern "C" fn extern_c_fn(_shape: &dyn Shape) {}
+            "#;
+        test_format_item(test_src, "total_area", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    inline double total_area(const ::rust_out::Shape& a, ::rust_out::Shape& b);
+                }
+            );
+            let impl_details = get_impl_details_snippet(&result);
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" double ...(
+                            const ::rust_out::Shape& a,
+                            const ::rust_out::Shape::__CrubitVtable* __param_0_vtable,
+                            ::rust_out::Shape& b,
+                            const ::rust_out::Shape::__CrubitVtable* __param_1_vtable);
+                    }
+                    inline double total_area(const ::rust_out::Shape& a, ::rust_out::Shape& b) {
+                        return __crubit_internal::...(
+                            a, &::rust_out::Shape::__crubit_vtable,
+                            b, &::rust_out::Shape::__crubit_vtable);
+                    }
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    extern "C" fn ...(
+                        a: *mut ::core::ffi::c_void,
+                        __param_0_vtable: &'static __CrubitCcVtable_rust_out_Shape,
+                        b: *mut ::core::ffi::c_void,
+                        __param_1_vtable: &'static __CrubitCcVtable_rust_out_Shape
+                    ) -> f64 {
+                        ::rust_out::total_area(
+                            &__CrubitCcObject_rust_out_Shape { obj: a, vtable: __param_0_vtable },
+                            &mut __CrubitCcObject_rust_out_Shape {
+                                obj: b,
+                                vtable: __param_1_vtable
+                            })
+                    }
+                }
+            );
+        });
+        test_format_item(test_src, "make_box", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! { inline ::rust_out::Shape::DynBox make_box(); }
+            );
+            // `Shape::DynBox` can't be used with just a forward declaration of `Shape`.
+            assert_eq!(1, main_api.prereqs.defs.len());
+            let impl_details = get_impl_details_snippet(&result);
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    extern "C" fn ...(
+                        __ret_slot: &mut ::core::mem::MaybeUninit<
+                            ::core::option::Option<::std::boxed::Box<dyn ::rust_out::Shape>>>
+                    ) -> () {
+                        __ret_slot.write(::core::option::Option::Some(::rust_out::make_box()));
+                    }
+                }
+            );
+        });
+        test_format_item(test_src, "take_arc", |result| {
+            let result = result.unwrap();
+            let impl_details = get_impl_details_snippet(&result);
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    inline void take_arc(::rust_out::Shape::DynArc _shape) {
+                        return __crubit_internal::...(&_shape);
+                    }
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    extern "C" fn ...(
+                        _shape: &mut ::core::option::Option<
+                            ::std::sync::Arc<dyn ::rust_out::Shape>>
+                    ) -> () {
+                        ::rust_out::take_arc(
+                            _shape.take().expect("Moved-from trait object passed from C++"))
+                    }
+                }
+            );
+        });
+        test_format_item(test_src, "return_ref", |result| {
+            let err = result.unwrap_err();
+            assert_eq!(err, "`&dyn Trait` is only supported as a function parameter type");
+        });
+        test_format_item(test_src, "extern_c_fn", |result| {
+            let err = result.unwrap_err();
+            assert_eq!(
+                err,
+                "Trait objects are not supported in signatures of `extern \"C\"` functions"
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_fn_with_result() {
+        let test_src = r#"
+                use std::fmt;
+
+                pub struct SomeError {
+                    pub code: i32,
+                }
+                impl fmt::Display for SomeError {
+                    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                        write!(f, "Error #{}", self.code)
+                    }
+                }
+                pub fn parse(x: i32) -> Result<i32, SomeError> {
+                    if x >= 0 { Ok(x) } else { Err(SomeError { code: x }) }
+                }
+                pub fn check(x: i32) -> Result<(), SomeError> {
+                    parse(x).map(|_| ())
+                }
+                pub fn take_result(r: Result<i32, SomeError>) -> bool { r.is_ok() }
+                pub fn take_unit_result(r: Result<(), SomeError>) -> bool { r.is_ok() }
+            "#;
+        test_format_item(test_src, "parse", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    inline rs_std::Result<std::int32_t, ::rust_out::SomeError> parse(
+                        std::int32_t x);
+                }
+            );
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" void ...(
+                            std::int32_t x,
+                            bool* __ret_is_ok,
+                            std::int32_t* __ret_ptr_ok,
+                            ::rust_out::SomeError* __ret_ptr_err);
+                    }
+                    inline rs_std::Result<std::int32_t, ::rust_out::SomeError> parse(
+                            std::int32_t x) {
+                        bool __ret_is_ok;
+                        crubit::ReturnValueSlot<std::int32_t> __ret_slot_ok;
+                        crubit::ReturnValueSlot<::rust_out::SomeError> __ret_slot_err;
+                        __crubit_internal::...(
+                            x, &__ret_is_ok, __ret_slot_ok.Get(), __ret_slot_err.Get());
+                        if (__ret_is_ok) {
+                            return rs_std::Result<std::int32_t, ::rust_out::SomeError>::Ok(
+                                std::move(__ret_slot_ok).AssumeInitAndTakeValue());
+                        }
+                        return rs_std::Result<std::int32_t, ::rust_out::SomeError>::Err(
+                            std::move(__ret_slot_err).AssumeInitAndTakeValue());
+                    }
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    extern "C" fn ...(
+                        x: i32,
+                        __ret_is_ok: &mut ::core::mem::MaybeUninit<bool>,
+                        __ret_slot_ok: &mut ::core::mem::MaybeUninit<i32>,
+                        __ret_slot_err: &mut ::core::mem::MaybeUninit<::rust_out::SomeError>
+                    ) -> () {
+                        match ::rust_out::parse(x) {
+                            Ok(__ret_value) => {
+                                __ret_slot_ok.write(__ret_value);
+                                __ret_is_ok.write(true);
+                            }
+                            Err(__ret_error) => {
+                                __ret_slot_err.write(__ret_error);
+                                __ret_is_ok.write(false);
+                            }
+                        }
+                    }
+                }
+            );
+        });
+        test_format_item(test_src, "check", |result| {
+            let result = result.unwrap();
+            let impl_details = get_impl_details_snippet(&result);
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    if (__ret_is_ok) {
+                        return rs_std::Result<std::monostate, ::rust_out::SomeError>::Ok(
+                            std::monostate());
+                    }
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    match ::rust_out::check(x) {
+                        Ok(()) => { __ret_is_ok.write(true); }
+                        Err(__ret_error) => { ... }
+                    }
+                }
+            );
+        });
+        test_format_item(test_src, "take_result", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    inline bool take_result(
+                        rs_std::Result<std::int32_t, ::rust_out::SomeError> r);
+                }
+            );
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" bool ...(
+                            bool r_is_ok, std::int32_t* r_ok, ::rust_out::SomeError* r_err);
+                    }
+                    inline bool take_result(
+                            rs_std::Result<std::int32_t, ::rust_out::SomeError> r) {
+                        return __crubit_internal::...(
+                            r.is_ok(), r.get_if_ok(), r.get_if_err());
+                    }
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    extern "C" fn ...(
+                        r_is_ok: bool,
+                        r_ok: ::core::option::Option<&mut ::core::mem::MaybeUninit<i32>>,
+                        r_err: ::core::option::Option<
+                            &mut ::core::mem::MaybeUninit<::rust_out::SomeError>>
+                    ) -> bool {
+                        ::rust_out::take_result(
+                            if r_is_ok {
+                                ::core::result::Result::Ok(unsafe {
+                                    r_ok.expect("Valueless `rs_std::Result` passed from C++")
+                                        .assume_init_read()
+                                })
+                            } else {
+                                ::core::result::Result::Err(unsafe {
+                                    r_err.expect("Valueless `rs_std::Result` passed from C++")
+                                        .assume_init_read()
+                                })
+                            })
+                    }
+                }
+            );
+        });
+        test_format_item(test_src, "take_unit_result", |result| {
+            let result = result.unwrap();
+            let impl_details = get_impl_details_snippet(&result);
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    return __crubit_internal::...(r.is_ok(), r.get_if_err());
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    if r_is_ok {
+                        ::core::result::Result::Ok(())
+                    } else {
+                        ...
+                    }
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_fn_with_result_mapped_to_absl_status_or() {
+        let test_src = r#"
+                use std::fmt;
+
+                pub struct SomeError {
+                    pub code: i32,
+                }
+                impl fmt::Display for SomeError {
+                    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                        write!(f, "Error #{}", self.code)
+                    }
+                }
+                pub fn parse(x: i32) -> Result<i32, SomeError> {
+                    if x >= 0 { Ok(x) } else { Err(SomeError { code: x }) }
+                }
+                pub fn check(x: i32) -> Result<(), SomeError> {
+                    parse(x).map(|_| ())
+                }
+                pub fn take_result(r: Result<i32, SomeError>) -> bool { r.is_ok() }
+            "#;
+        run_compiler_for_testing(test_src, |tcx| {
+            let input = Input {
+                result_mapping: ResultMapping::AbslStatusOr,
+                ..bindings_input_for_tests(tcx)
+            };
+
+            let result = format_item(&input, find_def_id_by_name(tcx, "parse")).unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    inline absl::StatusOr<std::int32_t> parse(std::int32_t x);
+                }
+            );
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    if (__ret_is_ok) {
+                        return std::move(__ret_slot_ok).AssumeInitAndTakeValue();
+                    }
+                    return absl::UnknownError(
+                        std::move(__ret_slot_err).AssumeInitAndTakeValue().ToString());
+                }
+            );
+
+            let result = format_item(&input, find_def_id_by_name(tcx, "check")).unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    inline absl::Status check(std::int32_t x);
+                }
+            );
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    if (__ret_is_ok) { return absl::OkStatus(); }
+                }
+            );
+
+            let err = format_item(&input, find_def_id_by_name(tcx, "take_result")).unwrap_err();
+            assert_eq!(
+                format!("{err:#}"),
+                "Error handling parameter #0: \
+                 `Result` mapped to `absl::StatusOr` is only supported as a return type"
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_unsupported_fn_extern_c_with_option() {
+        let test_src = r#"
+                #[allow(improper_ctypes_definitions)]
+                #[no_mangle]
+                pub extern "C" fn find(x: i32) -> Option<i32> { Some(x) }
+            "#;
+        test_format_item(test_src, "find", |result| {
+            let err = result.unwrap_err();
+            assert_eq!(
+                err,
+                "`Option`, `Result`, `Box`, `Rc`, `Arc`, `String`, `Vec`, and Rust-ABI \
+                 function pointers are not supported in signatures of `extern \"C\"` functions"
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_fn_with_int128() {
+        let test_src = r#"
+                pub fn add(x: i128, y: u128) -> u128 { x as u128 + y }
+            "#;
+        test_format_item(test_src, "add", |result| {
+            // Parameters and return values are supported even on targets where Rust uses
+            // 8-byte alignment for `i128` (see `format_int128_for_cc` and
+            // `test_format_ty_for_cc_int128`).
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    inline unsigned __int128 add(__int128 x, unsigned __int128 y);
+                }
+            );
+            // `i128` and `u128` are passed and returned by pointer (because their
+            // `extern "C"` ABI may differ from the ABI of `__int128`).
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" void ...(
+                            __int128* x, unsigned __int128* y, unsigned __int128* __ret_ptr);
+                    }
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    extern "C" fn ...(
+                        x: &mut ::core::mem::MaybeUninit<i128>,
+                        y: &mut ::core::mem::MaybeUninit<u128>,
+                        __ret_slot: &mut ::core::mem::MaybeUninit<u128>
+                    ) -> () {
+                        ...
+                    }
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_unsupported_fn_with_nested_references() {
+        let test_src = r#"
+                pub fn foo(_x: &&i32) {}
+            "#;
+        test_format_item(test_src, "foo", |result| {
+            let err = result.unwrap_err();
+            assert_eq!(
+                err,
+                "Error handling parameter #0: \
+                 References to references are not supported yet: &&i32 (b/258235219)"
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_unsupported_generic_fn() {
+        let test_src = r#"
+                use std::default::Default;
+                use std::fmt::Display;
+                pub fn generic_function<T: Default + Display>() {
+                    println!("{}", T::default());
+                }
+            "#;
+        test_format_item(test_src, "generic_function", |result| {
+            let err = result.unwrap_err();
+            assert_eq!(err, "Generic functions are not supported yet (b/259749023)");
+        });
+    }
+
+    #[test]
+    fn test_format_item_generic_fn_with_instantiations() {
+        let test_src = r#"
+                pub struct SomeStruct(i32);
+                pub fn generic_function<T: Copy>(t: T) -> T { t }
+            "#;
+        let instantiations =
+            ["rust_out::generic_function::<i32>", "rust_out::generic_function::<f64>"];
+        test_format_item_with_instantiations(
+            test_src,
+            "generic_function",
+            &instantiations,
+            |result| {
+                let result = result.unwrap();
+                let main_api = get_main_api_snippet(&result);
+                let impl_details = get_impl_details_snippet(&result);
+                assert!(main_api.prereqs.is_empty());
+                assert_cc_matches!(
+                    main_api.tokens,
+                    quote! {
+                        template <typename T>
+                        T generic_function(T t) = delete;
+                    }
+                );
+                assert_cc_matches!(
+                    impl_details.cc.tokens,
+                    quote! {
+                        namespace __crubit_internal {
+                            extern "C" std::int32_t ...(std::int32_t t);
+                        }
+                        template <>
+                        inline std::int32_t generic_function<std::int32_t>(std::int32_t t) {
+                            return __crubit_internal::...(t);
+                        }
+                        namespace __crubit_internal {
+                            extern "C" double ...(double t);
+                        }
+                        template <>
+                        inline double generic_function<double>(double t) {
+                            return __crubit_internal::...(t);
+                        }
+                    }
+                );
+                assert_rs_matches!(
+                    impl_details.rs,
+                    quote! {
+                        #[no_mangle]
+                        extern "C"
+                        fn ...(t: i32) -> i32 {
+                            ::rust_out::generic_function::<i32>(t)
+                        }
+                        #[no_mangle]
+                        extern "C"
+                        fn ...(t: f64) -> f64 {
+                            ::rust_out::generic_function::<f64>(t)
+                        }
+                    }
+                );
+            },
+        );
+    }
+
+    /// Instantiations with different Rust type arguments may map to the same C++
+    /// explicit specialization (e.g. `isize` and `i64` on 64-bit platforms).
+    #[test]
+    fn test_format_item_generic_fn_with_instantiations_with_same_cc_types() {
+        let test_src = r#"
+                pub fn generic_function<T: Copy>(t: T) -> T { t }
+            "#;
+        let instantiations = [
+            "rust_out::generic_function::<i64>",
+            "rust_out::generic_function::<isize>",
+        ];
+        test_format_item_with_instantiations(
+            test_src,
+            "generic_function",
+            &instantiations,
+            |result| {
+                let result = result.unwrap();
+                let impl_details = get_impl_details_snippet(&result);
+                assert_cc_matches!(
+                    impl_details.cc.tokens,
+                    quote! {
+                        template <>
+                        inline std::int64_t generic_function<std::int64_t>(std::int64_t t) {
+                            ...
+                        }
+                        __COMMENT__ "Error generating bindings for the instantiation \
+                                     `generic_function::<isize>`: The C++ template arguments \
+                                     of this instantiation are the same as the ones of the \
+                                     instantiation `generic_function::<i64>`"
+                    }
+                );
+                assert_cc_not_matches!(
+                    impl_details.cc.tokens,
+                    quote! { generic_function<std::intptr_t> }
+                );
+            },
+        );
+    }
+
+    /// Only the clashing instantiation of a generic type is skipped - the other
+    /// instantiations are still emitted.
+    #[test]
+    fn test_format_item_generic_struct_with_instantiations_with_same_cc_types() {
+        let test_src = r#"
+                pub struct Wrapper<T>(pub T);
+            "#;
+        let instantiations = [
+            "rust_out::Wrapper<u64>",
+            "rust_out::Wrapper<usize>",
+            "rust_out::Wrapper<f32>",
+        ];
+        test_format_item_with_instantiations(test_src, "Wrapper", &instantiations, |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    template <> struct ... Wrapper<std::uint64_t> final { ... };
+                    ...
+                    __COMMENT__ "Error generating bindings for the instantiation \
+                                 `Wrapper<usize>`: The C++ template arguments of this \
+                                 instantiation are the same as the ones of the instantiation \
+                                 `Wrapper<u64>`"
+                    template <> struct ... Wrapper<float> final { ... };
+                }
+            );
+            assert_cc_not_matches!(main_api.tokens, quote! { Wrapper<std::uintptr_t> });
+        });
+    }
+
+    #[test]
+    fn test_format_item_generic_fn_with_struct_instantiation() {
+        let test_src = r#"
+                #[derive(Clone, Copy)]
+                pub struct SomeStruct(i32);
+                pub fn generic_function<T: Copy>(t: T) -> T { t }
+            "#;
+        let instantiations = ["rust_out::generic_function::<rust_out::SomeStruct>"];
+        test_format_item_with_instantiations(
+            test_src,
+            "generic_function",
+            &instantiations,
+            |result| {
+                let result = result.unwrap();
+                let impl_details = get_impl_details_snippet(&result);
+                assert_eq!(1, impl_details.cc.prereqs.defs.len()); // `SomeStruct`
+                assert_cc_matches!(
+                    impl_details.cc.tokens,
+                    quote! {
+                        template <>
+                        inline ::rust_out::SomeStruct
+                        generic_function<::rust_out::SomeStruct>(::rust_out::SomeStruct t) {
+                            ...
+                        }
+                    }
+                );
+                assert_rs_matches!(
+                    impl_details.rs,
+                    quote! {
+                        ::rust_out::generic_function::<::rust_out::SomeStruct>(...)
+                    }
+                );
+            },
+        );
+    }
+
+    #[test]
+    fn test_format_item_generic_fn_with_late_bound_lifetimes_and_instantiations() {
+        let test_src = r#"
+                pub fn generic_function<'a, T>(x: &'a T) -> &'a T { x }
+            "#;
+        let instantiations = ["rust_out::generic_function::<i32>"];
+        test_format_item_with_instantiations(
+            test_src,
+            "generic_function",
+            &instantiations,
+            |result| {
+                let result = result.unwrap();
+                let main_api = get_main_api_snippet(&result);
+                let impl_details = get_impl_details_snippet(&result);
+                assert_cc_matches!(
+                    main_api.tokens,
+                    quote! {
+                        template <typename T>
+                        const T& generic_function(const T& x [[clang::lifetimebound]]) = delete;
+                    }
+                );
+                assert_cc_matches!(
+                    impl_details.cc.tokens,
+                    quote! {
+                        template <>
+                        inline const std::int32_t& generic_function<std::int32_t>(
+                                const std::int32_t& x [[clang::lifetimebound]]) {
+                            return *__crubit_internal::...(x);
+                        }
+                    }
+                );
+            },
+        );
+    }
 
-    use anyhow::Result;
-    use itertools::Itertools;
-    use proc_macro2::TokenStream;
-    use quote::quote;
-    use rustc_middle::ty::{Ty, TyCtxt};
-    use rustc_span::def_id::LocalDefId;
+    #[test]
+    fn test_parse_instantiations() {
+        let test_src = r#"
+                pub struct SomeStruct(i32);
+                pub struct GenericStruct<T>(T);
+                pub mod some_module {
+                    pub fn generic_function<T, U>(_t: T, _u: U) {}
+                }
+                impl SomeStruct {
+                    pub fn generic_method<T>(_t: T) {}
+                }
+            "#;
+        run_compiler_for_testing(test_src, |tcx| {
+            let instantiations = parse_instantiations(
+                tcx,
+                [
+                    "rust_out::some_module::generic_function::<u8, rust_out::SomeStruct>",
+                    "rust_out::SomeStruct::generic_method::<bool>",
+                    "rust_out::SomeStruct::generic_method::<bool>", // Duplicates are ignored.
+                    "rust_out::GenericStruct<rust_out::SomeStruct>",
+                    "rust_out::GenericStruct<rust_out::GenericStruct<f32>>",
+                ],
+            )
+            .unwrap();
+            let instantiations = instantiations
+                .into_iter()
+                .map(|(def_id, substs_list)| {
+                    let substs_list = substs_list
+                        .into_iter()
+                        .map(|substs| substs.types().map(|ty| ty.to_string()).join(", "))
+                        .collect_vec();
+                    (tcx.item_name(def_id).to_string(), substs_list)
+                })
+                .sorted()
+                .collect_vec();
+            assert_eq!(
+                instantiations,
+                vec![
+                    (
+                        "GenericStruct".to_string(),
+                        vec!["SomeStruct".to_string(), "GenericStruct<f32>".to_string()]
+                    ),
+                    ("generic_function".to_string(), vec!["u8, SomeStruct".to_string()]),
+                    ("generic_method".to_string(), vec!["bool".to_string()]),
+                ]
+            );
+        });
+    }
 
-    use crate::run_compiler::tests::run_compiler_for_testing;
-    use code_gen_utils::format_cc_includes;
-    use token_stream_matchers::{
-        assert_cc_matches, assert_cc_not_matches, assert_rs_matches, assert_rs_not_matches,
-    };
+    #[test]
+    fn test_parse_instantiations_errors() {
+        let test_src = r#"
+                pub struct SomeStruct(i32);
+                pub struct GenericStruct<T>(T);
+                pub fn generic_function<T: Copy>(t: T) -> T { t }
+                pub fn impl_trait_function(_t: impl Copy) {}
+            "#;
+        let testcases = [
+            (
+                "rust_out::generic_function",
+                "Expected `PATH::<TYPE_ARGS>` or `PATH<TYPE_ARGS>`",
+            ),
+            (
+                "rust_out::no_such_function::<i32>",
+                "Couldn't find a function named `rust_out::no_such_function` in the current crate",
+            ),
+            (
+                "rust_out::generic_function::<i32, i32>",
+                "`rust_out::generic_function` expects 1 type argument(s), but got 2",
+            ),
+            (
+                "rust_out::generic_function::<NoSuchType>",
+                "Unsupported type argument `NoSuchType` (expected either a primitive type, \
+                 or a path to a struct, enum, or union from the current crate)",
+            ),
+            (
+                "rust_out::generic_function::<rust_out::GenericStruct<i32>>",
+                "The type arguments don't satisfy the trait bound: \
+                 `GenericStruct<i32>: std::marker::Copy`",
+            ),
+            (
+                "rust_out::generic_function::<rust_out::SomeStruct>",
+                "The type arguments don't satisfy the trait bound: `SomeStruct: std::marker::Copy`",
+            ),
+            (
+                "rust_out::impl_trait_function::<i32>",
+                "`impl Trait` parameters are not supported yet (b/259749023)",
+            ),
+        ];
+        run_compiler_for_testing(test_src, |tcx| {
+            for (instantiation, expected_err) in testcases {
+                let err = parse_instantiations(tcx, [instantiation]).unwrap_err();
+                let expected_err =
+                    format!("Error parsing instantiation `{instantiation}`: {expected_err}");
+                assert_eq!(format!("{err:#}"), expected_err);
+            }
+        });
+    }
 
     #[test]
-    #[should_panic(expected = "No items named `missing_name`.\n\
-                               Instead found:\n`bar`,\n`foo`,\n`m1`,\n`m2`,\n`std`")]
-    fn test_find_def_id_by_name_panic_when_no_item_with_matching_name() {
+    fn test_format_item_unsupported_generic_struct() {
         let test_src = r#"
-                pub extern "C" fn foo() {}
+                pub struct Point<T> {
+                    pub x: T,
+                    pub y: T,
+                }
+            "#;
+        test_format_item(test_src, "Point", |result| {
+            let err = result.unwrap_err();
+            assert_eq!(err, "Generic types are not supported yet (b/259749095)");
+        });
+    }
 
-                pub mod m1 {
-                    pub fn bar() {}
+    #[test]
+    fn test_format_item_unsupported_generic_enum() {
+        let test_src = r#"
+                pub enum Point<T> {
+                    Cartesian{x: T, y: T},
+                    Polar{angle: T, dist: T},
+                }
+            "#;
+        test_format_item(test_src, "Point", |result| {
+            let err = result.unwrap_err();
+            assert_eq!(err, "Generic types are not supported yet (b/259749095)");
+        });
+    }
+
+    #[test]
+    fn test_format_item_unsupported_generic_union() {
+        let test_src = r#"
+                pub union SomeUnion<T> {
+                    pub x: std::mem::ManuallyDrop<T>,
+                    pub y: i32,
+                }
+            "#;
+        test_format_item(test_src, "SomeUnion", |result| {
+            let err = result.unwrap_err();
+            assert_eq!(err, "Generic types are not supported yet (b/259749095)");
+        });
+    }
+
+    #[test]
+    fn test_format_item_generic_struct_with_instantiations() {
+        let test_src = r#"
+                /// Doc comment of Wrapper.
+                pub struct Wrapper<T> {
+                    pub value: T,
+                }
+                impl<T> Wrapper<T> {
+                    pub fn create(value: T) -> Self {
+                        Self { value }
+                    }
+                }
+                impl Wrapper<f32> {
+                    pub fn only_for_f32() -> f32 {
+                        0.0
+                    }
+                }
+            "#;
+        let instantiations = ["rust_out::Wrapper<i32>", "rust_out::Wrapper<f32>"];
+        test_format_item_with_instantiations(test_src, "Wrapper", &instantiations, |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            assert!(main_api.prereqs.defs.is_empty());
+            assert!(main_api.prereqs.fwd_decls.is_empty());
+            let comment = " Doc comment of Wrapper.\n\n\
+                           Generated from: <crubit_unittests.rs>;l=3";
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    __COMMENT__ #comment
+                    template <typename T> struct Wrapper;
+                    template <> struct alignas(4) Wrapper<std::int32_t> final {
+                        public:
+                            ...
+                            Wrapper(Wrapper&&) = default;
+                            ...
+                        public:
+                            ...
+                            static inline ::rust_out::Wrapper<std::int32_t> create(
+                                std::int32_t value);
+                        private:
+                            std::int32_t value;
+                            ...
+                    };
+                    template <> struct alignas(4) Wrapper<float> final {
+                        ...
+                            static inline ::rust_out::Wrapper<float> create(float value);
+                            ...
+                            static inline float only_for_f32();
+                        private:
+                            float value;
+                            ...
+                    };
+                }
+            );
+            assert_cc_not_matches!(
+                main_api.tokens,
+                quote! { static inline std::int32_t only_for_f32(); }
+            );
+
+            let impl_details = get_impl_details_snippet(&result);
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    static_assert(sizeof(Wrapper<std::int32_t>) == 4, ...);
+                    static_assert(alignof(Wrapper<std::int32_t>) == 4, ...);
+                    inline void Wrapper<std::int32_t>::__crubit_field_offset_assertions() {
+                        static_assert(0 == offsetof(Wrapper, value));
+                    }
+                }
+            );
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    inline ::rust_out::Wrapper<std::int32_t>
+                    Wrapper<std::int32_t>::create(std::int32_t value) {
+                        ...
+                    }
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    const _: () = assert!(::std::mem::size_of::<::rust_out::Wrapper<i32>>() == 4);
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! { ::rust_out::Wrapper::<f32>::create(value) }
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_generic_struct_with_generic_method() {
+        let test_src = r#"
+                pub struct Wrapper<T> {
+                    pub value: T,
+                }
+                impl<T> Wrapper<T> {
+                    pub fn generic_method<U>(_u: U) {}
+                }
+            "#;
+        let instantiations = ["rust_out::Wrapper<i32>"];
+        test_format_item_with_instantiations(test_src, "Wrapper", &instantiations, |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    __COMMENT__ "Error generating bindings for `Wrapper::<T>::generic_method` \
+                                 defined at <crubit_unittests.rs>;l=6: \
+                                 Generic methods of generic types are not supported yet \
+                                 (b/259749023)"
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_fn_with_generic_struct_instantiation() {
+        let test_src = r#"
+                pub struct Wrapper<T> {
+                    pub value: T,
+                }
+                pub fn unwrap(w: Wrapper<i32>) -> i32 { w.value }
+            "#;
+        let instantiations = ["rust_out::Wrapper<i32>"];
+        test_format_item_with_instantiations(test_src, "unwrap", &instantiations, |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    inline std::int32_t unwrap(::rust_out::Wrapper<std::int32_t> w);
+                }
+            );
+            let impl_details = get_impl_details_snippet(&result);
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    extern "C" fn ...(
+                        w: &mut ::core::mem::MaybeUninit<::rust_out::Wrapper<i32>>
+                    ) -> i32 { ... }
+                }
+            );
+        });
+        test_format_item(test_src, "unwrap", |result| {
+            let err = result.unwrap_err();
+            assert_eq!(
+                err,
+                "Error handling parameter #0: Generic types are not supported yet (b/259749095)"
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_unsupported_fn_async() {
+        let test_src = r#"
+                pub async fn async_function() {}
+            "#;
+        test_format_item(test_src, "async_function", |result| {
+            let err = result.unwrap_err();
+            assert_eq!(err, "Error formatting function return type: \
+                             The following Rust type is not supported yet: \
+                             impl std::future::Future<Output = ()>");
+        });
+    }
+
+    #[test]
+    fn test_format_item_fn_rust_abi() {
+        let test_src = r#"
+                pub fn add(x: f64, y: f64) -> f64 { x * y }
+            "#;
+        test_format_item(test_src, "add", |result| {
+            // TODO(b/261074843): Re-add thunk name verification once we are using stable name
+            // mangling (which may be coming in Q1 2023).  (This might mean reverting cl/492333432
+            // + manual review and tweaks.)
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            assert!(main_api.prereqs.is_empty());
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    inline double add(double x, double y);
+                }
+            );
+            assert!(impl_details.cc.prereqs.is_empty());
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" double ...(double x, double y);
+                    }
+                    ...
+                    inline double add(double x, double y) {
+                        return __crubit_internal::...(x, y);
+                    }
                 }
-                pub mod m2 {
-                    pub fn bar() {}
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    #[no_mangle]
+                    extern "C"
+                    fn ...(x: f64, y: f64) -> f64 {
+                        ::rust_out::add(x, y)
+                    }
                 }
-            "#;
-        run_compiler_for_testing(test_src, |tcx| find_def_id_by_name(tcx, "missing_name"));
+            );
+        });
     }
 
     #[test]
-    #[should_panic(expected = "More than one item named `some_name`")]
-    fn test_find_def_id_by_name_panic_when_multiple_items_with_matching_name() {
+    fn test_format_item_fn_rust_abi_with_param_taking_struct_by_value() {
         let test_src = r#"
-                pub mod m1 {
-                    pub fn some_name() {}
+                pub struct S(i32);
+                pub fn into_i32(s: S) -> i32 { s.0 }
+            "#;
+        test_format_item(test_src, "into_i32", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    inline std::int32_t into_i32(::rust_out::S s);
                 }
-                pub mod m2 {
-                    pub fn some_name() {}
+            );
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" std::int32_t ...(::rust_out::S* s);
+                    }
+                    ...
+                    inline std::int32_t into_i32(::rust_out::S s) {
+                        return __crubit_internal::...(&s);
+                    }
                 }
-            "#;
-        run_compiler_for_testing(test_src, |tcx| find_def_id_by_name(tcx, "some_name"));
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    #[no_mangle]
+                    extern "C"
+                    fn ...(s: &mut ::core::mem::MaybeUninit<::rust_out::S>) -> i32 {
+                        ::rust_out::into_i32(unsafe { s.assume_init_read() })
+                    }
+                }
+            );
+        });
     }
 
-    /// This test covers only a single example of a function that should get a
-    /// C++ binding. The test focuses on verification that the output from
-    /// `format_fn` gets propagated all the way to `GenerateBindings::new`.
-    /// Additional coverage of how functions are formatted is provided
-    /// by `test_format_item_..._fn_...` tests (which work at the `format_fn`
-    /// level).
     #[test]
-    fn test_generated_bindings_fn_no_mangle_extern_c() {
+    fn test_format_item_fn_rust_abi_returning_struct_by_value() {
         let test_src = r#"
-                #[no_mangle]
-                pub extern "C" fn public_function() {
-                    println!("foo");
-                }
+                pub struct S(i32);
+                pub fn create(i: i32) -> S { S(i) }
             "#;
-        test_generated_bindings(test_src, |bindings| {
-            let bindings = bindings.unwrap();
+        test_format_item(test_src, "create", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
             assert_cc_matches!(
-                bindings.h_body,
+                main_api.tokens,
                 quote! {
-                    extern "C" void public_function();
+                    inline ::rust_out::S create(std::int32_t i);
+                }
+            );
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" void ...(std::int32_t i, ::rust_out::S* __ret_ptr);
+                    }
+                    ...
+                    inline ::rust_out::S create(std::int32_t i) {
+                        crubit::ReturnValueSlot<::rust_out::S> __ret_slot;
+                        __crubit_internal::...(i, __ret_slot.Get());
+                        return std::move(__ret_slot).AssumeInitAndTakeValue();
+                    }
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    #[no_mangle]
+                    extern "C"
+                    fn ...(
+                        i: i32,
+                        __ret_slot: &mut ::core::mem::MaybeUninit<::rust_out::S>
+                    ) -> () {
+                        __ret_slot.write(::rust_out::create(i));
+                    }
                 }
             );
-
-            // No Rust thunks should be generated in this test scenario.
-            assert_rs_not_matches!(bindings.rs_body, quote!{ public_function });
         });
     }
 
-    /// `test_generated_bindings_fn_export_name` covers a scenario where
-    /// `MixedSnippet::cc` is present but `MixedSnippet::rs` is empty
-    /// (because no Rust thunks are needed).
     #[test]
-    fn test_generated_bindings_fn_export_name() {
+    fn test_format_item_fn_rust_abi_with_param_taking_tuple_by_value() {
         let test_src = r#"
-                #[export_name = "export_name"]
-                pub extern "C" fn public_function(x: f64, y: f64) -> f64 { x + y }
+                pub fn sum(pair: (i32, i32)) -> i32 { pair.0 + pair.1 }
             "#;
-        test_generated_bindings(test_src, |bindings| {
-            let bindings = bindings.unwrap();
+        test_format_item(test_src, "sum", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
             assert_cc_matches!(
-                bindings.h_body,
+                main_api.tokens,
                 quote! {
-                    namespace rust_out {
-                        ...
-                        inline double public_function(double x, double y);
-                        namespace __crubit_internal {
-                            extern "C" double export_name(double x, double y);
-                        }
-                        inline double public_function(double x, double y) {
-                            return __crubit_internal::export_name(x, y);
-                        }
+                    inline std::int32_t sum(std::tuple<std::int32_t, std::int32_t> pair);
+                }
+            );
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" std::int32_t ...(std::int32_t* pair_0, std::int32_t* pair_1);
+                    }
+                    ...
+                    inline std::int32_t sum(std::tuple<std::int32_t, std::int32_t> pair) {
+                        return __crubit_internal::...(&std::get<0>(pair), &std::get<1>(pair));
+                    }
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    #[no_mangle]
+                    extern "C"
+                    fn ...(
+                        pair_0: &mut ::core::mem::MaybeUninit<i32>,
+                        pair_1: &mut ::core::mem::MaybeUninit<i32>
+                    ) -> i32 {
+                        ::rust_out::sum((
+                            unsafe { pair_0.assume_init_read() },
+                            unsafe { pair_1.assume_init_read() },
+                        ))
                     }
                 }
             );
         });
     }
 
-    /// The `test_generated_bindings_struct` test covers only a single example
-    /// of an ADT (struct/enum/union) that should get a C++ binding.
-    /// Additional coverage of how items are formatted is provided by
-    /// `test_format_item_..._struct_...`, `test_format_item_..._enum_...`,
-    /// and `test_format_item_..._union_...` tests.
-    ///
-    /// We don't want to duplicate coverage already provided by
-    /// `test_format_item_struct_with_fields`, but we do want to verify that
-    /// * `format_crate` will actually find and process the struct
-    ///   (`test_format_item_...` doesn't cover this aspect - it uses a
-    ///   test-only `find_def_id_by_name` instead)
-    /// * The actual shape of the bindings still looks okay at this level.
     #[test]
-    fn test_generated_bindings_struct() {
+    fn test_format_item_fn_rust_abi_returning_tuple_by_value() {
         let test_src = r#"
-                pub struct Point {
-                    pub x: i32,
-                    pub y: i32,
-                }
+                pub struct S(i32);
+                pub fn create(i: i32) -> (S, f64) { (S(i), 0.5) }
             "#;
-        test_generated_bindings(test_src, |bindings| {
-            let bindings = bindings.unwrap();
+        test_format_item(test_src, "create", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
             assert_cc_matches!(
-                bindings.h_body,
+                main_api.tokens,
                 quote! {
-                    namespace rust_out {
-                        ...
-                        struct alignas(4) Point final {
-                            // No point replicating test coverage of
-                            // `test_format_item_struct_with_fields`.
-                            ...
-                        };
-                        static_assert(sizeof(Point) == 8, ...);
-                        static_assert(alignof(Point) == 4, ...);
-                        ... // Other static_asserts are covered by
-                            // `test_format_item_struct_with_fields`
-                    }  // namespace rust_out
+                    inline std::tuple<::rust_out::S, double> create(std::int32_t i);
+                }
+            );
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" void ...(
+                            std::int32_t i,
+                            ::rust_out::S* __ret_ptr_0,
+                            double* __ret_ptr_1);
+                    }
+                    ...
+                    inline std::tuple<::rust_out::S, double> create(std::int32_t i) {
+                        crubit::ReturnValueSlot<::rust_out::S> __ret_slot_0;
+                        crubit::ReturnValueSlot<double> __ret_slot_1;
+                        __crubit_internal::...(i, __ret_slot_0.Get(), __ret_slot_1.Get());
+                        return std::tuple<::rust_out::S, double>(
+                            std::move(__ret_slot_0).AssumeInitAndTakeValue(),
+                            std::move(__ret_slot_1).AssumeInitAndTakeValue());
+                    }
                 }
             );
             assert_rs_matches!(
-                bindings.rs_body,
+                impl_details.rs,
                 quote! {
-                    // No point replicating test coverage of
-                    // `test_format_item_struct_with_fields`.
-                    const _: () = assert!(::std::mem::size_of::<::rust_out::Point>() == 8);
-                    const _: () = assert!(::std::mem::align_of::<::rust_out::Point>() == 4);
-                    const _: () = assert!( memoffset::offset_of!(::rust_out::Point, x) == 0);
-                    const _: () = assert!( memoffset::offset_of!(::rust_out::Point, y) == 4);
+                    #[no_mangle]
+                    extern "C"
+                    fn ...(
+                        i: i32,
+                        __ret_slot_0: &mut ::core::mem::MaybeUninit<::rust_out::S>,
+                        __ret_slot_1: &mut ::core::mem::MaybeUninit<f64>
+                    ) -> () {
+                        let (__ret_value_0, __ret_value_1,) = ::rust_out::create(i);
+                        __ret_slot_0.write(__ret_value_0);
+                        __ret_slot_1.write(__ret_value_1);
+                    }
                 }
             );
         });
     }
 
-    /// The `test_generated_bindings_impl` test covers only a single example of
-    /// a non-trait `impl`. Additional coverage of how items are formatted
-    /// should be provided in the future by `test_format_item_...` tests.
-    ///
-    /// We don't want to duplicate coverage already provided by
-    /// `test_format_item_static_method`, but we do want to verify that
-    /// * `format_crate` won't process the `impl` as a standalone HIR item
-    /// * The actual shape of the bindings still looks okay at this level.
     #[test]
-    fn test_generated_bindings_impl() {
+    fn test_format_item_unsupported_fn_extern_c_with_tuple_param() {
         let test_src = r#"
-                pub struct SomeStruct(i32);
+                #![allow(improper_ctypes_definitions)]
 
-                impl SomeStruct {
-                    pub fn public_static_method() -> i32 { 123 }
+                #[no_mangle]
+                pub extern "C" fn foo(_pair: (i32, i32)) {}
+            "#;
+        test_format_item(test_src, "foo", |result| {
+            let err = result.unwrap_err();
+            assert_eq!(
+                err,
+                "Tuples are not supported in signatures of `extern \"C\"` functions \
+                 (b/254099023)"
+            );
+        });
+    }
 
-                    #[allow(dead_code)]
-                    fn private_static_method() -> i32 { 123 }
-                }
+    /// `test_format_item_fn_rust_abi` tests a function call that is not a
+    /// C-ABI, and is not the default Rust ABI.  It can't use `"stdcall"`,
+    /// because it is not supported on the targets where Crubit's tests run.
+    /// So, it ended up using `"vectorcall"`.
+    ///
+    /// This test almost entirely replicates `test_format_item_fn_rust_abi`,
+    /// except for the `extern "vectorcall"` part in the `test_src` test
+    /// input.
+    ///
+    /// This test verifies the current behavior that gives reasonable and
+    /// functional FFI bindings.  OTOH, in the future we may decide to avoid
+    /// having the extra thunk for cases where the given non-C-ABI function
+    /// call convention is supported by both C++ and Rust
+    /// (see also `format_cc_call_conv_as_clang_attribute` in
+    /// `rs_bindings_from_cc/src_code_gen.rs`)
+    #[test]
+    fn test_format_item_fn_vectorcall_abi() {
+        let test_src = r#"
+                #![feature(abi_vectorcall)]
+                pub extern "vectorcall" fn add(x: f64, y: f64) -> f64 { x * y }
             "#;
-        test_generated_bindings(test_src, |bindings| {
-            let bindings = bindings.unwrap();
+        test_format_item(test_src, "add", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            assert!(main_api.prereqs.is_empty());
             assert_cc_matches!(
-                bindings.h_body,
+                main_api.tokens,
                 quote! {
-                    namespace rust_out {
-                        ...
-                        struct ... SomeStruct ... {
-                            // No point replicating test coverage of
-                            // `test_format_item_static_method`.
-                            ...
-                            std::int32_t public_static_method();
-                            ...
-                        };
-                        ...
-                        std::int32_t SomeStruct::public_static_method() {
-                            ...
-                        }
-                    }  // namespace rust_out
+                    inline double add(double x, double y);
+                }
+            );
+            assert!(impl_details.cc.prereqs.is_empty());
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" double ...(double x, double y);
+                    }
+                    ...
+                    inline double add(double x, double y) {
+                        return __crubit_internal::...(x, y);
+                    }
                 }
             );
             assert_rs_matches!(
-                bindings.rs_body,
+                impl_details.rs,
                 quote! {
-                    extern "C" fn ...() -> i32 {
-                        ::rust_out::SomeStruct::public_static_method()
+                    #[no_mangle]
+                    extern "C"
+                    fn ...(x: f64, y: f64) -> f64 {
+                        ::rust_out::add(x, y)
                     }
                 }
             );
@@ -1802,596 +10882,626 @@ pub mod tests {
     }
 
     #[test]
-    fn test_generated_bindings_includes() {
+    fn test_format_item_unsupported_fn_variadic() {
         let test_src = r#"
+                #![feature(c_variadic)]
+
                 #[no_mangle]
-                pub extern "C" fn public_function(i: i32, d: isize, u: u64) {
-                    dbg!(i);
-                    dbg!(d);
-                    dbg!(u);
-                }
+                pub unsafe extern "C" fn variadic_function(_fmt: *const u8, ...) {}
             "#;
-        test_generated_bindings(test_src, |bindings| {
-            let bindings = bindings.unwrap();
-            assert_cc_matches!(
-                bindings.h_body,
-                quote! {
-                    __HASH_TOKEN__ include <cstdint> ...
-                    namespace ... {
-                        ...
-                        extern "C" void public_function(
-                            std::int32_t i,
-                            std::intptr_t d,
-                            std::uint64_t u);
-                    }
-                }
-            );
+        test_format_item(test_src, "variadic_function", |result| {
+            // TODO(b/254097223): Add support for variadic functions.
+            let err = result.unwrap_err();
+            assert_eq!(err, "C variadic functions are not supported (b/254097223)");
         });
     }
 
-    /// Tests that `toposort` is used to reorder item bindings.
     #[test]
-    fn test_generated_bindings_prereq_defs_field_deps_require_reordering() {
+    fn test_format_item_fn_params() {
         let test_src = r#"
-                // In the generated bindings `Outer` needs to come *after* `Inner`.
-                pub struct Outer(Inner);
-                pub struct Inner(bool);
+                #[allow(unused_variables)]
+                #[no_mangle]
+                pub extern "C" fn foo(b: bool, f: f64) {}
             "#;
-        test_generated_bindings(test_src, |bindings| {
-            let bindings = bindings.unwrap();
+        test_format_item(test_src, "foo", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            assert!(main_api.prereqs.is_empty());
             assert_cc_matches!(
-                bindings.h_body,
+                main_api.tokens,
                 quote! {
-                    namespace rust_out {
-                    ...
-                        struct alignas(1) Inner final {
-                          ...  bool __field0; ...
-                        };
                     ...
-                        struct alignas(1) Outer final {
-                          ...  ::rust_out::Inner __field0; ...
-                        };
-                    ...
-                    }  // namespace rust_out
+                    extern "C" void foo(bool b, double f);
                 }
             );
         });
     }
 
-    /// Tests that a forward declaration is present when it is required to
-    /// preserve the original source order.  In this test the
-    /// `CcPrerequisites::fwd_decls` dependency comes from a pointer parameter.
     #[test]
-    fn test_generated_bindings_prereq_fwd_decls_for_ptr_param() {
+    fn test_format_item_fn_param_name_reserved_keyword() {
         let test_src = r#"
-                // To preserve original API order we need to forward declare S.
-                pub fn f(_: *const S) {}
-                pub struct S(bool);
+                #[allow(unused_variables)]
+                #[no_mangle]
+                pub extern "C" fn some_function(reinterpret_cast: f64) {}
             "#;
-        test_generated_bindings(test_src, |bindings| {
-            let bindings = bindings.unwrap();
+        test_format_item(test_src, "some_function", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            assert!(main_api.prereqs.is_empty());
             assert_cc_matches!(
-                bindings.h_body,
+                main_api.tokens,
                 quote! {
-                    namespace rust_out {
-                        ...
-                        // Verifing the presence of this forward declaration
-                        // it the essence of this test.  The order of the items
-                        // below also matters.
-                        struct S;
-                        ...
-                        inline void f(const ::rust_out::S* __param_0);
-                        ...
-                        struct alignas(...) S final { ... }
-                        ...
-                        inline void f(const ::rust_out::S* __param_0) { ... }
-                        ...
-                    }  // namespace rust_out
+                    ...
+                    extern "C" void some_function(double __param_0);
                 }
             );
         });
     }
 
-    /// Tests that a forward declaration is present when it is required to
-    /// preserve the original source order.  In this test the
-    /// `CcPrerequisites::fwd_decls` dependency comes from a parameter
-    /// that takes a struct by value, but is only needed in a C++ function
-    /// declaration (not in a C++ function definition).
     #[test]
-    fn test_generated_bindings_prereq_fwd_decls_for_cpp_fn_decl() {
+    fn test_format_item_fn_with_multiple_anonymous_parameter_names() {
         let test_src = r#"
-                // `f` will only have a C++ declaration (and no C++ definition)
-                // in the generated `..._cc_api.h` header.
-                //
-                // Therefore `f`'s `CcPrerequisites` should include `S`
-                // as a `fwd_decls` edge, rather than as a `defs` edge.
-                #[no_mangle]
-                pub extern "C" fn f(s: S) -> bool { s.0 }
-
-                #[repr(C)]
-                pub struct S(bool);
+                pub fn foo(_: f64, _: f64) {}
             "#;
-
-        test_generated_bindings(test_src, |bindings| {
-            let bindings = bindings.unwrap();
+        test_format_item(test_src, "foo", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            assert!(main_api.prereqs.is_empty());
             assert_cc_matches!(
-                bindings.h_body,
+                main_api.tokens,
                 quote! {
-                    namespace rust_out {
-                        ...
-                        // Verifing the presence of this forward declaration
-                        // it the essence of this test.  The order also matters:
-                        // 1. The fwd decl of `S` should come first,
-                        // 2. Declaration of `f` and definition of `S` should come next
-                        //    (in their original order - `f` first and then `S`).
-                        struct S;
-                        ...
-                        extern "C" bool f(::rust_out::S s);
-                        ...
-                        struct alignas(...) S final { ... }
-                        ...
-                    }  // namespace rust_out
+                    inline void foo(double __param_0, double __param_1);
+                }
+            );
+            assert!(impl_details.cc.prereqs.is_empty());
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" void ...(
+                            double __param_0, double __param_1);
+                    }
+                    ...
+                    inline void foo(double __param_0, double __param_1) {
+                        return __crubit_internal::...(__param_0, __param_1);
+                    }
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    #[no_mangle]
+                    extern "C" fn ...(__param_0: f64, __param_1: f64) -> () {
+                        ::rust_out::foo(__param_0, __param_1)
+                    }
                 }
             );
         });
     }
 
-    /// This test verifies that a forward declaration for a given ADT is only
-    /// emitted once (and not once for every API item that requires the
-    /// forward declaration as a prerequisite).
-    #[test]
-    fn test_generated_bindings_prereq_fwd_decls_no_duplication() {
-        let test_src = r#"
-                // All three functions below require a forward declaration of S.
-                pub fn f1(_: *const S) {}
-                pub fn f2(_: *const S) {}
-                pub fn f3(_: *const S) {}
-
-                pub struct S(bool);
-
-                // This function also includes S in its CcPrerequisites::fwd_decls
-                // (although here it is not required, because the definition of S
-                // is already available above).
-                pub fn f4(_: *const S) {}
-            "#;
-        test_generated_bindings(test_src, |bindings| {
-            let bindings = bindings.unwrap().h_body.to_string();
-
-            // Only a single forward declaration is expected.
-            assert_eq!(1, bindings.matches("struct S ;").count(), "bindings = {bindings}");
-        });
-    }
-
-    /// This test verifies that forward declarations are emitted in a
-    /// deterministic order. The particular order doesn't matter _that_
-    /// much, but it definitely shouldn't change every time
-    /// `cc_bindings_from_rs` is invoked again.  The current order preserves
-    /// the original source order of the Rust API items.
     #[test]
-    fn test_generated_bindings_prereq_fwd_decls_deterministic_order() {
+    fn test_format_item_fn_with_destructuring_parameter_name() {
         let test_src = r#"
-                // To try to mix things up, the bindings for the functions below
-                // will *ask* for forward declarations in a different order:
-                // * Different from the order in which the forward declarations
-                //   are expected to be *emitted* (the original source order).
-                // * Different from alphabetical order.
-                pub fn f1(_: *const b::S3) {}
-                pub fn f2(_: *const a::S2) {}
-                pub fn f3(_: *const a::S1) {}
-
-                pub mod a {
-                    pub struct S1(bool);
-                    pub struct S2(bool);
+                pub struct S {
+                    pub f1: i32,
+                    pub f2: i32,
                 }
 
-                pub mod b {
-                    pub struct S3(bool);
-                }
+                // This test mostly focuses on the weird parameter "name" below.
+                // See also
+                // https://doc.rust-lang.org/reference/items/functions.html#function-parameters
+                // which points out that function parameters are just irrefutable patterns.
+                pub fn func(S{f1, f2}: S) -> i32 { f1 + f2 }
             "#;
-        test_generated_bindings(test_src, |bindings| {
-            let bindings = bindings.unwrap();
+        test_format_item(test_src, "func", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
             assert_cc_matches!(
-                bindings.h_body,
+                main_api.tokens,
                 quote! {
-                    namespace rust_out {
-                        ...
-                        // Verifying that we get the same order in each test
-                        // run is the essence of this test.
-                        namespace a {
-                        struct S1;
-                        struct S2;
-                        }
-                        namespace b {
-                        struct S3;
-                        }
-                        ...
-                        inline void f1 ...
-                        inline void f2 ...
-                        inline void f3 ...
-
-                        namespace a { ...
-                        struct alignas(...) S1 final { ... } ...
-                        struct alignas(...) S2 final { ... } ...
-                        } ...
-                        namespace b { ...
-                        struct alignas(...) S3 final { ... } ...
-                        } ...
-                    }  // namespace rust_out
+                    inline std::int32_t func(::rust_out::S __param_0);
+                }
+            );
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" std::int32_t ...(::rust_out::S* __param_0);
+                    }
+                    ...
+                    inline std::int32_t func(::rust_out::S __param_0) {
+                        return __crubit_internal::...(&__param_0);
+                    }
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    #[no_mangle]
+                    extern "C" fn ...(
+                        __param_0: &mut ::core::mem::MaybeUninit<::rust_out::S>
+                    ) -> i32 {
+                        ::rust_out::func(unsafe {__param_0.assume_init_read() })
+                    }
                 }
             );
         });
     }
 
-    /// This test verifies that forward declarations are not emitted if they are
-    /// not needed (e.g. if bindings the given `struct` or other ADT have
-    /// already been defined earlier).  In particular, we don't want to emit
-    /// forward declarations for *all* `structs` (regardless if they are
-    /// needed or not).
     #[test]
-    fn test_generated_bindings_prereq_fwd_decls_not_needed_because_of_initial_order() {
+    fn test_format_item_unsupported_fn_param_type() {
         let test_src = r#"
-                pub struct S(bool);
-
-                // S is already defined above - no need for forward declaration in C++.
-                pub fn f(_s: *const S) {}
+                pub fn foo(_param: (i32, i32)) {}
             "#;
-        test_generated_bindings(test_src, |bindings| {
-            let bindings = bindings.unwrap();
-            assert_cc_not_matches!(bindings.h_body, quote! { struct S; });
-            assert_cc_matches!(bindings.h_body, quote! { void f(const ::rust_out::S* _s); });
+        test_format_item(test_src, "foo", |result| {
+            let err = result.unwrap_err();
+            assert_eq!(err, "Error handling parameter #0: \
+                             Tuples are not supported yet: (i32, i32) (b/254099023)");
         });
     }
 
-    /// This test verifies that a method declaration doesn't ask for a forward
-    /// declaration to the struct.
     #[test]
-    fn test_generated_bindings_prereq_fwd_decls_not_needed_inside_struct_definition() {
+    fn test_format_item_unsupported_fn_param_type_unit() {
         let test_src = r#"
-                #![allow(dead_code)]
+                #[no_mangle]
+                pub fn fn_with_params(_param: ()) {}
+            "#;
+        test_format_item(test_src, "fn_with_params", |result| {
+            let err = result.unwrap_err();
+            assert_eq!(err, "Error handling parameter #0: \
+                             `()` / `void` is only supported as a return type (b/254507801)");
+        });
+    }
 
-                pub struct S {
-                    // This shouldn't require a fwd decl of S.
-                    field: *const S,
-                }
+    #[test]
+    fn test_format_item_unsupported_fn_param_type_never() {
+        let test_src = r#"
+                #![feature(never_type)]
 
-                impl S {
-                    // This shouldn't require a fwd decl of S.
-                    pub fn create() -> S { Self{ field: std::ptr::null() } }
-                }
+                #[no_mangle]
+                pub extern "C" fn fn_with_params(_param: !) {}
             "#;
-        test_generated_bindings(test_src, |bindings| {
-            let bindings = bindings.unwrap();
-            assert_cc_not_matches!(bindings.h_body, quote! { struct S; });
-            assert_cc_matches!(
-                bindings.h_body,
-                quote! {
-                    static inline ::rust_out::S create(); ...
-                    const ::rust_out::S* field; ...
-                }
+        test_format_item(test_src, "fn_with_params", |result| {
+            let err = result.unwrap_err();
+            assert_eq!(
+                err,
+                "Error handling parameter #0: \
+                 The never type `!` is only supported as a return type (b/254507801)"
             );
         });
     }
 
+    /// This is a test for a regular struct - a struct with named fields.
+    /// https://doc.rust-lang.org/reference/items/structs.html refers to this kind of struct as
+    /// `StructStruct` or "nominal struct type".
     #[test]
-    fn test_generated_bindings_module_basics() {
+    fn test_format_item_struct_with_fields() {
         let test_src = r#"
-                pub mod some_module {
-                    pub fn some_func() {}
+                pub struct SomeStruct {
+                    pub x: i32,
+                    pub y: i32,
                 }
+
+                const _: () = assert!(std::mem::size_of::<SomeStruct>() == 8);
+                const _: () = assert!(std::mem::align_of::<SomeStruct>() == 4);
             "#;
-        test_generated_bindings(test_src, |bindings| {
-            let bindings = bindings.unwrap();
+        test_format_item(test_src, "SomeStruct", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            assert!(!main_api.prereqs.is_empty());
             assert_cc_matches!(
-                bindings.h_body,
+                main_api.tokens,
                 quote! {
-                    namespace rust_out {
-                        namespace some_module {
-                            ...
-                            inline void some_func() { ... }
-                            ...
-                        }  // namespace some_module
-                    }  // namespace rust_out
+                    ...
+                    struct alignas(4) SomeStruct final {
+                        public:
+                            // In this test there is no `Default` implementation.
+                            SomeStruct() = delete;
+
+                            // In this test there is no `Copy` implementation / derive.
+                            SomeStruct(const SomeStruct&) = delete;
+
+                            // All Rust types are trivially-movable.
+                            SomeStruct(SomeStruct&&) = default;
+
+                            // Assignment operators are disabled for now.
+                            SomeStruct& operator=(const SomeStruct&) = delete;
+                            SomeStruct& operator=(SomeStruct&&) = delete;
+
+                            // In this test there is no custom `Drop`, so C++ can also
+                            // just use the `default` destructor.
+                            ~SomeStruct() = default;
+
+                        private:
+                            ...  std::int32_t x;
+                            ...  std::int32_t y;
+                            // Structs with fields of scalar types can be constructed in C++
+                            // constant expressions (see `is_constexpr_struct`), but only by
+                            // the bindings of Rust constants.
+                            constexpr explicit SomeStruct(std::int32_t x, std::int32_t y)
+                                : x(x), y(y) {}
+                            friend struct ::crubit::ConstFactory;
+                            inline static void __crubit_field_offset_assertions();
+                    };
                 }
             );
-            assert_rs_matches!(
-                bindings.rs_body,
+            assert_cc_matches!(
+                impl_details.cc.tokens,
                 quote! {
-                    #[no_mangle]
-                    extern "C"
-                    fn ...() -> () {
-                        ::rust_out::some_module::some_func()
+                    static_assert(sizeof(SomeStruct) == 8, ...);
+                    static_assert(alignof(SomeStruct) == 4, ...);
+                    inline void SomeStruct::__crubit_field_offset_assertions() {
+                      static_assert(0 == offsetof(SomeStruct, x));
+                      static_assert(4 == offsetof(SomeStruct, y));
                     }
                 }
             );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    const _: () = assert!(::std::mem::size_of::<::rust_out::SomeStruct>() == 8);
+                    const _: () = assert!(::std::mem::align_of::<::rust_out::SomeStruct>() == 4);
+                    const _: () = assert!( memoffset::offset_of!(::rust_out::SomeStruct, x) == 0);
+                    const _: () = assert!( memoffset::offset_of!(::rust_out::SomeStruct, y) == 4);
+                }
+            );
         });
     }
 
+    /// This is a test for `TupleStruct` or "tuple struct" - for more details
+    /// please refer to https://doc.rust-lang.org/reference/items/structs.html
     #[test]
-    fn test_generated_bindings_module_name_is_cpp_reserved_keyword() {
+    fn test_format_item_struct_with_tuple() {
         let test_src = r#"
-                pub mod working_module {
-                    pub fn working_module_f1() {}
-                    pub fn working_module_f2() {}
-                }
-                pub mod reinterpret_cast {
-                    pub fn broken_module_f1() {}
-                    pub fn broken_module_f2() {}
-                }
+                pub struct TupleStruct(pub i32, pub i32);
+                const _: () = assert!(std::mem::size_of::<TupleStruct>() == 8);
+                const _: () = assert!(std::mem::align_of::<TupleStruct>() == 4);
             "#;
-        test_generated_bindings(test_src, |bindings| {
-            let bindings = bindings.unwrap();
+        test_format_item(test_src, "TupleStruct", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            assert!(!main_api.prereqs.is_empty());
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    ...
+                    struct alignas(4) TupleStruct final {
+                        public:
+                            // In this test there is no `Default` implementation.
+                            TupleStruct() = delete;
 
-            // Items in the broken module should be replaced with a comment explaining the
-            // problem.
-            let broken_module_msg = "Failed to format namespace name `reinterpret_cast`: \
-                                     `reinterpret_cast` is a C++ reserved keyword \
-                                     and can't be used as a C++ identifier";
-            assert_cc_not_matches!(bindings.h_body, quote! { namespace reinterpret_cast });
-            assert_cc_not_matches!(bindings.h_body, quote! { broken_module_f1 });
-            assert_cc_not_matches!(bindings.h_body, quote! { broken_module_f2 });
+                            // In this test there is no `Copy` implementation / derive.
+                            TupleStruct(const TupleStruct&) = delete;
 
-            // Items in the other module should still go through.
+                            // All Rust types are trivially-movable.
+                            TupleStruct(TupleStruct&&) = default;
+
+                            // Assignment operators are disabled for now.
+                            TupleStruct& operator=(const TupleStruct&) = delete;
+                            TupleStruct& operator=(TupleStruct&&) = delete;
+
+                            // In this test there is no custom `Drop`, so C++ can also
+                            // just use the `default` destructor.
+                            ~TupleStruct() = default;
+                        private:
+                            ...  std::int32_t __field0;
+                            ...  std::int32_t __field1;
+                            constexpr explicit TupleStruct(std::int32_t __field0,
+                                                           std::int32_t __field1)
+                                : __field0(__field0), __field1(__field1) {}
+                            friend struct ::crubit::ConstFactory;
+                            inline static void __crubit_field_offset_assertions();
+                    };
+                }
+            );
             assert_cc_matches!(
-                bindings.h_body,
+                impl_details.cc.tokens,
                 quote! {
-                    namespace rust_out {
-                        namespace working_module {
-                            ...
-                            inline void working_module_f1();
-                            ...
-                            inline void working_module_f2();
-                            ...
-                        }  // namespace some_module
-
-                        __COMMENT__ #broken_module_msg
-                        ...
-                    }  // namespace rust_out
+                    static_assert(sizeof(TupleStruct) == 8, ...);
+                    static_assert(alignof(TupleStruct) == 4, ...);
+                    inline void TupleStruct::__crubit_field_offset_assertions() {
+                      static_assert(0 == offsetof(TupleStruct, __field0));
+                      static_assert(4 == offsetof(TupleStruct, __field1));
+                    }
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    const _: () = assert!(::std::mem::size_of::<::rust_out::TupleStruct>() == 8);
+                    const _: () = assert!(::std::mem::align_of::<::rust_out::TupleStruct>() == 4);
+                    const _: () = assert!( memoffset::offset_of!(::rust_out::TupleStruct, 0) == 0);
+                    const _: () = assert!( memoffset::offset_of!(::rust_out::TupleStruct, 1) == 4);
                 }
             );
         });
     }
 
-    /// `test_generated_bindings_non_pub_items` verifies that non-public items
-    /// are not present/propagated into the generated bindings.
+    /// This test the scenario where Rust lays out field in a different order
+    /// than the source order.
     #[test]
-    fn test_generated_bindings_non_pub_items() {
+    fn test_format_item_struct_with_reordered_field_offsets() {
         let test_src = r#"
-                #![allow(dead_code)]
-
-                extern "C" fn private_function() {
-                    println!("foo");
-                }
-
-                struct PrivateStruct {
-                    x: i32,
-                    y: i32,
-                }
-
-                pub struct PublicStruct(i32);
-
-                impl PublicStruct {
-                    fn private_method() {}
-                }
-
-                pub mod public_module {
-                    fn priv_func_in_pub_module() {}
+                pub struct SomeStruct {
+                    pub field1: i16,
+                    pub field2: i32,
+                    pub field3: i16,
                 }
 
-                mod private_module {
-                    pub fn pub_func_in_priv_module() { priv_func_in_priv_module() }
-                    fn priv_func_in_priv_module() {}
-                }
+                const _: () = assert!(std::mem::size_of::<SomeStruct>() == 8);
+                const _: () = assert!(std::mem::align_of::<SomeStruct>() == 4);
             "#;
-        test_generated_bindings(test_src, |bindings| {
-            let bindings = bindings.unwrap();
-            assert_cc_not_matches!(bindings.h_body, quote! { private_function });
-            assert_rs_not_matches!(bindings.rs_body, quote! { private_function });
-            assert_cc_not_matches!(bindings.h_body, quote! { PrivateStruct });
-            assert_rs_not_matches!(bindings.rs_body, quote! { PrivateStruct });
-            assert_cc_not_matches!(bindings.h_body, quote! { private_method });
-            assert_rs_not_matches!(bindings.rs_body, quote! { private_method });
-            assert_cc_not_matches!(bindings.h_body, quote! { priv_func_in_priv_module });
-            assert_rs_not_matches!(bindings.rs_body, quote! { priv_func_in_priv_module });
-            assert_cc_not_matches!(bindings.h_body, quote! { priv_func_in_pub_module });
-            assert_rs_not_matches!(bindings.rs_body, quote! { priv_func_in_pub_module });
-            assert_cc_not_matches!(bindings.h_body, quote! { private_module });
-            assert_rs_not_matches!(bindings.rs_body, quote! { private_module });
-            assert_cc_not_matches!(bindings.h_body, quote! { pub_func_in_priv_module });
-            assert_rs_not_matches!(bindings.rs_body, quote! { pub_func_in_priv_module });
-        });
-    }
-
-    #[test]
-    fn test_generated_bindings_top_level_items() {
-        let test_src = "pub fn public_function() {}";
-        test_generated_bindings(test_src, |bindings| {
-            let bindings = bindings.unwrap();
-            let expected_comment_txt =
-                "Automatically @generated C++ bindings for the following Rust crate:\n\
-                 rust_out";
+        test_format_item(test_src, "SomeStruct", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            assert!(!main_api.prereqs.is_empty());
             assert_cc_matches!(
-                bindings.h_body,
+                main_api.tokens,
                 quote! {
-                    __COMMENT__ #expected_comment_txt
-                    ...
-                    __HASH_TOKEN__ pragma once
                     ...
-                    namespace rust_out {
+                    struct alignas(4) SomeStruct final {
                         ...
-                    }
+                        private:
+                            // The particular order below is not guaranteed,
+                            // so we may need to adjust this test assertion
+                            // (if Rust changes how it lays out the fields).
+                            ...  std::int32_t field2;
+                            ...  std::int16_t field1;
+                            ...  std::int16_t field3;
+                            // The parameters of the `constexpr` constructor follow the source
+                            // order of the fields, but member initializers follow the
+                            // declaration order of the fields.
+                            constexpr explicit SomeStruct(std::int16_t field1,
+                                                          std::int32_t field2,
+                                                          std::int16_t field3)
+                                : field2(field2), field1(field1), field3(field3) {}
+                            friend struct ::crubit::ConstFactory;
+                            inline static void __crubit_field_offset_assertions();
+                    };
                 }
             );
             assert_cc_matches!(
-                bindings.rs_body,
+                impl_details.cc.tokens,
                 quote! {
-                    __COMMENT__ #expected_comment_txt
+                    static_assert(sizeof(SomeStruct) == 8, ...);
+                    static_assert(alignof(SomeStruct) == 4, ...);
+                    inline void SomeStruct::__crubit_field_offset_assertions() {
+                      static_assert(0 == offsetof(SomeStruct, field2));
+                      static_assert(4 == offsetof(SomeStruct, field1));
+                      static_assert(6 == offsetof(SomeStruct, field3));
+                    }
                 }
             );
-        })
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    const _: () = assert!(::std::mem::size_of::<::rust_out::SomeStruct>() == 8);
+                    const _: () = assert!(::std::mem::align_of::<::rust_out::SomeStruct>() == 4);
+                    const _: () = assert!( memoffset::offset_of!(::rust_out::SomeStruct, field2)
+                                           == 0);
+                    const _: () = assert!( memoffset::offset_of!(::rust_out::SomeStruct, field1)
+                                           == 4);
+                    const _: () = assert!( memoffset::offset_of!(::rust_out::SomeStruct, field3)
+                                           == 6);
+                }
+            );
+        });
     }
 
-    /// The `test_generated_bindings_unsupported_item` test verifies how `Err`
-    /// from `format_item` is formatted as a C++ comment (in `format_crate`
-    /// and `format_unsupported_def`):
-    /// - This test covers only a single example of an unsupported item.
-    ///   Additional coverage is provided by `test_format_item_unsupported_...`
-    ///   tests.
-    /// - This test somewhat arbitrarily chooses an example of an unsupported
-    ///   item, trying to pick one that 1) will never be supported (b/254104998
-    ///   has some extra notes about APIs named after reserved C++ keywords) and
-    ///   2) tests that the full error chain is included in the message.
     #[test]
-    fn test_generated_bindings_unsupported_item() {
+    fn test_format_item_struct_with_packed_layout() {
         let test_src = r#"
-                #[no_mangle]
-                pub extern "C" fn reinterpret_cast() {}
+                #[repr(packed(1))]
+                pub struct SomeStruct {
+                    pub field1: u16,
+                    pub field2: u32,
+                }
+                const _: () = assert!(::std::mem::size_of::<SomeStruct>() == 6);
+                const _: () = assert!(::std::mem::align_of::<SomeStruct>() == 1);
             "#;
-        test_generated_bindings(test_src, |bindings| {
-            let bindings = bindings.unwrap();
-            let expected_comment_txt = "Error generating bindings for `reinterpret_cast` \
-                 defined at <crubit_unittests.rs>;l=3: \
-                 Error formatting function name: \
-                 `reinterpret_cast` is a C++ reserved keyword \
-                 and can't be used as a C++ identifier";
+        test_format_item(test_src, "SomeStruct", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            assert!(!main_api.prereqs.is_empty());
             assert_cc_matches!(
-                bindings.h_body,
+                main_api.tokens,
                 quote! {
-                    __COMMENT__ #expected_comment_txt
+                    ...
+                    struct alignas(1) __attribute__((packed)) SomeStruct final {
+                        ...
+                        std::uint16_t field1;
+                        std::uint32_t field2;
+                        constexpr explicit SomeStruct(std::uint16_t field1, std::uint32_t field2)
+                            : field1(field1), field2(field2) {}
+                        inline static void __crubit_field_offset_assertions();
+                    };
                 }
             );
-        })
-    }
-
-    #[test]
-    fn test_generated_bindings_reimports() {
-        let test_src = r#"
-                #![allow(dead_code)]
-                #![allow(unused_imports)]
-                mod private_submodule1 {
-                    pub fn subfunction1() {}
-                    pub fn subfunction2() {}
-                    pub fn subfunction3() {}
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    static_assert(sizeof(SomeStruct) == 6, ...);
+                    static_assert(alignof(SomeStruct) == 1, ...);
+                    inline void SomeStruct::__crubit_field_offset_assertions() {
+                      static_assert(0 == offsetof(SomeStruct, field1));
+                      static_assert(2 == offsetof(SomeStruct, field2));
+                    }
                 }
-                mod private_submodule2 {
-                    pub fn subfunction8() {}
-                    pub fn subfunction9() {}
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    const _: () = assert!(::std::mem::size_of::<::rust_out::SomeStruct>() == 6);
+                    const _: () = assert!(::std::mem::align_of::<::rust_out::SomeStruct>() == 1);
+                    const _: () = assert!( memoffset::offset_of!(::rust_out::SomeStruct, field1)
+                                           == 0);
+                    const _: () = assert!( memoffset::offset_of!(::rust_out::SomeStruct, field2)
+                                           == 2);
                 }
-
-                // Public re-import.
-                pub use private_submodule1::subfunction1;
-
-                // Private re-import.
-                use private_submodule1::subfunction2;
-
-                // Re-import that renames.
-                pub use private_submodule1::subfunction3 as public_function3;
-
-                // Re-import of multiple items via glob.
-                pub use private_submodule2::*;
-            "#;
-        test_generated_bindings(test_src, |bindings| {
-            let bindings = bindings.unwrap();
-
-            let failures = vec![(1, 15), (3, 21), (4, 24)];
-            for (use_number, line_number) in failures.into_iter() {
-                let expected_comment_txt = format!(
-                    "Error generating bindings for `{{use#{use_number}}}` defined at \
-                     <crubit_unittests.rs>;l={line_number}: \
-                     Unsupported rustc_hir::hir::ItemKind: `use` import"
-                );
-                assert_cc_matches!(
-                    bindings.h_body,
-                    quote! {
-                        __COMMENT__ #expected_comment_txt
-                    }
-                );
-            }
+            );
         });
     }
 
     #[test]
-    fn test_format_item_fn_extern_c_no_mangle_no_params_no_return_type() {
+    fn test_format_item_struct_with_explicit_padding_in_generated_code() {
         let test_src = r#"
-                #[no_mangle]
-                pub extern "C" fn public_function() {}
+                pub struct SomeStruct {
+                    pub f1: u8,
+                    pub f2: u32,
+                }
+                const _: () = assert!(::std::mem::size_of::<SomeStruct>() == 8);
+                const _: () = assert!(::std::mem::align_of::<SomeStruct>() == 4);
             "#;
-        test_format_item(test_src, "public_function", |result| {
+        test_format_item(test_src, "SomeStruct", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
-            assert!(main_api.prereqs.is_empty());
+            let impl_details = get_impl_details_snippet(&result);
+            assert!(!main_api.prereqs.is_empty());
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
-                    extern "C" void public_function();
+                    ...
+                    struct alignas(4) SomeStruct final {
+                        ...
+                        std::uint32_t f2;
+                        std::uint8_t f1;
+                        unsigned char __padding0[3];
+                        // C++17 requires `constexpr` constructors to initialize all the
+                        // members (including the padding).
+                        constexpr explicit SomeStruct(std::uint8_t f1, std::uint32_t f2)
+                            : f2(f2), f1(f1), __padding0{} {}
+                        inline static void __crubit_field_offset_assertions();
+                    };
+                }
+            );
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    static_assert(sizeof(SomeStruct) == 8, ...);
+                    static_assert(alignof(SomeStruct) == 4, ...);
+                    inline void SomeStruct::__crubit_field_offset_assertions() {
+                      static_assert(0 == offsetof(SomeStruct, f2));
+                      static_assert(4 == offsetof(SomeStruct, f1));
+                    }
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    const _: () = assert!(::std::mem::size_of::<::rust_out::SomeStruct>() == 8);
+                    const _: () = assert!(::std::mem::align_of::<::rust_out::SomeStruct>() == 4);
+                    const _: () = assert!( memoffset::offset_of!(::rust_out::SomeStruct, f2) == 0);
+                    const _: () = assert!( memoffset::offset_of!(::rust_out::SomeStruct, f1) == 4);
                 }
             );
         });
     }
 
-    /// The `test_format_item_fn_explicit_unit_return_type` test below is very
-    /// similar to the
-    /// `test_format_item_fn_extern_c_no_mangle_no_params_no_return_type` above,
-    /// except that the return type is explicitly spelled out.  There is no
-    /// difference in `ty::FnSig` so our code behaves exactly the same, but the
-    /// test has been planned based on earlier, hir-focused approach and having
-    /// this extra test coverage shouldn't hurt. (`hir::FnSig`
-    /// and `hir::FnRetTy` _would_ see a difference between the two tests, even
-    /// though there is no different in the current `bindings.rs` code).
     #[test]
-    fn test_format_item_fn_explicit_unit_return_type() {
+    fn test_format_item_struct_with_tuple_field() {
         let test_src = r#"
-                #[no_mangle]
-                pub extern "C" fn explicit_unit_return_type() -> () {}
+                pub struct SomeStruct {
+                    pub pair: (i32, u8),
+                }
             "#;
-        test_format_item(test_src, "explicit_unit_return_type", |result| {
+        test_format_item(test_src, "SomeStruct", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
-            assert!(main_api.prereqs.is_empty());
+            let impl_details = get_impl_details_snippet(&result);
+            let comment = "Replicates the layout of the Rust tuple type `(i32, u8)`.";
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
-                    extern "C" void explicit_unit_return_type();
+                    ...
+                    struct alignas(4) SomeStruct final {
+                        ...
+                        private:
+                            __COMMENT__ #comment
+                            struct alignas(4) __CrubitTupleField0 final {
+                                std::int32_t __field0;
+                                std::uint8_t __field1;
+                                unsigned char __padding1[3];
+                            };
+                            __CrubitTupleField0 pair;
+                            inline static void __crubit_field_offset_assertions();
+                    };
+                }
+            );
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    inline void SomeStruct::__crubit_field_offset_assertions() {
+                        static_assert(0 == offsetof(SomeStruct, pair));
+                        static_assert(0 == offsetof(__CrubitTupleField0, __field0));
+                        static_assert(4 == offsetof(__CrubitTupleField0, __field1));
+                        static_assert(sizeof(__CrubitTupleField0) == 8);
+                        static_assert(alignof(__CrubitTupleField0) == 4);
+                    }
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    const _: () = assert!(memoffset::offset_of!(::rust_out::SomeStruct, pair) == 0);
+                    const _: () = assert!(memoffset::offset_of_tuple!((i32, u8), 0) == 0);
+                    const _: () = assert!(memoffset::offset_of_tuple!((i32, u8), 1) == 4);
+                    const _: () = assert!(::std::mem::size_of::<(i32, u8)>() == 8);
+                    const _: () = assert!(::std::mem::align_of::<(i32, u8)>() == 4);
                 }
             );
         });
     }
 
     #[test]
-    fn test_format_item_fn_never_return_type() {
+    fn test_format_item_struct_with_unsupported_tuple_field() {
         let test_src = r#"
-                #[no_mangle]
-                pub extern "C" fn never_returning_function() -> ! {
-                    panic!("This function panics and therefore never returns");
+                pub struct SomeStruct {
+                    pub nested: ((i32, i32), i32),
                 }
             "#;
-        test_format_item(test_src, "never_returning_function", |result| {
-            // TODO(b/254507801): The function should be annotated with the `[[noreturn]]`
-            // attribute.
-            // TODO(b/254507801): Expect `crubit::Never` instead (see the bug for more
-            // details).
+        test_format_item(test_src, "SomeStruct", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
-            assert!(main_api.prereqs.is_empty());
+            let broken_field_msg = "Field type has been replaced with a blob of bytes: \
+                                    Failed to format the tuple type `((i32, i32), i32)`: \
+                                    Nested tuples are not supported in struct fields yet \
+                                    (b/254099023)";
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
-                    extern "C" void never_returning_function();
+                    ...
+                    struct alignas(4) SomeStruct final {
+                        ...
+                        private:
+                            __COMMENT__ #broken_field_msg
+                            unsigned char nested[12];
+                            inline static void __crubit_field_offset_assertions();
+                    };
                 }
             );
-        })
+        });
     }
 
-    /// `test_format_item_fn_mangling` checks that bindings can be generated for
-    /// `extern "C"` functions that do *not* have `#[no_mangle]` attribute.  The
-    /// test elides away the mangled name in the `assert_cc_matches` checks
-    /// below, but end-to-end test coverage should eventually be provided by
-    /// `test/functions` (see b/262904507).
     #[test]
-    fn test_format_item_fn_mangling() {
+    fn test_format_item_static_method() {
         let test_src = r#"
-                pub extern "C" fn public_function(x: f64, y: f64) -> f64 { x + y }
+                /// No-op `f32` placeholder is used, because ZSTs are not supported
+                /// (b/258259459).
+                pub struct Math(f32);
+
+                impl Math {
+                    pub fn add_i32(x: f32, y: f32) -> f32 {
+                        x + y
+                    }
+                }
             "#;
-        test_format_item(test_src, "public_function", |result| {
+        test_format_item(test_src, "Math", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
             let impl_details = get_impl_details_snippet(&result);
@@ -2399,20 +11509,33 @@ pub mod tests {
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
-                    inline double public_function(double x, double y);
+                    ...
+                    struct ... Math final {
+                        ...
+                        public:
+                          ...
+                          static inline float add_i32(float x, float y);
+                        ...
+                    };
                 }
             );
-            assert!(impl_details.rs.is_empty());
-            assert!(impl_details.cc.prereqs.is_empty());
             assert_cc_matches!(
                 impl_details.cc.tokens,
                 quote! {
                     namespace __crubit_internal {
-                        extern "C" double ...(double x, double y);
+                    extern "C" float ... (float x, float y);
                     }
-                    ...
-                    inline double public_function(double x, double y) {
-                        return __crubit_internal::...(x, y);
+                    inline float Math::add_i32(float x, float y) {
+                      return __crubit_internal::...(x, y);
+                    }
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    #[no_mangle]
+                    extern "C" fn ...(x: f32, y: f32) -> f32 {
+                        ::rust_out::Math::add_i32(x, y)
                     }
                 }
             );
@@ -2420,33 +11543,87 @@ pub mod tests {
     }
 
     #[test]
-    fn test_format_item_fn_export_name() {
+    fn test_format_item_static_method_with_generic_type_parameters() {
         let test_src = r#"
-                #[export_name = "export_name"]
-                pub extern "C" fn public_function(x: f64, y: f64) -> f64 { x + y }
+                /// No-op `f32` placeholder is used, because ZSTs are not supported
+                /// (b/258259459).
+                pub struct SomeStruct(f32);
+
+                impl SomeStruct {
+                    // To make this testcase distinct / non-overlapping wrt
+                    // test_format_item_static_method_with_generic_lifetime_parameters
+                    // `t` is taken by value below.
+                    pub fn generic_method<T: Clone>(t: T) -> T {
+                        t.clone()
+                    }
+                }
             "#;
-        test_format_item(test_src, "public_function", |result| {
+        test_format_item(test_src, "SomeStruct", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
             let impl_details = get_impl_details_snippet(&result);
             assert!(main_api.prereqs.is_empty());
+            let unsupported_msg = "Error generating bindings for `SomeStruct::generic_method` \
+                                   defined at <crubit_unittests.rs>;l=10: \
+                                   Generic functions are not supported yet (b/259749023)";
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
-                    inline double public_function(double x, double y);
+                    ...
+                    struct ... SomeStruct final {
+                        ...
+                        __COMMENT__ #unsupported_msg
+                        ...
+                    };
+                    ...
+                }
+            );
+            assert_cc_not_matches!(impl_details.cc.tokens, quote! { SomeStruct::generic_method },);
+            assert_rs_not_matches!(impl_details.rs, quote! { generic_method },);
+        });
+    }
+
+    #[test]
+    fn test_format_item_static_method_with_generic_lifetime_parameters() {
+        let test_src = r#"
+                /// No-op `f32` placeholder is used, because ZSTs are not supported
+                /// (b/258259459).
+                pub struct SomeStruct(f32);
+
+                impl SomeStruct {
+                    pub fn fn_taking_reference<'a>(x: &'a i32) -> i32 { *x }
+                }
+            "#;
+        test_format_item(test_src, "SomeStruct", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    ...
+                    struct ... SomeStruct final {
+                        ...
+                        public:
+                          ...
+                          static inline std::int32_t fn_taking_reference(const std::int32_t& x);
+                        ...
+                    };
                 }
             );
-            assert!(impl_details.rs.is_empty());
-            assert!(impl_details.cc.prereqs.is_empty());
             assert_cc_matches!(
                 impl_details.cc.tokens,
                 quote! {
-                    namespace __crubit_internal {
-                        extern "C" double export_name(double x, double y);
+                    inline std::int32_t SomeStruct::fn_taking_reference(const std::int32_t& x) {
+                        return __crubit_internal::...(x);
                     }
-                    ...
-                    inline double public_function(double x, double y) {
-                        return __crubit_internal::export_name(x, y);
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    extern "C" fn ...(x: &i32) -> i32 {
+                        ::rust_out::SomeStruct::fn_taking_reference(x)
                     }
                 }
             );
@@ -2454,55 +11631,93 @@ pub mod tests {
     }
 
     #[test]
-    fn test_format_item_unsupported_fn_unsafe() {
+    fn test_format_item_method_taking_self_by_value() {
         let test_src = r#"
-                #[no_mangle]
-                pub unsafe extern "C" fn foo() {}
+                pub struct SomeStruct(f32);
+
+                impl SomeStruct {
+                    pub fn into_f32(self) -> f32 {
+                        self.0
+                    }
+                }
             "#;
-        test_format_item(test_src, "foo", |result| {
-            let err = result.unwrap_err();
-            assert_eq!(
-                err,
-                "Bindings for `unsafe` functions \
-                             are not fully designed yet (b/254095482)"
+        test_format_item(test_src, "SomeStruct", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            assert!(main_api.prereqs.is_empty());
+            let unsupported_msg = "Error generating bindings for `SomeStruct::into_f32` \
+                                   defined at <crubit_unittests.rs>;l=5: \
+                                   `self` parameter is not supported yet";
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    ...
+                    struct ... SomeStruct final {
+                        ...
+                        __COMMENT__ #unsupported_msg
+                        ...
+                    };
+                    ...
+                }
             );
-        });
-    }
-
-    /// `test_format_item_fn_const` tests how bindings for an `const fn` are
-    /// generated.
-    ///
-    /// Right now the `const` qualifier is ignored, but one can imagine that in
-    /// the (very) long-term future such functions (including their bodies)
-    /// could be translated into C++ `consteval` functions.
+            assert_cc_not_matches!(impl_details.cc.tokens, quote! { SomeStruct::into_f32 },);
+            assert_rs_not_matches!(impl_details.rs, quote! { into_f32 },);
+        });
+    }
+
     #[test]
-    fn test_format_item_fn_const() {
+    fn test_format_item_method_taking_self_by_const_ref() {
         let test_src = r#"
-                pub const fn foo(i: i32) -> i32 { i * 42 }
+                pub struct SomeStruct(f32);
+
+                impl SomeStruct {
+                    pub fn get_f32(&self) -> f32 {
+                        self.0
+                    }
+                    pub fn get_f32_ref(&self) -> &f32 {
+                        &self.0
+                    }
+                }
             "#;
-        test_format_item(test_src, "foo", |result| {
-            // TODO(b/254095787): Update test expectations below once `const fn` from Rust
-            // is translated into a `consteval` C++ function.
+        test_format_item(test_src, "SomeStruct", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
             let impl_details = get_impl_details_snippet(&result);
-            assert!(!main_api.prereqs.is_empty());
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
-                    inline std::int32_t foo(std::int32_t i);
+                    ...
+                    struct ... SomeStruct final {
+                        ...
+                        public:
+                          ...
+                          inline float get_f32() const;
+                          ...
+                          inline const float& get_f32_ref() const [[clang::lifetimebound]];
+                        ...
+                    };
                 }
             );
-            assert!(!impl_details.cc.prereqs.is_empty());
             assert_cc_matches!(
                 impl_details.cc.tokens,
                 quote! {
                     namespace __crubit_internal {
-                        extern "C" std::int32_t ...( std::int32_t i);
+                        extern "C" float ...(const ::rust_out::SomeStruct& __self);
                     }
-                    ...
-                    inline std::int32_t foo(std::int32_t i) {
-                        return __crubit_internal::...(i);
+                    inline float SomeStruct::get_f32() const {
+                        return __crubit_internal::...(*this);
+                    }
+                }
+            );
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" const float* ...(const ::rust_out::SomeStruct& __self);
+                    }
+                    inline const float& SomeStruct::get_f32_ref() const [[clang::lifetimebound]] {
+                        return *__crubit_internal::...(*this);
                     }
                 }
             );
@@ -2510,380 +11725,358 @@ pub mod tests {
                 impl_details.rs,
                 quote! {
                     #[no_mangle]
-                    extern "C"
-                    fn ...(i: i32) -> i32 {
-                        ::rust_out::foo(i)
+                    extern "C" fn ...(__self: &::rust_out::SomeStruct) -> f32 {
+                        ::rust_out::SomeStruct::get_f32(__self)
                     }
                 }
             );
-        });
-    }
-
-    #[test]
-    fn test_format_item_fn_with_c_unwind_abi() {
-        // See also https://rust-lang.github.io/rfcs/2945-c-unwind-abi.html
-        let test_src = r#"
-                #![feature(c_unwind)]
-
-                #[no_mangle]
-                pub extern "C-unwind" fn may_throw() {}
-            "#;
-        test_format_item(test_src, "may_throw", |result| {
-            let result = result.unwrap();
-            let main_api = get_main_api_snippet(&result);
-            assert!(main_api.prereqs.is_empty());
-            assert_cc_matches!(
-                main_api.tokens,
+            assert_rs_matches!(
+                impl_details.rs,
                 quote! {
-                    extern "C" void may_throw();
+                    #[no_mangle]
+                    extern "C" fn ...(__self: &::rust_out::SomeStruct) -> *const f32 {
+                        ::rust_out::SomeStruct::get_f32_ref(__self) as *const f32
+                    }
                 }
             );
         });
     }
 
-    /// This test mainly verifies that `format_item` correctly propagates
-    /// `CcPrerequisites` of parameter types and return type.
     #[test]
-    fn test_format_item_fn_cc_prerequisites_if_cpp_definition_needed() {
+    fn test_format_item_method_taking_self_by_mutable_ref() {
         let test_src = r#"
-                pub fn foo(_i: i32) -> S { panic!("foo") }
-                pub struct S(i32);
+                pub struct SomeStruct(f32);
+
+                impl SomeStruct {
+                    pub fn set_f32(&mut self, new_value: f32) {
+                        self.0 = new_value;
+                    }
+                }
             "#;
-        test_format_item(test_src, "foo", |result| {
+        test_format_item(test_src, "SomeStruct", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
             let impl_details = get_impl_details_snippet(&result);
-
-            // Minimal coverage, just to double-check that the test setup works.
-            //
-            // Note that this is a definition, and therefore `S` should be defined
-            // earlier (not just forward declared).
-            assert_cc_matches!(main_api.tokens, quote! { S foo(std::int32_t _i);});
-            assert_cc_matches!(impl_details.cc.tokens, quote! { S foo(std::int32_t _i) { ... }});
-
-            // Main checks: `CcPrerequisites::includes`.
             assert_cc_matches!(
-                format_cc_includes(&main_api.prereqs.includes),
-                quote! { include <cstdint> }
+                main_api.tokens,
+                quote! {
+                    ...
+                    struct ... SomeStruct final {
+                        ...
+                        public:
+                          ...
+                          inline void set_f32(float new_value);
+                        ...
+                    };
+                }
             );
             assert_cc_matches!(
-                format_cc_includes(&impl_details.cc.prereqs.includes),
-                quote! { include <cstdint> }
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" void ...(::rust_out::SomeStruct& __self, float new_value);
+                    }
+                    inline void SomeStruct::set_f32(float new_value) {
+                        return __crubit_internal::...(*this, new_value);
+                    }
+                }
             );
-
-            // Main checks: `CcPrerequisites::defs` and `CcPrerequisites::fwd_decls`.
-            //
-            // Verifying the actual def_id is tricky, because `test_format_item` doesn't
-            // expose `tcx` to the verification function (and therefore calling
-            // `find_def_id_by_name` is not easily possible).
-            //
-            // Note that `main_api` and `impl_details` have different expectations.
-            assert_eq!(0, main_api.prereqs.defs.len());
-            assert_eq!(1, main_api.prereqs.fwd_decls.len());
-            assert_eq!(1, impl_details.cc.prereqs.defs.len());
-            assert_eq!(0, impl_details.cc.prereqs.fwd_decls.len());
-        });
-    }
-
-    /// This test verifies that `format_item` uses `CcPrerequisites::fwd_decls`
-    /// rather than `CcPrerequisites::defs` for functions that only need a
-    /// C++ declaration (and don't need a C++ definition).
-    #[test]
-    fn test_format_item_fn_cc_prerequisites_if_only_cpp_declaration_needed() {
-        let test_src = r#"
-                // `foo` will only have a C++ declaration (and no C++ definition)
-                // in the generated `..._cc_api.h` header.
-                //
-                // Therefore `foo`'s `CcPrerequisites` should include `S`
-                // as a `fwd_decls` edge, rather than as a `defs` edge.
-                #[no_mangle]
-                pub extern "C" fn foo(s: S) -> bool { s.0 }
-
-                #[repr(C)]
-                pub struct S(bool);
-            "#;
-        test_format_item(test_src, "foo", |result| {
-            let result = result.unwrap();
-            let main_api = get_main_api_snippet(&result);
-
-            // Minimal coverage, just to double-check that the test setup works.
-            //
-            // Note that this is only a function *declaration* (not a function definition -
-            // there is no function body), and therefore `S` just needs to be
-            // forward-declared earlier.
-            assert_cc_matches!(main_api.tokens, quote! { extern "C" bool foo(::rust_out::S s); });
-
-            // Main checks: `CcPrerequisites::defs` and `CcPrerequisites::fwd_decls`.
-            //
-            // Verifying the actual def_id is tricky, because `test_format_item` doesn't
-            // expose `tcx` to the verification function (and therefore calling
-            // `find_def_id_by_name` is not easily possible).
-            assert_eq!(0, main_api.prereqs.defs.len());
-            assert_eq!(1, main_api.prereqs.fwd_decls.len());
-        });
-    }
-
-    #[test]
-    fn test_format_item_fn_with_type_aliased_return_type() {
-        // Type aliases disappear at the `rustc_middle::ty::Ty` level and therefore in
-        // the short-term the generated bindings also ignore type aliases.
-        //
-        // TODO(b/254096006): Consider preserving `type` aliases when generating
-        // bindings.
-        let test_src = r#"
-                type MyTypeAlias = f64;
-
-                #[no_mangle]
-                pub extern "C" fn type_aliased_return() -> MyTypeAlias { 42.0 }
-            "#;
-        test_format_item(test_src, "type_aliased_return", |result| {
-            let result = result.unwrap();
-            let main_api = get_main_api_snippet(&result);
-            assert!(main_api.prereqs.is_empty());
-            assert_cc_matches!(
-                main_api.tokens,
+            assert_rs_matches!(
+                impl_details.rs,
                 quote! {
-                    extern "C" double type_aliased_return();
+                    #[no_mangle]
+                    extern "C" fn ...(__self: &mut ::rust_out::SomeStruct, new_value: f32) -> () {
+                        ::rust_out::SomeStruct::set_f32(__self, new_value)
+                    }
                 }
             );
         });
     }
 
     #[test]
-    fn test_format_item_fn_with_doc_comment_with_unmangled_name() {
+    fn test_format_item_method_taking_self_by_explicit_ref() {
         let test_src = r#"
-            /// Outer line doc.
-            /** Outer block doc that spans lines.
-             */
-            #[doc = "Doc comment via doc attribute."]
-            #[no_mangle]
-            pub extern "C" fn fn_with_doc_comment_with_unmangled_name() {}
-          "#;
-        test_format_item(test_src, "fn_with_doc_comment_with_unmangled_name", |result| {
+                pub struct SomeStruct(f32);
+
+                impl SomeStruct {
+                    pub fn get_f32(self: &Self) -> f32 {
+                        self.0
+                    }
+                }
+            "#;
+        test_format_item(test_src, "SomeStruct", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
-            assert!(main_api.prereqs.is_empty());
-            let doc_comments = [
-                " Outer line doc.",
-                "",
-                " Outer block doc that spans lines.",
-                "             ",
-                "",
-                "Doc comment via doc attribute.",
-                "",
-                "Generated from: <crubit_unittests.rs>;l=7",
-            ]
-            .join("\n");
+            let impl_details = get_impl_details_snippet(&result);
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
-                    __COMMENT__ #doc_comments
-                    extern "C" void fn_with_doc_comment_with_unmangled_name();
+                    ...
+                    struct ... SomeStruct final {
+                        ...
+                        public:
+                          ...
+                          inline float get_f32() const;
+                        ...
+                    };
                 }
             );
-        });
-    }
-
-    #[test]
-    fn test_format_item_fn_with_inner_doc_comment_with_unmangled_name() {
-        let test_src = r#"
-            /// Outer doc comment.
-            #[no_mangle]
-            pub extern "C" fn fn_with_inner_doc_comment_with_unmangled_name() {
-                //! Inner doc comment.
-            }
-          "#;
-        test_format_item(test_src, "fn_with_inner_doc_comment_with_unmangled_name", |result| {
-            let result = result.unwrap();
-            let main_api = get_main_api_snippet(&result);
-            assert!(main_api.prereqs.is_empty());
-            let doc_comments = [
-                " Outer doc comment.",
-                " Inner doc comment.",
-                "Generated from: <crubit_unittests.rs>;l=4",
-            ]
-            .join("\n\n");
             assert_cc_matches!(
-                main_api.tokens,
+                impl_details.cc.tokens,
                 quote! {
-                    __COMMENT__ #doc_comments
-                    extern "C" void fn_with_inner_doc_comment_with_unmangled_name();
+                    namespace __crubit_internal {
+                        extern "C" float ...(const ::rust_out::SomeStruct& __self);
+                    }
+                    inline float SomeStruct::get_f32() const {
+                        return __crubit_internal::...(*this);
+                    }
                 }
             );
         });
     }
 
     #[test]
-    fn test_format_item_fn_with_doc_comment_with_mangled_name() {
+    fn test_format_item_method_taking_self_by_pinned_mutable_ref() {
         let test_src = r#"
-                /// Doc comment of a function with mangled name.
-                pub extern "C" fn fn_with_doc_comment_with_mangled_name() {}
+                use std::pin::Pin;
+
+                pub struct SomeStruct(f32);
+
+                impl SomeStruct {
+                    pub fn set_f32(self: Pin<&mut Self>, new_value: f32) {
+                        self.get_mut().0 = new_value;
+                    }
+                }
             "#;
-        test_format_item(test_src, "fn_with_doc_comment_with_mangled_name", |result| {
+        test_format_item(test_src, "SomeStruct", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
-            assert!(main_api.prereqs.is_empty());
-            let comment = " Doc comment of a function with mangled name.\n\n\
-                           Generated from: <crubit_unittests.rs>;l=3";
+            let impl_details = get_impl_details_snippet(&result);
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
-                    __COMMENT__ #comment
-                    inline void fn_with_doc_comment_with_mangled_name();
+                    ...
+                    struct ... SomeStruct final {
+                        ...
+                        public:
+                          ...
+                          inline void set_f32(float new_value);
+                        ...
+                    };
                 }
             );
-        });
-    }
-
-    #[test]
-    fn test_format_item_unsupported_fn_name_is_reserved_cpp_keyword() {
-        let test_src = r#"
-                #[no_mangle]
-                pub extern "C" fn reinterpret_cast() -> () {}
-            "#;
-        test_format_item(test_src, "reinterpret_cast", |result| {
-            let err = result.unwrap_err();
-            assert_eq!(
-                err,
-                "Error formatting function name: \
-                       `reinterpret_cast` is a C++ reserved keyword \
-                       and can't be used as a C++ identifier"
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" void ...(::rust_out::SomeStruct& __self, float new_value);
+                    }
+                    inline void SomeStruct::set_f32(float new_value) {
+                        return __crubit_internal::...(*this, new_value);
+                    }
+                }
             );
-        });
-    }
-
-    #[test]
-    fn test_format_item_unsupported_fn_ret_type() {
-        let test_src = r#"
-                pub fn foo() -> (i32, i32) { (123, 456) }
-            "#;
-        test_format_item(test_src, "foo", |result| {
-            let err = result.unwrap_err();
-            assert_eq!(
-                err,
-                "Error formatting function return type: \
-                       Tuples are not supported yet: (i32, i32) (b/254099023)"
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    #[no_mangle]
+                    extern "C" fn ...(__self: &mut ::rust_out::SomeStruct, new_value: f32) -> () {
+                        ::rust_out::SomeStruct::set_f32(::core::pin::Pin::new(__self), new_value)
+                    }
+                }
             );
         });
     }
 
+    /// Known limitation: C++ can move objects of any type (the C++ bindings of
+    /// `!Unpin` types are not made non-movable), so pinned receivers are only
+    /// supported for `Unpin` types - i.e. not for the types that need pinning.
     #[test]
-    fn test_format_item_unsupported_fn_with_late_bound_lifetimes() {
-        // TODO(b/258235219): Expect success after adding support for references.
+    fn test_format_item_method_taking_self_by_pinned_ref_of_non_unpin_type() {
         let test_src = r#"
-                pub fn foo(arg: &i32) -> &i32 { arg }
-
-                // Lifetime inference translates the above into:
-                //     pub fn foo<'a>(arg: &'a i32) -> &'a i32 { ... }
-                // leaving 'a lifetime late-bound (it is bound with a lifetime
-                // taken from each of the callsites).  In other words, we can't
-                // just call `no_bound_vars` on this `FnSig`'s `Binder`.
-            "#;
-        test_format_item(test_src, "foo", |result| {
-            let err = result.unwrap_err();
-            assert_eq!(err, "Generic functions are not supported yet (b/259749023)");
-        });
-    }
+                use std::marker::PhantomPinned;
+                use std::pin::Pin;
 
-    #[test]
-    fn test_format_item_unsupported_generic_fn() {
-        let test_src = r#"
-                use std::default::Default;
-                use std::fmt::Display;
-                pub fn generic_function<T: Default + Display>() {
-                    println!("{}", T::default());
+                pub struct SomeStruct {
+                    value: f32,
+                    _pinned: PhantomPinned,
                 }
-            "#;
-        test_format_item(test_src, "generic_function", |result| {
-            let err = result.unwrap_err();
-            assert_eq!(err, "Generic functions are not supported yet (b/259749023)");
-        });
-    }
 
-    #[test]
-    fn test_format_item_unsupported_generic_struct() {
-        let test_src = r#"
-                pub struct Point<T> {
-                    pub x: T,
-                    pub y: T,
+                impl SomeStruct {
+                    pub fn get_f32(self: Pin<&Self>) -> f32 {
+                        self.value
+                    }
                 }
             "#;
-        test_format_item(test_src, "Point", |result| {
-            let err = result.unwrap_err();
-            assert_eq!(err, "Generic types are not supported yet (b/259749095)");
+        test_format_item(test_src, "SomeStruct", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let unsupported_msg = "Error generating bindings for `SomeStruct::get_f32` \
+                                   defined at <crubit_unittests.rs>;l=11: \
+                                   `self: std::pin::Pin<&SomeStruct>` receivers are only \
+                                   supported for types that implement `Unpin` (C++ doesn't \
+                                   prevent moving pinned objects)";
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    ...
+                    struct ... SomeStruct final {
+                        ...
+                        __COMMENT__ #unsupported_msg
+                        ...
+                    };
+                    ...
+                }
+            );
         });
     }
 
     #[test]
-    fn test_format_item_unsupported_generic_enum() {
+    fn test_format_item_method_taking_self_by_box() {
         let test_src = r#"
-                pub enum Point<T> {
-                    Cartesian{x: T, y: T},
-                    Polar{angle: T, dist: T},
+                pub struct SomeStruct(f32);
+
+                impl SomeStruct {
+                    pub fn into_f32(self: Box<Self>) -> f32 {
+                        self.0
+                    }
                 }
             "#;
-        test_format_item(test_src, "Point", |result| {
-            let err = result.unwrap_err();
-            assert_eq!(err, "Generic types are not supported yet (b/259749095)");
+        test_format_item(test_src, "SomeStruct", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    ...
+                    struct ... SomeStruct final {
+                        ...
+                        public:
+                          ...
+                          inline float into_f32() &&;
+                          static inline float into_f32(
+                              rs_std::Box<::rust_out::SomeStruct>&& __self);
+                        ...
+                    };
+                }
+            );
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" float ...(
+                            ::rust_out::SomeStruct* __self,
+                            rs_std::Box<::rust_out::SomeStruct>* __self_box);
+                    }
+                    inline float SomeStruct::into_f32() && {
+                        return __crubit_internal::...(this, nullptr);
+                    }
+                    inline float SomeStruct::into_f32(
+                            rs_std::Box<::rust_out::SomeStruct>&& __self) {
+                        return __crubit_internal::...(nullptr, &__self);
+                    }
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    #[no_mangle]
+                    extern "C" fn ...(
+                        __self: ::core::option::Option<
+                            &mut ::core::mem::MaybeUninit<::rust_out::SomeStruct>>,
+                        __self_box: ::core::option::Option<
+                            &mut ::core::option::Option<::std::boxed::Box<::rust_out::SomeStruct>>>
+                    ) -> f32 {
+                        ::rust_out::SomeStruct::into_f32(
+                            match __self_box {
+                                Some(__self_box) => __self_box
+                                    .take()
+                                    .expect("Null `rs_std::Box` passed as `Box<T>`"),
+                                None => ::std::boxed::Box::new(unsafe {
+                                    __self.expect("Null `this` passed as `self: Box<Self>`")
+                                        .assume_init_read()
+                                }),
+                            })
+                    }
+                }
+            );
         });
     }
 
     #[test]
-    fn test_format_item_unsupported_generic_union() {
+    fn test_format_item_method_taking_self_by_box_with_drop_glue() {
         let test_src = r#"
-                pub union SomeUnion<T> {
-                    pub x: std::mem::ManuallyDrop<T>,
-                    pub y: i32,
+                #[derive(Default)]
+                pub struct SomeStruct(String);
+
+                impl SomeStruct {
+                    pub fn into_len(self: Box<Self>) -> usize {
+                        self.0.len()
+                    }
                 }
             "#;
-        test_format_item(test_src, "SomeUnion", |result| {
-            let err = result.unwrap_err();
-            assert_eq!(err, "Generic types are not supported yet (b/259749095)");
+        test_format_item(test_src, "SomeStruct", |result| {
+            let result = result.unwrap();
+            let impl_details = get_impl_details_snippet(&result);
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    #[no_mangle]
+                    extern "C" fn ...(
+                        __self: ::core::option::Option<&mut ::rust_out::SomeStruct>,
+                        __self_box: ...
+                    ) -> usize {
+                        ::rust_out::SomeStruct::into_len(
+                            match __self_box {
+                                ...
+                                None => ::std::boxed::Box::new(::core::mem::take(
+                                    __self.expect("Null `this` passed as `self: Box<Self>`"))),
+                            })
+                    }
+                }
+            );
         });
     }
 
     #[test]
-    fn test_format_item_unsupported_fn_async() {
+    fn test_format_item_method_taking_self_by_rc() {
         let test_src = r#"
-                pub async fn async_function() {}
-            "#;
-        test_format_item(test_src, "async_function", |result| {
-            let err = result.unwrap_err();
-            assert_eq!(err, "Error formatting function return type: \
-                             The following Rust type is not supported yet: \
-                             impl std::future::Future<Output = ()>");
-        });
-    }
+                use std::rc::Rc;
 
-    #[test]
-    fn test_format_item_fn_rust_abi() {
-        let test_src = r#"
-                pub fn add(x: f64, y: f64) -> f64 { x * y }
+                pub struct SomeStruct(f32);
+
+                impl SomeStruct {
+                    pub fn get_f32(self: Rc<Self>) -> f32 {
+                        self.0
+                    }
+                }
             "#;
-        test_format_item(test_src, "add", |result| {
-            // TODO(b/261074843): Re-add thunk name verification once we are using stable name
-            // mangling (which may be coming in Q1 2023).  (This might mean reverting cl/492333432
-            // + manual review and tweaks.)
+        test_format_item(test_src, "SomeStruct", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
             let impl_details = get_impl_details_snippet(&result);
-            assert!(main_api.prereqs.is_empty());
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
-                    inline double add(double x, double y);
+                    ...
+                    struct ... SomeStruct final {
+                        ...
+                        public:
+                          ...
+                          static inline float get_f32(rs_std::Rc<::rust_out::SomeStruct> self);
+                        ...
+                    };
                 }
             );
-            assert!(impl_details.cc.prereqs.is_empty());
             assert_cc_matches!(
                 impl_details.cc.tokens,
                 quote! {
                     namespace __crubit_internal {
-                        extern "C" double ...(double x, double y);
+                        extern "C" float ...(rs_std::Rc<::rust_out::SomeStruct>* self);
                     }
-                    ...
-                    inline double add(double x, double y) {
-                        return __crubit_internal::...(x, y);
+                    inline float SomeStruct::get_f32(rs_std::Rc<::rust_out::SomeStruct> self) {
+                        return __crubit_internal::...(&self);
                     }
                 }
             );
@@ -2891,9 +12084,50 @@ pub mod tests {
                 impl_details.rs,
                 quote! {
                     #[no_mangle]
-                    extern "C"
-                    fn ...(x: f64, y: f64) -> f64 {
-                        ::rust_out::add(x, y)
+                    extern "C" fn ...(__self: &mut *const ::rust_out::SomeStruct) -> f32 {
+                        ::rust_out::SomeStruct::get_f32(__crubit_take_rc(__self))
+                    }
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_method_taking_self_by_arc() {
+        let test_src = r#"
+                use std::sync::Arc;
+
+                pub struct SomeStruct(f32);
+
+                impl SomeStruct {
+                    pub fn get_f32(self: Arc<Self>) -> f32 {
+                        self.0
+                    }
+                }
+            "#;
+        test_format_item(test_src, "SomeStruct", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    ...
+                    struct ... SomeStruct final {
+                        ...
+                        public:
+                          ...
+                          static inline float get_f32(rs_std::Arc<::rust_out::SomeStruct> self);
+                        ...
+                    };
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    #[no_mangle]
+                    extern "C" fn ...(__self: &mut *const ::rust_out::SomeStruct) -> f32 {
+                        ::rust_out::SomeStruct::get_f32(__crubit_take_arc(__self))
                     }
                 }
             );
@@ -2901,134 +12135,164 @@ pub mod tests {
     }
 
     #[test]
-    fn test_format_item_fn_rust_abi_with_param_taking_struct_by_value() {
+    fn test_format_item_unsupported_struct_with_name_that_is_reserved_keyword() {
         let test_src = r#"
-                pub struct S(i32);
-                pub fn into_i32(s: S) -> i32 { s.0 }
+                #[allow(non_camel_case_types)]
+                pub struct reinterpret_cast {
+                    pub x: i32,
+                    pub y: i32,
+                }
             "#;
-        test_format_item(test_src, "into_i32", |result| {
+        test_format_item(test_src, "reinterpret_cast", |result| {
+            let err = result.unwrap_err();
+            assert_eq!(
+                err,
+                "Error formatting item name: \
+                             `reinterpret_cast` is a C++ reserved keyword \
+                             and can't be used as a C++ identifier"
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_struct_with_unsupported_field_type() {
+        let test_src = r#"
+                pub struct SomeStruct {
+                    pub successful_field: i32,
+                    pub unsupported_field: Option<[i32; 3]>,
+                }
+            "#;
+        test_format_item(test_src, "SomeStruct", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
             let impl_details = get_impl_details_snippet(&result);
+            let broken_field_msg = "Field type has been replaced with a blob of bytes: \
+                                    Generic types are not supported yet (b/259749095)";
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
-                    inline std::int32_t into_i32(::rust_out::S s);
+                    ...
+                    struct ... SomeStruct final {
+                        ...
+                        private:
+                            __COMMENT__ #broken_field_msg
+                            unsigned char unsupported_field[16];
+                            std::int32_t successful_field;
+                            inline static void __crubit_field_offset_assertions();
+                    };
+                    ...
                 }
             );
             assert_cc_matches!(
                 impl_details.cc.tokens,
                 quote! {
-                    namespace __crubit_internal {
-                        extern "C" std::int32_t ...(::rust_out::S* s);
-                    }
-                    ...
-                    inline std::int32_t into_i32(::rust_out::S s) {
-                        return __crubit_internal::...(&s);
+                    static_assert(sizeof(SomeStruct) == 20, ...);
+                    static_assert(alignof(SomeStruct) == 4, ...);
+                    inline void SomeStruct::__crubit_field_offset_assertions() {
+                      static_assert(0 == offsetof(SomeStruct, unsupported_field));
+                      static_assert(16 == offsetof(SomeStruct, successful_field));
                     }
                 }
             );
             assert_rs_matches!(
                 impl_details.rs,
                 quote! {
-                    #[no_mangle]
-                    extern "C"
-                    fn ...(s: &mut ::core::mem::MaybeUninit<::rust_out::S>) -> i32 {
-                        ::rust_out::into_i32(unsafe { s.assume_init_read() })
-                    }
+                    const _: () = assert!(::std::mem::size_of::<::rust_out::SomeStruct>() == 20);
+                    const _: () = assert!(::std::mem::align_of::<::rust_out::SomeStruct>() == 4);
+                    const _: () = assert!( memoffset::offset_of!(::rust_out::SomeStruct,
+                                                                 unsupported_field) == 0);
+                    const _: () = assert!( memoffset::offset_of!(::rust_out::SomeStruct,
+                                                                 successful_field) == 16);
                 }
             );
         });
     }
 
     #[test]
-    fn test_format_item_fn_rust_abi_returning_struct_by_value() {
+    fn test_format_item_struct_with_copy_trait() {
         let test_src = r#"
-                pub struct S(i32);
-                pub fn create(i: i32) -> S { S(i) }
+                #[derive(Clone, Copy)]
+                pub struct Point {
+                    pub x: i32,
+                    pub y: i32,
+                }
             "#;
-        test_format_item(test_src, "create", |result| {
+        test_format_item(test_src, "Point", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
             let impl_details = get_impl_details_snippet(&result);
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
-                    inline ::rust_out::S create(std::int32_t i);
-                }
-            );
-            assert_cc_matches!(
-                impl_details.cc.tokens,
-                quote! {
-                    namespace __crubit_internal {
-                        extern "C" void ...(std::int32_t i, ::rust_out::S* __ret_ptr);
-                    }
                     ...
-                    inline ::rust_out::S create(std::int32_t i) {
-                        crubit::ReturnValueSlot<::rust_out::S> __ret_slot;
-                        __crubit_internal::...(i, __ret_slot.Get());
-                        return std::move(__ret_slot).AssumeInitAndTakeValue();
-                    }
-                }
-            );
-            assert_rs_matches!(
-                impl_details.rs,
-                quote! {
-                    #[no_mangle]
-                    extern "C"
-                    fn ...(
-                        i: i32,
-                        __ret_slot: &mut ::core::mem::MaybeUninit<::rust_out::S>
-                    ) -> () {
-                        __ret_slot.write(::rust_out::create(i));
-                    }
+                    struct alignas(4) Point final {
+                        public:
+                            Point() = delete;
+                            // `Copy` types are trivially copyable.
+                            Point(const Point&) = default;
+                            Point(Point&&) = default;
+                            Point& operator=(const Point&) = default;
+                            Point& operator=(Point&&) = default;
+                            ~Point() = default;
+                        ...
+                    };
                 }
             );
+            // No copy/move thunks are needed for trivially copyable types.
+            assert_cc_not_matches!(impl_details.cc.tokens, quote! { Point::Point });
+            assert_rs_not_matches!(impl_details.rs, quote! { ::core::clone::Clone });
         });
     }
 
-    /// `test_format_item_fn_rust_abi` tests a function call that is not a
-    /// C-ABI, and is not the default Rust ABI.  It can't use `"stdcall"`,
-    /// because it is not supported on the targets where Crubit's tests run.
-    /// So, it ended up using `"vectorcall"`.
-    ///
-    /// This test almost entirely replicates `test_format_item_fn_rust_abi`,
-    /// except for the `extern "vectorcall"` part in the `test_src` test
-    /// input.
-    ///
-    /// This test verifies the current behavior that gives reasonable and
-    /// functional FFI bindings.  OTOH, in the future we may decide to avoid
-    /// having the extra thunk for cases where the given non-C-ABI function
-    /// call convention is supported by both C++ and Rust
-    /// (see also `format_cc_call_conv_as_clang_attribute` in
-    /// `rs_bindings_from_cc/src_code_gen.rs`)
     #[test]
-    fn test_format_item_fn_vectorcall_abi() {
+    fn test_format_item_struct_with_clone_and_default_traits() {
         let test_src = r#"
-                #![feature(abi_vectorcall)]
-                pub extern "vectorcall" fn add(x: f64, y: f64) -> f64 { x * y }
+                #[derive(Clone, Default)]
+                pub struct SomeStruct {
+                    pub x: i32,
+                }
             "#;
-        test_format_item(test_src, "add", |result| {
+        test_format_item(test_src, "SomeStruct", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
             let impl_details = get_impl_details_snippet(&result);
-            assert!(main_api.prereqs.is_empty());
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
-                    inline double add(double x, double y);
+                    ...
+                    struct alignas(4) SomeStruct final {
+                        public:
+                            // The default constructor, the copy constructor and the copy
+                            // assignment operator are defined out-of-line (see
+                            // `impl_details` below).
+                            SomeStruct();
+                            SomeStruct(const SomeStruct&);
+                            SomeStruct(SomeStruct&&) = default;
+                            SomeStruct& operator=(const SomeStruct&);
+                            ~SomeStruct() = default;
+                        ...
+                    };
                 }
             );
-            assert!(impl_details.cc.prereqs.is_empty());
             assert_cc_matches!(
                 impl_details.cc.tokens,
                 quote! {
                     namespace __crubit_internal {
-                        extern "C" double ...(double x, double y);
+                        extern "C" void ...(SomeStruct* __self);
+                        extern "C" void ...(SomeStruct* __self, const SomeStruct* __other);
+                        extern "C" void ...(SomeStruct* __self, const SomeStruct* __other);
                     }
-                    ...
-                    inline double add(double x, double y) {
-                        return __crubit_internal::...(x, y);
+                    inline SomeStruct::SomeStruct() {
+                        __crubit_internal::...(this);
+                    }
+                    inline SomeStruct::SomeStruct(const SomeStruct& other) {
+                        __crubit_internal::...(this, &other);
+                    }
+                    inline SomeStruct& SomeStruct::operator=(const SomeStruct& other) {
+                        if (this != &other) {
+                            __crubit_internal::...(this, &other);
+                        }
+                        return *this;
                     }
                 }
             );
@@ -3036,9 +12300,24 @@ pub mod tests {
                 impl_details.rs,
                 quote! {
                     #[no_mangle]
-                    extern "C"
-                    fn ...(x: f64, y: f64) -> f64 {
-                        ::rust_out::add(x, y)
+                    extern "C" fn ...(
+                        __self: &mut ::core::mem::MaybeUninit<::rust_out::SomeStruct>
+                    ) {
+                        __self.write(::core::default::Default::default());
+                    }
+                    #[no_mangle]
+                    extern "C" fn ...(
+                        __self: &mut ::core::mem::MaybeUninit<::rust_out::SomeStruct>,
+                        __other: &::rust_out::SomeStruct
+                    ) {
+                        __self.write(::core::clone::Clone::clone(__other));
+                    }
+                    #[no_mangle]
+                    extern "C" fn ...(
+                        __self: &mut ::rust_out::SomeStruct,
+                        __other: &::rust_out::SomeStruct
+                    ) {
+                        ::core::clone::Clone::clone_from(__self, __other);
                     }
                 }
             );
@@ -3046,98 +12325,124 @@ pub mod tests {
     }
 
     #[test]
-    fn test_format_item_unsupported_fn_variadic() {
+    fn test_format_item_struct_with_clone_trait_and_adt_field() {
         let test_src = r#"
-                #![feature(c_variadic)]
-
-                #[no_mangle]
-                pub unsafe extern "C" fn variadic_function(_fmt: *const u8, ...) {}
-            "#;
-        test_format_item(test_src, "variadic_function", |result| {
-            // TODO(b/254097223): Add support for variadic functions.
-            let err = result.unwrap_err();
-            assert_eq!(err, "C variadic functions are not supported (b/254097223)");
-        });
-    }
+                #[derive(Clone, Copy)]
+                pub struct Inner(i32);
 
-    #[test]
-    fn test_format_item_fn_params() {
-        let test_src = r#"
-                #[allow(unused_variables)]
-                #[no_mangle]
-                pub extern "C" fn foo(b: bool, f: f64) {}
-            "#;
-        test_format_item(test_src, "foo", |result| {
-            let result = result.unwrap();
-            let main_api = get_main_api_snippet(&result);
-            assert!(main_api.prereqs.is_empty());
-            assert_cc_matches!(
-                main_api.tokens,
-                quote! {
-                    ...
-                    extern "C" void foo(bool b, double f);
+                #[derive(Clone, Default)]
+                pub struct Outer {
+                    pub inner: Inner,
                 }
-            );
-        });
-    }
 
-    #[test]
-    fn test_format_item_fn_param_name_reserved_keyword() {
-        let test_src = r#"
-                #[allow(unused_variables)]
-                #[no_mangle]
-                pub extern "C" fn some_function(reinterpret_cast: f64) {}
+                impl Default for Inner {
+                    fn default() -> Self { Self(123) }
+                }
             "#;
-        test_format_item(test_src, "some_function", |result| {
+        test_format_item(test_src, "Outer", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
-            assert!(main_api.prereqs.is_empty());
+            // User-provided constructors would default-initialize the `inner` field (and
+            // the default constructor of `Inner` is not trivial), so `Clone` and `Default`
+            // are not used in this case.
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
                     ...
-                    extern "C" void some_function(double __param_0);
+                    struct alignas(4) Outer final {
+                        public:
+                            Outer() = delete;
+                            Outer(const Outer&) = delete;
+                            ...
+                    };
                 }
             );
         });
     }
 
     #[test]
-    fn test_format_item_fn_with_multiple_anonymous_parameter_names() {
+    fn test_format_item_struct_with_comparison_traits() {
         let test_src = r#"
-                pub fn foo(_: f64, _: f64) {}
+                #[derive(PartialEq, Eq, PartialOrd, Ord)]
+                pub struct SomeStruct {
+                    pub x: i32,
+                }
             "#;
-        test_format_item(test_src, "foo", |result| {
+        test_format_item(test_src, "SomeStruct", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
             let impl_details = get_impl_details_snippet(&result);
-            assert!(main_api.prereqs.is_empty());
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
-                    inline void foo(double __param_0, double __param_1);
+                    ...
+                    struct alignas(4) SomeStruct final {
+                        public:
+                            ...
+                            ~SomeStruct() = default;
+                            ...
+                            bool operator==(const SomeStruct& other) const;
+                            bool operator!=(const SomeStruct& other) const;
+                            __HASH_TOKEN__ if __cplusplus > 201703L __NEWLINE__
+                                std::weak_ordering operator<=>(const SomeStruct& other) const;
+                            __HASH_TOKEN__ else __NEWLINE__
+                                bool operator<(const SomeStruct& other) const;
+                                bool operator<=(const SomeStruct& other) const;
+                                bool operator>(const SomeStruct& other) const;
+                                bool operator>=(const SomeStruct& other) const;
+                            __HASH_TOKEN__ endif __NEWLINE__
+                        ...
+                    };
                 }
             );
-            assert!(impl_details.cc.prereqs.is_empty());
             assert_cc_matches!(
                 impl_details.cc.tokens,
                 quote! {
                     namespace __crubit_internal {
-                        extern "C" void ...(
-                            double __param_0, double __param_1);
+                        extern "C" bool ...(
+                            const SomeStruct* __self, const SomeStruct* __other);
+                        extern "C" std::int8_t ...(
+                            const SomeStruct* __self, const SomeStruct* __other);
                     }
-                    ...
-                    inline void foo(double __param_0, double __param_1) {
-                        return __crubit_internal::...(__param_0, __param_1);
+                    inline bool SomeStruct::operator==(const SomeStruct& other) const {
+                        return __crubit_internal::...(this, &other);
                     }
+                    inline bool SomeStruct::operator!=(const SomeStruct& other) const {
+                        return !(*this == other);
+                    }
+                    __HASH_TOKEN__ if __cplusplus > 201703L __NEWLINE__
+                        inline std::weak_ordering SomeStruct::operator<=>(
+                                const SomeStruct& other) const {
+                            std::int8_t result =
+                                __crubit_internal::...(this, &other);
+                            if (result < 0) return std::weak_ordering::less;
+                            if (result > 0) return std::weak_ordering::greater;
+                            return std::weak_ordering::equivalent;
+                        }
+                    __HASH_TOKEN__ else __NEWLINE__
+                        inline bool SomeStruct::operator<(const SomeStruct& other) const {
+                            return __crubit_internal::...(this, &other) == -1;
+                        }
+                        ...
+                    __HASH_TOKEN__ endif __NEWLINE__
                 }
             );
             assert_rs_matches!(
                 impl_details.rs,
                 quote! {
                     #[no_mangle]
-                    extern "C" fn ...(__param_0: f64, __param_1: f64) -> () {
-                        ::rust_out::foo(__param_0, __param_1)
+                    extern "C" fn ...(
+                        __self: &::rust_out::SomeStruct,
+                        __other: &::rust_out::SomeStruct
+                    ) -> bool {
+                        ::core::cmp::PartialEq::eq(__self, __other)
+                    }
+                    #[no_mangle]
+                    extern "C" fn ...(
+                        __self: &::rust_out::SomeStruct,
+                        __other: &::rust_out::SomeStruct
+                    ) -> i8 {
+                        ::core::cmp::Ord::cmp(__self, __other) as i8
                     }
                 }
             );
@@ -3145,38 +12450,38 @@ pub mod tests {
     }
 
     #[test]
-    fn test_format_item_fn_with_destructuring_parameter_name() {
+    fn test_format_item_struct_with_partial_ord_trait_only() {
         let test_src = r#"
-                pub struct S {
-                    pub f1: i32,
-                    pub f2: i32,
+                #[derive(PartialEq, PartialOrd)]
+                pub struct SomeStruct {
+                    pub x: f32,
                 }
-
-                // This test mostly focuses on the weird parameter "name" below.
-                // See also
-                // https://doc.rust-lang.org/reference/items/functions.html#function-parameters
-                // which points out that function parameters are just irrefutable patterns.
-                pub fn func(S{f1, f2}: S) -> i32 { f1 + f2 }
             "#;
-        test_format_item(test_src, "func", |result| {
+        test_format_item(test_src, "SomeStruct", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
             let impl_details = get_impl_details_snippet(&result);
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
-                    inline std::int32_t func(::rust_out::S __param_0);
+                    ...
+                    __HASH_TOKEN__ if __cplusplus > 201703L __NEWLINE__
+                        std::partial_ordering operator<=>(const SomeStruct& other) const;
+                    __HASH_TOKEN__ else __NEWLINE__
+                    ...
                 }
             );
             assert_cc_matches!(
                 impl_details.cc.tokens,
                 quote! {
-                    namespace __crubit_internal {
-                        extern "C" std::int32_t ...(::rust_out::S* __param_0);
-                    }
-                    ...
-                    inline std::int32_t func(::rust_out::S __param_0) {
-                        return __crubit_internal::...(&__param_0);
+                    inline std::partial_ordering SomeStruct::operator<=>(
+                            const SomeStruct& other) const {
+                        std::int8_t result =
+                            __crubit_internal::...(this, &other);
+                        if (result == 2) return std::partial_ordering::unordered;
+                        if (result < 0) return std::partial_ordering::less;
+                        if (result > 0) return std::partial_ordering::greater;
+                        return std::partial_ordering::equivalent;
                     }
                 }
             );
@@ -3185,9 +12490,13 @@ pub mod tests {
                 quote! {
                     #[no_mangle]
                     extern "C" fn ...(
-                        __param_0: &mut ::core::mem::MaybeUninit<::rust_out::S>
-                    ) -> i32 {
-                        ::rust_out::func(unsafe {__param_0.assume_init_read() })
+                        __self: &::rust_out::SomeStruct,
+                        __other: &::rust_out::SomeStruct
+                    ) -> i8 {
+                        match ::core::cmp::PartialOrd::partial_cmp(__self, __other) {
+                            Some(ordering) => ordering as i8,
+                            None => 2,
+                        }
                     }
                 }
             );
@@ -3195,1169 +12504,1681 @@ pub mod tests {
     }
 
     #[test]
-    fn test_format_item_unsupported_fn_param_type() {
-        let test_src = r#"
-                pub fn foo(_param: (i32, i32)) {}
-            "#;
-        test_format_item(test_src, "foo", |result| {
-            let err = result.unwrap_err();
-            assert_eq!(err, "Error handling parameter #0: \
-                             Tuples are not supported yet: (i32, i32) (b/254099023)");
-        });
-    }
-
-    #[test]
-    fn test_format_item_unsupported_fn_param_type_unit() {
-        let test_src = r#"
-                #[no_mangle]
-                pub fn fn_with_params(_param: ()) {}
-            "#;
-        test_format_item(test_src, "fn_with_params", |result| {
-            let err = result.unwrap_err();
-            assert_eq!(err, "Error handling parameter #0: \
-                             `()` / `void` is only supported as a return type (b/254507801)");
-        });
-    }
-
-    #[test]
-    fn test_format_item_unsupported_fn_param_type_never() {
+    fn test_format_item_struct_without_comparison_traits() {
         let test_src = r#"
-                #![feature(never_type)]
-
-                #[no_mangle]
-                pub extern "C" fn fn_with_params(_param: !) {}
+                pub struct SomeStruct {
+                    pub x: i32,
+                }
             "#;
-        test_format_item(test_src, "fn_with_params", |result| {
-            let err = result.unwrap_err();
-            assert_eq!(
-                err,
-                "Error handling parameter #0: \
-                 The never type `!` is only supported as a return type (b/254507801)"
-            );
+        test_format_item(test_src, "SomeStruct", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            assert_cc_not_matches!(main_api.tokens, quote! { operator== });
+            assert_cc_not_matches!(main_api.tokens, quote! { operator<=> });
         });
     }
 
-    /// This is a test for a regular struct - a struct with named fields.
-    /// https://doc.rust-lang.org/reference/items/structs.html refers to this kind of struct as
-    /// `StructStruct` or "nominal struct type".
     #[test]
-    fn test_format_item_struct_with_fields() {
+    fn test_format_item_struct_with_display_and_debug_traits() {
         let test_src = r#"
+                #[derive(Debug)]
                 pub struct SomeStruct {
                     pub x: i32,
-                    pub y: i32,
                 }
 
-                const _: () = assert!(std::mem::size_of::<SomeStruct>() == 8);
-                const _: () = assert!(std::mem::align_of::<SomeStruct>() == 4);
+                impl std::fmt::Display for SomeStruct {
+                    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                        write!(f, "x = {}", self.x)
+                    }
+                }
             "#;
         test_format_item(test_src, "SomeStruct", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
             let impl_details = get_impl_details_snippet(&result);
-            assert!(!main_api.prereqs.is_empty());
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
                     ...
                     struct alignas(4) SomeStruct final {
                         public:
-                            // In this test there is no `Default` implementation.
-                            SomeStruct() = delete;
-
-                            // In this test there is no `Copy` implementation / derive.
-                            SomeStruct(const SomeStruct&) = delete;
-
-                            // All Rust types are trivially-movable.
-                            SomeStruct(SomeStruct&&) = default;
-
-                            // Assignment operators are disabled for now.
-                            SomeStruct& operator=(const SomeStruct&) = delete;
-                            SomeStruct& operator=(SomeStruct&&) = delete;
-
-                            // In this test there is no custom `Drop`, so C++ can also
-                            // just use the `default` destructor.
+                            ...
                             ~SomeStruct() = default;
-                        private:
-                            ...  std::int32_t x;
-                            ...  std::int32_t y;
-                            inline static void __crubit_field_offset_assertions();
+                            ...
+                            std::string ToString() const;
+                            std::string DebugString() const;
+                        ...
                     };
                 }
             );
             assert_cc_matches!(
                 impl_details.cc.tokens,
                 quote! {
-                    static_assert(sizeof(SomeStruct) == 8, ...);
-                    static_assert(alignof(SomeStruct) == 4, ...);
-                    inline void SomeStruct::__crubit_field_offset_assertions() {
-                      static_assert(0 == offsetof(SomeStruct, x));
-                      static_assert(4 == offsetof(SomeStruct, y));
+                    namespace __crubit_internal {
+                        extern "C" void ...(const SomeStruct* __self, std::string* __out,
+                                            crubit::internal::AppendToStringFn __append);
+                        extern "C" void ...(const SomeStruct* __self, std::string* __out,
+                                            crubit::internal::AppendToStringFn __append);
+                    }
+                    inline std::string SomeStruct::ToString() const {
+                        std::string result;
+                        __crubit_internal::...(this, &result, &crubit::internal::AppendToString);
+                        return result;
+                    }
+                    inline std::string SomeStruct::DebugString() const {
+                        ...
+                    }
+                    inline std::ostream& operator<<(std::ostream& os, const SomeStruct& value) {
+                        return os << value.ToString();
                     }
                 }
             );
             assert_rs_matches!(
                 impl_details.rs,
                 quote! {
-                    const _: () = assert!(::std::mem::size_of::<::rust_out::SomeStruct>() == 8);
-                    const _: () = assert!(::std::mem::align_of::<::rust_out::SomeStruct>() == 4);
-                    const _: () = assert!( memoffset::offset_of!(::rust_out::SomeStruct, x) == 0);
-                    const _: () = assert!( memoffset::offset_of!(::rust_out::SomeStruct, y) == 4);
+                    #[no_mangle]
+                    extern "C" fn ...(
+                        __self: &::rust_out::SomeStruct,
+                        __out: *mut ::core::ffi::c_void,
+                        __append: extern "C" fn(*mut ::core::ffi::c_void, *const u8, usize)
+                    ) {
+                        __crubit_write_fmt(__out, __append, ::core::format_args!("{}", __self));
+                    }
+                    #[no_mangle]
+                    extern "C" fn ...(
+                        ...
+                    ) {
+                        __crubit_write_fmt(__out, __append, ::core::format_args!("{:?}", __self));
+                    }
                 }
             );
         });
     }
 
-    /// This is a test for `TupleStruct` or "tuple struct" - for more details
-    /// please refer to https://doc.rust-lang.org/reference/items/structs.html
     #[test]
-    fn test_format_item_struct_with_tuple() {
+    fn test_format_item_struct_with_debug_trait_only() {
         let test_src = r#"
-                pub struct TupleStruct(pub i32, pub i32);
-                const _: () = assert!(std::mem::size_of::<TupleStruct>() == 8);
-                const _: () = assert!(std::mem::align_of::<TupleStruct>() == 4);
+                #[derive(Debug)]
+                pub struct SomeStruct {
+                    pub x: i32,
+                }
             "#;
-        test_format_item(test_src, "TupleStruct", |result| {
+        test_format_item(test_src, "SomeStruct", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
             let impl_details = get_impl_details_snippet(&result);
-            assert!(!main_api.prereqs.is_empty());
-            assert_cc_matches!(
-                main_api.tokens,
-                quote! {
-                    ...
-                    struct alignas(4) TupleStruct final {
-                        public:
-                            // In this test there is no `Default` implementation.
-                            TupleStruct() = delete;
-
-                            // In this test there is no `Copy` implementation / derive.
-                            TupleStruct(const TupleStruct&) = delete;
-
-                            // All Rust types are trivially-movable.
-                            TupleStruct(TupleStruct&&) = default;
-
-                            // Assignment operators are disabled for now.
-                            TupleStruct& operator=(const TupleStruct&) = delete;
-                            TupleStruct& operator=(TupleStruct&&) = delete;
-
-                            // In this test there is no custom `Drop`, so C++ can also
-                            // just use the `default` destructor.
-                            ~TupleStruct() = default;
-                        private:
-                            ...  std::int32_t __field0;
-                            ...  std::int32_t __field1;
-                            inline static void __crubit_field_offset_assertions();
-                    };
-                }
-            );
+            assert_cc_not_matches!(main_api.tokens, quote! { ToString });
             assert_cc_matches!(
                 impl_details.cc.tokens,
                 quote! {
-                    static_assert(sizeof(TupleStruct) == 8, ...);
-                    static_assert(alignof(TupleStruct) == 4, ...);
-                    inline void TupleStruct::__crubit_field_offset_assertions() {
-                      static_assert(0 == offsetof(TupleStruct, __field0));
-                      static_assert(4 == offsetof(TupleStruct, __field1));
+                    inline std::ostream& operator<<(std::ostream& os, const SomeStruct& value) {
+                        return os << value.DebugString();
                     }
                 }
             );
-            assert_rs_matches!(
-                impl_details.rs,
-                quote! {
-                    const _: () = assert!(::std::mem::size_of::<::rust_out::TupleStruct>() == 8);
-                    const _: () = assert!(::std::mem::align_of::<::rust_out::TupleStruct>() == 4);
-                    const _: () = assert!( memoffset::offset_of!(::rust_out::TupleStruct, 0) == 0);
-                    const _: () = assert!( memoffset::offset_of!(::rust_out::TupleStruct, 1) == 4);
-                }
-            );
         });
     }
 
-    /// This test the scenario where Rust lays out field in a different order
-    /// than the source order.
     #[test]
-    fn test_format_item_struct_with_reordered_field_offsets() {
+    fn test_format_item_struct_with_custom_drop_impl() {
         let test_src = r#"
-                pub struct SomeStruct {
-                    pub field1: i16,
-                    pub field2: i32,
-                    pub field3: i16,
+                pub struct StructWithCustomDropImpl {
+                    pub x: i32,
+                    pub y: i32,
                 }
 
-                const _: () = assert!(std::mem::size_of::<SomeStruct>() == 8);
-                const _: () = assert!(std::mem::align_of::<SomeStruct>() == 4);
+                impl Drop for StructWithCustomDropImpl {
+                    fn drop(&mut self) {}
+                }
             "#;
-        test_format_item(test_src, "SomeStruct", |result| {
+        test_format_item(test_src, "StructWithCustomDropImpl", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
             let impl_details = get_impl_details_snippet(&result);
-            assert!(!main_api.prereqs.is_empty());
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
                     ...
-                    struct alignas(4) SomeStruct final {
-                        ...
+                    struct alignas(4) StructWithCustomDropImpl final {
+                        public:
+                            StructWithCustomDropImpl() = delete;
+                            StructWithCustomDropImpl(const StructWithCustomDropImpl&) = delete;
+
+                            // In this test there is no `Default` implementation, so the
+                            // moved-from object can't be reset before dropping it.
+                            StructWithCustomDropImpl(StructWithCustomDropImpl&&) = delete;
+
+                            StructWithCustomDropImpl& operator=(
+                                const StructWithCustomDropImpl&) = delete;
+                            StructWithCustomDropImpl& operator=(
+                                StructWithCustomDropImpl&&) = delete;
+
+                            // The destructor is defined out-of-line (see `impl_details` below).
+                            ~StructWithCustomDropImpl();
                         private:
-                            // The particular order below is not guaranteed,
-                            // so we may need to adjust this test assertion
-                            // (if Rust changes how it lays out the fields).
-                            ...  std::int32_t field2;
-                            ...  std::int16_t field1;
-                            ...  std::int16_t field3;
-                            inline static void __crubit_field_offset_assertions();
+                            ...  std::int32_t x;
+                            ...  std::int32_t y;
+                            ...
                     };
                 }
             );
             assert_cc_matches!(
                 impl_details.cc.tokens,
                 quote! {
-                    static_assert(sizeof(SomeStruct) == 8, ...);
-                    static_assert(alignof(SomeStruct) == 4, ...);
-                    inline void SomeStruct::__crubit_field_offset_assertions() {
-                      static_assert(0 == offsetof(SomeStruct, field2));
-                      static_assert(4 == offsetof(SomeStruct, field1));
-                      static_assert(6 == offsetof(SomeStruct, field3));
+                    ...
+                    namespace __crubit_internal {
+                        extern "C" void ...(StructWithCustomDropImpl* __self);
+                    }
+                    inline StructWithCustomDropImpl::~StructWithCustomDropImpl() {
+                        __crubit_internal::...(this);
                     }
                 }
             );
+            assert_cc_not_matches!(impl_details.cc.tokens, quote! { __other });
             assert_rs_matches!(
                 impl_details.rs,
                 quote! {
-                    const _: () = assert!(::std::mem::size_of::<::rust_out::SomeStruct>() == 8);
-                    const _: () = assert!(::std::mem::align_of::<::rust_out::SomeStruct>() == 4);
-                    const _: () = assert!( memoffset::offset_of!(::rust_out::SomeStruct, field2)
-                                           == 0);
-                    const _: () = assert!( memoffset::offset_of!(::rust_out::SomeStruct, field1)
-                                           == 4);
-                    const _: () = assert!( memoffset::offset_of!(::rust_out::SomeStruct, field3)
-                                           == 6);
+                    #[no_mangle]
+                    extern "C" fn ...(
+                        __self: &mut ::core::mem::MaybeUninit<::rust_out::StructWithCustomDropImpl>
+                    ) {
+                        unsafe { ::core::ptr::drop_in_place(__self.as_mut_ptr()) };
+                    }
                 }
             );
+            assert_rs_not_matches!(impl_details.rs, quote! { ::core::mem::take });
         });
     }
 
     #[test]
-    fn test_format_item_struct_with_packed_layout() {
+    fn test_format_item_struct_with_custom_drop_glue_and_default() {
         let test_src = r#"
-                #[repr(packed(1))]
-                pub struct SomeStruct {
-                    pub field1: u16,
-                    pub field2: u32,
+                #![allow(dead_code)]
+
+                // `i32` is present to avoid hitting the ZST checks related to (b/258259459)
+                #[derive(Default)]
+                pub struct StructWithCustomDropImpl(i32);
+
+                impl Drop for StructWithCustomDropImpl {
+                    fn drop(&mut self) {
+                        println!("dropping!");
+                    }
+                }
+
+                #[derive(Default)]
+                pub struct StructRequiringCustomDropGlue {
+                    field: StructWithCustomDropImpl,
                 }
-                const _: () = assert!(::std::mem::size_of::<SomeStruct>() == 6);
-                const _: () = assert!(::std::mem::align_of::<SomeStruct>() == 1);
             "#;
-        test_format_item(test_src, "SomeStruct", |result| {
+        test_format_item(test_src, "StructRequiringCustomDropGlue", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
             let impl_details = get_impl_details_snippet(&result);
-            assert!(!main_api.prereqs.is_empty());
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
                     ...
-                    struct alignas(1) __attribute__((packed)) SomeStruct final {
-                        ...
-                        std::uint16_t field1;
-                        std::uint32_t field2;
-                        inline static void __crubit_field_offset_assertions();
+                    struct alignas(4) StructRequiringCustomDropGlue final {
+                        public:
+                            ...
+                            // `Default` is implemented, so the move constructor can be
+                            // provided (it is defined out-of-line - see `impl_details`).
+                            StructRequiringCustomDropGlue(StructRequiringCustomDropGlue&&);
+                            ...
+                            ~StructRequiringCustomDropGlue();
+                        private:
+                            // The field is dropped by the Rust drop glue of the whole struct
+                            // and therefore its type can't be exposed in C++ (where it would
+                            // have its own non-trivial destructor).
+                            __COMMENT__ "Field type has been replaced with a blob of bytes: \
+                                         Fields of ADT types are not supported yet in types \
+                                         that need drop glue (b/258251148)"
+                            unsigned char field[4];
+                            ...
                     };
                 }
             );
             assert_cc_matches!(
                 impl_details.cc.tokens,
                 quote! {
-                    static_assert(sizeof(SomeStruct) == 6, ...);
-                    static_assert(alignof(SomeStruct) == 1, ...);
-                    inline void SomeStruct::__crubit_field_offset_assertions() {
-                      static_assert(0 == offsetof(SomeStruct, field1));
-                      static_assert(2 == offsetof(SomeStruct, field2));
+                    ...
+                    namespace __crubit_internal {
+                        extern "C" void ...(StructRequiringCustomDropGlue* __self);
+                        extern "C" void ...(StructRequiringCustomDropGlue* __self,
+                                            StructRequiringCustomDropGlue* __other);
+                    }
+                    inline StructRequiringCustomDropGlue::~StructRequiringCustomDropGlue() {
+                        __crubit_internal::...(this);
+                    }
+                    inline StructRequiringCustomDropGlue::StructRequiringCustomDropGlue(
+                            StructRequiringCustomDropGlue&& other) {
+                        __crubit_internal::...(this, &other);
                     }
                 }
             );
             assert_rs_matches!(
                 impl_details.rs,
                 quote! {
-                    const _: () = assert!(::std::mem::size_of::<::rust_out::SomeStruct>() == 6);
-                    const _: () = assert!(::std::mem::align_of::<::rust_out::SomeStruct>() == 1);
-                    const _: () = assert!( memoffset::offset_of!(::rust_out::SomeStruct, field1)
-                                           == 0);
-                    const _: () = assert!( memoffset::offset_of!(::rust_out::SomeStruct, field2)
-                                           == 2);
+                    #[no_mangle]
+                    extern "C" fn ...(
+                        __self: &mut ::core::mem::MaybeUninit<
+                            ::rust_out::StructRequiringCustomDropGlue>,
+                        __other: &mut ::rust_out::StructRequiringCustomDropGlue
+                    ) {
+                        __self.write(::core::mem::take(__other));
+                    }
                 }
             );
         });
     }
 
     #[test]
-    fn test_format_item_struct_with_explicit_padding_in_generated_code() {
+    fn test_format_item_fn_rust_abi_with_param_needing_drop_glue() {
         let test_src = r#"
-                pub struct SomeStruct {
-                    pub f1: u8,
-                    pub f2: u32,
+                #[derive(Default)]
+                pub struct S(i32);
+
+                impl Drop for S {
+                    fn drop(&mut self) {}
                 }
-                const _: () = assert!(::std::mem::size_of::<SomeStruct>() == 8);
-                const _: () = assert!(::std::mem::align_of::<SomeStruct>() == 4);
+
+                pub fn foo(_s: S) {}
             "#;
-        test_format_item(test_src, "SomeStruct", |result| {
+        test_format_item(test_src, "foo", |result| {
             let result = result.unwrap();
-            let main_api = get_main_api_snippet(&result);
             let impl_details = get_impl_details_snippet(&result);
-            assert!(!main_api.prereqs.is_empty());
-            assert_cc_matches!(
-                main_api.tokens,
-                quote! {
-                    ...
-                    struct alignas(4) SomeStruct final {
-                        ...
-                        std::uint32_t f2;
-                        std::uint8_t f1;
-                        unsigned char __padding0[3];
-                        inline static void __crubit_field_offset_assertions();
-                    };
-                }
-            );
             assert_cc_matches!(
                 impl_details.cc.tokens,
                 quote! {
-                    static_assert(sizeof(SomeStruct) == 8, ...);
-                    static_assert(alignof(SomeStruct) == 4, ...);
-                    inline void SomeStruct::__crubit_field_offset_assertions() {
-                      static_assert(0 == offsetof(SomeStruct, f2));
-                      static_assert(4 == offsetof(SomeStruct, f1));
+                    namespace __crubit_internal {
+                        extern "C" void ...(::rust_out::S* _s);
+                    }
+                    inline void foo(::rust_out::S _s) {
+                        return __crubit_internal::...(&_s);
                     }
                 }
             );
             assert_rs_matches!(
                 impl_details.rs,
                 quote! {
-                    const _: () = assert!(::std::mem::size_of::<::rust_out::SomeStruct>() == 8);
-                    const _: () = assert!(::std::mem::align_of::<::rust_out::SomeStruct>() == 4);
-                    const _: () = assert!( memoffset::offset_of!(::rust_out::SomeStruct, f2) == 0);
-                    const _: () = assert!( memoffset::offset_of!(::rust_out::SomeStruct, f1) == 4);
+                    #[no_mangle]
+                    extern "C" fn ...(_s: &mut ::rust_out::S) -> () {
+                        ::rust_out::foo(::core::mem::take(_s))
+                    }
                 }
             );
         });
     }
 
     #[test]
-    fn test_format_item_static_method() {
+    fn test_format_item_unsupported_fn_returning_immovable_type_needing_drop_glue() {
         let test_src = r#"
-                /// No-op `f32` placeholder is used, because ZSTs are not supported
-                /// (b/258259459).
-                pub struct Math(f32);
+                pub struct S(i32);
 
-                impl Math {
-                    pub fn add_i32(x: f32, y: f32) -> f32 {
-                        x + y
-                    }
+                impl Drop for S {
+                    fn drop(&mut self) {}
                 }
+
+                pub fn foo() -> S { S(123) }
             "#;
-        test_format_item(test_src, "Math", |result| {
+        test_format_item(test_src, "foo", |result| {
+            let err = result.unwrap_err();
+            assert_eq!(
+                err,
+                "`S` needs drop glue, but doesn't implement `Default` - this means that \
+                 values of this type can't be moved in C++ (b/258251148)"
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_unsupported_fn_extern_c_with_param_needing_drop_glue() {
+        let test_src = r#"
+                #[derive(Default)]
+                #[repr(C)]
+                pub struct S(i32);
+
+                impl Drop for S {
+                    fn drop(&mut self) {}
+                }
+
+                #[no_mangle]
+                pub extern "C" fn foo(_s: S) {}
+            "#;
+        test_format_item(test_src, "foo", |result| {
+            let err = result.unwrap_err();
+            assert_eq!(
+                err,
+                "Types that need drop glue can't be passed by value to `extern \"C\"` \
+                 functions (b/258251148)"
+            );
+        });
+    }
+
+    /// This test covers how ZSTs (zero-sized-types) are handled.
+    /// https://doc.rust-lang.org/reference/items/structs.html refers to this kind of struct as a
+    /// "unit-like struct".
+    #[test]
+    fn test_format_item_unsupported_struct_zero_sized_type_with_no_fields() {
+        let test_src = r#"
+                pub struct ZeroSizedType1;
+                pub struct ZeroSizedType2();
+                pub struct ZeroSizedType3{}
+            "#;
+        for name in ["ZeroSizedType1", "ZeroSizedType2", "ZeroSizedType3"] {
+            test_format_item(test_src, name, |result| {
+                let err = result.unwrap_err();
+                assert_eq!(err, "Zero-sized types (ZSTs) are not supported (b/258259459)");
+            });
+        }
+    }
+
+    #[test]
+    fn test_format_item_unsupported_struct_with_only_zero_sized_type_fields() {
+        let test_src = r#"
+                pub struct ZeroSizedType;
+                pub struct SomeStruct {
+                    pub zst1: ZeroSizedType,
+                    pub zst2: ZeroSizedType,
+                }
+            "#;
+        test_format_item(test_src, "SomeStruct", |result| {
+            let err = result.unwrap_err();
+            assert_eq!(err, "Zero-sized types (ZSTs) are not supported (b/258259459)",);
+        });
+    }
+
+    /// This is a test for an enum that only has `EnumItemDiscriminant` items
+    /// (and doesn't have `EnumItemTuple` or `EnumItemStruct` items).  See
+    /// also https://doc.rust-lang.org/reference/items/enumerations.html
+    #[test]
+    fn test_format_item_enum_with_only_discriminant_items() {
+        let test_src = r#"
+                pub enum SomeEnum {
+                    Red,
+                    Green = 123,
+                    Blue,
+                }
+
+                const _: () = assert!(std::mem::size_of::<SomeEnum>() == 1);
+                const _: () = assert!(std::mem::align_of::<SomeEnum>() == 1);
+            "#;
+        test_format_item(test_src, "SomeEnum", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
             let impl_details = get_impl_details_snippet(&result);
-            assert!(main_api.prereqs.is_empty());
+            assert_eq!(main_api.prereqs.includes.len(), 1);
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
-                    ...
-                    struct ... Math final {
-                        ...
-                        public:
-                          ...
-                          static inline float add_i32(float x, float y);
-                        ...
+                    enum class SomeEnum : std::uint8_t {
+                        Red = 0,
+                        Green = 123,
+                        Blue = 124
                     };
                 }
             );
             assert_cc_matches!(
                 impl_details.cc.tokens,
                 quote! {
-                    namespace __crubit_internal {
-                    extern "C" float ... (float x, float y);
-                    }
-                    inline float Math::add_i32(float x, float y) {
-                      return __crubit_internal::...(x, y);
-                    }
+                    static_assert(sizeof(SomeEnum) == 1, ...);
+                    static_assert(alignof(SomeEnum) == 1, ...);
                 }
             );
             assert_rs_matches!(
                 impl_details.rs,
                 quote! {
-                    #[no_mangle]
-                    extern "C" fn ...(x: f32, y: f32) -> f32 {
-                        ::rust_out::Math::add_i32(x, y)
-                    }
+                    const _: () = assert!(::std::mem::size_of::<::rust_out::SomeEnum>() == 1);
+                    const _: () = assert!(::std::mem::align_of::<::rust_out::SomeEnum>() == 1);
                 }
             );
         });
     }
 
     #[test]
-    fn test_format_item_static_method_with_generic_type_parameters() {
+    fn test_format_item_enum_with_negative_discriminants() {
         let test_src = r#"
-                /// No-op `f32` placeholder is used, because ZSTs are not supported
-                /// (b/258259459).
-                pub struct SomeStruct(f32);
-
-                impl SomeStruct {
-                    // To make this testcase distinct / non-overlapping wrt
-                    // test_format_item_static_method_with_generic_lifetime_parameters
-                    // `t` is taken by value below.
-                    pub fn generic_method<T: Clone>(t: T) -> T {
-                        t.clone()
-                    }
+                #[repr(i16)]
+                pub enum SomeEnum {
+                    Negative = -1,
+                    Zero,
+                    Big = 1000,
                 }
             "#;
-        test_format_item(test_src, "SomeStruct", |result| {
+        test_format_item(test_src, "SomeEnum", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
-            let impl_details = get_impl_details_snippet(&result);
-            assert!(main_api.prereqs.is_empty());
-            let unsupported_msg = "Error generating bindings for `SomeStruct::generic_method` \
-                                   defined at <crubit_unittests.rs>;l=10: \
-                                   Generic functions are not supported yet (b/259749023)";
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
-                    ...
-                    struct ... SomeStruct final {
-                        ...
-                        __COMMENT__ #unsupported_msg
-                        ...
+                    enum class SomeEnum : std::int16_t {
+                        Negative = -1,
+                        Zero = 0,
+                        Big = 1000
                     };
-                    ...
                 }
             );
-            assert_cc_not_matches!(impl_details.cc.tokens, quote! { SomeStruct::generic_method },);
-            assert_rs_not_matches!(impl_details.rs, quote! { generic_method },);
         });
     }
 
     #[test]
-    fn test_format_item_static_method_with_generic_lifetime_parameters() {
+    fn test_format_item_enum_class_ub_warning() {
         let test_src = r#"
-                /// No-op `f32` placeholder is used, because ZSTs are not supported
-                /// (b/258259459).
-                pub struct SomeStruct(f32);
-
-                impl SomeStruct {
-                    pub fn fn_taking_reference<'a>(x: &'a i32) -> i32 { *x }
+                pub enum SomeEnum {
+                    A,
+                    B,
                 }
             "#;
-        test_format_item(test_src, "SomeStruct", |result| {
+        test_format_item(test_src, "SomeEnum", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
-            let impl_details = get_impl_details_snippet(&result);
-            assert!(main_api.prereqs.is_empty());
-            let unsupported_msg = "Error generating bindings for `SomeStruct::fn_taking_reference` \
-                                   defined at <crubit_unittests.rs>;l=7: \
-                                   Generic functions are not supported yet (b/259749023)";
+            let warning = "Warning: Unlike a Rust enum, this `enum class` can hold values that \
+                           don't correspond to any of its enumerators (e.g. values created with \
+                           `static_cast`).  Passing such values to Rust is Undefined Behavior.";
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
-                    ...
-                    struct ... SomeStruct final {
-                        ...
-                        __COMMENT__ #unsupported_msg
-                        ...
-                    };
-                    ...
+                    __COMMENT__ #warning
+                    enum class SomeEnum : std::uint8_t { A = 0, B = 1 };
                 }
             );
-            assert_cc_not_matches!(
-                impl_details.cc.tokens,
-                quote! { SomeStruct::fn_taking_reference },
-            );
-            assert_rs_not_matches!(impl_details.rs, quote! { fn_taking_reference },);
         });
     }
 
+    /// A C++ `enum class` can't have member functions, so fieldless enums with
+    /// methods are represented as a C++ struct (just like enums with fields).
     #[test]
-    fn test_format_item_method_taking_self_by_value() {
+    fn test_format_item_fieldless_enum_with_methods() {
         let test_src = r#"
-                pub struct SomeStruct(f32);
+                pub enum SomeEnum {
+                    A,
+                    B,
+                }
 
-                impl SomeStruct {
-                    pub fn into_f32(self) -> f32 {
-                        self.0
+                impl SomeEnum {
+                    pub fn create() -> Self {
+                        SomeEnum::B
+                    }
+
+                    pub fn is_a(&self) -> bool {
+                        matches!(self, SomeEnum::A)
                     }
                 }
             "#;
-        test_format_item(test_src, "SomeStruct", |result| {
+        test_format_item(test_src, "SomeEnum", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
-            let impl_details = get_impl_details_snippet(&result);
-            assert!(main_api.prereqs.is_empty());
-            let unsupported_msg = "Error generating bindings for `SomeStruct::into_f32` \
-                                   defined at <crubit_unittests.rs>;l=5: \
-                                   `self` parameter is not supported yet";
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
-                    ...
-                    struct ... SomeStruct final {
+                    struct ... SomeEnum final {
                         ...
-                        __COMMENT__ #unsupported_msg
+                        static inline ::rust_out::SomeEnum create();
+                        ...
+                        inline bool is_a() const;
                         ...
                     };
-                    ...
                 }
             );
-            assert_cc_not_matches!(impl_details.cc.tokens, quote! { SomeStruct::into_f32 },);
-            assert_rs_not_matches!(impl_details.rs, quote! { into_f32 },);
+            assert_cc_not_matches!(main_api.tokens, quote! { enum class });
         });
     }
 
+    /// Trait `impl`s are exposed as members of the C++ class (see
+    /// `format_trait_impl_items`), so they also prevent representing a fieldless
+    /// enum as an `enum class`.
     #[test]
-    fn test_format_item_method_taking_self_by_const_ref() {
+    fn test_format_item_fieldless_enum_with_trait_impl() {
         let test_src = r#"
-                pub struct SomeStruct(f32);
+                pub trait Describe {
+                    fn describe(&self) -> i32;
+                }
+
+                pub enum SomeEnum {
+                    A,
+                    B,
+                }
+
+                impl Describe for SomeEnum {
+                    fn describe(&self) -> i32 { 42 }
+                }
+            "#;
+        test_format_item(test_src, "SomeEnum", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    struct ... SomeEnum final {
+                        ...
+                        inline std::int32_t describe() const;
+                        ...
+                    };
+                }
+            );
+        });
+    }
 
-                impl SomeStruct {
-                    pub fn get_f32(&self) -> f32 {
-                        self.0
-                    }
+    /// This is a test for an enum with fields and a primitive representation
+    /// (`#[repr(u8)]`) which guarantees the layout of the enum variants.
+    #[test]
+    fn test_format_item_enum_with_primitive_representation() {
+        let test_src = r#"
+                #[repr(u8)]
+                pub enum Shape {
+                    Circle { radius: f32 },
+                    Rectangle(i16, f32),
+                    Empty = 42,
                 }
             "#;
-        test_format_item(test_src, "SomeStruct", |result| {
+        test_format_item(test_src, "Shape", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
             let impl_details = get_impl_details_snippet(&result);
-            assert!(main_api.prereqs.is_empty());
-            let unsupported_msg = "Error generating bindings for `SomeStruct::get_f32` \
-                                   defined at <crubit_unittests.rs>;l=5: \
-                                   Generic functions are not supported yet (b/259749023)";
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
-                    ...
-                    struct ... SomeStruct final {
-                        ...
-                        __COMMENT__ #unsupported_msg
-                        ...
+                    struct alignas(4) Shape final {
+                        public:
+                            ...
+                        public:
+                            enum class Tag : std::uint8_t {
+                                Circle = 0,
+                                Rectangle = 1,
+                                Empty = 42
+                            };
+                            struct CircleVariant final {
+                                Tag __tag;
+                                unsigned char __padding0[3];
+                                float radius;
+                            };
+                            struct RectangleVariant final {
+                                Tag __tag;
+                                unsigned char __padding0[1];
+                                std::int16_t __field0;
+                                float __field1;
+                            };
+                            struct EmptyVariant final {
+                                Tag __tag;
+                            };
+                            Tag tag() const;
+                            bool is_Circle() const;
+                            ...
+                            const CircleVariant& as_Circle() const;
+                            static Shape Circle(float radius);
+                            bool is_Rectangle() const;
+                            ...
+                            const RectangleVariant& as_Rectangle() const;
+                            static Shape Rectangle(std::int16_t __field0, float __field1);
+                            bool is_Empty() const;
+                            static Shape Empty();
+                        private:
+                            explicit Shape(CircleVariant&& __variant);
+                            explicit Shape(RectangleVariant&& __variant);
+                            explicit Shape(EmptyVariant&& __variant);
+                            union {
+                                CircleVariant __variant_Circle;
+                                RectangleVariant __variant_Rectangle;
+                                EmptyVariant __variant_Empty;
+                            };
+                            inline static void __crubit_field_offset_assertions();
                     };
+                }
+            );
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    inline Shape::Tag Shape::tag() const {
+                        return __variant_Circle.__tag;
+                    }
+                    inline bool Shape::is_Circle() const {
+                        return tag() == Tag::Circle;
+                    }
+                    inline const Shape::CircleVariant& Shape::as_Circle() const {
+                        assert(is_Circle());
+                        return __variant_Circle;
+                    }
+                    inline Shape Shape::Circle(float radius) {
+                        return Shape(CircleVariant{ Tag::Circle, {}, std::move(radius) });
+                    }
+                    inline Shape::Shape(CircleVariant&& __variant)
+                        : __variant_Circle(std::move(__variant)) {}
                     ...
+                    inline Shape Shape::Empty() {
+                        return Shape(EmptyVariant{ Tag::Empty });
+                    }
+                    ...
+                }
+            );
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    inline void Shape::__crubit_field_offset_assertions() {
+                        static_assert(4 == offsetof(CircleVariant, radius));
+                        static_assert(0 == offsetof(Shape, __variant_Circle));
+                        static_assert(2 == offsetof(RectangleVariant, __field0));
+                        static_assert(4 == offsetof(RectangleVariant, __field1));
+                        static_assert(0 == offsetof(Shape, __variant_Rectangle));
+                        static_assert(0 == offsetof(Shape, __variant_Empty));
+                    }
                 }
             );
-            assert_cc_not_matches!(impl_details.cc.tokens, quote! { SomeStruct::get_f32 },);
-            assert_rs_not_matches!(impl_details.rs, quote! { get_f32 },);
         });
     }
 
+    /// This test verifies that `#[repr(C)]` enums with fields use the layout of
+    /// a C tagged union (e.g. a 32-bit tag).
     #[test]
-    fn test_format_item_method_taking_self_by_mutable_ref() {
+    fn test_format_item_enum_with_repr_c() {
         let test_src = r#"
-                pub struct SomeStruct(f32);
-
-                impl SomeStruct {
-                    pub fn set_f32(&mut self, new_value: f32) {
-                        self.0 = new_value;
-                    }
+                #[repr(C)]
+                pub enum SomeEnum {
+                    A(u8),
+                    B,
                 }
             "#;
-        test_format_item(test_src, "SomeStruct", |result| {
+        test_format_item(test_src, "SomeEnum", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
-            let impl_details = get_impl_details_snippet(&result);
-            assert!(main_api.prereqs.is_empty());
-            let unsupported_msg = "Error generating bindings for `SomeStruct::set_f32` \
-                                   defined at <crubit_unittests.rs>;l=5: \
-                                   Generic functions are not supported yet (b/259749023)";
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
-                    ...
-                    struct ... SomeStruct final {
-                        ...
-                        __COMMENT__ #unsupported_msg
-                        ...
+                    enum class Tag : std::uint32_t { A = 0, B = 1 };
+                    struct AVariant final {
+                        Tag __tag;
+                        std::uint8_t __field0;
                     };
-                    ...
                 }
             );
-            assert_cc_not_matches!(impl_details.cc.tokens, quote! { SomeStruct::set_f32 },);
-            assert_rs_not_matches!(impl_details.rs, quote! { set_f32 },);
         });
     }
 
     #[test]
-    fn test_format_item_unsupported_struct_with_name_that_is_reserved_keyword() {
+    fn test_format_item_enum_with_ambiguous_member_names() {
         let test_src = r#"
-                #[allow(non_camel_case_types)]
-                pub struct reinterpret_cast {
-                    pub x: i32,
-                    pub y: i32,
+                #[repr(u8)]
+                pub enum SomeEnum {
+                    A(i32),
+                    AVariant,
                 }
             "#;
-        test_format_item(test_src, "reinterpret_cast", |result| {
-            let err = result.unwrap_err();
-            assert_eq!(
-                err,
-                "Error formatting item name: \
-                             `reinterpret_cast` is a C++ reserved keyword \
-                             and can't be used as a C++ identifier"
+        test_format_item(test_src, "SomeEnum", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let broken_field_msg = "Field type has been replaced with a blob of bytes: \
+                                    The name `AVariant` of a member of the generated C++ struct \
+                                    is ambiguous";
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    private:
+                        __COMMENT__ #broken_field_msg
+                        unsigned char __opaque_blob_of_bytes[8];
+                }
             );
         });
     }
 
+    /// This is a test for an enum that has `EnumItemTuple` and `EnumItemStruct`
+    /// items. See also https://doc.rust-lang.org/reference/items/enumerations.html
     #[test]
-    fn test_format_item_struct_with_unsupported_field_type() {
+    fn test_format_item_enum_with_tuple_and_struct_items() {
         let test_src = r#"
-                pub struct SomeStruct {
-                    pub successful_field: i32,
-                    pub unsupported_field: Option<[i32; 3]>,
+                pub enum Point {
+                    Cartesian(f32, f32),
+                    Polar{ dist: f32, angle: f32 },
                 }
+
+                const _: () = assert!(std::mem::size_of::<Point>() == 12);
+                const _: () = assert!(std::mem::align_of::<Point>() == 4);
             "#;
-        test_format_item(test_src, "SomeStruct", |result| {
+        test_format_item(test_src, "Point", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
             let impl_details = get_impl_details_snippet(&result);
-            let broken_field_msg = "Field type has been replaced with a blob of bytes: \
-                                    Generic types are not supported yet (b/259749095)";
+            let no_fields_msg = "Field type has been replaced with a blob of bytes: \
+                                 No support for bindings of individual fields of \
+                                 `union` (b/272801632) or `enum`";
+            assert!(main_api.prereqs.is_empty());
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
                     ...
-                    struct ... SomeStruct final {
-                        ...
+                    struct alignas(4) Point final {
+                        public:
+                            // In this test there is no `Default` implementation.
+                            Point() = delete;
+
+                            // In this test there is no `Copy` implementation / derive.
+                            Point(const Point&) = delete;
+
+                            // All Rust types are trivially-movable.
+                            Point(Point&&) = default;
+
+                            // Assignment operators are disabled for now.
+                            Point& operator=(const Point&) = delete;
+                            Point& operator=(Point&&) = delete;
+
+                            // In this test there is no custom `Drop`, so C++ can also
+                            // just use the `default` destructor.
+                            ~Point() = default;
                         private:
-                            __COMMENT__ #broken_field_msg
-                            unsigned char unsupported_field[16];
-                            std::int32_t successful_field;
+                            __COMMENT__ #no_fields_msg
+                            unsigned char __opaque_blob_of_bytes[12];
                             inline static void __crubit_field_offset_assertions();
                     };
-                    ...
                 }
             );
             assert_cc_matches!(
                 impl_details.cc.tokens,
                 quote! {
-                    static_assert(sizeof(SomeStruct) == 20, ...);
-                    static_assert(alignof(SomeStruct) == 4, ...);
-                    inline void SomeStruct::__crubit_field_offset_assertions() {
-                      static_assert(0 == offsetof(SomeStruct, unsupported_field));
-                      static_assert(16 == offsetof(SomeStruct, successful_field));
-                    }
+                    static_assert(sizeof(Point) == 12, ...);
+                    static_assert(alignof(Point) == 4, ...);
                 }
             );
             assert_rs_matches!(
                 impl_details.rs,
                 quote! {
-                    const _: () = assert!(::std::mem::size_of::<::rust_out::SomeStruct>() == 20);
-                    const _: () = assert!(::std::mem::align_of::<::rust_out::SomeStruct>() == 4);
-                    const _: () = assert!( memoffset::offset_of!(::rust_out::SomeStruct,
-                                                                 unsupported_field) == 0);
-                    const _: () = assert!( memoffset::offset_of!(::rust_out::SomeStruct,
-                                                                 successful_field) == 16);
+                    const _: () = assert!(::std::mem::size_of::<::rust_out::Point>() == 12);
+                    const _: () = assert!(::std::mem::align_of::<::rust_out::Point>() == 4);
                 }
             );
         });
     }
 
+    /// This test covers how zero-variant enums are handled.  See also
+    /// https://doc.rust-lang.org/reference/items/enumerations.html#zero-variant-enums
     #[test]
-    fn test_format_item_unsupported_struct_with_custom_drop_impl() {
+    fn test_format_item_unsupported_enum_zero_variants() {
         let test_src = r#"
-                pub struct StructWithCustomDropImpl {
-                    pub x: i32,
-                    pub y: i32,
-                }
-
-                impl Drop for StructWithCustomDropImpl {
-                    fn drop(&mut self) {}
-                }
+                pub enum ZeroVariantEnum {}
             "#;
-        test_format_item(test_src, "StructWithCustomDropImpl", |result| {
+        test_format_item(test_src, "ZeroVariantEnum", |result| {
             let err = result.unwrap_err();
-            assert_eq!(err, "`Drop` trait and \"drop glue\" are not supported yet (b/258251148)");
+            assert_eq!(err, "Zero-sized types (ZSTs) are not supported (b/258259459)");
         });
     }
 
+    /// This is a test for a `union`.  See also
+    /// https://doc.rust-lang.org/reference/items/unions.html
     #[test]
-    fn test_format_item_unsupported_struct_with_custom_drop_glue() {
+    fn test_format_item_union() {
         let test_src = r#"
-                #![allow(dead_code)]
-
-                // `i32` is present to avoid hitting the ZST checks related to (b/258259459)
-                struct StructWithCustomDropImpl(i32);
-
-                impl Drop for StructWithCustomDropImpl {
-                    fn drop(&mut self) {
-                        println!("dropping!");
-                    }
+                pub union SomeUnion {
+                    pub i: i32,
+                    pub f: f64,
                 }
 
-                pub struct StructRequiringCustomDropGlue {
-                    field: StructWithCustomDropImpl,
-                }
+                const _: () = assert!(std::mem::size_of::<SomeUnion>() == 8);
+                const _: () = assert!(std::mem::align_of::<SomeUnion>() == 8);
             "#;
-        test_format_item(test_src, "StructRequiringCustomDropGlue", |result| {
-            let err = result.unwrap_err();
-            assert_eq!(err, "`Drop` trait and \"drop glue\" are not supported yet (b/258251148)");
-        });
-    }
+        test_format_item(test_src, "SomeUnion", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let impl_details = get_impl_details_snippet(&result);
+            let no_fields_msg = "Field type has been replaced with a blob of bytes: \
+                                 No support for bindings of individual fields of \
+                                 `union` (b/272801632) or `enum`";
+            assert!(main_api.prereqs.is_empty());
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    ...
+                    union alignas(8) SomeUnion final {
+                        public:
+                            // In this test there is no `Default` implementation.
+                            SomeUnion() = delete;
 
-    /// This test covers how ZSTs (zero-sized-types) are handled.
-    /// https://doc.rust-lang.org/reference/items/structs.html refers to this kind of struct as a
-    /// "unit-like struct".
-    #[test]
-    fn test_format_item_unsupported_struct_zero_sized_type_with_no_fields() {
-        let test_src = r#"
-                pub struct ZeroSizedType1;
-                pub struct ZeroSizedType2();
-                pub struct ZeroSizedType3{}
-            "#;
-        for name in ["ZeroSizedType1", "ZeroSizedType2", "ZeroSizedType3"] {
-            test_format_item(test_src, name, |result| {
-                let err = result.unwrap_err();
-                assert_eq!(err, "Zero-sized types (ZSTs) are not supported (b/258259459)");
-            });
-        }
-    }
+                            // In this test there is no `Copy` implementation / derive.
+                            SomeUnion(const SomeUnion&) = delete;
 
-    #[test]
-    fn test_format_item_unsupported_struct_with_only_zero_sized_type_fields() {
-        let test_src = r#"
-                pub struct ZeroSizedType;
-                pub struct SomeStruct {
-                    pub zst1: ZeroSizedType,
-                    pub zst2: ZeroSizedType,
+                            // All Rust types are trivially-movable.
+                            SomeUnion(SomeUnion&&) = default;
+
+                            // Assignment operators are disabled for now.
+                            SomeUnion& operator=(const SomeUnion&) = delete;
+                            SomeUnion& operator=(SomeUnion&&) = delete;
+
+                            // In this test there is no custom `Drop`, so C++ can also
+                            // just use the `default` destructor.
+                            ~SomeUnion() = default;
+                        private:
+                            __COMMENT__ #no_fields_msg
+                            unsigned char __opaque_blob_of_bytes[8];
+                            inline static void __crubit_field_offset_assertions();
+                    };
                 }
-            "#;
-        test_format_item(test_src, "SomeStruct", |result| {
-            let err = result.unwrap_err();
-            assert_eq!(err, "Zero-sized types (ZSTs) are not supported (b/258259459)",);
+            );
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    static_assert(sizeof(SomeUnion) == 8, ...);
+                    static_assert(alignof(SomeUnion) == 8, ...);
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    const _: () = assert!(::std::mem::size_of::<::rust_out::SomeUnion>() == 8);
+                    const _: () = assert!(::std::mem::align_of::<::rust_out::SomeUnion>() == 8);
+                }
+            );
         });
     }
 
-    /// This is a test for an enum that only has `EnumItemDiscriminant` items
-    /// (and doesn't have `EnumItemTuple` or `EnumItemStruct` items).  See
-    /// also https://doc.rust-lang.org/reference/items/enumerations.html
+    /// This is a test for a `#[repr(C)]` union - all its fields are guaranteed to
+    /// have offset 0, and therefore the fields can be represented as members of a
+    /// C++ `union`.
     #[test]
-    fn test_format_item_enum_with_only_discriminant_items() {
+    fn test_format_item_repr_c_union() {
         let test_src = r#"
-                pub enum SomeEnum {
-                    Red,
-                    Green = 123,
-                    Blue,
+                #[derive(Clone, Copy)]
+                pub struct SomeStruct {
+                    pub x: u16,
                 }
 
-                const _: () = assert!(std::mem::size_of::<SomeEnum>() == 1);
-                const _: () = assert!(std::mem::align_of::<SomeEnum>() == 1);
+                #[repr(C)]
+                pub union SomeUnion {
+                    pub i: i32,
+                    pub f: f64,
+                    pub s: SomeStruct,
+                    pub t: (i32, i32),
+                }
+
+                const _: () = assert!(std::mem::size_of::<SomeUnion>() == 8);
+                const _: () = assert!(std::mem::align_of::<SomeUnion>() == 8);
             "#;
-        test_format_item(test_src, "SomeEnum", |result| {
+        test_format_item(test_src, "SomeUnion", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
             let impl_details = get_impl_details_snippet(&result);
-            let no_fields_msg = "Field type has been replaced with a blob of bytes: \
-                                 No support for bindings of individual fields of \
-                                 `union` (b/272801632) or `enum`";
-            assert!(main_api.prereqs.is_empty());
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
                     ...
-                    struct alignas(1) SomeEnum final {
+                    union alignas(8) SomeUnion final {
+                        ...
                         public:
-                            // In this test there is no `Default` implementation.
-                            SomeEnum() = delete;
-
-                            // In this test there is no `Copy` implementation / derive.
-                            SomeEnum(const SomeEnum&) = delete;
-
-                            // All Rust types are trivially-movable.
-                            SomeEnum(SomeEnum&&) = default;
-
-                            // Assignment operators are disabled for now.
-                            SomeEnum& operator=(const SomeEnum&) = delete;
-                            SomeEnum& operator=(SomeEnum&&) = delete;
-
-                            // In this test there is no custom `Drop`, so C++ can also
-                            // just use the `default` destructor.
-                            ~SomeEnum() = default;
+                            std::int32_t i;
+                            double f;
+                            ::rust_out::SomeStruct s;
+                            __COMMENT__ "Replicates the layout of the Rust tuple type `(i32, i32)`."
+                            struct alignas(4) __CrubitTupleField3 final {
+                                std::int32_t __field0;
+                                std::int32_t __field1;
+                            };
+                            __CrubitTupleField3 t;
                         private:
-                            __COMMENT__ #no_fields_msg
-                            unsigned char __opaque_blob_of_bytes[1];
                             inline static void __crubit_field_offset_assertions();
                     };
                 }
             );
+            assert_cc_not_matches!(main_api.tokens, quote! { __padding0 });
+            assert_cc_not_matches!(main_api.tokens, quote! { __padding1 });
             assert_cc_matches!(
                 impl_details.cc.tokens,
                 quote! {
-                    static_assert(sizeof(SomeEnum) == 1, ...);
-                    static_assert(alignof(SomeEnum) == 1, ...);
+                    static_assert(sizeof(SomeUnion) == 8, ...);
+                    static_assert(alignof(SomeUnion) == 8, ...);
+                    inline void SomeUnion::__crubit_field_offset_assertions() {
+                        static_assert(0 == offsetof(SomeUnion, i));
+                        static_assert(0 == offsetof(SomeUnion, f));
+                        static_assert(0 == offsetof(SomeUnion, s));
+                        static_assert(0 == offsetof(SomeUnion, t));
+                        static_assert(0 == offsetof(__CrubitTupleField3, __field0));
+                        static_assert(4 == offsetof(__CrubitTupleField3, __field1));
+                        ...
+                    }
                 }
             );
-            assert_rs_matches!(
-                impl_details.rs,
+            assert_rs_not_matches!(impl_details.rs, quote! { offset_of! });
+        });
+    }
+
+    #[test]
+    fn test_format_item_doc_comments_union() {
+        let test_src = r#"
+            /// Doc for some union.
+            pub union SomeUnionWithDocs {
+                /// Doc for a field in a union.
+                pub i: i32,
+                pub f: f64
+            }
+        "#;
+        test_format_item(test_src, "SomeUnionWithDocs", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let comment = " Doc for some union.\n\n\
+                           Generated from: <crubit_unittests.rs>;l=3";
+            assert_cc_matches!(
+                main_api.tokens,
                 quote! {
-                    const _: () = assert!(::std::mem::size_of::<::rust_out::SomeEnum>() == 1);
-                    const _: () = assert!(::std::mem::align_of::<::rust_out::SomeEnum>() == 1);
+                    __COMMENT__ #comment
+                    union ... SomeUnionWithDocs final {
+                        ...
+                    }
+                    ...
                 }
             );
         });
     }
 
-    /// This is a test for an enum that has `EnumItemTuple` and `EnumItemStruct`
-    /// items. See also https://doc.rust-lang.org/reference/items/enumerations.html
     #[test]
-    fn test_format_item_enum_with_tuple_and_struct_items() {
+    fn test_format_item_doc_comments_enum() {
         let test_src = r#"
-                pub enum Point {
-                    Cartesian(f32, f32),
-                    Polar{ dist: f32, angle: f32 },
+            /** Doc for some enum. */
+            pub enum SomeEnumWithDocs {
+                Kind1(i32),
+            }
+        "#;
+        test_format_item(test_src, "SomeEnumWithDocs", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let comment = " Doc for some enum. \n\n\
+                            Generated from: <crubit_unittests.rs>;l=3";
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    __COMMENT__ #comment
+                    struct ... SomeEnumWithDocs final {
+                        ...
+                    }
+                    ...
                 }
+            );
+        });
+    }
 
-                const _: () = assert!(std::mem::size_of::<Point>() == 12);
-                const _: () = assert!(std::mem::align_of::<Point>() == 4);
-            "#;
-        test_format_item(test_src, "Point", |result| {
+    #[test]
+    fn test_format_item_doc_comments_struct() {
+        let test_src = r#"
+            #![allow(dead_code)]
+            #[doc = "Doc for some struct."]
+            pub struct SomeStructWithDocs {
+                #[doc = "Doc for first field."]
+                some_field : i32,
+            }
+        "#;
+        test_format_item(test_src, "SomeStructWithDocs", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
-            let impl_details = get_impl_details_snippet(&result);
-            let no_fields_msg = "Field type has been replaced with a blob of bytes: \
-                                 No support for bindings of individual fields of \
-                                 `union` (b/272801632) or `enum`";
-            assert!(main_api.prereqs.is_empty());
+            let comment = "Doc for some struct.\n\n\
+                           Generated from: <crubit_unittests.rs>;l=4";
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    __COMMENT__ #comment
+                    struct ... SomeStructWithDocs final {
+                        ...
+                    }
+                    ...
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_doc_comments_tuple_struct() {
+        let test_src = r#"
+            /// Doc for some tuple struct.
+            pub struct SomeTupleStructWithDocs(i32);
+        "#;
+        test_format_item(test_src, "SomeTupleStructWithDocs", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let comment = " Doc for some tuple struct.\n\n\
+                           Generated from: <crubit_unittests.rs>;l=3";
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
+                    __COMMENT__ #comment
+                    struct ... SomeTupleStructWithDocs final {
+                        ...
+                    }
                     ...
-                    struct alignas(4) Point final {
-                        public:
-                            // In this test there is no `Default` implementation.
-                            Point() = delete;
+                },
+            );
+        });
+    }
 
-                            // In this test there is no `Copy` implementation / derive.
-                            Point(const Point&) = delete;
+    #[test]
+    fn test_format_item_source_loc_macro_rules() {
+        let test_src = r#"
+            macro_rules! some_tuple_struct_macro_for_testing_source_loc {
+                () => {
+                    /// Some doc on SomeTupleStructMacroForTesingSourceLoc.
+                    pub struct SomeTupleStructMacroForTesingSourceLoc(i32);
+                };
+            }
 
-                            // All Rust types are trivially-movable.
-                            Point(Point&&) = default;
+            some_tuple_struct_macro_for_testing_source_loc!();
+        "#;
+        test_format_item(test_src, "SomeTupleStructMacroForTesingSourceLoc", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let source_loc_comment = " Some doc on SomeTupleStructMacroForTesingSourceLoc.\n\n\
+                                      Generated from: <crubit_unittests.rs>;l=5";
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    __COMMENT__ #source_loc_comment
+                    struct ... SomeTupleStructMacroForTesingSourceLoc final {
+                        ...
+                    }
+                    ...
+                },
+            );
+        });
+    }
 
-                            // Assignment operators are disabled for now.
-                            Point& operator=(const Point&) = delete;
-                            Point& operator=(Point&&) = delete;
+    #[test]
+    fn test_format_item_source_loc_with_no_doc_comment() {
+        let test_src = r#"
+            pub struct SomeTupleStructWithNoDocComment(i32);
+        "#;
+        test_format_item(test_src, "SomeTupleStructWithNoDocComment", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            let comment = "Generated from: <crubit_unittests.rs>;l=2";
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! {
+                    __COMMENT__ #comment
+                    struct ... SomeTupleStructWithNoDocComment final {
+                        ...
+                    }
+                    ...
+                },
+            );
+        });
+    }
 
-                            // In this test there is no custom `Drop`, so C++ can also
-                            // just use the `default` destructor.
-                            ~Point() = default;
-                        private:
-                            __COMMENT__ #no_fields_msg
-                            unsigned char __opaque_blob_of_bytes[12];
-                            inline static void __crubit_field_offset_assertions();
-                    };
+    #[test]
+    fn test_generated_bindings_const_of_scalar_types() {
+        let test_src = r#"
+                pub const BOOL: bool = true;
+                pub const I8: i8 = -128;
+                pub const I32: i32 = 40 + 2;
+                pub const I64_MIN: i64 = i64::MIN;
+                pub const U64_MAX: u64 = u64::MAX;
+                pub const USIZE: usize = 123;
+                pub const F32: f32 = 0.1;
+                pub const F64: f64 = -1.25;
+                pub const F64_INFINITY: f64 = f64::INFINITY;
+                pub const F64_NAN: f64 = f64::NAN;
+                pub const CHAR: char = 'A';
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    ...
+                    inline constexpr bool BOOL = true;
+                    ...
+                    inline constexpr std::int8_t I8 = -128;
+                    ...
+                    inline constexpr std::int32_t I32 = 42;
+                    ...
+                    // `-9223372036854775808` would be ill-formed.
+                    inline constexpr std::int64_t I64_MIN = (-9223372036854775807 - 1);
+                    ...
+                    // `18446744073709551615` would be ill-formed.
+                    inline constexpr std::uint64_t U64_MAX = static_cast<std::uint64_t>(-1);
+                    ...
+                    inline constexpr std::uintptr_t USIZE = 123;
+                    ...
+                    // `f32` values are spelled as the (exactly equal) `f64` value.
+                    inline constexpr float F32 = 0.10000000149011612;
+                    ...
+                    inline constexpr double F64 = -1.25;
+                    ...
+                    inline constexpr double F64_INFINITY =
+                        std::numeric_limits<double>::infinity();
+                    ...
+                    inline constexpr double F64_NAN = std::numeric_limits<double>::quiet_NaN();
+                    ...
+                    inline constexpr rs_std::rs_char CHAR =
+                        rs_std::rs_char::from_u32(65).value();
+                    ...
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_const_of_struct_type() {
+        let test_src = r#"
+                pub struct Point {
+                    pub x: i32,
+                    pub y: i32,
                 }
-            );
+
+                /// Doc comment of `ORIGIN`.
+                pub const ORIGIN: Point = Point { x: 0, y: -1 };
+            "#;
+        test_format_item(test_src, "ORIGIN", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            // The constant uses the `constexpr` factory method from the definition of `Point`.
+            assert_eq!(1, main_api.prereqs.defs.len());
+            let comment = " Doc comment of `ORIGIN`.\n\n\
+                           Generated from: <crubit_unittests.rs>;l=8";
             assert_cc_matches!(
-                impl_details.cc.tokens,
+                main_api.tokens,
                 quote! {
-                    static_assert(sizeof(Point) == 12, ...);
-                    static_assert(alignof(Point) == 4, ...);
+                    __COMMENT__ #comment
+                    inline constexpr ::rust_out::Point ORIGIN =
+                        ::crubit::ConstFactory::Make<::rust_out::Point>(0, -1);
                 }
             );
-            assert_rs_matches!(
-                impl_details.rs,
-                quote! {
-                    const _: () = assert!(::std::mem::size_of::<::rust_out::Point>() == 12);
-                    const _: () = assert!(::std::mem::align_of::<::rust_out::Point>() == 4);
+        });
+    }
+
+    #[test]
+    fn test_format_item_unsupported_const_of_struct_type_with_adt_fields() {
+        let test_src = r#"
+                pub struct Inner(pub i32);
+                pub struct Outer {
+                    pub inner: Inner,
                 }
+                pub const OUTER: Outer = Outer { inner: Inner(123) };
+            "#;
+        test_format_item(test_src, "OUTER", |result| {
+            let err = result.unwrap_err();
+            assert_eq!(
+                err,
+                "Constants of struct types are only supported if the struct doesn't need drop \
+                 glue and only has fields of scalar types: Outer"
             );
         });
     }
 
-    /// This test covers how zero-variant enums are handled.  See also
-    /// https://doc.rust-lang.org/reference/items/enumerations.html#zero-variant-enums
     #[test]
-    fn test_format_item_unsupported_enum_zero_variants() {
+    fn test_format_item_unsupported_const_of_tuple_type() {
         let test_src = r#"
-                pub enum ZeroVariantEnum {}
+                pub const PAIR: (i32, i32) = (1, 2);
             "#;
-        test_format_item(test_src, "ZeroVariantEnum", |result| {
+        test_format_item(test_src, "PAIR", |result| {
             let err = result.unwrap_err();
-            assert_eq!(err, "Zero-sized types (ZSTs) are not supported (b/258259459)");
+            assert_eq!(
+                err,
+                "Only constants of scalar and struct types are supported yet: (i32, i32)"
+            );
         });
     }
 
-    /// This is a test for a `union`.  See also
-    /// https://doc.rust-lang.org/reference/items/unions.html
     #[test]
-    fn test_format_item_union() {
+    fn test_format_item_assoc_consts() {
         let test_src = r#"
-                pub union SomeUnion {
-                    pub i: i32,
-                    pub f: f64,
+                pub struct Point {
+                    pub x: i32,
+                    pub y: i32,
                 }
 
-                const _: () = assert!(std::mem::size_of::<SomeUnion>() == 8);
-                const _: () = assert!(std::mem::align_of::<SomeUnion>() == 8);
+                impl Point {
+                    /// Doc comment of `DIMENSIONS`.
+                    pub const DIMENSIONS: usize = 2;
+
+                    pub const ORIGIN: Point = Point { x: 0, y: 0 };
+                }
             "#;
-        test_format_item(test_src, "SomeUnion", |result| {
+        test_format_item(test_src, "Point", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
             let impl_details = get_impl_details_snippet(&result);
-            let no_fields_msg = "Field type has been replaced with a blob of bytes: \
-                                 No support for bindings of individual fields of \
-                                 `union` (b/272801632) or `enum`";
-            assert!(main_api.prereqs.is_empty());
+            let comment = " Doc comment of `DIMENSIONS`.\n\n\
+                           Generated from: <crubit_unittests.rs>;l=9";
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
-                    ...
-                    union alignas(8) SomeUnion final {
+                    struct alignas(4) Point final {
+                        ...
                         public:
-                            // In this test there is no `Default` implementation.
-                            SomeUnion() = delete;
-
-                            // In this test there is no `Copy` implementation / derive.
-                            SomeUnion(const SomeUnion&) = delete;
-
-                            // All Rust types are trivially-movable.
-                            SomeUnion(SomeUnion&&) = default;
-
-                            // Assignment operators are disabled for now.
-                            SomeUnion& operator=(const SomeUnion&) = delete;
-                            SomeUnion& operator=(SomeUnion&&) = delete;
-
-                            // In this test there is no custom `Drop`, so C++ can also
-                            // just use the `default` destructor.
-                            ~SomeUnion() = default;
+                            __COMMENT__ #comment
+                            static constexpr std::uintptr_t DIMENSIONS = 2;
+                            __COMMENT__ ...
+                            // `Point` is incomplete at this point, so `ORIGIN` can't be
+                            // declared as `constexpr` yet.
+                            static const ::rust_out::Point ORIGIN;
                         private:
-                            __COMMENT__ #no_fields_msg
-                            unsigned char __opaque_blob_of_bytes[8];
-                            inline static void __crubit_field_offset_assertions();
+                        ...
                     };
                 }
             );
             assert_cc_matches!(
                 impl_details.cc.tokens,
                 quote! {
-                    static_assert(sizeof(SomeUnion) == 8, ...);
-                    static_assert(alignof(SomeUnion) == 8, ...);
-                }
-            );
-            assert_rs_matches!(
-                impl_details.rs,
-                quote! {
-                    const _: () = assert!(::std::mem::size_of::<::rust_out::SomeUnion>() == 8);
-                    const _: () = assert!(::std::mem::align_of::<::rust_out::SomeUnion>() == 8);
+                    constexpr ::rust_out::Point Point::ORIGIN =
+                        ::crubit::ConstFactory::Make<::rust_out::Point>(0, 0);
                 }
             );
         });
     }
 
     #[test]
-    fn test_format_item_doc_comments_union() {
+    fn test_format_item_static() {
         let test_src = r#"
-            /// Doc for some union.
-            pub union SomeUnionWithDocs {
-                /// Doc for a field in a union.
-                pub i: i32,
-                pub f: f64
-            }
-        "#;
-        test_format_item(test_src, "SomeUnionWithDocs", |result| {
+                /// Doc comment of `STATIC_VALUE`.
+                #[no_mangle]
+                pub static STATIC_VALUE: i32 = 42;
+            "#;
+        test_format_item(test_src, "STATIC_VALUE", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
-            let comment = " Doc for some union.\n\n\
-                           Generated from: <crubit_unittests.rs>;l=3";
+            let impl_details = get_impl_details_snippet(&result);
+            let comment = " Doc comment of `STATIC_VALUE`.\n\n\
+                           Generated from: <crubit_unittests.rs>;l=4";
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
+                    namespace __crubit_internal {
+                        extern "C" const std::int32_t* const __crubit_static_STATIC_VALUE;
+                    }
                     __COMMENT__ #comment
-                    union ... SomeUnionWithDocs final {
-                        ...
+                    inline const std::int32_t& STATIC_VALUE() {
+                        return *__crubit_internal::__crubit_static_STATIC_VALUE;
                     }
-                    ...
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    #[no_mangle]
+                    static __crubit_static_STATIC_VALUE: &i32 = &::rust_out::STATIC_VALUE;
                 }
             );
         });
     }
 
     #[test]
-    fn test_format_item_doc_comments_enum() {
+    fn test_format_item_unsupported_static_mut() {
         let test_src = r#"
-            /** Doc for some enum. */
-            pub enum SomeEnumWithDocs {
-                Kind1(i32),
-            }
-        "#;
-        test_format_item(test_src, "SomeEnumWithDocs", |result| {
+                pub static mut STATIC_VALUE: i32 = 42;
+            "#;
+        test_format_item(test_src, "STATIC_VALUE", |result| {
+            let err = result.unwrap_err();
+            assert_eq!(err, "Bindings for `static mut` items are not supported yet");
+        });
+    }
+
+    #[test]
+    fn test_format_item_type_alias() {
+        let test_src = r#"
+                /// Doc comment of the type alias.
+                pub type TypeAlias = i32;
+            "#;
+        test_format_item(test_src, "TypeAlias", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
-            let comment = " Doc for some enum. \n\n\
-                            Generated from: <crubit_unittests.rs>;l=3";
+            assert_cc_matches!(
+                format_cc_includes(&main_api.prereqs.includes),
+                quote! { include <cstdint> }
+            );
+            let doc_comment = " Doc comment of the type alias.\n\n\
+                               Generated from: <crubit_unittests.rs>;l=3";
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
-                    __COMMENT__ #comment
-                    struct ... SomeEnumWithDocs final {
-                        ...
-                    }
-                    ...
+                    __COMMENT__ #doc_comment
+                    using TypeAlias = std::int32_t;
                 }
             );
         });
     }
 
     #[test]
-    fn test_format_item_doc_comments_struct() {
+    fn test_format_item_type_alias_of_struct() {
         let test_src = r#"
-            #![allow(dead_code)]
-            #[doc = "Doc for some struct."]
-            pub struct SomeStructWithDocs {
-                #[doc = "Doc for first field."]
-                some_field : i32,
-            }
-        "#;
-        test_format_item(test_src, "SomeStructWithDocs", |result| {
+                pub struct SomeStruct {
+                    pub x: i32,
+                }
+                pub type StructAlias = SomeStruct;
+                pub type PointerAlias = *const SomeStruct;
+            "#;
+        test_format_item(test_src, "StructAlias", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
-            let comment = "Doc for some struct.\n\n\
-                           Generated from: <crubit_unittests.rs>;l=4";
+            // The definition of the aliased struct is needed before the alias.
+            assert_eq!(1, main_api.prereqs.defs.len());
+            assert_eq!(0, main_api.prereqs.fwd_decls.len());
             assert_cc_matches!(
                 main_api.tokens,
-                quote! {
-                    __COMMENT__ #comment
-                    struct ... SomeStructWithDocs final {
-                        ...
-                    }
-                    ...
-                }
+                quote! { using StructAlias = ::rust_out::SomeStruct; }
+            );
+        });
+        test_format_item(test_src, "PointerAlias", |result| {
+            let result = result.unwrap();
+            let main_api = get_main_api_snippet(&result);
+            // Only a forward declaration of the struct is needed for a pointer alias.
+            assert_eq!(0, main_api.prereqs.defs.len());
+            assert_eq!(1, main_api.prereqs.fwd_decls.len());
+            assert_cc_matches!(
+                main_api.tokens,
+                quote! { using PointerAlias = const ::rust_out::SomeStruct*; }
             );
         });
     }
 
     #[test]
-    fn test_format_item_doc_comments_tuple_struct() {
+    fn test_generated_bindings_type_alias_ordering() {
         let test_src = r#"
-            /// Doc for some tuple struct.
-            pub struct SomeTupleStructWithDocs(i32);
-        "#;
-        test_format_item(test_src, "SomeTupleStructWithDocs", |result| {
-            let result = result.unwrap();
-            let main_api = get_main_api_snippet(&result);
-            let comment = " Doc for some tuple struct.\n\n\
-                           Generated from: <crubit_unittests.rs>;l=3";
+                pub mod some_module {
+                    pub type StructAlias = SomeStruct;
+
+                    pub struct SomeStruct {
+                        pub x: i32,
+                    }
+                }
+            "#;
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+
+            // `toposort` should place the alias in the same namespace, but *after* the
+            // definition of the aliased struct.
             assert_cc_matches!(
-                main_api.tokens,
+                bindings.h_body,
                 quote! {
-                    __COMMENT__ #comment
-                    struct ... SomeTupleStructWithDocs final {
+                    namespace some_module {
+                        ...
+                        struct ... SomeStruct final {
+                            ...
+                        };
+                        ...
+                        using StructAlias = ::rust_out::some_module::SomeStruct;
                         ...
                     }
-                    ...
-                },
+                }
             );
         });
     }
 
     #[test]
-    fn test_format_item_source_loc_macro_rules() {
+    fn test_format_item_unsupported_generic_type_alias() {
         let test_src = r#"
-            macro_rules! some_tuple_struct_macro_for_testing_source_loc {
-                () => {
-                    /// Some doc on SomeTupleStructMacroForTesingSourceLoc.
-                    pub struct SomeTupleStructMacroForTesingSourceLoc(i32);
-                };
-            }
+                pub type GenericAlias<T> = Option<T>;
+            "#;
+        test_format_item(test_src, "GenericAlias", |result| {
+            let err = result.unwrap_err();
+            assert_eq!(err, "Generic type aliases are not supported yet (b/254096006)");
+        });
+    }
 
-            some_tuple_struct_macro_for_testing_source_loc!();
-        "#;
-        test_format_item(test_src, "SomeTupleStructMacroForTesingSourceLoc", |result| {
+    #[test]
+    fn test_format_item_unsupported_type_alias_of_unsupported_type() {
+        let test_src = r#"
+                pub type ArrayAlias = [i32; 4];
+            "#;
+        test_format_item(test_src, "ArrayAlias", |result| {
+            let err = result.unwrap_err();
+            assert_eq!(
+                err,
+                "Error formatting the aliased type: \
+                 The following Rust type is not supported yet: [i32; 4]"
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_item_trait_impl_items() {
+        let test_src = r#"
+                pub struct SomeStruct(i32);
+
+                pub trait SomeTrait {
+                    const SIZE: i32;
+                    fn create(x: i32) -> Self;
+                    fn get(&self) -> i32;
+                }
+
+                impl SomeTrait for SomeStruct {
+                    const SIZE: i32 = 42;
+                    fn create(x: i32) -> Self {
+                        Self(x)
+                    }
+                    fn get(&self) -> i32 {
+                        self.0
+                    }
+                }
+            "#;
+        test_format_item(test_src, "SomeStruct", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
-            let source_loc_comment = " Some doc on SomeTupleStructMacroForTesingSourceLoc.\n\n\
-                                      Generated from: <crubit_unittests.rs>;l=5";
+            let impl_details = get_impl_details_snippet(&result);
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
-                    __COMMENT__ #source_loc_comment
-                    struct ... SomeTupleStructMacroForTesingSourceLoc final {
-                        ...
+                    ...
+                    struct ... SomeStruct final {
+                        ...
+                        public:
+                          ...
+                          static constexpr std::int32_t SIZE = 42;
+                          ...
+                          static inline ::rust_out::SomeStruct create(std::int32_t x);
+                          ...
+                          inline std::int32_t get() const;
+                        ...
+                    };
+                }
+            );
+            assert_cc_matches!(
+                impl_details.cc.tokens,
+                quote! {
+                    namespace __crubit_internal {
+                        extern "C" std::int32_t ...(const ::rust_out::SomeStruct& __self);
+                    }
+                    inline std::int32_t SomeStruct::get() const {
+                        return __crubit_internal::...(*this);
+                    }
+                }
+            );
+            assert_rs_matches!(
+                impl_details.rs,
+                quote! {
+                    #[no_mangle]
+                    extern "C" fn ...(__self: &::rust_out::SomeStruct) -> i32 {
+                        <::rust_out::SomeStruct as ::rust_out::SomeTrait>::get(__self)
                     }
-                    ...
-                },
+                }
             );
         });
     }
 
     #[test]
-    fn test_format_item_source_loc_with_no_doc_comment() {
+    fn test_format_item_trait_impl_of_private_trait() {
         let test_src = r#"
-            pub struct SomeTupleStructWithNoDocComment(i32);
-        "#;
-        test_format_item(test_src, "SomeTupleStructWithNoDocComment", |result| {
+                pub struct SomeStruct(i32);
+
+                trait PrivateTrait {
+                    fn get(&self) -> i32;
+                }
+
+                impl PrivateTrait for SomeStruct {
+                    fn get(&self) -> i32 {
+                        self.0
+                    }
+                }
+            "#;
+        test_format_item(test_src, "SomeStruct", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
-            let comment = "Generated from: <crubit_unittests.rs>;l=2";
-            assert_cc_matches!(
-                main_api.tokens,
-                quote! {
-                    __COMMENT__ #comment
-                    struct ... SomeTupleStructWithNoDocComment final {
-                        ...
-                    }
-                    ...
-                },
-            );
+            let impl_details = get_impl_details_snippet(&result);
+            assert_cc_not_matches!(main_api.tokens, quote! { get });
+            assert_rs_not_matches!(impl_details.rs, quote! { PrivateTrait });
         });
     }
 
     #[test]
-    fn test_format_item_unsupported_static_value() {
+    fn test_generated_bindings_trait_impl_name_clash() {
         let test_src = r#"
-                #[no_mangle]
-                pub static STATIC_VALUE: i32 = 42;
+                pub mod traits {
+                    pub trait Named {
+                        fn name(&self) -> i32;
+                    }
+                }
+
+                pub struct SomeStruct(i32);
+
+                impl SomeStruct {
+                    pub fn name(&self) -> i32 {
+                        self.0
+                    }
+                }
+
+                impl traits::Named for SomeStruct {
+                    fn name(&self) -> i32 {
+                        -self.0
+                    }
+                }
             "#;
-        test_format_item(test_src, "STATIC_VALUE", |result| {
-            let err = result.unwrap_err();
-            assert_eq!(err, "Unsupported rustc_hir::hir::ItemKind: static item");
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    struct ... SomeStruct final {
+                        ...
+                        public:
+                          ...
+                          inline std::int32_t name() const;
+                        ...
+                    };
+                    ...
+                    namespace Named {
+                        ...
+                        inline std::int32_t name(const ::rust_out::SomeStruct& self);
+                    }
+                }
+            );
+            assert_cc_matches!(
+                bindings.h_body,
+                quote! {
+                    namespace Named {
+                        namespace __crubit_internal {
+                            extern "C" std::int32_t ...(const ::rust_out::SomeStruct& self);
+                        }
+                        inline std::int32_t name(const ::rust_out::SomeStruct& self) {
+                            return __crubit_internal::...(self);
+                        }
+                    }
+                }
+            );
+            assert_rs_matches!(
+                bindings.rs_body,
+                quote! {
+                    #[no_mangle]
+                    extern "C" fn ...(__self: &::rust_out::SomeStruct) -> i32 {
+                        <::rust_out::SomeStruct as ::rust_out::traits::Named>::name(__self)
+                    }
+                }
+            );
         });
     }
 
     #[test]
-    fn test_format_item_unsupported_const_value() {
+    fn test_generated_bindings_trait_impl_name_clash_with_trait_class() {
         let test_src = r#"
-                pub const CONST_VALUE: i32 = 42;
+                pub trait Named {
+                    fn name(&self) -> i32;
+                }
+
+                pub struct SomeStruct(i32);
+
+                impl SomeStruct {
+                    pub fn name(&self) -> i32 {
+                        self.0
+                    }
+                }
+
+                impl Named for SomeStruct {
+                    fn name(&self) -> i32 {
+                        -self.0
+                    }
+                }
             "#;
-        test_format_item(test_src, "CONST_VALUE", |result| {
-            let err = result.unwrap_err();
-            assert_eq!(err, "Unsupported rustc_hir::hir::ItemKind: constant item");
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            let unsupported_msg = "Error generating bindings for `<SomeStruct as Named>::name` \
+                                   defined at <crubit_unittests.rs>;l=15: \
+                                   The name of `name` clashes with another member of \
+                                   `SomeStruct`, but it can't be exposed in the `Named` \
+                                   namespace, because the namespace would clash with the \
+                                   bindings of the trait";
+            assert_cc_matches!(bindings.h_body, quote! { __COMMENT__ #unsupported_msg });
+            assert_cc_not_matches!(bindings.h_body, quote! { namespace Named });
         });
     }
 
     #[test]
-    fn test_format_item_unsupported_type_alias() {
+    fn test_generated_bindings_trait_impl_name_clash_with_same_named_trait() {
         let test_src = r#"
-                pub type TypeAlias = i32;
+                pub mod a {
+                    pub trait Named {
+                        fn name(&self) -> i32;
+                    }
+                }
+                pub mod b {
+                    pub trait Named {
+                        fn name(&self) -> i32;
+                    }
+                }
+
+                pub struct SomeStruct(i32);
+
+                impl a::Named for SomeStruct {
+                    fn name(&self) -> i32 {
+                        self.0
+                    }
+                }
+
+                impl b::Named for SomeStruct {
+                    fn name(&self) -> i32 {
+                        -self.0
+                    }
+                }
             "#;
-        test_format_item(test_src, "TypeAlias", |result| {
-            // TODO(b/254096006): Add support for type alias definitions.
-            let err = result.unwrap_err();
-            assert_eq!(err, "Unsupported rustc_hir::hir::ItemKind: type alias");
+        test_generated_bindings(test_src, |bindings| {
+            let bindings = bindings.unwrap();
+            let unsupported_msg_a = "Error generating bindings for \
+                                     `<SomeStruct as a::Named>::name` \
+                                     defined at <crubit_unittests.rs>;l=16: \
+                                     The name of `name` clashes with another member of \
+                                     `SomeStruct`, but it can't be exposed in the `Named` \
+                                     namespace, because another trait with the same name also \
+                                     has an item named `name`";
+            let unsupported_msg_b = "Error generating bindings for \
+                                     `<SomeStruct as b::Named>::name` \
+                                     defined at <crubit_unittests.rs>;l=22: \
+                                     The name of `name` clashes with another member of \
+                                     `SomeStruct`, but it can't be exposed in the `Named` \
+                                     namespace, because another trait with the same name also \
+                                     has an item named `name`";
+            assert_cc_matches!(bindings.h_body, quote! { __COMMENT__ #unsupported_msg_a });
+            assert_cc_matches!(bindings.h_body, quote! { __COMMENT__ #unsupported_msg_b });
+            assert_cc_not_matches!(bindings.h_body, quote! { namespace Named });
         });
     }
 
     #[test]
-    fn test_format_item_unsupported_impl_item_const_value() {
+    fn test_format_item_unsupported_trait_impl_assoc_type() {
         let test_src = r#"
                 pub struct SomeStruct(i32);
 
-                impl SomeStruct {
-                    pub const CONST_VALUE: i32 = 42;
+                pub trait SomeTrait {
+                    type Output;
+                }
+
+                impl SomeTrait for SomeStruct {
+                    type Output = i32;
                 }
             "#;
         test_format_item(test_src, "SomeStruct", |result| {
             let result = result.unwrap();
             let main_api = get_main_api_snippet(&result);
-            assert!(!main_api.prereqs.is_empty());
-            let unsupported_msg = "Error generating bindings for `SomeStruct::CONST_VALUE` \
-                                   defined at <crubit_unittests.rs>;l=5: \
-                                   Unsupported `impl` item kind: Const";
+            let unsupported_msg = "Error generating bindings for \
+                                   `<SomeStruct as SomeTrait>::Output` \
+                                   defined at <crubit_unittests.rs>;l=9: \
+                                   Associated types of trait `impl`s are not supported yet";
             assert_cc_matches!(
                 main_api.tokens,
                 quote! {
@@ -4433,14 +14254,83 @@ pub mod tests {
             ("SomeStruct", ("::rust_out::SomeStruct", "", "SomeStruct", "")),
             ("SomeEnum", ("::rust_out::SomeEnum", "", "SomeEnum", "")),
             ("SomeUnion", ("::rust_out::SomeUnion", "", "SomeUnion", "")),
+            // Types re-exported from a private module are named by their public path:
+            ("ReexportedStruct", ("::rust_out::ReexportedStruct", "", "ReexportedStruct", "")),
             ("*const i32", ("const std::int32_t*", "<cstdint>", "", "")),
             ("*mut i32", ("std::int32_t*", "<cstdint>", "", "")),
             // `SomeStruct` is a `fwd_decls` prerequisite (not `defs` prerequisite):
             ("*mut SomeStruct", ("::rust_out::SomeStruct*", "", "", "SomeStruct")),
             // Testing propagation of deeper/nested `fwd_decls`:
             ("*mut *mut SomeStruct", (":: rust_out :: SomeStruct * *", "", "", "SomeStruct")),
+            // References:
+            ("&'static i32", ("const std::int32_t&", "<cstdint>", "", "")),
+            ("&'static mut i32", ("std::int32_t&", "<cstdint>", "", "")),
+            // `SomeStruct` is a `fwd_decls` prerequisite (not `defs` prerequisite):
+            ("&'static SomeStruct", ("const ::rust_out::SomeStruct&", "", "", "SomeStruct")),
+            ("&'static *const SomeStruct", (
+                "const ::rust_out::SomeStruct* const&", "", "", "SomeStruct")),
+            // Slice references:
+            ("&'static [i32]", (
+                "rs_std::SliceRef<const std::int32_t>",
+                "\"crubit/support/for/tests/rs_std/slice_ref.h\"", "", "")),
+            ("&'static mut [i32]", (
+                "rs_std::SliceRef<std::int32_t>",
+                "\"crubit/support/for/tests/rs_std/slice_ref.h\"", "", "")),
+            ("&'static [*const i32]", (
+                "rs_std::SliceRef<const std::int32_t* const>",
+                "\"crubit/support/for/tests/rs_std/slice_ref.h\"", "", "")),
+            ("&'static [SomeStruct]", (
+                "rs_std::SliceRef<const ::rust_out::SomeStruct>",
+                "\"crubit/support/for/tests/rs_std/slice_ref.h\"", "", "SomeStruct")),
+            // String references:
+            ("&'static str", ("rs_std::StrRef", "\"crubit/support/for/tests/rs_std/str_ref.h\"", "", "")),
+            // Boxes (`SomeStruct` is a `fwd_decls` prerequisite):
+            ("Box<SomeStruct>", (
+                "rs_std::Box<::rust_out::SomeStruct>",
+                "\"crubit/support/for/tests/rs_std/box.h\"", "", "SomeStruct")),
+            ("Option<Box<SomeStruct>>", (
+                "rs_std::Box<::rust_out::SomeStruct>",
+                "\"crubit/support/for/tests/rs_std/box.h\"", "", "SomeStruct")),
+            // Options and results:
+            ("Option<i32>", ("std::optional<std::int32_t>", "<optional>", "", "")),
+            ("Option<SomeStruct>", (
+                "std::optional<::rust_out::SomeStruct>", "<optional>", "SomeStruct", "")),
+            ("Result<i32, SomeStruct>", (
+                "rs_std::Result<std::int32_t, ::rust_out::SomeStruct>",
+                "\"crubit/support/for/tests/rs_std/result.h\"", "SomeStruct", "")),
+            ("Result<(), SomeStruct>", (
+                "rs_std::Result<std::monostate, ::rust_out::SomeStruct>",
+                "<variant>", "SomeStruct", "")),
+            // Strings and vectors:
+            ("String", ("rs_std::String", "\"crubit/support/for/tests/rs_std/rs_string.h\"", "", "")),
+            ("Vec<i32>", (
+                "rs_std::Vec<std::int32_t>",
+                "\"crubit/support/for/tests/rs_std/vec.h\"", "", "")),
+            ("Vec<SomeStruct>", (
+                "rs_std::Vec<::rust_out::SomeStruct>",
+                "\"crubit/support/for/tests/rs_std/vec.h\"", "SomeStruct", "")),
+            ("Option<String>", ("std::optional<rs_std::String>", "<optional>", "", "")),
+            // Function pointers (`SomeStruct` is a `fwd_decls` prerequisite):
+            ("extern \"C\" fn()", ("std::add_pointer_t<void()>", "<type_traits>", "", "")),
+            ("unsafe extern \"C\" fn(i32, f64) -> i32", (
+                "std::add_pointer_t<std::int32_t(std::int32_t, double)>",
+                "<type_traits>", "", "")),
+            ("extern \"C\" fn(&SomeStruct) -> bool", (
+                "std::add_pointer_t<bool(const ::rust_out::SomeStruct&)>",
+                "<type_traits>", "", "SomeStruct")),
+            ("fn(i32) -> i32", (
+                "rs_std::FnPtr<std::int32_t(std::int32_t)>",
+                "\"crubit/support/for/tests/rs_std/fn_ptr.h\"", "", "")),
             // Extra parens/sugar are expected to be ignored:
             ("(bool)", ("bool", "", "", "")),
+            // Non-empty tuples:
+            ("(bool,)", ("std::tuple<bool>", "<tuple>", "", "")),
+            ("(bool, f64)", ("std::tuple<bool, double>", "<tuple>", "", "")),
+            // Testing propagation of `defs` from tuple elements:
+            (
+                "(bool, SomeStruct)",
+                ("std::tuple<bool, ::rust_out::SomeStruct>", "<tuple>", "SomeStruct", ""),
+            ),
         ];
         let preamble = quote! {
             #![allow(unused_parens)]
@@ -4457,6 +14347,13 @@ pub mod tests {
                 pub x: i32,
                 pub y: i32,
             }
+
+            mod private_submodule {
+                pub struct ReexportedStruct {
+                    pub x: i32,
+                }
+            }
+            pub use private_submodule::*;
         };
         test_ty(
             &testcases,
@@ -4539,24 +14436,51 @@ pub mod tests {
                 "The never type `!` is only supported as a return type (b/254507801)"
             ),
             (
-                "(i32, i32)", // Non-empty TyKind::Tuple
-                "Tuples are not supported yet: (i32, i32) (b/254099023)",
+                "((i32, i32), i32)", // Nested TyKind::Tuple
+                "Nested tuples are not supported yet: ((i32, i32), i32) (b/254099023)",
+            ),
+            (
+                "(i32, [i32; 42])", // TyKind::Tuple with an unsupported element type
+                "Failed to format element #1 of the tuple type `(i32, [i32; 42])`: \
+                 The following Rust type is not supported yet: [i32; 42]",
+            ),
+            (
+                "*const (i32, i32)", // TyKind::Tuple underneath TyKind::RawPtr
+                "Pointers to tuples are not supported yet: (i32, i32) (b/254099023)",
             ),
             (
-                "&'static i32", // TyKind::Ref
-                "The following Rust type is not supported yet: &'static i32",
+                "&'static &'static i32", // TyKind::Ref underneath TyKind::Ref
+                "References to references are not supported yet: \
+                 &'static &'static i32 (b/258235219)",
+            ),
+            (
+                "*const &'static i32", // TyKind::Ref underneath TyKind::RawPtr
+                "Pointers to references are not supported yet: &'static i32 (b/258235219)",
+            ),
+            (
+                "(i32, &'static i32)", // TyKind::Ref underneath TyKind::Tuple
+                "References in tuples are not supported yet: (i32, &'static i32) (b/258235219)",
             ),
             (
                 "[i32; 42]", // TyKind::Array
                 "The following Rust type is not supported yet: [i32; 42]",
             ),
             (
-                "&'static [i32]", // TyKind::Slice (nested underneath TyKind::Ref)
-                "The following Rust type is not supported yet: &'static [i32]",
+                "[i32]", // TyKind::Slice (not nested underneath TyKind::Ref)
+                "The following Rust type is not supported yet: [i32]",
+            ),
+            (
+                "&'static [(i32, i32)]", // TyKind::Tuple underneath TyKind::Slice
+                "References to tuples are not supported yet: (i32, i32) (b/254099023)",
+            ),
+            (
+                "&'static [[i32; 42]]", // TyKind::Slice with an unsupported element type
+                "Failed to format the element type of the slice type `&'static [[i32; 42]]`: \
+                 The following Rust type is not supported yet: [i32; 42]",
             ),
             (
-                "&'static str", // TyKind::Str (nested underneath TyKind::Ref)
-                "The following Rust type is not supported yet: &'static str",
+                "&'static mut str", // TyKind::Str (nested underneath a `mut` TyKind::Ref)
+                "`&mut str` is not supported yet: &'static mut str",
             ),
             (
                 "impl Eq", // TyKind::Alias
@@ -4566,14 +14490,10 @@ pub mod tests {
                 "fn(i32) -> i32", // TyKind::FnPtr
                 "The following Rust type is not supported yet: fn(i32) -> i32",
             ),
-            // TODO(b/254094650): Consider mapping this to Clang's (and GCC's) `__int128`
-            // or to `absl::in128`.
-            ("i128", "C++ doesn't have a standard equivalent of `i128` (b/254094650)"),
-            ("u128", "C++ doesn't have a standard equivalent of `u128` (b/254094650)"),
             (
-                "StructWithCustomDrop",
-                "Failed to generate bindings for the definition of `StructWithCustomDrop`: \
-                 `Drop` trait and \"drop glue\" are not supported yet (b/258251148)"
+                "ZeroSizedStruct",
+                "Failed to generate bindings for the definition of `ZeroSizedStruct`: \
+                 Zero-sized types (ZSTs) are not supported (b/258259459)"
             ),
             (
                 "ConstGenericStruct<42>",
@@ -4596,45 +14516,148 @@ pub mod tests {
             ),
             (
                 "std::cmp::Ordering",
-                "Cross-crate dependencies are not supported yet (b/258261328)",
+                "Type `std::cmp::Ordering` comes from the `core` crate, \
+                 but no `--crate-header` was specified for this crate",
             ),
             (
-                "Option<i8>",
-                "Generic types are not supported yet (b/259749095)",
+                "Option<(i32, i32)>",
+                "Tuples in `Option` and `Result` are not supported yet: \
+                 std::option::Option<(i32, i32)>",
+            ),
+            (
+                "Option<&'static i32>",
+                "References in `Option` and `Result` are not supported yet: \
+                 std::option::Option<&'static i32>",
+            ),
+            (
+                "Option<Option<i8>>",
+                "Nested `Option` and `Result` types are not supported yet: \
+                 std::option::Option<std::option::Option<i8>>",
+            ),
+            (
+                "Option<()>",
+                "`Option<()>` is not supported yet",
+            ),
+            (
+                "Result<i32, ()>",
+                "`Result<T, ()>` is not supported yet",
+            ),
+            (
+                "Result<i32, [i32; 42]>",
+                "Failed to format a type argument of `std::result::Result<i32, [i32; 42]>`: \
+                 The following Rust type is not supported yet: [i32; 42]",
+            ),
+            (
+                "&'static Option<i8>",
+                "References to `Option` and `Result` are not supported yet: \
+                 std::option::Option<i8>",
+            ),
+            (
+                "(i32, Option<i8>)",
+                "`Option` and `Result` in tuples are not supported yet: \
+                 (i32, std::option::Option<i8>)",
+            ),
+            (
+                "Box<Option<ZeroSizedStruct>>",
+                "`Box<T>` is only supported for structs, enums, and unions: \
+                 std::boxed::Box<std::option::Option<ZeroSizedStruct>>",
             ),
             (
                 "PublicReexportOfStruct",
-                "Not directly public type (re-exports are not supported yet - b/262052635)",
+                "Not a public type (renaming re-exports are not supported yet - b/262052635)",
             ),
             (
-                // This testcase is like `PublicReexportOfStruct`, but the private type and the
-                // re-export are in another crate.  When authoring this test
-                // `core::alloc::LayoutError` was a public re-export of
-                // `core::alloc::layout::LayoutError`:
+                // This testcase verifies that re-exports are followed for types from other
+                // crates.  When authoring this test `core::alloc::LayoutError` was a public
+                // re-export of `core::alloc::layout::LayoutError`:
                 // `https://play.rust-lang.org/?version=stable&mode=debug&edition=2021&gist=d2b5528af9b33b25abe44cc4646d65e3`
                 // TODO(b/258261328): Once cross-crate bindings are supported we should try
                 // to test them via a test crate that we control (rather than testing via
                 // implementation details of the std crate).
                 "core::alloc::LayoutError",
-                "Not directly public type (re-exports are not supported yet - b/262052635)",
+                "Type `std::alloc::LayoutError` comes from the `core` crate, \
+                 but no `--crate-header` was specified for this crate",
             ),
             (
                 "*const Option<i8>",
-                "Failed to format the pointee of the pointer type `std::option::Option<i8>`: \
-                 Generic types are not supported yet (b/259749095)",
+                "Pointers to `Option` and `Result` are not supported yet: std::option::Option<i8>",
+            ),
+            (
+                "*const String",
+                "Pointers to `String` and `Vec` are not supported yet: std::string::String",
+            ),
+            (
+                "fn(i32, (i32, i32))",
+                "Function pointer parameters need to have a C-compatible ABI: \
+                 `(i32, i32)` in `fn(i32, (i32, i32))`",
+            ),
+            (
+                "extern \"C\" fn() -> Option<i32>",
+                "Function pointer return types need to have a C-compatible ABI: \
+                 `std::option::Option<i32>` in `extern \"C\" fn() -> std::option::Option<i32>`",
+            ),
+            (
+                "fn() -> &'static i32",
+                "Rust-ABI function pointers returning references are not supported yet: \
+                 fn() -> &'static i32",
+            ),
+            (
+                "Option<fn()>",
+                "Rust-ABI function pointers in `Option` and `Result` are not supported yet: \
+                 std::option::Option<fn()>",
+            ),
+            (
+                "&'static fn()",
+                "References to Rust-ABI function pointers are not supported yet: fn()",
+            ),
+            (
+                "unsafe extern \"C\" fn(i32, ...)",
+                "C-variadic function pointers are not supported: \
+                 unsafe extern \"C\" fn(i32, ...)",
+            ),
+            (
+                "&'static Vec<i32>",
+                "References to `String` and `Vec` are not supported yet \
+                 (`&str` and `&[T]` are supported): std::vec::Vec<i32>",
+            ),
+            (
+                "Vec<(i32, i32)>",
+                "Tuples in `Vec` are not supported yet: std::vec::Vec<(i32, i32)> (b/254099023)",
+            ),
+            (
+                "Vec<Option<i32>>",
+                "`Option`, `Result`, `String`, and `Vec` in `Vec` are not supported yet: \
+                 std::vec::Vec<std::option::Option<i32>>",
+            ),
+            (
+                "Vec<[i32; 42]>",
+                "Failed to format the element type of `std::vec::Vec<[i32; 42]>`: \
+                 The following Rust type is not supported yet: [i32; 42]",
+            ),
+            (
+                "Box<i32>",
+                "`Box<T>` is only supported for structs, enums, and unions: std::boxed::Box<i32>",
+            ),
+            (
+                "Box<ZeroSizedStruct>",
+                "Failed to format the pointee of `std::boxed::Box<ZeroSizedStruct>`: \
+                 Failed to generate bindings for the definition of `ZeroSizedStruct`: \
+                 Zero-sized types (ZSTs) are not supported (b/258259459)",
+            ),
+            (
+                "&'static dyn std::fmt::Debug",
+                "Trait objects of traits from other crates are not supported yet",
+            ),
+            (
+                "Box<dyn std::any::Any + Send>",
+                "Trait objects with auto traits or associated types are not supported yet: \
+                 dyn std::any::Any + std::marker::Send",
             ),
         ];
         let preamble = quote! {
             #![feature(never_type)]
 
-            pub struct StructWithCustomDrop {
-                pub x: i32,
-                pub y: i32,
-            }
-
-            impl Drop for StructWithCustomDrop {
-                fn drop(&mut self) {}
-            }
+            pub struct ZeroSizedStruct;
 
             pub struct ConstGenericStruct<const N: usize> {
                 pub arr: [u8; N],
@@ -4662,6 +14685,150 @@ pub mod tests {
         });
     }
 
+    /// `test_format_ty_for_cc_int128` provides test coverage for `i128` and
+    /// `u128`.  The expectations depend on the layout of these types on the
+    /// target platform (e.g. Rust uses 8-byte alignment for `i128` on some
+    /// targets).
+    #[test]
+    fn test_format_ty_for_cc_int128() {
+        let testcases = [
+            // ( <Rust type>, (<expected builtin C++ type>, <expected Abseil C++ type>) )
+            ("i128", ("__int128", "absl::int128")),
+            ("u128", ("unsigned __int128", "absl::uint128")),
+        ];
+        test_ty(&testcases, quote! {}, |desc, tcx, ty, (expected_builtin, expected_absl)| {
+            // The expectations below depend on the layout that `tcx` reports for the
+            // target - `i128` is 16-byte aligned on some targets and 8-byte aligned on
+            // others.
+            let layout = tcx.layout_of(ty::ParamEnv::empty().and(ty)).unwrap().layout;
+            let align = layout.align().abi.bytes();
+            assert_eq!(layout.size().bytes(), 16, "{desc}");
+            assert!(align == 8 || align == 16, "{desc}: align = {align}");
+            let mut input = bindings_input_for_tests(tcx);
+
+            // Function parameters and return values are supported regardless of the
+            // alignment (see `format_int128_for_cc`).
+            let CcSnippet { tokens, .. } = format_param_ty_for_cc(&input, ty).unwrap();
+            let expected_tokens = expected_builtin.parse::<TokenStream>().unwrap().to_string();
+            assert_eq!(tokens.to_string(), expected_tokens, "{desc}");
+            let CcSnippet { tokens, .. } = format_ret_ty_for_cc(&input, ty).unwrap();
+            assert_eq!(tokens.to_string(), expected_tokens, "{desc}");
+
+            if align != 16 {
+                let anyhow_err = format_ty_for_cc(&input, ty).unwrap_err();
+                assert_eq!(
+                    format!("{anyhow_err:#}"),
+                    format!(
+                        "`{ty}` has a different layout in Rust (size = 16, align = {align}) \
+                         than in C++ (size = 16, align = 16) on this target (b/254094650)"
+                    ),
+                    "{desc}"
+                );
+                return;
+            }
+
+            let CcSnippet { tokens, prereqs } = format_ty_for_cc(&input, ty).unwrap();
+            let expected_tokens = expected_builtin.parse::<TokenStream>().unwrap().to_string();
+            assert_eq!(tokens.to_string(), expected_tokens, "{desc}");
+            assert_cc_matches!(
+                format_cc_includes(&prereqs.includes),
+                quote! { __HASH_TOKEN__ include "crubit/support/for/tests/internal/int128.h" }
+            );
+
+            input.int128_mapping = Int128Mapping::Absl;
+            let CcSnippet { tokens, prereqs } = format_ty_for_cc(&input, ty).unwrap();
+            let expected_tokens = expected_absl.parse::<TokenStream>().unwrap().to_string();
+            assert_eq!(tokens.to_string(), expected_tokens, "{desc}");
+            assert_cc_matches!(
+                format_cc_includes(&prereqs.includes),
+                quote! { __HASH_TOKEN__ include "absl/numeric/int128.h" }
+            );
+        });
+    }
+
+    /// `test_format_ty_for_cc_result_mapping` provides test coverage for
+    /// `ResultMapping::AbslStatusOr`.
+    #[test]
+    fn test_format_ty_for_cc_result_mapping() {
+        let testcases = [
+            // ( <Rust type>, (<expected C++ type>, <expected #include>) )
+            ("Result<i32, DisplayError>", (
+                "absl::StatusOr<std::int32_t>", "\"absl/status/statusor.h\"")),
+            ("Result<(), DisplayError>", ("absl::Status", "\"absl/status/status.h\"")),
+            // `E` needs to implement `Display`:
+            ("Result<i32, NonDisplayError>", (
+                "rs_std::Result<std::int32_t, ::rust_out::NonDisplayError>",
+                "\"crubit/support/for/tests/rs_std/result.h\"")),
+            // `ToString` is not generated for `enum class`es:
+            ("Result<i32, DisplayEnumClass>", (
+                "rs_std::Result<std::int32_t, ::rust_out::DisplayEnumClass>",
+                "\"crubit/support/for/tests/rs_std/result.h\"")),
+        ];
+        let preamble = quote! {
+            pub struct DisplayError {
+                pub code: i32,
+            }
+            impl std::fmt::Display for DisplayError {
+                fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                    write!(f, "Error #{}", self.code)
+                }
+            }
+            pub struct NonDisplayError {
+                pub code: i32,
+            }
+            pub enum DisplayEnumClass {
+                Foo,
+                Bar,
+            }
+            impl std::fmt::Display for DisplayEnumClass {
+                fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                    write!(f, "DisplayEnumClass")
+                }
+            }
+        };
+        test_ty(&testcases, preamble, |desc, tcx, ty, (expected_tokens, expected_include)| {
+            let mut input = bindings_input_for_tests(tcx);
+            input.result_mapping = ResultMapping::AbslStatusOr;
+            let CcSnippet { tokens, prereqs } = format_ty_for_cc(&input, ty).unwrap();
+            let expected_tokens = expected_tokens.parse::<TokenStream>().unwrap().to_string();
+            assert_eq!(tokens.to_string(), expected_tokens, "{desc}");
+            let expected_include: TokenStream = expected_include.parse().unwrap();
+            assert_cc_matches!(
+                format_cc_includes(&prereqs.includes),
+                quote! { __HASH_TOKEN__ include #expected_include }
+            );
+        });
+    }
+
+    /// `test_format_ty_for_cc_cross_crate` provides test coverage for types
+    /// that come from another crate.  `core` is used as the other crate
+    /// (until we can test via a test crate that we control).
+    #[test]
+    fn test_format_ty_for_cc_cross_crate() {
+        let testcases = [
+            // ( <Rust type>, <expected C++ type> )
+            ("std::cmp::Ordering", "::core::cmp::Ordering"),
+            ("*const std::cmp::Ordering", "const ::core::cmp::Ordering*"),
+        ];
+        test_ty(&testcases, quote! {}, |desc, tcx, ty, expected_tokens| {
+            let mut input = bindings_input_for_tests(tcx);
+            input
+                .crate_name_to_include_path
+                .insert("core".into(), CcInclude::user_header("some/path/core_cc_api.h".into()));
+            let CcSnippet { tokens, prereqs } = format_ty_for_cc(&input, ty).unwrap();
+
+            let expected_tokens = expected_tokens.parse::<TokenStream>().unwrap().to_string();
+            assert_eq!(tokens.to_string(), expected_tokens, "{desc}");
+
+            assert!(prereqs.defs.is_empty(), "{desc}");
+            assert!(prereqs.fwd_decls.is_empty(), "{desc}");
+            assert_cc_matches!(
+                format_cc_includes(&prereqs.includes),
+                quote! { __HASH_TOKEN__ include "some/path/core_cc_api.h" }
+            );
+        });
+    }
+
     #[test]
     fn test_format_ty_for_rs_successes() {
         // Test coverage for cases where `format_ty_for_rs` returns an `Ok(...)`.
@@ -4685,6 +14852,10 @@ pub mod tests {
             ("char", "char"),
             ("!", "!"),
             ("()", "()"),
+            // Non-empty tuples:
+            ("(bool,)", "(bool,)"),
+            ("(i32, f64)", "(i32, f64,)"),
+            ("(i32, SomeStruct)", "(i32, ::rust_out::SomeStruct,)"),
             // ADTs:
             ("SomeStruct", "::rust_out::SomeStruct"),
             ("SomeEnum", "::rust_out::SomeEnum"),
@@ -4696,6 +14867,30 @@ pub mod tests {
             ("*mut i32", "*mut i32"),
             // Pointer to an ADT:
             ("*mut SomeStruct", "* mut :: rust_out :: SomeStruct"),
+            // References (only `'static` lifetimes are preserved - see `liberate_fn_sig`):
+            ("&'static i32", "& 'static i32"),
+            ("&'static mut SomeStruct", "& 'static mut :: rust_out :: SomeStruct"),
+            // Slice and string references:
+            ("&'static [i32]", "& 'static [i32]"),
+            ("&'static mut [SomeStruct]", "& 'static mut [:: rust_out :: SomeStruct]"),
+            ("&'static str", "& 'static str"),
+            // Raw slice pointers (used when returning slice references from thunks):
+            ("*const [i32]", "*const [i32]"),
+            // Boxes and options:
+            ("Box<SomeStruct>", "::std::boxed::Box<::rust_out::SomeStruct>"),
+            ("Option<i8>", "::core::option::Option<i8>"),
+            (
+                "Option<Box<SomeStruct>>",
+                "::core::option::Option<::std::boxed::Box<::rust_out::SomeStruct>>",
+            ),
+            ("Result<(), SomeStruct>", "::core::result::Result<(), ::rust_out::SomeStruct>"),
+            // Strings and vectors:
+            ("String", "::std::string::String"),
+            ("Vec<SomeStruct>", "::std::vec::Vec<::rust_out::SomeStruct>"),
+            // Function pointers (non-`'static` lifetimes are elided):
+            ("fn(i32) -> i32", "fn(i32) -> i32"),
+            ("extern \"C\" fn()", "extern \"C\" fn() -> ()"),
+            ("unsafe fn(&SomeStruct) -> bool", "unsafe fn(&::rust_out::SomeStruct) -> bool"),
         ];
         let preamble = quote! {
             #![feature(never_type)]
@@ -4727,24 +14922,18 @@ pub mod tests {
         let testcases = [
             // ( <Rust type>, <expected error message> )
             (
-                "(i32, i32)", // Non-empty TyKind::Tuple
-                "Tuples are not supported yet: (i32, i32) (b/254099023)",
-            ),
-            (
-                "&'static i32", // TyKind::Ref
-                "The following Rust type is not supported yet: &'static i32",
+                "(i32, [i32; 42])", // TyKind::Tuple with an unsupported element type
+                "The following Rust type is not supported yet: [i32; 42]",
             ),
             (
                 "[i32; 42]", // TyKind::Array
                 "The following Rust type is not supported yet: [i32; 42]",
             ),
             (
-                "&'static [i32]", // TyKind::Slice (nested underneath TyKind::Ref)
-                "The following Rust type is not supported yet: &'static [i32]",
-            ),
-            (
-                "&'static str", // TyKind::Str (nested underneath TyKind::Ref)
-                "The following Rust type is not supported yet: &'static str",
+                "&'static [[i32; 42]]", // TyKind::Slice with an unsupported element type
+                "Failed to format the referent of the reference type `&'static [[i32; 42]]`: \
+                 Failed to format the element type of the slice type `[[i32; 42]]`: \
+                 The following Rust type is not supported yet: [i32; 42]",
             ),
             (
                 "impl Eq", // TyKind::Alias
@@ -4755,7 +14944,7 @@ pub mod tests {
                 "The following Rust type is not supported yet: fn(i32) -> i32",
             ),
             (
-                "Option<i8>", // TyKind::Adt - generic + different crate
+                "std::num::Wrapping<i8>", // TyKind::Adt - generic + different crate
                 "Generic types are not supported yet (b/259749095)",
             ),
         ];
@@ -4841,13 +15030,33 @@ pub mod tests {
     /// (`test_function` should typically `assert!` that it got the expected
     /// result from `format_item`.)
     fn test_format_item<F, T>(source: &str, name: &str, test_function: F) -> T
+    where
+        F: FnOnce(Result<Vec<(SnippetKey, MixedSnippet)>, String>) -> T + Send,
+        T: Send,
+    {
+        test_format_item_with_instantiations(source, name, &[], test_function)
+    }
+
+    /// Like `test_format_item`, but also populates `Input::instantiations`
+    /// based on `instantiations` (see also `parse_instantiations`).
+    fn test_format_item_with_instantiations<F, T>(
+        source: &str,
+        name: &str,
+        instantiations: &[&str],
+        test_function: F,
+    ) -> T
     where
         F: FnOnce(Result<Vec<(SnippetKey, MixedSnippet)>, String>) -> T + Send,
         T: Send,
     {
         run_compiler_for_testing(source, |tcx| {
             let def_id = find_def_id_by_name(tcx, name);
-            let result = format_item(&bindings_input_for_tests(tcx), def_id);
+            let input = Input {
+                instantiations: parse_instantiations(tcx, instantiations.iter().copied())
+                    .unwrap(),
+                ..bindings_input_for_tests(tcx)
+            };
+            let result = format_item(&input, def_id);
 
             // Sort the vector of results to make the tests more deterministic.  Below (i.e. in
             // tests) we use a somewhat arbitrary SnippetKey-based order.  The order of these
@@ -4870,7 +15079,14 @@ pub mod tests {
     /// Finds the definition id of a Rust item with the specified `name`.
     /// Panics if no such item is found, or if there is more than one match.
     fn find_def_id_by_name(tcx: TyCtxt, name: &str) -> LocalDefId {
-        let hir_items = || tcx.hir().items().map(|item_id| tcx.hir().item(item_id));
+        // `use` items are skipped, because `pub use foo::SomeName;` would otherwise be
+        // ambiguous with the definition of `SomeName`.
+        let hir_items = || {
+            tcx.hir()
+                .items()
+                .map(|item_id| tcx.hir().item(item_id))
+                .filter(|item| !matches!(item.kind, ItemKind::Use(..)))
+        };
         let items_with_matching_name =
             hir_items().filter(|item| item.ident.name.as_str() == name).collect_vec();
         match *items_with_matching_name.as_slice() {
@@ -4894,7 +15110,12 @@ pub mod tests {
             tcx,
             crubit_support_path: "crubit/support/for/tests".into(),
             _features: (),
-            _crate_to_include_map: (),
+            panic_handling: PanicHandling::Abort,
+            int128_mapping: Int128Mapping::Builtin,
+            result_mapping: ResultMapping::RsStd,
+            exclude_unsafe_fns: false,
+            crate_name_to_include_path: Default::default(),
+            instantiations: Default::default(),
         }
     }
 
@@ -12,12 +12,14 @@ extern crate rustc_error_codes;
 extern crate rustc_errors;
 extern crate rustc_feature;
 extern crate rustc_hir;
+extern crate rustc_infer;
 extern crate rustc_interface;
 extern crate rustc_lint_defs;
 extern crate rustc_middle;
 extern crate rustc_session;
 extern crate rustc_span;
 extern crate rustc_target;
+extern crate rustc_trait_selection;
 
 // TODO(b/254679226): `bindings`, `cmdline`, and `run_compiler` should be
 // separate crates.
@@ -26,6 +28,7 @@ mod cmdline;
 mod run_compiler;
 
 use anyhow::Context;
+use code_gen_utils::CcInclude;
 use itertools::Itertools;
 use rustc_middle::ty::TyCtxt; // See also <internal link>/ty.html#import-conventions
 use std::path::Path;
@@ -45,7 +48,49 @@ fn run_with_tcx(cmdline: &Cmdline, tcx: TyCtxt) -> anyhow::Result<()> {
     use bindings::*;
     let Output { h_body, rs_body } = {
         let crubit_support_path = cmdline.crubit_support_path.as_str().into();
-        let input = Input { tcx, crubit_support_path, _features: (), _crate_to_include_map: () };
+        let crate_name_to_include_path = cmdline
+            .crate_headers
+            .iter()
+            .map(|(crate_name, include_path)| {
+                (crate_name.as_str().into(), CcInclude::user_header(include_path.as_str().into()))
+            })
+            .collect();
+        let panic_handling = match cmdline.panic_handling {
+            cmdline::PanicHandling::Abort => PanicHandling::Abort,
+            cmdline::PanicHandling::Report => PanicHandling::Report,
+        };
+        let int128_mapping = match cmdline.int128_mapping {
+            cmdline::Int128Mapping::Builtin => Int128Mapping::Builtin,
+            cmdline::Int128Mapping::Absl => Int128Mapping::Absl,
+        };
+        let result_mapping = match cmdline.result_mapping {
+            cmdline::ResultMapping::RsStd => ResultMapping::RsStd,
+            cmdline::ResultMapping::AbslStatusOr => ResultMapping::AbslStatusOr,
+        };
+        let instantiations = match cmdline.instantiations_file.as_ref() {
+            None => Default::default(),
+            Some(path) => {
+                let content = std::fs::read_to_string(path)
+                    .with_context(|| format!("Error when reading {}", path.display()))?;
+                let lines = content
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty() && !line.starts_with('#'));
+                parse_instantiations(tcx, lines)
+                    .with_context(|| format!("Error when parsing {}", path.display()))?
+            }
+        };
+        let input = Input {
+            tcx,
+            crubit_support_path,
+            _features: (),
+            panic_handling,
+            int128_mapping,
+            result_mapping,
+            exclude_unsafe_fns: cmdline.exclude_unsafe_fns,
+            crate_name_to_include_path,
+            instantiations,
+        };
         generate_bindings(&input)?
     };
 
@@ -364,21 +409,55 @@ extern "C" fn __crubit_thunk__ANY_IDENTIFIER_CHARACTERS()
         Ok(())
     }
 
-    /// `test_rustc_unsupported_panic_mechanism` tests that `panic=unwind` results
-    /// in an error.
+    /// `test_rustc_panic_mechanism_unwind_abort` tests that with `panic=unwind` the generated
+    /// thunks catch panics and abort the process by default.
     ///
     /// This is tested at the `cc_bindings_from_rs.rs` level instead of at the `bindings.rs` level,
     /// because `run_compiler::tests::run_compiler_for_testing` doesn't support specifying a custom
     /// panic mechanism.
     #[test]
-    fn test_rustc_unsupported_panic_mechanism() -> anyhow::Result<()> {
-        let err = TestArgs::default_args()?
+    fn test_rustc_panic_mechanism_unwind_abort() -> anyhow::Result<()> {
+        let test_result = TestArgs::default_args()?.with_panic_mechanism("unwind").run()?;
+
+        let rs_body = std::fs::read_to_string(&test_result.rs_path)?;
+        assert!(rs_body.contains("fn __crubit_abort_on_panic("), "rs_body = {rs_body}");
+        assert!(rs_body.contains("::std::panic::catch_unwind("), "rs_body = {rs_body}");
+        assert!(!rs_body.contains("__report_panic"), "rs_body = {rs_body}");
+
+        let h_body = std::fs::read_to_string(&test_result.h_path)?;
+        assert!(!h_body.contains("PanicSlot"), "h_body = {h_body}");
+        Ok(())
+    }
+
+    /// `test_rustc_panic_mechanism_unwind_report` tests that with `panic=unwind` and
+    /// `--panic-handling=report` the generated thunks report panics to the C++ caller.
+    #[test]
+    fn test_rustc_panic_mechanism_unwind_report() -> anyhow::Result<()> {
+        let test_result = TestArgs::default_args()?
             .with_panic_mechanism("unwind")
-            .run()
-            .expect_err("panic=unwind should trigger an error");
+            .with_extra_crubit_args(&["--panic-handling=report"])
+            .run()?;
 
-        let msg = format!("{err:#}");
-        assert_eq!("No support for panic=unwind strategy (b/254049425)", msg);
+        let rs_body = std::fs::read_to_string(&test_result.rs_path)?;
+        assert!(rs_body.contains("::std::panic::catch_unwind("), "rs_body = {rs_body}");
+        assert!(rs_body.contains("__report_panic("), "rs_body = {rs_body}");
+
+        let h_body = std::fs::read_to_string(&test_result.h_path)?;
+        assert!(h_body.contains("crubit::internal::PanicSlot __panic_slot;"), "h_body = {h_body}");
+        assert!(h_body.contains("std::move(__panic_slot).ThrowIfPanicked();"), "h_body = {h_body}");
+        Ok(())
+    }
+
+    /// `test_rustc_panic_mechanism_abort` tests that with `panic=abort` the generated thunks don't
+    /// try to catch panics (even with `--panic-handling=report`), because panics never unwind.
+    #[test]
+    fn test_rustc_panic_mechanism_abort() -> anyhow::Result<()> {
+        let test_result = TestArgs::default_args()?
+            .with_extra_crubit_args(&["--panic-handling=report"])
+            .run()?;
+
+        let rs_body = std::fs::read_to_string(&test_result.rs_path)?;
+        assert!(!rs_body.contains("catch_unwind"), "rs_body = {rs_body}");
         Ok(())
     }
 
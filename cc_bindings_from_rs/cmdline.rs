@@ -2,7 +2,7 @@
 // Exceptions. See /LICENSE for license information.
 // SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
 
-use anyhow::Result;
+use anyhow::{anyhow, ensure, Result};
 use clap::Parser;
 use std::path::PathBuf;
 
@@ -27,6 +27,51 @@ pub struct Cmdline {
     // (not caring about path normalization, directory separator character, etc.).
     pub crubit_support_path: String,
 
+    /// Maps a crate name to the path of the C++ header with the bindings of
+    /// that crate (in a format that should be used in the `#include`
+    /// directives inside the generated C++ files).
+    /// Example: "other_crate=some/path/other_crate_cc_api.h".
+    #[clap(long = "crate-header", value_parser = parse_crate_header,
+           value_name = "CRATE_NAME=INCLUDE_PATH")]
+    // The include path is a `String` rather than `PathBuf` for the same reasons as
+    // `crubit_support_path` above.
+    pub crate_headers: Vec<(String, String)>,
+
+    /// Specifies how the generated bindings should handle Rust panics (only
+    /// relevant for crates compiled with `-Cpanic=unwind`). `abort` prints
+    /// the panic message and aborts the process. `report` reports the panic
+    /// to the C++ caller by throwing `crubit::RustPanic`.
+    #[clap(long, value_enum, value_name = "PANIC_HANDLING",
+           default_value_t = PanicHandling::Abort)]
+    pub panic_handling: PanicHandling,
+
+    /// Specifies how Rust's `i128` and `u128` types should be represented in
+    /// C++. `builtin` uses `__int128` and `unsigned __int128`. `absl` uses
+    /// `absl::int128` and `absl::uint128`.
+    #[clap(long, value_enum, value_name = "INT128_MAPPING",
+           default_value_t = Int128Mapping::Builtin)]
+    pub int128_mapping: Int128Mapping,
+
+    /// Specifies how Rust's `Result<T, E>` should be represented in C++.
+    /// `rs-std` uses `rs_std::Result<T, E>`. `absl-status-or` uses
+    /// `absl::StatusOr<T>` if `E` implements `Display` (and falls back to
+    /// `rs_std::Result<T, E>` otherwise).
+    #[clap(long, value_enum, value_name = "RESULT_MAPPING",
+           default_value_t = ResultMapping::RsStd)]
+    pub result_mapping: ResultMapping,
+
+    /// Excludes `unsafe` functions from the generated bindings (e.g. for C++
+    /// consumers that only want to call safe Rust APIs).
+    #[clap(long, action)]
+    pub exclude_unsafe_fns: bool,
+
+    /// Path to a file that lists the instantiations of generic functions and
+    /// types that should get C++ bindings (one instantiation per line; empty
+    /// lines and lines starting with `#` are ignored). Example lines:
+    /// "my_crate::parse::<u32>", "my_crate::Wrapper<i32>".
+    #[clap(long, value_parser, value_name = "FILE")]
+    pub instantiations_file: Option<PathBuf>,
+
     /// Path to a clang-format executable that will be used to format the
     /// C++ header files generated by the tool.
     #[clap(long, value_parser, value_name = "FILE")]
@@ -47,6 +92,27 @@ pub struct Cmdline {
     pub rustc_args: Vec<String>,
 }
 
+/// Cmdline equivalent of `bindings::PanicHandling`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, clap::ValueEnum)]
+pub enum PanicHandling {
+    Abort,
+    Report,
+}
+
+/// Cmdline equivalent of `bindings::Int128Mapping`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, clap::ValueEnum)]
+pub enum Int128Mapping {
+    Builtin,
+    Absl,
+}
+
+/// Cmdline equivalent of `bindings::ResultMapping`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, clap::ValueEnum)]
+pub enum ResultMapping {
+    RsStd,
+    AbslStatusOr,
+}
+
 impl Cmdline {
     pub fn new(args: &[String]) -> Result<Self> {
         assert_ne!(
@@ -76,6 +142,16 @@ impl Cmdline {
     }
 }
 
+/// Parses the value of the `--crate-header` cmdline flag.
+fn parse_crate_header(s: &str) -> Result<(String, String)> {
+    let (crate_name, include_path) = s
+        .split_once('=')
+        .ok_or_else(|| anyhow!("Expected `CRATE_NAME=INCLUDE_PATH`, but got: `{s}`"))?;
+    ensure!(!crate_name.is_empty(), "Empty crate name in: `{s}`");
+    ensure!(!include_path.is_empty(), "Empty include path in: `{s}`");
+    Ok((crate_name.to_string(), include_path.to_string()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -111,6 +187,12 @@ mod tests {
         assert_eq!(Path::new("clang-format.exe"), cmdline.clang_format_exe_path);
         assert_eq!(Path::new("rustfmt.exe"), cmdline.rustfmt_exe_path);
         assert!(cmdline.rustfmt_config_path.is_none());
+        assert!(cmdline.crate_headers.is_empty());
+        assert_eq!(PanicHandling::Abort, cmdline.panic_handling);
+        assert_eq!(Int128Mapping::Builtin, cmdline.int128_mapping);
+        assert_eq!(ResultMapping::RsStd, cmdline.result_mapping);
+        assert!(!cmdline.exclude_unsafe_fns);
+        assert!(cmdline.instantiations_file.is_none());
         // Ignoring `rustc_args` in this test - they are covered in a separate
         // test below: `test_rustc_args_happy_path`.
     }
@@ -141,6 +223,123 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_crate_headers() {
+        let cmdline = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--clang-format-exe-path=clang-format.exe",
+            "--rustfmt-exe-path=rustfmt.exe",
+            "--crate-header=other_crate=some/path/other_crate_cc_api.h",
+            "--crate-header=yet_another_crate=yet_another_crate_cc_api.h",
+        ])
+        .unwrap();
+
+        assert_eq!(
+            vec![
+                ("other_crate".to_string(), "some/path/other_crate_cc_api.h".to_string()),
+                ("yet_another_crate".to_string(), "yet_another_crate_cc_api.h".to_string()),
+            ],
+            cmdline.crate_headers,
+        );
+    }
+
+    #[test]
+    fn test_crate_headers_missing_equal_sign() {
+        let anyhow_err = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--clang-format-exe-path=clang-format.exe",
+            "--rustfmt-exe-path=rustfmt.exe",
+            "--crate-header=other_crate",
+        ])
+        .expect_err("--crate-header without `=` should trigger an error");
+
+        let msg = format!("{anyhow_err:#}");
+        assert!(
+            msg.contains("Expected `CRATE_NAME=INCLUDE_PATH`, but got: `other_crate`"),
+            "msg = {}",
+            msg,
+        );
+    }
+
+    #[test]
+    fn test_panic_handling() {
+        let cmdline = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--clang-format-exe-path=clang-format.exe",
+            "--rustfmt-exe-path=rustfmt.exe",
+            "--panic-handling=report",
+        ])
+        .unwrap();
+
+        assert_eq!(PanicHandling::Report, cmdline.panic_handling);
+    }
+
+    #[test]
+    fn test_int128_mapping() {
+        let cmdline = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--clang-format-exe-path=clang-format.exe",
+            "--rustfmt-exe-path=rustfmt.exe",
+            "--int128-mapping=absl",
+        ])
+        .unwrap();
+
+        assert_eq!(Int128Mapping::Absl, cmdline.int128_mapping);
+    }
+
+    #[test]
+    fn test_result_mapping() {
+        let cmdline = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--clang-format-exe-path=clang-format.exe",
+            "--rustfmt-exe-path=rustfmt.exe",
+            "--result-mapping=absl-status-or",
+        ])
+        .unwrap();
+
+        assert_eq!(ResultMapping::AbslStatusOr, cmdline.result_mapping);
+    }
+
+    #[test]
+    fn test_exclude_unsafe_fns() {
+        let cmdline = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--clang-format-exe-path=clang-format.exe",
+            "--rustfmt-exe-path=rustfmt.exe",
+            "--exclude-unsafe-fns",
+        ])
+        .unwrap();
+
+        assert!(cmdline.exclude_unsafe_fns);
+    }
+
+    #[test]
+    fn test_instantiations_file() {
+        let cmdline = new_cmdline([
+            "--h-out=foo.h",
+            "--rs-out=foo_impl.rs",
+            "--crubit-support-path=crubit/support/for/tests",
+            "--clang-format-exe-path=clang-format.exe",
+            "--rustfmt-exe-path=rustfmt.exe",
+            "--instantiations-file=instantiations.txt",
+        ])
+        .unwrap();
+
+        assert_eq!(Some(Path::new("instantiations.txt")), cmdline.instantiations_file.as_deref());
+    }
+
     /// The `test_help` unit test below has multiple purposes:
     /// - Direct/obvious purpose: testing that `--help` works
     /// - Double-checking the overall shape of our cmdline "API" (i.e.
@@ -177,16 +376,53 @@ OPTIONS:
             Path to a clang-format executable that will be used to format the C++ header files
             generated by the tool
 
+        --crate-header <CRATE_NAME=INCLUDE_PATH>
+            Maps a crate name to the path of the C++ header with the bindings of that crate (in a
+            format that should be used in the `#include` directives inside the generated C++ files).
+            Example: "other_crate=some/path/other_crate_cc_api.h"
+
         --crubit-support-path <STRING>
             Path to the `crubit/support` directory in a format that should be used in the `#include`
             directives inside the generated C++ files. Example: "crubit/support"
 
+        --exclude-unsafe-fns
+            Excludes `unsafe` functions from the generated bindings (e.g. for C++ consumers that
+            only want to call safe Rust APIs)
+
         --h-out <FILE>
             Output path for C++ header file with bindings
 
     -h, --help
             Print help information
 
+        --instantiations-file <FILE>
+            Path to a file that lists the instantiations of generic functions and types that should
+            get C++ bindings (one instantiation per line; empty lines and lines starting with `#`
+            are ignored). Example lines: "my_crate::parse::<u32>", "my_crate::Wrapper<i32>"
+
+        --int128-mapping <INT128_MAPPING>
+            Specifies how Rust's `i128` and `u128` types should be represented in C++. `builtin`
+            uses `__int128` and `unsigned __int128`. `absl` uses `absl::int128` and `absl::uint128`
+            
+            [default: builtin]
+            [possible values: builtin, absl]
+
+        --panic-handling <PANIC_HANDLING>
+            Specifies how the generated bindings should handle Rust panics (only relevant for crates
+            compiled with `-Cpanic=unwind`). `abort` prints the panic message and aborts the
+            process. `report` reports the panic to the C++ caller by throwing `crubit::RustPanic`
+            
+            [default: abort]
+            [possible values: abort, report]
+
+        --result-mapping <RESULT_MAPPING>
+            Specifies how Rust's `Result<T, E>` should be represented in C++. `rs-std` uses
+            `rs_std::Result<T, E>`. `absl-status-or` uses `absl::StatusOr<T>` if `E` implements
+            `Display` (and falls back to `rs_std::Result<T, E>` otherwise)
+            
+            [default: rs-std]
+            [possible values: rs-std, absl-status-or]
+
         --rs-out <FILE>
             Output path for Rust implementation of the bindings
 
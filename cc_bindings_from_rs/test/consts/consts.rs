@@ -0,0 +1,57 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+//! This crate is used as a test input for `cc_bindings_from_rs` and the
+//! generated C++ bindings are then tested via `consts_test.cc`.
+
+pub const BOOL: bool = true;
+pub const I8_MIN: i8 = i8::MIN;
+pub const I32: i32 = 40 + 2;
+pub const I64_MIN: i64 = i64::MIN;
+pub const U64_MAX: u64 = u64::MAX;
+pub const F32: f32 = 0.1;
+pub const F64: f64 = -1.25;
+pub const F64_INFINITY: f64 = f64::INFINITY;
+pub const CHAR: char = 'ą';
+
+/// Test for constants of struct types.
+pub mod struct_consts {
+    #[derive(Clone, Copy)]
+    pub struct Point {
+        pub x: u8,
+        pub y: i32,
+    }
+
+    impl Point {
+        pub const DIMENSIONS: usize = 2;
+        pub const ORIGIN: Point = Point { x: 0, y: 0 };
+        pub const UNIT_X: Point = Point::create(1, 0);
+
+        pub const fn create(x: u8, y: i32) -> Self {
+            Self { x, y }
+        }
+
+        pub fn get_x(&self) -> u8 {
+            self.x
+        }
+
+        pub fn get_y(&self) -> i32 {
+            self.y
+        }
+    }
+
+    pub const SOME_POINT: Point = Point::create(123, -456);
+}
+
+/// Test for `static` items.
+pub mod statics {
+    pub static STATIC_I32: i32 = 123;
+
+    pub static STATIC_POINT: crate::struct_consts::Point =
+        crate::struct_consts::Point::create(4, 5);
+
+    pub fn get_address_of_static_i32() -> *const i32 {
+        &STATIC_I32
+    }
+}
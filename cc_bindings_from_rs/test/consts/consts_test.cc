@@ -0,0 +1,67 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#include <cstdint>
+#include <limits>
+#include <type_traits>
+
+#include "gmock/gmock.h"
+#include "gtest/gtest.h"
+#include "cc_bindings_from_rs/test/consts/consts_cc_api.h"
+
+namespace crubit {
+namespace {
+
+// Constants can be used in C++ constant expressions.
+static_assert(consts::BOOL);
+static_assert(consts::I8_MIN == std::numeric_limits<std::int8_t>::min());
+static_assert(consts::I32 == 42);
+static_assert(consts::I64_MIN == std::numeric_limits<std::int64_t>::min());
+static_assert(consts::U64_MAX == std::numeric_limits<std::uint64_t>::max());
+static_assert(consts::F32 == 0.1f);
+static_assert(consts::F64 == -1.25);
+static_assert(consts::F64_INFINITY == std::numeric_limits<double>::infinity());
+static_assert(static_cast<std::uint32_t>(consts::CHAR) == 0x105);
+static_assert(consts::struct_consts::Point::DIMENSIONS == 2);
+
+// The `constexpr` constructor of `Point` is only accessible to the bindings of
+// Rust constants.
+static_assert(!std::is_constructible_v<consts::struct_consts::Point,
+                                       std::uint8_t, std::int32_t>);
+
+TEST(ConstsTest, StructConsts) {
+  namespace test = consts::struct_consts;
+  constexpr test::Point origin = test::Point::ORIGIN;
+  EXPECT_EQ(0, origin.get_x());
+  EXPECT_EQ(0, origin.get_y());
+
+  EXPECT_EQ(1, test::Point::UNIT_X.get_x());
+  EXPECT_EQ(0, test::Point::UNIT_X.get_y());
+
+  EXPECT_EQ(123, test::SOME_POINT.get_x());
+  EXPECT_EQ(-456, test::SOME_POINT.get_y());
+}
+
+TEST(ConstsTest, Statics) {
+  namespace test = consts::statics;
+  EXPECT_EQ(123, test::STATIC_I32());
+  // The C++ reference refers to the Rust `static` (rather than to a copy).
+  EXPECT_EQ(&test::STATIC_I32(), test::get_address_of_static_i32());
+  static_assert(
+      std::is_same_v<decltype(test::STATIC_I32()), const std::int32_t&>);
+
+  EXPECT_EQ(4, test::STATIC_POINT().get_x());
+  EXPECT_EQ(5, test::STATIC_POINT().get_y());
+}
+
+// Statics can be read by dynamic initializers of C++ globals (regardless of
+// the initialization order of translation units).
+const std::int32_t kCopyOfStaticI32 = consts::statics::STATIC_I32();
+
+TEST(ConstsTest, StaticReadDuringStaticInitialization) {
+  EXPECT_EQ(123, kCopyOfStaticI32);
+}
+
+}  // namespace
+}  // namespace crubit
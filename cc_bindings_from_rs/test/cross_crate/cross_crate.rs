@@ -0,0 +1,20 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+//! This crate is used as a test input for `cc_bindings_from_rs` and the
+//! generated C++ bindings are then tested via `cross_crate_test.cc`.
+
+use other_crate::Point;
+
+pub fn get_x(p: Point) -> i32 {
+    p.x
+}
+
+pub fn get_y_via_ptr(p: *const Point) -> i32 {
+    unsafe { (*p).y }
+}
+
+pub fn swap_coordinates(p: Point) -> Point {
+    Point { x: p.y, y: p.x }
+}
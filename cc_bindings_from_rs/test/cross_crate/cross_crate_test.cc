@@ -0,0 +1,31 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#include <utility>
+
+#include "gtest/gtest.h"
+#include "cc_bindings_from_rs/test/cross_crate/cross_crate_cc_api.h"
+#include "cc_bindings_from_rs/test/cross_crate/other_crate_cc_api.h"
+
+namespace crubit {
+namespace {
+
+TEST(CrossCrateTest, StructFromOtherCrateTakenByValue) {
+  other_crate::Point p = other_crate::create_point(123, 456);
+  EXPECT_EQ(123, cross_crate::get_x(std::move(p)));
+}
+
+TEST(CrossCrateTest, StructFromOtherCrateTakenByPointer) {
+  other_crate::Point p = other_crate::create_point(123, 456);
+  EXPECT_EQ(456, cross_crate::get_y_via_ptr(&p));
+}
+
+TEST(CrossCrateTest, StructFromOtherCrateReturnedByValue) {
+  other_crate::Point p =
+      cross_crate::swap_coordinates(other_crate::create_point(123, 456));
+  EXPECT_EQ(456, cross_crate::get_x(std::move(p)));
+}
+
+}  // namespace
+}  // namespace crubit
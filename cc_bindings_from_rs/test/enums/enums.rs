@@ -0,0 +1,97 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+//! This crate is used as a test input for `cc_bindings_from_rs` and the
+//! generated C++ bindings are then tested via `enums_test.cc`.
+
+/// Test for a fieldless enum (represented in C++ as an `enum class`).
+pub mod fieldless {
+    pub enum Color {
+        Red,
+        Green = 123,
+        Blue,
+    }
+
+    pub fn next_color(color: Color) -> Color {
+        match color {
+            Color::Red => Color::Green,
+            Color::Green => Color::Blue,
+            Color::Blue => Color::Red,
+        }
+    }
+
+    #[repr(i32)]
+    pub enum Sign {
+        Negative = -1,
+        Zero = 0,
+        Positive = 1,
+    }
+
+    pub fn sign_of(x: i32) -> Sign {
+        match x {
+            i32::MIN..=-1 => Sign::Negative,
+            0 => Sign::Zero,
+            1.. => Sign::Positive,
+        }
+    }
+}
+
+/// Test for a fieldless enum with methods (represented in C++ as a struct,
+/// because a C++ `enum class` can't have member functions).
+pub mod fieldless_with_methods {
+    pub enum Direction {
+        North,
+        South,
+    }
+
+    impl Direction {
+        pub fn create() -> Self {
+            Direction::North
+        }
+
+        pub fn is_north(&self) -> bool {
+            matches!(self, Direction::North)
+        }
+
+        pub fn opposite(&self) -> Self {
+            match self {
+                Direction::North => Direction::South,
+                Direction::South => Direction::North,
+            }
+        }
+    }
+}
+
+/// Test for enums with fields and a defined layout (represented in C++ as a
+/// tagged union).
+pub mod tagged_union {
+    #[repr(u8)]
+    pub enum Shape {
+        Circle { radius: f32 },
+        Rectangle(f32, f32),
+        Empty,
+    }
+
+    pub fn area(shape: Shape) -> f32 {
+        match shape {
+            Shape::Circle { radius } => 3.0 * radius * radius,
+            Shape::Rectangle(width, height) => width * height,
+            Shape::Empty => 0.0,
+        }
+    }
+
+    pub fn make_square(side: f32) -> Shape {
+        Shape::Rectangle(side, side)
+    }
+
+    #[repr(C)]
+    pub enum MaybeInt {
+        Nothing,
+        Just(i64),
+    }
+
+    pub fn maybe_half(x: i64) -> MaybeInt {
+        if x % 2 == 0 { MaybeInt::Just(x / 2) } else { MaybeInt::Nothing }
+    }
+}
@@ -0,0 +1,81 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#include <cstdint>
+#include <type_traits>
+#include <utility>
+
+#include "gmock/gmock.h"
+#include "gtest/gtest.h"
+#include "cc_bindings_from_rs/test/enums/enums_cc_api.h"
+
+namespace crubit {
+namespace {
+
+TEST(EnumsTest, FieldlessEnumIsEnumClass) {
+  using enums::fieldless::Color;
+  static_assert(std::is_enum_v<Color>);
+  static_assert(std::is_same_v<std::underlying_type_t<Color>, std::uint8_t>);
+  EXPECT_EQ(0, static_cast<int>(Color::Red));
+  EXPECT_EQ(123, static_cast<int>(Color::Green));
+  EXPECT_EQ(124, static_cast<int>(Color::Blue));
+}
+
+TEST(EnumsTest, FieldlessEnumPassedByValue) {
+  using enums::fieldless::Color;
+  EXPECT_EQ(Color::Green, enums::fieldless::next_color(Color::Red));
+  EXPECT_EQ(Color::Red, enums::fieldless::next_color(Color::Blue));
+}
+
+TEST(EnumsTest, FieldlessEnumWithNegativeDiscriminant) {
+  using enums::fieldless::Sign;
+  static_assert(std::is_same_v<std::underlying_type_t<Sign>, std::int32_t>);
+  EXPECT_EQ(-1, static_cast<int>(Sign::Negative));
+  EXPECT_EQ(Sign::Negative, enums::fieldless::sign_of(-42));
+  EXPECT_EQ(Sign::Zero, enums::fieldless::sign_of(0));
+  EXPECT_EQ(Sign::Positive, enums::fieldless::sign_of(42));
+}
+
+TEST(EnumsTest, FieldlessEnumWithMethods) {
+  using enums::fieldless_with_methods::Direction;
+  static_assert(!std::is_enum_v<Direction>);
+  Direction north = Direction::create();
+  EXPECT_TRUE(north.is_north());
+  EXPECT_FALSE(north.opposite().is_north());
+}
+
+TEST(EnumsTest, TaggedUnionConstructedInCc) {
+  using enums::tagged_union::Shape;
+  Shape circle = Shape::Circle(2.0);
+  EXPECT_TRUE(circle.is_Circle());
+  EXPECT_FALSE(circle.is_Rectangle());
+  EXPECT_EQ(Shape::Tag::Circle, circle.tag());
+  EXPECT_EQ(2.0, circle.as_Circle().radius);
+  EXPECT_EQ(12.0, enums::tagged_union::area(std::move(circle)));
+
+  Shape empty = Shape::Empty();
+  EXPECT_TRUE(empty.is_Empty());
+  EXPECT_EQ(0.0, enums::tagged_union::area(std::move(empty)));
+}
+
+TEST(EnumsTest, TaggedUnionReturnedFromRust) {
+  using enums::tagged_union::Shape;
+  Shape square = enums::tagged_union::make_square(3.0);
+  ASSERT_TRUE(square.is_Rectangle());
+  EXPECT_EQ(3.0, square.as_Rectangle().__field0);
+  EXPECT_EQ(3.0, square.as_Rectangle().__field1);
+}
+
+TEST(EnumsTest, ReprCTaggedUnion) {
+  using enums::tagged_union::MaybeInt;
+  static_assert(
+      std::is_same_v<std::underlying_type_t<MaybeInt::Tag>, std::uint32_t>);
+  MaybeInt just = enums::tagged_union::maybe_half(42);
+  ASSERT_TRUE(just.is_Just());
+  EXPECT_EQ(21, just.as_Just().__field0);
+  EXPECT_TRUE(enums::tagged_union::maybe_half(7).is_Nothing());
+}
+
+}  // namespace
+}  // namespace crubit
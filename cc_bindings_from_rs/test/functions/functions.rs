@@ -69,6 +69,17 @@ pub mod fn_param_ty_tests {
         }
     }
 
+    /// Testing 128-bit integers (passed to and returned from the Rust thunks
+    /// by pointer).  The values used by the C++ test exercise both halves of
+    /// the integers.
+    pub fn add_u128(x: u128, y: u128) -> u128 {
+        x.wrapping_add(y)
+    }
+
+    pub fn negate_i128(x: i128) -> i128 {
+        x.wrapping_neg()
+    }
+
     pub fn char_to_ascii_lowercase(c: char) -> char {
         // This function used to return unmodified `c` value, but (as we learned when
         // authoring `rs_bindings_from_cc/test/struct/abi_class` tests) making
@@ -102,3 +113,148 @@ pub mod other_fn_param_tests {
         x + y
     }
 }
+
+/// APIs for testing functions that take or return tuples.
+pub mod tuple_tests {
+    pub fn add_i32_pair(pair: (i32, i32)) -> i32 {
+        pair.0 + pair.1
+    }
+
+    pub fn swap_i32_and_f64(pair: (i32, f64)) -> (f64, i32) {
+        (pair.1, pair.0)
+    }
+}
+
+/// APIs for testing functions that take or return references.
+pub mod ref_tests {
+    pub fn get_ref_to_larger(x: &i32, y: &i32) -> i32 {
+        *std::cmp::max(x, y)
+    }
+
+    pub fn increment(x: &mut i32) {
+        *x += 1;
+    }
+
+    pub fn get_first<'a>(first: &'a mut i32, _second: &i32) -> &'a mut i32 {
+        first
+    }
+}
+
+pub mod slice_and_str_tests {
+    pub fn sum(xs: &[i32]) -> i32 {
+        xs.iter().sum()
+    }
+
+    pub fn double_all(xs: &mut [i32]) {
+        for x in xs.iter_mut() {
+            *x *= 2;
+        }
+    }
+
+    pub fn get_tail(xs: &[i32]) -> &[i32] {
+        if xs.is_empty() { xs } else { &xs[1..] }
+    }
+
+    pub fn get_str_len(s: &str) -> usize {
+        s.len()
+    }
+
+    pub fn get_first_word(s: &str) -> &str {
+        s.split(' ').next().unwrap_or(s)
+    }
+}
+
+/// APIs for testing functions that take or return `Option` or `Result`.
+pub mod option_and_result_tests {
+    use std::fmt;
+
+    pub fn checked_div(x: i32, y: i32) -> Option<i32> {
+        x.checked_div(y)
+    }
+
+    pub fn unwrap_or(x: Option<i32>, default: i32) -> i32 {
+        x.unwrap_or(default)
+    }
+
+    pub struct ParseError {
+        pub position: usize,
+    }
+
+    impl fmt::Display for ParseError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "Invalid digit at position {}", self.position)
+        }
+    }
+
+    pub fn parse_digits(s: &str) -> Result<u32, ParseError> {
+        let mut result = 0u32;
+        for (position, c) in s.chars().enumerate() {
+            let digit = c.to_digit(10).ok_or(ParseError { position })?;
+            result = result * 10 + digit;
+        }
+        Ok(result)
+    }
+
+    pub fn validate_digits(s: &str) -> Result<(), ParseError> {
+        parse_digits(s).map(|_| ())
+    }
+
+    pub fn error_position(r: Result<u32, ParseError>) -> Option<usize> {
+        r.err().map(|err| err.position)
+    }
+}
+
+pub mod string_and_vec_tests {
+    pub fn repeat(s: &str, n: usize) -> String {
+        s.repeat(n)
+    }
+
+    pub fn make_vec(len: usize) -> Vec<i32> {
+        (0..len as i32).collect()
+    }
+
+    pub fn sum_vec(v: Vec<i32>) -> i32 {
+        v.into_iter().sum()
+    }
+
+    pub fn string_len(s: String) -> usize {
+        s.len()
+    }
+}
+
+pub mod fn_ptr_tests {
+    pub fn apply_twice(f: extern "C" fn(i32) -> i32, x: i32) -> i32 {
+        f(f(x))
+    }
+
+    fn add_one(x: i32) -> i32 {
+        x + 1
+    }
+
+    pub fn get_add_one() -> fn(i32) -> i32 {
+        add_one
+    }
+
+    pub fn call_rust_fn(f: fn(i32) -> i32, x: i32) -> i32 {
+        f(x)
+    }
+}
+
+pub mod unsafe_fn_tests {
+    /// Returns the sum of the two `i32` values pointed to by `x` and `y`.
+    ///
+    /// # Safety
+    ///
+    /// `x` and `y` need to be valid, aligned, and initialized pointers.
+    pub unsafe fn unsafe_add(x: *const i32, y: *const i32) -> i32 {
+        *x + *y
+    }
+
+    /// # Safety
+    ///
+    /// `x` needs to be a valid, aligned, and initialized pointer.
+    #[no_mangle]
+    pub unsafe extern "C" fn unsafe_deref_extern_c(x: *const i32) -> i32 {
+        *x
+    }
+}
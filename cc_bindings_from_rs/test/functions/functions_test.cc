@@ -2,15 +2,28 @@
 // Exceptions. See /LICENSE for license information.
 // SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
 
+#include <optional>
+#include <string>
+#include <string_view>
+#include <tuple>
+#include <variant>
+
 #include "gmock/gmock.h"
 #include "gtest/gtest.h"
 #include "cc_bindings_from_rs/test/functions/functions_cc_api.h"
+#include "support/rs_std/fn_ptr.h"
+#include "support/rs_std/result.h"
 #include "support/rs_std/rs_char.h"
+#include "support/rs_std/rs_string.h"
+#include "support/rs_std/slice_ref.h"
+#include "support/rs_std/str_ref.h"
+#include "support/rs_std/vec.h"
 
 namespace crubit {
 namespace {
 
 using testing::DoubleEq;
+using testing::ElementsAre;
 
 namespace fn_abi_tests = functions::fn_abi_tests;
 namespace fn_param_ty_tests = functions::fn_param_ty_tests;
@@ -50,6 +63,22 @@ TEST(FnParamTyTests, Int32) {
   EXPECT_EQ(12 + 34, fn_param_ty_tests::add_i32(12, 34));
 }
 
+TEST(FnParamTyTests, UInt128) {
+  using u128 = unsigned __int128;
+  u128 x = (u128{0x0123456789abcdef} << 64) | u128{0xfedcba9876543210};
+  u128 y = (u128{1} << 64) | u128{0x10};
+  u128 expected = (u128{0x0123456789abcdf0} << 64) | u128{0xfedcba9876543220};
+  EXPECT_TRUE(expected == fn_param_ty_tests::add_u128(x, y));
+
+  // Wrapping arithmetic is done by Rust.
+  EXPECT_TRUE(u128{1} == fn_param_ty_tests::add_u128(~u128{0}, 2));
+}
+
+TEST(FnParamTyTests, Int128) {
+  __int128 x = -(__int128{0x0123456789abcdef} << 64);
+  EXPECT_TRUE(-x == fn_param_ty_tests::negate_i128(x));
+}
+
 TEST(FnParamTyTests, rs_char) {
   std::optional<const rs_std::rs_char> input = rs_std::rs_char::from_u32(U'A');
   ASSERT_TRUE(input.has_value());
@@ -82,5 +111,196 @@ TEST(OtherFnTests, DuplicatedParamNames) {
                          12, 34, 56, 78));
 }
 
+TEST(TupleTests, TupleParam) {
+  namespace tests = functions::tuple_tests;
+  EXPECT_EQ(12 + 34, tests::add_i32_pair(std::make_tuple(12, 34)));
+}
+
+TEST(TupleTests, TupleReturnValue) {
+  namespace tests = functions::tuple_tests;
+  std::tuple<double, std::int32_t> swapped =
+      tests::swap_i32_and_f64(std::make_tuple(12, 34.0));
+  EXPECT_THAT(std::get<0>(swapped), DoubleEq(34.0));
+  EXPECT_EQ(12, std::get<1>(swapped));
+}
+
+TEST(RefTests, ConstRefParams) {
+  namespace tests = functions::ref_tests;
+  std::int32_t x = 123;
+  std::int32_t y = 456;
+  EXPECT_EQ(456, tests::get_ref_to_larger(x, y));
+}
+
+TEST(RefTests, MutRefParam) {
+  namespace tests = functions::ref_tests;
+  std::int32_t x = 123;
+  tests::increment(x);
+  EXPECT_EQ(124, x);
+}
+
+TEST(RefTests, MutRefReturnValue) {
+  namespace tests = functions::ref_tests;
+  std::int32_t x = 123;
+  std::int32_t y = 456;
+  std::int32_t& first = tests::get_first(x, y);
+  EXPECT_EQ(&x, &first);
+  first = 789;
+  EXPECT_EQ(789, x);
+}
+
+TEST(SliceAndStrTests, SliceParam) {
+  namespace tests = functions::slice_and_str_tests;
+  const std::int32_t xs[] = {1, 2, 3};
+  EXPECT_EQ(6, tests::sum(rs_std::SliceRef<const std::int32_t>(xs, 3)));
+  EXPECT_EQ(0, tests::sum(rs_std::SliceRef<const std::int32_t>()));
+}
+
+TEST(SliceAndStrTests, MutSliceParam) {
+  namespace tests = functions::slice_and_str_tests;
+  std::int32_t xs[] = {1, 2, 3};
+  tests::double_all(rs_std::SliceRef<std::int32_t>(xs, 3));
+  EXPECT_THAT(xs, ElementsAre(2, 4, 6));
+}
+
+TEST(SliceAndStrTests, SliceReturnValue) {
+  namespace tests = functions::slice_and_str_tests;
+  const std::int32_t xs[] = {1, 2, 3};
+  rs_std::SliceRef<const std::int32_t> tail =
+      tests::get_tail(rs_std::SliceRef<const std::int32_t>(xs, 3));
+  EXPECT_EQ(&xs[1], tail.data());
+  EXPECT_EQ(2, tail.size());
+}
+
+TEST(SliceAndStrTests, StrParam) {
+  namespace tests = functions::slice_and_str_tests;
+  std::optional<rs_std::StrRef> s = rs_std::StrRef::from_utf8("foo bar");
+  ASSERT_TRUE(s.has_value());
+  EXPECT_EQ(7, tests::get_str_len(*s));
+}
+
+TEST(SliceAndStrTests, StrReturnValue) {
+  namespace tests = functions::slice_and_str_tests;
+  std::string_view input = "hello world";
+  std::optional<rs_std::StrRef> s = rs_std::StrRef::from_utf8(input);
+  ASSERT_TRUE(s.has_value());
+  std::string_view first_word = tests::get_first_word(*s);
+  EXPECT_EQ("hello", first_word);
+  EXPECT_EQ(input.data(), first_word.data());
+}
+
+TEST(OptionAndResultTests, OptionReturnValue) {
+  namespace tests = functions::option_and_result_tests;
+  EXPECT_EQ(std::optional<std::int32_t>(5), tests::checked_div(10, 2));
+  EXPECT_EQ(std::nullopt, tests::checked_div(10, 0));
+}
+
+TEST(OptionAndResultTests, OptionParam) {
+  namespace tests = functions::option_and_result_tests;
+  EXPECT_EQ(123, tests::unwrap_or(123, 456));
+  EXPECT_EQ(456, tests::unwrap_or(std::nullopt, 456));
+}
+
+TEST(OptionAndResultTests, ResultReturnValue) {
+  namespace tests = functions::option_and_result_tests;
+  std::optional<rs_std::StrRef> digits = rs_std::StrRef::from_utf8("123");
+  ASSERT_TRUE(digits.has_value());
+  auto ok = tests::parse_digits(*digits);
+  ASSERT_TRUE(ok.is_ok());
+  EXPECT_EQ(std::optional<std::uint32_t>(123), ok.ok());
+
+  std::optional<rs_std::StrRef> not_digits = rs_std::StrRef::from_utf8("12x");
+  ASSERT_TRUE(not_digits.has_value());
+  auto err = tests::parse_digits(*not_digits);
+  ASSERT_TRUE(err.is_err());
+  EXPECT_EQ(2, err.err()->position);
+  EXPECT_EQ("Invalid digit at position 2", err.err()->ToString());
+}
+
+TEST(OptionAndResultTests, UnitResultReturnValue) {
+  namespace tests = functions::option_and_result_tests;
+  std::optional<rs_std::StrRef> digits = rs_std::StrRef::from_utf8("123");
+  ASSERT_TRUE(digits.has_value());
+  rs_std::Result<std::monostate, tests::ParseError> result =
+      tests::validate_digits(*digits);
+  EXPECT_TRUE(result.is_ok());
+}
+
+TEST(OptionAndResultTests, ResultParam) {
+  namespace tests = functions::option_and_result_tests;
+  std::optional<rs_std::StrRef> digits = rs_std::StrRef::from_utf8("123");
+  ASSERT_TRUE(digits.has_value());
+  EXPECT_EQ(std::nullopt, tests::error_position(tests::parse_digits(*digits)));
+
+  std::optional<rs_std::StrRef> not_digits = rs_std::StrRef::from_utf8("1x");
+  ASSERT_TRUE(not_digits.has_value());
+  EXPECT_EQ(std::optional<std::uintptr_t>(1),
+            tests::error_position(tests::parse_digits(*not_digits)));
+}
+
+TEST(StringAndVecTests, StringReturnValue) {
+  namespace tests = functions::string_and_vec_tests;
+  std::optional<rs_std::StrRef> s = rs_std::StrRef::from_utf8("ab");
+  ASSERT_TRUE(s.has_value());
+  rs_std::String result = tests::repeat(*s, 3);
+  EXPECT_EQ("ababab", result.to_string_view());
+}
+
+TEST(StringAndVecTests, StringRoundTrip) {
+  namespace tests = functions::string_and_vec_tests;
+  std::optional<rs_std::StrRef> s = rs_std::StrRef::from_utf8("xyz");
+  ASSERT_TRUE(s.has_value());
+  rs_std::String str = tests::repeat(*s, 2);
+  EXPECT_EQ(6, tests::string_len(std::move(str)));
+  EXPECT_TRUE(str.empty());  // NOLINT(bugprone-use-after-move)
+}
+
+TEST(StringAndVecTests, VecReturnValue) {
+  namespace tests = functions::string_and_vec_tests;
+  rs_std::Vec<std::int32_t> v = tests::make_vec(4);
+  ASSERT_EQ(4, v.size());
+  EXPECT_EQ(0, v[0]);
+  EXPECT_EQ(3, v[3]);
+  std::int32_t sum = 0;
+  for (std::int32_t x : v) sum += x;
+  EXPECT_EQ(6, sum);
+}
+
+TEST(StringAndVecTests, VecParam) {
+  namespace tests = functions::string_and_vec_tests;
+  EXPECT_EQ(10, tests::sum_vec(tests::make_vec(5)));
+  EXPECT_EQ(0, tests::sum_vec(rs_std::Vec<std::int32_t>()));
+}
+
+std::int32_t Triple(std::int32_t x) { return 3 * x; }
+
+TEST(FnPtrTests, ExternCFnPtrParam) {
+  namespace tests = functions::fn_ptr_tests;
+  EXPECT_EQ(45, tests::apply_twice(&Triple, 5));
+}
+
+TEST(FnPtrTests, RustFnPtrReturnValue) {
+  namespace tests = functions::fn_ptr_tests;
+  rs_std::FnPtr<std::int32_t(std::int32_t)> add_one = tests::get_add_one();
+  EXPECT_EQ(124, add_one(123));
+}
+
+TEST(FnPtrTests, RustFnPtrRoundTrip) {
+  namespace tests = functions::fn_ptr_tests;
+  EXPECT_EQ(8, tests::call_rust_fn(tests::get_add_one(), 7));
+}
+
+TEST(UnsafeFnTests, UnsafeFnWithRustAbi) {
+  namespace tests = functions::unsafe_fn_tests;
+  std::int32_t x = 123;
+  std::int32_t y = 456;
+  EXPECT_EQ(123 + 456, tests::unsafe_add(&x, &y));
+}
+
+TEST(UnsafeFnTests, UnsafeFnWithCAbi) {
+  namespace tests = functions::unsafe_fn_tests;
+  std::int32_t x = 123;
+  EXPECT_EQ(123, tests::unsafe_deref_extern_c(&x));
+}
+
 }  // namespace
 }  // namespace crubit
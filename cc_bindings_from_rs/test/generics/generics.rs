@@ -0,0 +1,82 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+//! This crate is used as a test input for `cc_bindings_from_rs` and the
+//! generated C++ bindings are then tested via `generics_test.cc`.
+
+/// Generic functions - the `BUILD` file lists which of their instantiations
+/// should get C++ bindings.
+pub mod generic_fns {
+    use std::ops::Add;
+    use std::str::FromStr;
+
+    pub fn add<T: Add<Output = T>>(x: T, y: T) -> T {
+        x + y
+    }
+
+    /// The type parameter is only used in the return type - the C++ caller
+    /// needs to specify it explicitly (e.g. `parse_or_default<std::uint8_t>`).
+    pub fn parse_or_default<T: FromStr + Default>(s_len: usize) -> T {
+        "123456".get(..s_len).and_then(|s| s.parse().ok()).unwrap_or_default()
+    }
+
+    #[derive(Clone, Copy, PartialEq, PartialOrd)]
+    pub struct Meters(pub f64);
+
+    impl Meters {
+        pub fn get_value(m: Meters) -> f64 {
+            m.0
+        }
+
+        pub fn from_value<T: Into<f64>>(value: T) -> Meters {
+            Meters(value.into())
+        }
+    }
+
+    pub fn max_of<T: PartialOrd>(x: T, y: T) -> T {
+        if x < y { y } else { x }
+    }
+}
+
+/// Generic structs - the `BUILD` file lists which of their instantiations
+/// should get C++ bindings.
+pub mod generic_structs {
+    pub struct Wrapper<T> {
+        pub value: T,
+    }
+
+    impl<T: Copy> Wrapper<T> {
+        pub fn create(value: T) -> Self {
+            Self { value }
+        }
+
+        pub fn get_value(w: Self) -> T {
+            w.value
+        }
+    }
+
+    /// Only `Wrapper<f32>` (but not `Wrapper<i32>`) should have this method.
+    impl Wrapper<f32> {
+        pub fn get_doubled_value(w: Self) -> f32 {
+            w.value * 2.0
+        }
+    }
+
+    pub struct Pair<T, U> {
+        pub first: T,
+        pub second: U,
+    }
+
+    pub fn make_pair(first: i32, second: f64) -> Pair<i32, f64> {
+        Pair { first, second }
+    }
+
+    pub fn swap_pair(pair: Pair<i32, f64>) -> Pair<f64, i32> {
+        Pair { first: pair.second, second: pair.first }
+    }
+
+    pub fn get_first(pair: Pair<f64, i32>) -> f64 {
+        pair.first
+    }
+}
@@ -0,0 +1,68 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#include <cstdint>
+#include <utility>
+
+#include "gtest/gtest.h"
+#include "cc_bindings_from_rs/test/generics/generics_cc_api.h"
+
+namespace crubit {
+namespace {
+
+namespace generic_fns = generics::generic_fns;
+
+TEST(GenericFnsTest, ExplicitTemplateArgs) {
+  EXPECT_EQ(12 + 34, generic_fns::add<std::int32_t>(12, 34));
+  EXPECT_EQ(0.5 + 0.25, generic_fns::add<double>(0.5, 0.25));
+}
+
+TEST(GenericFnsTest, DeducedTemplateArgs) {
+  EXPECT_EQ(12 + 34, generic_fns::add(std::int32_t{12}, std::int32_t{34}));
+  EXPECT_EQ(0.5 + 0.25, generic_fns::add(0.5, 0.25));
+}
+
+TEST(GenericFnsTest, TypeParamOnlyUsedInReturnType) {
+  EXPECT_EQ(123, generic_fns::parse_or_default<std::uint8_t>(3));
+  // 123456 doesn't fit into `u8`, so `parse` fails and `default` is used.
+  EXPECT_EQ(0, generic_fns::parse_or_default<std::uint8_t>(6));
+  EXPECT_EQ(123456, generic_fns::parse_or_default<std::uint64_t>(6));
+}
+
+TEST(GenericFnsTest, StructTypeArg) {
+  generic_fns::Meters small = generic_fns::Meters::from_value<std::uint32_t>(1);
+  generic_fns::Meters big = generic_fns::Meters::from_value<std::uint32_t>(2);
+  generic_fns::Meters max =
+      generic_fns::max_of<generic_fns::Meters>(std::move(small), std::move(big));
+  EXPECT_EQ(2.0, generic_fns::Meters::get_value(std::move(max)));
+}
+
+namespace generic_structs = generics::generic_structs;
+
+TEST(GenericStructsTest, Layout) {
+  static_assert(sizeof(generic_structs::Wrapper<std::int32_t>) == 4);
+  static_assert(sizeof(generic_structs::Wrapper<float>) == 4);
+  static_assert(sizeof(generic_structs::Pair<std::int32_t, double>) == 16);
+  static_assert(alignof(generic_structs::Pair<double, std::int32_t>) == 8);
+}
+
+TEST(GenericStructsTest, Methods) {
+  generic_structs::Wrapper<std::int32_t> i =
+      generic_structs::Wrapper<std::int32_t>::create(42);
+  EXPECT_EQ(42, generic_structs::Wrapper<std::int32_t>::get_value(std::move(i)));
+
+  generic_structs::Wrapper<float> f = generic_structs::Wrapper<float>::create(0.5);
+  EXPECT_EQ(1.0, generic_structs::Wrapper<float>::get_doubled_value(std::move(f)));
+}
+
+TEST(GenericStructsTest, FunctionsWithInstantiationsInSignatures) {
+  generic_structs::Pair<std::int32_t, double> pair =
+      generic_structs::make_pair(123, 0.5);
+  generic_structs::Pair<double, std::int32_t> swapped =
+      generic_structs::swap_pair(std::move(pair));
+  EXPECT_EQ(0.5, generic_structs::get_first(std::move(swapped)));
+}
+
+}  // namespace
+}  // namespace crubit
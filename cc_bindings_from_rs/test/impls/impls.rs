@@ -65,3 +65,69 @@ pub mod non_contiguous_method_decls_and_defs {
         }
     }
 }
+
+/// Methods that take `&self` or `&mut self`.
+pub mod ref_self_methods {
+    pub struct Counter(i32);
+
+    impl Counter {
+        pub fn create(i: i32) -> Self {
+            Self(i)
+        }
+        pub fn get(&self) -> i32 {
+            self.0
+        }
+        pub fn get_ref(&self) -> &i32 {
+            &self.0
+        }
+        pub fn add(&mut self, delta: i32) {
+            self.0 += delta;
+        }
+    }
+}
+
+/// Methods that take `self: Pin<&mut Self>`, `self: Box<Self>`, `self:
+/// Rc<Self>`, or `self: Arc<Self>`.
+pub mod smart_pointer_self_methods {
+    use std::pin::Pin;
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    #[derive(Default)]
+    pub struct Accumulator {
+        values: Vec<i32>,
+    }
+
+    impl Accumulator {
+        pub fn create() -> Self {
+            Self::default()
+        }
+        pub fn push(self: Pin<&mut Self>, value: i32) {
+            self.get_mut().values.push(value);
+        }
+        pub fn into_sum(self: Box<Self>) -> i32 {
+            self.values.iter().sum()
+        }
+        pub fn create_boxed() -> Box<Self> {
+            Box::default()
+        }
+        pub fn create_rc(value: i32) -> Rc<Self> {
+            Rc::new(Self { values: vec![value] })
+        }
+        pub fn rc_sum(self: Rc<Self>) -> i32 {
+            self.values.iter().sum()
+        }
+        pub fn rc_strong_count(self: Rc<Self>) -> usize {
+            Rc::strong_count(&self)
+        }
+        pub fn create_arc(value: i32) -> Arc<Self> {
+            Arc::new(Self { values: vec![value] })
+        }
+        pub fn arc_sum(self: Arc<Self>) -> i32 {
+            self.values.iter().sum()
+        }
+        pub fn arc_strong_count(self: Arc<Self>) -> usize {
+            Arc::strong_count(&self)
+        }
+    }
+}
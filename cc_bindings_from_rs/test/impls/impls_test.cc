@@ -30,5 +30,71 @@ TEST(ImplsTest, NonContiguousMethodDeclsAndDefs) {
                                              test::S2::create(456)));
 }
 
+TEST(ImplsTest, RefSelfMethods) {
+  namespace test = impls::ref_self_methods;
+  test::Counter counter = test::Counter::create(123);
+  EXPECT_EQ(123, counter.get());
+  counter.add(1);
+  EXPECT_EQ(124, counter.get());
+
+  const test::Counter& const_counter = counter;
+  const std::int32_t& value = const_counter.get_ref();
+  counter.add(1);
+  EXPECT_EQ(125, value);
+}
+
+TEST(ImplsTest, SmartPointerSelfMethods) {
+  namespace test = impls::smart_pointer_self_methods;
+  test::Accumulator accumulator = test::Accumulator::create();
+  accumulator.push(123);
+  accumulator.push(456);
+  EXPECT_EQ(123 + 456, std::move(accumulator).into_sum());
+
+  // `into_sum` has moved the values out of `accumulator`.
+  EXPECT_EQ(0, std::move(accumulator).into_sum());
+}
+
+TEST(ImplsTest, BoxSelfMethodTakingOwnershipOfBox) {
+  namespace test = impls::smart_pointer_self_methods;
+  rs_std::Box<test::Accumulator> boxed = test::Accumulator::create_boxed();
+  boxed->push(123);
+  boxed->push(456);
+  EXPECT_EQ(123 + 456, test::Accumulator::into_sum(std::move(boxed)));
+
+  // The `Box` has been handed over to Rust (which has dropped it).
+  EXPECT_FALSE(boxed);  // NOLINT(bugprone-use-after-move)
+}
+
+TEST(ImplsTest, RcSelfMethods) {
+  namespace test = impls::smart_pointer_self_methods;
+  rs_std::Rc<test::Accumulator> rc = test::Accumulator::create_rc(123);
+  rs_std::Rc<test::Accumulator> copy = rc;
+  EXPECT_EQ(rc.get(), copy.get());
+
+  // `rc`, `copy`, and the copy passed to `rc_strong_count`.
+  EXPECT_EQ(3u, test::Accumulator::rc_strong_count(rc));
+  EXPECT_EQ(2u, test::Accumulator::rc_strong_count(std::move(copy)));
+  EXPECT_EQ(nullptr, copy.get());  // NOLINT(bugprone-use-after-move)
+  EXPECT_EQ(1u, test::Accumulator::rc_strong_count(std::move(rc)));
+
+  EXPECT_EQ(456, test::Accumulator::rc_sum(test::Accumulator::create_rc(456)));
+}
+
+TEST(ImplsTest, ArcSelfMethods) {
+  namespace test = impls::smart_pointer_self_methods;
+  rs_std::Arc<test::Accumulator> arc = test::Accumulator::create_arc(123);
+  rs_std::Arc<test::Accumulator> copy = arc;
+  EXPECT_EQ(arc.get(), copy.get());
+
+  // `arc`, `copy`, and the copy passed to `arc_strong_count`.
+  EXPECT_EQ(3u, test::Accumulator::arc_strong_count(arc));
+  EXPECT_EQ(2u, test::Accumulator::arc_strong_count(std::move(copy)));
+  EXPECT_EQ(nullptr, copy.get());  // NOLINT(bugprone-use-after-move)
+  EXPECT_EQ(1u, test::Accumulator::arc_strong_count(std::move(arc)));
+
+  EXPECT_EQ(456,
+            test::Accumulator::arc_sum(test::Accumulator::create_arc(456)));
+}
+
 }  // namespace
 }  // namespace crubit
@@ -10,3 +10,35 @@ pub mod basic_module {
         x + y
     }
 }
+
+/// Test coverage for the "facade" pattern, where the public API of a crate is
+/// implemented in private modules and published via `pub use`.
+mod private_impl {
+    pub struct Point {
+        pub x: i32,
+        pub y: i32,
+    }
+
+    impl Point {
+        pub fn sum(&self) -> i32 {
+            self.x + self.y
+        }
+    }
+
+    pub fn create_point(x: i32, y: i32) -> Point {
+        Point { x, y }
+    }
+
+    pub mod details {
+        pub fn multiply_i32(x: i32, y: i32) -> i32 {
+            x * y
+        }
+    }
+}
+pub use private_impl::details as public_details;
+pub use private_impl::{create_point, Point};
+
+pub mod other_module {
+    pub use crate::private_impl::Point;
+    pub use crate::private_impl::Point as RenamedPoint;
+}
@@ -2,6 +2,7 @@
 // Exceptions. See /LICENSE for license information.
 // SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
 
+#include <type_traits>
 #include <utility>
 
 #include "gmock/gmock.h"
@@ -15,5 +16,20 @@ TEST(ModulesTest, BasicModule) {
   ASSERT_EQ(123 + 456, modules::basic_module::add_i32(123, 456));
 }
 
+TEST(ModulesTest, ReexportedItems) {
+  modules::Point point = modules::create_point(123, 456);
+  EXPECT_EQ(123, point.x);
+  EXPECT_EQ(456, point.y);
+  EXPECT_EQ(123 + 456, point.sum());
+
+  EXPECT_EQ(123 * 456, modules::public_details::multiply_i32(123, 456));
+}
+
+TEST(ModulesTest, AdditionalPublicPaths) {
+  static_assert(std::is_same_v<modules::Point, modules::other_module::Point>);
+  static_assert(
+      std::is_same_v<modules::Point, modules::other_module::RenamedPoint>);
+}
+
 }  // namespace
 }  // namespace crubit
@@ -118,3 +118,266 @@ pub mod abi_classification {
         }
     }
 }
+
+/// Test of a struct with a custom `Drop` impl.  The C++ destructor of the
+/// struct should invoke the Rust `Drop` impl exactly once per value (i.e. a
+/// moved-from value shouldn't be dropped again).
+pub mod drop_impl {
+    use std::sync::atomic::{AtomicI32, Ordering};
+
+    static DROP_COUNT: AtomicI32 = AtomicI32::new(0);
+
+    /// `Default` is required for moving the struct in C++ (the moved-from value
+    /// is replaced with `Default::default()`).  `DropCounter` only counts
+    /// drops of non-default values.
+    #[derive(Default)]
+    pub struct DropCounter {
+        value: i32,
+    }
+
+    impl Drop for DropCounter {
+        fn drop(&mut self) {
+            if self.value != 0 {
+                DROP_COUNT.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+    }
+
+    impl DropCounter {
+        pub fn create(value: i32) -> Self {
+            Self { value }
+        }
+        pub fn consume(counter: Self) -> i32 {
+            counter.value
+        }
+    }
+
+    pub fn get_drop_count() -> i32 {
+        DROP_COUNT.load(Ordering::SeqCst)
+    }
+}
+
+/// Test for a `#[repr(C)]` union (represented in C++ as a `union`).
+pub mod repr_c_union {
+    #[repr(C)]
+    pub union IntOrFloat {
+        pub i: u32,
+        pub f: f32,
+    }
+
+    impl IntOrFloat {
+        pub fn from_float(f: f32) -> Self {
+            Self { f }
+        }
+        pub fn to_bits(value: Self) -> u32 {
+            unsafe { value.i }
+        }
+    }
+}
+
+/// Test of structs that implement `Copy`, `Clone`, and/or `Default`.
+pub mod clone_and_default {
+    /// `Copy` types are trivially copyable in C++.
+    #[derive(Clone, Copy)]
+    pub struct CopyablePoint {
+        x: i32,
+        y: i32,
+    }
+
+    impl CopyablePoint {
+        pub fn create(x: i32, y: i32) -> Self {
+            Self { x, y }
+        }
+        pub fn get_x(&self) -> i32 {
+            self.x
+        }
+        pub fn get_y(&self) -> i32 {
+            self.y
+        }
+    }
+
+    /// `CloneCounter::clone` increments the `clone_count` of the new value
+    /// (this helps to verify that C++ copy constructor and copy assignment
+    /// operator call into the Rust `impl`s).
+    pub struct CloneCounter {
+        value: i32,
+        clone_count: i32,
+    }
+
+    impl Clone for CloneCounter {
+        fn clone(&self) -> Self {
+            Self { value: self.value, clone_count: self.clone_count + 1 }
+        }
+    }
+
+    impl Default for CloneCounter {
+        fn default() -> Self {
+            Self { value: 42, clone_count: 0 }
+        }
+    }
+
+    impl CloneCounter {
+        pub fn create(value: i32) -> Self {
+            Self { value, clone_count: 0 }
+        }
+        pub fn get_value(&self) -> i32 {
+            self.value
+        }
+        pub fn get_clone_count(&self) -> i32 {
+            self.clone_count
+        }
+    }
+}
+
+/// Test of `PartialEq`, `PartialOrd`, and `Ord` bindings (i.e. of C++
+/// comparison operators).
+pub mod comparison {
+    #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+    pub struct Version {
+        major: i32,
+        minor: i32,
+    }
+
+    impl Version {
+        pub fn create(major: i32, minor: i32) -> Self {
+            Self { major, minor }
+        }
+        pub fn get_major(&self) -> i32 {
+            self.major
+        }
+        pub fn get_minor(&self) -> i32 {
+            self.minor
+        }
+    }
+
+    /// `Task` implements `Clone`, but not `Copy` - moving it in C++ falls back
+    /// to the copy constructor and the copy assignment operator.
+    #[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+    pub struct Task {
+        priority: i32,
+    }
+
+    impl Task {
+        pub fn create(priority: i32) -> Self {
+            Self { priority }
+        }
+        pub fn get_priority(&self) -> i32 {
+            self.priority
+        }
+    }
+
+    /// `f32::NAN` makes some `Measurement`s unordered.
+    #[derive(Clone, Copy, PartialEq, PartialOrd)]
+    pub struct Measurement {
+        value: f32,
+    }
+
+    impl Measurement {
+        pub fn create(value: f32) -> Self {
+            Self { value }
+        }
+        pub fn create_nan() -> Self {
+            Self { value: f32::NAN }
+        }
+    }
+}
+
+/// Test of `Display` and `Debug` bindings (i.e. of `ToString()`,
+/// `DebugString()`, and `operator<<`).
+pub mod fmt {
+    #[derive(Debug)]
+    pub struct Point {
+        x: i32,
+        y: i32,
+    }
+
+    impl Point {
+        pub fn create(x: i32, y: i32) -> Self {
+            Self { x, y }
+        }
+    }
+
+    impl std::fmt::Display for Point {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "({}, {})", self.x, self.y)
+        }
+    }
+
+    /// `DebugOnly` doesn't implement `Display` - `operator<<` uses `Debug`.
+    #[derive(Debug)]
+    pub struct DebugOnly {
+        value: i32,
+    }
+
+    impl DebugOnly {
+        pub fn create(value: i32) -> Self {
+            Self { value }
+        }
+    }
+}
+
+/// Test of `Box<T>` and `Option<Box<T>>` bindings (i.e. of `rs_std::Box<T>`).
+pub mod boxes {
+    use std::sync::atomic::{AtomicI32, Ordering};
+
+    static DROP_COUNT: AtomicI32 = AtomicI32::new(0);
+
+    pub struct Tracked {
+        value: i32,
+    }
+
+    impl Drop for Tracked {
+        fn drop(&mut self) {
+            DROP_COUNT.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    impl Tracked {
+        pub fn get_value(&self) -> i32 {
+            self.value
+        }
+    }
+
+    pub fn get_drop_count() -> i32 {
+        DROP_COUNT.load(Ordering::SeqCst)
+    }
+
+    pub fn make_box(value: i32) -> Box<Tracked> {
+        Box::new(Tracked { value })
+    }
+
+    pub fn make_optional_box(value: i32) -> Option<Box<Tracked>> {
+        if value == 0 {
+            None
+        } else {
+            Some(make_box(value))
+        }
+    }
+
+    pub fn consume_box(b: Box<Tracked>) -> i32 {
+        b.value
+    }
+
+    pub fn consume_optional_box(b: Option<Box<Tracked>>) -> i32 {
+        b.map_or(-1, |b| b.value)
+    }
+}
+
+/// Test for struct fields of tuple types.
+pub mod tuple_fields {
+    pub struct Pair {
+        pub pair: (i32, u8),
+    }
+
+    pub fn create(first: i32, second: u8) -> Pair {
+        Pair { pair: (first, second) }
+    }
+
+    pub fn get_first(p: &Pair) -> i32 {
+        p.pair.0
+    }
+
+    pub fn get_second(p: &Pair) -> u8 {
+        p.pair.1
+    }
+}
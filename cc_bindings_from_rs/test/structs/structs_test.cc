@@ -2,7 +2,13 @@
 // Exceptions. See /LICENSE for license information.
 // SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
 
+#include <algorithm>
+#include <cstdint>
+#include <sstream>
+#include <string>
+#include <type_traits>
 #include <utility>
+#include <vector>
 
 #include "gmock/gmock.h"
 #include "gtest/gtest.h"
@@ -48,5 +54,244 @@ TEST(StructsTest, StructMemory) {
   EXPECT_EQ(321 * 654, test::StructMemory::inspect(std::move(product)));
 }
 
+TEST(StructsTest, DropImplInvokedByCcDestructor) {
+  namespace test = structs::drop_impl;
+  int32_t initial_drop_count = test::get_drop_count();
+  {
+    test::DropCounter counter = test::DropCounter::create(123);
+    EXPECT_EQ(initial_drop_count, test::get_drop_count());
+  }
+  EXPECT_EQ(initial_drop_count + 1, test::get_drop_count());
+}
+
+TEST(StructsTest, DropImplNotInvokedTwiceAfterMove) {
+  namespace test = structs::drop_impl;
+  int32_t initial_drop_count = test::get_drop_count();
+  {
+    test::DropCounter counter = test::DropCounter::create(123);
+    test::DropCounter moved_counter = std::move(counter);
+    EXPECT_EQ(initial_drop_count, test::get_drop_count());
+  }
+  EXPECT_EQ(initial_drop_count + 1, test::get_drop_count());
+}
+
+TEST(StructsTest, DropImplInvokedByRustAfterPassingByValue) {
+  namespace test = structs::drop_impl;
+  int32_t initial_drop_count = test::get_drop_count();
+  {
+    test::DropCounter counter = test::DropCounter::create(123);
+    EXPECT_EQ(123, test::DropCounter::consume(std::move(counter)));
+    EXPECT_EQ(initial_drop_count + 1, test::get_drop_count());
+  }
+  EXPECT_EQ(initial_drop_count + 1, test::get_drop_count());
+}
+
+TEST(StructsTest, ReprCUnion) {
+  namespace test = structs::repr_c_union;
+  static_assert(std::is_union_v<test::IntOrFloat>);
+  static_assert(sizeof(test::IntOrFloat) == 4);
+  test::IntOrFloat value = test::IntOrFloat::from_float(1.0);
+  EXPECT_EQ(1.0f, value.f);
+  EXPECT_EQ(0x3f800000, test::IntOrFloat::to_bits(std::move(value)));
+
+  // `pub` fields of a `#[repr(C)]` union can be written and read from C++.
+  test::IntOrFloat other = test::IntOrFloat::from_float(0.0);
+  other.i = 0x40000000;
+  EXPECT_EQ(0x40000000, other.i);
+  EXPECT_EQ(0x40000000, test::IntOrFloat::to_bits(std::move(other)));
+}
+
+TEST(StructsTest, CopyTypeIsTriviallyCopyable) {
+  namespace test = structs::clone_and_default;
+  static_assert(std::is_trivially_copyable_v<test::CopyablePoint>);
+  test::CopyablePoint p = test::CopyablePoint::create(1, 2);
+  test::CopyablePoint copy = p;
+  EXPECT_EQ(1, copy.get_x());
+  EXPECT_EQ(2, copy.get_y());
+  copy = test::CopyablePoint::create(3, 4);
+  EXPECT_EQ(3, copy.get_x());
+  EXPECT_EQ(1, p.get_x());
+}
+
+TEST(StructsTest, DefaultConstructorCallsDefaultImpl) {
+  namespace test = structs::clone_and_default;
+  test::CloneCounter counter;
+  EXPECT_EQ(42, counter.get_value());
+  EXPECT_EQ(0, counter.get_clone_count());
+}
+
+TEST(StructsTest, CopyConstructorCallsCloneImpl) {
+  namespace test = structs::clone_and_default;
+  test::CloneCounter original = test::CloneCounter::create(123);
+  test::CloneCounter copy = original;
+  EXPECT_EQ(123, copy.get_value());
+  EXPECT_EQ(1, copy.get_clone_count());
+  EXPECT_EQ(0, original.get_clone_count());
+}
+
+TEST(StructsTest, CopyAssignmentCallsCloneFromImpl) {
+  namespace test = structs::clone_and_default;
+  test::CloneCounter original = test::CloneCounter::create(123);
+  test::CloneCounter copy;
+  copy = original;
+  EXPECT_EQ(123, copy.get_value());
+  EXPECT_EQ(1, copy.get_clone_count());
+
+  // Self-assignment is a no-op.
+  test::CloneCounter& self = copy;
+  copy = self;
+  EXPECT_EQ(1, copy.get_clone_count());
+}
+
+TEST(StructsTest, PartialEqImplUsedByEqualityOperators) {
+  namespace test = structs::comparison;
+  test::Version v1_2 = test::Version::create(1, 2);
+  EXPECT_TRUE(v1_2 == test::Version::create(1, 2));
+  EXPECT_FALSE(v1_2 != test::Version::create(1, 2));
+  EXPECT_FALSE(v1_2 == test::Version::create(1, 3));
+  EXPECT_TRUE(v1_2 != test::Version::create(1, 3));
+}
+
+TEST(StructsTest, OrdImplUsedByOrderingOperators) {
+  namespace test = structs::comparison;
+  test::Version v1_2 = test::Version::create(1, 2);
+  test::Version v1_10 = test::Version::create(1, 10);
+  EXPECT_TRUE(v1_2 < v1_10);
+  EXPECT_TRUE(v1_2 <= v1_10);
+  EXPECT_TRUE(v1_2 <= v1_2);
+  EXPECT_FALSE(v1_2 > v1_10);
+  EXPECT_TRUE(v1_10 >= v1_2);
+#if __cplusplus > 201703L
+  EXPECT_TRUE((v1_2 <=> v1_10) < 0);
+  EXPECT_TRUE((v1_2 <=> v1_2) == 0);
+#endif
+}
+
+TEST(StructsTest, OrdImplUsedByStdSort) {
+  namespace test = structs::comparison;
+  std::vector<test::Version> versions = {
+      test::Version::create(2, 0),
+      test::Version::create(1, 10),
+      test::Version::create(1, 2),
+  };
+  std::sort(versions.begin(), versions.end());
+  ASSERT_EQ(3, versions.size());
+  EXPECT_EQ(1, versions[0].get_major());
+  EXPECT_EQ(2, versions[0].get_minor());
+  EXPECT_EQ(1, versions[1].get_major());
+  EXPECT_EQ(10, versions[1].get_minor());
+  EXPECT_EQ(2, versions[2].get_major());
+  EXPECT_TRUE(std::is_sorted(versions.begin(), versions.end()));
+}
+
+TEST(StructsTest, CloneTypeIsMoveAssignable) {
+  namespace test = structs::comparison;
+  static_assert(!std::is_trivially_copyable_v<test::Task>);
+  static_assert(std::is_move_assignable_v<test::Task>);
+  test::Task task = test::Task::create(1);
+  task = test::Task::create(2);
+  EXPECT_EQ(2, task.get_priority());
+  test::Task other = test::Task::create(3);
+  task = std::move(other);
+  EXPECT_EQ(3, task.get_priority());
+}
+
+TEST(StructsTest, OrdImplOfCloneTypeUsedByStdSort) {
+  namespace test = structs::comparison;
+  std::vector<test::Task> tasks = {
+      test::Task::create(3),
+      test::Task::create(1),
+      test::Task::create(2),
+  };
+  std::sort(tasks.begin(), tasks.end());
+  ASSERT_EQ(3, tasks.size());
+  EXPECT_EQ(1, tasks[0].get_priority());
+  EXPECT_EQ(2, tasks[1].get_priority());
+  EXPECT_EQ(3, tasks[2].get_priority());
+  std::swap(tasks[0], tasks[2]);
+  EXPECT_EQ(3, tasks[0].get_priority());
+  EXPECT_EQ(1, tasks[2].get_priority());
+}
+
+TEST(StructsTest, PartialOrdImplWithUnorderedValues) {
+  namespace test = structs::comparison;
+  test::Measurement one = test::Measurement::create(1.0);
+  test::Measurement two = test::Measurement::create(2.0);
+  test::Measurement nan = test::Measurement::create_nan();
+  EXPECT_TRUE(one < two);
+  EXPECT_TRUE(two >= one);
+  EXPECT_FALSE(nan == nan);
+  EXPECT_FALSE(nan < one);
+  EXPECT_FALSE(nan <= one);
+  EXPECT_FALSE(nan > one);
+  EXPECT_FALSE(nan >= one);
+#if __cplusplus > 201703L
+  EXPECT_EQ(std::partial_ordering::unordered, nan <=> one);
+#endif
+}
+
+TEST(StructsTest, DisplayImplUsedByToString) {
+  namespace test = structs::fmt;
+  test::Point p = test::Point::create(1, 2);
+  EXPECT_EQ("(1, 2)", p.ToString());
+}
+
+TEST(StructsTest, DebugImplUsedByDebugString) {
+  namespace test = structs::fmt;
+  test::Point p = test::Point::create(1, 2);
+  EXPECT_EQ("Point { x: 1, y: 2 }", p.DebugString());
+}
+
+TEST(StructsTest, OstreamOperatorPrefersDisplayImpl) {
+  namespace test = structs::fmt;
+  std::ostringstream os;
+  os << test::Point::create(1, 2) << " " << test::DebugOnly::create(3);
+  EXPECT_EQ("(1, 2) DebugOnly { value: 3 }", os.str());
+}
+
+TEST(StructsTest, BoxReturnedByRustIsDroppedByCcDestructor) {
+  namespace test = structs::boxes;
+  int32_t drop_count = test::get_drop_count();
+  {
+    rs_std::Box<test::Tracked> b = test::make_box(123);
+    ASSERT_TRUE(b);
+    EXPECT_EQ(123, b->get_value());
+    EXPECT_EQ(drop_count, test::get_drop_count());
+  }
+  EXPECT_EQ(drop_count + 1, test::get_drop_count());
+}
+
+TEST(StructsTest, BoxMovedIntoRustIsDroppedOnce) {
+  namespace test = structs::boxes;
+  int32_t drop_count = test::get_drop_count();
+  {
+    rs_std::Box<test::Tracked> b = test::make_box(123);
+    EXPECT_EQ(123, test::consume_box(std::move(b)));
+    EXPECT_EQ(drop_count + 1, test::get_drop_count());
+  }
+  EXPECT_EQ(drop_count + 1, test::get_drop_count());
+}
+
+TEST(StructsTest, OptionalBox) {
+  namespace test = structs::boxes;
+  EXPECT_FALSE(test::make_optional_box(0));
+  rs_std::Box<test::Tracked> b = test::make_optional_box(456);
+  ASSERT_TRUE(b);
+  EXPECT_EQ(456, (*b).get_value());
+  EXPECT_EQ(456, test::consume_optional_box(std::move(b)));
+  EXPECT_EQ(-1, test::consume_optional_box(nullptr));
+}
+
+TEST(StructsTest, TupleField) {
+  namespace test = structs::tuple_fields;
+  // The tuple field is not replaced with a blob of bytes, so `Pair` has the
+  // same size and alignment as the Rust struct.
+  static_assert(sizeof(test::Pair) == 8);
+  static_assert(alignof(test::Pair) == 4);
+  test::Pair p = test::create(123, 45);
+  EXPECT_EQ(123, test::get_first(p));
+  EXPECT_EQ(45, test::get_second(p));
+}
+
 }  // namespace
 }  // namespace crubit
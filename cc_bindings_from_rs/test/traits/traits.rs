@@ -0,0 +1,117 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+//! This crate is used as a test input for `cc_bindings_from_rs` and the
+//! generated C++ bindings are then tested via `traits_test.cc`.
+
+/// APIs for testing `dyn Trait` objects (represented in C++ as an abstract
+/// class, and as `Trait::DynBox` and `Trait::DynArc` wrappers).
+pub mod dyn_tests {
+    use std::sync::Arc;
+
+    /// A trait that is implemented both in Rust (see `Square` below) and in
+    /// C++ (see `traits_test.cc`).
+    pub trait Shape {
+        fn area(&self) -> f64;
+        fn scale(&mut self, factor: f64);
+
+        /// Not available through a trait object.
+        fn name(&self) -> &'static str
+        where
+            Self: Sized,
+        {
+            "shape"
+        }
+    }
+
+    struct Square {
+        side: f64,
+    }
+
+    impl Shape for Square {
+        fn area(&self) -> f64 {
+            self.side * self.side
+        }
+        fn scale(&mut self, factor: f64) {
+            self.side *= factor;
+        }
+    }
+
+    pub fn make_square_box(side: f64) -> Box<dyn Shape> {
+        Box::new(Square { side })
+    }
+
+    pub fn make_square_arc(side: f64) -> Arc<dyn Shape> {
+        Arc::new(Square { side })
+    }
+
+    /// Calls `area` through the C++ vtable (if `shape` is a C++ object).
+    pub fn get_area(shape: &dyn Shape) -> f64 {
+        shape.area()
+    }
+
+    /// Calls `scale` through the C++ vtable (if `shape` is a C++ object).
+    pub fn scale_twice(shape: &mut dyn Shape, factor: f64) {
+        shape.scale(factor);
+        shape.scale(factor);
+    }
+
+    pub fn get_boxed_area(shape: Box<dyn Shape>) -> f64 {
+        shape.area()
+    }
+
+    pub fn get_arc_strong_count(shape: Arc<dyn Shape>) -> usize {
+        Arc::strong_count(&shape)
+    }
+}
+
+/// APIs for testing methods and associated constants from `impl`s of traits.
+/// Such items are exposed as members of the C++ class, unless their names
+/// clash with other members (in this case they are exposed in a namespace
+/// named after the trait).
+pub mod impl_tests {
+    pub trait Describe {
+        const KIND: i32;
+        fn with_id(id: i32) -> Self;
+        fn id(&self) -> i32;
+    }
+
+    /// The C++ class of an object-safe trait can't be in the same namespace as
+    /// the namespace of the clashing `Counter::get` below.
+    pub mod other_traits {
+        pub trait Counter {
+            fn get(&self) -> i32;
+            fn increment(&mut self);
+        }
+    }
+
+    pub struct Widget {
+        value: i32,
+    }
+
+    impl Widget {
+        pub fn get(&self) -> i32 {
+            self.value
+        }
+    }
+
+    impl Describe for Widget {
+        const KIND: i32 = 7;
+        fn with_id(id: i32) -> Self {
+            Self { value: id }
+        }
+        fn id(&self) -> i32 {
+            self.value
+        }
+    }
+
+    impl other_traits::Counter for Widget {
+        fn get(&self) -> i32 {
+            self.value * 10
+        }
+        fn increment(&mut self) {
+            self.value += 1;
+        }
+    }
+}
@@ -0,0 +1,83 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#include <utility>
+
+#include "gmock/gmock.h"
+#include "gtest/gtest.h"
+#include "cc_bindings_from_rs/test/traits/traits_cc_api.h"
+
+namespace crubit {
+namespace {
+
+using testing::DoubleEq;
+
+namespace dyn_tests = traits::dyn_tests;
+
+// A C++ implementation of the Rust `Shape` trait.
+class Rectangle final : public dyn_tests::Shape {
+ public:
+  Rectangle(double width, double height) : width_(width), height_(height) {}
+  double area() const override { return width_ * height_; }
+  void scale(double factor) override {
+    width_ *= factor;
+    height_ *= factor;
+  }
+
+ private:
+  double width_;
+  double height_;
+};
+
+TEST(DynTests, CcObjectAsDynRef) {
+  Rectangle rectangle(2.0, 3.0);
+  EXPECT_THAT(dyn_tests::get_area(rectangle), DoubleEq(6.0));
+}
+
+TEST(DynTests, CcObjectAsDynMutRef) {
+  Rectangle rectangle(2.0, 3.0);
+  dyn_tests::scale_twice(rectangle, 2.0);
+  EXPECT_THAT(rectangle.area(), DoubleEq(96.0));
+}
+
+TEST(DynTests, DynBox) {
+  dyn_tests::Shape::DynBox square = dyn_tests::make_square_box(3.0);
+  EXPECT_THAT(square.area(), DoubleEq(9.0));
+  square.scale(2.0);
+  EXPECT_THAT(square.area(), DoubleEq(36.0));
+
+  dyn_tests::Shape::DynBox moved = std::move(square);
+  EXPECT_THAT(moved.area(), DoubleEq(36.0));
+  EXPECT_THAT(dyn_tests::get_boxed_area(std::move(moved)), DoubleEq(36.0));
+}
+
+TEST(DynTests, DynArc) {
+  dyn_tests::Shape::DynArc square = dyn_tests::make_square_arc(3.0);
+  EXPECT_THAT(square.area(), DoubleEq(9.0));
+
+  dyn_tests::Shape::DynArc copy = square;
+  EXPECT_THAT(copy.area(), DoubleEq(9.0));
+  EXPECT_EQ(3, dyn_tests::get_arc_strong_count(copy));
+  EXPECT_EQ(2, dyn_tests::get_arc_strong_count(std::move(copy)));
+}
+
+namespace impl_tests = traits::impl_tests;
+
+TEST(ImplTests, MembersFromTraitImpls) {
+  impl_tests::Widget widget = impl_tests::Widget::with_id(123);
+  EXPECT_EQ(123, widget.id());
+  EXPECT_EQ(7, impl_tests::Widget::KIND);
+
+  widget.increment();
+  EXPECT_EQ(124, widget.id());
+}
+
+TEST(ImplTests, FreeFunctionsForClashingNames) {
+  impl_tests::Widget widget = impl_tests::Widget::with_id(5);
+  EXPECT_EQ(5, widget.get());
+  EXPECT_EQ(50, impl_tests::Counter::get(widget));
+}
+
+}  // namespace
+}  // namespace crubit
@@ -0,0 +1,28 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+//! This crate is used as a test input for `cc_bindings_from_rs` and the
+//! generated C++ bindings are then tested via `type_aliases_test.cc`.
+
+pub type TypeAliasOfI32 = i32;
+
+pub fn add_i32(x: TypeAliasOfI32, y: TypeAliasOfI32) -> TypeAliasOfI32 {
+    x + y
+}
+
+pub mod struct_aliases {
+    /// Type alias that is defined *before* the aliased struct.
+    pub type AliasOfPoint = Point;
+
+    pub struct Point {
+        pub x: i32,
+        pub y: i32,
+    }
+
+    pub type AliasOfAlias = AliasOfPoint;
+
+    pub fn create_point(x: i32, y: i32) -> AliasOfPoint {
+        Point { x, y }
+    }
+}
@@ -0,0 +1,34 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#include <cstdint>
+#include <type_traits>
+
+#include "gmock/gmock.h"
+#include "gtest/gtest.h"
+#include "cc_bindings_from_rs/test/type_aliases/type_aliases_cc_api.h"
+
+namespace crubit {
+namespace {
+
+static_assert(std::is_same_v<type_aliases::TypeAliasOfI32, std::int32_t>);
+static_assert(std::is_same_v<type_aliases::struct_aliases::AliasOfPoint,
+                             type_aliases::struct_aliases::Point>);
+static_assert(std::is_same_v<type_aliases::struct_aliases::AliasOfAlias,
+                             type_aliases::struct_aliases::Point>);
+
+TEST(TypeAliasesTest, AliasOfPrimitiveType) {
+  type_aliases::TypeAliasOfI32 x = 123;
+  EXPECT_EQ(123 + 456, type_aliases::add_i32(x, 456));
+}
+
+TEST(TypeAliasesTest, AliasOfStruct) {
+  namespace test = type_aliases::struct_aliases;
+  test::AliasOfPoint point = test::create_point(123, 456);
+  EXPECT_EQ(123, point.x);
+  EXPECT_EQ(456, point.y);
+}
+
+}  // namespace
+}  // namespace crubit
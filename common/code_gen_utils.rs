@@ -172,6 +172,13 @@ pub enum CcInclude {
 }
 
 impl CcInclude {
+    /// Creates a `CcInclude` that represents `#include <cassert>` and provides
+    /// C++ macros like `assert`.  See also
+    /// https://en.cppreference.com/w/cpp/header/cassert
+    pub fn cassert() -> Self {
+        Self::SystemHeader("cassert")
+    }
+
     /// Creates a `CcInclude` that represents `#include <cstddef>` and provides
     /// C++ types like `std::size_t` or `std::ptrdiff_t`.  See also
     /// https://en.cppreference.com/w/cpp/header/cstddef
@@ -186,19 +193,68 @@ impl CcInclude {
         Self::SystemHeader("cstdint")
     }
 
+    /// Creates a `CcInclude` that represents `#include <limits>` and provides
+    /// C++ types like `std::numeric_limits`.
+    /// See also https://en.cppreference.com/w/cpp/header/limits
+    pub fn limits() -> Self {
+        Self::SystemHeader("limits")
+    }
+
     /// Creates a `CcInclude` that represents `#include <memory>`.
     /// See also https://en.cppreference.com/w/cpp/header/memory
     pub fn memory() -> Self {
         Self::SystemHeader("memory")
     }
 
+    /// Creates a `CcInclude` that represents `#include <optional>` and provides
+    /// C++ types like `std::optional`.
+    /// See also https://en.cppreference.com/w/cpp/header/optional
+    pub fn optional() -> Self {
+        Self::SystemHeader("optional")
+    }
+
+    /// Creates a `CcInclude` that represents `#include <ostream>` and provides
+    /// C++ types like `std::ostream`.
+    /// See also https://en.cppreference.com/w/cpp/header/ostream
+    pub fn ostream() -> Self {
+        Self::SystemHeader("ostream")
+    }
+
+    /// Creates a `CcInclude` that represents `#include <string>` and provides
+    /// C++ types like `std::string`.
+    /// See also https://en.cppreference.com/w/cpp/header/string
+    pub fn string() -> Self {
+        Self::SystemHeader("string")
+    }
+
+    /// Creates a `CcInclude` that represents `#include <tuple>` and provides
+    /// C++ types like `std::tuple` and C++ functions like `std::get`.
+    /// See also https://en.cppreference.com/w/cpp/header/tuple
+    pub fn tuple() -> Self {
+        Self::SystemHeader("tuple")
+    }
+
+    /// Creates a `CcInclude` that represents `#include <type_traits>` and
+    /// provides C++ type traits like `std::add_pointer_t`.
+    /// See also https://en.cppreference.com/w/cpp/header/type_traits
+    pub fn type_traits() -> Self {
+        Self::SystemHeader("type_traits")
+    }
+
     /// Creates a `CcInclude` that represents `#include <utility>` and provides
-    /// C++ functions like `std::move` and C++ types like `std::tuple`.
+    /// C++ functions like `std::move` and C++ types like `std::pair`.
     /// See also https://en.cppreference.com/w/cpp/header/utility
     pub fn utility() -> Self {
         Self::SystemHeader("utility")
     }
 
+    /// Creates a `CcInclude` that represents `#include <variant>` and provides
+    /// C++ types like `std::variant` and `std::monostate`.
+    /// See also https://en.cppreference.com/w/cpp/header/variant
+    pub fn variant() -> Self {
+        Self::SystemHeader("variant")
+    }
+
     /// Creates a user include: `#include "some/path/to/header.h"`.
     pub fn user_header(path: Rc<str>) -> Self {
         Self::UserHeader(path)
@@ -0,0 +1,46 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#ifndef THIRD_PARTY_CRUBIT_SUPPORT_INTERNAL_CONST_FACTORY_H_
+#define THIRD_PARTY_CRUBIT_SUPPORT_INTERNAL_CONST_FACTORY_H_
+
+namespace crubit {
+
+// `ConstFactory` is used by the C++ bindings of Rust constants (generated by
+// `cc_bindings_from_rs`) to create values of Rust structs in C++ constant
+// expressions.  For example:
+//
+//     ```rs
+//     pub struct Point { x: i32, y: i32 }
+//     pub const ORIGIN: Point = Point { x: 0, y: 0 };
+//     ```
+//
+// The generated C++ header will look like this:
+//
+//     ```cc
+//     struct Point final {
+//       ...
+//      private:
+//       std::int32_t x;
+//       std::int32_t y;
+//       constexpr explicit Point(std::int32_t x, std::int32_t y) : x(x), y(y) {}
+//       friend struct crubit::ConstFactory;
+//     };
+//     inline constexpr Point ORIGIN = crubit::ConstFactory::Make<Point>(0, 0);
+//     ```
+//
+// The `constexpr` constructor of `Point` is private, because it doesn't
+// uphold the invariants that Rust code may associate with the (possibly
+// private) fields of the struct.  `ConstFactory` is an implementation detail
+// of the generated bindings and shouldn't be used directly by other C++ code.
+struct ConstFactory final {
+  template <typename T, typename... Args>
+  static constexpr T Make(Args... args) {
+    return T(args...);
+  }
+};
+
+}  // namespace crubit
+
+#endif  // THIRD_PARTY_CRUBIT_SUPPORT_INTERNAL_CONST_FACTORY_H_
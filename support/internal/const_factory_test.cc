@@ -0,0 +1,38 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#include "support/internal/const_factory.h"
+
+#include <cstdint>
+#include <type_traits>
+
+#include "gtest/gtest.h"
+
+namespace crubit {
+namespace {
+
+class Point final {
+ public:
+  std::int32_t x() const { return x_; }
+  std::int32_t y() const { return y_; }
+
+ private:
+  constexpr explicit Point(std::int32_t x, std::int32_t y) : x_(x), y_(y) {}
+  friend struct crubit::ConstFactory;
+
+  std::int32_t x_;
+  std::int32_t y_;
+};
+
+constexpr Point kPoint = ConstFactory::Make<Point>(1, -2);
+
+static_assert(!std::is_constructible_v<Point, std::int32_t, std::int32_t>);
+
+TEST(ConstFactoryTest, Make) {
+  EXPECT_EQ(1, kPoint.x());
+  EXPECT_EQ(-2, kPoint.y());
+}
+
+}  // namespace
+}  // namespace crubit
@@ -6,6 +6,7 @@
 #define CRUBIT_RS_BINDINGS_FROM_CC_SUPPORT_CXX20_BACKPORTS_H_
 
 #if __cplusplus > 201703L
+#include <compare>
 #include <memory>
 #include <type_traits>
 #else
@@ -0,0 +1,49 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#ifndef THIRD_PARTY_CRUBIT_SUPPORT_INTERNAL_FMT_H_
+#define THIRD_PARTY_CRUBIT_SUPPORT_INTERNAL_FMT_H_
+
+#include <cstddef>
+#include <string>
+
+namespace crubit {
+namespace internal {
+
+// Signature of `AppendToString` - used in the declarations of Rust thunks.
+using AppendToStringFn = void (*)(std::string* out, const char* data,
+                                  std::size_t size) noexcept;
+
+// `AppendToString` is used by the `ToString()` and `DebugString()` member
+// functions generated by `cc_bindings_from_rs` for Rust types that implement
+// the `Display` and `Debug` traits.  An example will help to illustrate how
+// this function is used by the generated bindings:
+//
+//     ```cc
+//     inline std::string SomeStruct::ToString() const {
+//       std::string result;
+//       __rust_thunk_for_to_string(this, &result,
+//                                  &crubit::internal::AppendToString);
+//       return result;
+//     }
+//     ```
+//
+// The Rust thunk calls `AppendToString` (possibly multiple times) to append
+// the formatted output to the `std::string` owned by the C++ caller.  The
+// data is copied - the memory pointed to by `data` only needs to be valid for
+// the duration of the call.
+//
+// `AppendToString` is called from Rust frames, and therefore it is `noexcept`:
+// unwinding a C++ exception (e.g. `std::bad_alloc` thrown by `append`) through
+// Rust frames would be Undefined Behavior, so such failures terminate the
+// process instead.
+inline void AppendToString(std::string* out, const char* data,
+                           std::size_t size) noexcept {
+  out->append(data, size);
+}
+
+}  // namespace internal
+}  // namespace crubit
+
+#endif  // THIRD_PARTY_CRUBIT_SUPPORT_INTERNAL_FMT_H_
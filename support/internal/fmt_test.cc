@@ -0,0 +1,42 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#include "support/internal/fmt.h"
+
+#include <cstddef>
+#include <string>
+#include <type_traits>
+
+#include "gtest/gtest.h"
+
+namespace crubit {
+namespace {
+
+TEST(AppendToString, AppendsAllChunks) {
+  std::string result;
+  internal::AppendToStringFn append = &internal::AppendToString;
+  std::string chunk1 = "foo";
+  std::string chunk2 = "bar";
+  append(&result, chunk1.data(), chunk1.size());
+  append(&result, chunk2.data(), 0);
+  append(&result, chunk2.data(), chunk2.size());
+  EXPECT_EQ("foobar", result);
+}
+
+TEST(AppendToString, EmbeddedNulls) {
+  std::string result;
+  internal::AppendToString(&result, "a\0b", 3);
+  EXPECT_EQ(std::string("a\0b", 3), result);
+}
+
+TEST(AppendToString, IsNoexcept) {
+  // Exceptions can't unwind through the Rust frames that call `AppendToString`.
+  static_assert(noexcept(internal::AppendToString(nullptr, nullptr, 0)));
+  static_assert(std::is_nothrow_invocable_v<internal::AppendToStringFn,
+                                            std::string*, const char*,
+                                            std::size_t>);
+}
+
+}  // namespace
+}  // namespace crubit
@@ -0,0 +1,30 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#ifndef THIRD_PARTY_CRUBIT_SUPPORT_INTERNAL_INT128_H_
+#define THIRD_PARTY_CRUBIT_SUPPORT_INTERNAL_INT128_H_
+
+// `cc_bindings_from_rs` maps Rust's `i128` and `u128` to `__int128` and
+// `unsigned __int128` (or to `absl::int128` and `absl::uint128`, which use the
+// builtin types under the hood when they are available).
+// `cc_bindings_from_rs` verifies that on the target platform the Rust types are
+// 16 bytes big and 16-byte aligned - the assertions below verify that the C++
+// types match this layout.  See also `rust_builtin_type_abi_assumptions.md`.
+
+#ifndef __SIZEOF_INT128__
+#error "Bindings of Rust's `i128` and `u128` require C++ support for `__int128`"
+#endif
+
+static_assert(sizeof(__int128) == 16,
+              "Rust's `i128` and C++'s `__int128` have a different size");
+static_assert(alignof(__int128) == 16,
+              "Rust's `i128` and C++'s `__int128` have a different alignment");
+static_assert(sizeof(unsigned __int128) == 16,
+              "Rust's `u128` and C++'s `unsigned __int128` have a different "
+              "size");
+static_assert(alignof(unsigned __int128) == 16,
+              "Rust's `u128` and C++'s `unsigned __int128` have a different "
+              "alignment");
+
+#endif  // THIRD_PARTY_CRUBIT_SUPPORT_INTERNAL_INT128_H_
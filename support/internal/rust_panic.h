@@ -0,0 +1,91 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#ifndef THIRD_PARTY_CRUBIT_SUPPORT_INTERNAL_RUST_PANIC_H_
+#define THIRD_PARTY_CRUBIT_SUPPORT_INTERNAL_RUST_PANIC_H_
+
+#include <cstddef>
+#include <cstdio>
+#include <cstdlib>
+#include <optional>
+#include <stdexcept>
+#include <string>
+#include <utility>
+
+namespace crubit {
+
+// `RustPanic` is thrown by the C++ bindings generated by `cc_bindings_from_rs
+// --panic-handling=report` when the called Rust function panics.  `what()`
+// returns the panic message.  See also `docs/panics_and_exceptions.md`.
+class RustPanic : public std::runtime_error {
+ public:
+  using std::runtime_error::runtime_error;
+};
+
+namespace internal {
+
+// `PanicSlot` is used to report a Rust panic from an `extern "C"` Rust thunk
+// to the C++ caller.  An example will help to illustrate how this class is used
+// by the generated bindings:
+//
+//     ```cc
+//              inline int32_t foo(int32_t arg) {
+//     /* 1 */    crubit::ReturnValueSlot<int32_t> __ret_slot;
+//     /* 2 */    crubit::internal::PanicSlot __panic_slot;
+//     /* 3 */    __rust_thunk_for_foo(arg, __ret_slot.Get(), &__panic_slot,
+//                                     &crubit::internal::PanicSlot::Report);
+//     /* 4 */    std::move(__panic_slot).ThrowIfPanicked();
+//     /* 5 */    return std::move(__ret_slot).AssumeInitAndTakeValue();
+//              }
+//     ```
+//
+// - On line 3 the Rust thunk catches a panic (if any) and calls `Report` to
+//   store the panic message in the `PanicSlot`.
+// - On line 4 `ThrowIfPanicked` throws `RustPanic` if a panic has been
+//   reported.  Note that in this case the return value slot has not been
+//   initialized (and therefore it is important that line 5 doesn't run).
+class PanicSlot {
+ public:
+  // Signature of `Report` - used in the declarations of Rust thunks.
+  using ReportFn = void (*)(PanicSlot* slot, const char* message_data,
+                            std::size_t message_size) noexcept;
+
+  PanicSlot() = default;
+
+  PanicSlot(const PanicSlot&) = delete;
+  PanicSlot& operator=(const PanicSlot&) = delete;
+
+  // Called by Rust thunks to report a panic.  The panic message is copied -
+  // the memory pointed to by `message_data` only needs to be valid for the
+  // duration of the call.
+  //
+  // `Report` is called from Rust frames, and therefore it is `noexcept`:
+  // unwinding a C++ exception (e.g. `std::bad_alloc` thrown when copying the
+  // message) through Rust frames would be Undefined Behavior, so such failures
+  // terminate the process instead.
+  static void Report(PanicSlot* slot, const char* message_data,
+                     std::size_t message_size) noexcept {
+    slot->message_.emplace(message_data, message_size);
+  }
+
+  // Throws `RustPanic` if a panic has been reported.  If C++ exceptions are
+  // disabled, then prints the panic message and aborts the process instead.
+  void ThrowIfPanicked() && {
+    if (!message_.has_value()) return;
+#if defined(__cpp_exceptions)
+    throw RustPanic(std::move(*message_));
+#else
+    std::fprintf(stderr, "Rust panic: %s\n", message_->c_str());
+    std::abort();
+#endif
+  }
+
+ private:
+  std::optional<std::string> message_;
+};
+
+}  // namespace internal
+}  // namespace crubit
+
+#endif  // THIRD_PARTY_CRUBIT_SUPPORT_INTERNAL_RUST_PANIC_H_
@@ -0,0 +1,57 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#include "support/internal/rust_panic.h"
+
+#include <cstddef>
+#include <string>
+#include <type_traits>
+#include <utility>
+
+#include "gtest/gtest.h"
+
+namespace crubit {
+namespace {
+
+TEST(PanicSlot, NoPanic) {
+  internal::PanicSlot slot;
+  // Expecting no crash / no exception.
+  std::move(slot).ThrowIfPanicked();
+}
+
+TEST(PanicSlot, ReportIsNoexcept) {
+  // Exceptions can't unwind through the Rust frames that call `Report`.
+  static_assert(noexcept(internal::PanicSlot::Report(nullptr, nullptr, 0)));
+  static_assert(std::is_nothrow_invocable_v<internal::PanicSlot::ReportFn,
+                                            internal::PanicSlot*, const char*,
+                                            std::size_t>);
+}
+
+#if defined(__cpp_exceptions)
+TEST(PanicSlot, ReportedPanicThrows) {
+  internal::PanicSlot slot;
+  std::string message = "Some panic message";
+  internal::PanicSlot::Report(&slot, message.data(), message.size());
+  try {
+    std::move(slot).ThrowIfPanicked();
+    FAIL() << "ThrowIfPanicked should throw";
+  } catch (const RustPanic& panic) {
+    EXPECT_STREQ("Some panic message", panic.what());
+  }
+}
+#else
+TEST(PanicSlot, ReportedPanicAborts) {
+  EXPECT_DEATH(
+      {
+        internal::PanicSlot slot;
+        std::string message = "Some panic message";
+        internal::PanicSlot::Report(&slot, message.data(), message.size());
+        std::move(slot).ThrowIfPanicked();
+      },
+      "Rust panic: Some panic message");
+}
+#endif
+
+}  // namespace
+}  // namespace crubit
@@ -0,0 +1,67 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#ifndef CRUBIT_SUPPORT_RS_STD_ARC_H_
+#define CRUBIT_SUPPORT_RS_STD_ARC_H_
+
+#include <utility>
+
+namespace rs_std {
+
+// `rs_std::Arc<T>` is a C++ representation of Rust's `Arc<T>` type (where `T`
+// is a type with C++ bindings generated by `cc_bindings_from_rs` - `Arc<dyn
+// Trait>` is represented by `Trait::DynArc` instead).
+// `rust_builtin_type_abi_assumptions.md` documents how values of this type are
+// passed across the FFI boundary.
+//
+// `Arc<T>` is like `rs_std::Rc<T>` (see `rc.h`), except that the (atomic)
+// reference count is managed by `__crubit_arc_clone(const T*)` and
+// `__crubit_arc_drop(const T*)`, and that copies of the same `Arc` may be
+// created and destroyed concurrently.
+//
+// A moved-from `Arc` is null and may only be destroyed or assigned to.
+// Passing a null `Arc` to Rust results in a Rust panic.
+template <typename T>
+class Arc final {
+ public:
+  Arc(const Arc& other) noexcept : ptr_(other.ptr_) {
+    if (ptr_ != nullptr) {
+      __crubit_arc_clone(ptr_);
+    }
+  }
+  Arc& operator=(const Arc& other) noexcept {
+    *this = Arc(other);
+    return *this;
+  }
+
+  Arc(Arc&& other) noexcept : ptr_(std::exchange(other.ptr_, nullptr)) {}
+  Arc& operator=(Arc&& other) noexcept {
+    if (this != &other) {
+      reset();
+      ptr_ = std::exchange(other.ptr_, nullptr);
+    }
+    return *this;
+  }
+
+  ~Arc() { reset(); }
+
+  // Precondition: `*this` is not null.
+  constexpr const T* get() const noexcept { return ptr_; }
+  constexpr const T& operator*() const noexcept { return *ptr_; }
+  constexpr const T* operator->() const noexcept { return ptr_; }
+
+ private:
+  void reset() noexcept {
+    if (const T* ptr = std::exchange(ptr_, nullptr)) {
+      __crubit_arc_drop(ptr);
+    }
+  }
+
+  // The pointer returned by Rust's `Arc::into_raw`.
+  const T* ptr_;
+};
+
+}  // namespace rs_std
+
+#endif  // CRUBIT_SUPPORT_RS_STD_ARC_H_
@@ -0,0 +1,90 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#include "support/rs_std/arc.h"
+
+#include <cstring>
+#include <type_traits>
+#include <utility>
+
+#include "gtest/gtest.h"
+#include "support/internal/return_value_slot.h"
+
+namespace {
+
+// `SomeStruct` stands in for a type with generated bindings - the generated
+// bindings define `__crubit_arc_clone` and `__crubit_arc_drop` next to the type
+// (so that they can be found via ADL).
+struct SomeStruct {
+  int value;
+  int strong_count;
+};
+
+void __crubit_arc_clone(const SomeStruct* ptr) {
+  const_cast<SomeStruct*>(ptr)->strong_count++;
+}
+
+void __crubit_arc_drop(const SomeStruct* ptr) {
+  const_cast<SomeStruct*>(ptr)->strong_count--;
+}
+
+// Creates an `Arc` the same way as the generated bindings (where a Rust thunk
+// writes the result of `Arc::into_raw` into a `crubit::ReturnValueSlot`).
+rs_std::Arc<SomeStruct> MakeArc(const SomeStruct* ptr) {
+  crubit::ReturnValueSlot<rs_std::Arc<SomeStruct>> slot;
+  std::memcpy(static_cast<void*>(slot.Get()), &ptr, sizeof(ptr));
+  return std::move(slot).AssumeInitAndTakeValue();
+}
+
+static_assert(std::is_nothrow_copy_constructible_v<rs_std::Arc<SomeStruct>>);
+static_assert(std::is_nothrow_copy_assignable_v<rs_std::Arc<SomeStruct>>);
+static_assert(std::is_nothrow_move_constructible_v<rs_std::Arc<SomeStruct>>);
+static_assert(std::is_nothrow_move_assignable_v<rs_std::Arc<SomeStruct>>);
+
+// Layout tests.
+//
+// `rust_builtin_type_abi_assumptions.md` documents that `Arc<T>` is passed
+// across the FFI boundary as the pointer returned by `Arc::into_raw`.
+static_assert(sizeof(rs_std::Arc<SomeStruct>) == sizeof(void*));
+static_assert(alignof(rs_std::Arc<SomeStruct>) == alignof(void*));
+static_assert(std::is_standard_layout_v<rs_std::Arc<SomeStruct>>);
+
+TEST(ArcTest, CopiesShareTheObject) {
+  SomeStruct s{/*value=*/123, /*strong_count=*/1};
+  {
+    rs_std::Arc<SomeStruct> arc1 = MakeArc(&s);
+    EXPECT_EQ(&s, arc1.get());
+    EXPECT_EQ(123, arc1->value);
+    {
+      rs_std::Arc<SomeStruct> arc2 = arc1;
+      EXPECT_EQ(2, s.strong_count);
+      EXPECT_EQ(&s, arc2.get());
+      EXPECT_EQ(123, (*arc2).value);
+
+      arc2 = arc1;
+      EXPECT_EQ(2, s.strong_count);
+    }
+    EXPECT_EQ(1, s.strong_count);
+  }
+  EXPECT_EQ(0, s.strong_count);
+}
+
+TEST(ArcTest, MovedFromIsNull) {
+  SomeStruct s{/*value=*/123, /*strong_count=*/1};
+  {
+    rs_std::Arc<SomeStruct> arc1 = MakeArc(&s);
+    rs_std::Arc<SomeStruct> arc2 = std::move(arc1);
+    EXPECT_EQ(nullptr, arc1.get());  // NOLINT(bugprone-use-after-move)
+    EXPECT_EQ(&s, arc2.get());
+    EXPECT_EQ(1, s.strong_count);
+
+    arc1 = std::move(arc2);
+    EXPECT_EQ(nullptr, arc2.get());  // NOLINT(bugprone-use-after-move)
+    EXPECT_EQ(&s, arc1.get());
+    EXPECT_EQ(1, s.strong_count);
+  }
+  EXPECT_EQ(0, s.strong_count);
+}
+
+}  // namespace
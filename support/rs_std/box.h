@@ -0,0 +1,69 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#ifndef CRUBIT_SUPPORT_RS_STD_BOX_H_
+#define CRUBIT_SUPPORT_RS_STD_BOX_H_
+
+#include <cstddef>
+#include <utility>
+
+namespace rs_std {
+
+// `rs_std::Box<T>` is a C++ representation of Rust's `Box<T>` and
+// `Option<Box<T>>` types (where `T` is a type with C++ bindings generated by
+// `cc_bindings_from_rs`).  `rust_builtin_type_abi_assumptions.md` documents
+// the ABI compatibility of these types.
+//
+// `Box<T>` has `std::unique_ptr`-like semantics: it owns a heap-allocated `T`
+// and is move-only.  The owned object has been allocated by Rust and therefore
+// has to be freed by Rust - the destructor calls `__crubit_box_drop(T*)` which
+// is found via ADL and which is defined in the generated bindings of `T` (it
+// calls a Rust thunk that drops the object and frees its memory).
+//
+// A null `Box` represents `None` of `Option<Box<T>>` (and is also what is left
+// behind in a moved-from `Box`).  Passing a null `Box` where Rust expects a
+// `Box<T>` (rather than an `Option<Box<T>>`) results in a Rust panic.
+template <typename T>
+class Box final {
+ public:
+  // Creates a null `Box`.
+  constexpr Box() noexcept : ptr_(nullptr) {}
+  constexpr Box(std::nullptr_t) noexcept  // NOLINT(google-explicit-constructor)
+      : Box() {}
+
+  Box(const Box&) = delete;
+  Box& operator=(const Box&) = delete;
+
+  Box(Box&& other) noexcept : ptr_(std::exchange(other.ptr_, nullptr)) {}
+  Box& operator=(Box&& other) noexcept {
+    if (this != &other) {
+      reset();
+      ptr_ = std::exchange(other.ptr_, nullptr);
+    }
+    return *this;
+  }
+
+  ~Box() { reset(); }
+
+  // Drops the owned object (if any) and leaves the `Box` null.
+  void reset() noexcept {
+    if (T* ptr = std::exchange(ptr_, nullptr)) {
+      __crubit_box_drop(ptr);
+    }
+  }
+
+  constexpr T* get() const noexcept { return ptr_; }
+  constexpr explicit operator bool() const noexcept { return ptr_ != nullptr; }
+
+  // Precondition: `*this` is not null.
+  constexpr T& operator*() const noexcept { return *ptr_; }
+  constexpr T* operator->() const noexcept { return ptr_; }
+
+ private:
+  T* ptr_;
+};
+
+}  // namespace rs_std
+
+#endif  // CRUBIT_SUPPORT_RS_STD_BOX_H_
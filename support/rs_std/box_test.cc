@@ -0,0 +1,69 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#include "support/rs_std/box.h"
+
+#include <type_traits>
+#include <utility>
+
+#include "gtest/gtest.h"
+
+namespace {
+
+// `SomeStruct` stands in for a type with generated bindings - the generated
+// bindings define `__crubit_box_drop` next to the type (so that it can be
+// found via ADL).
+struct SomeStruct {
+  int value;
+};
+
+int drop_count = 0;
+
+void __crubit_box_drop(SomeStruct* ptr) {
+  drop_count++;
+  delete ptr;
+}
+
+// `rs_std::Box<T>` is move-only.
+static_assert(!std::is_copy_constructible_v<rs_std::Box<SomeStruct>>);
+static_assert(!std::is_copy_assignable_v<rs_std::Box<SomeStruct>>);
+static_assert(std::is_nothrow_move_constructible_v<rs_std::Box<SomeStruct>>);
+static_assert(std::is_nothrow_move_assignable_v<rs_std::Box<SomeStruct>>);
+
+// Layout tests.
+//
+// `rust_builtin_type_abi_assumptions.md` documents that `Box<T>` and
+// `Option<Box<T>>` are represented as a (nullable) pointer.
+static_assert(sizeof(rs_std::Box<SomeStruct>) == sizeof(void*));
+static_assert(alignof(rs_std::Box<SomeStruct>) == alignof(void*));
+static_assert(std::is_standard_layout_v<rs_std::Box<SomeStruct>>);
+
+TEST(BoxTest, DefaultIsNull) {
+  drop_count = 0;
+  {
+    rs_std::Box<SomeStruct> b;
+    EXPECT_FALSE(b);
+    EXPECT_EQ(nullptr, b.get());
+
+    rs_std::Box<SomeStruct> from_nullptr = nullptr;
+    EXPECT_FALSE(from_nullptr);
+  }
+  EXPECT_EQ(0, drop_count);
+}
+
+TEST(BoxTest, MovedFromIsNull) {
+  drop_count = 0;
+  {
+    rs_std::Box<SomeStruct> b1;
+    rs_std::Box<SomeStruct> b2 = std::move(b1);
+    EXPECT_FALSE(b1);  // NOLINT(bugprone-use-after-move)
+    EXPECT_FALSE(b2);
+    b1 = std::move(b2);
+    EXPECT_FALSE(b2);  // NOLINT(bugprone-use-after-move)
+    b1.reset();
+  }
+  EXPECT_EQ(0, drop_count);
+}
+
+}  // namespace
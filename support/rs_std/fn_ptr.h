@@ -0,0 +1,62 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#ifndef CRUBIT_SUPPORT_RS_STD_FN_PTR_H_
+#define CRUBIT_SUPPORT_RS_STD_FN_PTR_H_
+
+namespace rs_std {
+
+template <typename F>
+class FnPtr;
+
+// `rs_std::FnPtr<R(Args...)>` is a C++ representation of a Rust-ABI function
+// pointer like `fn(i32) -> i32`.  (`extern "C"` function pointers are
+// represented directly as C++ function pointers.)
+//
+// C++ can't call a Rust-ABI function directly, so `FnPtr` holds both the
+// Rust function pointer and a pointer to an `extern "C"` trampoline (generated
+// by `cc_bindings_from_rs`) that forwards the call.  The layout of `FnPtr`
+// needs to stay in sync with `__CrubitFnPtr<F>` from the generated Rust
+// bindings.
+//
+// `FnPtr` can only be obtained from Rust (it can't wrap an arbitrary C++
+// function).  C++ callbacks should instead be passed to Rust functions that
+// take `extern "C"` function pointers.
+template <typename R, typename... Args>
+class FnPtr<R(Args...)> final {
+ public:
+  using Trampoline = R (*)(const void* fn, Args... args);
+
+  // Creates a `FnPtr` from a Rust function pointer `fn` and the `trampoline`
+  // that knows how to call it.
+  //
+  // Safety: `fn` has to be a Rust function pointer with a signature that
+  // corresponds to `R(Args...)` and `trampoline` has to be the corresponding
+  // trampoline generated by `cc_bindings_from_rs`.
+  static constexpr FnPtr from_raw_parts(const void* fn,
+                                        Trampoline trampoline) noexcept {
+    return FnPtr(fn, trampoline);
+  }
+
+  constexpr FnPtr(const FnPtr&) noexcept = default;
+  constexpr FnPtr& operator=(const FnPtr&) noexcept = default;
+
+  R operator()(Args... args) const {
+    return trampoline_(fn_, static_cast<Args>(args)...);
+  }
+
+  constexpr const void* fn() const noexcept { return fn_; }
+  constexpr Trampoline trampoline() const noexcept { return trampoline_; }
+
+ private:
+  constexpr FnPtr(const void* fn, Trampoline trampoline) noexcept
+      : fn_(fn), trampoline_(trampoline) {}
+
+  const void* fn_;
+  Trampoline trampoline_;
+};
+
+}  // namespace rs_std
+
+#endif  // CRUBIT_SUPPORT_RS_STD_FN_PTR_H_
@@ -0,0 +1,59 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#include "support/rs_std/fn_ptr.h"
+
+#include <cstdint>
+#include <type_traits>
+
+#include "gtest/gtest.h"
+
+namespace {
+
+using IntFn = rs_std::FnPtr<std::int32_t(std::int32_t, std::int32_t)>;
+
+// `FnPtr` is passed to/from Rust by value (as a pair of pointers).
+static_assert(std::is_trivially_copyable_v<IntFn>);
+static_assert(std::is_trivially_destructible_v<IntFn>);
+static_assert(sizeof(IntFn) == 2 * sizeof(void*));
+static_assert(alignof(IntFn) == alignof(void*));
+static_assert(std::is_standard_layout_v<IntFn>);
+
+// Stand-ins for a Rust function and for its generated trampoline.
+std::int32_t Add(std::int32_t x, std::int32_t y) { return x + y; }
+
+std::int32_t AddTrampoline(const void* fn, std::int32_t x, std::int32_t y) {
+  return reinterpret_cast<decltype(&Add)>(const_cast<void*>(fn))(x, y);
+}
+
+void IncrementTrampoline(const void* fn, std::int32_t* counter) {
+  EXPECT_EQ(nullptr, fn);
+  ++*counter;
+}
+
+TEST(FnPtrTest, Call) {
+  IntFn f = IntFn::from_raw_parts(reinterpret_cast<const void*>(&Add),
+                                  &AddTrampoline);
+  EXPECT_EQ(5, f(2, 3));
+  EXPECT_EQ(reinterpret_cast<const void*>(&Add), f.fn());
+  EXPECT_EQ(&AddTrampoline, f.trampoline());
+}
+
+TEST(FnPtrTest, Copy) {
+  IntFn f = IntFn::from_raw_parts(reinterpret_cast<const void*>(&Add),
+                                  &AddTrampoline);
+  IntFn copy = f;
+  EXPECT_EQ(7, copy(3, 4));
+}
+
+TEST(FnPtrTest, VoidReturn) {
+  auto f = rs_std::FnPtr<void(std::int32_t*)>::from_raw_parts(
+      nullptr, &IncrementTrampoline);
+  std::int32_t counter = 0;
+  f(&counter);
+  f(&counter);
+  EXPECT_EQ(2, counter);
+}
+
+}  // namespace
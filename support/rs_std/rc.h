@@ -0,0 +1,71 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#ifndef CRUBIT_SUPPORT_RS_STD_RC_H_
+#define CRUBIT_SUPPORT_RS_STD_RC_H_
+
+#include <utility>
+
+namespace rs_std {
+
+// `rs_std::Rc<T>` is a C++ representation of Rust's `Rc<T>` type (where `T` is
+// a type with C++ bindings generated by `cc_bindings_from_rs`).
+// `rust_builtin_type_abi_assumptions.md` documents how values of this type are
+// passed across the FFI boundary.
+//
+// `Rc<T>` has `std::shared_ptr`-like semantics: it shares the ownership of a
+// heap-allocated `T` that (like through Rust's `Rc<T>`) can only be accessed
+// as a `const T&`.  The reference count is managed by Rust - the copy
+// constructor and the destructor call `__crubit_rc_clone(const T*)` and
+// `__crubit_rc_drop(const T*)` which are found via ADL and which are defined
+// in the generated bindings of `T`.
+//
+// Like Rust's `Rc<T>`, `rs_std::Rc<T>` is not thread-safe: copies of the same
+// `Rc` may not be created or destroyed concurrently (`rs_std::Arc<T>` may).
+//
+// A moved-from `Rc` is null and may only be destroyed or assigned to.  Passing
+// a null `Rc` to Rust results in a Rust panic.
+template <typename T>
+class Rc final {
+ public:
+  Rc(const Rc& other) noexcept : ptr_(other.ptr_) {
+    if (ptr_ != nullptr) {
+      __crubit_rc_clone(ptr_);
+    }
+  }
+  Rc& operator=(const Rc& other) noexcept {
+    *this = Rc(other);
+    return *this;
+  }
+
+  Rc(Rc&& other) noexcept : ptr_(std::exchange(other.ptr_, nullptr)) {}
+  Rc& operator=(Rc&& other) noexcept {
+    if (this != &other) {
+      reset();
+      ptr_ = std::exchange(other.ptr_, nullptr);
+    }
+    return *this;
+  }
+
+  ~Rc() { reset(); }
+
+  // Precondition: `*this` is not null.
+  constexpr const T* get() const noexcept { return ptr_; }
+  constexpr const T& operator*() const noexcept { return *ptr_; }
+  constexpr const T* operator->() const noexcept { return ptr_; }
+
+ private:
+  void reset() noexcept {
+    if (const T* ptr = std::exchange(ptr_, nullptr)) {
+      __crubit_rc_drop(ptr);
+    }
+  }
+
+  // The pointer returned by Rust's `Rc::into_raw`.
+  const T* ptr_;
+};
+
+}  // namespace rs_std
+
+#endif  // CRUBIT_SUPPORT_RS_STD_RC_H_
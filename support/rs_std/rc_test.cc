@@ -0,0 +1,90 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#include "support/rs_std/rc.h"
+
+#include <cstring>
+#include <type_traits>
+#include <utility>
+
+#include "gtest/gtest.h"
+#include "support/internal/return_value_slot.h"
+
+namespace {
+
+// `SomeStruct` stands in for a type with generated bindings - the generated
+// bindings define `__crubit_rc_clone` and `__crubit_rc_drop` next to the type
+// (so that they can be found via ADL).
+struct SomeStruct {
+  int value;
+  int strong_count;
+};
+
+void __crubit_rc_clone(const SomeStruct* ptr) {
+  const_cast<SomeStruct*>(ptr)->strong_count++;
+}
+
+void __crubit_rc_drop(const SomeStruct* ptr) {
+  const_cast<SomeStruct*>(ptr)->strong_count--;
+}
+
+// Creates an `Rc` the same way as the generated bindings (where a Rust thunk
+// writes the result of `Rc::into_raw` into a `crubit::ReturnValueSlot`).
+rs_std::Rc<SomeStruct> MakeRc(const SomeStruct* ptr) {
+  crubit::ReturnValueSlot<rs_std::Rc<SomeStruct>> slot;
+  std::memcpy(static_cast<void*>(slot.Get()), &ptr, sizeof(ptr));
+  return std::move(slot).AssumeInitAndTakeValue();
+}
+
+static_assert(std::is_nothrow_copy_constructible_v<rs_std::Rc<SomeStruct>>);
+static_assert(std::is_nothrow_copy_assignable_v<rs_std::Rc<SomeStruct>>);
+static_assert(std::is_nothrow_move_constructible_v<rs_std::Rc<SomeStruct>>);
+static_assert(std::is_nothrow_move_assignable_v<rs_std::Rc<SomeStruct>>);
+
+// Layout tests.
+//
+// `rust_builtin_type_abi_assumptions.md` documents that `Rc<T>` is passed
+// across the FFI boundary as the pointer returned by `Rc::into_raw`.
+static_assert(sizeof(rs_std::Rc<SomeStruct>) == sizeof(void*));
+static_assert(alignof(rs_std::Rc<SomeStruct>) == alignof(void*));
+static_assert(std::is_standard_layout_v<rs_std::Rc<SomeStruct>>);
+
+TEST(RcTest, CopiesShareTheObject) {
+  SomeStruct s{/*value=*/123, /*strong_count=*/1};
+  {
+    rs_std::Rc<SomeStruct> rc1 = MakeRc(&s);
+    EXPECT_EQ(&s, rc1.get());
+    EXPECT_EQ(123, rc1->value);
+    {
+      rs_std::Rc<SomeStruct> rc2 = rc1;
+      EXPECT_EQ(2, s.strong_count);
+      EXPECT_EQ(&s, rc2.get());
+      EXPECT_EQ(123, (*rc2).value);
+
+      rc2 = rc1;
+      EXPECT_EQ(2, s.strong_count);
+    }
+    EXPECT_EQ(1, s.strong_count);
+  }
+  EXPECT_EQ(0, s.strong_count);
+}
+
+TEST(RcTest, MovedFromIsNull) {
+  SomeStruct s{/*value=*/123, /*strong_count=*/1};
+  {
+    rs_std::Rc<SomeStruct> rc1 = MakeRc(&s);
+    rs_std::Rc<SomeStruct> rc2 = std::move(rc1);
+    EXPECT_EQ(nullptr, rc1.get());  // NOLINT(bugprone-use-after-move)
+    EXPECT_EQ(&s, rc2.get());
+    EXPECT_EQ(1, s.strong_count);
+
+    rc1 = std::move(rc2);
+    EXPECT_EQ(nullptr, rc2.get());  // NOLINT(bugprone-use-after-move)
+    EXPECT_EQ(&s, rc1.get());
+    EXPECT_EQ(1, s.strong_count);
+  }
+  EXPECT_EQ(0, s.strong_count);
+}
+
+}  // namespace
@@ -0,0 +1,80 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#ifndef CRUBIT_SUPPORT_RS_STD_RESULT_H_
+#define CRUBIT_SUPPORT_RS_STD_RESULT_H_
+
+#include <optional>
+#include <utility>
+#include <variant>
+
+namespace rs_std {
+
+// `rs_std::Result<T, E>` is a C++ representation of Rust's `Result<T, E>`
+// type.  `Result<(), E>` is represented as `rs_std::Result<std::monostate, E>`.
+//
+// Unlike `rs_std::SliceRef<T>` or `rs_std::Box<T>`, `rs_std::Result<T, E>`
+// doesn't replicate the layout of the Rust type (Rust doesn't guarantee any
+// specific layout of `Result<T, E>`).  Instead, the bindings generated by
+// `cc_bindings_from_rs` marshal either the `T` value or the `E` error across
+// the FFI boundary.
+template <typename T, typename E>
+class Result final {
+ public:
+  // Mimics `Ok(value)` in Rust.
+  static Result Ok(T value) {
+    return Result(std::in_place_index<0>, std::move(value));
+  }
+
+  // Mimics `Err(error)` in Rust.
+  static Result Err(E error) {
+    return Result(std::in_place_index<1>, std::move(error));
+  }
+
+  bool is_ok() const noexcept { return value_.index() == 0; }
+  bool is_err() const noexcept { return value_.index() == 1; }
+
+  // Mimics Rust's `Result::ok` - returns the `T` value (or `std::nullopt` if
+  // `*this` holds an error).
+  std::optional<T> ok() const& {
+    if (!is_ok()) return std::nullopt;
+    return std::get<0>(value_);
+  }
+  std::optional<T> ok() && {
+    if (!is_ok()) return std::nullopt;
+    return std::get<0>(std::move(value_));
+  }
+
+  // Mimics Rust's `Result::err` - returns the `E` error (or `std::nullopt` if
+  // `*this` holds a `T` value).
+  std::optional<E> err() const& {
+    if (!is_err()) return std::nullopt;
+    return std::get<1>(value_);
+  }
+  std::optional<E> err() && {
+    if (!is_err()) return std::nullopt;
+    return std::get<1>(std::move(value_));
+  }
+
+  // Mimics `std::get_if` - returns a pointer to the `T` value (or `nullptr` if
+  // `*this` holds an error).
+  T* get_if_ok() noexcept { return std::get_if<0>(&value_); }
+  const T* get_if_ok() const noexcept { return std::get_if<0>(&value_); }
+
+  // Mimics `std::get_if` - returns a pointer to the `E` error (or `nullptr` if
+  // `*this` holds a `T` value).
+  E* get_if_err() noexcept { return std::get_if<1>(&value_); }
+  const E* get_if_err() const noexcept { return std::get_if<1>(&value_); }
+
+ private:
+  template <std::size_t kIndex, typename U>
+  Result(std::in_place_index_t<kIndex> index, U&& value)
+      : value_(index, std::forward<U>(value)) {}
+
+  std::variant<T, E> value_;
+};
+
+}  // namespace rs_std
+
+#endif  // CRUBIT_SUPPORT_RS_STD_RESULT_H_
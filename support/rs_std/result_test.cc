@@ -0,0 +1,66 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#include "support/rs_std/result.h"
+
+#include <memory>
+#include <optional>
+#include <string>
+#include <utility>
+#include <variant>
+
+#include "gtest/gtest.h"
+
+namespace {
+
+TEST(ResultTest, Ok) {
+  auto result = rs_std::Result<int, std::string>::Ok(123);
+  EXPECT_TRUE(result.is_ok());
+  EXPECT_FALSE(result.is_err());
+  EXPECT_EQ(std::optional<int>(123), result.ok());
+  EXPECT_EQ(std::nullopt, result.err());
+}
+
+TEST(ResultTest, Err) {
+  auto result = rs_std::Result<int, std::string>::Err("error message");
+  EXPECT_FALSE(result.is_ok());
+  EXPECT_TRUE(result.is_err());
+  EXPECT_EQ(std::nullopt, result.ok());
+  EXPECT_EQ(std::optional<std::string>("error message"), result.err());
+}
+
+TEST(ResultTest, SameOkAndErrTypes) {
+  auto ok = rs_std::Result<int, int>::Ok(1);
+  auto err = rs_std::Result<int, int>::Err(2);
+  EXPECT_EQ(std::optional<int>(1), ok.ok());
+  EXPECT_EQ(std::nullopt, ok.err());
+  EXPECT_EQ(std::nullopt, err.ok());
+  EXPECT_EQ(std::optional<int>(2), err.err());
+}
+
+TEST(ResultTest, GetIf) {
+  auto ok = rs_std::Result<int, std::string>::Ok(1);
+  auto err = rs_std::Result<int, std::string>::Err("error message");
+  ASSERT_NE(nullptr, ok.get_if_ok());
+  EXPECT_EQ(1, *ok.get_if_ok());
+  EXPECT_EQ(nullptr, ok.get_if_err());
+  EXPECT_EQ(nullptr, err.get_if_ok());
+  ASSERT_NE(nullptr, err.get_if_err());
+  EXPECT_EQ("error message", *err.get_if_err());
+}
+
+TEST(ResultTest, Unit) {
+  auto result = rs_std::Result<std::monostate, int>::Ok(std::monostate());
+  EXPECT_TRUE(result.is_ok());
+}
+
+TEST(ResultTest, MoveOnlyTypes) {
+  auto result =
+      rs_std::Result<std::unique_ptr<int>, int>::Ok(std::make_unique<int>(42));
+  std::optional<std::unique_ptr<int>> value = std::move(result).ok();
+  ASSERT_TRUE(value.has_value());
+  EXPECT_EQ(42, **value);
+}
+
+}  // namespace
@@ -0,0 +1,80 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#ifndef CRUBIT_SUPPORT_RS_STD_RS_STRING_H_
+#define CRUBIT_SUPPORT_RS_STD_RS_STRING_H_
+
+#include <cstddef>
+#include <string_view>
+#include <utility>
+
+// Relative to this header, because the generated bindings may include
+// `rs_std` headers from a different path (see `--crubit-support-path`).
+#include "vec.h"
+
+namespace rs_std {
+
+// `rs_std::String` is a C++ representation of Rust's `String` type.
+//
+// `String` owns a buffer of UTF-8 bytes that has been allocated by Rust (see
+// also the doc comment of `rs_std::Vec<T>` - a `String` is a `Vec<char>`
+// under the hood).  Similarly to Rust's `String`, `rs_std::String` always
+// holds valid UTF-8 - this is why it only provides read-only access to its
+// bytes.
+class String final {
+ public:
+  using DropFn = Vec<char>::DropFn;
+
+  // Creates an empty `String`.
+  String() noexcept = default;
+
+  // Takes ownership of the `size` UTF-8 bytes starting at `data`.  This
+  // function mimics Rust's `String::from_raw_parts` and is intended to only be
+  // used by the generated bindings (and by tests): `data` has to point to a
+  // buffer that has been allocated by Rust, and `drop` has to free this
+  // buffer.
+  static String from_raw_parts(char* data, size_t size, size_t capacity,
+                               DropFn drop) noexcept {
+    return String(Vec<char>::from_raw_parts(data, size, capacity, drop));
+  }
+
+  String(const String&) = delete;
+  String& operator=(const String&) = delete;
+  String(String&&) noexcept = default;
+  String& operator=(String&&) noexcept = default;
+  ~String() = default;
+
+  // Frees the buffer and leaves the `String` empty.
+  void reset() noexcept { bytes_.reset(); }
+
+  // Converts a `rs_std::String` into a `std::string_view`.
+  std::string_view to_string_view() const noexcept {
+    return std::string_view(bytes_.data(), bytes_.size());
+  }
+  operator std::string_view()  // NOLINT(google-explicit-constructor)
+      const noexcept {
+    return to_string_view();
+  }
+
+  // Returns a pointer to the first byte.  Note that the returned pointer is
+  // never null, but it is dangling (i.e. may not be dereferenced) when the
+  // string is empty.  Note that (unlike `std::string`) the bytes are not
+  // followed by a NUL terminator.
+  const char* data() const noexcept { return bytes_.data(); }
+  size_t size() const noexcept { return bytes_.size(); }
+  size_t capacity() const noexcept { return bytes_.capacity(); }
+  bool empty() const noexcept { return bytes_.empty(); }
+
+  const char* begin() const noexcept { return bytes_.begin(); }
+  const char* end() const noexcept { return bytes_.end(); }
+
+ private:
+  explicit String(Vec<char> bytes) noexcept : bytes_(std::move(bytes)) {}
+
+  Vec<char> bytes_;
+};
+
+}  // namespace rs_std
+
+#endif  // CRUBIT_SUPPORT_RS_STD_RS_STRING_H_
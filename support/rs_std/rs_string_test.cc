@@ -0,0 +1,91 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#include "support/rs_std/rs_string.h"
+
+#include <cstddef>
+#include <cstring>
+#include <memory>
+#include <string>
+#include <string_view>
+#include <type_traits>
+#include <utility>
+
+#include "gtest/gtest.h"
+
+namespace {
+
+// `rs_std::String` is move-only.
+static_assert(!std::is_copy_constructible_v<rs_std::String>);
+static_assert(!std::is_copy_assignable_v<rs_std::String>);
+static_assert(std::is_nothrow_move_constructible_v<rs_std::String>);
+static_assert(std::is_nothrow_move_assignable_v<rs_std::String>);
+
+// Layout tests.
+//
+// The generated bindings write the raw parts of a Rust `String` (a pointer, a
+// length, a capacity, and a drop function) directly into `rs_std::String`.
+static_assert(sizeof(rs_std::String) == 4 * sizeof(void*));
+static_assert(alignof(rs_std::String) == alignof(void*));
+static_assert(std::is_standard_layout_v<rs_std::String>);
+
+int drop_count = 0;
+
+// Stands in for the Rust function that drops a Rust `String`.
+void DropCharBuffer(char* data, size_t size, size_t capacity) {
+  drop_count++;
+  std::allocator<char>().deallocate(data, capacity);
+}
+
+rs_std::String MakeString(std::string_view s) {
+  char* data = std::allocator<char>().allocate(s.size());
+  std::memcpy(data, s.data(), s.size());
+  return rs_std::String::from_raw_parts(data, s.size(), s.size(),
+                                        &DropCharBuffer);
+}
+
+TEST(RsStringTest, Empty) {
+  drop_count = 0;
+  {
+    rs_std::String s;
+    EXPECT_TRUE(s.empty());
+    EXPECT_EQ(0, s.size());
+    EXPECT_EQ("", s.to_string_view());
+
+    // Rust requires a non-null pointer even for empty strings.
+    EXPECT_NE(nullptr, s.data());
+  }
+  EXPECT_EQ(0, drop_count);
+}
+
+TEST(RsStringTest, StringView) {
+  drop_count = 0;
+  {
+    rs_std::String s = MakeString("hello");
+    EXPECT_FALSE(s.empty());
+    EXPECT_EQ(5, s.size());
+    std::string_view sv = s;
+    EXPECT_EQ("hello", sv);
+    EXPECT_EQ(s.data(), sv.data());
+    EXPECT_EQ("hello", std::string(s.begin(), s.end()));
+  }
+  EXPECT_EQ(1, drop_count);
+}
+
+TEST(RsStringTest, Move) {
+  drop_count = 0;
+  {
+    rs_std::String s1 = MakeString("foo");
+    rs_std::String s2 = std::move(s1);
+    EXPECT_TRUE(s1.empty());  // NOLINT(bugprone-use-after-move)
+    EXPECT_EQ("foo", s2.to_string_view());
+
+    s2 = MakeString("bar");  // Drops the old value of `s2`.
+    EXPECT_EQ(1, drop_count);
+    EXPECT_EQ("bar", s2.to_string_view());
+  }
+  EXPECT_EQ(2, drop_count);
+}
+
+}  // namespace
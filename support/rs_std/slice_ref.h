@@ -0,0 +1,88 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#ifndef CRUBIT_SUPPORT_RS_STD_SLICE_REF_H_
+#define CRUBIT_SUPPORT_RS_STD_SLICE_REF_H_
+
+#include <cstddef>
+#include <cstdint>
+#include <type_traits>
+
+#if __cplusplus > 201703L
+#include <span>
+#endif
+
+namespace rs_std {
+
+// `rs_std::SliceRef<T>` is a C++ representation of a reference to a Rust
+// slice: `SliceRef<const T>` represents `&[T]` and `SliceRef<T>` represents
+// `&mut [T]`.  `rust_builtin_type_abi_assumptions.md` documents the ABI
+// compatibility of these types.
+//
+// Note that (unlike `std::span`) `SliceRef` never stores a null pointer - Rust
+// requires that even empty slices use a non-null, aligned pointer.
+template <typename T>
+class SliceRef final {
+ public:
+  // Creates an empty `SliceRef`.
+  SliceRef() noexcept : data_(Dangling()), size_(0) {}
+
+  // Creates a `SliceRef` that refers to the `size` elements starting at
+  // `data`.  `data` may be null if `size` is 0.
+  SliceRef(T* data, size_t size) noexcept
+      : data_(size == 0 ? Dangling() : data), size_(size) {}
+
+  // Converts a `SliceRef<T>` into a `SliceRef<const T>` (this mimics how
+  // Rust coerces `&mut [T]` into `&[T]`).
+  template <typename U,
+            typename = std::enable_if_t<std::is_same_v<const U, T> &&
+                                        !std::is_same_v<U, T>>>
+  SliceRef(SliceRef<U> other) noexcept  // NOLINT(google-explicit-constructor)
+      : data_(other.data()), size_(other.size()) {}
+
+#if __cplusplus > 201703L
+  // Converts a `std::span<T>` into a `SliceRef<T>`.
+  SliceRef(std::span<T> span) noexcept  // NOLINT(google-explicit-constructor)
+      : SliceRef(span.data(), span.size()) {}
+
+  // Converts a `SliceRef<T>` into a `std::span<T>`.
+  std::span<T> to_span() const noexcept { return std::span<T>(data_, size_); }
+  operator std::span<T>() const noexcept {  // NOLINT(google-explicit-constructor)
+    return to_span();
+  }
+#endif
+
+  constexpr SliceRef(const SliceRef&) = default;
+  constexpr SliceRef& operator=(const SliceRef&) = default;
+  constexpr SliceRef(SliceRef&&) = default;
+  constexpr SliceRef& operator=(SliceRef&&) = default;
+  ~SliceRef() = default;
+
+  // Returns a pointer to the first element.  Note that the returned pointer is
+  // never null, but it is dangling (i.e. may not be dereferenced) when the
+  // slice is empty.
+  constexpr T* data() const noexcept { return data_; }
+  constexpr size_t size() const noexcept { return size_; }
+  constexpr bool empty() const noexcept { return size_ == 0; }
+
+  constexpr T* begin() const noexcept { return data_; }
+  constexpr T* end() const noexcept { return data_ + size_; }
+
+  // Precondition: `i < size()`.
+  constexpr T& operator[](size_t i) const noexcept { return data_[i]; }
+
+ private:
+  // Mimics Rust's `NonNull::dangling` - a non-null, well-aligned pointer that
+  // is used by empty slices.
+  static T* Dangling() noexcept {
+    return reinterpret_cast<T*>(static_cast<std::uintptr_t>(alignof(T)));
+  }
+
+  T* data_;
+  size_t size_;
+};
+
+}  // namespace rs_std
+
+#endif  // CRUBIT_SUPPORT_RS_STD_SLICE_REF_H_
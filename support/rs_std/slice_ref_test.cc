@@ -0,0 +1,93 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#include "support/rs_std/slice_ref.h"
+
+#include <stdint.h>
+
+#include <type_traits>
+#include <vector>
+
+#include "gmock/gmock.h"
+#include "gtest/gtest.h"
+
+namespace {
+
+// Check that `rs_std::SliceRef` is trivially destructible, copyable, and
+// moveable (this is required for passing it by value to/from Rust).
+static_assert(std::is_trivially_destructible_v<rs_std::SliceRef<const int>>);
+static_assert(
+    std::is_trivially_copy_constructible_v<rs_std::SliceRef<const int>>);
+static_assert(std::is_trivially_copy_assignable_v<rs_std::SliceRef<const int>>);
+static_assert(
+    std::is_trivially_move_constructible_v<rs_std::SliceRef<const int>>);
+static_assert(std::is_trivially_move_assignable_v<rs_std::SliceRef<const int>>);
+
+// Layout tests.
+//
+// `rust_builtin_type_abi_assumptions.md` documents that `&[T]` is a (pointer,
+// length) pair.
+static_assert(sizeof(rs_std::SliceRef<const int>) == 2 * sizeof(void*));
+static_assert(alignof(rs_std::SliceRef<const int>) == alignof(void*));
+static_assert(std::is_standard_layout_v<rs_std::SliceRef<const int>>);
+
+// `SliceRef<T>` (i.e. `&mut [T]`) converts into `SliceRef<const T>` (i.e.
+// `&[T]`), but not the other way around.
+static_assert(std::is_convertible_v<rs_std::SliceRef<int>,
+                                    rs_std::SliceRef<const int>>);
+static_assert(!std::is_convertible_v<rs_std::SliceRef<const int>,
+                                     rs_std::SliceRef<int>>);
+
+TEST(SliceRefTest, Empty) {
+  rs_std::SliceRef<const int64_t> s;
+  EXPECT_TRUE(s.empty());
+  EXPECT_EQ(0, s.size());
+  EXPECT_EQ(s.begin(), s.end());
+
+  // Rust requires a non-null, aligned pointer even for empty slices.
+  ASSERT_NE(nullptr, s.data());
+  EXPECT_EQ(0, reinterpret_cast<uintptr_t>(s.data()) % alignof(int64_t));
+}
+
+TEST(SliceRefTest, EmptyFromNullptr) {
+  rs_std::SliceRef<const int64_t> s(nullptr, 0);
+  EXPECT_TRUE(s.empty());
+  ASSERT_NE(nullptr, s.data());
+  EXPECT_EQ(0, reinterpret_cast<uintptr_t>(s.data()) % alignof(int64_t));
+}
+
+TEST(SliceRefTest, FromPointerAndSize) {
+  std::vector<int> v = {1, 2, 3};
+  rs_std::SliceRef<const int> s(v.data(), v.size());
+  EXPECT_FALSE(s.empty());
+  EXPECT_EQ(v.data(), s.data());
+  EXPECT_EQ(3, s.size());
+  EXPECT_EQ(2, s[1]);
+  EXPECT_THAT(std::vector<int>(s.begin(), s.end()),
+              testing::ElementsAre(1, 2, 3));
+}
+
+TEST(SliceRefTest, Mutation) {
+  std::vector<int> v = {1, 2, 3};
+  rs_std::SliceRef<int> s(v.data(), v.size());
+  s[0] = 10;
+  for (int& i : s) i += 1;
+  EXPECT_THAT(v, testing::ElementsAre(11, 3, 4));
+
+  rs_std::SliceRef<const int> const_s = s;
+  EXPECT_EQ(s.data(), const_s.data());
+  EXPECT_EQ(s.size(), const_s.size());
+}
+
+#if __cplusplus > 201703L
+TEST(SliceRefTest, SpanRoundTrip) {
+  std::vector<int> v = {1, 2, 3};
+  rs_std::SliceRef<const int> s = std::span<const int>(v);
+  std::span<const int> span = s;
+  EXPECT_EQ(v.data(), span.data());
+  EXPECT_EQ(3, span.size());
+}
+#endif
+
+}  // namespace
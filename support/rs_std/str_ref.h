@@ -0,0 +1,128 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#ifndef CRUBIT_SUPPORT_RS_STD_STR_REF_H_
+#define CRUBIT_SUPPORT_RS_STD_STR_REF_H_
+
+#include <cstddef>
+#include <cstdint>
+#include <optional>
+#include <string_view>
+
+#include "absl/base/optimization.h"
+
+namespace rs_std {
+
+// `rs_std::StrRef` is a C++ representation of the `&str` type from Rust.
+// `rust_builtin_type_abi_assumptions.md` documents the ABI compatibility of
+// these types.
+//
+// Similarly to Rust's `&str`, `StrRef` always refers to valid UTF-8 bytes.
+// Additionally (unlike `std::string_view`) `StrRef` never stores a null
+// pointer - Rust requires that even empty strings use a non-null pointer.
+class StrRef final {
+ public:
+  // Creates an empty `StrRef`.
+  StrRef() noexcept : StrRef(Dangling(), 0) {}
+
+  // Converts a `std::string_view` into a `rs_std::StrRef`.
+  //
+  // Note that not all `std::string_view`s are valid `rs_std::StrRef`s.
+  // `from_utf8` will return `std::nullopt` if the input is not valid UTF-8.
+  //
+  // This function mimics Rust's `std::str::from_utf8`:
+  // https://doc.rust-lang.org/std/str/fn.from_utf8.html
+  static std::optional<StrRef> from_utf8(std::string_view s) {
+    if (ABSL_PREDICT_FALSE(!IsValidUtf8(s))) {
+      return std::nullopt;
+    }
+    return from_utf8_unchecked(s);
+  }
+
+  constexpr StrRef(const StrRef&) = default;
+  constexpr StrRef& operator=(const StrRef&) = default;
+  constexpr StrRef(StrRef&&) = default;
+  constexpr StrRef& operator=(StrRef&&) = default;
+  ~StrRef() = default;
+
+  // Converts a `rs_std::StrRef` into a `std::string_view`.
+  constexpr std::string_view to_string_view() const noexcept {
+    return std::string_view(data_, size_);
+  }
+  constexpr operator std::string_view()  // NOLINT(google-explicit-constructor)
+      const noexcept {
+    return to_string_view();
+  }
+
+  // Returns a pointer to the first byte.  Note that the returned pointer is
+  // never null, but it is dangling (i.e. may not be dereferenced) when the
+  // string is empty.
+  constexpr const char* data() const noexcept { return data_; }
+  constexpr size_t size() const noexcept { return size_; }
+  constexpr bool empty() const noexcept { return size_ == 0; }
+
+ private:
+  // This function mimics Rust's `std::str::from_utf8_unchecked`:
+  // https://doc.rust-lang.org/std/str/fn.from_utf8_unchecked.html
+  //
+  // TODO(b/254095482): Figure out how to annotate/expose unsafe functions in
+  // C++ and then make this method public.
+  static StrRef from_utf8_unchecked(std::string_view s) {
+    return s.empty() ? StrRef() : StrRef(s.data(), s.size());
+  }
+
+  // Private constructor - intended to only be used from `from_utf8_unchecked`.
+  constexpr StrRef(const char* data, size_t size) : data_(data), size_(size) {}
+
+  // Mimics Rust's `NonNull::dangling` for `u8`.
+  static const char* Dangling() noexcept {
+    return reinterpret_cast<const char*>(static_cast<std::uintptr_t>(1));
+  }
+
+  // Validates UTF-8 the same way as Rust's `std::str::from_utf8` (e.g.
+  // overlong encodings, surrogates and code points above U+10FFFF are
+  // rejected).
+  static constexpr bool IsValidUtf8(std::string_view s) {
+    size_t i = 0;
+    while (i < s.size()) {
+      unsigned char c = static_cast<unsigned char>(s[i]);
+      if (c < 0x80) {
+        i += 1;
+        continue;
+      }
+      size_t len;
+      unsigned char min_second = 0x80;
+      unsigned char max_second = 0xbf;
+      if (c >= 0xc2 && c <= 0xdf) {
+        len = 2;
+      } else if (c >= 0xe0 && c <= 0xef) {
+        len = 3;
+        if (c == 0xe0) min_second = 0xa0;  // Overlong encoding.
+        if (c == 0xed) max_second = 0x9f;  // Surrogates.
+      } else if (c >= 0xf0 && c <= 0xf4) {
+        len = 4;
+        if (c == 0xf0) min_second = 0x90;  // Overlong encoding.
+        if (c == 0xf4) max_second = 0x8f;  // Above U+10FFFF.
+      } else {
+        return false;
+      }
+      if (s.size() - i < len) return false;
+      unsigned char second = static_cast<unsigned char>(s[i + 1]);
+      if (second < min_second || second > max_second) return false;
+      for (size_t j = 2; j < len; ++j) {
+        unsigned char continuation = static_cast<unsigned char>(s[i + j]);
+        if (continuation < 0x80 || continuation > 0xbf) return false;
+      }
+      i += len;
+    }
+    return true;
+  }
+
+  const char* data_;
+  size_t size_;
+};
+
+}  // namespace rs_std
+
+#endif  // CRUBIT_SUPPORT_RS_STD_STR_REF_H_
@@ -0,0 +1,87 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#include "support/rs_std/str_ref.h"
+
+#include <stdint.h>
+
+#include <optional>
+#include <string_view>
+#include <type_traits>
+
+#include "gmock/gmock.h"
+#include "gtest/gtest.h"
+
+namespace {
+
+// Check that `rs_std::StrRef` is trivially destructible, copyable, and
+// moveable (this is required for passing it by value to/from Rust).
+static_assert(std::is_trivially_destructible_v<rs_std::StrRef>);
+static_assert(std::is_trivially_copy_constructible_v<rs_std::StrRef>);
+static_assert(std::is_trivially_copy_assignable_v<rs_std::StrRef>);
+static_assert(std::is_trivially_move_constructible_v<rs_std::StrRef>);
+static_assert(std::is_trivially_move_assignable_v<rs_std::StrRef>);
+
+// Layout tests.
+//
+// `rust_builtin_type_abi_assumptions.md` documents that `&str` is a (pointer,
+// length) pair.
+static_assert(sizeof(rs_std::StrRef) == 2 * sizeof(void*));
+static_assert(alignof(rs_std::StrRef) == alignof(void*));
+static_assert(std::is_standard_layout_v<rs_std::StrRef>);
+
+TEST(StrRefTest, Empty) {
+  rs_std::StrRef s;
+  EXPECT_TRUE(s.empty());
+  EXPECT_EQ(0, s.size());
+  EXPECT_NE(nullptr, s.data());
+  EXPECT_EQ("", s.to_string_view());
+}
+
+TEST(StrRefTest, FromEmptyStringView) {
+  std::optional<rs_std::StrRef> s = rs_std::StrRef::from_utf8({});
+  ASSERT_TRUE(s.has_value());
+  EXPECT_TRUE(s->empty());
+
+  // Rust requires a non-null pointer even for empty strings (unlike a
+  // default-constructed `std::string_view`).
+  EXPECT_NE(nullptr, s->data());
+}
+
+TEST(StrRefTest, FromAscii) {
+  std::string_view sv = "foo";
+  std::optional<rs_std::StrRef> s = rs_std::StrRef::from_utf8(sv);
+  ASSERT_TRUE(s.has_value());
+  EXPECT_EQ(sv.data(), s->data());
+  EXPECT_EQ(3, s->size());
+  std::string_view round_trip = *s;
+  EXPECT_EQ("foo", round_trip);
+}
+
+TEST(StrRefTest, FromValidUtf8) {
+  EXPECT_TRUE(rs_std::StrRef::from_utf8("\xC4\x85").has_value());  // U+0105
+  EXPECT_TRUE(rs_std::StrRef::from_utf8("\xE2\x82\xAC").has_value());  // €
+  EXPECT_TRUE(
+      rs_std::StrRef::from_utf8("\xF0\x9F\x98\x80").has_value());  // U+1F600
+  EXPECT_TRUE(rs_std::StrRef::from_utf8("\xF4\x8F\xBF\xBF")
+                  .has_value());  // U+10FFFF
+}
+
+TEST(StrRefTest, FromInvalidUtf8) {
+  // Unexpected continuation byte.
+  EXPECT_FALSE(rs_std::StrRef::from_utf8("\x80").has_value());
+  // Truncated sequences.
+  EXPECT_FALSE(rs_std::StrRef::from_utf8("\xC4").has_value());
+  EXPECT_FALSE(rs_std::StrRef::from_utf8("\xE2\x82").has_value());
+  // Overlong encodings.
+  EXPECT_FALSE(rs_std::StrRef::from_utf8("\xC0\xAF").has_value());
+  EXPECT_FALSE(rs_std::StrRef::from_utf8("\xE0\x80\xAF").has_value());
+  EXPECT_FALSE(rs_std::StrRef::from_utf8("\xF0\x80\x80\xAF").has_value());
+  // Surrogates.
+  EXPECT_FALSE(rs_std::StrRef::from_utf8("\xED\xA0\x80").has_value());
+  // Above U+10FFFF.
+  EXPECT_FALSE(rs_std::StrRef::from_utf8("\xF4\x90\x80\x80").has_value());
+}
+
+}  // namespace
@@ -0,0 +1,150 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#ifndef CRUBIT_SUPPORT_RS_STD_VEC_H_
+#define CRUBIT_SUPPORT_RS_STD_VEC_H_
+
+#include <cstddef>
+#include <cstdint>
+#include <utility>
+
+// A path relative to this file works for any `--crubit-support-path`.
+#include "slice_ref.h"
+
+#if __cplusplus > 201703L
+#include <span>
+#endif
+
+namespace rs_std {
+
+// `rs_std::Vec<T>` is a C++ representation of Rust's `Vec<T>` type (where `T`
+// is a type with C++ bindings generated by `cc_bindings_from_rs`).
+//
+// `Vec<T>` owns a buffer of elements that has been allocated by Rust and is
+// move-only.  Rust doesn't guarantee any specific layout of `Vec<T>`, so the
+// generated bindings decompose a Rust `Vec<T>` into its raw parts (a pointer,
+// a length, and a capacity) when it crosses the FFI boundary.  The buffer has
+// to be freed by Rust - the destructor calls the Rust function that has been
+// provided together with the raw parts (it drops the elements and frees the
+// buffer).
+//
+// Like `rs_std::SliceRef<T>`, `Vec<T>` never stores a null pointer - Rust
+// requires a non-null, aligned pointer even for empty vectors.  An empty `Vec`
+// is also what is left behind in a moved-from `Vec`.
+template <typename T>
+class Vec final {
+ public:
+  // Drops the `size` elements starting at `data` and frees the buffer (which
+  // has room for `capacity` elements).
+  using DropFn = void (*)(T* data, size_t size, size_t capacity);
+
+  // Creates an empty `Vec`.
+  Vec() noexcept : data_(Dangling()), size_(0), capacity_(0), drop_(nullptr) {}
+
+  // Takes ownership of the `size` elements starting at `data`.  This function
+  // mimics Rust's `Vec::from_raw_parts` and is intended to only be used by the
+  // generated bindings (and by tests): `data` has to point to a buffer that
+  // has been allocated by Rust, and `drop` has to free this buffer.
+  static Vec from_raw_parts(T* data, size_t size, size_t capacity,
+                            DropFn drop) noexcept {
+    return Vec(data, size, capacity, drop);
+  }
+
+  Vec(const Vec&) = delete;
+  Vec& operator=(const Vec&) = delete;
+
+  Vec(Vec&& other) noexcept
+      : data_(std::exchange(other.data_, Dangling())),
+        size_(std::exchange(other.size_, 0)),
+        capacity_(std::exchange(other.capacity_, 0)),
+        drop_(std::exchange(other.drop_, nullptr)) {}
+  Vec& operator=(Vec&& other) noexcept {
+    if (this != &other) {
+      reset();
+      data_ = std::exchange(other.data_, Dangling());
+      size_ = std::exchange(other.size_, 0);
+      capacity_ = std::exchange(other.capacity_, 0);
+      drop_ = std::exchange(other.drop_, nullptr);
+    }
+    return *this;
+  }
+
+  ~Vec() { reset(); }
+
+  // Drops all the elements, frees the buffer, and leaves the `Vec` empty.
+  void reset() noexcept {
+    if (DropFn drop = std::exchange(drop_, nullptr)) {
+      drop(data_, size_, capacity_);
+    }
+    data_ = Dangling();
+    size_ = 0;
+    capacity_ = 0;
+  }
+
+  // Returns a pointer to the first element.  Note that the returned pointer is
+  // never null, but it is dangling (i.e. may not be dereferenced) when the
+  // vector is empty.
+  T* data() noexcept { return data_; }
+  const T* data() const noexcept { return data_; }
+  size_t size() const noexcept { return size_; }
+  size_t capacity() const noexcept { return capacity_; }
+  bool empty() const noexcept { return size_ == 0; }
+
+  T* begin() noexcept { return data_; }
+  T* end() noexcept { return data_ + size_; }
+  const T* begin() const noexcept { return data_; }
+  const T* end() const noexcept { return data_ + size_; }
+
+  // Precondition: `i < size()`.
+  T& operator[](size_t i) noexcept { return data_[i]; }
+  const T& operator[](size_t i) const noexcept { return data_[i]; }
+
+  // Converts a `Vec<T>` into a `SliceRef<const T>` or a `SliceRef<T>` (this
+  // mimics how Rust coerces `&Vec<T>` into `&[T]` and `&mut Vec<T>` into
+  // `&mut [T]`).
+  SliceRef<const T> as_slice() const noexcept {
+    return SliceRef<const T>(data_, size_);
+  }
+  SliceRef<T> as_mut_slice() noexcept { return SliceRef<T>(data_, size_); }
+  operator SliceRef<const T>()  // NOLINT(google-explicit-constructor)
+      const noexcept {
+    return as_slice();
+  }
+  operator SliceRef<T>() noexcept {  // NOLINT(google-explicit-constructor)
+    return as_mut_slice();
+  }
+
+#if __cplusplus > 201703L
+  // Converts a `Vec<T>` into a `std::span`.  (Note that `Vec<T>` is a
+  // contiguous range, and therefore a `std::span` can also be constructed
+  // directly from a `Vec<T>`.)
+  std::span<const T> to_span() const noexcept {
+    return std::span<const T>(data_, size_);
+  }
+  std::span<T> to_span() noexcept { return std::span<T>(data_, size_); }
+#endif
+
+ private:
+  Vec(T* data, size_t size, size_t capacity, DropFn drop) noexcept
+      : data_(data), size_(size), capacity_(capacity), drop_(drop) {}
+
+  // Mimics Rust's `NonNull::dangling` - a non-null, well-aligned pointer that
+  // is used by empty vectors.
+  static T* Dangling() noexcept {
+    return reinterpret_cast<T*>(static_cast<std::uintptr_t>(alignof(T)));
+  }
+
+  // The generated Rust bindings write the raw parts of a Rust `Vec<T>`
+  // directly into these fields (see `__CrubitVec<T>` in the generated
+  // `..._cc_api_impl.rs`) - the order and the types of the fields below have
+  // to stay in sync with `__CrubitVec<T>`.
+  T* data_;
+  size_t size_;
+  size_t capacity_;
+  DropFn drop_;
+};
+
+}  // namespace rs_std
+
+#endif  // CRUBIT_SUPPORT_RS_STD_VEC_H_
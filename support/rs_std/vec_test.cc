@@ -0,0 +1,139 @@
+// Part of the Crubit project, under the Apache License v2.0 with LLVM
+// Exceptions. See /LICENSE for license information.
+// SPDX-License-Identifier: Apache-2.0 WITH LLVM-exception
+
+#include "support/rs_std/vec.h"
+
+#include <stdint.h>
+
+#include <algorithm>
+#include <cstddef>
+#include <memory>
+#include <type_traits>
+#include <utility>
+#include <vector>
+
+#include "gmock/gmock.h"
+#include "gtest/gtest.h"
+#include "support/rs_std/slice_ref.h"
+
+namespace {
+
+// `rs_std::Vec<T>` is move-only.
+static_assert(!std::is_copy_constructible_v<rs_std::Vec<int>>);
+static_assert(!std::is_copy_assignable_v<rs_std::Vec<int>>);
+static_assert(std::is_nothrow_move_constructible_v<rs_std::Vec<int>>);
+static_assert(std::is_nothrow_move_assignable_v<rs_std::Vec<int>>);
+
+// Layout tests.
+//
+// The generated bindings write the raw parts of a Rust `Vec<T>` (a pointer, a
+// length, a capacity, and a drop function) directly into `rs_std::Vec<T>`.
+static_assert(sizeof(rs_std::Vec<int>) == 4 * sizeof(void*));
+static_assert(alignof(rs_std::Vec<int>) == alignof(void*));
+static_assert(std::is_standard_layout_v<rs_std::Vec<int>>);
+
+int drop_count = 0;
+
+// Stands in for the Rust function that drops a Rust `Vec<i32>`.
+void DropIntBuffer(int* data, size_t size, size_t capacity) {
+  drop_count++;
+  std::allocator<int>().deallocate(data, capacity);
+}
+
+rs_std::Vec<int> MakeVec(std::vector<int> values) {
+  size_t capacity = values.size() + 1;
+  int* data = std::allocator<int>().allocate(capacity);
+  std::copy(values.begin(), values.end(), data);
+  return rs_std::Vec<int>::from_raw_parts(data, values.size(), capacity,
+                                          &DropIntBuffer);
+}
+
+TEST(VecTest, Empty) {
+  drop_count = 0;
+  {
+    rs_std::Vec<int64_t> v;
+    EXPECT_TRUE(v.empty());
+    EXPECT_EQ(0, v.size());
+    EXPECT_EQ(0, v.capacity());
+    EXPECT_EQ(v.begin(), v.end());
+
+    // Rust requires a non-null, aligned pointer even for empty vectors.
+    ASSERT_NE(nullptr, v.data());
+    EXPECT_EQ(0, reinterpret_cast<uintptr_t>(v.data()) % alignof(int64_t));
+  }
+  EXPECT_EQ(0, drop_count);
+}
+
+TEST(VecTest, Elements) {
+  drop_count = 0;
+  {
+    rs_std::Vec<int> v = MakeVec({1, 2, 3});
+    EXPECT_FALSE(v.empty());
+    EXPECT_EQ(3, v.size());
+    EXPECT_EQ(4, v.capacity());
+    EXPECT_EQ(2, v[1]);
+    v[0] = 10;
+    for (int& i : v) i += 1;
+    EXPECT_THAT(std::vector<int>(v.begin(), v.end()),
+                testing::ElementsAre(11, 3, 4));
+  }
+  EXPECT_EQ(1, drop_count);
+}
+
+TEST(VecTest, Move) {
+  drop_count = 0;
+  {
+    rs_std::Vec<int> v1 = MakeVec({1, 2, 3});
+    const int* data = v1.data();
+    rs_std::Vec<int> v2 = std::move(v1);
+    EXPECT_TRUE(v1.empty());  // NOLINT(bugprone-use-after-move)
+    EXPECT_EQ(data, v2.data());
+    EXPECT_EQ(3, v2.size());
+
+    v1 = MakeVec({4, 5});
+    v2 = std::move(v1);  // Drops the old value of `v2`.
+    EXPECT_EQ(1, drop_count);
+    EXPECT_TRUE(v1.empty());  // NOLINT(bugprone-use-after-move)
+    EXPECT_THAT(std::vector<int>(v2.begin(), v2.end()),
+                testing::ElementsAre(4, 5));
+  }
+  EXPECT_EQ(2, drop_count);
+}
+
+TEST(VecTest, Reset) {
+  drop_count = 0;
+  rs_std::Vec<int> v = MakeVec({1, 2, 3});
+  v.reset();
+  EXPECT_EQ(1, drop_count);
+  EXPECT_TRUE(v.empty());
+  EXPECT_EQ(0, v.capacity());
+  v.reset();
+  EXPECT_EQ(1, drop_count);
+}
+
+TEST(VecTest, SliceRef) {
+  drop_count = 0;
+  {
+    rs_std::Vec<int> v = MakeVec({1, 2, 3});
+    rs_std::SliceRef<const int> s = v;
+    EXPECT_EQ(v.data(), s.data());
+    EXPECT_EQ(3, s.size());
+
+    rs_std::SliceRef<int> mut_s = v;
+    mut_s[0] = 10;
+    EXPECT_EQ(10, v[0]);
+  }
+  EXPECT_EQ(1, drop_count);
+}
+
+#if __cplusplus > 201703L
+TEST(VecTest, Span) {
+  rs_std::Vec<int> v = MakeVec({1, 2, 3});
+  std::span<const int> span = v.to_span();
+  EXPECT_EQ(v.data(), span.data());
+  EXPECT_EQ(3, span.size());
+}
+#endif
+
+}  // namespace